@@ -106,6 +106,16 @@ pub fn new() -> Result<Self> {
         })
     }
 
+    /// Create a builder for an explicit `bwrap` binary, skipping discovery.
+    pub fn with_binary(bwrap: impl Into<PathBuf>) -> Self {
+        Self {
+            bwrap: bwrap.into(),
+            bwrap_args: Vec::new(),
+            program: None,
+            program_args: Vec::new(),
+        }
+    }
+
     /// Request that bwrap unshare each given namespace.
     pub fn unshare<I>(mut self, namespaces: I) -> Self
     where
@@ -162,6 +172,45 @@ pub fn dev_bind<H, G>(mut self, host: H, guest: G) -> Self
         self
     }
 
+    /// Map the sandbox user to `uid` inside a new user namespace
+    /// (`--uid uid`; requires [`Namespace::User`]).
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.bwrap_args.push(OsString::from("--uid"));
+        self.bwrap_args.push(OsString::from(uid.to_string()));
+        self
+    }
+
+    /// Map the sandbox group to `gid` inside a new user namespace
+    /// (`--gid gid`; requires [`Namespace::User`]).
+    pub fn gid(mut self, gid: u32) -> Self {
+        self.bwrap_args.push(OsString::from("--gid"));
+        self.bwrap_args.push(OsString::from(gid.to_string()));
+        self
+    }
+
+    /// Drop a capability, or every capability with `"ALL"`
+    /// (`--cap-drop cap`).
+    pub fn cap_drop(mut self, cap: &str) -> Self {
+        self.bwrap_args.push(OsString::from("--cap-drop"));
+        self.bwrap_args.push(OsString::from(cap));
+        self
+    }
+
+    /// Mount a procfs for the sandbox's PID namespace at `mount_point`
+    /// (`--proc mount_point`).
+    pub fn proc<P: AsRef<OsStr>>(mut self, mount_point: P) -> Self {
+        self.bwrap_args.push(OsString::from("--proc"));
+        self.bwrap_args.push(mount_point.as_ref().to_os_string());
+        self
+    }
+
+    /// Remount an already-mounted `path` read-only (`--remount-ro path`).
+    pub fn remount_ro<P: AsRef<OsStr>>(mut self, path: P) -> Self {
+        self.bwrap_args.push(OsString::from("--remount-ro"));
+        self.bwrap_args.push(path.as_ref().to_os_string());
+        self
+    }
+
     /// Mount a fresh tmpfs at `mount_point` (`--tmpfs mount_point`).
     pub fn tmpfs<P: AsRef<OsStr>>(mut self, mount_point: P) -> Self {
         self.bwrap_args.push(OsString::from("--tmpfs"));
@@ -242,4 +291,37 @@ fn namespace_flags_match_bwrap_cli() {
         assert_eq!(Namespace::Cgroup.flag(), "--unshare-cgroup");
         assert_eq!(Namespace::Net.flag(), "--unshare-net");
     }
+
+    #[test]
+    fn typed_helpers_emit_flags_in_order() {
+        let cmd = BwrapCommand::with_binary("/usr/bin/bwrap")
+            .unshare([Namespace::User])
+            .uid(1000)
+            .gid(100)
+            .cap_drop("ALL")
+            .proc("/proc")
+            .remount_ro("/proc/sys")
+            .program("/bin/true")
+            .into_command();
+        let args: Vec<_> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            [
+                "--unshare-user",
+                "--uid",
+                "1000",
+                "--gid",
+                "100",
+                "--cap-drop",
+                "ALL",
+                "--proc",
+                "/proc",
+                "--remount-ro",
+                "/proc/sys",
+                "--",
+                "/bin/true",
+            ]
+        );
+        assert_eq!(cmd.get_program(), "/usr/bin/bwrap");
+    }
 }
@@ -34,6 +34,31 @@ pub enum Error {
         source: io::Error,
     },
 
+    /// No cgroup v2 subtree this process may manage: not root, and not
+    /// inside a systemd-delegated user subtree.
+    #[error(
+        "no writable cgroup v2 hierarchy (run as root or inside a systemd user session with delegation)"
+    )]
+    Unavailable,
+
+    /// `cpuset.cpus` value is not a kernel CPU list.
+    #[error("invalid cpu list {0:?} (expected e.g. \"0-3,6\")")]
+    InvalidCpuList(String),
+
+    /// The path is not on a filesystem backed by a block device, so it
+    /// cannot be throttled through `io.max`.
+    #[error("{} is not on a block device", .0.display())]
+    NotBlockDevice(PathBuf),
+
+    /// A cgroup statistics file could not be parsed.
+    #[error("malformed cgroup file {file}: {line:?}")]
+    Parse {
+        /// The statistics file (e.g. `cpu.stat`).
+        file: &'static str,
+        /// The offending line.
+        line: String,
+    },
+
     /// Generic I/O error in cgroup plumbing.
     #[error(transparent)]
     Io(#[from] io::Error),
@@ -3,6 +3,9 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use crate::error::Result;
+use crate::stats::{self, CgroupStats};
+
 /// RAII guard that removes the cgroup directory on drop.
 ///
 /// Removal is best-effort: if processes remain in the cgroup when the
@@ -28,6 +31,16 @@ pub(crate) const fn new(path: PathBuf) -> Self {
     pub fn path(&self) -> &Path {
         &self.path
     }
+
+    /// Live CPU, memory and IO usage of the cgroup.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Io`] if a statistics file is unreadable, or
+    /// [`crate::Error::Parse`] if one is malformed.
+    pub fn stats(&self) -> Result<CgroupStats> {
+        stats::read(&self.path)
+    }
 }
 
 impl Drop for CgroupGuard {
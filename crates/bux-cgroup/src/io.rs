@@ -0,0 +1,191 @@
+//! Per-device block IO throttles (`io.max`).
+//!
+//! The io controller only throttles whole disks: a limit on a partition
+//! is rejected with `ENODEV`. [`block_device`] therefore resolves a file
+//! to the disk that backs it, not to the partition it lives on.
+
+use std::fmt;
+
+/// A block device `major:minor` number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DeviceNumber {
+    /// Major number (driver).
+    pub major: u32,
+    /// Minor number (instance).
+    pub minor: u32,
+}
+
+impl DeviceNumber {
+    /// Device `major:minor`.
+    #[must_use]
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+
+    /// Decode a Linux `dev_t` (e.g. `st_dev`), glibc's `gnu_dev_major` /
+    /// `gnu_dev_minor` layout.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "both halves are masked to 32 bits before the cast"
+    )]
+    pub const fn from_dev(dev: u64) -> Self {
+        let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & 0xffff_f000);
+        let minor = (dev & 0xff) | ((dev >> 12) & 0xffff_ff00);
+        Self::new(major as u32, minor as u32)
+    }
+}
+
+impl fmt::Display for DeviceNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+impl std::str::FromStr for DeviceNumber {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.trim().split_once(':').unwrap_or((s, ""));
+        Ok(Self::new(major.parse()?, minor.parse()?))
+    }
+}
+
+/// Throttles for one block device. `None` leaves a value unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IoMax {
+    /// Whole-disk device the limits apply to.
+    pub device: DeviceNumber,
+    /// Read bytes per second (`rbps`).
+    pub read_bps: Option<u64>,
+    /// Write bytes per second (`wbps`).
+    pub write_bps: Option<u64>,
+    /// Read operations per second (`riops`).
+    pub read_iops: Option<u64>,
+    /// Write operations per second (`wiops`).
+    pub write_iops: Option<u64>,
+}
+
+impl IoMax {
+    /// No throttles on `device`.
+    #[must_use]
+    pub const fn new(device: DeviceNumber) -> Self {
+        Self {
+            device,
+            read_bps: None,
+            write_bps: None,
+            read_iops: None,
+            write_iops: None,
+        }
+    }
+
+    /// Caps read throughput.
+    #[must_use]
+    pub const fn read_bps(mut self, bytes: u64) -> Self {
+        self.read_bps = Some(bytes);
+        self
+    }
+
+    /// Caps write throughput.
+    #[must_use]
+    pub const fn write_bps(mut self, bytes: u64) -> Self {
+        self.write_bps = Some(bytes);
+        self
+    }
+
+    /// Caps read operations per second.
+    #[must_use]
+    pub const fn read_iops(mut self, ops: u64) -> Self {
+        self.read_iops = Some(ops);
+        self
+    }
+
+    /// Caps write operations per second.
+    #[must_use]
+    pub const fn write_iops(mut self, ops: u64) -> Self {
+        self.write_iops = Some(ops);
+        self
+    }
+
+    /// The `io.max` line, e.g. `"8:0 rbps=1048576 wbps=max riops=max
+    /// wiops=100"`. Unset keys are written as `max` so a rewrite clears
+    /// earlier limits.
+    #[must_use]
+    pub fn to_line(self) -> String {
+        let keys = [
+            ("rbps", self.read_bps),
+            ("wbps", self.write_bps),
+            ("riops", self.read_iops),
+            ("wiops", self.write_iops),
+        ];
+        let values = keys.map(|(key, value)| {
+            value.map_or_else(|| format!("{key}=max"), |v| format!("{key}={v}"))
+        });
+        format!("{} {}", self.device, values.join(" "))
+    }
+}
+
+/// The whole disk holding `path`, for use in [`IoMax`].
+///
+/// # Errors
+///
+/// - [`crate::Error::Io`] if `path` cannot be stat'ed.
+/// - [`crate::Error::NotBlockDevice`] if the filesystem is not backed by a
+///   block device (tmpfs, overlayfs, network filesystems).
+#[cfg(target_os = "linux")]
+pub fn block_device(path: &std::path::Path) -> crate::Result<DeviceNumber> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = DeviceNumber::from_dev(std::fs::metadata(path)?.dev());
+    let sys = std::path::PathBuf::from(format!("/sys/dev/block/{dev}"));
+    let not_block = || crate::Error::NotBlockDevice(path.to_path_buf());
+    let node = std::fs::canonicalize(&sys).map_err(|_| not_block())?;
+    if !node.join("partition").exists() {
+        return Ok(dev);
+    }
+    let disk = node.parent().ok_or_else(not_block)?;
+    std::fs::read_to_string(disk.join("dev"))?
+        .parse()
+        .map_err(|_| not_block())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_dev_t() {
+        assert_eq!(DeviceNumber::from_dev(0x0801), DeviceNumber::new(8, 1));
+        // nvme0n1p2 on a typical host: 259:2.
+        assert_eq!(DeviceNumber::from_dev(0x10302), DeviceNumber::new(259, 2));
+        // Large minors spill into the high bits.
+        let dev = (0x12_u64 << 8) | 0x34 | (0xabc_u64 << 20);
+        assert_eq!(
+            DeviceNumber::from_dev(dev),
+            DeviceNumber::new(0x12, 0x34 | (0xabc << 8))
+        );
+    }
+
+    #[test]
+    fn parses_and_prints_major_minor() {
+        let dev: DeviceNumber = "259:0\n".parse().unwrap();
+        assert_eq!(dev, DeviceNumber::new(259, 0));
+        assert_eq!(dev.to_string(), "259:0");
+        assert!("259".parse::<DeviceNumber>().is_err());
+    }
+
+    #[test]
+    fn io_max_line_writes_every_key() {
+        let line = IoMax::new(DeviceNumber::new(8, 0))
+            .read_bps(1 << 20)
+            .write_iops(100)
+            .to_line();
+        assert_eq!(line, "8:0 rbps=1048576 wbps=max riops=max wiops=100");
+    }
+}
@@ -2,8 +2,9 @@
 //!
 //! bux-cgroup is a tiny, zero-dependency (beyond `thiserror`) L1 platform
 //! primitive. It creates per-VM cgroups under the unified cgroup v2
-//! hierarchy (`/sys/fs/cgroup`), writes CPU / memory / swap limits, and
-//! returns an RAII guard that removes the cgroup on drop.
+//! hierarchy (`/sys/fs/cgroup`), writes CPU / memory / swap / pids / IO /
+//! cpuset limits, reads usage back ([`CgroupGuard::stats`]), and returns
+//! an RAII guard that removes the cgroup on drop.
 //!
 //! The crate is **Linux-only**: on every other target it compiles to an
 //! empty module so downstream `cfg(target_os = "linux")` gates remain
@@ -18,14 +19,23 @@
 //! /sys/fs/cgroup/bux/{name}/
 //!     cpu.max
 //!     memory.max
+//!     memory.high
 //!     memory.swap.max
+//!     pids.max
+//!     io.weight
+//!     io.max         ← one line per throttled disk
+//!     cpuset.cpus
 //!     cgroup.procs   ← write PIDs here via `add_pid`
 //! ```
 //!
-//! The parent `/sys/fs/cgroup/bux` directory is created on demand, and
-//! the `cpu` / `memory` controllers are enabled there (best-effort —
-//! failure is non-fatal because the subsequent control-file writes will
-//! surface a clear error if they are not actually available).
+//! Rootless callers cannot write there; [`Placement::detect`] then falls
+//! back to the subtree systemd delegates to the user manager
+//! (`/sys/fs/cgroup/user.slice/user-UID.slice/user@UID.service/bux`).
+//!
+//! The parent `bux` directory is created on demand, and the controllers
+//! the limits need are enabled there (best-effort — failure is non-fatal
+//! because the subsequent control-file writes will surface a clear error
+//! if they are not actually available).
 //!
 //! # Example
 //!
@@ -51,17 +61,29 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 mod error;
+mod io;
 mod limits;
 
 #[cfg(target_os = "linux")]
 mod guard;
 #[cfg(target_os = "linux")]
 mod ops;
+#[cfg(target_os = "linux")]
+mod placement;
+#[cfg(target_os = "linux")]
+mod stats;
 
 pub use error::{Error, Result};
-pub use limits::{ResourceLimits, ResourceLimitsBuilder};
+#[cfg(target_os = "linux")]
+pub use io::block_device;
+pub use io::{DeviceNumber, IoMax};
+pub use limits::{ResourceLimits, ResourceLimitsBuilder, validate_cpu_list};
 
 #[cfg(target_os = "linux")]
 pub use guard::CgroupGuard;
 #[cfg(target_os = "linux")]
-pub use ops::{add_pid, create};
+pub use ops::{add_pid, create, create_in, remove, stats};
+#[cfg(target_os = "linux")]
+pub use placement::Placement;
+#[cfg(target_os = "linux")]
+pub use stats::{CgroupStats, CpuStats, IoStat, MemoryEvents};
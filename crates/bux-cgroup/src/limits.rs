@@ -1,5 +1,8 @@
 //! Resource limit configuration for a cgroup.
 
+use crate::error::{Error, Result};
+use crate::io::IoMax;
+
 /// Resource limits applied to a cgroup v2 subtree.
 ///
 /// All fields are optional — unset fields inherit from the parent cgroup
@@ -20,7 +23,7 @@
 /// assert_eq!(limits.memory_bytes, Some(512 * 1024 * 1024));
 /// assert_eq!(limits.memory_swap_bytes, None);
 /// ```
-#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 #[non_exhaustive]
 pub struct ResourceLimits {
     /// Maximum CPU bandwidth as a fraction of cores (e.g. `2.0` = 2 cores).
@@ -32,11 +35,31 @@ pub struct ResourceLimits {
     /// Memory limit in bytes. Written to `memory.max`.
     pub memory_bytes: Option<u64>,
 
+    /// Memory soft limit in bytes. Written to `memory.high`.
+    ///
+    /// Above it the kernel throttles and reclaims aggressively instead of
+    /// OOM-killing; keep it below `memory_bytes` to get a warning band.
+    pub memory_high_bytes: Option<u64>,
+
     /// Memory + swap limit in bytes. Written to `memory.swap.max`.
     ///
     /// Set equal to `memory_bytes` to effectively disable swap for
     /// processes inside the cgroup.
     pub memory_swap_bytes: Option<u64>,
+
+    /// Maximum number of tasks (processes + threads). Written to `pids.max`.
+    pub pids_max: Option<u64>,
+
+    /// Proportional block IO weight, `1..=10000` (kernel default 100).
+    /// Written to `io.weight` as `"default {weight}"`.
+    pub io_weight: Option<u16>,
+
+    /// Per-device throttles. Written to `io.max`, one line per device.
+    pub io_max: Vec<IoMax>,
+
+    /// CPUs the cgroup may run on, in kernel list syntax (`"0-3,6"`).
+    /// Written to `cpuset.cpus`.
+    pub cpuset_cpus: Option<String>,
 }
 
 impl ResourceLimits {
@@ -46,7 +69,12 @@ pub const fn builder() -> ResourceLimitsBuilder {
             limits: Self {
                 cpu_cores: None,
                 memory_bytes: None,
+                memory_high_bytes: None,
                 memory_swap_bytes: None,
+                pids_max: None,
+                io_weight: None,
+                io_max: Vec::new(),
+                cpuset_cpus: None,
             },
         }
     }
@@ -54,12 +82,41 @@ pub const fn builder() -> ResourceLimitsBuilder {
     /// Returns `true` if no limits are set (cgroup creation is a no-op).
     #[must_use]
     pub const fn is_empty(&self) -> bool {
-        self.cpu_cores.is_none() && self.memory_bytes.is_none() && self.memory_swap_bytes.is_none()
+        self.cpu_cores.is_none()
+            && self.memory_bytes.is_none()
+            && self.memory_high_bytes.is_none()
+            && self.memory_swap_bytes.is_none()
+            && self.pids_max.is_none()
+            && self.io_weight.is_none()
+            && self.io_max.is_empty()
+            && self.cpuset_cpus.is_none()
     }
 }
 
+/// Check `list` is a kernel CPU list: comma-separated CPUs or `a-b`
+/// ranges with `a <= b`, e.g. `"0-3,6"`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidCpuList`] otherwise.
+pub fn validate_cpu_list(list: &str) -> Result<()> {
+    let invalid = || Error::InvalidCpuList(list.to_owned());
+    if list.is_empty() {
+        return Err(invalid());
+    }
+    for item in list.split(',') {
+        let (lo, hi) = item.split_once('-').unwrap_or((item, item));
+        let lo: u32 = lo.parse().map_err(|_| invalid())?;
+        let hi: u32 = hi.parse().map_err(|_| invalid())?;
+        if lo > hi {
+            return Err(invalid());
+        }
+    }
+    Ok(())
+}
+
 /// Fluent builder for [`ResourceLimits`].
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[must_use = "builders do nothing unless you call `.build()`"]
 pub struct ResourceLimitsBuilder {
     /// Accumulated limits being built.
@@ -85,9 +142,39 @@ pub const fn memory_swap_bytes(mut self, bytes: u64) -> Self {
         self
     }
 
+    /// Sets the maximum number of tasks.
+    pub const fn pids_max(mut self, max: u64) -> Self {
+        self.limits.pids_max = Some(max);
+        self
+    }
+
+    /// Sets the proportional IO weight (`1..=10000`).
+    pub const fn io_weight(mut self, weight: u16) -> Self {
+        self.limits.io_weight = Some(weight);
+        self
+    }
+
+    /// Sets the memory soft limit in bytes.
+    pub const fn memory_high_bytes(mut self, bytes: u64) -> Self {
+        self.limits.memory_high_bytes = Some(bytes);
+        self
+    }
+
+    /// Adds throttles for one block device.
+    pub fn io_max(mut self, limit: IoMax) -> Self {
+        self.limits.io_max.push(limit);
+        self
+    }
+
+    /// Pins the cgroup to `cpus` (kernel list syntax, e.g. `"0-3,6"`).
+    pub fn cpuset_cpus(mut self, cpus: impl Into<String>) -> Self {
+        self.limits.cpuset_cpus = Some(cpus.into());
+        self
+    }
+
     /// Finalises the builder.
     #[must_use]
-    pub const fn build(self) -> ResourceLimits {
+    pub fn build(self) -> ResourceLimits {
         self.limits
     }
 }
@@ -113,17 +200,52 @@ fn builder_sets_fields() {
             .cpu_cores(1.5)
             .memory_bytes(256 * 1024 * 1024)
             .memory_swap_bytes(256 * 1024 * 1024)
+            .pids_max(512)
+            .io_weight(50)
             .build();
         assert_eq!(l.cpu_cores, Some(1.5));
+        assert_eq!(l.pids_max, Some(512));
+        assert_eq!(l.io_weight, Some(50));
         assert_eq!(l.memory_bytes, Some(256 * 1024 * 1024));
         assert_eq!(l.memory_swap_bytes, Some(256 * 1024 * 1024));
         assert!(!l.is_empty());
     }
 
+    #[test]
+    fn cpu_lists() {
+        for ok in ["0", "0-3", "0-3,6", "1,1,2-2"] {
+            assert!(validate_cpu_list(ok).is_ok(), "{ok}");
+        }
+        for bad in ["", "a", "3-1", "0,", "-1", "0-"] {
+            assert!(validate_cpu_list(bad).is_err(), "{bad}");
+        }
+    }
+
     #[test]
     fn is_empty_detects_any_set_field() {
         assert!(!ResourceLimits::builder().cpu_cores(1.0).build().is_empty());
         assert!(!ResourceLimits::builder().memory_bytes(1).build().is_empty());
+        assert!(!ResourceLimits::builder().pids_max(1).build().is_empty());
+        assert!(!ResourceLimits::builder().io_weight(1).build().is_empty());
+        assert!(
+            !ResourceLimits::builder()
+                .memory_high_bytes(1)
+                .build()
+                .is_empty()
+        );
+        assert!(
+            !ResourceLimits::builder()
+                .cpuset_cpus("0")
+                .build()
+                .is_empty()
+        );
+        let dev = crate::io::DeviceNumber::new(8, 0);
+        assert!(
+            !ResourceLimits::builder()
+                .io_max(IoMax::new(dev))
+                .build()
+                .is_empty()
+        );
         assert!(
             !ResourceLimits::builder()
                 .memory_swap_bytes(1)
@@ -5,29 +5,40 @@
 
 use crate::error::{Error, Result};
 use crate::guard::CgroupGuard;
-use crate::limits::ResourceLimits;
-
-/// Base path for the unified cgroup v2 hierarchy on every supported distro.
-const CGROUP_ROOT: &str = "/sys/fs/cgroup";
-
-/// Parent cgroup directory created under the root hierarchy.
-const PARENT_GROUP: &str = "bux";
+use crate::limits::{ResourceLimits, validate_cpu_list};
+use crate::placement::Placement;
+use crate::stats::{self, CgroupStats};
 
 /// CPU bandwidth accounting period, in microseconds (100 ms — kernel default).
 const CPU_PERIOD_US: u64 = 100_000;
 
-/// Create a per-VM cgroup under `/sys/fs/cgroup/bux/{name}` and apply limits.
+/// Create a per-VM cgroup `{name}` under the detected [`Placement`] and
+/// apply limits.
 ///
-/// The parent cgroup `/sys/fs/cgroup/bux` is created on demand, and the
-/// `cpu` / `memory` controllers are enabled there (best-effort). Returns
-/// a [`CgroupGuard`] that removes the cgroup on drop.
+/// The parent `bux` cgroup is created on demand, and the controllers the
+/// limits need are enabled on it and its parent (best-effort). Returns a
+/// [`CgroupGuard`] that removes the cgroup on drop.
 ///
 /// # Errors
 ///
+/// - [`Error::Unavailable`] if there is no writable cgroup v2 subtree.
+/// - [`Error::InvalidCpuList`] if `cpuset_cpus` is malformed.
 /// - [`Error::CreateDir`] if the cgroup directory cannot be created.
 /// - [`Error::WriteFile`] if any control file write fails.
 pub fn create(name: &str, limits: &ResourceLimits) -> Result<CgroupGuard> {
-    let parent = Path::new(CGROUP_ROOT).join(PARENT_GROUP);
+    let placement = Placement::detect().ok_or(Error::Unavailable)?;
+    create_in(placement.dir(), name, limits)
+}
+
+/// Like [`create`], under an explicit parent directory.
+///
+/// # Errors
+///
+/// See [`create`].
+pub fn create_in(parent: &Path, name: &str, limits: &ResourceLimits) -> Result<CgroupGuard> {
+    if let Some(ref cpus) = limits.cpuset_cpus {
+        validate_cpu_list(cpus)?;
+    }
     let cgroup_dir = parent.join(name);
 
     fs::create_dir_all(&cgroup_dir).map_err(|source| Error::CreateDir {
@@ -35,7 +46,11 @@ pub fn create(name: &str, limits: &ResourceLimits) -> Result<CgroupGuard> {
         source,
     })?;
 
-    enable_controllers(&parent);
+    let controllers = controllers_for(limits);
+    if let Some(grandparent) = parent.parent() {
+        enable_controllers(grandparent, &controllers);
+    }
+    enable_controllers(parent, &controllers);
 
     if let Some(cores) = limits.cpu_cores {
         write_control(&cgroup_dir, "cpu.max", &format_cpu_max(cores))?;
@@ -49,9 +64,72 @@ pub fn create(name: &str, limits: &ResourceLimits) -> Result<CgroupGuard> {
         write_control(&cgroup_dir, "memory.swap.max", &swap.to_string())?;
     }
 
+    if let Some(high) = limits.memory_high_bytes {
+        write_control(&cgroup_dir, "memory.high", &high.to_string())?;
+    }
+
+    if let Some(pids) = limits.pids_max {
+        write_control(&cgroup_dir, "pids.max", &pids.to_string())?;
+    }
+
+    if let Some(weight) = limits.io_weight {
+        write_control(&cgroup_dir, "io.weight", &format!("default {weight}"))?;
+    }
+
+    for io_max in &limits.io_max {
+        write_control(&cgroup_dir, "io.max", &io_max.to_line())?;
+    }
+
+    if let Some(ref cpus) = limits.cpuset_cpus {
+        write_control(&cgroup_dir, "cpuset.cpus", cpus)?;
+    }
+
     Ok(CgroupGuard::new(cgroup_dir))
 }
 
+/// Remove the per-VM cgroup `{name}` once its processes have exited.
+///
+/// For callers that outlive the [`CgroupGuard`] returned by [`create`]
+/// (e.g. a CLI that exits while the VM keeps running). A missing cgroup
+/// is not an error.
+///
+/// # Errors
+///
+/// Returns [`Error::Io`] if the directory exists but cannot be removed
+/// (typically `EBUSY` while processes remain).
+pub fn remove(name: &str) -> Result<()> {
+    let Some(placement) = Placement::detect() else {
+        return Ok(());
+    };
+    match fs::remove_dir(placement.dir().join(name)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        Ok(()) | Err(_) => Ok(()),
+    }
+}
+
+/// Read live usage of the per-VM cgroup `{name}`.
+///
+/// For callers that no longer hold the [`CgroupGuard`]; see
+/// [`CgroupGuard::stats`].
+///
+/// # Errors
+///
+/// - [`Error::Unavailable`] if there is no writable cgroup v2 subtree.
+/// - [`Error::Io`] if the cgroup does not exist or a file is unreadable.
+/// - [`Error::Parse`] if a statistics file is malformed.
+pub fn stats(name: &str) -> Result<CgroupStats> {
+    let placement = Placement::detect().ok_or(Error::Unavailable)?;
+    let dir = placement.dir().join(name);
+    if !dir.is_dir() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("cgroup {} not found", dir.display()),
+        )
+        .into());
+    }
+    stats::read(&dir)
+}
+
 /// Add a process (by PID) to the cgroup.
 ///
 /// Writes the PID to `cgroup.procs`. The kernel atomically moves the
@@ -77,16 +155,41 @@ fn format_cpu_max(cores: f64) -> String {
     format!("{quota} {CPU_PERIOD_US}")
 }
 
-/// Enable `cpu` and `memory` controllers in the parent cgroup.
+/// Controllers `limits` needs, as `cgroup.subtree_control` tokens.
+fn controllers_for(limits: &ResourceLimits) -> Vec<&'static str> {
+    [
+        (limits.cpu_cores.is_some(), "+cpu"),
+        (
+            limits.memory_bytes.is_some()
+                || limits.memory_high_bytes.is_some()
+                || limits.memory_swap_bytes.is_some(),
+            "+memory",
+        ),
+        (limits.pids_max.is_some(), "+pids"),
+        (
+            limits.io_weight.is_some() || !limits.io_max.is_empty(),
+            "+io",
+        ),
+        (limits.cpuset_cpus.is_some(), "+cpuset"),
+    ]
+    .into_iter()
+    .filter_map(|(needed, token)| needed.then_some(token))
+    .collect()
+}
+
+/// Enable `controllers` for the children of `dir`, one at a time.
 ///
-/// This is best-effort because the write fails if the controllers are
-/// already enabled or if the caller lacks `CAP_SYS_ADMIN`. Failure is
-/// non-fatal — the actual limit writes will fail later with a clear
-/// error if the controllers truly are not available.
-fn enable_controllers(parent: &Path) {
-    let subtree_control = parent.join("cgroup.subtree_control");
+/// This is best-effort because a write fails if the caller lacks
+/// permission or the controller is not delegated here, and one missing
+/// controller must not block the others. Failure is non-fatal — the
+/// actual limit writes will fail later with a clear error if a
+/// controller truly is not available.
+fn enable_controllers(dir: &Path, controllers: &[&str]) {
+    let subtree_control = dir.join("cgroup.subtree_control");
     if subtree_control.exists() {
-        drop(fs::write(&subtree_control, "+cpu +memory"));
+        for controller in controllers {
+            drop(fs::write(&subtree_control, controller));
+        }
     }
 }
 
@@ -109,6 +212,21 @@ fn write_control(cgroup_dir: &Path, filename: &str, value: &str) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn controllers_follow_limits() {
+        assert!(controllers_for(&ResourceLimits::default()).is_empty());
+        let limits = ResourceLimits::builder()
+            .memory_swap_bytes(1)
+            .pids_max(64)
+            .io_weight(10)
+            .cpuset_cpus("0-1")
+            .build();
+        assert_eq!(
+            controllers_for(&limits),
+            ["+memory", "+pids", "+io", "+cpuset"]
+        );
+    }
+
     #[test]
     fn format_cpu_max_one_core() {
         assert_eq!(format_cpu_max(1.0), "100000 100000");
@@ -0,0 +1,114 @@
+//! Where per-VM cgroups live: the root hierarchy, or the subtree systemd
+//! delegates to the user.
+//!
+//! Root may write anywhere under `/sys/fs/cgroup`, so its VMs go in
+//! `/sys/fs/cgroup/bux`. A rootless caller can only manage the subtree
+//! systemd delegates to its user manager (`user@UID.service`), and only
+//! from inside it: moving a process requires write access to
+//! `cgroup.procs` of the nearest common ancestor. Those VMs go in
+//! `…/user@UID.service/bux`.
+
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Base path for the unified cgroup v2 hierarchy on every supported distro.
+pub(crate) const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Parent cgroup directory created under the chosen subtree.
+const PARENT_GROUP: &str = "bux";
+
+/// The calling process's cgroup membership.
+const PROC_SELF_CGROUP: &str = "/proc/self/cgroup";
+
+/// Parent directory for per-VM cgroups, by who owns it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Placement {
+    /// `/sys/fs/cgroup/bux` — the caller can write the root hierarchy.
+    Root(PathBuf),
+    /// `…/user@UID.service/bux` — rootless, in the systemd-delegated
+    /// subtree of the caller's user manager.
+    Delegated(PathBuf),
+}
+
+impl Placement {
+    /// Pick the placement for the current process.
+    ///
+    /// Returns `None` when cgroup v2 is not mounted, or the caller can
+    /// write neither the root hierarchy nor a delegated subtree it lives
+    /// in (e.g. rootless outside a systemd user session).
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        let root = Path::new(CGROUP_ROOT);
+        if !root.join("cgroup.controllers").exists() {
+            return None;
+        }
+        if writable(&root.join("cgroup.procs")) {
+            return Some(Self::Root(root.join(PARENT_GROUP)));
+        }
+        let own = fs::read_to_string(PROC_SELF_CGROUP).ok()?;
+        let service = root.join(delegated_service(&own)?);
+        writable(&service.join("cgroup.procs")).then(|| Self::Delegated(service.join(PARENT_GROUP)))
+    }
+
+    /// Directory per-VM cgroups are created in.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        match self {
+            Self::Root(dir) | Self::Delegated(dir) => dir,
+        }
+    }
+}
+
+/// Relative path of the `user@UID.service` cgroup containing the process
+/// described by `proc_self_cgroup` (the cgroup v2 `0::/path` line).
+fn delegated_service(proc_self_cgroup: &str) -> Option<PathBuf> {
+    let path = proc_self_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))?;
+    let mut service = PathBuf::new();
+    for component in path.split('/').filter(|c| !c.is_empty()) {
+        service.push(component);
+        if component.starts_with("user@") && component.ends_with(".service") {
+            return Some(service);
+        }
+    }
+    None
+}
+
+/// Whether the caller may write `path` (opened, never written).
+fn writable(path: &Path) -> bool {
+    OpenOptions::new().write(true).open(path).is_ok()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_user_manager_service() {
+        let own = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/term.scope\n";
+        assert_eq!(
+            delegated_service(own).unwrap(),
+            Path::new("user.slice/user-1000.slice/user@1000.service")
+        );
+    }
+
+    #[test]
+    fn ignores_v1_lines_and_system_services() {
+        let own = "12:pids:/user.slice\n0::/system.slice/sshd.service\n";
+        assert_eq!(delegated_service(own), None);
+        assert_eq!(delegated_service("0::/\n"), None);
+    }
+
+    #[test]
+    fn dir_is_the_bux_group() {
+        let p = Placement::Delegated(PathBuf::from("/sys/fs/cgroup/u/user@1.service/bux"));
+        assert!(p.dir().ends_with("bux"));
+    }
+}
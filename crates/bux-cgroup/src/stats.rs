@@ -0,0 +1,275 @@
+//! Live usage read back from a cgroup's statistics files.
+//!
+//! Every file is a flat list of `key value` pairs (`io.stat` adds a
+//! device prefix per line). Keys this crate does not know are skipped, so
+//! newer kernels that add fields keep parsing. Files absent on older
+//! kernels (`memory.peak` before 5.19) or for controllers that are not
+//! enabled read as `None` / empty.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::io::DeviceNumber;
+
+/// CPU time from `cpu.stat`, in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CpuStats {
+    /// Total CPU time (`usage_usec`).
+    pub usage_usec: u64,
+    /// User-mode CPU time (`user_usec`).
+    pub user_usec: u64,
+    /// Kernel-mode CPU time (`system_usec`).
+    pub system_usec: u64,
+    /// Enforcement periods elapsed under `cpu.max` (`nr_periods`).
+    pub nr_periods: u64,
+    /// Periods in which the group was throttled (`nr_throttled`).
+    pub nr_throttled: u64,
+    /// Total time spent throttled (`throttled_usec`).
+    pub throttled_usec: u64,
+}
+
+/// Limit events from `memory.events` (counts since creation).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryEvents {
+    /// Reclaimed under `memory.low` protection (`low`).
+    pub low: u64,
+    /// Throttled for exceeding `memory.high` (`high`).
+    pub high: u64,
+    /// Usage hit `memory.max` (`max`).
+    pub max: u64,
+    /// OOM situations: reclaim at `memory.max` failed (`oom`).
+    pub oom: u64,
+    /// Processes killed by the OOM killer (`oom_kill`).
+    pub oom_kill: u64,
+}
+
+/// Cumulative block IO for one device, from `io.stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct IoStat {
+    /// Device the counters belong to.
+    pub device: DeviceNumber,
+    /// Bytes read (`rbytes`).
+    pub rbytes: u64,
+    /// Bytes written (`wbytes`).
+    pub wbytes: u64,
+    /// Read operations (`rios`).
+    pub rios: u64,
+    /// Write operations (`wios`).
+    pub wios: u64,
+}
+
+/// Snapshot of a cgroup's usage.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CgroupStats {
+    /// CPU time.
+    pub cpu: CpuStats,
+    /// Current memory use in bytes (`memory.current`).
+    pub memory_current: Option<u64>,
+    /// High-water memory use in bytes (`memory.peak`, Linux 5.19+).
+    pub memory_peak: Option<u64>,
+    /// Memory limit events, including OOM kills.
+    pub memory_events: MemoryEvents,
+    /// Per-device block IO.
+    pub io: Vec<IoStat>,
+}
+
+/// Read every statistics file in `dir`.
+///
+/// # Errors
+///
+/// - [`Error::Io`] if a present file cannot be read.
+/// - [`Error::Parse`] if a file is malformed.
+pub(crate) fn read(dir: &Path) -> Result<CgroupStats> {
+    Ok(CgroupStats {
+        cpu: read_optional(dir, "cpu.stat")?
+            .map(|s| parse_cpu_stat(&s))
+            .transpose()?
+            .unwrap_or_default(),
+        memory_current: read_optional(dir, "memory.current")?
+            .map(|s| parse_single("memory.current", &s))
+            .transpose()?,
+        memory_peak: read_optional(dir, "memory.peak")?
+            .map(|s| parse_single("memory.peak", &s))
+            .transpose()?,
+        memory_events: read_optional(dir, "memory.events")?
+            .map(|s| parse_memory_events(&s))
+            .transpose()?
+            .unwrap_or_default(),
+        io: read_optional(dir, "io.stat")?
+            .map(|s| parse_io_stat(&s))
+            .transpose()?
+            .unwrap_or_default(),
+    })
+}
+
+/// Contents of `dir/file`, or `None` if it does not exist.
+fn read_optional(dir: &Path, file: &str) -> Result<Option<String>> {
+    match fs::read_to_string(dir.join(file)) {
+        Ok(s) => Ok(Some(s)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// A file holding one number (`memory.current`, `memory.peak`).
+fn parse_single(file: &'static str, s: &str) -> Result<u64> {
+    let line = s.trim();
+    line.parse().map_err(|_| Error::Parse {
+        file,
+        line: line.to_owned(),
+    })
+}
+
+/// `key value` pairs, one per line.
+fn pairs(file: &'static str, s: &str) -> Result<Vec<(String, u64)>> {
+    s.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| {
+            line.split_once(' ')
+                .and_then(|(k, v)| Some((k.to_owned(), v.trim().parse().ok()?)))
+                .ok_or_else(|| Error::Parse {
+                    file,
+                    line: line.to_owned(),
+                })
+        })
+        .collect()
+}
+
+/// Parse `cpu.stat`.
+fn parse_cpu_stat(s: &str) -> Result<CpuStats> {
+    let mut out = CpuStats::default();
+    for (key, value) in pairs("cpu.stat", s)? {
+        let slot = match key.as_str() {
+            "usage_usec" => &mut out.usage_usec,
+            "user_usec" => &mut out.user_usec,
+            "system_usec" => &mut out.system_usec,
+            "nr_periods" => &mut out.nr_periods,
+            "nr_throttled" => &mut out.nr_throttled,
+            "throttled_usec" => &mut out.throttled_usec,
+            _ => continue,
+        };
+        *slot = value;
+    }
+    Ok(out)
+}
+
+/// Parse `memory.events`.
+fn parse_memory_events(s: &str) -> Result<MemoryEvents> {
+    let mut out = MemoryEvents::default();
+    for (key, value) in pairs("memory.events", s)? {
+        let slot = match key.as_str() {
+            "low" => &mut out.low,
+            "high" => &mut out.high,
+            "max" => &mut out.max,
+            "oom" => &mut out.oom,
+            "oom_kill" => &mut out.oom_kill,
+            _ => continue,
+        };
+        *slot = value;
+    }
+    Ok(out)
+}
+
+/// Parse `io.stat`: `MAJ:MIN key=value ...` per device.
+fn parse_io_stat(s: &str) -> Result<Vec<IoStat>> {
+    s.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|line| {
+            parse_io_line(line).ok_or_else(|| Error::Parse {
+                file: "io.stat",
+                line: line.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// One `io.stat` line.
+fn parse_io_line(line: &str) -> Option<IoStat> {
+    let mut fields = line.split_whitespace();
+    let mut out = IoStat {
+        device: fields.next()?.parse().ok()?,
+        rbytes: 0,
+        wbytes: 0,
+        rios: 0,
+        wios: 0,
+    };
+    for field in fields {
+        let (key, value) = field.split_once('=')?;
+        let value = value.parse().ok()?;
+        match key {
+            "rbytes" => out.rbytes = value,
+            "wbytes" => out.wbytes = value,
+            "rios" => out.rios = value,
+            "wios" => out.wios = value,
+            _ => {}
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    clippy::indexing_slicing,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cpu_stat_and_skips_unknown_keys() {
+        let s = "usage_usec 1500\nuser_usec 1000\nsystem_usec 500\ncore_sched.force_idle_usec 0\n\
+                 nr_periods 10\nnr_throttled 2\nthrottled_usec 300\nnr_bursts 0\n";
+        let cpu = parse_cpu_stat(s).unwrap();
+        assert_eq!(cpu.usage_usec, 1500);
+        assert_eq!(cpu.system_usec, 500);
+        assert_eq!(cpu.nr_throttled, 2);
+        assert_eq!(cpu.throttled_usec, 300);
+        assert!(parse_cpu_stat("usage_usec lots\n").is_err());
+    }
+
+    #[test]
+    fn parses_memory_events() {
+        let s = "low 0\nhigh 7\nmax 3\noom 1\noom_kill 1\noom_group_kill 0\n";
+        let ev = parse_memory_events(s).unwrap();
+        assert_eq!(ev.high, 7);
+        assert_eq!(ev.max, 3);
+        assert_eq!(ev.oom_kill, 1);
+        assert_eq!(parse_single("memory.peak", "4096\n").unwrap(), 4096);
+        assert!(parse_single("memory.current", "max\n").is_err());
+    }
+
+    #[test]
+    fn parses_io_stat_per_device() {
+        let s = "8:0 rbytes=4096 wbytes=8192 rios=1 wios=2 dbytes=0 dios=0\n\
+                 259:0 rbytes=1 wbytes=0 rios=1 wios=0 dbytes=0 dios=0\n";
+        let io = parse_io_stat(s).unwrap();
+        assert_eq!(io.len(), 2);
+        assert_eq!(io[0].device, DeviceNumber::new(8, 0));
+        assert_eq!((io[0].rbytes, io[0].wbytes, io[0].wios), (4096, 8192, 2));
+        assert_eq!(io[1].device, DeviceNumber::new(259, 0));
+        assert!(parse_io_stat("8:0 rbytes\n").is_err());
+        assert!(parse_io_stat("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_a_directory_with_missing_files() {
+        let dir = std::env::temp_dir().join(format!("bux-cgroup-stats-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("memory.current"), "1024\n").unwrap();
+        fs::write(dir.join("cpu.stat"), "usage_usec 9\n").unwrap();
+        let stats = read(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(stats.memory_current, Some(1024));
+        assert_eq!(stats.memory_peak, None);
+        assert_eq!(stats.cpu.usage_usec, 9);
+        assert!(stats.io.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+//! `bux system doctor` — preflight probes of the host.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use bux::{Probe, ProbeStatus};
+
+use crate::OutputFormat;
+
+#[derive(clap::Args)]
+pub struct DoctorArgs {
+    /// Output format.
+    #[arg(long, default_value = "table")]
+    format: OutputFormat,
+
+    /// Skip the test boot.
+    #[arg(long)]
+    no_boot: bool,
+
+    /// Image for the test boot.
+    #[arg(long, value_name = "IMAGE", default_value = "alpine:latest")]
+    boot_image: String,
+
+    /// Seconds to wait for the test VM to become ready.
+    #[arg(long, value_name = "SECS", default_value_t = 60)]
+    boot_timeout: u64,
+}
+
+pub async fn doctor(args: DoctorArgs) -> Result<()> {
+    let data_dir = bux::default_data_dir();
+    let mut probes = bux::doctor::probe_host(&data_dir);
+    let boot = if args.no_boot {
+        Probe::new("boot", ProbeStatus::Skip, "skipped (--no-boot)")
+    } else if probes.iter().any(Probe::is_failure) {
+        Probe::new(
+            "boot",
+            ProbeStatus::Skip,
+            "skipped: fix the failed probes first",
+        )
+    } else {
+        match bux::Runtime::open(&data_dir) {
+            Ok(rt) => {
+                let timeout = Duration::from_secs(args.boot_timeout);
+                bux::doctor::probe_boot(&rt, &args.boot_image, timeout).await
+            }
+            Err(e) => Probe::new(
+                "boot",
+                ProbeStatus::Fail,
+                format!("cannot open the runtime in {}: {e}", data_dir.display()),
+            ),
+        }
+    };
+    probes.push(boot);
+    let failed = probes.iter().filter(|p| p.is_failure()).count();
+
+    if matches!(args.format, OutputFormat::Json) {
+        let obj = serde_json::json!({
+            "healthy": failed == 0,
+            "probes": probes,
+        });
+        println!("{}", serde_json::to_string_pretty(&obj)?);
+    } else {
+        println!("{:<6} {:<16} EXPLANATION", "STATUS", "PROBE");
+        for p in &probes {
+            println!("{:<6} {:<16} {}", p.status, p.name, p.explanation);
+            if let Some(ref fix) = p.remediation {
+                println!("{:<23} fix: {fix}", "");
+            }
+        }
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        anyhow::bail!("{failed} doctor probe(s) failed")
+    }
+}
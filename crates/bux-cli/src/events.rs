@@ -0,0 +1,57 @@
+//! `bux events` — show a VM's recorded network activity.
+
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use clap::Args;
+
+use crate::OutputFormat;
+use crate::vm::open_runtime;
+
+/// Arguments for `bux events`.
+#[derive(Args)]
+pub struct EventsArgs {
+    /// VM ID, name, or prefix whose network activity to show.
+    #[arg(long = "net", value_name = "VM")]
+    pub net: String,
+
+    /// Number of events from the end to show (0 = all).
+    #[arg(long, short = 'n', default_value_t = 0)]
+    pub tail: usize,
+
+    /// Output format.
+    #[arg(long, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+#[cfg(unix)]
+pub fn events(args: &EventsArgs) -> Result<()> {
+    let rt = open_runtime()?;
+    let id = rt.get(&args.net)?.state().id.clone();
+    let mut events = rt.network().activity(&id)?;
+    if args.tail > 0 {
+        events.drain(..events.len().saturating_sub(args.tail));
+    }
+
+    if matches!(args.format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&events)?);
+        return Ok(());
+    }
+    if events.is_empty() {
+        println!("No network activity recorded for {id}.");
+        return Ok(());
+    }
+    println!("{:<16} EVENT", "TIME");
+    for event in &events {
+        let since = event
+            .timestamp()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        println!(
+            "{:<16} {}",
+            format!("{}.{:03}", since.as_secs(), since.subsec_millis()),
+            event.kind
+        );
+    }
+    Ok(())
+}
@@ -13,13 +13,17 @@
     reason = "binary crate: CLI conventions differ from library lints"
 )]
 
+mod doctor;
+mod events;
 mod logs;
+mod network;
+mod policy;
 mod run;
 mod vm;
 mod volume;
 
 use anyhow::Result;
-use bux::{Feature, Vm};
+use bux::Vm;
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::Shell;
 
@@ -46,6 +50,9 @@ enum Command {
     /// Show shim stderr logs for a VM.
     Logs(logs::LogsArgs),
 
+    /// Show a VM's recorded network activity (DNS, connections, denials).
+    Events(events::EventsArgs),
+
     /// List VMs.
     #[command(visible_alias = "ls")]
     Ps(vm::PsArgs),
@@ -100,10 +107,12 @@ enum Command {
         image: String,
     },
 
-    /// List locally stored images.
+    /// List locally stored images, or maintain the image store.
     Images {
+        #[command(subcommand)]
+        action: Option<ImagesAction>,
         /// Output format.
-        #[arg(long, default_value = "table")]
+        #[arg(long, global = true, default_value = "table")]
         format: OutputFormat,
     },
 
@@ -130,6 +139,12 @@ enum Command {
         action: SystemAction,
     },
 
+    /// Manage named private networks connecting VMs.
+    Network {
+        #[command(subcommand)]
+        action: network::NetworkAction,
+    },
+
     /// Manage named volumes (`{data_dir}/volumes/`).
     Volume {
         #[command(subcommand)]
@@ -164,6 +179,39 @@ enum SystemAction {
         #[arg(long, default_value = "table")]
         format: OutputFormat,
     },
+    /// Host policy file (`policy.toml` / `policy.json` in the data dir).
+    Policy {
+        #[command(subcommand)]
+        action: policy::PolicyAction,
+    },
+    /// Preflight probes with a fix for each problem, ending in a test boot.
+    ///
+    /// Exits non-zero when any probe fails.
+    Doctor(doctor::DoctorArgs),
+}
+
+/// Subcommands for `bux images`.
+#[derive(Subcommand)]
+enum ImagesAction {
+    /// Show shared vs unique disk usage per image.
+    Du,
+    /// Check the image store for corruption, orphans, and stale staging files.
+    Fsck {
+        /// Delete orphans and corrupt blobs, and fix layer ref counts.
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Remove images not used recently. Images backing existing VMs are kept.
+    ///
+    /// With no flags, every image not backing a VM is removed.
+    Prune {
+        /// Keep images pulled or used within this many seconds.
+        #[arg(long, value_name = "SECS")]
+        unused_for: Option<u64>,
+        /// Keep the N most recently used images.
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+    },
 }
 
 /// Subcommands for `bux snapshot`.
@@ -235,6 +283,7 @@ async fn dispatch(self) -> Result<()> {
             Command::Create(args) => args.run().await,
             Command::Exec(args) => vm::exec(args).await,
             Command::Logs(ref args) => logs::logs(args),
+            Command::Events(ref args) => events::events(args),
             Command::Ps(ref args) => vm::ps(args),
             Command::Stop(args) => vm::stop(args).await,
             Command::Kill(ref args) => vm::kill(args),
@@ -250,12 +299,20 @@ async fn dispatch(self) -> Result<()> {
             Command::Clone(ref args) => vm::clone_box(args),
             Command::Export(ref args) => vm::export(args),
             Command::Pull { image } => pull(&image).await,
-            Command::Images { format } => images(format),
+            Command::Images { action, format } => match action {
+                None => images(format),
+                Some(ImagesAction::Du) => images_du(format),
+                Some(ImagesAction::Fsck { repair }) => images_fsck(repair, format),
+                Some(ImagesAction::Prune { unused_for, keep }) => images_prune(unused_for, keep),
+            },
             Command::Rmi { images } => rmi(&images),
             Command::Info { format } => system_info(format),
             Command::System { action } => match action {
                 SystemAction::Info { format } => system_info(format),
+                SystemAction::Policy { action } => policy::dispatch(action),
+                SystemAction::Doctor(args) => doctor::doctor(args).await,
             },
+            Command::Network { action } => network::dispatch(action),
             Command::Volume { action } => volume::dispatch(action),
             Command::Sweep => sweep_cmd(),
             Command::Disk { action } => disk_cmd(action),
@@ -302,11 +359,18 @@ async fn snapshot_cmd(action: SnapshotAction) -> Result<()> {
 
 async fn pull(image: &str) -> Result<()> {
     let oci = bux_oci::Oci::open()?;
-    let result = oci.pull(image, |msg| eprintln!("{msg}")).await?;
+    let result = oci.pull(image, report_pull).await?;
     println!("{}", result.reference);
     Ok(())
 }
 
+/// Prints pull milestones to stderr; per-chunk byte counts are omitted.
+fn report_pull(progress: &bux_oci::PullProgress) {
+    if !matches!(progress, bux_oci::PullProgress::LayerProgress { .. }) {
+        eprintln!("{progress}");
+    }
+}
+
 fn images(format: OutputFormat) -> Result<()> {
     let oci = bux_oci::Oci::open()?;
     let list = oci.images()?;
@@ -333,6 +397,90 @@ fn images(format: OutputFormat) -> Result<()> {
     Ok(())
 }
 
+fn images_du(format: OutputFormat) -> Result<()> {
+    let oci = bux_oci::Oci::open()?;
+    let usage = oci.disk_usage()?;
+
+    if matches!(format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&usage)?);
+        return Ok(());
+    }
+
+    println!("{:<50} {:>12} {:>12}", "REFERENCE", "SHARED", "UNIQUE");
+    for u in &usage {
+        println!(
+            "{:<50} {:>12} {:>12}",
+            u.reference,
+            human_size(u.shared_bytes),
+            human_size(u.unique_bytes)
+        );
+    }
+    Ok(())
+}
+
+fn images_fsck(repair: bool, format: OutputFormat) -> Result<()> {
+    let oci = bux_oci::Oci::open()?;
+    let report = oci.fsck(repair)?;
+
+    if matches!(format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let paths = |v: &[std::path::PathBuf]| -> Vec<String> {
+            v.iter().map(|p| p.display().to_string()).collect()
+        };
+        let sections = [
+            ("corrupt layer", report.corrupt_layers.clone()),
+            ("missing layer", report.missing_layers.clone()),
+            ("orphaned layer", report.orphaned_layers.clone()),
+            ("orphaned config", paths(&report.orphaned_configs)),
+            ("orphaned rootfs", paths(&report.orphaned_rootfs)),
+            ("stale staging", paths(&report.stale_staging)),
+            ("ref count mismatch", report.ref_count_mismatches.clone()),
+        ];
+        for (label, items) in &sections {
+            for item in items {
+                println!("{label}: {item}");
+            }
+        }
+        if report.is_clean() {
+            println!("Image store is clean.");
+        } else if report.repaired {
+            println!("Repaired.");
+        }
+    }
+    if !report.is_clean() && !report.repaired {
+        anyhow::bail!("image store has problems; rerun with --repair");
+    }
+    Ok(())
+}
+
+fn images_prune(unused_for: Option<u64>, keep: Option<usize>) -> Result<()> {
+    let oci = bux_oci::Oci::open()?;
+    let mut policy = bux_oci::KeepPolicy::new();
+    if let Some(secs) = unused_for {
+        policy = policy.unused_for(std::time::Duration::from_secs(secs));
+    }
+    if let Some(n) = keep {
+        policy = policy.keep_recent(n);
+    }
+    #[cfg(unix)]
+    for vm in vm::open_runtime()?.list()? {
+        if let Some(image) = vm.image {
+            policy = policy.protect(image);
+        }
+    }
+
+    let report = oci.prune(&policy)?;
+    for r in &report.removed {
+        println!("{r}");
+    }
+    eprintln!(
+        "Total reclaimed space: {}",
+        human_size(report.reclaimed_bytes)
+    );
+    Ok(())
+}
+
 fn rmi(refs: &[String]) -> Result<()> {
     let oci = bux_oci::Oci::open()?;
     for r in refs {
@@ -342,20 +490,6 @@ fn rmi(refs: &[String]) -> Result<()> {
     Ok(())
 }
 
-const FEATURES: &[(Feature, &str)] = &[
-    (Feature::Net, "net"),
-    (Feature::Blk, "blk"),
-    (Feature::Gpu, "gpu"),
-    (Feature::Snd, "snd"),
-    (Feature::Input, "input"),
-    (Feature::Efi, "efi"),
-    (Feature::Tee, "tee"),
-    (Feature::AmdSev, "amd-sev"),
-    (Feature::IntelTdx, "intel-tdx"),
-    (Feature::AwsNitro, "aws-nitro"),
-    (Feature::VirglResourceMap2, "virgl-resource-map2"),
-];
-
 /// Environment variables that affect host capture / paths (documented for operators).
 const CAPTURE_ENV: &[(&str, &str)] = &[
     (
@@ -379,7 +513,7 @@ fn rmi(refs: &[String]) -> Result<()> {
 
 fn system_info(format: OutputFormat) -> Result<()> {
     let max_vcpus = Vm::max_vcpus().ok();
-    let supported: Vec<&str> = FEATURES
+    let supported: Vec<&str> = bux::doctor::KRUN_FEATURES
         .iter()
         .filter(|(f, _)| Vm::has_feature(*f).unwrap_or(false))
         .map(|(_, name)| *name)
@@ -435,6 +569,7 @@ fn system_info(format: OutputFormat) -> Result<()> {
     }
     println!("virtualization: {}", yn(host.virtualization));
     println!("namespaces:     {} (bwrap)", yn(host.namespaces));
+    println!("user ns:        {}", yn(host.user_namespaces));
     println!("landlock:       {}", yn(host.landlock));
     println!("seccomp:        {}", yn(host.seccomp));
     println!("cgroups v2:     {}", yn(host.cgroups));
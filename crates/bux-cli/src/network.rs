@@ -0,0 +1,76 @@
+//! `bux network` — named private network management.
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+
+use crate::OutputFormat;
+use crate::vm::open_runtime;
+
+/// Subcommands for `bux network`.
+#[derive(Subcommand)]
+pub enum NetworkAction {
+    /// Create a named private network.
+    Create {
+        /// Network name (a DNS label; also the members' search domain).
+        name: String,
+    },
+    /// List named private networks.
+    #[command(visible_alias = "ls")]
+    List {
+        /// Output format.
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Remove a named private network (fails while VMs are attached).
+    Rm {
+        /// Network name.
+        name: String,
+    },
+}
+
+pub fn dispatch(action: NetworkAction) -> Result<()> {
+    match action {
+        NetworkAction::Create { name } => create(&name),
+        NetworkAction::List { format } => list(format),
+        NetworkAction::Rm { name } => rm(&name),
+    }
+}
+
+fn create(name: &str) -> Result<()> {
+    let rt = open_runtime()?;
+    let info = rt
+        .network()
+        .create_network(name)
+        .context("create network")?;
+    println!("{}", info.name);
+    Ok(())
+}
+
+fn list(format: OutputFormat) -> Result<()> {
+    let rt = open_runtime()?;
+    let networks = rt.network().networks().context("list networks")?;
+
+    if matches!(format, OutputFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&networks)?);
+        return Ok(());
+    }
+
+    if networks.is_empty() {
+        println!("No networks.");
+        return Ok(());
+    }
+    println!("NAME");
+    for n in &networks {
+        println!("{}", n.name);
+    }
+    Ok(())
+}
+
+fn rm(name: &str) -> Result<()> {
+    let rt = open_runtime()?;
+    rt.network()
+        .remove_network(name)
+        .context("remove network")?;
+    println!("{name}");
+    Ok(())
+}
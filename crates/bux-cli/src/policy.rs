@@ -0,0 +1,94 @@
+//! `bux system policy` — the runtime-wide host policy file.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use bux::HostPolicy;
+use clap::Subcommand;
+
+use crate::OutputFormat;
+use crate::run::CreateArgs;
+
+/// Subcommands for `bux system policy`.
+#[derive(Subcommand)]
+pub enum PolicyAction {
+    /// Dry-run `bux create` flags against the host policy.
+    ///
+    /// Exits non-zero when the policy would reject the VM.
+    Check {
+        /// Policy file to check against (default: `policy.toml` or
+        /// `policy.json` in the data dir).
+        #[arg(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
+
+        /// Output format.
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+
+        #[command(flatten)]
+        create: Box<CreateArgs>,
+    },
+}
+
+pub fn dispatch(action: PolicyAction) -> Result<()> {
+    match action {
+        PolicyAction::Check {
+            policy,
+            format,
+            create,
+        } => check(policy.as_deref(), format, *create),
+    }
+}
+
+/// Rejects `opts` the data dir's host policy would refuse at create.
+pub fn enforce(opts: &bux::VmOptions) -> Result<()> {
+    let (_, policy) = load(None)?;
+    policy.enforce(opts)?;
+    Ok(())
+}
+
+/// Policy at `path`, else the data dir's policy file, with its source.
+fn load(path: Option<&Path>) -> Result<(Option<PathBuf>, HostPolicy)> {
+    let source = path
+        .map(Path::to_path_buf)
+        .or_else(|| HostPolicy::find(&bux::default_data_dir()));
+    let policy = match source {
+        Some(ref file) => HostPolicy::from_file(file)
+            .with_context(|| format!("load host policy {}", file.display()))?,
+        None => HostPolicy::new(),
+    };
+    Ok((source, policy))
+}
+
+fn check(path: Option<&Path>, format: OutputFormat, create: CreateArgs) -> Result<()> {
+    let (source, policy) = load(path)?;
+    let violations = policy.check(&create.vm_options()?);
+
+    if matches!(format, OutputFormat::Json) {
+        let obj = serde_json::json!({
+            "policy": source,
+            "allowed": violations.is_empty(),
+            "violations": violations,
+        });
+        println!("{}", serde_json::to_string_pretty(&obj)?);
+    } else {
+        match source {
+            Some(ref file) => println!("policy: {}", file.display()),
+            None => println!("policy: none (everything allowed)"),
+        }
+        if violations.is_empty() {
+            println!("allowed");
+        } else {
+            println!("{:<26} MESSAGE", "RULE");
+            for v in &violations {
+                println!("{:<26} {}", v.rule, v.message);
+            }
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        anyhow::bail!("{} host policy violation(s)", violations.len())
+    }
+}
@@ -13,6 +13,7 @@ struct ResolvedRootfs {
     path: String,
     oci_cfg: Option<bux_oci::ImageConfig>,
     disk_cache_key: Option<String>,
+    verification: Option<bux_oci::Verification>,
 }
 
 /// Arguments for `bux run`.
@@ -61,20 +62,121 @@ pub struct RunArgs {
     #[arg(short = 'w', long)]
     workdir: Option<String>,
 
-    /// Publish a TCP port (host:guest, guest, 0:guest, :guest; optional /tcp).
+    /// Publish a port ([bind:]host:guest, guest, :guest; ranges as
+    /// start-end; optional /tcp or /udp).
     ///
-    /// Host bind is always 0.0.0.0. UDP is not supported in v1.
+    /// Host bind defaults to 0.0.0.0; use 127.0.0.1:8080:80 to stay local.
     #[arg(short = 'p', long = "publish")]
     publish: Vec<String>,
 
-    /// Restrict egress to hostnames/CIDRs (repeatable). Empty = unrestricted.
+    /// Egress rule (repeatable). Empty = unrestricted.
+    ///
+    /// Syntax: `[!]dest[:port[-port]][/tcp|/udp]` where dest is `*`, an IP
+    /// (`[v6]` before a port), a CIDR, a hostname or `*.suffix`. `!` denies;
+    /// deny rules win over allow rules.
     #[arg(long = "allow-net")]
     allow_net: Vec<String>,
 
-    /// Network mode: `enabled` (gvproxy virtio-net, default) or `none` (TSI / offline).
-    #[arg(long, default_value = "enabled", value_parser = ["enabled", "none"])]
+    /// Action for egress no rule matches (default: deny if any rule allows).
+    #[arg(long = "egress-default", value_parser = ["allow", "deny"])]
+    egress_default: Option<String>,
+
+    /// Network mode: `enabled` (gvproxy virtio-net, default), `userspace`
+    /// (pure-Rust virtio-net backend, no `--secret`) or `none` (TSI / offline).
+    #[arg(long, default_value = "enabled", value_parser = ["enabled", "userspace", "none"])]
     network: String,
 
+    /// Join a named private network (see `bux network create`).
+    #[arg(long = "net")]
+    net: Option<String>,
+
+    /// Hostname on the private network (default: the VM name).
+    #[arg(long, requires = "net")]
+    hostname: Option<String>,
+
+    /// Guest → network rate limit in bytes per second (e.g. `1M`).
+    #[arg(long = "egress-rate")]
+    egress_rate: Option<String>,
+
+    /// Network → guest rate limit in bytes per second (e.g. `10M`).
+    #[arg(long = "ingress-rate")]
+    ingress_rate: Option<String>,
+
+    /// Total guest → network bytes before `--on-quota` applies (e.g. `1G`).
+    #[arg(long = "egress-quota")]
+    egress_quota: Option<String>,
+
+    /// Total network → guest bytes before `--on-quota` applies.
+    #[arg(long = "ingress-quota")]
+    ingress_quota: Option<String>,
+
+    /// Action once a quota is used up: `block` (refuse new connections,
+    /// default), `throttle:RATE` or `stop`.
+    #[arg(long = "on-quota")]
+    on_quota: Option<String>,
+
+    /// Resolve a name inside the guest (`NAME=IP`, repeatable).
+    #[arg(long = "dns-record")]
+    dns_records: Vec<String>,
+
+    /// DNS resolver for guest queries instead of the host's (`IP[:PORT]`,
+    /// userspace backend only).
+    #[arg(long = "dns-upstream")]
+    dns_upstream: Option<String>,
+
+    /// Resolve `host.bux.internal` to an address reaching the host's loopback.
+    #[arg(long = "host-gateway")]
+    host_gateway: bool,
+
+    /// Give the guest an IPv6 address alongside IPv4 (`--network=userspace`).
+    #[arg(long)]
+    ipv6: bool,
+
+    /// Host CPU cap for the VMM in cores (e.g. `1.5`; cgroup v2, Linux).
+    #[arg(long = "cpu-limit")]
+    cpu_limit: Option<f64>,
+
+    /// Host memory cap for the VMM (e.g. `1G`), including page cache.
+    #[arg(long = "memory-limit")]
+    memory_limit: Option<String>,
+
+    /// Host memory soft limit for the VMM: reclaim and throttle above it
+    /// instead of OOM-killing (e.g. `768M`).
+    #[arg(long = "memory-high")]
+    memory_high: Option<String>,
+
+    /// Host swap cap for the VMM (e.g. `0` to disable swap).
+    #[arg(long = "memory-swap")]
+    memory_swap: Option<String>,
+
+    /// Maximum processes + threads in the VMM.
+    #[arg(long = "pids-limit")]
+    pids_limit: Option<u64>,
+
+    /// Block IO weight for the VMM, 1-10000 (default 100).
+    #[arg(long = "io-weight")]
+    io_weight: Option<u16>,
+
+    /// Disk image read limit in bytes per second (e.g. `50M`).
+    #[arg(long = "disk-read-bps")]
+    disk_read_bps: Option<String>,
+
+    /// Disk image write limit in bytes per second.
+    #[arg(long = "disk-write-bps")]
+    disk_write_bps: Option<String>,
+
+    /// Disk image read operations per second.
+    #[arg(long = "disk-read-iops")]
+    disk_read_iops: Option<u64>,
+
+    /// Disk image write operations per second.
+    #[arg(long = "disk-write-iops")]
+    disk_write_iops: Option<u64>,
+
+    /// Host CPUs the VMM may run on (e.g. `0-3,6`).
+    #[arg(long = "cpuset-cpus")]
+    cpuset_cpus: Option<String>,
+
     /// Host MITM secret (`name=value@host1,host2` or `name=value` using --allow-net hosts).
     ///
     /// Real values never enter the guest; use placeholders like `<BUX_SECRET:name>` in traffic.
@@ -157,18 +259,112 @@ pub struct CreateArgs {
     #[arg(long, short = 'm', default_value_t = 512)]
     memory: u32,
 
-    /// Publish a TCP port.
+    /// Publish a port ([bind:]host:guest[/udp]; ranges as start-end).
     #[arg(short = 'p', long = "publish")]
     publish: Vec<String>,
 
-    /// Restrict egress (repeatable). Empty = unrestricted.
+    /// Egress rule (`[!]dest[:ports][/proto]`, repeatable). Empty = unrestricted.
     #[arg(long = "allow-net")]
     allow_net: Vec<String>,
 
-    /// Network mode: `enabled` or `none`.
-    #[arg(long, default_value = "enabled", value_parser = ["enabled", "none"])]
+    /// Action for egress no rule matches: `allow` or `deny`.
+    #[arg(long = "egress-default", value_parser = ["allow", "deny"])]
+    egress_default: Option<String>,
+
+    /// Network mode: `enabled`, `userspace` or `none`.
+    #[arg(long, default_value = "enabled", value_parser = ["enabled", "userspace", "none"])]
     network: String,
 
+    /// Join a named private network.
+    #[arg(long = "net")]
+    net: Option<String>,
+
+    /// Hostname on the private network (default: the VM name).
+    #[arg(long, requires = "net")]
+    hostname: Option<String>,
+
+    /// Guest → network rate limit in bytes per second.
+    #[arg(long = "egress-rate")]
+    egress_rate: Option<String>,
+
+    /// Network → guest rate limit in bytes per second.
+    #[arg(long = "ingress-rate")]
+    ingress_rate: Option<String>,
+
+    /// Total guest → network bytes before `--on-quota` applies.
+    #[arg(long = "egress-quota")]
+    egress_quota: Option<String>,
+
+    /// Total network → guest bytes before `--on-quota` applies.
+    #[arg(long = "ingress-quota")]
+    ingress_quota: Option<String>,
+
+    /// Action once a quota is used up: `block`, `throttle:RATE` or `stop`.
+    #[arg(long = "on-quota")]
+    on_quota: Option<String>,
+
+    /// Resolve a name inside the guest (`NAME=IP`, repeatable).
+    #[arg(long = "dns-record")]
+    dns_records: Vec<String>,
+
+    /// DNS resolver for guest queries instead of the host's (`IP[:PORT]`,
+    /// userspace backend only).
+    #[arg(long = "dns-upstream")]
+    dns_upstream: Option<String>,
+
+    /// Resolve `host.bux.internal` to an address reaching the host's loopback.
+    #[arg(long = "host-gateway")]
+    host_gateway: bool,
+
+    /// Give the guest an IPv6 address alongside IPv4 (`--network=userspace`).
+    #[arg(long)]
+    ipv6: bool,
+
+    /// Host CPU cap for the VMM in cores (e.g. `1.5`; cgroup v2, Linux).
+    #[arg(long = "cpu-limit")]
+    cpu_limit: Option<f64>,
+
+    /// Host memory cap for the VMM (e.g. `1G`), including page cache.
+    #[arg(long = "memory-limit")]
+    memory_limit: Option<String>,
+
+    /// Host memory soft limit for the VMM: reclaim and throttle above it
+    /// instead of OOM-killing (e.g. `768M`).
+    #[arg(long = "memory-high")]
+    memory_high: Option<String>,
+
+    /// Host swap cap for the VMM (e.g. `0` to disable swap).
+    #[arg(long = "memory-swap")]
+    memory_swap: Option<String>,
+
+    /// Maximum processes + threads in the VMM.
+    #[arg(long = "pids-limit")]
+    pids_limit: Option<u64>,
+
+    /// Block IO weight for the VMM, 1-10000 (default 100).
+    #[arg(long = "io-weight")]
+    io_weight: Option<u16>,
+
+    /// Disk image read limit in bytes per second (e.g. `50M`).
+    #[arg(long = "disk-read-bps")]
+    disk_read_bps: Option<String>,
+
+    /// Disk image write limit in bytes per second.
+    #[arg(long = "disk-write-bps")]
+    disk_write_bps: Option<String>,
+
+    /// Disk image read operations per second.
+    #[arg(long = "disk-read-iops")]
+    disk_read_iops: Option<u64>,
+
+    /// Disk image write operations per second.
+    #[arg(long = "disk-write-iops")]
+    disk_write_iops: Option<u64>,
+
+    /// Host CPUs the VMM may run on (e.g. `0-3,6`).
+    #[arg(long = "cpuset-cpus")]
+    cpuset_cpus: Option<String>,
+
     /// Host MITM secret (`name=value@host` or `name=value`).
     #[arg(long = "secret")]
     secrets: Vec<String>,
@@ -185,8 +381,17 @@ pub struct CreateArgs {
 impl CreateArgs {
     /// Create + start VM, print ID (no initial command).
     pub async fn run(self) -> Result<()> {
-        // Reuse run path as detached empty-command run.
-        let args = RunArgs {
+        self.into_run_args().run().await
+    }
+
+    /// Managed options these flags would create (see [`RunArgs::vm_options`]).
+    pub fn vm_options(self) -> Result<bux::VmOptions> {
+        self.into_run_args().vm_options()
+    }
+
+    /// Reuse the run path as a detached empty-command run.
+    fn into_run_args(self) -> RunArgs {
+        RunArgs {
             image: Some(self.image),
             root: None,
             root_disk: None,
@@ -199,7 +404,30 @@ pub async fn run(self) -> Result<()> {
             workdir: None,
             publish: self.publish,
             allow_net: self.allow_net,
+            egress_default: self.egress_default,
             network: self.network,
+            net: self.net,
+            hostname: self.hostname,
+            egress_rate: self.egress_rate,
+            ingress_rate: self.ingress_rate,
+            egress_quota: self.egress_quota,
+            ingress_quota: self.ingress_quota,
+            on_quota: self.on_quota,
+            dns_records: self.dns_records,
+            dns_upstream: self.dns_upstream,
+            host_gateway: self.host_gateway,
+            ipv6: self.ipv6,
+            cpu_limit: self.cpu_limit,
+            memory_limit: self.memory_limit,
+            memory_high: self.memory_high,
+            memory_swap: self.memory_swap,
+            pids_limit: self.pids_limit,
+            io_weight: self.io_weight,
+            disk_read_bps: self.disk_read_bps,
+            disk_write_bps: self.disk_write_bps,
+            disk_read_iops: self.disk_read_iops,
+            disk_write_iops: self.disk_write_iops,
+            cpuset_cpus: self.cpuset_cpus,
             secrets: self.secrets,
             volume: self.volume,
             env: vec![],
@@ -214,12 +442,41 @@ pub async fn run(self) -> Result<()> {
             console_output: None,
             log_level: self.log_level,
             command: vec![],
-        };
-        args.run().await
+        }
     }
 }
 
 impl RunArgs {
+    /// Managed [`bux::VmOptions`] equivalent of the flags the host policy
+    /// constrains (image, size, egress, network, bind mounts).
+    pub fn vm_options(&self) -> Result<bux::VmOptions> {
+        let image = match (&self.image, &self.root) {
+            (Some(image), _) => bux::ImageRef::Oci(image.clone()),
+            (None, Some(root)) => bux::ImageRef::Rootfs(root.into()),
+            (None, None) => anyhow::bail!("an image or --root is required"),
+        };
+        let mut opts = bux::VmOptions::from_image(image)
+            .vcpus(self.cpus)
+            .ram_mib(self.memory)
+            .egress(&self.egress()?)
+            .virtio_net(self.network != "none");
+        for spec in &self.volume {
+            opts = opts.volume(
+                bux::parse_bind_spec(spec).with_context(|| format!("invalid -v {spec:?}"))?,
+            );
+        }
+        Ok(opts)
+    }
+
+    /// Egress rules and default from `--allow-net` / `--egress-default`.
+    fn egress(&self) -> Result<bux::EgressPolicy> {
+        let egress = bux::EgressPolicy::parse(&self.allow_net).context("invalid --allow-net")?;
+        Ok(match self.egress_default.as_deref() {
+            Some(action) => egress.with_default_deny(action == "deny"),
+            None => egress,
+        })
+    }
+
     /// Create/start VM according to CLI flags.
     #[allow(
         clippy::cognitive_complexity,
@@ -233,7 +490,11 @@ pub async fn run(self) -> Result<()> {
             )
         }
 
-        let resolved_root = self.resolve_rootfs().await?;
+        crate::policy::enforce(&self.vm_options()?)?;
+        let egress = self.egress()?;
+
+        let rt = crate::vm::open_runtime()?;
+        let resolved_root = self.resolve_rootfs(&rt).await?;
         let rootfs = resolved_root.path;
         let oci_cfg = resolved_root.oci_cfg;
         let disk_cache_key = resolved_root.disk_cache_key;
@@ -263,6 +524,9 @@ pub async fn run(self) -> Result<()> {
         } else {
             b = b.root(&rootfs);
         }
+        if let Some(verification) = resolved_root.verification {
+            b = b.image_verification(verification);
+        }
 
         // Working directory: CLI flag > OCI config > none.
         let workdir = self
@@ -298,22 +562,84 @@ pub async fn run(self) -> Result<()> {
             .chain(self.env)
             .collect();
 
-        // Ports: -p host:guest | guest | 0:guest | :guest [/tcp]
+        // Ports: -p [bind:]host:guest | guest | :guest, ranges, [/tcp|/udp]
         for spec in &self.publish {
             // Validate early with the same parser Runtime uses.
             bux::parse_publish_spec(spec).with_context(|| format!("invalid -p {spec:?}"))?;
             b = b.port(spec.clone());
         }
 
-        if !self.allow_net.is_empty() {
-            b = b.allow_net(self.allow_net.clone());
-        }
+        b = b.egress(&egress);
 
         let virtio_net = self.network != "none";
         b = b.virtio_net(virtio_net);
+        if self.network == "userspace" {
+            b = b.net_backend(bux::NetworkBackendKind::Userspace);
+        }
+        if let Some(net) = self.net {
+            if !virtio_net {
+                anyhow::bail!("--net requires virtio-net (not --network=none)");
+            }
+            let mut attachment = bux::NetworkAttachment::new(net);
+            if let Some(hostname) = self.hostname {
+                attachment = attachment.hostname(hostname);
+            }
+            b = b.network(attachment);
+        }
 
-        if !self.secrets.is_empty() {
+        let traffic = parse_traffic_limits(
+            [
+                self.egress_rate.as_deref(),
+                self.ingress_rate.as_deref(),
+                self.egress_quota.as_deref(),
+                self.ingress_quota.as_deref(),
+            ],
+            self.on_quota.as_deref(),
+        )?;
+        if !traffic.is_unlimited() {
             if !virtio_net {
+                anyhow::bail!("rate limits and quotas require virtio-net (not --network=none)");
+            }
+            b = b.traffic_limits(traffic);
+        }
+
+        let dns = parse_dns(
+            &self.dns_records,
+            self.dns_upstream.as_deref(),
+            self.host_gateway,
+        )?;
+        if !dns.is_empty() {
+            if !virtio_net {
+                anyhow::bail!("DNS options require virtio-net (not --network=none)");
+            }
+            b = b.dns(dns);
+        }
+        if self.ipv6 {
+            if self.network != "userspace" {
+                anyhow::bail!("--ipv6 requires --network=userspace");
+            }
+            b = b.ipv6(true);
+        }
+
+        let host_limits = parse_host_limits(
+            self.cpu_limit,
+            [
+                self.memory_limit.as_deref(),
+                self.memory_high.as_deref(),
+                self.memory_swap.as_deref(),
+                self.disk_read_bps.as_deref(),
+                self.disk_write_bps.as_deref(),
+            ],
+            [self.pids_limit, self.disk_read_iops, self.disk_write_iops],
+            self.io_weight,
+            self.cpuset_cpus.as_deref(),
+        )?;
+        if !host_limits.is_unlimited() {
+            b = b.host_limits(host_limits);
+        }
+
+        if !self.secrets.is_empty() {
+            if self.network != "enabled" {
                 anyhow::bail!("--secret requires --network=enabled (gvproxy MITM)");
             }
             let secrets = parse_secrets(&self.secrets, &self.allow_net)?;
@@ -389,22 +715,23 @@ pub async fn run(self) -> Result<()> {
                     "detached run with an initial command is not supported by the managed runtime; start the VM detached, then run the command with bux exec"
                 )
             }
-            spawn_vm(b, image, name, true, auto_remove).await
+            spawn_vm(rt, b, image, name, true, auto_remove).await
         } else {
-            run_foreground_vm(b, image, name, auto_remove, exec_req, interactive).await
+            run_foreground_vm(rt, b, image, name, auto_remove, exec_req, interactive).await
         }
     }
 
-    /// Resolves rootfs path and optional OCI config.
-    async fn resolve_rootfs(&self) -> Result<ResolvedRootfs> {
+    /// Resolves rootfs path and optional OCI config, admitting an image
+    /// against `rt`'s image policy.
+    async fn resolve_rootfs(&self, rt: &bux::Runtime) -> Result<ResolvedRootfs> {
         match (&self.image, &self.root, &self.root_disk) {
             (Some(img), None, None) => {
-                let oci = bux_oci::Oci::open()?;
-                let r = oci.ensure(img, |msg| eprintln!("{msg}")).await?;
+                let (r, verification) = pull_admitted(rt.oci(), &rt.image_policy(), img).await?;
                 Ok(ResolvedRootfs {
                     path: r.rootfs.to_string_lossy().into_owned(),
                     oci_cfg: r.config,
                     disk_cache_key: Some(r.digest.replace(':', "-")),
+                    verification: Some(verification),
                 })
             }
             (None, Some(root), None) => Ok(ResolvedRootfs {
@@ -414,19 +741,34 @@ async fn resolve_rootfs(&self) -> Result<ResolvedRootfs> {
                     .disk
                     .then(|| rootfs_cache_key(Path::new(root)))
                     .transpose()?,
+                verification: None,
             }),
             (None, None, Some(_)) => Ok(ResolvedRootfs {
                 path: String::new(),
                 oci_cfg: None,
                 disk_cache_key: None,
+                verification: None,
             }),
             _ => unreachable!("clap validation"),
         }
     }
 }
 
+/// Pulls `image` (or reuses the cached copy) and checks it against
+/// `policy`, as the managed create pipeline does.
+async fn pull_admitted(
+    oci: &bux_oci::Oci,
+    policy: &bux_oci::VerifyPolicy,
+    image: &str,
+) -> bux_oci::Result<(bux_oci::PullResult, bux_oci::Verification)> {
+    let pulled = oci.ensure(image, crate::report_pull).await?;
+    let verification = oci.verify(&pulled, policy).await?;
+    Ok((pulled, verification))
+}
+
 #[cfg(unix)]
 async fn run_foreground_vm(
+    rt: bux::Runtime,
     builder: bux::VmBuilder,
     image: Option<String>,
     name: Option<String>,
@@ -434,7 +776,6 @@ async fn run_foreground_vm(
     exec_req: Option<bux::ExecStart>,
     interactive: bool,
 ) -> Result<()> {
-    let rt = crate::vm::open_runtime()?;
     let mut handle = rt.spawn(&builder, image, name, auto_remove)?;
     handle
         .wait_ready(std::time::Duration::from_secs(30))
@@ -460,11 +801,11 @@ async fn run_foreground_vm(
     let exit_code = tokio::select! {
         result = run => result?,
         _ = sigterm.recv() => {
-            stop_vm(&id).await?;
+            stop_vm(&rt, &id).await?;
             128 + libc::SIGTERM
         }
         _ = sigint.recv() => {
-            stop_vm(&id).await?;
+            stop_vm(&rt, &id).await?;
             128 + libc::SIGINT
         }
     };
@@ -476,8 +817,7 @@ async fn run_foreground_vm(
 }
 
 #[cfg(unix)]
-async fn stop_vm(id: &str) -> Result<()> {
-    let rt = crate::vm::open_runtime()?;
+async fn stop_vm(rt: &bux::Runtime, id: &str) -> Result<()> {
     let mut handle = rt.get(id)?;
     match handle.stop().await {
         Err(_) if handle.is_alive() => {
@@ -488,11 +828,117 @@ async fn stop_vm(id: &str) -> Result<()> {
     }
 }
 
+/// Builds [`bux::TrafficLimits`] from the `--egress-rate`, `--ingress-rate`,
+/// `--egress-quota` and `--ingress-quota` values (in that order) and
+/// `--on-quota`.
+fn parse_traffic_limits(
+    sizes: [Option<&str>; 4],
+    on_quota: Option<&str>,
+) -> Result<bux::TrafficLimits> {
+    const FLAGS: [&str; 4] = [
+        "--egress-rate",
+        "--ingress-rate",
+        "--egress-quota",
+        "--ingress-quota",
+    ];
+    let mut parsed = [None; 4];
+    for ((slot, value), flag) in parsed.iter_mut().zip(sizes).zip(FLAGS) {
+        if let Some(value) = value {
+            *slot = Some(bux::parse_byte_size(value).with_context(|| format!("invalid {flag}"))?);
+        }
+    }
+    let [egress_rate, ingress_rate, egress_quota, ingress_quota] = parsed;
+    let limits = bux::TrafficLimits {
+        egress_rate,
+        ingress_rate,
+        egress_quota,
+        ingress_quota,
+        on_quota: on_quota
+            .map(str::parse)
+            .transpose()
+            .context("invalid --on-quota")?
+            .unwrap_or_default(),
+    };
+    limits.validate().context("invalid traffic limits")?;
+    Ok(limits)
+}
+
+/// Builds [`bux::DnsConfig`] from `--dns-record`, `--dns-upstream` and
+/// `--host-gateway`.
+fn parse_dns(
+    records: &[String],
+    upstream: Option<&str>,
+    host_gateway: bool,
+) -> Result<bux::DnsConfig> {
+    let mut dns = bux::DnsConfig::new().with_host_gateway(host_gateway);
+    for spec in records {
+        let record: bux::DnsRecord = spec
+            .parse()
+            .with_context(|| format!("invalid --dns-record {spec:?}"))?;
+        dns.records.push(record);
+    }
+    if let Some(upstream) = upstream {
+        dns.upstream = Some(bux::parse_dns_upstream(upstream).context("invalid --dns-upstream")?);
+    }
+    dns.validate().context("invalid DNS records")?;
+    Ok(dns)
+}
+
+/// Builds [`bux::HostLimits`] from `--cpu-limit`; the byte sizes
+/// `--memory-limit`, `--memory-high`, `--memory-swap`, `--disk-read-bps`
+/// and `--disk-write-bps`; the counts `--pids-limit`, `--disk-read-iops`
+/// and `--disk-write-iops` (each in that order); `--io-weight` and
+/// `--cpuset-cpus`.
+fn parse_host_limits(
+    cpus: Option<f64>,
+    sizes: [Option<&str>; 5],
+    counts: [Option<u64>; 3],
+    io_weight: Option<u16>,
+    cpuset: Option<&str>,
+) -> Result<bux::HostLimits> {
+    const FLAGS: [&str; 5] = [
+        "--memory-limit",
+        "--memory-high",
+        "--memory-swap",
+        "--disk-read-bps",
+        "--disk-write-bps",
+    ];
+    let mut parsed = [None; 5];
+    for ((slot, value), flag) in parsed.iter_mut().zip(sizes).zip(FLAGS) {
+        if let Some(value) = value {
+            *slot = Some(bux::parse_byte_size(value).with_context(|| format!("invalid {flag}"))?);
+        }
+    }
+    let [
+        memory_bytes,
+        memory_high_bytes,
+        memory_swap_bytes,
+        disk_read_bps,
+        disk_write_bps,
+    ] = parsed;
+    let [pids, disk_read_iops, disk_write_iops] = counts;
+    let limits = bux::HostLimits {
+        cpus,
+        memory_bytes,
+        memory_high_bytes,
+        memory_swap_bytes,
+        pids,
+        io_weight,
+        disk_read_bps,
+        disk_write_bps,
+        disk_read_iops,
+        disk_write_iops,
+        cpuset: cpuset.map(str::to_owned),
+    };
+    limits.validate().context("invalid host limits")?;
+    Ok(limits)
+}
+
 /// Parse `--secret` specs into [`bux::Secret`] values.
 ///
 /// Formats:
 /// - `name=value@host1,host2`
-/// - `name=value` (hosts from `--allow-net`, or `*` if empty)
+/// - `name=value` (hostnames allowed by `--allow-net`, or `*` if none)
 pub fn parse_secrets(specs: &[String], allow_net: &[String]) -> Result<Vec<bux::Secret>> {
     let mut out = Vec::with_capacity(specs.len());
     for spec in specs {
@@ -514,10 +960,11 @@ fn parse_one_secret(spec: &str, allow_net: &[String]) -> Result<bux::Secret> {
     }
     let hosts: Vec<String> = hosts_part.map_or_else(
         || {
-            if allow_net.is_empty() {
+            let allowed = allowed_hostnames(allow_net);
+            if allowed.is_empty() {
                 vec!["*".into()]
             } else {
-                allow_net.to_vec()
+                allowed
             }
         },
         |h| {
@@ -534,6 +981,20 @@ fn parse_one_secret(spec: &str, allow_net: &[String]) -> Result<bux::Secret> {
     Ok(bux::Secret::new(name, hosts, value))
 }
 
+/// Hostname patterns of the allow rules in `allow_net` (invalid rules skipped).
+fn allowed_hostnames(allow_net: &[String]) -> Vec<String> {
+    allow_net
+        .iter()
+        .filter_map(|raw| raw.parse::<bux::EgressRule>().ok())
+        .filter(|rule| rule.action == bux::EgressAction::Allow)
+        .filter_map(|rule| match rule.target {
+            bux::EgressTarget::Host(host) => Some(host),
+            bux::EgressTarget::Wildcard(suffix) => Some(format!("*.{suffix}")),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Creates an ext4 disk image from an OCI rootfs directory.
 #[cfg(unix)]
 fn create_disk_from_rootfs(rootfs: &str, cache_key: Option<&str>) -> Result<String> {
@@ -613,13 +1074,13 @@ fn hash_rootfs_entry(root: &Path, path: &Path, hasher: &mut Sha256) -> Result<()
 
 #[cfg(unix)]
 async fn spawn_vm(
+    rt: bux::Runtime,
     builder: bux::VmBuilder,
     image: Option<String>,
     name: Option<String>,
     detach: bool,
     auto_remove: bool,
 ) -> Result<()> {
-    let rt = crate::vm::open_runtime()?;
     let mut handle = if detach {
         rt.spawn_detached(&builder, image, name, auto_remove)?
     } else {
@@ -656,6 +1117,7 @@ async fn spawn_vm(
 #[cfg(not(unix))]
 #[allow(clippy::unused_async)]
 async fn spawn_vm(
+    _rt: bux::Runtime,
     _builder: bux::VmBuilder,
     _image: Option<String>,
     _name: Option<String>,
@@ -683,9 +1145,163 @@ fn parse_uses_allow_net() {
         assert_eq!(s.hosts, vec!["h1"]);
     }
 
+    #[test]
+    fn parse_uses_allowed_hostnames_only() {
+        let rules = [
+            "api.example.com:443".into(),
+            "!evil.com".into(),
+            "10.0.0.0/8".into(),
+        ];
+        let s = parse_one_secret("t=val", &rules).unwrap();
+        assert_eq!(s.hosts, vec!["api.example.com"]);
+    }
+
     #[test]
     fn parse_star_default() {
         let s = parse_one_secret("t=val", &[]).unwrap();
         assert_eq!(s.hosts, vec!["*"]);
     }
 }
+
+#[cfg(test)]
+mod traffic_tests {
+    use super::*;
+
+    #[test]
+    fn parses_limits_and_action() {
+        let limits =
+            parse_traffic_limits([Some("1M"), None, None, Some("2G")], Some("throttle:64K"))
+                .unwrap();
+        assert_eq!(limits.egress_rate, Some(1 << 20));
+        assert_eq!(limits.ingress_rate, None);
+        assert_eq!(limits.ingress_quota, Some(2 << 30));
+        assert_eq!(
+            limits.on_quota,
+            bux::QuotaAction::Throttle { rate: 64 << 10 }
+        );
+        assert!(
+            parse_traffic_limits([None; 4], None)
+                .unwrap()
+                .is_unlimited()
+        );
+        assert!(parse_traffic_limits([Some("fast"), None, None, None], None).is_err());
+        assert!(parse_traffic_limits([Some("0"), None, None, None], None).is_err());
+        assert!(parse_traffic_limits([None; 4], Some("drop")).is_err());
+    }
+}
+
+#[cfg(test)]
+mod host_limits_tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_and_counts() {
+        let limits = parse_host_limits(
+            Some(1.5),
+            [Some("1G"), Some("768M"), Some("0"), None, Some("10M")],
+            [Some(256), None, Some(100)],
+            Some(50),
+            Some("0-3"),
+        )
+        .unwrap();
+        assert_eq!(limits.cpus, Some(1.5));
+        assert_eq!(limits.memory_bytes, Some(1 << 30));
+        assert_eq!(limits.memory_high_bytes, Some(768 << 20));
+        assert_eq!(limits.memory_swap_bytes, Some(0));
+        assert_eq!(limits.disk_write_bps, Some(10 << 20));
+        assert_eq!(limits.pids, Some(256));
+        assert_eq!(limits.disk_write_iops, Some(100));
+        assert_eq!(limits.io_weight, Some(50));
+        assert_eq!(limits.cpuset.as_deref(), Some("0-3"));
+        assert!(
+            parse_host_limits(None, [None; 5], [None; 3], None, None)
+                .unwrap()
+                .is_unlimited()
+        );
+        let bad_size = [Some("lots"), None, None, None, None];
+        assert!(parse_host_limits(None, bad_size, [None; 3], None, None).is_err());
+        assert!(parse_host_limits(Some(0.0), [None; 5], [None; 3], None, None).is_err());
+        assert!(parse_host_limits(None, [None; 5], [None; 3], Some(20_000), None).is_err());
+        assert!(parse_host_limits(None, [None; 5], [None; 3], None, Some("x")).is_err());
+    }
+}
+
+#[cfg(test)]
+mod dns_tests {
+    use super::*;
+
+    #[test]
+    fn parses_records_and_upstream() {
+        let dns = parse_dns(
+            &["db.test=10.0.0.5".to_owned(), "cache=10.0.0.6".to_owned()],
+            Some("1.1.1.1"),
+            true,
+        )
+        .unwrap();
+        assert_eq!(dns.lookup("db.test"), Some("10.0.0.5".parse().unwrap()));
+        assert_eq!(dns.upstream, Some("1.1.1.1:53".parse().unwrap()));
+        assert!(dns.host_gateway);
+        assert!(parse_dns(&[], None, false).unwrap().is_empty());
+        assert!(parse_dns(&["db.test".to_owned()], None, false).is_err());
+        assert!(
+            parse_dns(
+                &["a=1.2.3.4".to_owned(), "A=1.2.3.4".to_owned()],
+                None,
+                false
+            )
+            .is_err()
+        );
+        assert!(parse_dns(&[], Some("dns.google"), false).is_err());
+    }
+}
+
+#[cfg(test)]
+mod image_policy_tests {
+    use bux_oci::test_registry::TestRegistry;
+
+    use super::*;
+
+    const ED25519_PEM: &str = "-----BEGIN PUBLIC KEY-----\n\
+        MCowBQYDK2VwAyEAAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=\n\
+        -----END PUBLIC KEY-----\n";
+
+    /// Image policy loaded from an `image-policy.toml` holding `toml`.
+    fn policy(dir: &Path, toml: &str) -> bux_oci::VerifyPolicy {
+        std::fs::write(dir.join("image-policy.toml"), toml).unwrap();
+        bux::ImagePolicy::load(dir).unwrap()
+    }
+
+    #[tokio::test]
+    async fn image_policy_blocks_unsigned_images() {
+        let registry = TestRegistry::start().await;
+        registry.add_image("1", &[("hello.txt", b"hi")]);
+        let image = format!("{}/team/app:1", registry.host());
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = bux_oci::OciConfig::default();
+        config.store_dir = dir.path().join("store");
+        let config = config.with_mirror(
+            registry.host(),
+            vec![bux_oci::RegistryEndpoint::new(registry.host()).plain_http()],
+        );
+        let oci = bux_oci::Oci::open_with(config).unwrap();
+        std::fs::write(dir.path().join("acme.pub"), ED25519_PEM).unwrap();
+
+        for rule in [
+            "default = { require = \"reject\" }",
+            "default = { require = \"signed\", keys = [\"acme.pub\"] }",
+        ] {
+            let err = pull_admitted(&oci, &policy(dir.path(), rule), &image)
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, bux_oci::OciError::Rejected { .. }),
+                "{rule}: {err}"
+            );
+        }
+
+        let accept = policy(dir.path(), "default = { require = \"accept\" }");
+        let (_, verification) = pull_admitted(&oci, &accept, &image).await.unwrap();
+        assert_eq!(verification.reference, image);
+    }
+}
@@ -425,6 +425,7 @@ pub fn inspect(args: &InspectArgs) -> Result<()> {
             "allow_net": h.allow_net(),
             "security": h.security_status(),
             "security_options": h.security_options(),
+            "image_verification": h.image_verification(),
             "last_error": h.last_error(),
             "phase_a_limits": h.phase_a_limits(),
             "config": s.config,
@@ -625,9 +626,32 @@ pub async fn stats(args: &StatsArgs) -> Result<()> {
     println!("Boot time:      {} ms", bm.boot_duration_ms());
     println!("Exec count:     {}", bm.exec_count());
     println!("Last exec:      {} ms", bm.last_exec_duration_ms());
+    #[cfg(target_os = "linux")]
+    if let Some(host) = handle.host_stats()? {
+        print_host_stats(&host);
+    }
     Ok(())
 }
 
+/// Prints the VMM's cgroup usage (host side, independent of the guest).
+#[cfg(target_os = "linux")]
+fn print_host_stats(host: &bux::CgroupStats) {
+    let opt = |v: Option<u64>| v.map_or_else(|| "-".to_owned(), |b| format!("{b} B"));
+    println!("Host CPU:       {} us", host.cpu.usage_usec);
+    println!(
+        "CPU throttled:  {} us ({} periods)",
+        host.cpu.throttled_usec, host.cpu.nr_throttled
+    );
+    println!("Host memory:    {}", opt(host.memory_current));
+    println!("Memory peak:    {}", opt(host.memory_peak));
+    println!("OOM kills:      {}", host.memory_events.oom_kill);
+    let (read, written) = host
+        .io
+        .iter()
+        .fold((0, 0), |(r, w), io| (r + io.rbytes, w + io.wbytes));
+    println!("Disk IO:        {read} B read, {written} B written");
+}
+
 #[cfg(unix)]
 pub fn clone_box(args: &CloneArgs) -> Result<()> {
     let rt = open_runtime()?;
@@ -1,32 +1,44 @@
 //! Guest NIC configuration for gvproxy virtio-net.
 //!
-//! Static addressing matches `bux_gvproxy::constants`:
-//! - eth0: `192.168.127.2/24`
+//! Static addressing matches `bux_net_types::constants`:
+//! - eth0: `192.168.127.2/24` (or the boot config's private-network address)
 //! - gateway / DNS: `192.168.127.1`
+//! - dual-stack only: the boot config's IPv6 address (`/64`), default
+//!   route via `fd42:6275:7800::1`
 //!
 //! Uses rtnetlink (pure Rust) — no `ip` binary dependency.
 
 use std::fs;
-use std::net::Ipv4Addr;
+use std::net::{Ipv4Addr, Ipv6Addr};
 
+use bux_proto::GuestBootConfig;
 use futures::stream::TryStreamExt;
 use rtnetlink::new_connection;
 
 /// Guest interface created by libkrun virtio-net.
 const IFACE: &str = "eth0";
-/// Static guest address (must match gvproxy DHCP/static lease MAC).
+/// Default static guest address (must match gvproxy DHCP/static lease MAC).
 const GUEST_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 127, 2);
 /// Prefix length for the virtual subnet.
 const PREFIX_LEN: u8 = 24;
 /// Gateway (= gvproxy) address; also DNS.
 const GATEWAY_IP: Ipv4Addr = Ipv4Addr::new(192, 168, 127, 1);
+/// IPv6 gateway on dual-stack networks.
+const GATEWAY_IPV6: Ipv6Addr = Ipv6Addr::new(0xfd42, 0x6275, 0x7800, 0, 0, 0, 0, 1);
+/// Prefix length of the IPv6 subnet.
+const PREFIX_LEN_V6: u8 = 64;
+/// Hostname when the boot config names none.
+const DEFAULT_HOSTNAME: &str = "bux";
 
 /// Bring up loopback + eth0 with static IP and default route.
 ///
+/// Returns the configured eth0 address.
+///
 /// # Errors
 ///
 /// Returns I/O error if eth0 is missing or netlink operations fail.
-pub async fn configure_static_eth0() -> std::io::Result<()> {
+pub async fn configure_static_eth0(boot: &GuestBootConfig) -> std::io::Result<Ipv4Addr> {
+    let guest_ip = boot.ip.unwrap_or(GUEST_IP);
     let (connection, handle, _) =
         new_connection().map_err(|e| std::io::Error::other(format!("netlink connect: {e}")))?;
     tokio::spawn(connection);
@@ -71,19 +83,13 @@ pub async fn configure_static_eth0() -> std::io::Result<()> {
         .await
         .map_err(|e| std::io::Error::other(format!("{IFACE} up: {e}")))?;
 
+    // Already assigned is fine on restart paths.
     handle
         .address()
-        .add(idx, GUEST_IP.into(), PREFIX_LEN)
+        .add(idx, guest_ip.into(), PREFIX_LEN)
         .execute()
         .await
-        .or_else(|e| {
-            // Already assigned is fine on restart paths.
-            if e.to_string().contains("File exists") || e.to_string().contains("EEXIST") {
-                Ok(())
-            } else {
-                Err(std::io::Error::other(format!("addr add: {e}")))
-            }
-        })?;
+        .or_else(|e| exists_ok(&e, "addr add"))?;
 
     handle
         .route()
@@ -92,50 +98,94 @@ pub async fn configure_static_eth0() -> std::io::Result<()> {
         .gateway(GATEWAY_IP)
         .execute()
         .await
-        .or_else(|e| {
-            if e.to_string().contains("File exists") || e.to_string().contains("EEXIST") {
-                Ok(())
-            } else {
-                Err(std::io::Error::other(format!("default route: {e}")))
-            }
-        })?;
+        .or_else(|e| exists_ok(&e, "default route"))?;
+
+    if let Some(ipv6) = boot.ipv6 {
+        enable_ipv6();
+        handle
+            .address()
+            .add(idx, ipv6.into(), PREFIX_LEN_V6)
+            .execute()
+            .await
+            .or_else(|e| exists_ok(&e, "IPv6 addr add"))?;
+        handle
+            .route()
+            .add()
+            .v6()
+            .gateway(GATEWAY_IPV6)
+            .execute()
+            .await
+            .or_else(|e| exists_ok(&e, "IPv6 default route"))?;
+    }
 
-    write_resolv_gateway();
-    ensure_hosts_and_hostname();
-    Ok(())
+    write_resolv_gateway(boot.search_domain.as_deref());
+    ensure_hosts_and_hostname(guest_ip, boot.ipv6, hostname(boot));
+    Ok(guest_ip)
+}
+
+/// Treats an `EEXIST` netlink error as success.
+fn exists_ok(e: &rtnetlink::Error, what: &str) -> std::io::Result<()> {
+    let msg = e.to_string();
+    if msg.contains("File exists") || msg.contains("EEXIST") {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!("{what}: {e}")))
+    }
+}
+
+/// Turns IPv6 on for eth0 without router advertisements or duplicate
+/// address detection: the address is static and the link has no other
+/// hosts, so it is usable immediately.
+fn enable_ipv6() {
+    for (key, value) in [
+        ("disable_ipv6", "0"),
+        ("accept_ra", "0"),
+        ("accept_dad", "0"),
+    ] {
+        let _ = fs::write(format!("/proc/sys/net/ipv6/conf/{IFACE}/{key}"), value);
+    }
 }
 
 /// Offline / Disabled network: lo identity only, no eth0 requirement.
-pub fn configure_offline() {
-    ensure_hosts_and_hostname();
+pub fn configure_offline(boot: &GuestBootConfig) {
+    ensure_hosts_and_hostname(GUEST_IP, None, hostname(boot));
+}
+
+/// Hostname requested by the boot config, or [`DEFAULT_HOSTNAME`].
+fn hostname(boot: &GuestBootConfig) -> &str {
+    boot.hostname.as_deref().unwrap_or(DEFAULT_HOSTNAME)
 }
 
-/// Point DNS at the gvproxy gateway.
-fn write_resolv_gateway() {
+/// Point DNS at the gvproxy gateway, searching the private network's
+/// domain when there is one.
+fn write_resolv_gateway(search_domain: Option<&str>) {
     let _ = fs::create_dir_all("/etc");
-    let _ = fs::write(
-        "/etc/resolv.conf",
-        "# Generated by bux-guest (gvproxy gateway DNS)\nnameserver 192.168.127.1\n",
+    let search = search_domain.map_or_else(String::new, |domain| format!("search {domain}\n"));
+    let resolv = format!(
+        "# Generated by bux-guest (gvproxy gateway DNS)\nnameserver {GATEWAY_IP}\n{search}"
     );
+    let _ = fs::write("/etc/resolv.conf", resolv);
 }
 
-/// Ensure `/etc/hosts` and hostname are set for the guest.
-fn ensure_hosts_and_hostname() {
+/// Ensure `/etc/hosts` (with the IPv6 address, if any) and hostname are
+/// set for the guest.
+fn ensure_hosts_and_hostname(ip: Ipv4Addr, ipv6: Option<Ipv6Addr>, name: &str) {
     let hosts = std::path::Path::new("/etc/hosts");
     if !hosts.is_file() || fs::read_to_string(hosts).map_or(true, |c| c.is_empty()) {
+        let ipv6 = ipv6.map_or_else(String::new, |addr| format!("{addr} {name}\n"));
         let _ = fs::write(
             hosts,
-            "127.0.0.1 localhost\n::1 localhost\n192.168.127.2 bux\n",
+            format!("127.0.0.1 localhost\n::1 localhost\n{ip} {name}\n{ipv6}"),
         );
     }
 
     let hostname = std::path::Path::new("/etc/hostname");
     if !hostname.is_file() {
-        let _ = fs::write(hostname, "bux\n");
+        let _ = fs::write(hostname, format!("{name}\n"));
     }
-    if let Ok(name) = std::ffi::CString::new("bux") {
+    if let Ok(cname) = std::ffi::CString::new(name) {
         unsafe {
-            libc::sethostname(name.as_ptr(), 3);
+            libc::sethostname(cname.as_ptr(), name.len());
         }
     }
 }
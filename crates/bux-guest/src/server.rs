@@ -45,14 +45,14 @@ pub async fn run() -> io::Result<()> {
 
     match boot.network {
         GuestNetworkMode::Enabled => {
-            network::configure_static_eth0().await?;
+            let ip = network::configure_static_eth0(&boot).await?;
             eprintln!(
-                "[bux-guest] T+{}ms: static eth0 192.168.127.2/24 configured",
+                "[bux-guest] T+{}ms: static eth0 {ip}/24 configured",
                 uptime_ms()
             );
         }
         GuestNetworkMode::Disabled => {
-            network::configure_offline();
+            network::configure_offline(&boot);
             eprintln!(
                 "[bux-guest] T+{}ms: network disabled (offline)",
                 uptime_ms()
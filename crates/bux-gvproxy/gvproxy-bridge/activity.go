@@ -0,0 +1,141 @@
+package main
+
+// activity.go — per-instance network activity log (mirrors bux_gvproxy::ActivityEvent).
+//
+// The TCP forwarder records connects, closes with byte counts, and egress
+// denials into a bounded ring; the MITM proxy adds HTTP requests and the
+// shaper used-up quotas (shaping.go). Rust drains it with gvproxy_drain_activity;
+// when the ring is full the oldest events are overwritten. DNS is served
+// by upstream gvisor-tap-vsock and is not recorded here.
+
+import (
+	"encoding/json"
+	"net"
+	"sync"
+	"sync/atomic"
+	"time"
+)
+
+// activityCapacity bounds events held between drains.
+const activityCapacity = 4096
+
+type activityEvent struct {
+	Kind        string `json:"kind"` // tcp_connect | tcp_close | denied | http_request | quota_exceeded
+	TimestampMs int64  `json:"timestamp_ms"`
+	Protocol    string `json:"protocol,omitempty"`
+	Remote      string `json:"remote,omitempty"`
+	Host        string `json:"host,omitempty"`
+	Rule        string `json:"rule,omitempty"`
+	BytesOut    uint64 `json:"bytes_out,omitempty"` // guest → remote
+	BytesIn     uint64 `json:"bytes_in,omitempty"`  // remote → guest
+	Method      string `json:"method,omitempty"`
+	Path        string `json:"path,omitempty"`
+	Status      int    `json:"status,omitempty"`
+	Blocked     string `json:"blocked,omitempty"` // HTTP policy refusal reason
+	Direction   string `json:"direction,omitempty"` // egress | ingress
+	Limit       uint64 `json:"limit,omitempty"`     // quota in bytes
+}
+
+// activityLog is safe for concurrent use; a nil log records nothing.
+type activityLog struct {
+	mu     sync.Mutex
+	events []activityEvent
+}
+
+func newActivityLog(enabled bool) *activityLog {
+	if !enabled {
+		return nil
+	}
+	return &activityLog{}
+}
+
+func (l *activityLog) record(ev activityEvent) {
+	if l == nil {
+		return
+	}
+	ev.TimestampMs = time.Now().UnixMilli()
+	l.mu.Lock()
+	defer l.mu.Unlock()
+	if len(l.events) >= activityCapacity {
+		l.events = l.events[1:]
+	}
+	l.events = append(l.events, ev)
+}
+
+// drainJSON returns the buffered events as a JSON array and clears them.
+func (l *activityLog) drainJSON() string {
+	if l == nil {
+		return "[]"
+	}
+	l.mu.Lock()
+	events := l.events
+	l.events = nil
+	l.mu.Unlock()
+	if events == nil {
+		return "[]"
+	}
+	data, err := json.Marshal(events)
+	if err != nil {
+		return "[]"
+	}
+	return string(data)
+}
+
+func (l *activityLog) connect(remote, host string) {
+	l.record(activityEvent{Kind: "tcp_connect", Protocol: "tcp", Remote: remote, Host: host})
+}
+
+func (l *activityLog) denied(remote, host, rule string) {
+	l.record(activityEvent{Kind: "denied", Protocol: "tcp", Remote: remote, Host: host, Rule: rule})
+}
+
+func (l *activityLog) httpRequest(host, method, path string, status int, bytesOut, bytesIn uint64, blocked string) {
+	l.record(activityEvent{
+		Kind:     "http_request",
+		Protocol: "tcp",
+		Host:     host,
+		Method:   method,
+		Path:     path,
+		Status:   status,
+		BytesOut: bytesOut,
+		BytesIn:  bytesIn,
+		Blocked:  blocked,
+	})
+}
+
+func (l *activityLog) quotaExceeded(direction string, limit uint64) {
+	l.record(activityEvent{Kind: "quota_exceeded", Direction: direction, Limit: limit})
+}
+
+func (l *activityLog) closed(remote string, conn *countingConn) {
+	if l == nil || conn == nil {
+		return
+	}
+	l.record(activityEvent{
+		Kind:     "tcp_close",
+		Protocol: "tcp",
+		Remote:   remote,
+		BytesOut: conn.read.Load(),
+		BytesIn:  conn.written.Load(),
+	})
+}
+
+// countingConn wraps the guest side of a relayed connection: bytes read
+// from the guest went out, bytes written to it came in.
+type countingConn struct {
+	net.Conn
+	read    atomic.Uint64
+	written atomic.Uint64
+}
+
+func (c *countingConn) Read(p []byte) (int, error) {
+	n, err := c.Conn.Read(p)
+	c.read.Add(uint64(n))
+	return n, err
+}
+
+func (c *countingConn) Write(p []byte) (int, error) {
+	n, err := c.Conn.Write(p)
+	c.written.Add(uint64(n))
+	return n, err
+}
@@ -0,0 +1,73 @@
+package main
+
+import (
+	"encoding/json"
+	"net"
+	"testing"
+)
+
+func TestActivityLog_DrainClearsAndCaps(t *testing.T) {
+	log := newActivityLog(true)
+	for i := 0; i < activityCapacity+10; i++ {
+		log.connect("1.2.3.4:443", "example.com")
+	}
+	var events []activityEvent
+	if err := json.Unmarshal([]byte(log.drainJSON()), &events); err != nil {
+		t.Fatal(err)
+	}
+	if len(events) != activityCapacity {
+		t.Fatalf("expected %d events, got %d", activityCapacity, len(events))
+	}
+	if events[0].Kind != "tcp_connect" || events[0].Host != "example.com" || events[0].TimestampMs == 0 {
+		t.Fatalf("unexpected event %+v", events[0])
+	}
+	if got := log.drainJSON(); got != "[]" {
+		t.Fatalf("second drain should be empty, got %s", got)
+	}
+}
+
+func TestActivityLog_NilIsNoop(t *testing.T) {
+	log := newActivityLog(false)
+	log.denied("1.2.3.4:80", "", "!1.2.3.4")
+	if got := log.drainJSON(); got != "[]" {
+		t.Fatalf("disabled log drained %s", got)
+	}
+}
+
+func TestActivityLog_CloseCountsGuestBytes(t *testing.T) {
+	guest, peer := net.Pipe()
+	defer peer.Close()
+	counted := &countingConn{Conn: guest}
+	go func() {
+		buf := make([]byte, 5)
+		_, _ = peer.Read(buf)
+		_, _ = peer.Write([]byte("pong!!"))
+	}()
+	if _, err := counted.Write([]byte("ping!")); err != nil {
+		t.Fatal(err)
+	}
+	buf := make([]byte, 6)
+	if _, err := counted.Read(buf); err != nil {
+		t.Fatal(err)
+	}
+
+	log := newActivityLog(true)
+	log.closed("1.2.3.4:443", counted)
+	var events []activityEvent
+	if err := json.Unmarshal([]byte(log.drainJSON()), &events); err != nil {
+		t.Fatal(err)
+	}
+	if len(events) != 1 || events[0].BytesOut != 6 || events[0].BytesIn != 5 {
+		t.Fatalf("unexpected close event %+v", events)
+	}
+}
+
+func TestEgressFilter_DenyingRule(t *testing.T) {
+	f := NewTCPFilter([]string{"*.example.com", " !10.0.0.0/8 "}, "", "")
+	if got := f.denyingRule("", net.ParseIP("10.1.1.1"), 22); got != "!10.0.0.0/8" {
+		t.Fatalf("expected deny rule, got %q", got)
+	}
+	if got := f.denyingRule("", net.ParseIP("8.8.8.8"), 22); got != "" {
+		t.Fatalf("default deny has no rule, got %q", got)
+	}
+}
@@ -3,7 +3,10 @@ package main
 // dns_filter.go — DNS sinkhole for network allowlist.
 //
 // Builds gvisor-tap-vsock DNS zones from an allow_net list.
-// Allowed hostnames resolve normally; everything else gets 0.0.0.0.
+// Allowed hostnames resolve normally; everything else gets NXDOMAIN, as
+// from the userspace backend. A zone without a DefaultIP answers NXDOMAIN
+// for names it has no record for (A queries only: other types are still
+// forwarded upstream by gvisor-tap-vsock).
 
 import (
 	"context"
@@ -20,11 +23,11 @@ import (
 // Strategy:
 //   - For each allowed hostname: resolve to IPs, create a zone with A records
 //   - For wildcard patterns (*.example.com): create zone with Regexp records
-//   - Add catch-all root zone "" with DefaultIP 0.0.0.0 (sinkhole)
+//   - Add catch-all root zone "" without DefaultIP (NXDOMAIN)
 //
 // Zone matching is first-match-wins with suffix matching. Specific zones
 // are added before the root zone, so allowed hosts resolve normally while
-// everything else gets sinkholed.
+// everything else gets NXDOMAIN.
 func buildAllowNetDNSZones(allowNet []string) []types.Zone {
 	zoneRecords := make(map[string][]types.Record)
 
@@ -71,10 +74,10 @@ func buildAllowNetDNSZones(allowNet []string) []types.Zone {
 
 	var zones []types.Zone
 	for zoneName, records := range zoneRecords {
+		// No DefaultIP: non-allowed hosts in this zone get NXDOMAIN
 		zones = append(zones, types.Zone{
-			Name:      zoneName,
-			Records:   records,
-			DefaultIP: net.IPv4(0, 0, 0, 0), // Sinkhole non-allowed hosts in this TLD
+			Name:    zoneName,
+			Records: records,
 		})
 		logrus.WithFields(logrus.Fields{
 			"zone":    zoneName,
@@ -82,11 +85,8 @@ func buildAllowNetDNSZones(allowNet []string) []types.Zone {
 		}).Debug("allowNet: added DNS zone")
 	}
 
-	// Catch-all root zone: sinkhole everything not explicitly allowed
-	zones = append(zones, types.Zone{
-		Name:      "",
-		DefaultIP: net.IPv4(0, 0, 0, 0),
-	})
+	// Catch-all root zone: NXDOMAIN for everything not explicitly allowed
+	zones = append(zones, types.Zone{Name: ""})
 
 	logrus.WithFields(logrus.Fields{
 		"allow_zones": len(zones) - 1,
@@ -96,6 +96,19 @@ func buildAllowNetDNSZones(allowNet []string) []types.Zone {
 	return zones
 }
 
+// buildDenyDNSZones answers NXDOMAIN under each denied suffix
+// (".example.com" for "!*.example.com").
+//
+// Exact-name deny rules cannot be expressed as zones (a zone only owns
+// the names below it); they are enforced when connecting instead.
+func buildDenyDNSZones(suffixes []string) []types.Zone {
+	zones := make([]types.Zone, 0, len(suffixes))
+	for _, suffix := range suffixes {
+		zones = append(zones, types.Zone{Name: strings.TrimPrefix(suffix, ".") + "."})
+	}
+	return zones
+}
+
 // resolveAndAddRecords resolves a hostname and adds A records to the zone.
 func resolveAndAddRecords(hostname, zoneName string, zoneRecords map[string][]types.Record) {
 	ctx := context.Background()
@@ -1,7 +1,6 @@
 package main
 
 import (
-	"net"
 	"testing"
 )
 
@@ -21,12 +20,12 @@ func TestBuildAllowNetDNSZones(t *testing.T) {
 	if lastZone.Name != "" {
 		t.Errorf("last zone should be root (empty name), got %q", lastZone.Name)
 	}
-	if !lastZone.DefaultIP.Equal(net.IPv4(0, 0, 0, 0)) {
-		t.Errorf("root zone should have DefaultIP 0.0.0.0, got %v", lastZone.DefaultIP)
+	if lastZone.DefaultIP != nil {
+		t.Errorf("root zone should answer NXDOMAIN, got DefaultIP %v", lastZone.DefaultIP)
 	}
 }
 
-func TestBuildAllowNetDNSZones_PerTLDZonesHaveSinkholeDefaultIP(t *testing.T) {
+func TestBuildAllowNetDNSZones_PerTLDZonesAnswerNXDOMAIN(t *testing.T) {
 	zones := buildAllowNetDNSZones([]string{"example.com"})
 
 	// Should have 2 zones: "com." (per-TLD) + "" (root catch-all)
@@ -34,15 +33,30 @@ func TestBuildAllowNetDNSZones_PerTLDZonesHaveSinkholeDefaultIP(t *testing.T) {
 		t.Fatalf("expected 2 zones, got %d", len(zones))
 	}
 
-	// Per-TLD zone must have DefaultIP 0.0.0.0 so non-allowed hosts
-	// in the same TLD get sinkholed (not NXDOMAIN which triggers DNS fallback)
+	// No zone has a DefaultIP, so non-allowed hosts in the same TLD get
+	// NXDOMAIN like everywhere else (and like the userspace backend)
 	for _, zone := range zones {
-		if !zone.DefaultIP.Equal(net.IPv4(0, 0, 0, 0)) {
-			t.Errorf("zone %q should have DefaultIP 0.0.0.0, got %v", zone.Name, zone.DefaultIP)
+		if zone.DefaultIP != nil {
+			t.Errorf("zone %q should answer NXDOMAIN, got DefaultIP %v", zone.Name, zone.DefaultIP)
 		}
 	}
 }
 
+func TestBuildDenyDNSZones(t *testing.T) {
+	filter := NewEgressFilter([]string{"!*.evil.com", "!*.ads.net:443", "!bad.org"}, nil, "", "")
+	suffixes := filter.dnsDenySuffixes()
+	if len(suffixes) != 1 || suffixes[0] != ".evil.com" {
+		t.Fatalf("only all-port wildcard denies apply to DNS, got %v", suffixes)
+	}
+	zones := buildDenyDNSZones(suffixes)
+	if len(zones) != 1 || zones[0].Name != "evil.com." || zones[0].DefaultIP != nil || len(zones[0].Records) != 0 {
+		t.Fatalf("unexpected deny zones %+v", zones)
+	}
+	if (*TCPFilter)(nil).dnsDenySuffixes() != nil {
+		t.Fatal("nil filter denies nothing")
+	}
+}
+
 func TestBuildAllowNetDNSZones_EmptyList(t *testing.T) {
 	zones := buildAllowNetDNSZones([]string{})
 
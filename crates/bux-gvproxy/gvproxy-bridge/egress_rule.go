@@ -0,0 +1,157 @@
+package main
+
+// egress_rule.go — structured egress rule syntax (mirrors bux_net::EgressRule).
+//
+//	[!]destination[:port[-port]][/tcp|/udp]
+//
+// destination is "*", an IPv4/IPv6 address ("[v6]" when a port follows),
+// a CIDR, a hostname, or "*.suffix". A leading "!" makes a deny rule; no
+// port means every port, no protocol means TCP and UDP.
+
+import (
+	"fmt"
+	"net"
+	"strconv"
+	"strings"
+)
+
+type egressRule struct {
+	raw    string // as written, for activity events
+	deny   bool
+	any    bool       // "*": every destination
+	ip     net.IP     // exact address
+	cidr   *net.IPNet // address range
+	host   string     // exact hostname, lowercase
+	suffix string     // ".example.com" for "*.example.com"
+	portLo uint16
+	portHi uint16 // 0 = all ports
+	proto  string // "" = TCP and UDP
+}
+
+// parseEgressRule parses one rule string.
+func parseEgressRule(raw string) (egressRule, error) {
+	r := egressRule{raw: strings.TrimSpace(raw)}
+	s := r.raw
+	if strings.HasPrefix(s, "!") {
+		r.deny = true
+		s = strings.TrimSpace(s[1:])
+	}
+	lower := strings.ToLower(s)
+	for _, proto := range []string{"tcp", "udp"} {
+		if strings.HasSuffix(lower, "/"+proto) {
+			r.proto = proto
+			s = s[:len(s)-len(proto)-1]
+			break
+		}
+	}
+
+	dest, ports, err := splitDestPorts(s)
+	if err != nil {
+		return r, fmt.Errorf("egress rule %q: %w", raw, err)
+	}
+	if ports != "" {
+		if r.portLo, r.portHi, err = parsePortRange(ports); err != nil {
+			return r, fmt.Errorf("egress rule %q: %w", raw, err)
+		}
+	}
+
+	switch {
+	case dest == "":
+		return r, fmt.Errorf("egress rule %q: empty destination", raw)
+	case dest == "*":
+		r.any = true
+	case net.ParseIP(dest) != nil:
+		r.ip = net.ParseIP(dest)
+	case strings.Contains(dest, "/"):
+		_, cidr, err := net.ParseCIDR(dest)
+		if err != nil {
+			return r, fmt.Errorf("egress rule %q: invalid CIDR", raw)
+		}
+		r.cidr = cidr
+	case strings.HasPrefix(dest, "*."):
+		r.suffix = strings.ToLower(strings.TrimSuffix(dest[1:], "."))
+	default:
+		r.host = strings.ToLower(strings.TrimSuffix(dest, "."))
+	}
+	return r, nil
+}
+
+// splitDestPorts separates "dest:ports"; bare IPv6 carries no port.
+func splitDestPorts(s string) (string, string, error) {
+	if strings.HasPrefix(s, "[") {
+		end := strings.Index(s, "]")
+		if end < 0 {
+			return "", "", fmt.Errorf("unclosed '['")
+		}
+		rest := s[end+1:]
+		if rest == "" {
+			return s[1:end], "", nil
+		}
+		if !strings.HasPrefix(rest, ":") {
+			return "", "", fmt.Errorf("unexpected %q after ']'", rest)
+		}
+		return s[1:end], rest[1:], nil
+	}
+	if strings.Count(s, ":") > 1 {
+		return s, "", nil
+	}
+	if dest, ports, ok := strings.Cut(s, ":"); ok {
+		return dest, ports, nil
+	}
+	return s, "", nil
+}
+
+// parsePortRange parses "443" or "8000-8100".
+func parsePortRange(s string) (uint16, uint16, error) {
+	loStr, hiStr, isRange := strings.Cut(s, "-")
+	if !isRange {
+		hiStr = loStr
+	}
+	lo, err := strconv.ParseUint(loStr, 10, 16)
+	if err != nil || lo == 0 {
+		return 0, 0, fmt.Errorf("invalid port %q", loStr)
+	}
+	hi, err := strconv.ParseUint(hiStr, 10, 16)
+	if err != nil || hi < lo {
+		return 0, 0, fmt.Errorf("invalid port range %q", s)
+	}
+	return uint16(lo), uint16(hi), nil
+}
+
+func (r egressRule) isHostname() bool {
+	return r.host != "" || r.suffix != ""
+}
+
+func (r egressRule) matchesHost(hostname string) bool {
+	if r.any {
+		return true
+	}
+	hostname = strings.ToLower(strings.TrimSuffix(hostname, "."))
+	if hostname == "" {
+		return false
+	}
+	if r.host != "" {
+		return hostname == r.host
+	}
+	return r.suffix != "" && strings.HasSuffix(hostname, r.suffix)
+}
+
+func (r egressRule) matchesIP(ip net.IP) bool {
+	if r.any {
+		return true
+	}
+	if ip == nil {
+		return false
+	}
+	if r.ip != nil {
+		return r.ip.Equal(ip)
+	}
+	return r.cidr != nil && r.cidr.Contains(ip)
+}
+
+func (r egressRule) matchesPort(port uint16, proto string) bool {
+	if r.proto != "" && r.proto != proto {
+		return false
+	}
+	return r.portHi == 0 || (port >= r.portLo && port <= r.portHi)
+}
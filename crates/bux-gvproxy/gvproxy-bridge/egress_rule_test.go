@@ -0,0 +1,87 @@
+package main
+
+import (
+	"net"
+	"testing"
+)
+
+func TestParseEgressRule(t *testing.T) {
+	r, err := parseEgressRule("*.github.com:443/tcp")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if r.deny || r.suffix != ".github.com" || r.portLo != 443 || r.portHi != 443 || r.proto != "tcp" {
+		t.Fatalf("unexpected rule %+v", r)
+	}
+
+	r, err = parseEgressRule("!169.254.169.254")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !r.deny || !r.ip.Equal(net.ParseIP("169.254.169.254")) || r.portHi != 0 {
+		t.Fatalf("unexpected rule %+v", r)
+	}
+
+	r, err = parseEgressRule("10.0.0.0/8:5000-5432")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if r.cidr == nil || r.portLo != 5000 || r.portHi != 5432 {
+		t.Fatalf("unexpected rule %+v", r)
+	}
+
+	r, err = parseEgressRule("[2001:db8::1]:53/udp")
+	if err != nil {
+		t.Fatal(err)
+	}
+	if !r.ip.Equal(net.ParseIP("2001:db8::1")) || r.proto != "udp" {
+		t.Fatalf("unexpected rule %+v", r)
+	}
+
+	for _, bad := range []string{"", "!", "host:0", "host:9-1", "host:http", "10.0.0.0/99", "[::1"} {
+		if _, err := parseEgressRule(bad); err == nil {
+			t.Errorf("expected %q to be rejected", bad)
+		}
+	}
+}
+
+func TestEgressFilter_DenyBeforeAllow(t *testing.T) {
+	f := NewTCPFilter([]string{"*", "!169.254.169.254", "!*.evil.com:443"}, "192.168.127.1", "192.168.127.2")
+	assertTrue(t, f.Allows("", net.ParseIP("1.2.3.4"), 22), "allowed by *")
+	assertFalse(t, f.Allows("", net.ParseIP("169.254.169.254"), 80), "metadata denied")
+	assertFalse(t, f.Allows("x.evil.com", net.ParseIP("1.2.3.4"), 443), "hostname deny on 443")
+	assertTrue(t, f.Allows("x.evil.com", net.ParseIP("1.2.3.4"), 80), "deny limited to 443")
+
+	if got := decideTCPRoute(net.ParseIP("1.2.3.4"), 443, f, nil); got != tcpRouteInspect {
+		t.Fatalf("hostname deny rule should force inspection, got %v", got)
+	}
+	if got := decideTCPRoute(net.ParseIP("169.254.169.254"), 80, f, nil); got != tcpRouteBlock {
+		t.Fatalf("IP deny should block, got %v", got)
+	}
+}
+
+func TestEgressFilter_PortsAndDefault(t *testing.T) {
+	f := NewTCPFilter([]string{"10.0.0.0/8:5432"}, "192.168.127.1", "192.168.127.2")
+	assertTrue(t, f.Allows("", net.ParseIP("10.1.1.1"), 5432), "allowed port")
+	assertFalse(t, f.Allows("", net.ParseIP("10.1.1.1"), 22), "other port denied by default")
+	assertTrue(t, f.Allows("", net.ParseIP("192.168.127.1"), 22), "gateway always allowed")
+
+	allow := false
+	open := NewEgressFilter([]string{"!1.1.1.1"}, &allow, "192.168.127.1", "192.168.127.2")
+	assertTrue(t, open.Allows("", net.ParseIP("8.8.8.8"), 53), "default allow")
+	assertFalse(t, open.Allows("", net.ParseIP("1.1.1.1"), 53), "explicit deny")
+	if _, sinkhole := open.dnsAllowHosts(); sinkhole {
+		t.Fatal("default-allow policy must not sinkhole DNS")
+	}
+}
+
+func TestEgressFilter_DNSAllowHosts(t *testing.T) {
+	f := NewTCPFilter([]string{"pypi.org:443", "*.pythonhosted.org", "10.0.0.0/8", "!bad.pypi.org"}, "192.168.127.1", "192.168.127.2")
+	hosts, sinkhole := f.dnsAllowHosts()
+	if !sinkhole || len(hosts) != 2 || hosts[0] != "pypi.org" || hosts[1] != "*.pythonhosted.org" {
+		t.Fatalf("unexpected DNS hosts %v (sinkhole %v)", hosts, sinkhole)
+	}
+	if _, sinkhole := NewTCPFilter([]string{"*:443"}, "", "").dnsAllowHosts(); sinkhole {
+		t.Fatal("allow * keeps DNS open")
+	}
+}
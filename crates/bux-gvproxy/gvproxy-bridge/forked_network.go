@@ -30,9 +30,9 @@ func OverrideTCPHandler(
 	vn *virtualnetwork.VirtualNetwork,
 	config *types.Configuration,
 	ec2MetadataAccess bool,
-	filter *TCPFilter,
+	policy *livePolicy,
 	ca *BoxCA,
-	secretMatcher *SecretHostMatcher,
+	activity *activityLog,
 ) error {
 	// Access private stack field via reflect
 	v := reflect.ValueOf(vn).Elem()
@@ -53,7 +53,7 @@ func OverrideTCPHandler(
 
 	// Replace TCP handler with our filtered version
 	var natLock sync.Mutex
-	tcpFwd := TCPWithFilter(s, nat, &natLock, ec2MetadataAccess, filter, ca, secretMatcher)
+	tcpFwd := TCPWithFilter(s, nat, &natLock, ec2MetadataAccess, policy, ca, activity)
 	s.SetTransportProtocolHandler(tcp.ProtocolNumber, tcpFwd.HandlePacket)
 
 	logrus.Info("allowNet TCP: handler overridden with SNI-inspecting forwarder")
@@ -4,10 +4,14 @@ package main
 //
 // Fork of gvisor-tap-vsock@v0.8.7/pkg/services/forwarder/tcp.go.
 // Two paths:
-//   - Standard: IP/CIDR match or no filter → upstream flow (Dial → Accept → relay)
-//   - Inspect:  port 443/80 with hostname rules → Accept → Peek SNI/Host → check → Dial → relay
+//   - Standard: IP/CIDR verdict or no filter → upstream flow (Dial → Accept → relay)
+//   - Inspect:  port 443/80 where a hostname rule could change the verdict →
+//     Accept → Peek SNI/Host → check → Dial → relay
 //
-// When filter is nil: identical to upstream (zero overhead).
+// When filter is nil: identical to upstream (zero overhead). A non-nil
+// activityLog records connects, closes and denials (see activity.go).
+// Port 443 to hosts with secrets or HTTP rules is terminated by the MITM
+// proxy (see mitm_proxy.go, mitm_http.go).
 
 import (
 	"bufio"
@@ -69,32 +73,57 @@ func decideTCPRoute(destIP net.IP, destPort uint16, filter *TCPFilter, secretMat
 	}
 
 	// Normalize the gVisor-provided IP into canonical IPv4 form before matching.
-	if ip4 := destIP.To4(); ip4 != nil && filter.MatchesIP(ip4) {
+	ip4 := destIP.To4()
+	if ip4 != nil && filter.alwaysAllow[toIPv4Key(ip4)] {
 		return tcpRouteStandardForward
 	}
+	inspectable := destPort == 443 || destPort == 80
 
-	if filter.HasHostnameRules() && (destPort == 443 || destPort == 80) {
-		return tcpRouteInspect
+	switch filter.evaluate("", ip4, destPort) {
+	case verdictDeny:
+		return tcpRouteBlock
+	case verdictAllow:
+		// A hostname deny rule still outranks an IP allow.
+		if inspectable && filter.hostnameRulesOn(destPort, true) {
+			return tcpRouteInspect
+		}
+		return tcpRouteStandardForward
 	}
 
-	return tcpRouteBlock
+	if inspectable && (filter.hostnameRulesOn(destPort, false) || filter.hostnameRulesOn(destPort, true)) {
+		return tcpRouteInspect
+	}
+	if filter.defaultDeny {
+		return tcpRouteBlock
+	}
+	return tcpRouteStandardForward
 }
 
 func TCPWithFilter(s *stack.Stack, nat map[tcpip.Address]tcpip.Address,
-	natLock *sync.Mutex, ec2MetadataAccess bool, filter *TCPFilter,
-	ca *BoxCA, secretMatcher *SecretHostMatcher) *tcp.Forwarder {
+	natLock *sync.Mutex, ec2MetadataAccess bool, policy *livePolicy,
+	ca *BoxCA, activity *activityLog) *tcp.Forwarder {
 
 	return tcp.NewForwarder(s, 0, 10, func(r *tcp.ForwarderRequest) {
 		localAddress := r.ID().LocalAddress
+		// Read once per connection; gvproxy_update may swap it concurrently.
+		filter, secretMatcher := policy.get()
 
 		if !ec2MetadataAccess && linkLocalSubnet.Contains(localAddress) {
 			r.Complete(true)
 			return
 		}
 
+		// A used-up blocking quota refuses every new connection.
+		if policy.shaper.blocksNewFlows() {
+			logrus.WithField("dst", localAddress).Debug("TCP: refused, transfer quota used up")
+			r.Complete(true) // RST
+			return
+		}
+
 		// NAT translation
 		natLock.Lock()
-		if replaced, ok := nat[localAddress]; ok {
+		replaced, natted := nat[localAddress]
+		if natted {
 			localAddress = replaced
 		}
 		natLock.Unlock()
@@ -104,12 +133,21 @@ func TCPWithFilter(s *stack.Stack, nat map[tcpip.Address]tcpip.Address,
 		destPort := r.ID().LocalPort
 		destAddr := fmt.Sprintf("%s:%d", localAddress, destPort)
 
-		switch decideTCPRoute(destIP, destPort, filter, secretMatcher) {
+		route := decideTCPRoute(destIP, destPort, filter, secretMatcher)
+		if policy.http != nil && destPort == 443 {
+			// HTTP rules need SNI even if the destination IP is broadly allowed.
+			route = tcpRouteInspect
+		}
+		if natted {
+			// NAT targets (the host gateway) are internal, like the gateway itself.
+			route = tcpRouteStandardForward
+		}
+		switch route {
 		case tcpRouteStandardForward:
-			standardForward(r, destAddr)
+			standardForward(r, destAddr, activity)
 			return
 		case tcpRouteInspect:
-			inspectAndForward(r, destAddr, destPort, filter, ca, secretMatcher)
+			inspectAndForward(r, destAddr, destIP, destPort, filter, ca, secretMatcher, policy.http, activity)
 			return
 		default:
 			// No matching rule: block
@@ -117,13 +155,14 @@ func TCPWithFilter(s *stack.Stack, nat map[tcpip.Address]tcpip.Address,
 				"dst_ip":   destIP,
 				"dst_port": destPort,
 			}).Info("allowNet TCP: blocked (no matching rule)")
+			activity.denied(destAddr, "", filter.denyingRule("", destIP, destPort))
 			r.Complete(true) // RST
 		}
 	})
 }
 
 // standardForward is the upstream flow: Dial → CreateEndpoint → relay.
-func standardForward(r *tcp.ForwarderRequest, destAddr string) {
+func standardForward(r *tcp.ForwarderRequest, destAddr string, activity *activityLog) {
 	outbound, err := net.Dial("tcp", destAddr)
 	if err != nil {
 		logrus.Tracef("net.Dial() = %v", err)
@@ -149,13 +188,26 @@ func standardForward(r *tcp.ForwarderRequest, destAddr string) {
 			return outbound, nil
 		},
 	}
-	remote.HandleConn(gonet.NewTCPConn(&wq, ep))
+	relay(gonet.NewTCPConn(&wq, ep), destAddr, "", activity, remote.HandleConn)
+}
+
+// relay runs handle on the guest connection, recording connect and close
+// (with byte counts) when activity is enabled.
+func relay(guest net.Conn, destAddr, hostname string, activity *activityLog, handle func(net.Conn)) {
+	if activity == nil {
+		handle(guest)
+		return
+	}
+	counted := &countingConn{Conn: guest}
+	activity.connect(destAddr, hostname)
+	handle(counted)
+	activity.closed(destAddr, counted)
 }
 
 // inspectAndForward: Accept → Peek SNI/Host → check allowlist → Dial → relay.
 // The flow is reversed from upstream because we need to read from the guest
 // before deciding whether to connect to the upstream server.
-func inspectAndForward(r *tcp.ForwarderRequest, destAddr string, destPort uint16, filter *TCPFilter, ca *BoxCA, secretMatcher *SecretHostMatcher) {
+func inspectAndForward(r *tcp.ForwarderRequest, destAddr string, destIP net.IP, destPort uint16, filter *TCPFilter, ca *BoxCA, secretMatcher *SecretHostMatcher, httpRules *httpPolicy, activity *activityLog) {
 	// Step 1: Accept TCP from guest first (reversed from upstream)
 	var wq waiter.Queue
 	ep, tcpErr := r.CreateEndpoint(&wq)
@@ -179,24 +231,33 @@ func inspectAndForward(r *tcp.ForwarderRequest, destAddr string, destPort uint16
 		hostname = peekHTTPHost(br)
 	}
 
-	// Step 3: Check for MITM secret substitution (HTTPS only, takes priority over allowlist)
-	if destPort == 443 && secretMatcher != nil && hostname != "" && secretMatcher.Matches(hostname) {
-		secrets := secretMatcher.SecretsForHost(hostname)
+	// Step 3: Check for MITM secret substitution or HTTP rules (HTTPS only, takes priority over allowlist)
+	hasSecrets := secretMatcher != nil && secretMatcher.Matches(hostname)
+	rule := httpRules.ruleFor(hostname)
+	if destPort == 443 && ca != nil && hostname != "" && (hasSecrets || rule != nil) {
+		opts := mitmOptions{rule: rule, activity: activity, logRequests: httpRules != nil && httpRules.logRequests}
+		if hasSecrets {
+			opts.secrets = secretMatcher.SecretsForHost(hostname)
+		}
 		logrus.WithFields(logrus.Fields{
 			"hostname":    hostname,
-			"num_secrets": len(secrets),
-		}).Debug("MITM: intercepting for secret substitution")
+			"num_secrets": len(opts.secrets),
+			"http_rule":   rule != nil,
+		}).Debug("MITM: intercepting")
 		bufferedGuest := &bufferedConn{Conn: guestConn, reader: br}
-		mitmAndForward(bufferedGuest, hostname, destAddr, ca, secrets)
+		relay(bufferedGuest, destAddr, hostname, activity, func(conn net.Conn) {
+			mitmWithOptions(conn, hostname, destAddr, ca, opts)
+		})
 		return
 	}
 
-	// Step 4: Check allowlist (skip if no allowlist — secrets-only mode allows all traffic)
-	if filter != nil && (hostname == "" || !filter.MatchesHostname(hostname)) {
+	// Step 4: Check the egress policy (skip if none — secrets-only mode allows all traffic)
+	if filter != nil && !filter.Allows(hostname, destIP, destPort) {
 		logrus.WithFields(logrus.Fields{
 			"dst":      destAddr,
 			"hostname": hostname,
-		}).Info("allowNet TCP: blocked (hostname not in allowlist)")
+		}).Info("allowNet TCP: blocked by egress policy")
+		activity.denied(destAddr, hostname, filter.denyingRule(hostname, destIP, destPort))
 		guestConn.Close()
 		return
 	}
@@ -224,7 +285,7 @@ func inspectAndForward(r *tcp.ForwarderRequest, destAddr string, destPort uint16
 			return outbound, nil
 		},
 	}
-	remote.HandleConn(bufferedGuest)
+	relay(bufferedGuest, destAddr, hostname, activity, remote.HandleConn)
 }
 
 // bufferedConn wraps a net.Conn with a bufio.Reader for Read operations.
@@ -0,0 +1,193 @@
+package main
+
+// live_update.go — runtime changes to a running instance (gvproxy_update).
+//
+// The TCP forwarder reads livePolicy on every new connection, so swapping
+// the allow-list filter or the secret matcher affects new flows at once;
+// established flows keep running. Port forwards and DNS zones go through
+// VirtualNetwork's services mux, the same API gvproxy serves over HTTP.
+
+import (
+	"bytes"
+	"encoding/json"
+	"fmt"
+	"net/http"
+	"net/http/httptest"
+	"strings"
+	"sync"
+
+	"github.com/containers/gvisor-tap-vsock/pkg/types"
+	"github.com/containers/gvisor-tap-vsock/pkg/virtualnetwork"
+	logrus "github.com/sirupsen/logrus"
+)
+
+// GvproxyUpdate matches the Rust structure (must stay in sync!)
+//
+// AllowNet, EgressDefaultDeny and PortMappings are the complete desired
+// state; Secrets is left unchanged when omitted.
+type GvproxyUpdate struct {
+	AllowNet          []string        `json:"allow_net"`
+	EgressDefaultDeny *bool           `json:"egress_default_deny,omitempty"`
+	PortMappings []PortMapping   `json:"port_mappings"`
+	Secrets      *[]SecretConfig `json:"secrets,omitempty"`
+}
+
+// livePolicy holds the inputs of the per-connection routing decision.
+// http and shaper are fixed at creation and not swapped by updates.
+type livePolicy struct {
+	mu            sync.RWMutex
+	filter        *TCPFilter
+	secretMatcher *SecretHostMatcher
+	http          *httpPolicy
+	shaper        *trafficShaper
+}
+
+func (p *livePolicy) get() (*TCPFilter, *SecretHostMatcher) {
+	p.mu.RLock()
+	defer p.mu.RUnlock()
+	return p.filter, p.secretMatcher
+}
+
+func (p *livePolicy) set(filter *TCPFilter, secretMatcher *SecretHostMatcher) {
+	p.mu.Lock()
+	p.filter = filter
+	p.secretMatcher = secretMatcher
+	p.mu.Unlock()
+}
+
+// newSecretMatcher returns nil for an empty list so port 443 is not
+// inspected when nothing needs substituting.
+func newSecretMatcher(secrets []SecretConfig) *SecretHostMatcher {
+	if len(secrets) == 0 {
+		return nil
+	}
+	return NewSecretHostMatcher(secrets)
+}
+
+// applyUpdate reconciles the instance with u. Steps run in order
+// (validation, forwards, DNS, policy); on error, earlier steps stay applied.
+func (instance *GvproxyInstance) applyUpdate(vn *virtualnetwork.VirtualNetwork, u GvproxyUpdate) error {
+	instance.updateMu.Lock()
+	defer instance.updateMu.Unlock()
+
+	// The DNS service can add zones but not remove them, so the sinkhole
+	// installed for a default-deny policy cannot be lifted without a restart.
+	filter := NewEgressFilter(u.AllowNet, u.EgressDefaultDeny, instance.gatewayIP, instance.guestIP)
+	dnsHosts, sinkhole := filter.dnsAllowHosts()
+	if instance.sinkhole && !sinkhole {
+		return fmt.Errorf("a default-deny egress policy cannot be lifted on a running instance")
+	}
+	if u.Secrets != nil && instance.ca == nil {
+		return fmt.Errorf("secrets can only be rotated on instances created with a MITM CA")
+	}
+
+	if err := instance.reconcileForwards(vn, u.PortMappings); err != nil {
+		return err
+	}
+
+	dnsDeny := filter.dnsDenySuffixes()
+	zones := buildDenyDNSZones(addedRules(instance.dnsDeny, dnsDeny))
+	added := addedRules(instance.dnsHosts, dnsHosts)
+	if sinkhole && (!instance.sinkhole || len(added) > 0) {
+		zones = append(zones, buildAllowNetDNSZones(added)...)
+	}
+	if len(zones) > 0 {
+		// Configured records stay ahead of the policy zones.
+		zones = append(append([]types.Zone{}, instance.localZones...), zones...)
+	}
+	// The DNS service prepends new zones; add the catch-all sinkhole
+	// first so the specific zones end up ahead of it.
+	for i := len(zones) - 1; i >= 0; i-- {
+		if err := postServices(vn, "/services/dns/add", zones[i]); err != nil {
+			return err
+		}
+	}
+	instance.dnsHosts = dnsHosts
+	instance.dnsDeny = dnsDeny
+	instance.sinkhole = sinkhole
+
+	_, secretMatcher := instance.policy.get()
+	if u.Secrets != nil {
+		secretMatcher = newSecretMatcher(*u.Secrets)
+	}
+	instance.policy.set(filter, secretMatcher)
+
+	logrus.WithFields(logrus.Fields{
+		"id":              instance.ID,
+		"allow_rules":     len(u.AllowNet),
+		"forwards":        len(instance.forwards),
+		"secrets_rotated": u.Secrets != nil,
+	}).Info("gvproxy instance updated")
+	return nil
+}
+
+// reconcileForwards exposes and unexposes TCP and UDP forwards to match want.
+func (instance *GvproxyInstance) reconcileForwards(vn *virtualnetwork.VirtualNetwork, want []PortMapping) error {
+	wanted := make(map[forwardKey]uint16, len(want))
+	for _, pm := range want {
+		wanted[pm.key()] = pm.GuestPort
+	}
+
+	for key, guest := range instance.forwards {
+		if g, ok := wanted[key]; ok && g == guest {
+			continue
+		}
+		req := types.UnexposeRequest{
+			Local:    key.local,
+			Protocol: key.protocol,
+		}
+		if err := postServices(vn, "/services/forwarder/unexpose", req); err != nil {
+			return err
+		}
+		delete(instance.forwards, key)
+		logrus.WithFields(logrus.Fields{"host": req.Local, "protocol": req.Protocol}).Info("Removed port forward")
+	}
+
+	for key, guest := range wanted {
+		if _, ok := instance.forwards[key]; ok {
+			continue
+		}
+		req := types.ExposeRequest{
+			Local:    key.local,
+			Remote:   fmt.Sprintf("%s:%d", instance.guestIP, guest),
+			Protocol: key.protocol,
+		}
+		if err := postServices(vn, "/services/forwarder/expose", req); err != nil {
+			return err
+		}
+		instance.forwards[key] = guest
+		logrus.WithFields(logrus.Fields{"host": req.Local, "guest": req.Remote, "protocol": req.Protocol}).Info("Added port forward")
+	}
+	return nil
+}
+
+// addedRules returns the rules in next that are not in prev.
+func addedRules(prev, next []string) []string {
+	seen := make(map[string]bool, len(prev))
+	for _, rule := range prev {
+		seen[strings.TrimSpace(rule)] = true
+	}
+	var added []string
+	for _, rule := range next {
+		if !seen[strings.TrimSpace(rule)] {
+			added = append(added, rule)
+		}
+	}
+	return added
+}
+
+// postServices invokes a services-mux endpoint in-process (no HTTP
+// server), like collectNetworkStats does for /stats.
+func postServices(vn *virtualnetwork.VirtualNetwork, path string, body interface{}) error {
+	payload, err := json.Marshal(body)
+	if err != nil {
+		return err
+	}
+	req := httptest.NewRequest(http.MethodPost, path, bytes.NewReader(payload))
+	rec := httptest.NewRecorder()
+	vn.ServicesMux().ServeHTTP(rec, req)
+	if rec.Code >= http.StatusMultipleChoices {
+		return fmt.Errorf("%s: %s", path, strings.TrimSpace(rec.Body.String()))
+	}
+	return nil
+}
@@ -0,0 +1,54 @@
+package main
+
+import (
+	"net"
+	"reflect"
+	"testing"
+)
+
+func TestAddedRules(t *testing.T) {
+	got := addedRules([]string{"a.com", "10.0.0.0/8"}, []string{"a.com", " b.com", "10.0.0.0/8"})
+	if !reflect.DeepEqual(got, []string{" b.com"}) {
+		t.Fatalf("addedRules = %v", got)
+	}
+	if got := addedRules([]string{"a.com"}, []string{"a.com"}); len(got) != 0 {
+		t.Fatalf("expected no added rules, got %v", got)
+	}
+}
+
+func TestLivePolicySwap(t *testing.T) {
+	p := &livePolicy{}
+	if f, m := p.get(); f != nil || m != nil {
+		t.Fatal("zero policy should be unrestricted")
+	}
+	p.set(NewTCPFilter([]string{"1.2.3.4"}, "192.168.127.1", "192.168.127.2"), newSecretMatcher(nil))
+	f, m := p.get()
+	if f == nil || m != nil {
+		t.Fatal("expected filter without secret matcher")
+	}
+	if decideTCPRoute(net.ParseIP("5.6.7.8"), 22, f, m) != tcpRouteBlock {
+		t.Fatal("swapped filter should block unlisted IPs")
+	}
+}
+
+func TestPortMappingKey(t *testing.T) {
+	cases := []struct {
+		pm     PortMapping
+		config string
+	}{
+		{PortMapping{HostPort: 8080, GuestPort: 80}, "0.0.0.0:8080"},
+		{PortMapping{HostPort: 8080, GuestPort: 80, BindIP: "127.0.0.1"}, "127.0.0.1:8080"},
+		{PortMapping{HostPort: 53, GuestPort: 53, Protocol: "udp"}, "udp:0.0.0.0:53"},
+		{PortMapping{HostPort: 9000, GuestPort: 9000, BindIP: "::1", Protocol: "udp"}, "udp:[::1]:9000"},
+	}
+	for _, c := range cases {
+		if got := c.pm.key().configKey(); got != c.config {
+			t.Errorf("%+v: configKey = %q, want %q", c.pm, got, c.config)
+		}
+	}
+	tcp := PortMapping{HostPort: 53, GuestPort: 53}.key()
+	udp := PortMapping{HostPort: 53, GuestPort: 53, Protocol: "udp"}.key()
+	if tcp == udp {
+		t.Fatal("TCP and UDP forwards on one port must not collide")
+	}
+}
@@ -22,6 +22,7 @@ import (
 	"os"
 	"runtime"
 	"runtime/debug"
+	"strconv"
 	"sync"
 	"time"
 	"unsafe"
@@ -162,14 +163,66 @@ func gvproxy_set_log_callback(callback unsafe.Pointer) {
 type PortMapping struct {
 	HostPort  uint16 `json:"host_port"`
 	GuestPort uint16 `json:"guest_port"`
+	BindIP    string `json:"bind_ip,omitempty"`  // empty = all interfaces
+	Protocol  string `json:"protocol,omitempty"` // tcp (default) | udp
+}
+
+// forwardKey identifies a host-side listener.
+type forwardKey struct {
+	local    string
+	protocol types.TransportProtocol
+}
+
+// key returns the host listener of pm ("0.0.0.0:8080" when BindIP is empty).
+func (pm PortMapping) key() forwardKey {
+	bind := pm.BindIP
+	if bind == "" {
+		bind = "0.0.0.0"
+	}
+	protocol := types.TCP
+	if pm.Protocol == "udp" {
+		protocol = types.UDP
+	}
+	return forwardKey{
+		local:    net.JoinHostPort(bind, strconv.Itoa(int(pm.HostPort))),
+		protocol: protocol,
+	}
+}
+
+// configKey is the Configuration.Forwards key: "udp:" marks UDP.
+func (k forwardKey) configKey() string {
+	if k.protocol == types.UDP {
+		return "udp:" + k.local
+	}
+	return k.local
 }
 
 // DNSZone represents a local DNS zone configuration
 // These are local DNS records served by the gateway's embedded DNS server.
 // Queries not matching any zone are forwarded to the host's system DNS.
 type DNSZone struct {
-	Name      string `json:"name"`       // Zone name (e.g., "myapp.local.", "." for root)
-	DefaultIP string `json:"default_ip"` // Default IP for unmatched queries in this zone
+	Name      string      `json:"name"`                 // Zone name (e.g., "myapp.local.", "." for root)
+	DefaultIP string      `json:"default_ip,omitempty"` // Default IP for unmatched queries; empty = NXDOMAIN
+	Records   []DNSRecord `json:"records,omitempty"`    // A records by label relative to the zone
+}
+
+// DNSRecord is one A record inside a DNSZone
+type DNSRecord struct {
+	Name string `json:"name"`
+	IP   string `json:"ip"`
+}
+
+// toZone converts a configured zone for the gvisor-tap-vsock DNS service.
+func (zone DNSZone) toZone() types.Zone {
+	records := make([]types.Record, 0, len(zone.Records))
+	for _, record := range zone.Records {
+		records = append(records, types.Record{Name: record.Name, IP: net.ParseIP(record.IP).To4()})
+	}
+	return types.Zone{
+		Name:      zone.Name,
+		DefaultIP: net.ParseIP(zone.DefaultIP),
+		Records:   records,
+	}
 }
 
 // GvproxyConfig matches the Rust structure (must stay in sync!)
@@ -187,9 +240,19 @@ type GvproxyConfig struct {
 	Debug       bool     `json:"debug"`
 	CaptureFile *string  `json:"capture_file,omitempty"`
 	AllowNet    []string       `json:"allow_net,omitempty"`
+	// Nil = deny unmatched traffic when any allow rule exists.
+	EgressDefaultDeny *bool          `json:"egress_default_deny,omitempty"`
 	Secrets     []SecretConfig `json:"secrets,omitempty"`
 	CACertPEM   string         `json:"ca_cert_pem,omitempty"`
 	CAKeyPEM    string         `json:"ca_key_pem,omitempty"`
+	// Record connects/closes/denials for gvproxy_drain_activity.
+	RecordActivity bool `json:"record_activity,omitempty"`
+	// Request rules and logging on the MITM path; needs the CA.
+	HTTPPolicy *HTTPPolicyConfig `json:"http_policy,omitempty"`
+	// Rate limits and transfer quotas on the guest link.
+	Shaping *ShapingConfig `json:"shaping,omitempty"`
+	// Virtual address NATed to the host's loopback (host gateway).
+	HostIP string `json:"host_ip,omitempty"`
 }
 
 // GvproxyInstance tracks a running gvisor-tap-vsock instance
@@ -203,7 +266,17 @@ type GvproxyInstance struct {
 	vn            *virtualnetwork.VirtualNetwork // Virtual network for stats collection
 	vnMu          sync.RWMutex                   // Protects vn field
 	ca            *BoxCA                         // Ephemeral MITM CA (nil if no secrets)
-	secretMatcher *SecretHostMatcher              // Hostname→secrets lookup (nil if no secrets)
+	policy        *livePolicy                    // AllowNet filter + secret matcher, swappable
+	updateMu      sync.Mutex                     // Serialises gvproxy_update
+	dnsHosts      []string                       // Hostname patterns exempt from the DNS sinkhole
+	dnsDeny       []string                       // Denied hostname suffixes answered with NXDOMAIN
+	sinkhole      bool                           // DNS sinkhole installed (cannot be removed)
+	localZones    []types.Zone                   // Configured records, kept ahead of policy zones
+	forwards      map[forwardKey]uint16          // Current host listener→guest port forwards
+	activity      *activityLog                   // Nil unless record_activity
+	shaper        *trafficShaper                 // Nil unless shaping configured
+	gatewayIP     string
+	guestIP       string
 }
 
 var (
@@ -248,22 +321,36 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 	}
 
 	// Build DNS zones from config
-	// These are local DNS records - queries not matching any zone are forwarded to host DNS
-	dnsZones := make([]types.Zone, len(config.DNSZones))
+	// These are local DNS records - queries not matching any zone are forwarded to host DNS.
+	// They come first so configured names resolve whatever the egress policy.
+	localZones := make([]types.Zone, len(config.DNSZones))
 	for i, zone := range config.DNSZones {
-		dnsZones[i] = types.Zone{
-			Name:      zone.Name,
-			DefaultIP: net.ParseIP(zone.DefaultIP),
-		}
+		localZones[i] = zone.toZone()
 	}
-
-	// Build DNS allowlist zones when AllowNet is configured
-	if len(config.AllowNet) > 0 {
-		allowNetZones := buildAllowNetDNSZones(config.AllowNet)
-		dnsZones = append(allowNetZones, dnsZones...)
+	dnsZones := append([]types.Zone{}, localZones...)
+
+	// Names the egress policy refuses get NXDOMAIN: denied suffixes, then
+	// everything outside the allowlist when the policy denies by default
+	egressFilter := NewEgressFilter(config.AllowNet, config.EgressDefaultDeny, config.GatewayIP, config.GuestIP)
+	dnsDeny := egressFilter.dnsDenySuffixes()
+	dnsZones = append(dnsZones, buildDenyDNSZones(dnsDeny)...)
+	dnsHosts, sinkhole := egressFilter.dnsAllowHosts()
+	if sinkhole {
+		dnsZones = append(dnsZones, buildAllowNetDNSZones(dnsHosts)...)
 		logrus.WithField("rules", len(config.AllowNet)).Info("Network allowlist enabled (DNS sinkhole)")
 	}
 
+	// Host gateway: a virtual address NATed to the host's loopback
+	nat := map[string]string{
+		config.GuestIP: "127.0.0.1",
+	}
+	gatewayVirtualIPs := []string{config.GatewayIP}
+	if config.HostIP != "" {
+		nat[config.HostIP] = "127.0.0.1"
+		gatewayVirtualIPs = append(gatewayVirtualIPs, config.HostIP)
+		logrus.WithField("host_ip", config.HostIP).Info("Host gateway enabled")
+	}
+
 	// Create gvisor-tap-vsock configuration from provided config
 	tapConfig := &types.Configuration{
 		Debug:             config.Debug,
@@ -274,11 +361,9 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		DHCPStaticLeases: map[string]string{
 			config.GuestIP: config.GuestMac,
 		},
-		Forwards: make(map[string]string),
-		NAT: map[string]string{
-			config.GuestIP: "127.0.0.1",
-		},
-		GatewayVirtualIPs: []string{config.GatewayIP},
+		Forwards:          make(map[string]string),
+		NAT:               nat,
+		GatewayVirtualIPs: gatewayVirtualIPs,
 		Protocol:          protocol,
 		DNS:               dnsZones,
 		DNSSearchDomains:  config.DNSSearchDomains,
@@ -291,15 +376,15 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 	}
 
 	// Add port forwards from config
-	// Format: "0.0.0.0:PORT" for TCP (default), or "udp:0.0.0.0:PORT" for UDP
+	// Format: "BIND:PORT" for TCP (default), or "udp:BIND:PORT" for UDP
 	// Do NOT use "tcp://" prefix - it causes "too many colons in address" error
 	// Forward to guest's DHCP IP, not localhost
 	// Containers bind to 0.0.0.0 inside the guest, accessible via guest IP
 	for _, pm := range config.PortMappings {
-		forwardKey := fmt.Sprintf("0.0.0.0:%d", pm.HostPort)
+		local := pm.key().configKey()
 		forwardVal := fmt.Sprintf("%s:%d", config.GuestIP, pm.GuestPort)
-		tapConfig.Forwards[forwardKey] = forwardVal
-		logrus.WithFields(logrus.Fields{"host": forwardKey, "guest": forwardVal}).Info("Added TCP port forward")
+		tapConfig.Forwards[local] = forwardVal
+		logrus.WithFields(logrus.Fields{"host": local, "guest": forwardVal}).Info("Added port forward")
 	}
 
 	// Platform-specific socket creation
@@ -336,7 +421,21 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 		Cancel:     cancel,
 		conn:       conn,
 		listener:   listener,
+		policy:     &livePolicy{filter: egressFilter},
+		dnsHosts:   dnsHosts,
+		dnsDeny:    dnsDeny,
+		sinkhole:   sinkhole,
+		localZones: localZones,
+		forwards:   make(map[forwardKey]uint16, len(config.PortMappings)),
+		activity:   newActivityLog(config.RecordActivity),
+		gatewayIP:  config.GatewayIP,
+		guestIP:    config.GuestIP,
+	}
+	for _, pm := range config.PortMappings {
+		instance.forwards[pm.key()] = pm.GuestPort
 	}
+	instance.shaper = newTrafficShaper(config.Shaping, instance.activity)
+	instance.policy.shaper = instance.shaper
 
 	// Parse MITM CA from config (generated by Rust) when secrets are configured
 	if config.CACertPEM != "" && config.CAKeyPEM != "" {
@@ -347,8 +446,16 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 			return -1
 		}
 		instance.ca = ca
-		instance.secretMatcher = NewSecretHostMatcher(config.Secrets)
-		logrus.WithField("num_secrets", len(config.Secrets)).Info("MITM: loaded CA from Rust config")
+		instance.policy.secretMatcher = newSecretMatcher(config.Secrets)
+		instance.policy.http = newHTTPPolicy(config.HTTPPolicy)
+		logrus.WithFields(logrus.Fields{
+			"num_secrets":    len(config.Secrets),
+			"http_policy":    instance.policy.http != nil,
+		}).Info("MITM: loaded CA from Rust config")
+	} else if newHTTPPolicy(config.HTTPPolicy) != nil {
+		logrus.Error("MITM: HTTP policy requires a CA")
+		cancel()
+		return -1
 	}
 
 	instancesMu.Lock()
@@ -389,15 +496,11 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 			return
 		}
 
-		// Override TCP handler with AllowNet filter and/or MITM secret substitution
-		if len(config.AllowNet) > 0 || instance.secretMatcher != nil {
-			var tcpFilter *TCPFilter
-			if len(config.AllowNet) > 0 {
-				tcpFilter = NewTCPFilter(config.AllowNet, config.GatewayIP, config.GuestIP)
-			}
-			if err := OverrideTCPHandler(vn, tapConfig, tapConfig.Ec2MetadataAccess, tcpFilter, instance.ca, instance.secretMatcher); err != nil {
-				logrus.WithError(err).Error("TCP: failed to override handler")
-			}
+		// Override TCP handler with the live AllowNet / MITM policy. Always
+		// installed so gvproxy_update can restrict an unrestricted instance;
+		// with an empty policy it forwards exactly like upstream.
+		if err := OverrideTCPHandler(vn, tapConfig, tapConfig.Ec2MetadataAccess, instance.policy, instance.ca, instance.activity); err != nil {
+			logrus.WithError(err).Error("TCP: failed to override handler")
 		}
 
 		// Store VirtualNetwork reference for stats collection
@@ -425,7 +528,7 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 				logrus.WithFields(logrus.Fields{"id": id, "remote": wrappedConn.RemoteAddr().String()}).Info("VFKit connection accepted")
 
 				// Handle the VFKit protocol with the wrapped connection
-				if err := vn.AcceptVfkit(ctx, wrappedConn); err != nil {
+				if err := vn.AcceptVfkit(ctx, instance.shaper.wrap(wrappedConn)); err != nil {
 					if ctx.Err() == nil {
 						logrus.WithFields(logrus.Fields{"error": err, "id": id}).Error("AcceptVfkit error")
 					}
@@ -451,7 +554,7 @@ func gvproxy_create(configJSON *C.char) C.longlong {
 				listener.Close()
 
 				// Handle the Qemu protocol
-				if err := vn.AcceptQemu(ctx, acceptedConn); err != nil {
+				if err := vn.AcceptQemu(ctx, instance.shaper.wrap(acceptedConn)); err != nil {
 					if ctx.Err() == nil {
 						logrus.WithFields(logrus.Fields{"error": err, "id": id}).Error("AcceptQemu error")
 					}
@@ -526,11 +629,52 @@ func gvproxy_get_stats(id C.longlong) *C.char {
 	if stats == "" {
 		return nil
 	}
+	stats = instance.shaper.statsJSON(stats)
 
 	// Explicit: CString allocates memory, caller must free it
 	return C.CString(stats)
 }
 
+//export gvproxy_update
+func gvproxy_update(id C.longlong, updateJSON *C.char) *C.char {
+	var update GvproxyUpdate
+	if err := json.Unmarshal([]byte(C.GoString(updateJSON)), &update); err != nil {
+		return C.CString(fmt.Sprintf("invalid update JSON: %v", err))
+	}
+
+	instancesMu.RLock()
+	instance, ok := instances[int64(id)]
+	instancesMu.RUnlock()
+	if !ok {
+		return C.CString(fmt.Sprintf("unknown instance %d", int64(id)))
+	}
+
+	instance.vnMu.RLock()
+	vn := instance.vn
+	instance.vnMu.RUnlock()
+	if vn == nil {
+		return C.CString("virtual network not ready yet")
+	}
+
+	if err := instance.applyUpdate(vn, update); err != nil {
+		logrus.WithFields(logrus.Fields{"error": err, "id": id}).Warn("gvproxy update rejected")
+		return C.CString(err.Error())
+	}
+	return nil
+}
+
+//export gvproxy_drain_activity
+func gvproxy_drain_activity(id C.longlong) *C.char {
+	instancesMu.RLock()
+	instance, ok := instances[int64(id)]
+	instancesMu.RUnlock()
+	if !ok {
+		return nil
+	}
+	// Caller frees with gvproxy_free_string.
+	return C.CString(instance.activity.drainJSON())
+}
+
 //export gvproxy_get_version
 func gvproxy_get_version() *C.char {
 	// Get gvisor-tap-vsock version from build info
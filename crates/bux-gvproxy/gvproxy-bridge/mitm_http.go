@@ -0,0 +1,176 @@
+package main
+
+// mitm_http.go — request policy and request logging on the MITM path.
+//
+// Hosts named by an HTTPRule are intercepted on port 443 like secret hosts.
+// The first rule whose hosts match applies: requests with a method or path
+// outside its lists are answered 403 without reaching upstream, and the
+// rule's header edits are applied to the rest. With LogRequests set every
+// intercepted request is recorded as an http_request activity event;
+// refusals are recorded whenever the activity log is enabled.
+
+import (
+	"io"
+	"net/http"
+	"path"
+	"strings"
+	"sync/atomic"
+)
+
+// HTTPRule matches the Rust structure (must stay in sync!)
+type HTTPRule struct {
+	Hosts        []string          `json:"hosts"`
+	Methods      []string          `json:"methods,omitempty"`       // Empty = any method
+	PathPrefixes []string          `json:"path_prefixes,omitempty"` // Empty = any path
+	StripHeaders []string          `json:"strip_headers,omitempty"`
+	SetHeaders   map[string]string `json:"set_headers,omitempty"`
+}
+
+// HTTPPolicyConfig matches the Rust structure (must stay in sync!)
+type HTTPPolicyConfig struct {
+	Rules       []HTTPRule `json:"rules,omitempty"`
+	LogRequests bool       `json:"log_requests,omitempty"`
+}
+
+// httpPolicy is the parsed HTTPPolicyConfig; a nil policy intercepts nothing.
+type httpPolicy struct {
+	rules       []HTTPRule
+	logRequests bool
+}
+
+// newHTTPPolicy returns nil when no rule exists, so port 443 is not
+// inspected on its account.
+func newHTTPPolicy(cfg *HTTPPolicyConfig) *httpPolicy {
+	if cfg == nil || len(cfg.Rules) == 0 {
+		return nil
+	}
+	return &httpPolicy{rules: cfg.Rules, logRequests: cfg.LogRequests}
+}
+
+// ruleFor returns the first rule naming hostname, or nil.
+func (p *httpPolicy) ruleFor(hostname string) *HTTPRule {
+	if p == nil {
+		return nil
+	}
+	h := strings.ToLower(hostname)
+	for i := range p.rules {
+		for _, host := range p.rules[i].Hosts {
+			host = strings.ToLower(host)
+			if host == h || (strings.HasPrefix(host, "*.") && matchesWildcard(h, host[1:])) {
+				return &p.rules[i]
+			}
+		}
+	}
+	return nil
+}
+
+// check returns why a request is refused, or "" when the rule permits it.
+// A nil rule permits everything.
+func (r *HTTPRule) check(method, reqPath string) string {
+	if r == nil {
+		return ""
+	}
+	if len(r.Methods) > 0 && !containsFold(r.Methods, method) {
+		return "method " + method + " not allowed"
+	}
+	if len(r.PathPrefixes) == 0 {
+		return ""
+	}
+	// Refuse paths that normalise differently ("/a/../b", "//b") so a
+	// prefix cannot be escaped by dot segments the upstream resolves.
+	clean := path.Clean("/" + reqPath)
+	if strings.HasSuffix(reqPath, "/") && clean != "/" {
+		clean += "/"
+	}
+	if clean != reqPath {
+		return "non-canonical path " + reqPath
+	}
+	for _, prefix := range r.PathPrefixes {
+		if strings.HasPrefix(reqPath, prefix) {
+			return ""
+		}
+	}
+	return "path " + reqPath + " not allowed"
+}
+
+// rewrite strips, then sets, the rule's headers.
+func (r *HTTPRule) rewrite(h http.Header) {
+	if r == nil {
+		return
+	}
+	for _, name := range r.StripHeaders {
+		h.Del(name)
+	}
+	for name, value := range r.SetHeaders {
+		h.Set(name, value)
+	}
+}
+
+func containsFold(list []string, s string) bool {
+	for _, v := range list {
+		if strings.EqualFold(v, s) {
+			return true
+		}
+	}
+	return false
+}
+
+// countingBody counts request body bytes read by the proxy.
+type countingBody struct {
+	inner io.ReadCloser
+	n     atomic.Uint64
+}
+
+func (b *countingBody) Read(p []byte) (int, error) {
+	n, err := b.inner.Read(p)
+	b.n.Add(uint64(n))
+	return n, err
+}
+
+func (b *countingBody) Close() error { return b.inner.Close() }
+
+// statusWriter records the status code and response body size. Unwrap lets
+// ReverseProxy reach the underlying writer's Flush.
+type statusWriter struct {
+	http.ResponseWriter
+	status  int
+	written uint64
+}
+
+func (w *statusWriter) WriteHeader(code int) {
+	if w.status == 0 {
+		w.status = code
+	}
+	w.ResponseWriter.WriteHeader(code)
+}
+
+func (w *statusWriter) Write(p []byte) (int, error) {
+	if w.status == 0 {
+		w.status = http.StatusOK
+	}
+	n, err := w.ResponseWriter.Write(p)
+	w.written += uint64(n)
+	return n, err
+}
+
+func (w *statusWriter) Unwrap() http.ResponseWriter { return w.ResponseWriter }
+
+// policyHandler enforces rule in front of next and records requests.
+func policyHandler(next http.Handler, hostname string, rule *HTTPRule, activity *activityLog, logRequests bool) http.Handler {
+	return http.HandlerFunc(func(w http.ResponseWriter, req *http.Request) {
+		if reason := rule.check(req.Method, req.URL.Path); reason != "" {
+			http.Error(w, "blocked by HTTP policy: "+reason, http.StatusForbidden)
+			activity.httpRequest(hostname, req.Method, req.URL.Path, http.StatusForbidden, 0, 0, reason)
+			return
+		}
+		if activity == nil || !logRequests {
+			next.ServeHTTP(w, req)
+			return
+		}
+		body := &countingBody{inner: req.Body}
+		req.Body = body
+		sw := &statusWriter{ResponseWriter: w}
+		next.ServeHTTP(sw, req)
+		activity.httpRequest(hostname, req.Method, req.URL.Path, sw.status, body.n.Load(), sw.written, "")
+	})
+}
@@ -0,0 +1,137 @@
+package main
+
+import (
+	"context"
+	"crypto/tls"
+	"encoding/json"
+	"fmt"
+	"io"
+	"net"
+	"net/http"
+	"strings"
+	"testing"
+	"time"
+)
+
+func TestHTTPRule_Check(t *testing.T) {
+	rule := &HTTPRule{
+		Hosts:        []string{"api.github.com"},
+		Methods:      []string{"GET", "head"},
+		PathPrefixes: []string{"/repos/"},
+	}
+	cases := []struct {
+		method, path string
+		allowed      bool
+	}{
+		{"GET", "/repos/qntx/bux", true},
+		{"HEAD", "/repos/", true},
+		{"POST", "/repos/qntx/bux", false},
+		{"GET", "/user", false},
+		{"GET", "/repos/../user", false},
+		{"GET", "/repos//x", false},
+	}
+	for _, c := range cases {
+		if got := rule.check(c.method, c.path) == ""; got != c.allowed {
+			t.Errorf("%s %s: allowed=%v, want %v", c.method, c.path, got, c.allowed)
+		}
+	}
+	var none *HTTPRule
+	if none.check("DELETE", "/anything") != "" {
+		t.Error("nil rule must allow everything")
+	}
+}
+
+func TestHTTPPolicy_FirstMatchingRule(t *testing.T) {
+	p := newHTTPPolicy(&HTTPPolicyConfig{Rules: []HTTPRule{
+		{Hosts: []string{"*.github.com"}, Methods: []string{"GET"}},
+		{Hosts: []string{"api.github.com"}},
+	}})
+	if r := p.ruleFor("API.github.com"); r == nil || len(r.Methods) != 1 {
+		t.Fatalf("expected wildcard rule first, got %+v", r)
+	}
+	if p.ruleFor("example.com") != nil {
+		t.Fatal("unrelated host matched")
+	}
+	if newHTTPPolicy(&HTTPPolicyConfig{LogRequests: true}) != nil {
+		t.Fatal("policy without rules should be nil")
+	}
+}
+
+func TestHTTPRule_Rewrite(t *testing.T) {
+	rule := &HTTPRule{
+		StripHeaders: []string{"cookie"},
+		SetHeaders:   map[string]string{"X-Agent": "bux"},
+	}
+	h := http.Header{}
+	h.Set("Cookie", "a=b")
+	h.Set("X-Agent", "other")
+	rule.rewrite(h)
+	if h.Get("Cookie") != "" || h.Get("X-Agent") != "bux" {
+		t.Fatalf("unexpected headers %v", h)
+	}
+}
+
+func TestMitmProxy_HTTPPolicyBlocksAndLogs(t *testing.T) {
+	ca := newTestCA(t)
+	addr, cleanup := startTestUpstream(t, func(w http.ResponseWriter, r *http.Request) {
+		fmt.Fprintf(w, "agent=%s cookie=%s", r.Header.Get("X-Agent"), r.Header.Get("Cookie"))
+	})
+	defer cleanup()
+
+	activity := newActivityLog(true)
+	opts := mitmOptions{
+		rule: &HTTPRule{
+			Hosts:        []string{"api.example.com"},
+			Methods:      []string{"GET"},
+			StripHeaders: []string{"Cookie"},
+			SetHeaders:   map[string]string{"X-Agent": "bux"},
+		},
+		activity:    activity,
+		logRequests: true,
+	}
+	caPool, _ := ca.CACertPool()
+	guest, proxy := net.Pipe()
+	go mitmWithOptions(proxy, "api.example.com", addr, ca, opts, &tls.Config{InsecureSkipVerify: true})
+	tlsConn := tls.Client(guest, &tls.Config{ServerName: "api.example.com", RootCAs: caPool, NextProtos: []string{"http/1.1"}})
+	client := &http.Client{
+		Transport: &http.Transport{
+			DialTLSContext: func(_ context.Context, _, _ string) (net.Conn, error) { return tlsConn, nil },
+		},
+		Timeout: 10 * time.Second,
+	}
+
+	req, _ := http.NewRequest("GET", "https://api.example.com/ok", nil)
+	req.Header.Set("Cookie", "session=1")
+	resp, err := client.Do(req)
+	if err != nil {
+		t.Fatal(err)
+	}
+	body, _ := io.ReadAll(resp.Body)
+	resp.Body.Close()
+	if got := string(body); got != "agent=bux cookie=" {
+		t.Fatalf("headers not rewritten: %s", got)
+	}
+
+	resp, err = client.Post("https://api.example.com/ok", "text/plain", strings.NewReader("x"))
+	if err != nil {
+		t.Fatal(err)
+	}
+	resp.Body.Close()
+	if resp.StatusCode != http.StatusForbidden {
+		t.Fatalf("POST should be refused, got %d", resp.StatusCode)
+	}
+
+	var events []activityEvent
+	if err := json.Unmarshal([]byte(activity.drainJSON()), &events); err != nil {
+		t.Fatal(err)
+	}
+	if len(events) != 2 {
+		t.Fatalf("expected 2 events, got %+v", events)
+	}
+	if e := events[0]; e.Kind != "http_request" || e.Method != "GET" || e.Path != "/ok" || e.Status != 200 || e.BytesIn == 0 {
+		t.Fatalf("unexpected logged request %+v", e)
+	}
+	if e := events[1]; e.Method != "POST" || e.Status != 403 || e.Blocked == "" {
+		t.Fatalf("unexpected refusal %+v", e)
+	}
+}
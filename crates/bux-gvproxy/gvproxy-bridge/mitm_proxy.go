@@ -14,9 +14,23 @@ import (
 
 const upstreamDialTimeout = 30 * time.Second
 
+// mitmOptions selects what the proxy does with intercepted requests.
+type mitmOptions struct {
+	secrets     []SecretConfig
+	rule        *HTTPRule    // HTTP policy for the host (nil = allow all)
+	activity    *activityLog // Receives refusals, and requests when logRequests
+	logRequests bool
+}
+
 // mitmAndForward handles a MITM'd connection: TLS termination, reverse proxy, secret substitution.
 // upstreamTLSConfig overrides the TLS config for upstream connections (nil = system defaults).
 func mitmAndForward(guestConn net.Conn, hostname string, destAddr string, ca *BoxCA, secrets []SecretConfig, upstreamTLSConfig ...*tls.Config) {
+	mitmWithOptions(guestConn, hostname, destAddr, ca, mitmOptions{secrets: secrets}, upstreamTLSConfig...)
+}
+
+// mitmWithOptions is mitmAndForward with an HTTP policy and request log.
+func mitmWithOptions(guestConn net.Conn, hostname string, destAddr string, ca *BoxCA, opts mitmOptions, upstreamTLSConfig ...*tls.Config) {
+	secrets := opts.secrets
 	cert, err := ca.GenerateHostCert(hostname)
 	if err != nil {
 		logrus.WithError(err).WithField("hostname", hostname).Error("MITM: cert generation failed")
@@ -44,6 +58,7 @@ func mitmAndForward(guestConn net.Conn, hostname string, destAddr string, ca *Bo
 			req.URL.Scheme = "https"
 			req.URL.Host = hostname
 			req.Host = hostname // HTTP/1.1 Host header must match
+			opts.rule.rewrite(req.Header)
 			// Headers substituted here; body substituted in secretTransport.RoundTrip
 			substituteHeaders(req, secrets)
 		},
@@ -68,15 +83,17 @@ func mitmAndForward(guestConn net.Conn, hostname string, destAddr string, ca *Bo
 		return
 	}
 
+	handler := policyHandler(proxy, hostname, opts.rule, opts.activity, opts.logRequests)
+
 	if tlsGuest.ConnectionState().NegotiatedProtocol == "h2" {
 		h2srv := &http2.Server{}
-		h2srv.ServeConn(tlsGuest, &http2.ServeConnOpts{Handler: proxy})
+		h2srv.ServeConn(tlsGuest, &http2.ServeConnOpts{Handler: handler})
 	} else {
 		// HTTP/1.1: use http.Server with a proper shutdown mechanism.
 		// After the single connection closes, shut down the server to avoid
 		// leaking a goroutine blocked in Accept().
 		listener := newSingleConnListener(tlsGuest)
-		srv := &http.Server{Handler: proxy}
+		srv := &http.Server{Handler: handler}
 		srv.Serve(listener) //nolint:errcheck
 		// Serve returns when the connection closes — shut down to release resources
 		srv.Close()
@@ -0,0 +1,195 @@
+package main
+
+// shaping.go — per-instance rate limits and transfer quotas (mirrors bux_gvproxy::ShapingConfig).
+//
+// The guest link connection is wrapped in a shapedConn: bytes read from
+// it are egress, bytes written to it ingress. Each direction has an
+// optional token bucket; the wrapper sleeps off any deficit, which holds
+// back the guest or gvisor-tap-vsock until the bucket refills. Quotas
+// count the same bytes. When one runs out a quota_exceeded activity event
+// is recorded once and, unless on_quota is "throttle", the TCP forwarder
+// refuses new connections (stopping the VM is up to the caller).
+
+import (
+	"encoding/json"
+	"net"
+	"sync"
+	"sync/atomic"
+	"time"
+
+	logrus "github.com/sirupsen/logrus"
+)
+
+// minBurst keeps one maximum-size frame within a single bucket.
+const minBurst = 64 * 1024
+
+// ShapingConfig matches the Rust structure (must stay in sync!)
+//
+// Rates are bytes per second, quotas bytes; zero means unlimited.
+type ShapingConfig struct {
+	EgressRate   uint64 `json:"egress_rate,omitempty"`
+	IngressRate  uint64 `json:"ingress_rate,omitempty"`
+	EgressQuota  uint64 `json:"egress_quota,omitempty"`
+	IngressQuota uint64 `json:"ingress_quota,omitempty"`
+	OnQuota      string `json:"on_quota,omitempty"` // block | throttle | stop
+	ThrottleRate uint64 `json:"throttle_rate,omitempty"`
+}
+
+// tokenBucket may run into deficit by one write; take reports the wait.
+type tokenBucket struct {
+	rate   float64
+	burst  float64
+	tokens float64
+	last   time.Time
+}
+
+func newTokenBucket(rate uint64, now time.Time) *tokenBucket {
+	burst := float64(rate)
+	if burst < minBurst {
+		burst = minBurst
+	}
+	return &tokenBucket{rate: float64(rate), burst: burst, tokens: burst, last: now}
+}
+
+func (b *tokenBucket) take(n int, now time.Time) time.Duration {
+	b.tokens += now.Sub(b.last).Seconds() * b.rate
+	if b.tokens > b.burst {
+		b.tokens = b.burst
+	}
+	b.last = now
+	b.tokens -= float64(n)
+	if b.tokens >= 0 {
+		return 0
+	}
+	return time.Duration(-b.tokens / b.rate * float64(time.Second))
+}
+
+// meter tracks one direction.
+type meter struct {
+	name     string
+	bucket   *tokenBucket
+	quota    uint64
+	used     uint64
+	exceeded bool
+}
+
+// trafficShaper is safe for concurrent use; a nil shaper admits everything.
+type trafficShaper struct {
+	mu           sync.Mutex
+	egress       meter
+	ingress      meter
+	onQuota      string
+	throttleRate uint64
+	blocked      atomic.Bool
+	exceeded     atomic.Bool
+	activity     *activityLog
+}
+
+func newTrafficShaper(config *ShapingConfig, activity *activityLog) *trafficShaper {
+	if config == nil {
+		return nil
+	}
+	now := time.Now()
+	s := &trafficShaper{
+		egress:       meter{name: "egress", quota: config.EgressQuota},
+		ingress:      meter{name: "ingress", quota: config.IngressQuota},
+		onQuota:      config.OnQuota,
+		throttleRate: config.ThrottleRate,
+		activity:     activity,
+	}
+	if config.EgressRate > 0 {
+		s.egress.bucket = newTokenBucket(config.EgressRate, now)
+	}
+	if config.IngressRate > 0 {
+		s.ingress.bucket = newTokenBucket(config.IngressRate, now)
+	}
+	return s
+}
+
+// admit accounts n bytes on m and returns how long to hold them.
+func (s *trafficShaper) admit(m *meter, n int, now time.Time) time.Duration {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+	m.used += uint64(n)
+	if m.quota > 0 && !m.exceeded && m.used > m.quota {
+		m.exceeded = true
+		s.quotaExceeded(m, now)
+	}
+	if m.bucket == nil {
+		return 0
+	}
+	return m.bucket.take(n, now)
+}
+
+// quotaExceeded applies on_quota; called with s.mu held.
+func (s *trafficShaper) quotaExceeded(m *meter, now time.Time) {
+	logrus.WithFields(logrus.Fields{
+		"direction": m.name,
+		"quota":     m.quota,
+		"action":    s.onQuota,
+	}).Warn("network quota exceeded")
+	s.exceeded.Store(true)
+	if s.onQuota == "throttle" && s.throttleRate > 0 {
+		rate := s.throttleRate
+		if m.bucket != nil && uint64(m.bucket.rate) < rate {
+			rate = uint64(m.bucket.rate)
+		}
+		m.bucket = newTokenBucket(rate, now)
+	} else {
+		s.blocked.Store(true)
+	}
+	s.activity.quotaExceeded(m.name, m.quota)
+}
+
+// blocksNewFlows reports whether the TCP forwarder must refuse connections.
+func (s *trafficShaper) blocksNewFlows() bool {
+	return s != nil && s.blocked.Load()
+}
+
+// wrap returns conn metered by s, or conn itself when s is nil.
+func (s *trafficShaper) wrap(conn net.Conn) net.Conn {
+	if s == nil {
+		return conn
+	}
+	return &shapedConn{Conn: conn, shaper: s}
+}
+
+// statsJSON adds a "Shaping" entry to the upstream /stats JSON.
+func (s *trafficShaper) statsJSON(stats string) string {
+	if s == nil {
+		return stats
+	}
+	var fields map[string]json.RawMessage
+	if err := json.Unmarshal([]byte(stats), &fields); err != nil {
+		return stats
+	}
+	shaping, err := json.Marshal(map[string]bool{"QuotaExceeded": s.exceeded.Load()})
+	if err != nil {
+		return stats
+	}
+	fields["Shaping"] = shaping
+	merged, err := json.Marshal(fields)
+	if err != nil {
+		return stats
+	}
+	return string(merged)
+}
+
+// shapedConn meters the guest link: reads are egress, writes ingress.
+type shapedConn struct {
+	net.Conn
+	shaper *trafficShaper
+}
+
+func (c *shapedConn) Read(p []byte) (int, error) {
+	n, err := c.Conn.Read(p)
+	if n > 0 {
+		time.Sleep(c.shaper.admit(&c.shaper.egress, n, time.Now()))
+	}
+	return n, err
+}
+
+func (c *shapedConn) Write(p []byte) (int, error) {
+	time.Sleep(c.shaper.admit(&c.shaper.ingress, len(p), time.Now()))
+	return c.Conn.Write(p)
+}
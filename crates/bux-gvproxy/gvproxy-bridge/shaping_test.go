@@ -0,0 +1,75 @@
+package main
+
+import (
+	"encoding/json"
+	"testing"
+	"time"
+)
+
+func TestTokenBucket_DelaysPastBurst(t *testing.T) {
+	start := time.Now()
+	b := newTokenBucket(1000, start)
+	if d := b.take(minBurst, start); d != 0 {
+		t.Fatalf("burst should pass, waited %v", d)
+	}
+	if d := b.take(500, start); d != 500*time.Millisecond {
+		t.Fatalf("expected 500ms wait, got %v", d)
+	}
+	if d := b.take(1000, start.Add(500*time.Millisecond)); d != time.Second {
+		t.Fatalf("expected 1s wait, got %v", d)
+	}
+}
+
+func TestTrafficShaper_NilAdmitsEverything(t *testing.T) {
+	s := newTrafficShaper(nil, nil)
+	if s.blocksNewFlows() {
+		t.Fatal("nil shaper must not block")
+	}
+	if got := s.statsJSON(`{"BytesSent":1}`); got != `{"BytesSent":1}` {
+		t.Fatalf("nil shaper changed stats: %s", got)
+	}
+}
+
+func TestTrafficShaper_QuotaBlocksAndRecordsOnce(t *testing.T) {
+	log := newActivityLog(true)
+	s := newTrafficShaper(&ShapingConfig{EgressQuota: 100, OnQuota: "stop"}, log)
+	now := time.Now()
+	s.admit(&s.egress, 100, now)
+	if s.blocksNewFlows() {
+		t.Fatal("quota not yet exceeded")
+	}
+	s.admit(&s.egress, 1, now)
+	s.admit(&s.egress, 1, now)
+	if !s.blocksNewFlows() {
+		t.Fatal("exceeded quota should block new flows")
+	}
+
+	var events []activityEvent
+	if err := json.Unmarshal([]byte(log.drainJSON()), &events); err != nil {
+		t.Fatal(err)
+	}
+	if len(events) != 1 || events[0].Kind != "quota_exceeded" || events[0].Direction != "egress" || events[0].Limit != 100 {
+		t.Fatalf("unexpected events %+v", events)
+	}
+
+	var stats map[string]map[string]bool
+	if err := json.Unmarshal([]byte(s.statsJSON(`{"BytesSent":1}`)), &stats); err != nil {
+		t.Fatal(err)
+	}
+	if !stats["Shaping"]["QuotaExceeded"] {
+		t.Fatalf("stats should flag the quota: %v", stats)
+	}
+}
+
+func TestTrafficShaper_ThrottleSlowsInsteadOfBlocking(t *testing.T) {
+	s := newTrafficShaper(&ShapingConfig{IngressQuota: 10, OnQuota: "throttle", ThrottleRate: 1000}, nil)
+	now := time.Now()
+	s.admit(&s.ingress, 11, now)
+	if s.blocksNewFlows() {
+		t.Fatal("throttle must not block")
+	}
+	s.admit(&s.ingress, minBurst-11, now)
+	if d := s.admit(&s.ingress, 2000, now); d != 2*time.Second {
+		t.Fatalf("expected 2s wait at throttle rate, got %v", d)
+	}
+}
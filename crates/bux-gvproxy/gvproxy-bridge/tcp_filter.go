@@ -1,41 +1,48 @@
 package main
 
-// tcp_filter.go — AllowNet matcher for TCP-level filtering.
+// tcp_filter.go — egress policy matcher for TCP-level filtering.
 //
-// Supports: exact IP, CIDR, exact hostname, wildcard hostname (*.example.com).
+// Rules use the syntax in egress_rule.go: exact IP, CIDR, exact hostname,
+// wildcard hostname (*.example.com) or "*", each optionally limited to a
+// port range and protocol, and "!" for deny. Deny rules are evaluated
+// before allow rules; with no match the policy default applies.
 // IP/CIDR rules are checked directly against destination IPs.
-// Hostname rules are checked via SNI/Host header inspection (see forked_tcp.go).
+// Hostname rules are checked via SNI/Host inspection (see forked_tcp.go).
 
 import (
 	"net"
-	"strings"
 
 	logrus "github.com/sirupsen/logrus"
 )
 
-// TCPFilter checks outbound TCP connections against an allowlist.
+// TCPFilter checks outbound TCP connections against an egress policy.
 // nil filter means no filtering (all traffic allowed).
 type TCPFilter struct {
-	exactIPs         map[[4]byte]bool
-	cidrs            []*net.IPNet
+	deny             []egressRule
+	allow            []egressRule
+	defaultDeny      bool
 	alwaysAllow      map[[4]byte]bool // gateway + guest IPs
-	exactHosts       map[string]bool  // "api.openai.com" → true
-	wildcardSuffixes []string         // ".example.com"
-	hasHostnameRules bool
+	hasHostnameRules bool             // any hostname allow rule
 }
 
-// NewTCPFilter parses allow_net rules into IP/CIDR and hostname categories.
-// Returns nil if rules is empty (zero overhead fast path).
+type egressVerdict int
+
+const (
+	verdictDefault egressVerdict = iota
+	verdictAllow
+	verdictDeny
+)
+
+// NewTCPFilter parses allow_net rules with the default inferred: deny when
+// any allow rule exists. Returns nil if rules is empty.
 func NewTCPFilter(rules []string, gatewayIP, guestIP string) *TCPFilter {
-	if len(rules) == 0 {
-		return nil
-	}
+	return NewEgressFilter(rules, nil, gatewayIP, guestIP)
+}
 
-	f := &TCPFilter{
-		exactIPs:    make(map[[4]byte]bool),
-		alwaysAllow: make(map[[4]byte]bool),
-		exactHosts:  make(map[string]bool),
-	}
+// NewEgressFilter parses rules; defaultDeny overrides the inferred default.
+// Returns nil when nothing would be filtered (zero overhead fast path).
+func NewEgressFilter(rules []string, defaultDeny *bool, gatewayIP, guestIP string) *TCPFilter {
+	f := &TCPFilter{alwaysAllow: make(map[[4]byte]bool)}
 
 	// Internal IPs always allowed
 	for _, ipStr := range []string{gatewayIP, guestIP} {
@@ -46,100 +53,173 @@ func NewTCPFilter(rules []string, gatewayIP, guestIP string) *TCPFilter {
 		}
 	}
 
-	for _, rule := range rules {
-		rule = strings.TrimSpace(rule)
-		if rule == "" {
+	for _, raw := range rules {
+		rule, err := parseEgressRule(raw)
+		if err != nil {
+			logrus.WithError(err).Warn("allowNet TCP: skipped invalid rule")
 			continue
 		}
-
-		// Exact IP: "1.2.3.4"
-		if ip := net.ParseIP(rule); ip != nil {
-			if ip4 := ip.To4(); ip4 != nil {
-				f.exactIPs[toIPv4Key(ip4)] = true
-				logrus.WithField("ip", rule).Debug("allowNet TCP: added exact IP")
-			}
-			continue
-		}
-
-		// CIDR: "10.0.0.0/8"
-		if _, cidr, err := net.ParseCIDR(rule); err == nil {
-			f.cidrs = append(f.cidrs, cidr)
-			logrus.WithField("cidr", rule).Debug("allowNet TCP: added CIDR")
-			continue
-		}
-
-		// Hostname (strip port if present)
-		host := rule
-		if h, _, err := net.SplitHostPort(rule); err == nil {
-			host = h
-		}
-
-		// Wildcard: *.example.com
-		if strings.HasPrefix(host, "*.") {
-			suffix := strings.ToLower(host[1:]) // ".example.com"
-			f.wildcardSuffixes = append(f.wildcardSuffixes, suffix)
-			f.hasHostnameRules = true
-			logrus.WithField("wildcard", host).Debug("allowNet TCP: added wildcard")
-			continue
+		if rule.deny {
+			f.deny = append(f.deny, rule)
+		} else {
+			f.allow = append(f.allow, rule)
+			f.hasHostnameRules = f.hasHostnameRules || rule.isHostname()
 		}
+		logrus.WithField("rule", raw).Debug("allowNet TCP: added rule")
+	}
 
-		// Exact hostname
-		f.exactHosts[strings.ToLower(host)] = true
-		f.hasHostnameRules = true
-		logrus.WithField("hostname", host).Debug("allowNet TCP: added hostname")
+	if defaultDeny != nil {
+		f.defaultDeny = *defaultDeny
+	} else {
+		f.defaultDeny = len(f.allow) > 0
+	}
+	if len(f.deny) == 0 && len(f.allow) == 0 && !f.defaultDeny {
+		return nil
 	}
 
 	logrus.WithFields(logrus.Fields{
-		"exact_ips": len(f.exactIPs),
-		"cidrs":     len(f.cidrs),
-		"hostnames": len(f.exactHosts),
-		"wildcards": len(f.wildcardSuffixes),
+		"allow":        len(f.allow),
+		"deny":         len(f.deny),
+		"default_deny": f.defaultDeny,
 	}).Info("allowNet TCP: filter initialized")
 
 	return f
 }
 
-// MatchesIP checks if destIP is allowed by IP/CIDR rules or always-allow.
+// MatchesIP checks if destIP is allowed by IP/CIDR allow rules (any port)
+// or always-allow.
 func (f *TCPFilter) MatchesIP(destIP net.IP) bool {
 	ip4 := destIP.To4()
 	if ip4 == nil {
 		return false
 	}
-	key := toIPv4Key(ip4)
-	if f.alwaysAllow[key] {
-		return true
-	}
-	if f.exactIPs[key] {
+	if f.alwaysAllow[toIPv4Key(ip4)] {
 		return true
 	}
-	for _, cidr := range f.cidrs {
-		if cidr.Contains(ip4) {
+	for _, rule := range f.allow {
+		if !rule.isHostname() && rule.matchesIP(ip4) {
 			return true
 		}
 	}
 	return false
 }
 
-// MatchesHostname checks if hostname is allowed by hostname rules.
+// MatchesHostname checks if hostname is allowed by hostname allow rules
+// (any port).
 func (f *TCPFilter) MatchesHostname(hostname string) bool {
-	hostname = strings.ToLower(strings.TrimSuffix(hostname, "."))
-	if hostname == "" {
-		return false
+	for _, rule := range f.allow {
+		if rule.isHostname() && rule.matchesHost(hostname) {
+			return true
+		}
 	}
-	if f.exactHosts[hostname] {
+	return false
+}
+
+// HasHostnameRules returns true if any hostname/wildcard allow rules exist.
+func (f *TCPFilter) HasHostnameRules() bool {
+	return f.hasHostnameRules
+}
+
+// Allows evaluates the whole policy for a TCP flow. hostname may be empty
+// when it is unknown.
+func (f *TCPFilter) Allows(hostname string, destIP net.IP, port uint16) bool {
+	if ip4 := destIP.To4(); ip4 != nil && f.alwaysAllow[toIPv4Key(ip4)] {
+		return true
+	}
+	switch f.evaluate(hostname, destIP, port) {
+	case verdictAllow:
 		return true
+	case verdictDeny:
+		return false
+	default:
+		return !f.defaultDeny
+	}
+}
+
+// evaluate returns the first matching deny rule, else the first matching
+// allow rule, else verdictDefault.
+func (f *TCPFilter) evaluate(hostname string, destIP net.IP, port uint16) egressVerdict {
+	verdict, _ := f.matchRule(hostname, destIP, port)
+	return verdict
+}
+
+// matchRule is evaluate plus the deciding rule as written ("" for the default).
+func (f *TCPFilter) matchRule(hostname string, destIP net.IP, port uint16) (egressVerdict, string) {
+	matches := func(rule egressRule) bool {
+		return rule.matchesPort(port, "tcp") && (rule.matchesIP(destIP) || (hostname != "" && rule.matchesHost(hostname)))
+	}
+	for _, rule := range f.deny {
+		if matches(rule) {
+			return verdictDeny, rule.raw
+		}
+	}
+	for _, rule := range f.allow {
+		if matches(rule) {
+			return verdictAllow, rule.raw
+		}
+	}
+	return verdictDefault, ""
+}
+
+// denyingRule names the deny rule blocking a flow ("" when the default did).
+func (f *TCPFilter) denyingRule(hostname string, destIP net.IP, port uint16) string {
+	if f == nil {
+		return ""
 	}
-	for _, suffix := range f.wildcardSuffixes {
-		if strings.HasSuffix(hostname, suffix) {
+	if verdict, rule := f.matchRule(hostname, destIP, port); verdict == verdictDeny {
+		return rule
+	}
+	return ""
+}
+
+// hostnameRulesOn reports whether a hostname deny (or allow) rule applies
+// to TCP port, i.e. whether SNI/Host inspection can change the verdict.
+func (f *TCPFilter) hostnameRulesOn(port uint16, deny bool) bool {
+	rules := f.allow
+	if deny {
+		rules = f.deny
+	}
+	for _, rule := range rules {
+		if rule.isHostname() && rule.matchesPort(port, "tcp") {
 			return true
 		}
 	}
 	return false
 }
 
-// HasHostnameRules returns true if any hostname/wildcard rules exist.
-func (f *TCPFilter) HasHostnameRules() bool {
-	return f.hasHostnameRules
+// dnsAllowHosts returns the hostname patterns that keep resolving when the
+// policy sinkholes DNS (default deny without an allow "*" rule).
+func (f *TCPFilter) dnsAllowHosts() ([]string, bool) {
+	if f == nil || !f.defaultDeny {
+		return nil, false
+	}
+	var hosts []string
+	for _, rule := range f.allow {
+		switch {
+		case rule.any:
+			return nil, false
+		case rule.host != "":
+			hosts = append(hosts, rule.host)
+		case rule.suffix != "":
+			hosts = append(hosts, "*"+rule.suffix)
+		}
+	}
+	return hosts, true
+}
+
+// dnsDenySuffixes returns the suffixes of wildcard deny rules that cover
+// every port and protocol; names under them never resolve.
+func (f *TCPFilter) dnsDenySuffixes() []string {
+	if f == nil {
+		return nil
+	}
+	var suffixes []string
+	for _, rule := range f.deny {
+		if rule.suffix != "" && rule.portHi == 0 && rule.proto == "" {
+			suffixes = append(suffixes, rule.suffix)
+		}
+	}
+	return suffixes
 }
 
 func toIPv4Key(ip net.IP) [4]byte {
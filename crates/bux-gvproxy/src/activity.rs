@@ -0,0 +1,122 @@
+//! Network activity drained from a gvproxy instance.
+//!
+//! Deserialized from the JSON array returned by `gvproxy_drain_activity()`
+//! (`gvproxy-bridge/activity.go`). Recording is opt-in via
+//! [`GvproxyConfig::with_activity`](crate::GvproxyConfig::with_activity).
+
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+/// What an [`ActivityEvent`] records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ActivityKind {
+    /// A guest TCP connection was relayed upstream.
+    TcpConnect,
+    /// A relayed TCP connection ended.
+    TcpClose,
+    /// The egress policy refused a connection.
+    Denied,
+    /// The MITM proxy handled (or refused) an HTTP request.
+    HttpRequest,
+    /// A transfer quota was used up.
+    QuotaExceeded,
+    /// A kind this crate version does not know.
+    #[serde(other)]
+    Unknown,
+}
+
+/// One entry from the Go-side activity ring.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    /// Event kind.
+    pub kind: ActivityKind,
+    /// When the Go side recorded it (Unix milliseconds).
+    pub timestamp_ms: u64,
+    /// `tcp` or `udp`.
+    #[serde(default)]
+    pub protocol: Option<String>,
+    /// Remote endpoint.
+    #[serde(default)]
+    pub remote: Option<SocketAddr>,
+    /// Hostname from SNI / HTTP `Host`, when inspected.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Deny rule as written; absent when the default denied.
+    #[serde(default)]
+    pub rule: Option<String>,
+    /// Bytes sent by the guest (`tcp_close`; request body for `http_request`).
+    #[serde(default)]
+    pub bytes_out: u64,
+    /// Bytes delivered to the guest (`tcp_close`; response body for `http_request`).
+    #[serde(default)]
+    pub bytes_in: u64,
+    /// Request method (`http_request`).
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Request URL path (`http_request`).
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Response status (`http_request`).
+    #[serde(default)]
+    pub status: Option<u16>,
+    /// Why the HTTP policy refused the request (`http_request`).
+    #[serde(default)]
+    pub blocked: Option<String>,
+    /// `egress` or `ingress` (`quota_exceeded`).
+    #[serde(default)]
+    pub direction: Option<String>,
+    /// The quota in bytes (`quota_exceeded`).
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+impl ActivityEvent {
+    /// Parse the JSON array returned by the Go FFI layer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the underlying `serde_json::Error` if the payload does
+    /// not conform to the expected schema.
+    pub fn from_json_array(json: &str) -> Result<Vec<Self>, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize() {
+        let json = r#"[
+            {"kind":"tcp_connect","timestamp_ms":1,"protocol":"tcp","remote":"1.2.3.4:443","host":"example.com"},
+            {"kind":"tcp_close","timestamp_ms":2,"protocol":"tcp","remote":"1.2.3.4:443","bytes_out":10,"bytes_in":20},
+            {"kind":"denied","timestamp_ms":3,"protocol":"tcp","remote":"10.0.0.1:22","rule":"!10.0.0.0/8"},
+            {"kind":"http_request","timestamp_ms":4,"host":"api.github.com","method":"POST","path":"/repos","status":403,"blocked":"method POST not allowed"},
+            {"kind":"dns_query","timestamp_ms":5},
+            {"kind":"quota_exceeded","timestamp_ms":6,"direction":"egress","limit":1024}
+        ]"#;
+        let events = ActivityEvent::from_json_array(json).unwrap();
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[0].host.as_deref(), Some("example.com"));
+        assert_eq!(events[1].bytes_in, 20);
+        assert_eq!(events[2].rule.as_deref(), Some("!10.0.0.0/8"));
+        assert_eq!(events[3].kind, ActivityKind::HttpRequest);
+        assert_eq!(events[3].status, Some(403));
+        assert_eq!(events[3].method.as_deref(), Some("POST"));
+        assert_eq!(events[4].kind, ActivityKind::Unknown);
+        assert_eq!(events[5].kind, ActivityKind::QuotaExceeded);
+        assert_eq!(events[5].direction.as_deref(), Some("egress"));
+        assert_eq!(events[5].limit, Some(1024));
+    }
+
+    #[test]
+    fn empty_and_invalid() {
+        assert!(ActivityEvent::from_json_array("[]").unwrap().is_empty());
+        assert!(ActivityEvent::from_json_array("invalid").is_err());
+    }
+}
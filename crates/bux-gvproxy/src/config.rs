@@ -12,18 +12,29 @@
 //! | `gateway_ip` / `gateway_mac` | same | |
 //! | `guest_ip` / `guest_mac` | same | |
 //! | `mtu` | `mtu` | |
-//! | `port_mappings` | `port_mappings` | `{host_port, guest_port}` |
-//! | `dns_zones` | `dns_zones` | |
+//! | `port_mappings` | `port_mappings` | `{host_port, guest_port, bind_ip, protocol}`; `bind_ip` omit `None` (all interfaces), `protocol` omit `tcp` |
+//! | `dns_zones` | `dns_zones` | `{name, default_ip, records}`; empty `default_ip` = NXDOMAIN |
 //! | `dns_search_domains` | `dns_search_domains` | |
 //! | `debug` | `debug` | |
 //! | `capture_file` | `capture_file` | omit empty |
-//! | `allow_net` | `allow_net` | omit empty; empty = full egress |
+//! | `allow_net` | `allow_net` | omit empty; egress rules (`egress_rule.go` syntax) |
+//! | `egress_default_deny` | `egress_default_deny` | omit `None`; `None` = deny iff an allow rule exists |
 //! | `secrets` | `secrets` | omit empty; requires CA PEMs |
 //! | `ca_cert_pem` / `ca_key_pem` | same | omit empty |
+//! | `record_activity` | `record_activity` | omit `false`; see [`crate::activity`] |
+//! | `http_policy` | `http_policy` | omit `None`; `mitm_http.go`; requires CA PEMs |
+//! | `shaping` | `shaping` | omit `None`; rates and quotas, `shaping.go` |
+//! | `host_ip` | `host_ip` | omit `None`; virtual address translated to host loopback |
+//!
+//! [`GvproxyUpdate`] is the payload of `gvproxy_update()` (`live_update.go`):
+//! `allow_net`, `egress_default_deny` and `port_mappings` are the complete
+//! desired state, `secrets` is omitted to leave substitutions unchanged.
 
-use std::fmt;
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 
+pub use bux_net_types::{PortProtocol, SecretConfig};
 use serde::{Deserialize, Serialize};
 
 use crate::constants;
@@ -31,54 +42,150 @@
 /// Local DNS zone served by the gateway's embedded DNS server.
 ///
 /// Queries that don't match any zone are forwarded to the host's
-/// system DNS resolver.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// system DNS resolver. A zone owns every name under it: names without a
+/// record get `default_ip`, or NXDOMAIN when it is empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DnsZone {
     /// Zone name (e.g. `"myapp.local."`, `"."` for root).
     pub name: String,
-    /// Default IP for unmatched queries in this zone.
+    /// Default IP for unmatched queries in this zone; empty = NXDOMAIN.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub default_ip: String,
+    /// `A` records, by label relative to the zone.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<DnsRecordConfig>,
+}
+
+/// `A` record inside a [`DnsZone`] (Go `DNSRecord` in `main.go`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsRecordConfig {
+    /// Name relative to the zone (`"db"` in zone `"example.test."`).
+    pub name: String,
+    /// IPv4 address.
+    pub ip: String,
 }
 
 /// A single port mapping entry.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub struct PortMapping {
-    /// Host port to bind (always `0.0.0.0` on the Go side today).
+    /// Host port to bind.
     pub host_port: u16,
     /// Guest port to forward to.
     pub guest_port: u16,
+    /// Host address to bind; `None` = all interfaces (`0.0.0.0`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind_ip: Option<IpAddr>,
+    /// Transport forwarded.
+    #[serde(default, skip_serializing_if = "PortProtocol::is_tcp")]
+    pub protocol: PortProtocol,
+}
+
+impl PortMapping {
+    /// TCP mapping on all interfaces.
+    #[must_use]
+    pub const fn new(host_port: u16, guest_port: u16) -> Self {
+        Self {
+            host_port,
+            guest_port,
+            bind_ip: None,
+            protocol: PortProtocol::Tcp,
+        }
+    }
+
+    /// Bind only `ip` on the host.
+    #[must_use]
+    pub const fn with_bind_ip(mut self, ip: IpAddr) -> Self {
+        self.bind_ip = Some(ip);
+        self
+    }
+
+    /// Forward `protocol` instead of TCP.
+    #[must_use]
+    pub const fn with_protocol(mut self, protocol: PortProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
 }
 
-/// Secret placeholder substitution config for MITM (host-side only).
+/// Request rule for HTTPS hosts terminated by the MITM proxy.
 ///
-/// Wire format matches Go `SecretConfig` in `mitm_replacer.go`.
-/// The real `value` is never logged: [`Debug`] redacts it.
-#[derive(Clone, Serialize, Deserialize)]
-pub struct SecretConfig {
-    /// Logical secret name (for host bookkeeping).
-    pub name: String,
-    /// Hostnames (SNI / Host header) this secret applies to.
+/// Wire format matches Go `HTTPRule` in `mitm_http.go`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRuleConfig {
+    /// Hostnames (SNI) the rule applies to; `*.example.com` matches one label.
     pub hosts: Vec<String>,
-    /// Placeholder string that appears in guest traffic (e.g. `<BUX_SECRET:TOKEN>`).
-    pub placeholder: String,
-    /// Real secret value substituted on the host proxy — never sent into the guest.
-    pub value: String,
+    /// Permitted methods. Empty permits any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    /// Permitted URL path prefixes. Empty permits any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prefixes: Vec<String>,
+    /// Request headers removed before forwarding.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_headers: Vec<String>,
+    /// Request headers set (replacing any guest value) before forwarding.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set_headers: BTreeMap<String, String>,
 }
 
-impl fmt::Debug for SecretConfig {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("SecretConfig")
-            .field("name", &self.name)
-            .field("hosts", &self.hosts)
-            .field("placeholder", &self.placeholder)
-            .field("value", &"[REDACTED]")
-            .finish()
-    }
+/// HTTP request policy and logging on the MITM path.
+///
+/// Wire format matches Go `HTTPPolicyConfig` in `mitm_http.go`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpPolicyConfig {
+    /// Rules in priority order; the first whose hosts match applies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<HttpRuleConfig>,
+    /// Record every intercepted request as an `http_request` activity event.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub log_requests: bool,
+}
+
+/// What the Go side does once a transfer quota is used up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnQuota {
+    /// Refuse new TCP connections.
+    #[default]
+    Block,
+    /// Cap the direction at [`ShapingConfig::throttle_rate`].
+    Throttle,
+    /// Refuse new TCP connections; the caller stops the VM.
+    Stop,
+}
+
+/// Rate limits and transfer quotas on the guest link.
+///
+/// Wire format matches Go `ShapingConfig` in `shaping.go`. Rates are bytes
+/// per second and quotas bytes; `None` is unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShapingConfig {
+    /// Guest → network rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_rate: Option<u64>,
+    /// Network → guest rate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_rate: Option<u64>,
+    /// Guest → network quota.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_quota: Option<u64>,
+    /// Network → guest quota.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_quota: Option<u64>,
+    /// Action once a quota is used up.
+    #[serde(default)]
+    pub on_quota: OnQuota,
+    /// Rate after a quota when `on_quota` is [`OnQuota::Throttle`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle_rate: Option<u64>,
 }
 
 /// Complete configuration for a gvproxy virtual-network instance.
 ///
-/// All values are sent as JSON to the Go c-archive.
+/// All values are sent as JSON to the Go c-archive. The network is
+/// IPv4-only: upstream `virtualnetwork.New` registers no IPv6 protocol on
+/// its stack, so dual-stack needs a bridge-side stack of its own and is
+/// not offered yet (`bux-net`'s userspace backend serves IPv6).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GvproxyConfig {
     /// Unix socket path for the network tap interface.
@@ -116,10 +223,16 @@ pub struct GvproxyConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub capture_file: Option<String>,
 
-    /// Egress allow-list (hostnames / CIDRs). Empty means unrestricted egress.
+    /// Egress rules (`[!]dest[:port[-port]][/proto]`). Empty with no
+    /// default-deny means unrestricted egress.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub allow_net: Vec<String>,
 
+    /// Action for traffic no rule matches. `None` denies iff `allow_net`
+    /// holds an allow rule (the plain allow-list behaviour).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_default_deny: Option<bool>,
+
     /// MITM secret substitutions. Empty means no MITM.
     ///
     /// When non-empty, [`Self::ca_cert_pem`] and [`Self::ca_key_pem`] must also
@@ -134,6 +247,25 @@ pub struct GvproxyConfig {
     /// PEM-encoded MITM CA private key. Empty when secrets unused.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub ca_key_pem: String,
+
+    /// Buffer connection activity for
+    /// [`GvproxyInstance::drain_activity`](crate::GvproxyInstance::drain_activity).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub record_activity: bool,
+
+    /// HTTP request rules and logging. Hosts named by a rule are
+    /// intercepted on port 443, so the CA PEMs must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_policy: Option<HttpPolicyConfig>,
+
+    /// Rate limits and transfer quotas. `None` = unlimited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shaping: Option<ShapingConfig>,
+
+    /// Virtual address translated to the host's loopback
+    /// ([`constants::HOST_IP`]). `None` = the host is not reachable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_ip: Option<String>,
 }
 
 impl GvproxyConfig {
@@ -150,10 +282,7 @@ pub fn new(socket_path: PathBuf, port_mappings: Vec<(u16, u16)>) -> Self {
             mtu: constants::DEFAULT_MTU,
             port_mappings: port_mappings
                 .into_iter()
-                .map(|(host_port, guest_port)| PortMapping {
-                    host_port,
-                    guest_port,
-                })
+                .map(|(host_port, guest_port)| PortMapping::new(host_port, guest_port))
                 .collect(),
             dns_zones: Vec::new(),
             dns_search_domains: constants::DNS_SEARCH_DOMAINS
@@ -163,9 +292,14 @@ pub fn new(socket_path: PathBuf, port_mappings: Vec<(u16, u16)>) -> Self {
             debug: false,
             capture_file: None,
             allow_net: Vec::new(),
+            egress_default_deny: None,
             secrets: Vec::new(),
             ca_cert_pem: String::new(),
             ca_key_pem: String::new(),
+            record_activity: false,
+            http_policy: None,
+            shaping: None,
+            host_ip: None,
         };
 
         // Allow packet capture via environment variable.
@@ -197,6 +331,15 @@ pub fn with_dns_zones(mut self, zones: Vec<DnsZone>) -> Self {
         self
     }
 
+    /// Set the guest address and MAC (static DHCP lease and port-forward
+    /// target).
+    #[must_use]
+    pub fn with_guest(mut self, ip: Ipv4Addr, mac: [u8; 6]) -> Self {
+        self.guest_ip = ip.to_string();
+        self.guest_mac = constants::mac_to_string(mac);
+        self
+    }
+
     /// Set custom MTU.
     #[must_use]
     pub const fn with_mtu(mut self, mtu: u16) -> Self {
@@ -218,10 +361,56 @@ pub fn with_allow_net(mut self, allow_net: Vec<String>) -> Self {
         self
     }
 
+    /// Set the action for traffic no egress rule matches.
+    #[must_use]
+    pub const fn with_egress_default_deny(mut self, deny: bool) -> Self {
+        self.egress_default_deny = Some(deny);
+        self
+    }
+
+    /// Record connects, closes and egress denials.
+    #[must_use]
+    pub const fn with_activity(mut self, record: bool) -> Self {
+        self.record_activity = record;
+        self
+    }
+
+    /// Set the HTTP request policy; requires CA PEMs (see
+    /// [`Self::with_secrets`], which accepts an empty secret list).
+    #[must_use]
+    pub fn with_http_policy(mut self, policy: HttpPolicyConfig) -> Self {
+        self.http_policy = Some(policy);
+        self
+    }
+
+    /// Replace the port mappings (bind addresses, UDP).
+    #[must_use]
+    pub fn with_port_mappings(mut self, port_mappings: Vec<PortMapping>) -> Self {
+        self.port_mappings = port_mappings;
+        self
+    }
+
+    /// Set rate limits and transfer quotas.
+    #[must_use]
+    pub const fn with_shaping(mut self, shaping: ShapingConfig) -> Self {
+        self.shaping = Some(shaping);
+        self
+    }
+
+    /// Translate [`constants::HOST_IP`] to the host's loopback.
+    ///
+    /// The translated connections skip the egress rules, like the
+    /// gateway itself.
+    #[must_use]
+    pub fn with_host_gateway(mut self) -> Self {
+        self.host_ip = Some(constants::HOST_IP.to_owned());
+        self
+    }
+
     /// Attach MITM secrets and CA PEMs.
     ///
     /// Callers must supply a CA (see [`crate::ca::generate`]) whenever
-    /// `secrets` is non-empty; the Go side loads CA only when secrets exist.
+    /// `secrets` or the HTTP policy is non-empty.
     #[must_use]
     pub fn with_secrets(
         mut self,
@@ -236,6 +425,66 @@ pub fn with_secrets(
     }
 }
 
+/// Live changes applied to a running instance via
+/// [`GvproxyInstance::update`](crate::GvproxyInstance::update).
+///
+/// Rules added to `allow_net` get DNS zones and TCP admission immediately;
+/// removed rules stop admitting new TCP connections, but DNS answers for
+/// them keep resolving until restart (the DNS service cannot drop zones).
+/// For the same reason a default-deny policy cannot become default-allow.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GvproxyUpdate {
+    /// Complete egress rule list after the update.
+    pub allow_net: Vec<String>,
+    /// Default action after the update (see [`GvproxyConfig::egress_default_deny`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_default_deny: Option<bool>,
+    /// Complete set of published ports after the update.
+    pub port_mappings: Vec<PortMapping>,
+    /// Replacement secret set; `None` leaves substitutions unchanged.
+    ///
+    /// Only valid on instances created with a MITM CA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Vec<SecretConfig>>,
+}
+
+impl GvproxyUpdate {
+    /// Creates an update to the given allow-list and `(host, guest)` ports.
+    #[must_use]
+    pub fn new(allow_net: Vec<String>, port_mappings: &[(u16, u16)]) -> Self {
+        Self {
+            allow_net,
+            port_mappings: port_mappings
+                .iter()
+                .map(|&(host_port, guest_port)| PortMapping::new(host_port, guest_port))
+                .collect(),
+            egress_default_deny: None,
+            secrets: None,
+        }
+    }
+
+    /// Set the default action for unmatched traffic.
+    #[must_use]
+    pub const fn with_egress_default_deny(mut self, deny: bool) -> Self {
+        self.egress_default_deny = Some(deny);
+        self
+    }
+
+    /// Replace the secret set.
+    #[must_use]
+    pub fn with_secrets(mut self, secrets: Vec<SecretConfig>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Replace the port mappings (bind addresses, UDP).
+    #[must_use]
+    pub fn with_port_mappings(mut self, port_mappings: Vec<PortMapping>) -> Self {
+        self.port_mappings = port_mappings;
+        self
+    }
+}
+
 #[cfg(test)]
 #[allow(
     clippy::unwrap_used,
@@ -265,6 +514,14 @@ fn defaults() {
         assert!(cfg.ca_key_pem.is_empty());
     }
 
+    #[test]
+    fn guest_override() {
+        let cfg = GvproxyConfig::new(test_socket(), vec![])
+            .with_guest(Ipv4Addr::new(192, 168, 127, 7), [2, 0, 0, 0, 0, 7]);
+        assert_eq!(cfg.guest_ip, "192.168.127.7");
+        assert_eq!(cfg.guest_mac, "02:00:00:00:00:07");
+    }
+
     #[test]
     fn port_mappings() {
         let cfg = GvproxyConfig::new(test_socket(), vec![(8080, 80), (8443, 443)]);
@@ -273,6 +530,26 @@ fn port_mappings() {
         assert_eq!(cfg.port_mappings[0].guest_port, 80);
     }
 
+    #[test]
+    fn port_mapping_json_parity_with_go() {
+        let cfg = GvproxyConfig::new(test_socket(), vec![]).with_port_mappings(vec![
+            PortMapping::new(8080, 80),
+            PortMapping::new(5353, 53)
+                .with_bind_ip(IpAddr::V4(Ipv4Addr::LOCALHOST))
+                .with_protocol(PortProtocol::Udp),
+        ]);
+        let json = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(
+            json["port_mappings"],
+            serde_json::json!([
+                {"host_port": 8080, "guest_port": 80},
+                {"host_port": 5353, "guest_port": 53, "bind_ip": "127.0.0.1", "protocol": "udp"},
+            ])
+        );
+        let back: GvproxyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(back.port_mappings, cfg.port_mappings);
+    }
+
     #[test]
     fn builder_pattern() {
         let cfg = GvproxyConfig::new(test_socket(), vec![(8080, 80)])
@@ -312,6 +589,17 @@ fn empty_allow_net_and_secrets_omitted_from_json() {
         );
     }
 
+    #[test]
+    fn record_activity_only_serialized_when_enabled() {
+        let off = GvproxyConfig::new(test_socket(), vec![]);
+        let off_json = serde_json::to_string(&off).unwrap();
+        assert!(!off_json.contains("record_activity"), "{off_json}");
+
+        let on = off.with_activity(true);
+        let on_json = serde_json::to_string(&on).unwrap();
+        assert!(on_json.contains("\"record_activity\":true"), "{on_json}");
+    }
+
     #[test]
     fn allow_net_and_secrets_json_parity_with_go() {
         let ca = ca::generate().unwrap();
@@ -349,16 +637,76 @@ fn allow_net_and_secrets_json_parity_with_go() {
     }
 
     #[test]
-    fn secret_debug_redacts_value() {
-        let s = SecretConfig {
-            name: "TOKEN".into(),
-            hosts: vec!["h".into()],
-            placeholder: "p".into(),
-            value: "must-not-appear".into(),
+    fn http_policy_json_parity_with_go() {
+        let policy = HttpPolicyConfig {
+            rules: vec![HttpRuleConfig {
+                hosts: vec!["api.github.com".into()],
+                methods: vec!["GET".into()],
+                set_headers: BTreeMap::from([("X-Agent".into(), "bux".into())]),
+                ..HttpRuleConfig::default()
+            }],
+            log_requests: true,
         };
-        let dbg = format!("{s:?}");
-        assert!(dbg.contains("REDACTED"));
-        assert!(!dbg.contains("must-not-appear"));
+        let cfg = GvproxyConfig::new(test_socket(), vec![]).with_http_policy(policy.clone());
+        let json = serde_json::to_value(&cfg).unwrap();
+        let rule = &json["http_policy"]["rules"][0];
+        assert_eq!(rule["hosts"][0], "api.github.com");
+        assert_eq!(rule["set_headers"]["X-Agent"], "bux");
+        assert!(rule.get("path_prefixes").is_none());
+        assert_eq!(json["http_policy"]["log_requests"], true);
+
+        let de: GvproxyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(de.http_policy, Some(policy));
+    }
+
+    #[test]
+    fn shaping_json_parity_with_go() {
+        let off = serde_json::to_value(GvproxyConfig::new(test_socket(), vec![])).unwrap();
+        assert!(off.get("shaping").is_none());
+
+        let cfg = GvproxyConfig::new(test_socket(), vec![]).with_shaping(ShapingConfig {
+            egress_rate: Some(1_000_000),
+            ingress_quota: Some(1 << 30),
+            on_quota: OnQuota::Throttle,
+            throttle_rate: Some(1000),
+            ..ShapingConfig::default()
+        });
+        let json = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(json["shaping"]["egress_rate"], 1_000_000);
+        assert_eq!(json["shaping"]["ingress_quota"], 1_u64 << 30);
+        assert_eq!(json["shaping"]["on_quota"], "throttle");
+        assert_eq!(json["shaping"]["throttle_rate"], 1000);
+        assert!(json["shaping"].get("ingress_rate").is_none());
+
+        let de: GvproxyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(de.shaping, cfg.shaping);
+    }
+
+    #[test]
+    fn dns_zones_and_host_gateway_json_parity_with_go() {
+        let off = serde_json::to_value(GvproxyConfig::new(test_socket(), vec![])).unwrap();
+        assert!(off.get("host_ip").is_none());
+
+        let cfg = GvproxyConfig::new(test_socket(), vec![])
+            .with_dns_zones(vec![DnsZone {
+                name: "example.test.".into(),
+                records: vec![DnsRecordConfig {
+                    name: "db".into(),
+                    ip: "10.0.0.5".into(),
+                }],
+                ..DnsZone::default()
+            }])
+            .with_host_gateway();
+        let json = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(json["host_ip"], constants::HOST_IP);
+        let zone = &json["dns_zones"][0];
+        assert_eq!(zone["name"], "example.test.");
+        assert!(zone.get("default_ip").is_none());
+        assert_eq!(zone["records"][0]["name"], "db");
+        assert_eq!(zone["records"][0]["ip"], "10.0.0.5");
+
+        let de: GvproxyConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(de.dns_zones, cfg.dns_zones);
     }
 
     #[test]
@@ -381,4 +729,16 @@ fn different_sockets_produce_different_json() {
             serde_json::to_string(&b).unwrap()
         );
     }
+
+    #[test]
+    fn update_json_omits_unchanged_secrets() {
+        let update = GvproxyUpdate::new(vec!["pypi.org".into()], &[(8080, 80)]);
+        let json = serde_json::to_value(&update).unwrap();
+        assert_eq!(json["allow_net"][0], "pypi.org");
+        assert_eq!(json["port_mappings"][0]["host_port"], 8080);
+        assert!(json.get("secrets").is_none());
+
+        let rotated = serde_json::to_value(update.with_secrets(Vec::new())).unwrap();
+        assert_eq!(rotated["secrets"], serde_json::json!([]));
+    }
 }
@@ -14,7 +14,7 @@
 use std::ffi::{CStr, CString};
 use std::os::raw::{c_char, c_int, c_longlong, c_void};
 
-use crate::config::GvproxyConfig;
+use crate::config::{GvproxyConfig, GvproxyUpdate};
 use crate::error::{Error, Result};
 
 // ============================================================================
@@ -48,6 +48,18 @@
     /// or NULL on error.
     fn gvproxy_get_stats(id: c_longlong) -> *mut c_char;
 
+    /// Apply a JSON `GvproxyUpdate` to a running instance.
+    ///
+    /// Returns NULL on success, or an error message that must be freed
+    /// with [`gvproxy_free_string`].
+    fn gvproxy_update(id: c_longlong, update_json: *const c_char) -> *mut c_char;
+
+    /// Take the buffered activity events as a JSON array.
+    ///
+    /// Returns a C string that must be freed with [`gvproxy_free_string`],
+    /// or NULL for an unknown instance.
+    fn gvproxy_drain_activity(id: c_longlong) -> *mut c_char;
+
     /// Get the library version string.
     ///
     /// Returns a C string that must be freed with [`gvproxy_free_string`].
@@ -94,6 +106,26 @@ pub(crate) fn destroy_instance(id: i64) -> Result<()> {
     Ok(())
 }
 
+/// Applies a live update to an instance.
+pub(crate) fn update_instance(id: i64, update: &GvproxyUpdate) -> Result<()> {
+    let json = serde_json::to_string(update)?;
+    let c_json = CString::new(json).map_err(|e| Error::Ffi(format!("invalid update JSON: {e}")))?;
+
+    let ptr = unsafe { gvproxy_update(id, c_json.as_ptr()) };
+    if ptr.is_null() {
+        tracing::info!(id, "updated gvproxy instance via FFI");
+        return Ok(());
+    }
+
+    let message = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { gvproxy_free_string(ptr) };
+    Err(Error::Ffi(format!(
+        "gvproxy_update failed for instance {id}: {message}"
+    )))
+}
+
 /// Returns the gvproxy-bridge library version.
 pub(crate) fn get_version() -> Result<String> {
     let ptr = unsafe { gvproxy_get_version() };
@@ -128,6 +160,24 @@ pub(crate) fn get_stats_json(id: i64) -> Result<String> {
     Ok(json)
 }
 
+/// Returns the buffered activity JSON for the given instance.
+pub(crate) fn drain_activity_json(id: i64) -> Result<String> {
+    let ptr = unsafe { gvproxy_drain_activity(id) };
+    if ptr.is_null() {
+        return Err(Error::Ffi(format!(
+            "gvproxy_drain_activity returned NULL for instance {id}"
+        )));
+    }
+
+    let json = unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|e| Error::Ffi(format!("invalid UTF-8 in activity JSON: {e}")))?
+        .to_owned();
+
+    unsafe { gvproxy_free_string(ptr) };
+    Ok(json)
+}
+
 /// Registers a Rust log callback with the Go side.
 ///
 /// # Safety
@@ -11,9 +11,9 @@
 use std::path::{Path, PathBuf};
 use std::sync::Weak;
 
-use crate::config::GvproxyConfig;
+use crate::config::{GvproxyConfig, GvproxyUpdate};
 use crate::error::{Error, Result};
-use crate::{ffi, logging, stats::NetworkStats};
+use crate::{activity::ActivityEvent, ffi, logging, stats::NetworkStats};
 
 /// Safe, RAII wrapper around a gvproxy (gvisor-tap-vsock) instance.
 ///
@@ -67,6 +67,20 @@ pub const fn id(&self) -> i64 {
         self.id
     }
 
+    /// Applies allow-list, port-forward and secret changes in place.
+    ///
+    /// New connections see the change immediately; established ones are
+    /// untouched. See [`GvproxyUpdate`] for what cannot be undone live.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Ffi`] with the Go-side reason if the instance is
+    /// unknown or not yet serving, a port cannot be bound, or the update
+    /// is not allowed (clearing `allow_net`, secrets without a CA).
+    pub fn update(&self, update: &GvproxyUpdate) -> Result<()> {
+        ffi::update_instance(self.id, update)
+    }
+
     /// Fetches live network statistics from the Go side.
     ///
     /// # Errors
@@ -82,6 +96,25 @@ pub fn get_stats(&self) -> Result<NetworkStats> {
             ))
         })
     }
+
+    /// Takes the activity recorded since the last call.
+    ///
+    /// Empty unless the instance was created with
+    /// [`GvproxyConfig::with_activity`]. The Go side keeps the most recent
+    /// 4096 events between calls.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Ffi`] if the instance is unknown or the JSON
+    /// cannot be parsed.
+    pub fn drain_activity(&self) -> Result<Vec<ActivityEvent>> {
+        let json = ffi::drain_activity_json(self.id)?;
+        ActivityEvent::from_json_array(&json).map_err(|e| {
+            Error::Ffi(format!(
+                "failed to parse activity JSON from gvproxy: {e} (raw: {json})"
+            ))
+        })
+    }
 }
 
 impl Drop for GvproxyInstance {
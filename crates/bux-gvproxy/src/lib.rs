@@ -8,8 +8,11 @@
 //! - [`GvproxyConfig`] — the JSON payload the Go side consumes (topology,
 //!   `allow_net`, secrets, CA PEMs).
 //! - [`ca`] — ephemeral MITM CA minting (`rcgen`).
-//! - [`GvproxyInstance`] — RAII handle owning the Go-side resources.
+//! - [`GvproxyInstance`] — RAII handle owning the Go-side resources;
+//!   [`GvproxyUpdate`] changes allow-list, ports and secrets in place.
 //! - [`NetworkStats`] / [`TcpStats`] — live counters decoded from JSON.
+//! - [`ActivityEvent`] — opt-in per-connection activity (connects,
+//!   closes with byte counts, egress denials).
 //! - [`init_logging`] — Go `slog` → Rust `tracing` bridge (idempotent).
 //!
 //! Higher layers (`bux-net` and callers) wire the `GvproxyInstance`
@@ -34,21 +37,27 @@
 //! # Ok::<(), bux_gvproxy::Error>(())
 //! ```
 
+pub mod activity;
 pub mod ca;
 pub mod config;
-pub mod constants;
 mod error;
 mod ffi;
 mod instance;
 mod logging;
 pub mod stats;
 
+pub use activity::{ActivityEvent, ActivityKind};
+/// Topology constants, shared with `bux-net` through `bux-net-types`.
+pub use bux_net_types::constants;
 pub use ca::{MitmCa, generate as generate_mitm_ca};
-pub use config::{DnsZone, GvproxyConfig, PortMapping, SecretConfig};
+pub use config::{
+    DnsRecordConfig, DnsZone, GvproxyConfig, GvproxyUpdate, HttpPolicyConfig, HttpRuleConfig,
+    OnQuota, PortMapping, PortProtocol, SecretConfig, ShapingConfig,
+};
 pub use error::{Error, Result};
 pub use instance::{GvproxyInstance, start_stats_logging};
 pub use logging::init as init_logging;
-pub use stats::{NetworkStats, TcpStats};
+pub use stats::{NetworkStats, ShapingStats, TcpStats};
 
 /// Returns the `libgvproxy` c-archive version string.
 ///
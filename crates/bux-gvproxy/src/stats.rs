@@ -20,6 +20,18 @@ pub struct NetworkStats {
     /// TCP-layer statistics.
     #[serde(rename = "TCP")]
     pub tcp: TcpStats,
+
+    /// Rate-limit and quota state (added by the bridge, not upstream).
+    #[serde(rename = "Shaping", default)]
+    pub shaping: ShapingStats,
+}
+
+/// Shaping state reported alongside the upstream counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShapingStats {
+    /// Whether a transfer quota has been used up.
+    #[serde(rename = "QuotaExceeded", default)]
+    pub quota_exceeded: bool,
 }
 
 /// TCP-specific counters.
@@ -83,6 +95,15 @@ fn deserialize() {
         assert_eq!(stats.bytes_received, 2048);
         assert_eq!(stats.tcp.forward_max_inflight_drop, 100);
         assert_eq!(stats.tcp.current_established, 5);
+        assert!(!stats.shaping.quota_exceeded);
+
+        let shaped = json.replacen('{', r#"{"Shaping": {"QuotaExceeded": true},"#, 1);
+        assert!(
+            NetworkStats::from_json_str(&shaped)
+                .unwrap()
+                .shaping
+                .quota_exceeded
+        );
     }
 
     #[test]
@@ -102,6 +123,7 @@ fn clone_eq() {
                 retransmits: 0,
                 timeouts: 0,
             },
+            shaping: ShapingStats::default(),
         };
         assert_eq!(stats, stats.clone());
     }
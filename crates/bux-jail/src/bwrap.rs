@@ -2,31 +2,74 @@
 //!
 //! Wraps the shim binary with namespace isolation: new PID/IPC/UTS/mount
 //! namespaces, read-only `/` bind, and selective writable mounts for
-//! rootfs, sockets, and virtiofs paths.
+//! rootfs, sockets, and virtiofs paths. Every capability is dropped, a
+//! fresh `/proc` is mounted, and kernel interfaces the VMM never needs
+//! are masked. User and network namespaces are opt-in via
+//! [`NamespaceOptions`].
 
 use std::path::Path;
 use std::process::Command;
 
 use bux_bwrap::{BwrapCommand, Namespace};
 
-use super::{JailConfig, Sandbox, SandboxCapabilities, SandboxKind};
+use super::{JailConfig, NamespaceOptions, Sandbox, SandboxCapabilities, SandboxKind};
+
+/// Files hidden behind `/dev/null` (kernel memory, keyrings, timer and
+/// scheduler internals that leak host activity).
+const MASKED_FILES: &[&str] = &[
+    "/proc/kcore",
+    "/proc/keys",
+    "/proc/latency_stats",
+    "/proc/timer_list",
+    "/proc/timer_stats",
+    "/proc/sched_debug",
+];
+
+/// Directories hidden behind an empty tmpfs.
+const MASKED_DIRS: &[&str] = &[
+    "/proc/acpi",
+    "/proc/asound",
+    "/proc/scsi",
+    "/sys/firmware",
+    "/sys/devices/virtual/powercap",
+];
+
+/// Kernel tunables left visible but read-only, bound onto themselves
+/// because `--remount-ro` only accepts mount points.
+const READONLY_PATHS: &[&str] = &[
+    "/proc/bus",
+    "/proc/fs",
+    "/proc/irq",
+    "/proc/sys",
+    "/proc/sysrq-trigger",
+];
 
 /// Bubblewrap (bwrap) sandbox for Linux.
 ///
-/// Provides namespace isolation (PID/IPC/UTS/mount), a read-only root
-/// bind, and selective writable mounts for VM resources.
+/// Provides namespace isolation (PID/IPC/UTS/mount, plus user and network
+/// when requested), a read-only root bind, and selective writable mounts
+/// for VM resources.
 #[derive(Debug, Clone, Copy, Default)]
-pub(crate) struct BwrapSandbox;
+pub(crate) struct BwrapSandbox {
+    /// Optional namespaces to unshare on top of the defaults.
+    namespaces: NamespaceOptions,
+}
+
+impl BwrapSandbox {
+    /// Sandbox unsharing `namespaces` in addition to PID/IPC/UTS/mount.
+    pub(crate) const fn new(namespaces: NamespaceOptions) -> Self {
+        Self { namespaces }
+    }
+}
 
 impl Sandbox for BwrapSandbox {
     fn wrap(&self, shim: &Path, config_path: &Path, jail: &JailConfig) -> Option<Command> {
-        let mut builder = BwrapCommand::new()
-            .ok()?
-            .unshare([Namespace::Pid, Namespace::Ipc, Namespace::Uts])
+        let mut builder = isolate(BwrapCommand::new().ok()?, self.namespaces, mapped_ids(jail))
             .die_with_parent()
             .ro_bind("/", "/")
             .tmpfs("/tmp")
             .tmpfs("/dev/shm");
+        builder = mask(builder, |p| Path::new(p).exists());
 
         if Path::new("/dev/kvm").exists() {
             builder = builder.dev_bind("/dev/kvm", "/dev/kvm");
@@ -54,6 +97,10 @@ fn capabilities(&self) -> SandboxCapabilities {
             seccomp: false,
             mandatory_access_control: false,
             cgroups: false,
+            user_namespace: self.namespaces.user,
+            network_namespace: self.namespaces.network,
+            capabilities_dropped: true,
+            masked_paths: true,
         }
     }
 
@@ -61,3 +108,142 @@ fn kind(&self) -> SandboxKind {
         SandboxKind::Bwrap
     }
 }
+
+/// Unshare namespaces and drop capabilities. With a user namespace the
+/// shim's `(uid, gid)` is mapped to itself so file ownership on bound
+/// paths is unchanged.
+fn isolate(builder: BwrapCommand, ns: NamespaceOptions, (uid, gid): (u32, u32)) -> BwrapCommand {
+    let mut builder = builder.unshare([Namespace::Pid, Namespace::Ipc, Namespace::Uts]);
+    if ns.user {
+        builder = builder.unshare([Namespace::User]).uid(uid).gid(gid);
+    }
+    if ns.network {
+        builder = builder.unshare([Namespace::Net]);
+    }
+    builder.cap_drop("ALL")
+}
+
+/// Mount a fresh `/proc` and mask the sensitive paths for which `exists`
+/// holds. Must follow the `/` bind so the masks land on top of it.
+fn mask(builder: BwrapCommand, exists: impl Fn(&str) -> bool) -> BwrapCommand {
+    let mut builder = builder.proc("/proc");
+    for &file in MASKED_FILES.iter().filter(|p| exists(p)) {
+        builder = builder.ro_bind("/dev/null", file);
+    }
+    for &dir in MASKED_DIRS.iter().filter(|p| exists(p)) {
+        builder = builder.tmpfs(dir);
+    }
+    for &path in READONLY_PATHS.iter().filter(|p| exists(p)) {
+        builder = builder.ro_bind(path, path);
+    }
+    builder
+}
+
+/// Ids the shim runs as: the jail's target credentials, else the real uid
+/// and gid of this process.
+#[allow(unsafe_code, reason = "getuid/getgid are libc calls")]
+fn mapped_ids(jail: &JailConfig) -> (u32, u32) {
+    let creds = jail.credentials.clone().unwrap_or_default();
+    // SAFETY: getuid cannot fail and has no preconditions.
+    let uid = creds.uid.unwrap_or_else(|| unsafe { libc::getuid() });
+    // SAFETY: getgid cannot fail and has no preconditions.
+    let gid = creds.gid.unwrap_or_else(|| unsafe { libc::getgid() });
+    (uid, gid)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to omit docs"
+)]
+mod tests {
+    use super::*;
+
+    fn args(builder: BwrapCommand) -> Vec<String> {
+        builder
+            .program("/bin/true")
+            .into_command()
+            .get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn default_profile_keeps_host_user_and_network() {
+        let a = args(isolate(
+            BwrapCommand::with_binary("bwrap"),
+            NamespaceOptions::new(),
+            (1000, 1000),
+        ));
+        assert!(a.contains(&"--unshare-pid".to_owned()));
+        assert!(!a.contains(&"--unshare-user".to_owned()));
+        assert!(!a.contains(&"--unshare-net".to_owned()));
+        assert!(a.windows(2).any(|w| w == ["--cap-drop", "ALL"]));
+    }
+
+    #[test]
+    fn user_namespace_maps_invoking_ids() {
+        let ns = NamespaceOptions::new().user(true).network(true);
+        let a = args(isolate(BwrapCommand::with_binary("bwrap"), ns, (1000, 100)));
+        assert!(a.contains(&"--unshare-net".to_owned()));
+        let user = a
+            .iter()
+            .position(|s| s == "--unshare-user")
+            .unwrap_or(usize::MAX);
+        assert_eq!(
+            a.get(user + 1..user + 5).map(<[String]>::to_vec),
+            Some(vec![
+                "--uid".into(),
+                "1000".into(),
+                "--gid".into(),
+                "100".into()
+            ])
+        );
+    }
+
+    #[test]
+    fn masks_only_existing_paths_after_fresh_proc() {
+        let a = args(mask(BwrapCommand::with_binary("bwrap"), |p| {
+            p != "/proc/timer_stats" && p != "/proc/asound"
+        }));
+        assert_eq!(
+            a.get(..2),
+            Some(&["--proc".to_owned(), "/proc".to_owned()][..])
+        );
+        assert!(
+            a.windows(3)
+                .any(|w| w == ["--ro-bind", "/dev/null", "/proc/kcore"])
+        );
+        assert!(a.windows(2).any(|w| w == ["--tmpfs", "/sys/firmware"]));
+        assert!(
+            a.windows(3)
+                .any(|w| w == ["--ro-bind", "/proc/sys", "/proc/sys"])
+        );
+        assert!(!a.contains(&"--remount-ro".to_owned()));
+        assert!(
+            !a.iter()
+                .any(|s| s == "/proc/timer_stats" || s == "/proc/asound")
+        );
+    }
+
+    #[test]
+    #[ignore = "runs bwrap: needs it on $PATH and unprivileged namespaces"]
+    fn generated_mounts_run_true() {
+        let builder = isolate(
+            BwrapCommand::new().unwrap(),
+            NamespaceOptions::new(),
+            (0, 0),
+        )
+        .die_with_parent()
+        .ro_bind("/", "/")
+        .tmpfs("/tmp")
+        .tmpfs("/dev/shm");
+        let status = mask(builder, |p| Path::new(p).exists())
+            .program("/bin/true")
+            .into_command()
+            .status()
+            .unwrap();
+        assert!(status.success(), "bwrap exited with {status}");
+    }
+}
@@ -17,6 +17,8 @@ pub struct HostCapabilities {
     pub virtualization: bool,
     /// Whether namespace isolation is available (Linux bubblewrap).
     pub namespaces: bool,
+    /// Whether unprivileged user namespaces may be created (Linux).
+    pub user_namespaces: bool,
     /// Whether seccomp BPF filtering is available (Linux only).
     pub seccomp: bool,
     /// Whether mandatory access control is available (AppArmor/SELinux/Seatbelt).
@@ -36,6 +38,7 @@ pub fn check_host() -> HostCapabilities {
     HostCapabilities {
         virtualization: check_virtualization(),
         namespaces: check_namespaces(),
+        user_namespaces: check_user_namespaces(),
         seccomp: check_seccomp(),
         mandatory_access_control: check_mac(),
         cgroups: check_cgroups(),
@@ -59,6 +62,12 @@ pub fn audit_isolation(caps: &HostCapabilities) -> Vec<String> {
                 .to_owned(),
         );
     }
+    if caps.namespaces && !caps.user_namespaces {
+        warnings.push(
+            "unprivileged user namespaces disabled — SecurityOptions.user_namespace cannot be enforced"
+                .to_owned(),
+        );
+    }
     if !caps.seccomp {
         warnings.push("seccomp BPF not available — shim runs without syscall filtering".to_owned());
     }
@@ -79,6 +88,22 @@ pub fn audit_isolation(caps: &HostCapabilities) -> Vec<String> {
     warnings
 }
 
+/// The running kernel's Landlock ABI version.
+///
+/// [`LandlockAbi::UNSUPPORTED`](crate::LandlockAbi::UNSUPPORTED) when
+/// Landlock is missing, disabled, or the host is not Linux.
+#[must_use]
+pub fn landlock_abi() -> crate::LandlockAbi {
+    bux_landlock::abi_version()
+}
+
+/// The `bwrap` binary the Linux sandbox would run, if one is installed.
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn bwrap_path() -> Option<&'static Path> {
+    bux_bwrap::path()
+}
+
 /// Checks if the guest binary at `path` is a valid static ELF for the host arch.
 ///
 /// Returns `Ok(())` if the binary passes all checks, or an error describing
@@ -148,6 +173,27 @@ fn check_namespaces() -> bool {
     }
 }
 
+/// Checks whether unprivileged user namespaces are enabled: a non-zero
+/// `user.max_user_namespaces` and, on kernels carrying the Debian patch,
+/// `kernel.unprivileged_userns_clone`.
+#[allow(clippy::missing_const_for_fn, reason = "body is non-const on Linux")]
+fn check_user_namespaces() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let sysctl = |path: &str| {
+            std::fs::read_to_string(path)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+        };
+        sysctl("/proc/sys/user/max_user_namespaces").is_some_and(|n| n > 0)
+            && sysctl("/proc/sys/kernel/unprivileged_userns_clone") != Some(0)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
 /// Checks whether seccomp BPF syscall filtering is available.
 #[allow(clippy::missing_const_for_fn, reason = "body is non-const on Linux")]
 fn check_seccomp() -> bool {
@@ -180,12 +226,13 @@ fn check_mac() -> bool {
     }
 }
 
-/// Checks whether cgroup v2 resource limits are available.
+/// Checks whether cgroup v2 resource limits are available: the
+/// hierarchy is mounted and this process may manage a subtree of it.
 #[allow(clippy::missing_const_for_fn, reason = "body is non-const on Linux")]
 fn check_cgroups() -> bool {
     #[cfg(target_os = "linux")]
     {
-        Path::new("/sys/fs/cgroup/cgroup.controllers").exists()
+        bux_cgroup::Placement::detect().is_some()
     }
     #[cfg(not(target_os = "linux"))]
     {
@@ -239,6 +286,7 @@ fn audit_reports_missing_features() {
         let caps = HostCapabilities {
             virtualization: true,
             namespaces: false,
+            user_namespaces: false,
             seccomp: false,
             mandatory_access_control: false,
             cgroups: false,
@@ -250,6 +298,28 @@ fn audit_reports_missing_features() {
         assert!(warnings.iter().any(|w| w.contains("Landlock")));
     }
 
+    #[test]
+    fn audit_flags_disabled_user_namespaces_only_with_bwrap() {
+        let mut caps = HostCapabilities {
+            virtualization: true,
+            namespaces: true,
+            user_namespaces: false,
+            seccomp: true,
+            mandatory_access_control: true,
+            cgroups: true,
+            landlock: true,
+        };
+        let warnings = audit_isolation(&caps);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings.iter().all(|w| w.contains("user namespaces")));
+        caps.namespaces = false;
+        assert!(
+            !audit_isolation(&caps)
+                .iter()
+                .any(|w| w.contains("user namespaces"))
+        );
+    }
+
     #[test]
     fn guest_binary_check_rejects_missing() {
         let result = check_guest_binary(Path::new("/nonexistent/path"));
@@ -7,12 +7,16 @@
 //!
 //! # Typical usage
 //!
-//! Called in the shim's `pre_exec` hook, after sandbox setup but before
-//! libkrun takes over:
+//! Called in the shim's `pre_exec` hook (see [`crate::JailConfig::credentials`]),
+//! before Landlock and the sandbox exec:
 //!
 //! ```text
-//! fork() → bwrap namespace setup → credentials::drop_privileges() → libkrun start
+//! fork() → credentials::drop_privileges() → Landlock → exec bwrap → bux-shim
 //! ```
+//!
+//! The files the shim creates access to must be owned by the target uid
+//! first; anything else it reaches through
+//! [`CredentialConfig::supplementary_gids`].
 
 #![cfg(target_os = "linux")]
 #![allow(
@@ -30,13 +34,16 @@
 use std::io;
 
 /// Configuration for credential reduction.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub struct CredentialConfig {
     /// Target UID to switch to. `None` keeps the current UID.
     pub uid: Option<u32>,
     /// Target GID to switch to. `None` keeps the current GID.
     pub gid: Option<u32>,
+    /// Supplementary groups kept after the switch (e.g. the group owning
+    /// `/dev/kvm`). Empty clears all supplementary groups.
+    pub supplementary_gids: Vec<u32>,
     /// Whether to clear all Linux capabilities after switching.
     pub drop_caps: bool,
 }
@@ -46,15 +53,41 @@ fn default() -> Self {
         Self {
             uid: None,
             gid: None,
+            supplementary_gids: Vec::new(),
+            drop_caps: true,
+        }
+    }
+}
+
+impl CredentialConfig {
+    /// Switch to `uid`/`gid` and drop every capability.
+    #[must_use]
+    pub const fn new(uid: u32, gid: u32) -> Self {
+        Self {
+            uid: Some(uid),
+            gid: Some(gid),
+            supplementary_gids: Vec::new(),
             drop_caps: true,
         }
     }
+
+    /// Keep `gids` as the supplementary groups (duplicates dropped).
+    #[must_use]
+    pub fn supplementary_gids(mut self, gids: impl IntoIterator<Item = u32>) -> Self {
+        for gid in gids {
+            if !self.supplementary_gids.contains(&gid) {
+                self.supplementary_gids.push(gid);
+            }
+        }
+        self
+    }
 }
 
 /// Drops privileges according to the given configuration.
 ///
 /// Order of operations (security-critical):
-/// 1. Set supplementary groups to empty (if switching GID).
+/// 1. Set supplementary groups to [`CredentialConfig::supplementary_gids`]
+///    (if switching GID).
 /// 2. Switch GID (must happen before UID drop on some kernels).
 /// 3. Switch UID.
 /// 4. Clear all capabilities (if `drop_caps` is true).
@@ -66,13 +99,14 @@ fn default() -> Self {
 /// case, the process is in a partially-reduced state and should be
 /// terminated.
 pub fn drop_privileges(config: &CredentialConfig) -> io::Result<()> {
-    // 1. Clear supplementary groups.
+    // 1. Replace supplementary groups (the Vec was built before fork).
     if config.gid.is_some() {
+        let groups = &config.supplementary_gids;
         #[allow(
             unsafe_code,
             reason = "credential manipulation requires raw libc calls"
         )]
-        let rc = unsafe { libc::setgroups(0, std::ptr::null()) };
+        let rc = unsafe { libc::setgroups(groups.len(), groups.as_ptr()) };
         if rc != 0 {
             return Err(io::Error::last_os_error());
         }
@@ -140,7 +174,7 @@ struct CapData {
     }
 
     // _LINUX_CAPABILITY_VERSION_3 — supports 64 capabilities (2 data structs).
-    const CAP_V3: u32 = 0x2008_0026;
+    const CAP_V3: u32 = 0x2008_0522;
 
     let header = CapHeader {
         version: CAP_V3,
@@ -179,6 +213,10 @@ fn default_config_drops_caps() {
         assert!(config.drop_caps);
         assert!(config.uid.is_none());
         assert!(config.gid.is_none());
+        let dedicated = CredentialConfig::new(4000, 4000).supplementary_gids([36, 100, 36]);
+        assert_eq!((dedicated.uid, dedicated.gid), (Some(4000), Some(4000)));
+        assert_eq!(dedicated.supplementary_gids, [36, 100]);
+        assert!(dedicated.drop_caps);
     }
 
     #[test]
@@ -2,11 +2,23 @@
 
 use std::path::{Path, PathBuf};
 
-use bux_landlock::PathRestrictions;
+use bux_landlock::{Enforcement, FsAccess, PathRestrictions};
 
 use super::JailConfig;
 
-/// Build a Landlock ruleset fd for the jail paths, or `None` if the kernel has no Landlock.
+/// Rights on `/dev`: open existing nodes and `ioctl` them (KVM, tun),
+/// but never create or remove nodes. `/dev/shm` is granted separately.
+const DEV_ACCESS: FsAccess = FsAccess::READ_FILE
+    .union(FsAccess::READ_DIR)
+    .union(FsAccess::WRITE_FILE)
+    .union(FsAccess::TRUNCATE)
+    .union(FsAccess::IOCTL_DEV);
+
+/// Rights on `/proc`: read only, no execute.
+const PROC_ACCESS: FsAccess = FsAccess::READ_FILE.union(FsAccess::READ_DIR);
+
+/// Build a Landlock ruleset fd for the jail paths plus what it enforces,
+/// or `None` if the kernel has no Landlock.
 ///
 /// # Errors
 ///
@@ -15,14 +27,23 @@ pub(crate) fn build_fd(
     jail: &JailConfig,
     shim: &Path,
     config_path: &Path,
-) -> Result<Option<std::os::fd::RawFd>, String> {
+) -> Result<Option<(std::os::fd::RawFd, Enforcement)>, String> {
     let restrictions = path_restrictions(jail, shim, config_path);
-    restrictions.build().map_err(|e| e.to_string())
+    restrictions.build_with_report().map_err(|e| e.to_string())
 }
 
 /// Assemble allow-lists matching bwrap binds + paths the shim needs.
 fn path_restrictions(jail: &JailConfig, shim: &Path, config_path: &Path) -> PathRestrictions {
-    let mut r = PathRestrictions::new();
+    // The shim talks to the runtime and net backend over path sockets
+    // only and never signals outside its own process tree.
+    let mut r = PathRestrictions::new()
+        .scope_abstract_unix_sockets()
+        .scope_signals();
+    // Without host networking (guest traffic flows through a socket
+    // backend) the VMM needs no TCP either.
+    if jail.namespaces.network {
+        r = r.deny_network();
+    }
 
     // System trees the shim / bwrap / libkrun need (exist-only).
     for p in [
@@ -44,7 +65,10 @@ fn path_restrictions(jail: &JailConfig, shim: &Path, config_path: &Path) -> Path
 
     // Device nodes (KVM, null, urandom).
     if Path::new("/dev").exists() {
-        r = r.allow_read_write("/dev");
+        r = r.allow("/dev", DEV_ACCESS);
+    }
+    if Path::new("/dev/shm").exists() {
+        r = r.allow_read_write("/dev/shm");
     }
     if Path::new("/tmp").exists() {
         r = r.allow_read_write("/tmp");
@@ -54,7 +78,7 @@ fn path_restrictions(jail: &JailConfig, shim: &Path, config_path: &Path) -> Path
     }
     // proc is needed by some runtimes; landlock may not cover /proc on all ABIs.
     if Path::new("/proc").exists() {
-        r = r.allow_read("/proc");
+        r = r.allow("/proc", PROC_ACCESS);
     }
 
     // Shim binary + sibling dylibs (libkrun).
@@ -124,6 +148,8 @@ fn restrictions_include_socks_dir() {
             stderr_file: None,
             landlock: true,
             allow_degraded_security: false,
+            namespaces: crate::NamespaceOptions::new().network(true),
+            credentials: None,
         };
         let r = path_restrictions(
             &jail,
@@ -135,5 +161,12 @@ fn restrictions_include_socks_dir() {
                 .iter()
                 .any(|p| p == Path::new("/tmp/bux-socks"))
         );
+        assert!(r.network_denied());
+        assert!(r.abstract_unix_sockets_scoped() && r.signals_scoped());
+        assert!(
+            r.path_rules()
+                .iter()
+                .all(|rule| !rule.access.contains(FsAccess::MAKE_CHAR))
+        );
     }
 }
@@ -30,6 +30,7 @@
 use std::process::{Child, Command, Stdio};
 
 pub use bux_cgroup::ResourceLimits;
+pub use bux_landlock::{AbiVersion as LandlockAbi, Enforcement as LandlockEnforcement, FsAccess};
 pub use error::{Error, Result};
 pub use security::{LayerStatus, SandboxKind, SecurityReport};
 
@@ -45,7 +46,7 @@
 pub const ENV_WATCHDOG_FD: &str = "BUX_WATCHDOG_FD";
 
 /// Describes the isolation features provided by a [`Sandbox`] implementation.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 #[non_exhaustive]
 #[allow(clippy::struct_excessive_bools, reason = "capability flags struct")]
 pub struct SandboxCapabilities {
@@ -57,6 +58,55 @@ pub struct SandboxCapabilities {
     pub mandatory_access_control: bool,
     /// Whether cgroup-based resource limits are enforced.
     pub cgroups: bool,
+    /// Whether the shim runs in its own user namespace.
+    pub user_namespace: bool,
+    /// Whether the shim runs in its own network namespace (no host network).
+    pub network_namespace: bool,
+    /// Whether every Linux capability is dropped.
+    pub capabilities_dropped: bool,
+    /// Whether sensitive `/proc` and `/sys` paths are masked.
+    pub masked_paths: bool,
+}
+
+/// Optional namespaces the platform sandbox unshares (Linux bwrap only).
+///
+/// PID, IPC, UTS and mount namespaces are always unshared; these add
+/// isolation that constrains what the shim may do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NamespaceOptions {
+    /// New user namespace mapping the invoking uid/gid to itself.
+    pub user: bool,
+    /// New network namespace with only a loopback interface. Safe only
+    /// when the guest network is a socket-based backend (gvproxy or the
+    /// userspace stack) the shim reaches through a bound Unix socket; TSI
+    /// needs the host network.
+    pub network: bool,
+}
+
+impl NamespaceOptions {
+    /// No optional namespaces.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            user: false,
+            network: false,
+        }
+    }
+
+    /// Unshare the user namespace.
+    #[must_use]
+    pub const fn user(mut self, enabled: bool) -> Self {
+        self.user = enabled;
+        self
+    }
+
+    /// Unshare the network namespace.
+    #[must_use]
+    pub const fn network(mut self, enabled: bool) -> Self {
+        self.network = enabled;
+        self
+    }
 }
 
 /// Trait for platform-specific process sandboxing.
@@ -137,6 +187,16 @@ pub struct JailConfig {
     pub landlock: bool,
     /// If true, missing Landlock (when requested) degrades instead of failing.
     pub allow_degraded_security: bool,
+    /// Optional namespaces for the platform sandbox (Linux bwrap).
+    pub namespaces: NamespaceOptions,
+    /// Drop to these credentials before exec'ing the sandbox (Linux).
+    ///
+    /// The caller owns the ids and must hand the shim's own files over to
+    /// them first; shared paths stay with their owners and are reached
+    /// through [`credentials::CredentialConfig::supplementary_gids`]. With
+    /// a user namespace the target ids are mapped to themselves.
+    #[cfg(target_os = "linux")]
+    pub credentials: Option<credentials::CredentialConfig>,
 }
 
 /// Result of spawning a shim process inside a sandbox.
@@ -158,8 +218,8 @@ pub struct SpawnResult {
 
 /// Spawn `bux-shim` inside a sandbox.
 ///
-/// Applies platform-specific isolation, Landlock (when requested), then
-/// pre-exec hardening (FD cleanup, die-with-parent).
+/// Applies platform-specific isolation, the credential drop and Landlock
+/// (when requested), then pre-exec hardening (FD cleanup, die-with-parent).
 ///
 /// # Errors
 ///
@@ -173,8 +233,9 @@ pub fn spawn(
     config: JailConfig,
     vm_id: &str,
 ) -> Result<SpawnResult> {
-    let (landlock_fd, landlock_status) = prepare_landlock(&config, shim, config_path)?;
-    let (mut cmd, sandbox_kind) = build_command(shim, config_path, &config);
+    let (landlock_fd, landlock_status, landlock_enforcement) =
+        prepare_landlock(&config, shim, config_path)?;
+    let (mut cmd, sandbox_kind, capabilities) = build_command(shim, config_path, &config);
     cmd.stdin(Stdio::null());
 
     let watchdog_fd = config.watchdog_fd;
@@ -193,19 +254,29 @@ pub fn spawn(
             watchdog: watchdog_fd,
             landlock: landlock_fd,
         },
+        #[cfg(target_os = "linux")]
+        config.credentials,
     );
-    let child = cmd.spawn()?;
+
+    // Create the cgroup first so a failure leaves no orphaned child.
+    #[cfg(target_os = "linux")]
+    let cgroup_guard = resource_limits
+        .as_ref()
+        .map(|limits| bux_cgroup::create(vm_id, limits))
+        .transpose()?;
+
+    #[cfg_attr(not(target_os = "linux"), allow(unused_mut))]
+    let mut child = cmd.spawn()?;
 
     #[cfg(target_os = "linux")]
-    let cgroup_guard = if let Some(ref limits) = resource_limits {
-        let guard = bux_cgroup::create(vm_id, limits)?;
+    if let Some(ref guard) = cgroup_guard {
         #[allow(clippy::cast_possible_wrap, reason = "PID fits in i32")]
-        bux_cgroup::add_pid(&guard, child.id() as i32)?;
-        Some(guard)
-    } else {
-        let _ = vm_id;
-        None
-    };
+        if let Err(e) = bux_cgroup::add_pid(guard, child.id() as i32) {
+            drop(child.kill());
+            drop(child.wait());
+            return Err(e.into());
+        }
+    }
 
     #[cfg(not(target_os = "linux"))]
     let _ = (vm_id, resource_limits);
@@ -228,12 +299,14 @@ pub fn spawn(
         security: SecurityReport {
             sandbox: sandbox_kind,
             landlock: landlock_status,
+            landlock_enforcement,
             mac,
+            capabilities,
         },
     })
 }
 
-/// Resolve Landlock fd + status (K22).
+/// Resolve Landlock fd + status + negotiated enforcement (K22).
 ///
 /// Returns [`Error::LandlockUnavailable`] / [`Error::Landlock`] on Linux when
 /// Landlock is requested and cannot be enforced (unless degraded is allowed).
@@ -246,7 +319,11 @@ fn prepare_landlock(
     config: &JailConfig,
     shim: &Path,
     config_path: &Path,
-) -> Result<(Option<RawFd>, LayerStatus)> {
+) -> Result<(
+    Option<RawFd>,
+    LayerStatus,
+    Option<bux_landlock::Enforcement>,
+)> {
     if !config.landlock {
         return Ok((
             None,
@@ -255,16 +332,17 @@ fn prepare_landlock(
             } else {
                 LayerStatus::NotApplicable
             },
+            None,
         ));
     }
 
     #[cfg(target_os = "linux")]
     {
         match landlock_setup::build_fd(config, shim, config_path) {
-            Ok(Some(fd)) => Ok((Some(fd), LayerStatus::Enforced)),
+            Ok(Some((fd, enforcement))) => Ok((Some(fd), LayerStatus::Enforced, Some(enforcement))),
             Ok(None) => {
                 if config.allow_degraded_security {
-                    Ok((None, LayerStatus::Degraded))
+                    Ok((None, LayerStatus::Degraded, None))
                 } else {
                     Err(Error::LandlockUnavailable)
                 }
@@ -277,28 +355,32 @@ fn prepare_landlock(
     {
         let _ = (shim, config_path);
         // Requested on non-Linux: treat as not applicable (no fail).
-        Ok((None, LayerStatus::NotApplicable))
+        Ok((None, LayerStatus::NotApplicable, None))
     }
 }
 
 /// Build the sandboxed `Command` using the configured (or auto-detected) sandbox.
-fn build_command(shim: &Path, config_path: &Path, config: &JailConfig) -> (Command, SandboxKind) {
+fn build_command(
+    shim: &Path,
+    config_path: &Path,
+    config: &JailConfig,
+) -> (Command, SandboxKind, SandboxCapabilities) {
     // Use explicit sandbox override if provided.
     if let Some(ref sandbox) = config.sandbox
         && let Some(cmd) = sandbox.wrap(shim, config_path, config)
     {
-        return (cmd, sandbox.kind());
+        return (cmd, sandbox.kind(), sandbox.capabilities());
     }
 
     // Auto-detect platform sandbox.
-    if let Some((cmd, kind)) = platform_sandbox(shim, config_path, config) {
-        return (cmd, kind);
+    if let Some(found) = platform_sandbox(shim, config_path, config) {
+        return found;
     }
 
     // Ultimate fallback: noop.
     let mut cmd = Command::new(shim);
     cmd.arg(config_path);
-    (cmd, SandboxKind::Noop)
+    (cmd, SandboxKind::Noop, SandboxCapabilities::default())
 }
 
 /// Try the platform-native sandbox.
@@ -306,12 +388,12 @@ fn platform_sandbox(
     shim: &Path,
     config_path: &Path,
     config: &JailConfig,
-) -> Option<(Command, SandboxKind)> {
+) -> Option<(Command, SandboxKind, SandboxCapabilities)> {
     #[cfg(target_os = "linux")]
     {
-        let sandbox = BwrapSandbox;
+        let sandbox = BwrapSandbox::new(config.namespaces);
         if let Some(cmd) = sandbox.wrap(shim, config_path, config) {
-            return Some((cmd, SandboxKind::Bwrap));
+            return Some((cmd, SandboxKind::Bwrap, sandbox.capabilities()));
         }
     }
 
@@ -319,7 +401,7 @@ fn platform_sandbox(
     {
         let sandbox = SeatbeltSandbox;
         if let Some(cmd) = sandbox.wrap(shim, config_path, config) {
-            return Some((cmd, SandboxKind::Seatbelt));
+            return Some((cmd, SandboxKind::Seatbelt, sandbox.capabilities()));
         }
     }
 
@@ -1,9 +1,11 @@
 //! Pre-exec hardening for child processes.
 //!
 //! Applied after `fork()` but before `exec()`:
-//! 1. **Landlock** (Linux, optional) — apply ruleset then close its fd.
-//! 2. **Die with parent** — `PR_SET_PDEATHSIG(SIGKILL)` (Linux).
-//! 3. **FD cleanup** — close inherited FDs ≥ 3 except preserved ones.
+//! 1. **Credentials** (Linux, optional) — switch uid/gid and drop capabilities.
+//! 2. **Landlock** (Linux, optional) — apply ruleset then close its fd.
+//! 3. **Die with parent** — `PR_SET_PDEATHSIG(SIGKILL)` (Linux). Set after
+//!    the credential switch, which clears it.
+//! 4. **FD cleanup** — close inherited FDs ≥ 3 except preserved ones.
 
 #![allow(
     unsafe_code,
@@ -28,13 +30,23 @@ pub fn apply(_cmd: &mut Command, _preserve: PreserveFds) {}
 
 /// Install pre-exec hooks on the command.
 #[cfg(unix)]
-pub(crate) fn apply(cmd: &mut Command, preserve: PreserveFds) {
+pub(crate) fn apply(
+    cmd: &mut Command,
+    preserve: PreserveFds,
+    #[cfg(target_os = "linux")] credentials: Option<crate::credentials::CredentialConfig>,
+) {
     use std::os::unix::process::CommandExt;
 
     // SAFETY: all operations inside are async-signal-safe syscalls.
     unsafe {
         cmd.pre_exec(move || {
-            // 1. Landlock first (closes its own fd). Linux only.
+            // 1. Credentials first: Landlock then runs under NO_NEW_PRIVS.
+            #[cfg(target_os = "linux")]
+            if let Some(ref creds) = credentials {
+                crate::credentials::drop_privileges(creds)?;
+            }
+
+            // 2. Landlock (closes its own fd). Linux only.
             #[cfg(target_os = "linux")]
             if let Some(fd) = preserve.landlock {
                 // SAFETY: fd is a ruleset from PathRestrictions::build.
@@ -46,11 +58,11 @@ pub(crate) fn apply(cmd: &mut Command, preserve: PreserveFds) {
             #[cfg(not(target_os = "linux"))]
             let _ = preserve.landlock;
 
-            // 2. Die when parent exits — prevents orphaned VM processes.
+            // 3. Die when parent exits — prevents orphaned VM processes.
             #[cfg(target_os = "linux")]
             libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
 
-            // 3. Close inherited FDs except watchdog (landlock already closed).
+            // 4. Close inherited FDs except watchdog (landlock already closed).
             close_inherited_fds(preserve.watchdog);
 
             Ok(())
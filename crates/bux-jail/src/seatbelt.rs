@@ -41,6 +41,10 @@ fn capabilities(&self) -> SandboxCapabilities {
             seccomp: false,
             mandatory_access_control: true,
             cgroups: false,
+            user_namespace: false,
+            network_namespace: false,
+            capabilities_dropped: false,
+            masked_paths: false,
         }
     }
 
@@ -47,6 +47,12 @@ pub struct SecurityReport {
     pub sandbox: SandboxKind,
     /// Landlock LSM (Linux).
     pub landlock: LayerStatus,
+    /// Rights, network rules and scopes Landlock enforces after ABI
+    /// negotiation; `Some` exactly when [`Self::landlock`] is enforced.
+    pub landlock_enforcement: Option<bux_landlock::Enforcement>,
     /// Seatbelt / MAC layer status (macOS seatbelt is covered by [`Self::sandbox`]).
     pub mac: LayerStatus,
+    /// Isolation the sandbox actually applied (namespaces, dropped
+    /// capabilities, masked paths).
+    pub capabilities: crate::SandboxCapabilities,
 }
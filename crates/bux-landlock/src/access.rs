@@ -0,0 +1,253 @@
+//! Landlock access rights and ABI levels.
+//!
+//! Modelled here rather than re-exported from the `landlock` crate so
+//! configuration and enforcement reports stay cross-platform. Bit values
+//! match the kernel UAPI (`LANDLOCK_ACCESS_FS_*`).
+
+use std::fmt;
+use std::ops::BitOr;
+
+/// A set of Landlock filesystem access rights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FsAccess(u64);
+
+impl FsAccess {
+    /// Execute a file.
+    pub const EXECUTE: Self = Self(1 << 0);
+    /// Open a file with write access.
+    pub const WRITE_FILE: Self = Self(1 << 1);
+    /// Open a file with read access.
+    pub const READ_FILE: Self = Self(1 << 2);
+    /// Open a directory or list its content.
+    pub const READ_DIR: Self = Self(1 << 3);
+    /// Remove an empty directory or rename one.
+    pub const REMOVE_DIR: Self = Self(1 << 4);
+    /// Unlink or rename a file.
+    pub const REMOVE_FILE: Self = Self(1 << 5);
+    /// Create a character device.
+    pub const MAKE_CHAR: Self = Self(1 << 6);
+    /// Create a directory.
+    pub const MAKE_DIR: Self = Self(1 << 7);
+    /// Create a regular file.
+    pub const MAKE_REG: Self = Self(1 << 8);
+    /// Create a Unix domain socket.
+    pub const MAKE_SOCK: Self = Self(1 << 9);
+    /// Create a named pipe.
+    pub const MAKE_FIFO: Self = Self(1 << 10);
+    /// Create a block device.
+    pub const MAKE_BLOCK: Self = Self(1 << 11);
+    /// Create a symbolic link.
+    pub const MAKE_SYM: Self = Self(1 << 12);
+    /// Link or rename a file across directories (ABI v2).
+    pub const REFER: Self = Self(1 << 13);
+    /// Truncate a file (ABI v3).
+    pub const TRUNCATE: Self = Self(1 << 14);
+    /// `ioctl(2)` on a character or block device (ABI v5).
+    pub const IOCTL_DEV: Self = Self(1 << 15);
+
+    /// Read and execute: what [`crate::PathRestrictions::allow_read`]
+    /// grants.
+    pub const READ: Self = Self(Self::EXECUTE.0 | Self::READ_FILE.0 | Self::READ_DIR.0);
+    /// Every right this crate models: what
+    /// [`crate::PathRestrictions::allow_read_write`] grants.
+    pub const ALL: Self = Self((1 << 16) - 1);
+
+    /// Names in kernel bit order.
+    const NAMES: [(Self, &'static str); 16] = [
+        (Self::EXECUTE, "execute"),
+        (Self::WRITE_FILE, "write_file"),
+        (Self::READ_FILE, "read_file"),
+        (Self::READ_DIR, "read_dir"),
+        (Self::REMOVE_DIR, "remove_dir"),
+        (Self::REMOVE_FILE, "remove_file"),
+        (Self::MAKE_CHAR, "make_char"),
+        (Self::MAKE_DIR, "make_dir"),
+        (Self::MAKE_REG, "make_reg"),
+        (Self::MAKE_SOCK, "make_sock"),
+        (Self::MAKE_FIFO, "make_fifo"),
+        (Self::MAKE_BLOCK, "make_block"),
+        (Self::MAKE_SYM, "make_sym"),
+        (Self::REFER, "refer"),
+        (Self::TRUNCATE, "truncate"),
+        (Self::IOCTL_DEV, "ioctl_dev"),
+    ];
+
+    /// No rights.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Raw UAPI bits.
+    #[must_use]
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Whether no right is set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every right in `other` is set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Rights in either set.
+    #[must_use]
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Rights in both sets.
+    #[must_use]
+    pub const fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Rights in `self` but not in `other`.
+    #[must_use]
+    pub const fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// Names of the set rights, e.g. `["execute", "read_file"]`.
+    #[must_use]
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMES
+            .iter()
+            .filter(|(right, _)| self.contains(*right))
+            .map(|&(_, name)| name)
+            .collect()
+    }
+}
+
+impl BitOr for FsAccess {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl fmt::Display for FsAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&self.names().join(","))
+        }
+    }
+}
+
+/// A Landlock ABI version; `0` means Landlock is unavailable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AbiVersion(u8);
+
+impl AbiVersion {
+    /// Landlock unavailable.
+    pub const UNSUPPORTED: Self = Self(0);
+    /// Highest ABI this crate models (v6, Linux 6.12: IPC scoping).
+    pub const LATEST: Self = Self(6);
+
+    /// ABI `version`.
+    #[must_use]
+    pub const fn new(version: u8) -> Self {
+        Self(version)
+    }
+
+    /// The version number.
+    #[must_use]
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// Whether Landlock is available at all.
+    #[must_use]
+    pub const fn is_supported(self) -> bool {
+        self.0 > 0
+    }
+
+    /// Filesystem rights this ABI can restrict.
+    #[must_use]
+    pub const fn fs_access(self) -> FsAccess {
+        let mut bits = match self.0 {
+            0 => 0,
+            _ => {
+                FsAccess::ALL.0
+                    & !(FsAccess::REFER.0 | FsAccess::TRUNCATE.0 | FsAccess::IOCTL_DEV.0)
+            }
+        };
+        if self.0 >= 2 {
+            bits |= FsAccess::REFER.0;
+        }
+        if self.0 >= 3 {
+            bits |= FsAccess::TRUNCATE.0;
+        }
+        if self.0 >= 5 {
+            bits |= FsAccess::IOCTL_DEV.0;
+        }
+        FsAccess(bits)
+    }
+
+    /// Whether TCP bind/connect can be restricted (v4, Linux 6.7).
+    #[must_use]
+    pub const fn supports_network(self) -> bool {
+        self.0 >= 4
+    }
+
+    /// Whether abstract Unix sockets and signals can be scoped (v6,
+    /// Linux 6.12).
+    #[must_use]
+    pub const fn supports_scoping(self) -> bool {
+        self.0 >= 6
+    }
+}
+
+impl fmt::Display for AbiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to omit docs"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abi_levels_add_rights() {
+        assert!(AbiVersion::UNSUPPORTED.fs_access().is_empty());
+        let v1 = AbiVersion::new(1).fs_access();
+        assert!(v1.contains(FsAccess::READ | FsAccess::MAKE_SOCK));
+        assert!(!v1.contains(FsAccess::REFER));
+        assert!(AbiVersion::new(3).fs_access().contains(FsAccess::TRUNCATE));
+        assert!(!AbiVersion::new(4).fs_access().contains(FsAccess::IOCTL_DEV));
+        assert_eq!(AbiVersion::new(5).fs_access(), FsAccess::ALL);
+        assert!(!AbiVersion::new(3).supports_network());
+        assert!(AbiVersion::new(4).supports_network());
+        assert!(!AbiVersion::new(5).supports_scoping());
+        assert!(AbiVersion::LATEST.supports_scoping());
+    }
+
+    #[test]
+    fn set_operations_and_names() {
+        let rw = FsAccess::READ | FsAccess::WRITE_FILE;
+        assert!(rw.contains(FsAccess::EXECUTE));
+        assert_eq!(rw.difference(FsAccess::READ), FsAccess::WRITE_FILE);
+        assert_eq!(rw.intersection(FsAccess::READ_DIR), FsAccess::READ_DIR);
+        assert_eq!(
+            rw.names(),
+            ["execute", "write_file", "read_file", "read_dir"]
+        );
+        assert_eq!(FsAccess::IOCTL_DEV.to_string(), "ioctl_dev");
+        assert_eq!(FsAccess::empty().to_string(), "none");
+        assert_eq!(FsAccess::ALL.names().len(), 16);
+    }
+}
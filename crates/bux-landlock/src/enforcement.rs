@@ -0,0 +1,77 @@
+//! What a ruleset actually enforces once negotiated against a kernel ABI.
+
+use std::fmt;
+
+use crate::access::{AbiVersion, FsAccess};
+
+/// Landlock restrictions in force after ABI negotiation.
+///
+/// Produced by [`crate::PathRestrictions::negotiate`]. Requests the
+/// kernel's ABI cannot express are dropped (best effort) and listed as
+/// unsupported rather than failing the build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Enforcement {
+    /// Negotiated ABI: the lower of the kernel's and
+    /// [`AbiVersion::LATEST`].
+    pub abi: AbiVersion,
+    /// Filesystem rights restricted to the granted paths.
+    pub filesystem: FsAccess,
+    /// Filesystem rights left unrestricted because the ABI cannot
+    /// express them.
+    pub filesystem_unsupported: FsAccess,
+    /// Whether TCP bind/connect is restricted to the allowed ports.
+    pub network: bool,
+    /// Whether connecting to abstract Unix sockets outside the sandbox
+    /// is denied.
+    pub abstract_unix_socket: bool,
+    /// Whether signalling processes outside the sandbox is denied.
+    pub signal: bool,
+    /// Requested non-filesystem restrictions the ABI cannot express
+    /// (`"network"`, `"abstract_unix_socket"`, `"signal"`).
+    pub unsupported: Vec<&'static str>,
+}
+
+impl Enforcement {
+    /// Whether every requested restriction is in force.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.filesystem_unsupported.is_empty() && self.unsupported.is_empty()
+    }
+
+    /// Names of the scopes in force.
+    #[must_use]
+    pub fn scopes(&self) -> Vec<&'static str> {
+        [
+            (self.abstract_unix_socket, "abstract_unix_socket"),
+            (self.signal, "signal"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect()
+    }
+}
+
+impl fmt::Display for Enforcement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scopes = self.scopes();
+        write!(
+            f,
+            "ABI {}; filesystem: {}; network: {}; scoped: {}",
+            self.abi,
+            self.filesystem,
+            if self.network { "tcp" } else { "none" },
+            if scopes.is_empty() {
+                "none".to_owned()
+            } else {
+                scopes.join(",")
+            },
+        )?;
+        if !self.is_complete() {
+            let mut missing = self.filesystem_unsupported.names();
+            missing.extend(&self.unsupported);
+            write!(f, "; not enforced: {}", missing.join(","))?;
+        }
+        Ok(())
+    }
+}
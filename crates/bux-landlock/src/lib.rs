@@ -11,9 +11,11 @@
 //! community `landlock` crate. It exposes exactly three moving parts:
 //!
 //! 1. [`PathRestrictions`] — a fluent builder that accumulates
-//!    read-only / read-write path lists and an optional network-deny
-//!    flag. Compiles on every platform so configuration code stays
-//!    `cfg`-free.
+//!    read-only / read-write path lists, per-path [`FsAccess`] rights,
+//!    TCP port rules and IPC scoping. Compiles on every platform so
+//!    configuration code stays `cfg`-free, and
+//!    [`PathRestrictions::negotiate`] reports which of those an
+//!    [`AbiVersion`] can enforce.
 //! 2. `PathRestrictions::build` *(Linux only)* — turns the config into
 //!    a kernel ruleset and returns its raw file descriptor; on older
 //!    kernels (< 5.13) it returns `Ok(None)`.
@@ -73,14 +75,18 @@
     reason = "L1 LSM wrapper — restrict_self is fundamentally unsafe (revokes caller thread rights permanently); safety contract documented on the function"
 )]
 
+mod access;
+mod enforcement;
 mod error;
 mod restrictions;
 
 #[cfg(target_os = "linux")]
 mod linux;
 
+pub use access::{AbiVersion, FsAccess};
+pub use enforcement::Enforcement;
 pub use error::{Error, Result};
-pub use restrictions::PathRestrictions;
+pub use restrictions::{PathRestrictions, PathRule};
 
 #[cfg(target_os = "linux")]
 use std::os::fd::RawFd;
@@ -104,10 +110,37 @@ impl PathRestrictions {
     /// Returns `Error::Ruleset` if the kernel rejects the compiled
     /// ruleset for any reason other than "Landlock unavailable".
     pub fn build(&self) -> Result<Option<RawFd>> {
+        Ok(self.build_with_report()?.map(|(fd, _)| fd))
+    }
+
+    /// Like [`Self::build`], also returning what the ruleset enforces
+    /// on this kernel (see [`Self::negotiate`]).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Self::build`].
+    pub fn build_with_report(&self) -> Result<Option<(RawFd, Enforcement)>> {
         linux::build(self)
     }
 }
 
+/// The running kernel's Landlock ABI version.
+///
+/// Returns [`AbiVersion::UNSUPPORTED`] when Landlock is missing or
+/// disabled, and on non-Linux targets.
+#[must_use]
+#[allow(clippy::missing_const_for_fn, reason = "Linux branch issues a syscall")]
+pub fn abi_version() -> AbiVersion {
+    #[cfg(target_os = "linux")]
+    {
+        linux::abi_version()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        AbiVersion::UNSUPPORTED
+    }
+}
+
 /// Apply a previously built Landlock ruleset to the current thread.
 ///
 /// Safe to call from a `std::os::unix::process::CommandExt::pre_exec`
@@ -6,22 +6,25 @@
 
 use std::os::fd::{IntoRawFd, OwnedFd, RawFd};
 
+use std::path::Path;
+
 use landlock::{
-    ABI, Access, AccessFs, AccessNet, CompatLevel, Compatible, PathBeneath, PathFd, Ruleset,
-    RulesetAttr, RulesetCreatedAttr, RulesetError,
+    ABI, Access, AccessFs, AccessNet, BitFlags, CompatLevel, Compatible, NetPort, PathBeneath,
+    PathFd, Ruleset, RulesetAttr, RulesetCreated, RulesetCreatedAttr, RulesetError, Scope,
 };
 
+use crate::access::{AbiVersion, FsAccess};
+use crate::enforcement::Enforcement;
 use crate::error::{Error, Result};
 use crate::restrictions::PathRestrictions;
 
-/// Target Landlock ABI version. V5 (kernel 6.10+) supports
-/// filesystem + network + ioctl-dev. `BestEffort` silently downgrades
-/// on older kernels, so the build call still succeeds on 5.13+ with a
-/// reduced ruleset.
-const TARGET_ABI: ABI = ABI::V5;
+/// `LANDLOCK_CREATE_RULESET_VERSION`: ask the kernel for its ABI version.
+const CREATE_RULESET_VERSION: libc::c_uint = 1;
 
-/// Build a Landlock ruleset and return the raw fd.
+/// Build a Landlock ruleset and return the raw fd with what it enforces.
 ///
+/// The ruleset handles exactly the rights [`PathRestrictions::negotiate`]
+/// reports for the kernel's ABI, so the report matches the kernel's view.
 /// Returns `Ok(None)` if the running kernel does not support Landlock
 /// at all. Returns `Err` only for unexpected failures (e.g. a rule was
 /// rejected for reasons other than kernel version).
@@ -29,43 +32,120 @@
 /// The returned fd is a **raw** file descriptor — the caller is
 /// responsible for passing it to a forked child and invoking
 /// [`restrict_self`] in the child's `pre_exec` hook, which closes it.
-pub(crate) fn build(r: &PathRestrictions) -> Result<Option<RawFd>> {
+pub(crate) fn build(r: &PathRestrictions) -> Result<Option<(RawFd, Enforcement)>> {
+    let enforcement = r.negotiate(abi_version());
+    let Some(abi) = to_abi(enforcement.abi) else {
+        return Ok(None);
+    };
+
     let mut ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
-        .handle_access(AccessFs::from_all(TARGET_ABI))
+        .handle_access(AccessFs::from_all(abi))
         .map_err(|e| ruleset_err("handle filesystem access", &e))?;
 
-    if r.network_denied() {
+    if enforcement.network {
         ruleset = ruleset
-            .handle_access(AccessNet::from_all(TARGET_ABI))
+            .handle_access(AccessNet::from_all(abi))
             .map_err(|e| ruleset_err("handle network access", &e))?;
     }
+    let mut scopes = BitFlags::<Scope>::empty();
+    if enforcement.abstract_unix_socket {
+        scopes |= Scope::AbstractUnixSocket;
+    }
+    if enforcement.signal {
+        scopes |= Scope::Signal;
+    }
+    if !scopes.is_empty() {
+        ruleset = ruleset
+            .scope(scopes)
+            .map_err(|e| ruleset_err("scope IPC", &e))?;
+    }
 
     let mut created = ruleset
         .create()
         .map_err(|e| ruleset_err("create ruleset", &e))?
         .set_compatibility(CompatLevel::BestEffort);
 
-    let read_access = AccessFs::from_read(TARGET_ABI);
-    for path in r.read_paths() {
-        if let Ok(fd) = PathFd::new(path) {
-            created = created
-                .add_rule(PathBeneath::new(fd, read_access))
-                .map_err(|e| ruleset_err(&format!("add read rule for {}", path.display()), &e))?;
-        }
+    let grants = r
+        .read_paths()
+        .iter()
+        .map(|p| (p.as_path(), FsAccess::READ))
+        .chain(
+            r.read_write_paths()
+                .iter()
+                .map(|p| (p.as_path(), FsAccess::ALL)),
+        )
+        .chain(
+            r.path_rules()
+                .iter()
+                .map(|rule| (rule.path.as_path(), rule.access)),
+        );
+    for (path, access) in grants {
+        created = add_path_rule(created, path, access.intersection(enforcement.filesystem))?;
     }
 
-    let all_access = AccessFs::from_all(TARGET_ABI);
-    for path in r.read_write_paths() {
-        if let Ok(fd) = PathFd::new(path) {
+    if enforcement.network {
+        let ports = r
+            .tcp_bind_ports()
+            .iter()
+            .map(|&p| (p, AccessNet::BindTcp))
+            .chain(
+                r.tcp_connect_ports()
+                    .iter()
+                    .map(|&p| (p, AccessNet::ConnectTcp)),
+            );
+        for (port, access) in ports {
             created = created
-                .add_rule(PathBeneath::new(fd, all_access))
-                .map_err(|e| ruleset_err(&format!("add write rule for {}", path.display()), &e))?;
+                .add_rule(NetPort::new(port, access))
+                .map_err(|e| ruleset_err(&format!("add rule for TCP port {port}"), &e))?;
         }
     }
 
     let owned_fd: Option<OwnedFd> = created.into();
-    Ok(owned_fd.map(IntoRawFd::into_raw_fd))
+    Ok(owned_fd.map(|fd| (fd.into_raw_fd(), enforcement)))
+}
+
+/// Grant `access` beneath `path`, skipping paths that cannot be opened
+/// and empty grants.
+fn add_path_rule(created: RulesetCreated, path: &Path, access: FsAccess) -> Result<RulesetCreated> {
+    let Ok(fd) = PathFd::new(path) else {
+        return Ok(created);
+    };
+    let rights = BitFlags::<AccessFs>::from_bits_truncate(access.bits());
+    if rights.is_empty() {
+        return Ok(created);
+    }
+    created
+        .add_rule(PathBeneath::new(fd, rights))
+        .map_err(|e| ruleset_err(&format!("add rule for {}", path.display()), &e))
+}
+
+/// The running kernel's Landlock ABI, or [`AbiVersion::UNSUPPORTED`].
+pub(crate) fn abi_version() -> AbiVersion {
+    // SAFETY: a null attr with size 0 and the VERSION flag only queries
+    // the ABI; the kernel reads no memory.
+    let v = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<libc::c_void>(),
+            0_usize,
+            CREATE_RULESET_VERSION,
+        )
+    };
+    AbiVersion::new(u8::try_from(v).unwrap_or(if v > 0 { u8::MAX } else { 0 }))
+}
+
+/// The `landlock` crate ABI matching a negotiated version.
+const fn to_abi(abi: AbiVersion) -> Option<ABI> {
+    Some(match abi.get() {
+        0 => return None,
+        1 => ABI::V1,
+        2 => ABI::V2,
+        3 => ABI::V3,
+        4 => ABI::V4,
+        5 => ABI::V5,
+        _ => ABI::V6,
+    })
 }
 
 /// Apply a previously built ruleset to the current thread.
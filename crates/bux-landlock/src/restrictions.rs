@@ -8,6 +8,9 @@
 
 use std::path::{Path, PathBuf};
 
+use crate::access::{AbiVersion, FsAccess};
+use crate::enforcement::Enforcement;
+
 /// Fluent builder for Landlock filesystem (and optional network)
 /// restrictions.
 ///
@@ -16,11 +19,15 @@
 /// ```no_run
 /// # #[cfg(target_os = "linux")]
 /// # fn main() -> Result<(), bux_landlock::Error> {
+/// use bux_landlock::FsAccess;
+///
 /// let ruleset = bux_landlock::PathRestrictions::new()
 ///     .allow_read("/usr")
 ///     .allow_read("/etc")
 ///     .allow_read_write("/tmp")
-///     .deny_network()
+///     .allow("/dev", FsAccess::READ_FILE | FsAccess::WRITE_FILE | FsAccess::IOCTL_DEV)
+///     .allow_tcp_connect(443)
+///     .scope_signals()
 ///     .build()?;
 ///
 /// if let Some(fd) = ruleset {
@@ -33,13 +40,37 @@
 /// ```
 #[derive(Debug, Clone, Default)]
 #[must_use = "PathRestrictions does nothing until you call `.build()`"]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "independent restriction toggles"
+)]
 pub struct PathRestrictions {
     /// Paths granted read-only access (recursive under the given path).
     read_paths: Vec<PathBuf>,
     /// Paths granted read-write access (recursive under the given path).
     read_write_paths: Vec<PathBuf>,
+    /// Paths granted an explicit set of rights.
+    rules: Vec<PathRule>,
     /// Whether to deny all TCP bind/connect (Landlock ABI v4+).
     deny_network: bool,
+    /// Ports TCP bind is allowed on.
+    tcp_bind: Vec<u16>,
+    /// Ports TCP connect is allowed to.
+    tcp_connect: Vec<u16>,
+    /// Whether to deny connecting to abstract Unix sockets created
+    /// outside the sandbox (ABI v6+).
+    scope_abstract_unix_socket: bool,
+    /// Whether to deny signalling processes outside the sandbox (ABI v6+).
+    scope_signal: bool,
+}
+
+/// Rights granted beneath one path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRule {
+    /// The path (recursive for directories).
+    pub path: PathBuf,
+    /// Rights granted beneath it.
+    pub access: FsAccess,
 }
 
 impl PathRestrictions {
@@ -48,7 +79,12 @@ pub const fn new() -> Self {
         Self {
             read_paths: Vec::new(),
             read_write_paths: Vec::new(),
+            rules: Vec::new(),
             deny_network: false,
+            tcp_bind: Vec::new(),
+            tcp_connect: Vec::new(),
+            scope_abstract_unix_socket: false,
+            scope_signal: false,
         }
     }
 
@@ -66,6 +102,17 @@ pub fn allow_read_write(mut self, path: impl AsRef<Path>) -> Self {
         self
     }
 
+    /// Grant exactly `access` beneath `path`. Rights the running kernel
+    /// cannot restrict stay unrestricted everywhere (see
+    /// [`Enforcement::filesystem_unsupported`]).
+    pub fn allow(mut self, path: impl AsRef<Path>, access: FsAccess) -> Self {
+        self.rules.push(PathRule {
+            path: path.as_ref().to_path_buf(),
+            access,
+        });
+        self
+    }
+
     /// Add several read-only paths at once.
     pub fn allow_read_many<I, P>(mut self, paths: I) -> Self
     where
@@ -97,6 +144,32 @@ pub const fn deny_network(mut self) -> Self {
         self
     }
 
+    /// Restrict TCP and allow binding `port`. Any port rule denies every
+    /// port without one, as [`Self::deny_network`] does.
+    pub fn allow_tcp_bind(mut self, port: u16) -> Self {
+        self.tcp_bind.push(port);
+        self
+    }
+
+    /// Restrict TCP and allow connecting to `port`.
+    pub fn allow_tcp_connect(mut self, port: u16) -> Self {
+        self.tcp_connect.push(port);
+        self
+    }
+
+    /// Deny connecting to abstract Unix sockets created outside the
+    /// sandbox (ABI v6+).
+    pub const fn scope_abstract_unix_sockets(mut self) -> Self {
+        self.scope_abstract_unix_socket = true;
+        self
+    }
+
+    /// Deny signalling processes outside the sandbox (ABI v6+).
+    pub const fn scope_signals(mut self) -> Self {
+        self.scope_signal = true;
+        self
+    }
+
     /// Read-only paths registered so far.
     #[must_use]
     pub fn read_paths(&self) -> &[PathBuf] {
@@ -109,10 +182,77 @@ pub fn read_write_paths(&self) -> &[PathBuf] {
         &self.read_write_paths
     }
 
-    /// Whether network will be denied by the resulting ruleset.
+    /// Paths with explicit rights registered so far.
+    #[must_use]
+    pub fn path_rules(&self) -> &[PathRule] {
+        &self.rules
+    }
+
+    /// Ports TCP bind is allowed on.
+    #[must_use]
+    pub fn tcp_bind_ports(&self) -> &[u16] {
+        &self.tcp_bind
+    }
+
+    /// Ports TCP connect is allowed to.
+    #[must_use]
+    pub fn tcp_connect_ports(&self) -> &[u16] {
+        &self.tcp_connect
+    }
+
+    /// Whether TCP will be restricted by the resulting ruleset, either
+    /// entirely or to the allowed ports.
     #[must_use]
     pub const fn network_denied(&self) -> bool {
-        self.deny_network
+        self.deny_network || !self.tcp_bind.is_empty() || !self.tcp_connect.is_empty()
+    }
+
+    /// Whether abstract Unix socket scoping was requested.
+    #[must_use]
+    pub const fn abstract_unix_sockets_scoped(&self) -> bool {
+        self.scope_abstract_unix_socket
+    }
+
+    /// Whether signal scoping was requested.
+    #[must_use]
+    pub const fn signals_scoped(&self) -> bool {
+        self.scope_signal
+    }
+
+    /// What this ruleset enforces on a kernel offering `abi`.
+    ///
+    /// Every modelled filesystem right the ABI supports is handled, so
+    /// anything not granted by a rule is denied.
+    #[must_use = "the report describes the negotiated restrictions"]
+    pub fn negotiate(&self, abi: AbiVersion) -> Enforcement {
+        let abi = abi.min(AbiVersion::LATEST);
+        let filesystem = abi.fs_access();
+        let requested = [
+            (self.network_denied(), abi.supports_network(), "network"),
+            (
+                self.scope_abstract_unix_socket,
+                abi.supports_scoping(),
+                "abstract_unix_socket",
+            ),
+            (self.scope_signal, abi.supports_scoping(), "signal"),
+        ];
+        Enforcement {
+            abi,
+            filesystem,
+            filesystem_unsupported: if abi.is_supported() {
+                FsAccess::ALL.difference(filesystem)
+            } else {
+                FsAccess::ALL
+            },
+            network: self.network_denied() && abi.supports_network(),
+            abstract_unix_socket: self.scope_abstract_unix_socket && abi.supports_scoping(),
+            signal: self.scope_signal && abi.supports_scoping(),
+            unsupported: requested
+                .into_iter()
+                .filter(|&(wanted, supported, _)| wanted && !supported)
+                .map(|(_, _, name)| name)
+                .collect(),
+        }
     }
 }
 
@@ -179,4 +319,63 @@ fn builder_is_fluent() {
         assert_eq!(r.read_write_paths().len(), 1);
         assert!(r.network_denied());
     }
+
+    #[test]
+    fn explicit_rights_and_ports() {
+        let r = PathRestrictions::new()
+            .allow("/dev", FsAccess::READ_FILE | FsAccess::IOCTL_DEV)
+            .allow_tcp_connect(443)
+            .allow_tcp_bind(8080);
+        assert_eq!(r.path_rules()[0].path, Path::new("/dev"));
+        assert!(r.path_rules()[0].access.contains(FsAccess::IOCTL_DEV));
+        assert_eq!(r.tcp_connect_ports(), [443]);
+        assert_eq!(r.tcp_bind_ports(), [8080]);
+        assert!(r.network_denied());
+    }
+
+    #[test]
+    fn negotiation_drops_what_the_abi_lacks() {
+        let r = PathRestrictions::new()
+            .allow_tcp_connect(443)
+            .scope_abstract_unix_sockets()
+            .scope_signals();
+
+        let v3 = r.negotiate(AbiVersion::new(3));
+        assert!(v3.filesystem.contains(FsAccess::TRUNCATE));
+        assert_eq!(v3.filesystem_unsupported, FsAccess::IOCTL_DEV);
+        assert!(!v3.network && !v3.signal);
+        assert_eq!(
+            v3.unsupported,
+            ["network", "abstract_unix_socket", "signal"]
+        );
+        assert!(!v3.is_complete());
+
+        let newer = r.negotiate(AbiVersion::new(9));
+        assert_eq!(newer.abi, AbiVersion::LATEST);
+        assert_eq!(newer.filesystem, FsAccess::ALL);
+        assert!(newer.network && newer.abstract_unix_socket && newer.signal);
+        assert!(newer.is_complete());
+    }
+
+    #[test]
+    fn unsupported_kernel_enforces_nothing() {
+        let e = PathRestrictions::new().negotiate(AbiVersion::UNSUPPORTED);
+        assert!(e.filesystem.is_empty());
+        assert_eq!(e.filesystem_unsupported, FsAccess::ALL);
+        assert!(e.scopes().is_empty());
+    }
+
+    #[test]
+    fn enforcement_display_lists_gaps() {
+        let e = PathRestrictions::new()
+            .deny_network()
+            .scope_signals()
+            .negotiate(AbiVersion::new(5));
+        assert_eq!(
+            e.to_string(),
+            "ABI v5; filesystem: execute,write_file,read_file,read_dir,remove_dir,remove_file,\
+             make_char,make_dir,make_reg,make_sock,make_fifo,make_block,make_sym,refer,truncate,\
+             ioctl_dev; network: tcp; scoped: none; not enforced: signal"
+        );
+    }
 }
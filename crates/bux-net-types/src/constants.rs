@@ -3,6 +3,11 @@
 //! These values define the default subnet, gateway, and guest addresses.
 //! They must remain consistent across the host runtime, network backend,
 //! and the guest agent's network configuration.
+//!
+//! Dual-stack guests also get an address in the unique local
+//! [`SUBNET_V6`], with the same host part as their IPv4 address.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
 
 /// Virtual network subnet.
 pub const SUBNET: &str = "192.168.127.0/24";
@@ -15,6 +20,51 @@
 /// Guest IP address (assigned via DHCP static lease).
 pub const GUEST_IP: &str = "192.168.127.2";
 
+/// [`GATEWAY_IP`] as an address.
+pub const GATEWAY_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 127, 1);
+
+/// [`GUEST_IP`] as an address.
+pub const GUEST_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 127, 2);
+
+/// IPv6 subnet of dual-stack guests (unique local, `/64`).
+pub const SUBNET_V6: &str = "fd42:6275:7800::/64";
+
+/// Gateway IPv6 address; the default IPv6 route.
+pub const GATEWAY_IPV6: Ipv6Addr = Ipv6Addr::new(0xfd42, 0x6275, 0x7800, 0, 0, 0, 0, 1);
+
+/// Guest IPv6 address, paired with [`GUEST_IPV4`].
+pub const GUEST_IPV6: Ipv6Addr = guest_ipv6(GUEST_IPV4);
+
+/// [`GUEST_IPV6`] with its prefix (for static assignment inside the guest).
+pub const GUEST_CIDR_V6: &str = "fd42:6275:7800::2/64";
+
+/// IPv6 address paired with the guest address `ipv4`: its last octet as the
+/// host part in [`SUBNET_V6`].
+#[must_use]
+pub const fn guest_ipv6(ipv4: Ipv4Addr) -> Ipv6Addr {
+    let [.., host] = ipv4.octets();
+    Ipv6Addr::new(
+        0xfd42,
+        0x6275,
+        0x7800,
+        0,
+        0,
+        0,
+        0,
+        u16::from_be_bytes([0, host]),
+    )
+}
+
+/// Virtual address the backends translate to the host's loopback when
+/// the host gateway is enabled.
+pub const HOST_IP: &str = "192.168.127.254";
+
+/// [`HOST_IP`] as an address.
+pub const HOST_IPV4: Ipv4Addr = Ipv4Addr::new(192, 168, 127, 254);
+
+/// Name the gateway resolves to [`HOST_IP`] when the host gateway is enabled.
+pub const HOST_GATEWAY_NAME: &str = "host.bux.internal";
+
 /// Guest IP with CIDR prefix (for static assignment inside the guest).
 pub const GUEST_CIDR: &str = "192.168.127.2/24";
 
@@ -71,6 +121,20 @@ fn mac_to_string_matches_constants() {
         assert_eq!(mac_to_string(GATEWAY_MAC), GATEWAY_MAC_STRING);
     }
 
+    #[test]
+    fn address_constants_agree() {
+        assert_eq!(GATEWAY_IPV4.to_string(), GATEWAY_IP);
+        assert_eq!(GUEST_IPV4.to_string(), GUEST_IP);
+        assert_eq!(format!("{GUEST_IP}/24"), GUEST_CIDR);
+        assert_eq!(format!("{GUEST_IPV6}/64"), GUEST_CIDR_V6);
+        let prefix = Ipv6Addr::from_bits(GATEWAY_IPV6.to_bits() & !u128::from(u64::MAX));
+        assert_eq!(format!("{prefix}/64"), SUBNET_V6);
+        assert_eq!(
+            guest_ipv6(Ipv4Addr::new(192, 168, 127, 10)).to_string(),
+            "fd42:6275:7800::a"
+        );
+    }
+
     #[test]
     fn mac_addresses_differ_by_last_byte() {
         for i in 0..5 {
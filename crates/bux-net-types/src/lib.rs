@@ -0,0 +1,101 @@
+//! Network topology constants and types shared by the bux network backends.
+//!
+//! [`constants`] is the single source of truth for the virtual network
+//! (subnets, gateway and guest addresses, MTU, DNS search domains).
+//! [`PortProtocol`] and [`SecretConfig`] appear both in the backend-neutral
+//! `bux-net` API and in the gvproxy wire format, so they live here, below
+//! both `bux-gvproxy` and `bux-net`, keeping the gvproxy dependency of
+//! `bux-net` optional.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+pub mod constants;
+
+/// Transport of a published port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortProtocol {
+    /// TCP (the default).
+    #[default]
+    Tcp,
+    /// UDP.
+    Udp,
+}
+
+impl PortProtocol {
+    /// Stable lowercase name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        }
+    }
+
+    /// Whether this is [`Self::Tcp`] (omitted from JSON).
+    #[must_use]
+    #[allow(
+        clippy::trivially_copy_pass_by_ref,
+        reason = "serde skip_serializing_if passes a reference"
+    )]
+    pub const fn is_tcp(&self) -> bool {
+        matches!(self, Self::Tcp)
+    }
+}
+
+impl fmt::Display for PortProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Secret placeholder substitution config for MITM (host-side only).
+///
+/// Wire format matches Go `SecretConfig` in `mitm_replacer.go`.
+/// The real `value` is never logged: [`Debug`] redacts it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SecretConfig {
+    /// Logical secret name (for host bookkeeping).
+    pub name: String,
+    /// Hostnames (SNI / Host header) this secret applies to.
+    pub hosts: Vec<String>,
+    /// Placeholder string that appears in guest traffic (e.g. `<BUX_SECRET:TOKEN>`).
+    pub placeholder: String,
+    /// Real secret value substituted on the host proxy — never sent into the guest.
+    pub value: String,
+}
+
+impl fmt::Debug for SecretConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretConfig")
+            .field("name", &self.name)
+            .field("hosts", &self.hosts)
+            .field("placeholder", &self.placeholder)
+            .field("value", &"[REDACTED]")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to omit docs"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_debug_redacts_value() {
+        let s = SecretConfig {
+            name: "TOKEN".into(),
+            hosts: vec!["h".into()],
+            placeholder: "p".into(),
+            value: "must-not-appear".into(),
+        };
+        let dbg = format!("{s:?}");
+        assert!(dbg.contains("REDACTED"));
+        assert!(!dbg.contains("must-not-appear"));
+    }
+}
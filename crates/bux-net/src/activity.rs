@@ -0,0 +1,315 @@
+//! Per-VM network activity events.
+//!
+//! Backends started with [`NetworkConfig::record_activity`] buffer a
+//! [`NetEvent`] for each DNS lookup, TCP connection and policy denial;
+//! callers collect them with [`NetworkBackend::drain_events`]. Buffers are
+//! bounded, so a caller that stops draining loses the oldest events rather
+//! than growing memory.
+//!
+//! Coverage differs per backend: the userspace stack sees every DNS and
+//! TCP event kind, while gvproxy resolves DNS upstream and reports only
+//! outbound TCP connects, closes and denials, plus HTTP requests on its
+//! MITM path (see [`crate::http`]). Both report used-up transfer quotas
+//! (see [`crate::shaping`]).
+//!
+//! [`NetworkConfig::record_activity`]: crate::NetworkConfig::record_activity
+//! [`NetworkBackend::drain_events`]: crate::NetworkBackend::drain_events
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::egress::EgressProtocol;
+use crate::shaping::{QuotaAction, TrafficDirection};
+
+/// Events a backend holds between drains.
+pub(crate) const EVENT_CAPACITY: usize = 4096;
+
+/// What happened on the guest's network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum NetEventKind {
+    /// The guest asked the gateway resolver for `name`.
+    DnsQuery {
+        /// Queried name.
+        name: String,
+    },
+    /// The resolver answered a query for `name`.
+    DnsAnswer {
+        /// Queried name.
+        name: String,
+        /// Addresses in the answer.
+        addresses: Vec<IpAddr>,
+    },
+    /// The guest opened an outbound TCP connection.
+    TcpConnect {
+        /// Destination.
+        remote: SocketAddr,
+        /// Hostname the destination was resolved from or requested as.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+    },
+    /// A host client connected to a published port.
+    TcpAccept {
+        /// Host-side client.
+        remote: SocketAddr,
+        /// Published host port.
+        host_port: u16,
+        /// Guest port it forwards to.
+        guest_port: u16,
+    },
+    /// An outbound TCP connection closed.
+    TcpClose {
+        /// Destination.
+        remote: SocketAddr,
+        /// Bytes sent by the guest.
+        bytes_out: u64,
+        /// Bytes delivered to the guest.
+        bytes_in: u64,
+    },
+    /// The egress policy refused a lookup or connection.
+    Denied {
+        /// Transport of the refused traffic.
+        protocol: EgressProtocol,
+        /// Destination, when the refusal was for an address.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        remote: Option<SocketAddr>,
+        /// Hostname, when known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+        /// Deny rule that matched; `None` when the default action refused.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rule: Option<String>,
+    },
+    /// The MITM proxy handled or refused an HTTPS request.
+    HttpRequest {
+        /// Server name.
+        host: String,
+        /// Request method.
+        method: String,
+        /// URL path.
+        path: String,
+        /// Response status; `403` when the HTTP policy refused it.
+        status: u16,
+        /// Request body bytes sent by the guest.
+        bytes_out: u64,
+        /// Response body bytes delivered to the guest.
+        bytes_in: u64,
+        /// Why the HTTP policy refused the request; `None` when forwarded.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        blocked: Option<String>,
+    },
+    /// The guest used up a transfer quota.
+    QuotaExceeded {
+        /// Direction whose quota ran out.
+        direction: TrafficDirection,
+        /// The quota, in bytes.
+        limit: u64,
+        /// Action now in effect.
+        action: QuotaAction,
+    },
+}
+
+impl fmt::Display for NetEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DnsQuery { name } => write!(f, "dns query {name}"),
+            Self::DnsAnswer { name, addresses } => {
+                write!(f, "dns answer {name} ->")?;
+                if addresses.is_empty() {
+                    return f.write_str(" (none)");
+                }
+                addresses.iter().try_for_each(|addr| write!(f, " {addr}"))
+            }
+            Self::TcpConnect { remote, host } => {
+                write!(f, "tcp connect {remote}")?;
+                host.as_ref().map_or(Ok(()), |host| write!(f, " ({host})"))
+            }
+            Self::TcpAccept {
+                remote,
+                host_port,
+                guest_port,
+            } => write!(f, "tcp accept {remote} on {host_port} -> {guest_port}"),
+            Self::TcpClose {
+                remote,
+                bytes_out,
+                bytes_in,
+            } => write!(f, "tcp close {remote} out={bytes_out} in={bytes_in}"),
+            Self::Denied {
+                protocol,
+                remote,
+                host,
+                rule,
+            } => {
+                let proto = match protocol {
+                    EgressProtocol::Tcp => "tcp",
+                    EgressProtocol::Udp => "udp",
+                    _ => "any",
+                };
+                write!(f, "denied {proto}")?;
+                if let Some(host) = host {
+                    write!(f, " {host}")?;
+                }
+                if let Some(remote) = remote {
+                    write!(f, " {remote}")?;
+                }
+                match rule {
+                    Some(rule) => write!(f, " by {rule}"),
+                    None => f.write_str(" (default)"),
+                }
+            }
+            Self::HttpRequest {
+                host,
+                method,
+                path,
+                status,
+                bytes_out,
+                bytes_in,
+                blocked,
+            } => {
+                write!(f, "http {method} {host}{path} {status}")?;
+                match blocked {
+                    Some(reason) => write!(f, " blocked: {reason}"),
+                    None => write!(f, " out={bytes_out} in={bytes_in}"),
+                }
+            }
+            Self::QuotaExceeded {
+                direction,
+                limit,
+                action,
+            } => write!(f, "{direction} quota of {limit} bytes exceeded: {action}"),
+        }
+    }
+}
+
+/// A timestamped [`NetEventKind`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetEvent {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: NetEventKind,
+}
+
+impl NetEvent {
+    /// Stamps `kind` with the current time.
+    #[must_use]
+    pub fn now(kind: NetEventKind) -> Self {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Self {
+            timestamp_ms: u64::try_from(since_epoch.as_millis()).unwrap_or(u64::MAX),
+            kind,
+        }
+    }
+
+    /// When the event happened.
+    #[must_use]
+    pub fn timestamp(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.timestamp_ms)
+    }
+}
+
+/// Bounded, thread-safe queue of events awaiting a drain.
+#[derive(Debug, Default)]
+pub(crate) struct EventBuffer {
+    /// Oldest event first.
+    events: Mutex<VecDeque<NetEvent>>,
+}
+
+impl EventBuffer {
+    /// Appends `kind` stamped now, evicting the oldest event when full.
+    pub(crate) fn push(&self, kind: NetEventKind) {
+        let mut events = self
+            .events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if events.len() >= EVENT_CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(NetEvent::now(kind));
+    }
+
+    /// Takes every buffered event.
+    pub(crate) fn drain(&self) -> Vec<NetEvent> {
+        self.events
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .drain(..)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buffer_drains_and_evicts_oldest() {
+        let buffer = EventBuffer::default();
+        for port in 0..=u16::try_from(EVENT_CAPACITY).unwrap() {
+            buffer.push(NetEventKind::TcpConnect {
+                remote: SocketAddr::from(([1, 2, 3, 4], port)),
+                host: None,
+            });
+        }
+        let events = buffer.drain();
+        assert_eq!(events.len(), EVENT_CAPACITY);
+        assert!(matches!(
+            &events[0].kind,
+            NetEventKind::TcpConnect { remote, .. } if remote.port() == 1
+        ));
+        assert!(buffer.drain().is_empty());
+    }
+
+    #[test]
+    fn serializes_flat_with_kind_tag() {
+        let event = NetEvent {
+            timestamp_ms: 1_700_000_000_000,
+            kind: NetEventKind::Denied {
+                protocol: EgressProtocol::Tcp,
+                remote: Some(SocketAddr::from(([10, 0, 0, 1], 22))),
+                host: None,
+                rule: Some("!10.0.0.0/8".into()),
+            },
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            json,
+            r#"{"timestamp_ms":1700000000000,"kind":"denied","protocol":"tcp","remote":"10.0.0.1:22","rule":"!10.0.0.0/8"}"#
+        );
+        let back: NetEvent = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, event);
+        assert_eq!(
+            event.kind.to_string(),
+            "denied tcp 10.0.0.1:22 by !10.0.0.0/8"
+        );
+    }
+
+    #[test]
+    fn quota_event_carries_direction_and_action() {
+        let json = r#"{"timestamp_ms":1,"kind":"quota_exceeded","direction":"ingress","limit":100,"action":"stop"}"#;
+        let event: NetEvent = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            event.kind,
+            NetEventKind::QuotaExceeded {
+                direction: TrafficDirection::Ingress,
+                limit: 100,
+                action: QuotaAction::Stop,
+            }
+        );
+        assert_eq!(serde_json::to_string(&event).unwrap(), json);
+        assert_eq!(
+            event.kind.to_string(),
+            "ingress quota of 100 bytes exceeded: stop"
+        );
+    }
+}
@@ -1,16 +1,23 @@
 //! Network backend trait and associated types.
 //!
 //! The [`NetworkBackend`] trait defines the interface that all network
-//! implementations must satisfy. Product managed networking uses
-//! gvproxy only; the trait exists for tests and mocks (not for product
-//! multi-backend switching in v1).
+//! implementations must satisfy. Managed networking defaults to gvproxy;
+//! [`NetworkBackendKind`] selects an alternative per VM.
 
 use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::path::PathBuf;
 
+use bux_net_types::PortProtocol;
+use bux_net_types::constants::{GUEST_IPV4, GUEST_MAC, guest_ipv6};
 use serde::{Deserialize, Serialize};
 
-use crate::error::Result;
+use crate::activity::NetEvent;
+use crate::dns::DnsConfig;
+use crate::egress::EgressPolicy;
+use crate::error::{NetError, Result};
+use crate::http::HttpPolicy;
+use crate::shaping::TrafficLimits;
 
 // ============================================================================
 // NetworkBackend trait
@@ -45,6 +52,66 @@ pub trait NetworkBackend: Send + Sync + fmt::Debug {
     fn metrics(&self) -> Result<Option<NetworkMetrics>> {
         Ok(None)
     }
+
+    /// Applies allow-list, port and secret changes while the guest runs.
+    ///
+    /// New connections see the change immediately; established ones are
+    /// left alone.
+    ///
+    /// # Errors
+    ///
+    /// Backends without live reconfiguration return
+    /// [`NetError::Config`] (the default). Implementations also fail if a
+    /// new host port cannot be bound or the change is not representable.
+    fn update(&self, _update: &NetworkUpdate) -> Result<()> {
+        Err(NetError::Config(format!(
+            "{} backend does not support live updates",
+            self.name()
+        )))
+    }
+
+    /// Takes the activity events buffered since the last call.
+    ///
+    /// Empty unless the backend was built with
+    /// [`NetworkConfig::record_activity`]; backends that record nothing
+    /// keep the default.
+    ///
+    /// # Errors
+    ///
+    /// Returns a backend-specific error if the events cannot be fetched
+    /// from the underlying transport.
+    fn drain_events(&self) -> Result<Vec<NetEvent>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Which [`NetworkBackend`] implementation serves a VM.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum NetworkBackendKind {
+    /// `gvisor-tap-vsock` via `bux-gvproxy` (supports TLS interception).
+    #[default]
+    Gvproxy,
+    /// Pure-Rust userspace stack (no Go library, no TLS interception).
+    Userspace,
+}
+
+impl NetworkBackendKind {
+    /// Stable lowercase name, as used in config files and on the CLI.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gvproxy => "gvproxy",
+            Self::Userspace => "userspace",
+        }
+    }
+}
+
+impl fmt::Display for NetworkBackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 // ============================================================================
@@ -64,7 +131,7 @@ pub enum NetworkEndpoint {
         /// Socket type expected by the backend.
         connection_type: ConnectionType,
         /// MAC address for the guest NIC — must match the static lease
-        /// configured inside the backend (`GUEST_MAC`).
+        /// configured inside the backend ([`NetworkConfig::guest`]).
         mac_address: [u8; 6],
     },
 }
@@ -83,22 +150,135 @@ pub enum ConnectionType {
 // Configuration
 // ============================================================================
 
+/// The guest NIC's address and MAC on the virtual subnet.
+///
+/// Isolated VMs all use [`Self::DEFAULT`]; members of a private network
+/// each get a distinct [`Self::host`] so they can reach one another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GuestAddress {
+    /// IPv4 address inside [`SUBNET`](bux_net_types::constants::SUBNET).
+    pub ip: Ipv4Addr,
+    /// NIC MAC address.
+    pub mac: [u8; 6],
+}
+
+impl GuestAddress {
+    /// `GUEST_IP` / `GUEST_MAC`.
+    pub const DEFAULT: Self = Self {
+        ip: GUEST_IPV4,
+        mac: GUEST_MAC,
+    };
+
+    /// Host `octet` of the subnet, with a MAC derived from it.
+    ///
+    /// The MAC differs from `GUEST_MAC` and `GATEWAY_MAC` in its fifth
+    /// byte, so every octet yields a distinct, non-colliding address.
+    #[must_use]
+    pub const fn host(octet: u8) -> Self {
+        let [a, b, c, _] = GUEST_IPV4.octets();
+        let [m0, m1, m2, m3, _, _] = GUEST_MAC;
+        Self {
+            ip: Ipv4Addr::new(a, b, c, octet),
+            mac: [m0, m1, m2, m3, 0x0d, octet],
+        }
+    }
+
+    /// IPv6 address on a dual-stack network (same host part as [`Self::ip`]).
+    #[must_use]
+    pub const fn ipv6(&self) -> Ipv6Addr {
+        guest_ipv6(self.ip)
+    }
+}
+
+impl Default for GuestAddress {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// One published port: `bind:host_port` on the host → `guest_port` in
+/// the guest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PortForward {
+    /// Host address the listener binds (`0.0.0.0` = all interfaces).
+    pub bind: IpAddr,
+    /// Host port. Always concrete (never 0).
+    pub host_port: u16,
+    /// Guest port.
+    pub guest_port: u16,
+    /// Transport forwarded.
+    #[serde(default)]
+    pub protocol: PortProtocol,
+}
+
+impl PortForward {
+    /// TCP forward on all interfaces.
+    #[must_use]
+    pub const fn tcp(host_port: u16, guest_port: u16) -> Self {
+        Self {
+            bind: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            host_port,
+            guest_port,
+            protocol: PortProtocol::Tcp,
+        }
+    }
+
+    /// Bind only `ip` on the host.
+    #[must_use]
+    pub const fn with_bind(mut self, ip: IpAddr) -> Self {
+        self.bind = ip;
+        self
+    }
+
+    /// Forward `protocol` instead of TCP.
+    #[must_use]
+    pub const fn with_protocol(mut self, protocol: PortProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Host socket address the listener binds.
+    #[must_use]
+    pub const fn host_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind, self.host_port)
+    }
+
+    /// Whether `other` needs the same host socket (same address, port and
+    /// transport).
+    #[must_use]
+    pub fn same_listener(&self, other: &Self) -> bool {
+        self.host_addr() == other.host_addr() && self.protocol == other.protocol
+    }
+}
+
+impl fmt::Display for PortForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}->{}/{}",
+            self.host_addr(),
+            self.guest_port,
+            self.protocol
+        )
+    }
+}
+
 /// Network configuration passed to a concrete backend constructor.
 ///
-/// Port mappings are always concrete `(host, guest)` pairs — ephemeral
-/// host ports must be resolved by the Runtime before construction.
+/// Port mappings are always concrete — ephemeral host ports must be
+/// resolved by the Runtime before construction.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
-    /// Port mappings: `(host_port, guest_port)`. Always concrete values.
-    pub port_mappings: Vec<(u16, u16)>,
+    /// Published ports. Always concrete values.
+    pub port_mappings: Vec<PortForward>,
     /// Unix socket path — must be unique per VM to avoid collisions.
     pub socket_path: PathBuf,
-    /// Egress allow-list (hostnames / CIDRs). Empty = unrestricted egress.
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub allow_net: Vec<String>,
+    /// Egress rules and default. Empty = unrestricted egress.
+    #[serde(default)]
+    pub egress: EgressPolicy,
     /// MITM secrets (placeholder → value). Empty = no MITM.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub secrets: Vec<bux_gvproxy::SecretConfig>,
+    pub secrets: Vec<bux_net_types::SecretConfig>,
     /// PEM CA certificate when secrets non-empty.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub ca_cert_pem: String,
@@ -108,27 +288,51 @@ pub struct NetworkConfig {
     /// When true, spawn background stats logging (opt-in; off by default).
     #[serde(default)]
     pub stats_logging: bool,
+    /// When true, buffer [`NetEvent`]s for [`NetworkBackend::drain_events`].
+    #[serde(default)]
+    pub record_activity: bool,
+    /// HTTPS request rules and logging. Non-empty requires the CA PEMs.
+    #[serde(default, skip_serializing_if = "HttpPolicy::is_empty")]
+    pub http: HttpPolicy,
+    /// Guest address and MAC the backend leases and forwards to.
+    #[serde(default)]
+    pub guest: GuestAddress,
+    /// Rate limits and transfer quotas. Default = unlimited.
+    #[serde(default)]
+    pub traffic: TrafficLimits,
+    /// Local records, upstream resolver and host gateway.
+    #[serde(default)]
+    pub dns: DnsConfig,
+    /// Serve IPv6 alongside IPv4 (userspace backend only).
+    #[serde(default)]
+    pub ipv6: bool,
 }
 
 impl NetworkConfig {
-    /// Creates a topology-only configuration (no `allow_net` / secrets).
+    /// Creates a topology-only configuration (no egress rules / secrets).
     #[must_use]
-    pub const fn new(port_mappings: Vec<(u16, u16)>, socket_path: PathBuf) -> Self {
+    pub const fn new(port_mappings: Vec<PortForward>, socket_path: PathBuf) -> Self {
         Self {
             port_mappings,
             socket_path,
-            allow_net: Vec::new(),
+            egress: EgressPolicy::new(Vec::new()),
             secrets: Vec::new(),
             ca_cert_pem: String::new(),
             ca_key_pem: String::new(),
             stats_logging: false,
+            record_activity: false,
+            http: HttpPolicy::new(),
+            guest: GuestAddress::DEFAULT,
+            traffic: TrafficLimits::new(),
+            dns: DnsConfig::new(),
+            ipv6: false,
         }
     }
 
-    /// Sets egress allow-list rules.
+    /// Sets the egress policy.
     #[must_use]
-    pub fn with_allow_net(mut self, allow_net: Vec<String>) -> Self {
-        self.allow_net = allow_net;
+    pub fn with_egress(mut self, egress: EgressPolicy) -> Self {
+        self.egress = egress;
         self
     }
 
@@ -136,7 +340,7 @@ pub fn with_allow_net(mut self, allow_net: Vec<String>) -> Self {
     #[must_use]
     pub fn with_secrets(
         mut self,
-        secrets: Vec<bux_gvproxy::SecretConfig>,
+        secrets: Vec<bux_net_types::SecretConfig>,
         ca_cert_pem: String,
         ca_key_pem: String,
     ) -> Self {
@@ -152,6 +356,84 @@ pub const fn with_stats_logging(mut self, enabled: bool) -> Self {
         self.stats_logging = enabled;
         self
     }
+
+    /// Sets the HTTPS request policy. Hosts it names are intercepted, so
+    /// a CA must be attached with [`Self::with_secrets`] (secrets may be
+    /// empty).
+    #[must_use]
+    pub fn with_http_policy(mut self, http: HttpPolicy) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Sets the guest address (private-network members need their own).
+    #[must_use]
+    pub const fn with_guest_address(mut self, guest: GuestAddress) -> Self {
+        self.guest = guest;
+        self
+    }
+
+    /// Sets rate limits and transfer quotas.
+    #[must_use]
+    pub const fn with_traffic_limits(mut self, traffic: TrafficLimits) -> Self {
+        self.traffic = traffic;
+        self
+    }
+
+    /// Sets local DNS records, the upstream resolver and the host gateway.
+    #[must_use]
+    pub fn with_dns(mut self, dns: DnsConfig) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    /// Opt into recording network activity events.
+    #[must_use]
+    pub const fn with_activity(mut self, enabled: bool) -> Self {
+        self.record_activity = enabled;
+        self
+    }
+
+    /// Serve IPv6 at [`GuestAddress::ipv6`] alongside IPv4.
+    #[must_use]
+    pub const fn with_ipv6(mut self, enabled: bool) -> Self {
+        self.ipv6 = enabled;
+        self
+    }
+}
+
+/// Desired network state for [`NetworkBackend::update`].
+///
+/// `port_mappings` and `egress` replace the current values; `secrets`
+/// is left unchanged when `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkUpdate {
+    /// Published ports. Always concrete values.
+    pub port_mappings: Vec<PortForward>,
+    /// Egress rules and default. Empty = unrestricted egress.
+    pub egress: EgressPolicy,
+    /// Replacement MITM secrets (same CA as at creation).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<Vec<bux_net_types::SecretConfig>>,
+}
+
+impl NetworkUpdate {
+    /// Creates an update to the given ports and egress policy.
+    #[must_use]
+    pub const fn new(port_mappings: Vec<PortForward>, egress: EgressPolicy) -> Self {
+        Self {
+            port_mappings,
+            egress,
+            secrets: None,
+        }
+    }
+
+    /// Replace the MITM secret set.
+    #[must_use]
+    pub fn with_secrets(mut self, secrets: Vec<bux_net_types::SecretConfig>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
 }
 
 // ============================================================================
@@ -159,7 +441,7 @@ pub const fn with_stats_logging(mut self, enabled: bool) -> Self {
 // ============================================================================
 
 /// Snapshot of live network counters.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct NetworkMetrics {
     /// Total bytes sent from host to guest.
     pub bytes_sent: u64,
@@ -169,6 +451,8 @@ pub struct NetworkMetrics {
     pub tcp_connections: Option<u64>,
     /// Total failed TCP connection attempts.
     pub tcp_connection_errors: Option<u64>,
+    /// Whether a transfer quota has been used up.
+    pub quota_exceeded: bool,
 }
 
 #[cfg(test)]
@@ -182,18 +466,60 @@ mod tests {
 
     #[test]
     fn network_config_defaults() {
-        let c = NetworkConfig::new(vec![(8080, 80)], PathBuf::from("/tmp/n.sock"));
-        assert!(c.allow_net.is_empty());
+        let c = NetworkConfig::new(
+            vec![PortForward::tcp(8080, 80)],
+            PathBuf::from("/tmp/n.sock"),
+        );
+        assert!(c.egress.is_unrestricted());
         assert!(c.secrets.is_empty());
         assert!(!c.stats_logging);
+        assert!(!c.record_activity);
+        assert_eq!(c.guest, GuestAddress::DEFAULT);
+        assert!(c.traffic.is_unlimited());
+        assert!(c.dns.is_empty());
+    }
+
+    #[test]
+    fn port_forward_display_and_listener_identity() {
+        let loopback = PortForward::tcp(8080, 80).with_bind(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(loopback.to_string(), "127.0.0.1:8080->80/tcp");
+        let udp = PortForward::tcp(53, 53)
+            .with_bind("::1".parse().unwrap())
+            .with_protocol(PortProtocol::Udp);
+        assert_eq!(udp.to_string(), "[::1]:53->53/udp");
+        assert!(loopback.same_listener(&PortForward {
+            guest_port: 81,
+            ..loopback
+        }));
+        assert!(!loopback.same_listener(&PortForward::tcp(8080, 80)));
+        assert!(!udp.same_listener(&PortForward {
+            protocol: PortProtocol::Tcp,
+            ..udp
+        }));
+    }
+
+    #[test]
+    fn segment_hosts_get_distinct_addresses() {
+        use bux_net_types::constants::GATEWAY_MAC;
+
+        let host = GuestAddress::host(7);
+        assert_eq!(host.ip, Ipv4Addr::new(192, 168, 127, 7));
+        assert_eq!(host.mac[5], 7);
+        for octet in [2, 0xdd, 0xee] {
+            let mac = GuestAddress::host(octet).mac;
+            assert_ne!(mac, GUEST_MAC);
+            assert_ne!(mac, GATEWAY_MAC);
+        }
     }
 
     #[test]
     fn network_config_builder() {
         let c = NetworkConfig::new(vec![], PathBuf::from("/tmp/n.sock"))
-            .with_allow_net(vec!["a.com".into()])
-            .with_stats_logging(true);
-        assert_eq!(c.allow_net, vec!["a.com".to_owned()]);
+            .with_egress(EgressPolicy::parse(["a.com"]).unwrap())
+            .with_stats_logging(true)
+            .with_activity(true);
+        assert_eq!(c.egress.rule_strings(), vec!["a.com".to_owned()]);
         assert!(c.stats_logging);
+        assert!(c.record_activity);
     }
 }
@@ -0,0 +1,289 @@
+//! Per-VM DNS records, upstream resolver and host gateway.
+//!
+//! [`DnsConfig`] adds local `A` records to the gateway resolver, replaces
+//! the host's upstream resolver, and opts into the host gateway:
+//! [`HOST_GATEWAY_NAME`] then resolves to [`HOST_IPV4`], an address both
+//! backends translate to the host's loopback. Connections through it skip
+//! the egress rules, like the gateway itself.
+//!
+//! Local names are answered before the egress policy is consulted. Every
+//! other name the policy refuses to look up gets `NXDOMAIN`.
+//!
+//! A single-label record (`db`) also answers under each search domain
+//! (`db.local`), which is the name the guest resolver asks first.
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::str::FromStr;
+
+use bux_net_types::constants::DNS_SEARCH_DOMAINS;
+pub use bux_net_types::constants::{HOST_GATEWAY_NAME, HOST_IPV4};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{NetError, Result};
+
+/// Port used when an upstream resolver is given without one.
+const DNS_PORT: u16 = 53;
+
+/// Lowercases `name` and drops a trailing dot.
+fn normalize(name: &str) -> String {
+    name.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Whether `name` is a valid hostname (letters, digits, `-`, `_`).
+fn is_hostname(name: &str) -> bool {
+    name.len() <= 253
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+}
+
+/// Local `A` record.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DnsRecord {
+    /// Fully qualified or single-label name, lowercase, no trailing dot.
+    pub name: String,
+    /// Address the name resolves to.
+    pub ip: Ipv4Addr,
+}
+
+impl DnsRecord {
+    /// Record mapping `name` (normalized) to `ip`.
+    #[must_use]
+    pub fn new(name: &str, ip: Ipv4Addr) -> Self {
+        Self {
+            name: normalize(name),
+            ip,
+        }
+    }
+
+    /// Whether the record answers `name` (normalized), directly or under
+    /// a search domain.
+    fn answers(&self, name: &str) -> bool {
+        name == self.name
+            || (!self.name.contains('.')
+                && DNS_SEARCH_DOMAINS.iter().any(|domain| {
+                    name.strip_suffix(domain)
+                        .and_then(|rest| rest.strip_suffix('.'))
+                        == Some(self.name.as_str())
+                }))
+    }
+}
+
+impl fmt::Display for DnsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.ip)
+    }
+}
+
+impl FromStr for DnsRecord {
+    type Err = NetError;
+
+    /// Parses `name=ip` (or `name:ip`).
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, ip) = s
+            .split_once('=')
+            .or_else(|| s.split_once(':'))
+            .ok_or_else(|| NetError::Config(format!("DNS record {s:?}: expected NAME=IP")))?;
+        let ip = ip
+            .trim()
+            .parse()
+            .map_err(|_| NetError::Config(format!("DNS record {s:?}: invalid IPv4 address")))?;
+        Ok(Self::new(name, ip))
+    }
+}
+
+/// DNS settings for one VM. The default adds nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsConfig {
+    /// Local records, answered ahead of the egress policy.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<DnsRecord>,
+    /// Resolver queries are relayed to instead of the host's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<SocketAddr>,
+    /// Resolve [`HOST_GATEWAY_NAME`] and route [`HOST_IPV4`] to the host's
+    /// loopback.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub host_gateway: bool,
+}
+
+impl DnsConfig {
+    /// No records, the host's resolver, no host gateway.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            records: Vec::new(),
+            upstream: None,
+            host_gateway: false,
+        }
+    }
+
+    /// Adds a local record.
+    #[must_use]
+    pub fn with_record(mut self, name: &str, ip: Ipv4Addr) -> Self {
+        self.records.push(DnsRecord::new(name, ip));
+        self
+    }
+
+    /// Relays queries to `upstream` instead of the host's resolver.
+    #[must_use]
+    pub const fn with_upstream(mut self, upstream: SocketAddr) -> Self {
+        self.upstream = Some(upstream);
+        self
+    }
+
+    /// Enables [`HOST_GATEWAY_NAME`].
+    #[must_use]
+    pub const fn with_host_gateway(mut self, enabled: bool) -> Self {
+        self.host_gateway = enabled;
+        self
+    }
+
+    /// Whether nothing is configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty() && self.upstream.is_none() && !self.host_gateway
+    }
+
+    /// Checks record names.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] for an invalid name, a duplicate, or a
+    /// record for [`HOST_GATEWAY_NAME`].
+    pub fn validate(&self) -> Result<()> {
+        for (i, record) in self.records.iter().enumerate() {
+            if !is_hostname(&record.name) {
+                return Err(NetError::Config(format!(
+                    "DNS record {:?}: invalid hostname",
+                    record.name
+                )));
+            }
+            if record.name == HOST_GATEWAY_NAME {
+                return Err(NetError::Config(format!(
+                    "{HOST_GATEWAY_NAME} is reserved; enable the host gateway instead"
+                )));
+            }
+            if self.records.iter().take(i).any(|r| r.name == record.name) {
+                return Err(NetError::Config(format!(
+                    "duplicate DNS record {:?}",
+                    record.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Local answer for `name`, if it has one.
+    #[must_use]
+    pub fn lookup(&self, name: &str) -> Option<Ipv4Addr> {
+        let name = normalize(name);
+        if self.host_gateway && name == HOST_GATEWAY_NAME {
+            return Some(HOST_IPV4);
+        }
+        self.records
+            .iter()
+            .find(|record| record.answers(&name))
+            .map(|record| record.ip)
+    }
+}
+
+/// Parses an upstream resolver: an IP address, with an optional port
+/// (`1.1.1.1`, `1.1.1.1:5353`, `[2606:4700::1111]:53`).
+///
+/// # Errors
+///
+/// Returns [`NetError::Config`] if `s` is neither form.
+pub fn parse_upstream(s: &str) -> Result<SocketAddr> {
+    let s = s.trim();
+    s.parse::<SocketAddr>()
+        .or_else(|_| s.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, DNS_PORT)))
+        .map_err(|_| NetError::Config(format!("invalid DNS resolver {s:?}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_records_and_host_gateway() {
+        let ip = Ipv4Addr::new(10, 0, 0, 5);
+        let config = DnsConfig::new()
+            .with_record("DB.Example.test.", ip)
+            .with_record("cache", Ipv4Addr::new(10, 0, 0, 6));
+        config.validate().unwrap();
+
+        assert_eq!(config.lookup("db.example.test"), Some(ip));
+        assert_eq!(config.lookup("db.example.test."), Some(ip));
+        assert_eq!(
+            config.lookup("cache.local"),
+            Some(Ipv4Addr::new(10, 0, 0, 6))
+        );
+        assert_eq!(config.lookup("db.example.test.local"), None);
+        assert_eq!(config.lookup(HOST_GATEWAY_NAME), None);
+        assert_eq!(
+            config.with_host_gateway(true).lookup("Host.Bux.Internal."),
+            Some(HOST_IPV4)
+        );
+    }
+
+    #[test]
+    fn validates_and_parses() {
+        let ip = Ipv4Addr::LOCALHOST;
+        assert!(
+            DnsConfig::new()
+                .with_record("bad name", ip)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            DnsConfig::new()
+                .with_record("-x.test", ip)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            DnsConfig::new()
+                .with_record(HOST_GATEWAY_NAME, ip)
+                .validate()
+                .is_err()
+        );
+        assert!(
+            DnsConfig::new()
+                .with_record("a.test", ip)
+                .with_record("A.test", ip)
+                .validate()
+                .is_err()
+        );
+
+        let record: DnsRecord = "api.test=10.1.2.3".parse().unwrap();
+        assert_eq!(
+            record,
+            DnsRecord::new("api.test", Ipv4Addr::new(10, 1, 2, 3))
+        );
+        assert_eq!(record.to_string(), "api.test=10.1.2.3");
+        assert!("api.test".parse::<DnsRecord>().is_err());
+        assert!("api.test=::1".parse::<DnsRecord>().is_err());
+
+        assert_eq!(
+            parse_upstream("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse().unwrap()
+        );
+        assert_eq!(
+            parse_upstream("[::1]:5353").unwrap(),
+            "[::1]:5353".parse().unwrap()
+        );
+        assert!(parse_upstream("dns.google").is_err());
+
+        let json = serde_json::to_string(&DnsConfig::new()).unwrap();
+        assert_eq!(json, "{}");
+    }
+}
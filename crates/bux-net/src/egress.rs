@@ -0,0 +1,780 @@
+//! Structured egress rules and their evaluation.
+//!
+//! One rule per string:
+//!
+//! ```text
+//! [!]destination[:port[-port]][/tcp|/udp]
+//! ```
+//!
+//! `destination` is `*` (anything), an IP address (`[v6]` when a port
+//! follows), a CIDR, a hostname, or `*.suffix` (subdomains only). A
+//! leading `!` makes a deny rule; no port means every port and no protocol
+//! means TCP and UDP. Plain `allow_net` entries (`example.com`,
+//! `10.0.0.0/8`) are valid rules.
+//!
+//! [`EgressPolicy::evaluate`] checks deny rules first, then allow rules,
+//! each in declaration order, and falls back to the default action. The
+//! default denies whenever an allow rule exists unless set explicitly, so
+//! an allow-list keeps allow-list semantics and a deny-only list blocks
+//! just what it names. Backends share this module, so the same syntax and
+//! order apply everywhere (`egress_rule.go` mirrors it for gvproxy).
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{NetError, Result};
+
+/// Whether a rule admits or blocks matching traffic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EgressAction {
+    /// Let matching traffic through.
+    Allow,
+    /// Block matching traffic.
+    Deny,
+}
+
+/// Transport a rule applies to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum EgressProtocol {
+    /// TCP and UDP.
+    #[default]
+    Any,
+    /// TCP only.
+    Tcp,
+    /// UDP only.
+    Udp,
+}
+
+impl EgressProtocol {
+    /// Whether a rule for `self` covers traffic using `flow`.
+    #[must_use]
+    pub fn covers(self, flow: Self) -> bool {
+        self == Self::Any || self == flow
+    }
+}
+
+/// Inclusive destination port range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortRange {
+    /// First port.
+    pub start: u16,
+    /// Last port (`>= start`).
+    pub end: u16,
+}
+
+impl PortRange {
+    /// Whether `port` falls in the range.
+    #[must_use]
+    pub const fn contains(self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+}
+
+/// What a rule's destination matches.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EgressTarget {
+    /// Every destination (`*`).
+    Any,
+    /// One address.
+    Ip(IpAddr),
+    /// An address range; `network` has its host bits cleared.
+    Cidr {
+        /// Network address.
+        network: IpAddr,
+        /// Prefix length.
+        prefix_len: u8,
+    },
+    /// One hostname (lowercase, no trailing dot).
+    Host(String),
+    /// Strict subdomains of a domain (`*.example.com` stores `example.com`).
+    Wildcard(String),
+}
+
+impl EgressTarget {
+    /// Whether this is a hostname or wildcard target.
+    #[must_use]
+    pub const fn is_hostname(&self) -> bool {
+        matches!(self, Self::Host(_) | Self::Wildcard(_))
+    }
+
+    /// Whether the target matches hostname `host`.
+    #[must_use]
+    pub fn matches_host(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        match self {
+            Self::Any => true,
+            Self::Host(name) => !host.is_empty() && host == *name,
+            Self::Wildcard(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.')),
+            Self::Ip(_) | Self::Cidr { .. } => false,
+        }
+    }
+
+    /// Whether the target matches address `ip`.
+    #[must_use]
+    pub fn matches_ip(&self, ip: IpAddr) -> bool {
+        match *self {
+            Self::Any => true,
+            Self::Ip(addr) => addr == ip,
+            Self::Cidr {
+                network,
+                prefix_len,
+            } => mask(ip, prefix_len).is_some_and(|masked| masked == network),
+            Self::Host(_) | Self::Wildcard(_) => false,
+        }
+    }
+}
+
+/// One parsed egress rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct EgressRule {
+    /// Allow or deny.
+    pub action: EgressAction,
+    /// Destination matcher.
+    pub target: EgressTarget,
+    /// Destination ports; `None` = every port.
+    pub ports: Option<PortRange>,
+    /// Transport.
+    pub protocol: EgressProtocol,
+}
+
+impl EgressRule {
+    /// Allow every port and protocol to `target`.
+    #[must_use]
+    pub const fn allow(target: EgressTarget) -> Self {
+        Self {
+            action: EgressAction::Allow,
+            target,
+            ports: None,
+            protocol: EgressProtocol::Any,
+        }
+    }
+
+    /// Deny every port and protocol to `target`.
+    #[must_use]
+    pub const fn deny(target: EgressTarget) -> Self {
+        Self {
+            action: EgressAction::Deny,
+            target,
+            ports: None,
+            protocol: EgressProtocol::Any,
+        }
+    }
+
+    /// Restrict the rule to `ports`.
+    #[must_use]
+    pub const fn with_ports(mut self, ports: PortRange) -> Self {
+        self.ports = Some(ports);
+        self
+    }
+
+    /// Restrict the rule to one transport.
+    #[must_use]
+    pub const fn with_protocol(mut self, protocol: EgressProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Whether the rule matches `flow`.
+    #[must_use]
+    pub fn matches(&self, flow: &EgressFlow<'_>) -> bool {
+        self.protocol.covers(flow.protocol)
+            && self.ports.is_none_or(|range| range.contains(flow.port))
+            && (flow.ip.is_some_and(|ip| self.target.matches_ip(ip))
+                || flow.host.is_some_and(|host| self.target.matches_host(host)))
+    }
+
+    /// Whether the rule covers `host` on every port and protocol.
+    fn covers_host(&self, host: &str) -> bool {
+        self.ports.is_none()
+            && self.protocol == EgressProtocol::Any
+            && self.target.matches_host(host)
+    }
+}
+
+impl FromStr for EgressRule {
+    type Err = NetError;
+
+    fn from_str(raw: &str) -> Result<Self> {
+        let invalid = |reason: &str| NetError::EgressRule {
+            rule: raw.to_owned(),
+            reason: reason.to_owned(),
+        };
+        let trimmed = raw.trim();
+        let (action, mut rest) = trimmed
+            .strip_prefix('!')
+            .map_or((EgressAction::Allow, trimmed), |stripped| {
+                (EgressAction::Deny, stripped.trim_start())
+            });
+
+        let mut protocol = EgressProtocol::Any;
+        let lower = rest.to_ascii_lowercase();
+        for (suffix, proto) in [("/tcp", EgressProtocol::Tcp), ("/udp", EgressProtocol::Udp)] {
+            if lower.ends_with(suffix) {
+                protocol = proto;
+                rest = rest.get(..rest.len() - suffix.len()).unwrap_or_default();
+                break;
+            }
+        }
+
+        let (dest, ports) = split_ports(rest).map_err(&invalid)?;
+        let ports = ports
+            .map(|spec| parse_ports(spec).ok_or_else(|| invalid("invalid port or port range")))
+            .transpose()?;
+        let target = parse_target(dest).map_err(invalid)?;
+        Ok(Self {
+            action,
+            target,
+            ports,
+            protocol,
+        })
+    }
+}
+
+impl TryFrom<String> for EgressRule {
+    type Error = NetError;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<EgressRule> for String {
+    fn from(rule: EgressRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl fmt::Display for EgressRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.action == EgressAction::Deny {
+            f.write_str("!")?;
+        }
+        let dest = match &self.target {
+            EgressTarget::Any => "*".to_owned(),
+            EgressTarget::Ip(ip) => ip.to_string(),
+            EgressTarget::Cidr {
+                network,
+                prefix_len,
+            } => format!("{network}/{prefix_len}"),
+            EgressTarget::Host(host) => host.clone(),
+            EgressTarget::Wildcard(domain) => format!("*.{domain}"),
+        };
+        let bracket = self.ports.is_some() && dest.contains(':');
+        if bracket {
+            write!(f, "[{dest}]")?;
+        } else {
+            f.write_str(&dest)?;
+        }
+        match self.ports {
+            Some(range) if range.start == range.end => write!(f, ":{}", range.start)?,
+            Some(range) => write!(f, ":{}-{}", range.start, range.end)?,
+            None => {}
+        }
+        match self.protocol {
+            EgressProtocol::Tcp => f.write_str("/tcp"),
+            EgressProtocol::Udp => f.write_str("/udp"),
+            EgressProtocol::Any => Ok(()),
+        }
+    }
+}
+
+/// A connection attempt to evaluate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EgressFlow<'a> {
+    /// Destination hostname, when known (DNS, SNI, HTTP `Host`).
+    pub host: Option<&'a str>,
+    /// Destination address, when known.
+    pub ip: Option<IpAddr>,
+    /// Destination port.
+    pub port: u16,
+    /// Transport ([`EgressProtocol::Tcp`] or [`EgressProtocol::Udp`]).
+    pub protocol: EgressProtocol,
+}
+
+impl<'a> EgressFlow<'a> {
+    /// A TCP connection to `ip:port`.
+    #[must_use]
+    pub const fn tcp(ip: IpAddr, port: u16) -> Self {
+        Self {
+            host: None,
+            ip: Some(ip),
+            port,
+            protocol: EgressProtocol::Tcp,
+        }
+    }
+
+    /// A UDP datagram to `ip:port`.
+    #[must_use]
+    pub const fn udp(ip: IpAddr, port: u16) -> Self {
+        Self {
+            host: None,
+            ip: Some(ip),
+            port,
+            protocol: EgressProtocol::Udp,
+        }
+    }
+
+    /// Attach the destination hostname.
+    #[must_use]
+    pub const fn with_host(mut self, host: &'a str) -> Self {
+        self.host = Some(host);
+        self
+    }
+}
+
+/// Outcome of [`EgressPolicy::evaluate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EgressDecision {
+    /// Whether the flow may proceed.
+    pub action: EgressAction,
+    /// Index into [`EgressPolicy::rules`] of the deciding rule; `None`
+    /// when the default applied.
+    pub rule: Option<usize>,
+}
+
+impl EgressDecision {
+    /// Whether the flow may proceed.
+    #[must_use]
+    pub fn is_allowed(self) -> bool {
+        self.action == EgressAction::Allow
+    }
+}
+
+/// Ordered egress rules plus the default action.
+///
+/// The empty policy allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EgressPolicy {
+    /// Rules in declaration order.
+    #[serde(default)]
+    rules: Vec<EgressRule>,
+    /// Explicit default; `None` infers it from the rules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default_deny: Option<bool>,
+}
+
+impl EgressPolicy {
+    /// Policy over `rules` with the inferred default.
+    #[must_use]
+    pub const fn new(rules: Vec<EgressRule>) -> Self {
+        Self {
+            rules,
+            default_deny: None,
+        }
+    }
+
+    /// Parses rule strings (blank entries are skipped).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::EgressRule`] for the first invalid rule.
+    pub fn parse<I, S>(rules: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let rules = rules
+            .into_iter()
+            .filter(|rule| !rule.as_ref().trim().is_empty())
+            .map(|rule| rule.as_ref().parse())
+            .collect::<Result<_>>()?;
+        Ok(Self::new(rules))
+    }
+
+    /// Append a rule.
+    #[must_use]
+    pub fn rule(mut self, rule: EgressRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Set the action for traffic no rule matches.
+    #[must_use]
+    pub const fn with_default_deny(mut self, deny: bool) -> Self {
+        self.default_deny = Some(deny);
+        self
+    }
+
+    /// Rules in declaration order.
+    #[must_use]
+    pub fn rules(&self) -> &[EgressRule] {
+        &self.rules
+    }
+
+    /// The explicit default, if one was set.
+    #[must_use]
+    pub const fn explicit_default_deny(&self) -> Option<bool> {
+        self.default_deny
+    }
+
+    /// Whether unmatched traffic is denied.
+    #[must_use]
+    pub fn default_deny(&self) -> bool {
+        self.default_deny.unwrap_or_else(|| {
+            self.rules
+                .iter()
+                .any(|rule| rule.action == EgressAction::Allow)
+        })
+    }
+
+    /// Whether the policy lets everything through without evaluation.
+    #[must_use]
+    pub fn is_unrestricted(&self) -> bool {
+        self.rules.is_empty() && !self.default_deny()
+    }
+
+    /// Rules rendered back to strings (the `allow_net` wire format).
+    #[must_use]
+    pub fn rule_strings(&self) -> Vec<String> {
+        self.rules.iter().map(ToString::to_string).collect()
+    }
+
+    /// Decides `flow`: first matching deny rule, else first matching
+    /// allow rule, else the default.
+    #[must_use]
+    pub fn evaluate(&self, flow: &EgressFlow<'_>) -> EgressDecision {
+        for action in [EgressAction::Deny, EgressAction::Allow] {
+            let hit = self
+                .rules
+                .iter()
+                .position(|rule| rule.action == action && rule.matches(flow));
+            if hit.is_some() {
+                return EgressDecision { action, rule: hit };
+            }
+        }
+        EgressDecision {
+            action: if self.default_deny() {
+                EgressAction::Deny
+            } else {
+                EgressAction::Allow
+            },
+            rule: None,
+        }
+    }
+
+    /// Whether the resolver should answer queries for `host`.
+    ///
+    /// Refused when a deny rule covers the name on every port and
+    /// protocol; otherwise answered if an allow rule names it or the
+    /// default allows.
+    #[must_use]
+    pub fn permits_lookup(&self, host: &str) -> bool {
+        if self.lookup_denial(host).is_some() {
+            return false;
+        }
+        !self.default_deny()
+            || self
+                .rules
+                .iter()
+                .any(|rule| rule.action == EgressAction::Allow && rule.target.matches_host(host))
+    }
+
+    /// The deny rule that makes [`Self::permits_lookup`] refuse `host`,
+    /// if any.
+    #[must_use]
+    pub fn lookup_denial(&self, host: &str) -> Option<&EgressRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.action == EgressAction::Deny && rule.covers_host(host))
+    }
+}
+
+/// Splits `dest[:ports]`; bare IPv6 addresses carry no port.
+fn split_ports(rule: &str) -> std::result::Result<(&str, Option<&str>), &'static str> {
+    if let Some(inner) = rule.strip_prefix('[') {
+        let (dest, rest) = inner.split_once(']').ok_or("unclosed '['")?;
+        return match rest {
+            "" => Ok((dest, None)),
+            _ => rest
+                .strip_prefix(':')
+                .map(|ports| (dest, Some(ports)))
+                .ok_or("unexpected text after ']'"),
+        };
+    }
+    if rule.matches(':').count() > 1 {
+        return Ok((rule, None));
+    }
+    Ok(rule
+        .split_once(':')
+        .map_or((rule, None), |(dest, ports)| (dest, Some(ports))))
+}
+
+/// Parses `443` or `8000-8100` (ports start at 1).
+fn parse_ports(spec: &str) -> Option<PortRange> {
+    let (start, end) = spec.split_once('-').unwrap_or((spec, spec));
+    let start: u16 = start.trim().parse().ok().filter(|port| *port > 0)?;
+    let end: u16 = end.trim().parse().ok().filter(|port| *port >= start)?;
+    Some(PortRange { start, end })
+}
+
+/// Parses a destination.
+fn parse_target(dest: &str) -> std::result::Result<EgressTarget, &'static str> {
+    if dest.is_empty() {
+        return Err("empty destination");
+    }
+    if dest == "*" {
+        return Ok(EgressTarget::Any);
+    }
+    if let Ok(ip) = dest.parse::<IpAddr>() {
+        return Ok(EgressTarget::Ip(ip));
+    }
+    if let Some((addr, len)) = dest.split_once('/') {
+        let addr: IpAddr = addr.parse().map_err(|_| "invalid CIDR address")?;
+        let prefix_len: u8 = len.parse().map_err(|_| "invalid CIDR prefix")?;
+        let network = mask(addr, prefix_len).ok_or("CIDR prefix too long")?;
+        return Ok(EgressTarget::Cidr {
+            network,
+            prefix_len,
+        });
+    }
+    let (wildcard, host) = dest
+        .strip_prefix("*.")
+        .map_or((false, dest), |domain| (true, domain));
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if !valid_hostname(&host) {
+        return Err("invalid hostname");
+    }
+    Ok(if wildcard {
+        EgressTarget::Wildcard(host)
+    } else {
+        EgressTarget::Host(host)
+    })
+}
+
+/// RFC 1123 hostname check (underscores tolerated for service records).
+fn valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+}
+
+/// Clears the host bits of `ip` beyond `prefix_len`; `None` if the prefix
+/// is too long for the family.
+fn mask(ip: IpAddr, prefix_len: u8) -> Option<IpAddr> {
+    match ip {
+        IpAddr::V4(v4) if prefix_len <= 32 => {
+            let bits = u32::MAX
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            Some(IpAddr::V4((u32::from(v4) & bits).into()))
+        }
+        IpAddr::V6(v6) if prefix_len <= 128 => {
+            let bits = u128::MAX
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            Some(IpAddr::V6((u128::from(v6) & bits).into()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    fn ip(addr: &str) -> IpAddr {
+        addr.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_ports_protocols_and_denies() {
+        let rule: EgressRule = "*.github.com:443/tcp".parse().unwrap();
+        assert_eq!(rule.action, EgressAction::Allow);
+        assert_eq!(rule.target, EgressTarget::Wildcard("github.com".into()));
+        assert_eq!(
+            rule.ports,
+            Some(PortRange {
+                start: 443,
+                end: 443
+            })
+        );
+        assert_eq!(rule.protocol, EgressProtocol::Tcp);
+
+        let metadata: EgressRule = "!169.254.169.254".parse().unwrap();
+        assert_eq!(
+            metadata,
+            EgressRule::deny(EgressTarget::Ip(ip("169.254.169.254")))
+        );
+
+        let cidr: EgressRule = "10.1.2.3/8:5000-5432".parse().unwrap();
+        assert_eq!(
+            cidr.target,
+            EgressTarget::Cidr {
+                network: ip("10.0.0.0"),
+                prefix_len: 8
+            }
+        );
+        assert_eq!(
+            cidr.ports,
+            Some(PortRange {
+                start: 5000,
+                end: 5432
+            })
+        );
+
+        let v6: EgressRule = "[2001:db8::1]:53/UDP".parse().unwrap();
+        assert_eq!(v6.target, EgressTarget::Ip(ip("2001:db8::1")));
+        assert_eq!(v6.protocol, EgressProtocol::Udp);
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for bad in [
+            "",
+            "!",
+            "host:0",
+            "host:9-1",
+            "host:http",
+            "10.0.0.0/33",
+            "[::1",
+            "bad host",
+            "-x.com",
+            "*.",
+        ] {
+            assert!(
+                matches!(bad.parse::<EgressRule>(), Err(NetError::EgressRule { .. })),
+                "{bad:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "example.com",
+            "!*.evil.com:443/tcp",
+            "10.0.0.0/8:5432",
+            "[2001:db8::/32]:80-90/udp",
+            "*",
+        ] {
+            let rule: EgressRule = text.parse().unwrap();
+            assert_eq!(rule.to_string(), text);
+        }
+        let rule: EgressRule = "API.Example.COM.".parse().unwrap();
+        assert_eq!(rule.to_string(), "api.example.com");
+    }
+
+    #[test]
+    fn deny_rules_win_regardless_of_order() {
+        let policy = EgressPolicy::parse(["*", "!169.254.169.254"]).unwrap();
+        let metadata = EgressFlow::tcp(ip("169.254.169.254"), 80);
+        assert_eq!(
+            policy.evaluate(&metadata),
+            EgressDecision {
+                action: EgressAction::Deny,
+                rule: Some(1)
+            }
+        );
+        assert!(
+            policy
+                .evaluate(&EgressFlow::tcp(ip("1.1.1.1"), 22))
+                .is_allowed()
+        );
+    }
+
+    #[test]
+    fn ports_protocols_and_hostnames_narrow_matches() {
+        let policy = EgressPolicy::parse(["*.github.com:443/tcp", "10.0.0.0/8:5432"]).unwrap();
+        assert!(policy.default_deny());
+
+        let github = EgressFlow::tcp(ip("140.82.112.3"), 443).with_host("api.github.com");
+        assert_eq!(policy.evaluate(&github).rule, Some(0));
+        let plain_http = EgressFlow::tcp(ip("140.82.112.3"), 80).with_host("api.github.com");
+        assert!(!policy.evaluate(&plain_http).is_allowed());
+        let apex = EgressFlow::tcp(ip("140.82.112.3"), 443).with_host("github.com");
+        assert!(!policy.evaluate(&apex).is_allowed());
+        let quic = EgressFlow::udp(ip("140.82.112.3"), 443).with_host("api.github.com");
+        assert!(!policy.evaluate(&quic).is_allowed());
+
+        assert!(
+            policy
+                .evaluate(&EgressFlow::tcp(ip("10.9.9.9"), 5432))
+                .is_allowed()
+        );
+        let ssh = EgressFlow::tcp(ip("10.9.9.9"), 22);
+        assert_eq!(
+            policy.evaluate(&ssh),
+            EgressDecision {
+                action: EgressAction::Deny,
+                rule: None
+            }
+        );
+    }
+
+    #[test]
+    fn default_is_inferred_or_explicit() {
+        assert!(EgressPolicy::default().is_unrestricted());
+
+        let deny_only = EgressPolicy::parse(["!1.1.1.1"]).unwrap();
+        assert!(!deny_only.default_deny());
+        assert!(
+            deny_only
+                .evaluate(&EgressFlow::tcp(ip("8.8.8.8"), 53))
+                .is_allowed()
+        );
+
+        let locked = EgressPolicy::default().with_default_deny(true);
+        assert!(!locked.is_unrestricted());
+        let flow = EgressFlow::udp(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)), 53);
+        assert!(!locked.evaluate(&flow).is_allowed());
+    }
+
+    #[test]
+    fn lookups_follow_host_rules() {
+        let policy = EgressPolicy::parse([
+            "pypi.org:443",
+            "*.pythonhosted.org",
+            "!bad.pythonhosted.org",
+        ])
+        .unwrap();
+        assert!(policy.permits_lookup("pypi.org."));
+        assert!(policy.permits_lookup("files.pythonhosted.org"));
+        assert!(!policy.permits_lookup("bad.pythonhosted.org"));
+        assert!(!policy.permits_lookup("evil.com"));
+        assert_eq!(
+            policy
+                .lookup_denial("bad.pythonhosted.org")
+                .map(ToString::to_string),
+            Some("!bad.pythonhosted.org".to_owned())
+        );
+        assert!(policy.lookup_denial("evil.com").is_none());
+
+        let open = EgressPolicy::parse(["!tracker.example:443"]).unwrap();
+        assert!(open.permits_lookup("tracker.example"));
+        assert!(open.permits_lookup("evil.com"));
+    }
+
+    #[test]
+    fn serializes_rules_as_strings() {
+        let policy = EgressPolicy::parse(["!10.0.0.0/8", "example.com:443"])
+            .unwrap()
+            .with_default_deny(true);
+        let json = serde_json::to_string(&policy).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rules":["!10.0.0.0/8","example.com:443"],"default_deny":true}"#
+        );
+        assert_eq!(serde_json::from_str::<EgressPolicy>(&json).unwrap(), policy);
+    }
+}
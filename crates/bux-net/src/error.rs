@@ -6,6 +6,7 @@
 pub enum NetError {
     /// A gvproxy FFI or config-serialisation error bubbled up from the
     /// [`bux_gvproxy`] crate.
+    #[cfg(feature = "gvproxy")]
     #[error(transparent)]
     Gvproxy(#[from] bux_gvproxy::Error),
 
@@ -13,6 +14,15 @@ pub enum NetError {
     #[error("invalid network config: {0}")]
     Config(String),
 
+    /// An egress rule string failed to parse.
+    #[error("invalid egress rule {rule:?}: {reason}")]
+    EgressRule {
+        /// The rule as given.
+        rule: String,
+        /// What is wrong with it.
+        reason: String,
+    },
+
     /// Socket path exceeds the `sun_path` limit.
     #[error("socket path too long: {0}")]
     SocketPath(String),
@@ -21,6 +31,10 @@ pub enum NetError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// The out-of-process backend failed or broke the control protocol.
+    #[error("network helper: {0}")]
+    Helper(String),
+
     /// Catch-all for unexpected failures.
     #[error("{0}")]
     Other(String),
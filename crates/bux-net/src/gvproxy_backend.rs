@@ -3,16 +3,42 @@
 //! The raw FFI / Go toolchain lives in the `bux-gvproxy` crate. This
 //! module just wires a [`GvproxyInstance`] to the backend-neutral
 //! [`NetworkBackend`] trait.
+//!
+//! Activity comes from the Go forwarder, which sees outbound TCP only:
+//! DNS is answered upstream in gvisor-tap-vsock and published ports are
+//! forwarded without a hook, so no DNS or accept events are reported.
+//!
+//! Traffic limits are enforced by the bridge on the guest link (see
+//! `shaping.go`); a used-up blocking quota refuses new TCP connections
+//! only, as UDP is relayed by upstream gvisor-tap-vsock.
+//!
+//! Local DNS records become gvisor-tap-vsock zones named after each
+//! record's parent domain. A zone owns every name below it, so other
+//! names under that domain get `NXDOMAIN` instead of being forwarded.
+//! gvisor-tap-vsock always forwards to the host's resolver, so an
+//! upstream override is rejected, and its network stack is IPv4-only, so
+//! is IPv6.
 
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use bux_gvproxy::{GvproxyConfig, GvproxyInstance, NetworkStats, constants::GUEST_MAC, version};
+use bux_gvproxy::{
+    ActivityEvent, ActivityKind, DnsRecordConfig, DnsZone, GvproxyConfig, GvproxyInstance,
+    GvproxyUpdate, NetworkStats, OnQuota, PortMapping, ShapingConfig, version,
+};
+use bux_net_types::constants::{DNS_SEARCH_DOMAINS, HOST_GATEWAY_NAME, HOST_IP};
 
+use crate::activity::{NetEvent, NetEventKind};
 use crate::backend::{
-    ConnectionType, NetworkBackend, NetworkConfig, NetworkEndpoint, NetworkMetrics,
+    ConnectionType, NetworkBackend, NetworkConfig, NetworkEndpoint, NetworkMetrics, NetworkUpdate,
+    PortForward,
 };
-use crate::error::Result;
+use crate::dns::DnsConfig;
+use crate::egress::EgressProtocol;
+use crate::error::{NetError, Result};
+use crate::shaping::{QuotaAction, TrafficDirection, TrafficLimits};
 
 /// `gvisor-tap-vsock` network backend.
 ///
@@ -25,6 +51,91 @@ pub struct GvproxyBackend {
     instance: Arc<GvproxyInstance>,
     /// Unix socket path exposed to the VM engine.
     socket_path: PathBuf,
+    /// Guest NIC MAC (the static lease).
+    guest_mac: [u8; 6],
+    /// Action reported with quota events.
+    on_quota: QuotaAction,
+}
+
+/// Bridge shaping config for `limits`, or `None` when unlimited.
+const fn shaping_config(limits: &TrafficLimits) -> Option<ShapingConfig> {
+    if limits.is_unlimited() {
+        return None;
+    }
+    let (on_quota, throttle_rate) = match limits.on_quota {
+        QuotaAction::Throttle { rate } => (OnQuota::Throttle, Some(rate)),
+        QuotaAction::Stop => (OnQuota::Stop, None),
+        _ => (OnQuota::Block, None),
+    };
+    Some(ShapingConfig {
+        egress_rate: limits.egress_rate,
+        ingress_rate: limits.ingress_rate,
+        egress_quota: limits.egress_quota,
+        ingress_quota: limits.ingress_quota,
+        on_quota,
+        throttle_rate,
+    })
+}
+
+/// Bridge port mappings for `forwards`; all-interfaces IPv4 binds are
+/// left to the Go default.
+fn port_mappings(forwards: &[PortForward]) -> Vec<PortMapping> {
+    forwards
+        .iter()
+        .map(|forward| {
+            let mapping = PortMapping::new(forward.host_port, forward.guest_port)
+                .with_protocol(forward.protocol);
+            if forward.bind == IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
+                mapping
+            } else {
+                mapping.with_bind_ip(forward.bind)
+            }
+        })
+        .collect()
+}
+
+/// Bridge DNS zones holding the local records of `dns`.
+///
+/// Each record sits in the zone of its parent domain; single-label names
+/// go under every search domain.
+fn dns_zones(dns: &DnsConfig) -> Vec<DnsZone> {
+    let mut zones: BTreeMap<String, Vec<DnsRecordConfig>> = BTreeMap::new();
+    let mut add = |name: &str, ip: String| match name.split_once('.') {
+        Some((label, parent)) => {
+            zones
+                .entry(format!("{parent}."))
+                .or_default()
+                .push(DnsRecordConfig {
+                    name: label.to_owned(),
+                    ip,
+                });
+        }
+        None => {
+            for domain in DNS_SEARCH_DOMAINS {
+                zones
+                    .entry(format!("{domain}."))
+                    .or_default()
+                    .push(DnsRecordConfig {
+                        name: name.to_owned(),
+                        ip: ip.clone(),
+                    });
+            }
+        }
+    };
+    for record in &dns.records {
+        add(&record.name, record.ip.to_string());
+    }
+    if dns.host_gateway {
+        add(HOST_GATEWAY_NAME, HOST_IP.to_owned());
+    }
+    zones
+        .into_iter()
+        .map(|(name, records)| DnsZone {
+            name,
+            records,
+            ..DnsZone::default()
+        })
+        .collect()
 }
 
 impl GvproxyBackend {
@@ -35,20 +146,55 @@ impl GvproxyBackend {
     ///
     /// # Errors
     ///
-    /// Forwards any [`bux_gvproxy::Error`] from instance construction.
+    /// Returns [`NetError::Config`] for invalid HTTP rules, traffic
+    /// limits or DNS records, an upstream resolver override or IPv6, and
+    /// forwards any [`bux_gvproxy::Error`] from instance construction.
     pub fn new(config: NetworkConfig) -> Result<Self> {
         tracing::debug!(
             socket_path = ?config.socket_path,
             port_mappings = ?config.port_mappings,
-            allow_net = ?config.allow_net,
+            egress = ?config.egress.rule_strings(),
             secrets = config.secrets.len(),
+            http_rules = config.http.rules.len(),
             "creating gvisor-tap-vsock backend",
         );
+        config.http.validate()?;
+        config.traffic.validate()?;
+        config.dns.validate()?;
+        if config.dns.upstream.is_some() {
+            return Err(NetError::Config(
+                "an upstream DNS resolver requires the userspace backend".to_owned(),
+            ));
+        }
+        if config.ipv6 {
+            return Err(NetError::Config(
+                "IPv6 is not implemented on the gvproxy backend (its stack is IPv4-only); \
+                 use the userspace backend"
+                    .to_owned(),
+            ));
+        }
+        if !config.http.is_empty() && config.ca_cert_pem.is_empty() {
+            return Err(NetError::Config("HTTP rules require a MITM CA".to_owned()));
+        }
 
-        let mut gv_config = GvproxyConfig::new(config.socket_path.clone(), config.port_mappings)
-            .with_allow_net(config.allow_net);
+        let mut gv_config = GvproxyConfig::new(config.socket_path.clone(), Vec::new())
+            .with_port_mappings(port_mappings(&config.port_mappings))
+            .with_allow_net(config.egress.rule_strings())
+            .with_egress_default_deny(config.egress.default_deny())
+            .with_guest(config.guest.ip, config.guest.mac)
+            .with_dns_zones(dns_zones(&config.dns))
+            .with_activity(config.record_activity);
 
-        if !config.secrets.is_empty() {
+        if !config.http.is_empty() {
+            gv_config = gv_config.with_http_policy(config.http.to_gvproxy());
+        }
+        if config.dns.host_gateway {
+            gv_config = gv_config.with_host_gateway();
+        }
+        if let Some(shaping) = shaping_config(&config.traffic) {
+            gv_config = gv_config.with_shaping(shaping);
+        }
+        if !config.secrets.is_empty() || !config.http.is_empty() {
             gv_config =
                 gv_config.with_secrets(config.secrets, config.ca_cert_pem, config.ca_key_pem);
         }
@@ -69,6 +215,8 @@ pub fn new(config: NetworkConfig) -> Result<Self> {
         Ok(Self {
             instance,
             socket_path,
+            guest_mac: config.guest.mac,
+            on_quota: config.traffic.on_quota,
         })
     }
 
@@ -93,7 +241,7 @@ fn endpoint(&self) -> Result<NetworkEndpoint> {
         Ok(NetworkEndpoint::UnixSocket {
             path: self.socket_path.clone(),
             connection_type,
-            mac_address: GUEST_MAC,
+            mac_address: self.guest_mac,
         })
     }
 
@@ -108,8 +256,86 @@ fn metrics(&self) -> Result<Option<NetworkMetrics>> {
             bytes_received: stats.bytes_received,
             tcp_connections: Some(stats.tcp.current_established),
             tcp_connection_errors: Some(stats.tcp.failed_connection_attempts),
+            quota_exceeded: stats.shaping.quota_exceeded,
         }))
     }
+
+    fn update(&self, update: &NetworkUpdate) -> Result<()> {
+        let mut gv_update = GvproxyUpdate::new(update.egress.rule_strings(), &[])
+            .with_port_mappings(port_mappings(&update.port_mappings))
+            .with_egress_default_deny(update.egress.default_deny());
+        if let Some(secrets) = &update.secrets {
+            gv_update = gv_update.with_secrets(secrets.clone());
+        }
+        self.instance.update(&gv_update)?;
+        tracing::info!(
+            socket_path = ?self.socket_path,
+            ports = update.port_mappings.len(),
+            egress_rules = update.egress.rules().len(),
+            secrets_rotated = update.secrets.is_some(),
+            "updated gvisor-tap-vsock backend"
+        );
+        Ok(())
+    }
+
+    fn drain_events(&self) -> Result<Vec<NetEvent>> {
+        Ok(self
+            .instance
+            .drain_activity()?
+            .into_iter()
+            .filter_map(|event| net_event(event, self.on_quota))
+            .collect())
+    }
+}
+
+/// Converts a Go-side activity record; unknown kinds are dropped.
+///
+/// `on_quota` is the configured action, which quota events do not carry.
+fn net_event(event: ActivityEvent, on_quota: QuotaAction) -> Option<NetEvent> {
+    let kind = match event.kind {
+        ActivityKind::TcpConnect => NetEventKind::TcpConnect {
+            remote: event.remote?,
+            host: event.host,
+        },
+        ActivityKind::TcpClose => NetEventKind::TcpClose {
+            remote: event.remote?,
+            bytes_out: event.bytes_out,
+            bytes_in: event.bytes_in,
+        },
+        ActivityKind::Denied => NetEventKind::Denied {
+            protocol: match event.protocol.as_deref() {
+                Some("tcp") => EgressProtocol::Tcp,
+                Some("udp") => EgressProtocol::Udp,
+                _ => EgressProtocol::Any,
+            },
+            remote: event.remote,
+            host: event.host,
+            rule: event.rule,
+        },
+        ActivityKind::HttpRequest => NetEventKind::HttpRequest {
+            host: event.host?,
+            method: event.method?,
+            path: event.path?,
+            status: event.status?,
+            bytes_out: event.bytes_out,
+            bytes_in: event.bytes_in,
+            blocked: event.blocked,
+        },
+        ActivityKind::QuotaExceeded => NetEventKind::QuotaExceeded {
+            direction: match event.direction.as_deref()? {
+                "egress" => TrafficDirection::Egress,
+                "ingress" => TrafficDirection::Ingress,
+                _ => return None,
+            },
+            limit: event.limit?,
+            action: on_quota,
+        },
+        _ => return None,
+    };
+    Some(NetEvent {
+        timestamp_ms: event.timestamp_ms,
+        kind,
+    })
 }
 
 impl Drop for GvproxyBackend {
@@ -117,3 +343,127 @@ fn drop(&mut self) {
         tracing::debug!(socket_path = ?self.socket_path, "dropping gvproxy backend");
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_forwards_to_bridge_ports() {
+        let forwards = [
+            PortForward::tcp(8080, 80),
+            PortForward::tcp(5353, 53)
+                .with_bind(IpAddr::V4(Ipv4Addr::LOCALHOST))
+                .with_protocol(bux_net_types::PortProtocol::Udp),
+        ];
+        assert_eq!(
+            port_mappings(&forwards),
+            vec![
+                PortMapping::new(8080, 80),
+                PortMapping::new(5353, 53)
+                    .with_bind_ip(IpAddr::V4(Ipv4Addr::LOCALHOST))
+                    .with_protocol(bux_net_types::PortProtocol::Udp),
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_activity_and_drops_unknown_kinds() {
+        let json = r#"[
+            {"kind":"tcp_close","timestamp_ms":7,"protocol":"tcp","remote":"1.2.3.4:443","bytes_out":3,"bytes_in":5},
+            {"kind":"denied","timestamp_ms":8,"protocol":"tcp","remote":"10.0.0.1:22"},
+            {"kind":"tcp_connect","timestamp_ms":9},
+            {"kind":"http_request","timestamp_ms":10},
+            {"kind":"http_request","timestamp_ms":11,"host":"api.github.com","method":"DELETE","path":"/repos/x","status":403,"blocked":"method DELETE not allowed"},
+            {"kind":"dns_query","timestamp_ms":12},
+            {"kind":"quota_exceeded","timestamp_ms":13,"direction":"ingress","limit":4096},
+            {"kind":"quota_exceeded","timestamp_ms":14}
+        ]"#;
+        let events: Vec<_> = ActivityEvent::from_json_array(json)
+            .unwrap()
+            .into_iter()
+            .filter_map(|event| net_event(event, QuotaAction::Stop))
+            .collect();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0].timestamp_ms, 7);
+        assert!(matches!(
+            events[0].kind,
+            NetEventKind::TcpClose {
+                bytes_out: 3,
+                bytes_in: 5,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[1].kind,
+            NetEventKind::Denied {
+                protocol: EgressProtocol::Tcp,
+                rule: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            events[2].kind.to_string(),
+            "http DELETE api.github.com/repos/x 403 blocked: method DELETE not allowed"
+        );
+        assert_eq!(
+            events[3].kind,
+            NetEventKind::QuotaExceeded {
+                direction: TrafficDirection::Ingress,
+                limit: 4096,
+                action: QuotaAction::Stop,
+            }
+        );
+    }
+
+    #[test]
+    fn maps_traffic_limits_to_bridge_shaping() {
+        assert!(shaping_config(&TrafficLimits::new()).is_none());
+        let shaping = shaping_config(
+            &TrafficLimits::new()
+                .with_ingress_rate(500)
+                .with_egress_quota(100)
+                .with_quota_action(QuotaAction::Throttle { rate: 10 }),
+        )
+        .unwrap();
+        assert_eq!(shaping.ingress_rate, Some(500));
+        assert_eq!(shaping.egress_quota, Some(100));
+        assert_eq!(shaping.on_quota, OnQuota::Throttle);
+        assert_eq!(shaping.throttle_rate, Some(10));
+    }
+
+    #[test]
+    fn maps_dns_records_to_parent_zones() {
+        let dns = DnsConfig::new()
+            .with_record("db.example.test", Ipv4Addr::new(10, 0, 0, 5))
+            .with_record("cache", Ipv4Addr::new(10, 0, 0, 6))
+            .with_host_gateway(true);
+        let zones = dns_zones(&dns);
+        let names: Vec<_> = zones.iter().map(|z| z.name.as_str()).collect();
+        assert_eq!(names, ["bux.internal.", "example.test.", "local."]);
+        let records: Vec<_> = zones
+            .iter()
+            .flat_map(|z| &z.records)
+            .map(|r| (r.name.as_str(), r.ip.as_str()))
+            .collect();
+        assert_eq!(
+            records,
+            [("host", HOST_IP), ("db", "10.0.0.5"), ("cache", "10.0.0.6")]
+        );
+        assert!(zones.iter().all(|z| z.default_ip.is_empty()));
+        assert!(dns_zones(&DnsConfig::new()).is_empty());
+
+        let upstream = NetworkConfig::new(vec![], PathBuf::from("/tmp/dns-upstream.sock"))
+            .with_dns(DnsConfig::new().with_upstream("1.1.1.1:53".parse().unwrap()));
+        assert!(matches!(
+            GvproxyBackend::new(upstream),
+            Err(NetError::Config(_))
+        ));
+        let ipv6 = NetworkConfig::new(vec![], PathBuf::from("/tmp/dns-ipv6.sock")).with_ipv6(true);
+        assert!(matches!(
+            GvproxyBackend::new(ipv6),
+            Err(NetError::Config(msg)) if msg.contains("userspace backend")
+        ));
+    }
+}
@@ -0,0 +1,514 @@
+//! Out-of-process network backend.
+//!
+//! [`HelperBackend`] keeps the real backend — and with it the packet
+//! path, the MITM CA key and secret values — in a separate helper
+//! process (the `bux-netd` binary) that the embedder spawns sandboxed.
+//! The two sides talk over a Unix stream, one JSON [`HelperRequest`] per
+//! line, each answered by one [`HelperResponse`] line:
+//!
+//! ```text
+//! → {"op":"start","kind":"gvproxy","config":{...}}
+//! ← {"op":"started","endpoint":{...}}
+//! → {"op":"metrics"}
+//! ← {"op":"metrics","metrics":{...}}
+//! → {"op":"shutdown"}
+//! ← {"op":"done"}
+//! ```
+//!
+//! The config travels over the socket, so secrets never touch the
+//! filesystem. The helper side is [`serve`]; it exits after `shutdown` or
+//! when the control stream closes.
+
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process::Child;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::UserspaceBackend;
+use crate::activity::NetEvent;
+use crate::backend::{
+    NetworkBackend, NetworkBackendKind, NetworkConfig, NetworkEndpoint, NetworkMetrics,
+    NetworkUpdate,
+};
+use crate::error::{NetError, Result};
+
+/// How long a started helper may take to answer one request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a helper may take to exit after `shutdown` before it is killed.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Poll interval while waiting for the helper to connect or exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Longest accepted message line, newline included.
+const MAX_MESSAGE: u64 = 16 * 1024 * 1024;
+
+/// A control message from the embedder to the helper.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum HelperRequest {
+    /// Build the backend. Must be the first request, sent once.
+    Start {
+        /// Backend implementation to run.
+        kind: NetworkBackendKind,
+        /// Full backend configuration, secrets included.
+        config: Box<NetworkConfig>,
+    },
+    /// Read [`NetworkBackend::metrics`].
+    Metrics,
+    /// Apply [`NetworkBackend::update`].
+    Update {
+        /// Desired ports, egress policy and secrets.
+        update: Box<NetworkUpdate>,
+    },
+    /// Take [`NetworkBackend::drain_events`].
+    DrainEvents,
+    /// Drop the backend and exit.
+    Shutdown,
+}
+
+/// The helper's answer to one [`HelperRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum HelperResponse {
+    /// The backend is running and the engine can attach here.
+    Started {
+        /// Where the VM engine connects the guest NIC.
+        endpoint: NetworkEndpoint,
+    },
+    /// Live counters, if the backend keeps any.
+    Metrics {
+        /// Counters at the time of the request.
+        metrics: Option<NetworkMetrics>,
+    },
+    /// Activity since the previous drain.
+    Events {
+        /// Drained events, oldest first.
+        events: Vec<NetEvent>,
+    },
+    /// The request succeeded and has no payload.
+    Done,
+    /// The request failed; the helper keeps serving.
+    Error {
+        /// Backend error message.
+        message: String,
+    },
+}
+
+/// Writes `message` as one JSON line.
+fn send(writer: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)
+        .map_err(|e| NetError::Helper(format!("cannot encode message: {e}")))?;
+    line.push(b'\n');
+    writer.write_all(&line)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads one JSON line, or `None` at end of stream.
+///
+/// A line longer than [`MAX_MESSAGE`] is an [`ErrorKind::InvalidData`]
+/// I/O error: the stream has lost its framing, so the session ends.
+fn recv<T: DeserializeOwned>(reader: &mut impl BufRead) -> Result<Option<T>> {
+    let mut line = Vec::new();
+    let read = reader.take(MAX_MESSAGE).read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') && read as u64 == MAX_MESSAGE {
+        return Err(NetError::Io(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("helper message exceeds {MAX_MESSAGE} bytes"),
+        )));
+    }
+    serde_json::from_slice(&line)
+        .map(Some)
+        .map_err(|e| NetError::Helper(format!("malformed message: {e}")))
+}
+
+/// Runs the helper side of the protocol on `control`.
+///
+/// Expects [`HelperRequest::Start`] first, answers it with the backend's
+/// endpoint, then serves requests until `shutdown` or end of stream.
+/// Backend errors are reported to the peer and do not end the session.
+///
+/// # Errors
+///
+/// Returns the backend construction error (after reporting it to the
+/// peer), [`NetError::Helper`] if the first message is not `start`, or
+/// I/O errors on the control stream.
+pub fn serve(control: UnixStream) -> Result<()> {
+    let mut reader = BufReader::new(control.try_clone()?);
+    let mut writer = control;
+
+    let Some(HelperRequest::Start { kind, config }) = recv(&mut reader)? else {
+        let err = NetError::Helper("expected a start request".to_owned());
+        send(&mut writer, &error_response(&err))?;
+        return Err(err);
+    };
+    let backend = match start_backend(kind, *config) {
+        Ok(backend) => backend,
+        Err(e) => {
+            send(&mut writer, &error_response(&e))?;
+            return Err(e);
+        }
+    };
+    send(
+        &mut writer,
+        &HelperResponse::Started {
+            endpoint: backend.endpoint()?,
+        },
+    )?;
+
+    loop {
+        let request = match recv::<HelperRequest>(&mut reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(NetError::Io(e)) => return Err(NetError::Io(e)),
+            Err(e) => {
+                send(&mut writer, &error_response(&e))?;
+                continue;
+            }
+        };
+        let shutdown = matches!(request, HelperRequest::Shutdown);
+        send(&mut writer, &handle(backend.as_ref(), request))?;
+        if shutdown {
+            return Ok(());
+        }
+    }
+}
+
+/// Builds the backend named by `kind`.
+fn start_backend(
+    kind: NetworkBackendKind,
+    config: NetworkConfig,
+) -> Result<Box<dyn NetworkBackend>> {
+    Ok(match kind {
+        NetworkBackendKind::Userspace => Box::new(UserspaceBackend::new(config)?),
+        #[cfg(feature = "gvproxy")]
+        NetworkBackendKind::Gvproxy => Box::new(crate::GvproxyBackend::new(config)?),
+        #[cfg(not(feature = "gvproxy"))]
+        NetworkBackendKind::Gvproxy => {
+            return Err(NetError::Config(
+                "bux-net was built without the gvproxy feature".to_owned(),
+            ));
+        }
+    })
+}
+
+/// Answers one request against a running backend.
+fn handle(backend: &dyn NetworkBackend, request: HelperRequest) -> HelperResponse {
+    let result = match request {
+        HelperRequest::Start { .. } => Err(NetError::Helper("backend already started".to_owned())),
+        HelperRequest::Metrics => backend
+            .metrics()
+            .map(|metrics| HelperResponse::Metrics { metrics }),
+        HelperRequest::Update { update } => backend.update(&update).map(|()| HelperResponse::Done),
+        HelperRequest::DrainEvents => backend
+            .drain_events()
+            .map(|events| HelperResponse::Events { events }),
+        HelperRequest::Shutdown => Ok(HelperResponse::Done),
+    };
+    result.unwrap_or_else(|e| error_response(&e))
+}
+
+/// Wire form of `err`.
+fn error_response(err: &NetError) -> HelperResponse {
+    HelperResponse::Error {
+        message: err.to_string(),
+    }
+}
+
+/// Both ends of the control stream, locked together so replies pair up
+/// with their requests.
+#[derive(Debug)]
+struct Control {
+    /// Buffered read half.
+    reader: BufReader<UnixStream>,
+    /// Write half.
+    writer: UnixStream,
+}
+
+impl Control {
+    /// Sends `request` and returns the helper's answer.
+    fn call(&mut self, request: &HelperRequest) -> Result<HelperResponse> {
+        send(&mut self.writer, request)?;
+        match recv(&mut self.reader)? {
+            Some(HelperResponse::Error { message }) => Err(NetError::Helper(message)),
+            Some(response) => Ok(response),
+            None => Err(NetError::Helper(
+                "helper closed the control socket".to_owned(),
+            )),
+        }
+    }
+}
+
+/// A [`NetworkBackend`] served by a helper process.
+///
+/// Dropping it asks the helper to shut down, then kills it if it has not
+/// exited within a short grace period.
+#[derive(Debug)]
+pub struct HelperBackend {
+    /// Control stream to the helper.
+    control: Mutex<Control>,
+    /// The helper process.
+    child: Child,
+    /// Endpoint reported by the helper.
+    endpoint: NetworkEndpoint,
+    /// Backend running inside the helper.
+    kind: NetworkBackendKind,
+}
+
+impl HelperBackend {
+    /// Waits up to `timeout` for the freshly spawned helper `child` to
+    /// connect to `listener`, then starts a `kind` backend in it with
+    /// `config`.
+    ///
+    /// The helper is killed if any step fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Helper`] if the helper exits or times out
+    /// before connecting, or rejects the config (with the backend's
+    /// message), and I/O errors on the control socket.
+    pub fn start(
+        mut child: Child,
+        listener: &UnixListener,
+        kind: NetworkBackendKind,
+        config: NetworkConfig,
+        timeout: Duration,
+    ) -> Result<Self> {
+        match handshake(&mut child, listener, kind, config, timeout) {
+            Ok((control, endpoint)) => Ok(Self {
+                control: Mutex::new(control),
+                child,
+                endpoint,
+                kind,
+            }),
+            Err(e) => {
+                drop(child.kill());
+                drop(child.wait());
+                Err(e)
+            }
+        }
+    }
+
+    /// Process id of the helper.
+    #[must_use]
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Sends `request` to the helper.
+    fn call(&self, request: &HelperRequest) -> Result<HelperResponse> {
+        self.control
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .call(request)
+    }
+}
+
+/// Accepts the helper's connection and starts its backend.
+fn handshake(
+    child: &mut Child,
+    listener: &UnixListener,
+    kind: NetworkBackendKind,
+    config: NetworkConfig,
+    timeout: Duration,
+) -> Result<(Control, NetworkEndpoint)> {
+    let stream = accept(child, listener, Instant::now() + timeout)?;
+    stream.set_read_timeout(Some(timeout.max(REQUEST_TIMEOUT)))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut control = Control {
+        reader: BufReader::new(stream.try_clone()?),
+        writer: stream,
+    };
+    let start = HelperRequest::Start {
+        kind,
+        config: Box::new(config),
+    };
+    match control.call(&start)? {
+        HelperResponse::Started { endpoint } => {
+            control.writer.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+            Ok((control, endpoint))
+        }
+        other => Err(unexpected(&other)),
+    }
+}
+
+/// Accepts one connection on `listener`, failing early if `child` exits.
+#[allow(
+    clippy::disallowed_methods,
+    reason = "sync accept loop polls the helper between attempts"
+)]
+fn accept(child: &mut Child, listener: &UnixListener, deadline: Instant) -> Result<UnixStream> {
+    listener.set_nonblocking(true)?;
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false)?;
+                return Ok(stream);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(NetError::Helper(format!(
+                "helper exited before connecting ({status})"
+            )));
+        }
+        if Instant::now() >= deadline {
+            return Err(NetError::Helper(
+                "helper did not connect in time".to_owned(),
+            ));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Error for a response that does not answer the request.
+fn unexpected(response: &HelperResponse) -> NetError {
+    NetError::Helper(format!("unexpected response {response:?}"))
+}
+
+impl NetworkBackend for HelperBackend {
+    fn endpoint(&self) -> Result<NetworkEndpoint> {
+        Ok(self.endpoint.clone())
+    }
+
+    fn name(&self) -> &'static str {
+        match self.kind {
+            NetworkBackendKind::Userspace => "userspace (helper)",
+            NetworkBackendKind::Gvproxy => "gvisor-tap-vsock (helper)",
+        }
+    }
+
+    fn metrics(&self) -> Result<Option<NetworkMetrics>> {
+        match self.call(&HelperRequest::Metrics)? {
+            HelperResponse::Metrics { metrics } => Ok(metrics),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn update(&self, update: &NetworkUpdate) -> Result<()> {
+        let request = HelperRequest::Update {
+            update: Box::new(update.clone()),
+        };
+        match self.call(&request)? {
+            HelperResponse::Done => Ok(()),
+            other => Err(unexpected(&other)),
+        }
+    }
+
+    fn drain_events(&self) -> Result<Vec<NetEvent>> {
+        match self.call(&HelperRequest::DrainEvents)? {
+            HelperResponse::Events { events } => Ok(events),
+            other => Err(unexpected(&other)),
+        }
+    }
+}
+
+impl Drop for HelperBackend {
+    #[allow(
+        clippy::disallowed_methods,
+        reason = "drop is sync; waits briefly for a clean helper exit"
+    )]
+    fn drop(&mut self) {
+        let control = self
+            .control
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        drop(send(&mut control.writer, &HelperRequest::Shutdown));
+        drop(control.writer.shutdown(std::net::Shutdown::Write));
+
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        while matches!(self.child.try_wait(), Ok(None)) && Instant::now() < deadline {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        drop(self.child.kill());
+        drop(self.child.wait());
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "unit tests"
+)]
+mod tests {
+    use std::process::Command;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn messages_are_tagged_json_lines() {
+        let mut buf = Vec::new();
+        send(&mut buf, &HelperRequest::DrainEvents).unwrap();
+        assert_eq!(buf, b"{\"op\":\"drain_events\"}\n");
+
+        let mut reader = &b"{\"op\":\"error\",\"message\":\"no\"}\n"[..];
+        let response: HelperResponse = recv(&mut reader).unwrap().unwrap();
+        assert!(matches!(response, HelperResponse::Error { message } if message == "no"));
+        assert!(recv::<HelperResponse>(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn oversized_message_ends_framing() {
+        let mut reader = BufReader::new(io::repeat(b' '));
+        let err = recv::<HelperRequest>(&mut reader).unwrap_err();
+        assert!(matches!(err, NetError::Io(e) if e.kind() == ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn serve_rejects_requests_before_start() {
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || serve(theirs));
+        let mut control = Control {
+            reader: BufReader::new(ours.try_clone().unwrap()),
+            writer: ours,
+        };
+        let err = control.call(&HelperRequest::Metrics).unwrap_err();
+        assert!(err.to_string().contains("expected a start request"));
+        assert!(server.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn helper_backend_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = UnixListener::bind(dir.path().join("control.sock")).unwrap();
+        let control_path = dir.path().join("control.sock");
+        let server = thread::spawn(move || serve(UnixStream::connect(control_path).unwrap()));
+
+        // Stands in for the helper process; the server thread plays its part.
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let config =
+            NetworkConfig::new(Vec::new(), dir.path().join("net.sock")).with_activity(true);
+        let backend = HelperBackend::start(
+            child,
+            &listener,
+            NetworkBackendKind::Userspace,
+            config,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let NetworkEndpoint::UnixSocket { path, .. } = backend.endpoint().unwrap();
+        assert_eq!(path, dir.path().join("net.sock"));
+        assert_eq!(backend.name(), "userspace (helper)");
+        backend.metrics().unwrap();
+        assert!(backend.drain_events().unwrap().is_empty());
+        backend.update(&NetworkUpdate::default()).unwrap();
+
+        drop(backend);
+        server.join().unwrap().unwrap();
+    }
+}
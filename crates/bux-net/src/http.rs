@@ -0,0 +1,295 @@
+//! HTTP request policy for hosts terminated by the MITM proxy.
+//!
+//! An [`HttpPolicy`] is an ordered list of [`HttpRule`]s. HTTPS
+//! connections to a host named by a rule are intercepted with the VM's
+//! MITM CA (the same path secret substitution uses) and the first matching
+//! rule applies to each request: methods and URL path prefixes outside its
+//! lists are answered `403 Forbidden` without reaching the server, and its
+//! header edits are applied to the rest. Paths with dot segments or empty
+//! segments are refused outright when a rule restricts paths, so a prefix
+//! cannot be escaped by `/repos/../user`.
+//!
+//! With [`HttpPolicy::log_requests`] set, every intercepted request is
+//! reported as a [`NetEventKind::HttpRequest`]; refusals are reported
+//! whenever activity is recorded. Plain HTTP on port 80 is not inspected.
+//! Only the gvproxy backend implements this (`mitm_http.go`).
+//!
+//! [`NetEventKind::HttpRequest`]: crate::NetEventKind::HttpRequest
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{NetError, Result};
+
+/// Methods [`HttpRule::read_only`] permits.
+const READ_ONLY_METHODS: [&str; 3] = ["GET", "HEAD", "OPTIONS"];
+
+/// Request rule for one set of hosts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpRule {
+    /// Hostnames (SNI) the rule applies to; `*.example.com` matches one
+    /// label below `example.com`.
+    pub hosts: Vec<String>,
+    /// Permitted methods. Empty permits any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub methods: Vec<String>,
+    /// Permitted URL path prefixes. Empty permits any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path_prefixes: Vec<String>,
+    /// Request headers removed before forwarding.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_headers: Vec<String>,
+    /// Request headers set, replacing any guest value, before forwarding.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set_headers: BTreeMap<String, String>,
+}
+
+impl HttpRule {
+    /// A rule for `hosts` that permits everything; requests are still
+    /// intercepted, so they can be logged.
+    #[must_use]
+    pub fn new<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            hosts: hosts.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// A rule for `hosts` that permits only `GET`, `HEAD` and `OPTIONS`.
+    #[must_use]
+    pub fn read_only<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(hosts).methods(READ_ONLY_METHODS)
+    }
+
+    /// Restricts the permitted methods.
+    #[must_use]
+    pub fn methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.methods = methods
+            .into_iter()
+            .map(|method| method.into().to_ascii_uppercase())
+            .collect();
+        self
+    }
+
+    /// Restricts the permitted URL path prefixes.
+    #[must_use]
+    pub fn path_prefixes<I, S>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.path_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Removes request header `name` before forwarding.
+    #[must_use]
+    pub fn strip_header(mut self, name: impl Into<String>) -> Self {
+        self.strip_headers.push(name.into());
+        self
+    }
+
+    /// Sets request header `name` to `value` before forwarding.
+    #[must_use]
+    pub fn set_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.set_headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Checks hosts, methods, paths and headers for well-formedness.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] naming the first bad entry.
+    pub fn validate(&self) -> Result<()> {
+        if self.hosts.is_empty() {
+            return Err(invalid("rule names no hosts"));
+        }
+        if let Some(host) = self.hosts.iter().find(|host| !valid_host(host)) {
+            return Err(invalid(format!("bad host {host:?}")));
+        }
+        if let Some(method) = self.methods.iter().find(|method| !is_token(method)) {
+            return Err(invalid(format!("bad method {method:?}")));
+        }
+        if let Some(prefix) = self.path_prefixes.iter().find(|p| !p.starts_with('/')) {
+            return Err(invalid(format!(
+                "path prefix {prefix:?} must start with '/'"
+            )));
+        }
+        let mut names = self.strip_headers.iter().chain(self.set_headers.keys());
+        if let Some(name) = names.find(|name| !is_token(name)) {
+            return Err(invalid(format!("bad header name {name:?}")));
+        }
+        if let Some((name, _)) = self
+            .set_headers
+            .iter()
+            .find(|(_, value)| value.contains(['\r', '\n', '\0']))
+        {
+            return Err(invalid(format!("bad value for header {name:?}")));
+        }
+        Ok(())
+    }
+
+    /// Converts to the gvproxy wire format.
+    #[cfg(feature = "gvproxy")]
+    fn to_gvproxy(&self) -> bux_gvproxy::HttpRuleConfig {
+        bux_gvproxy::HttpRuleConfig {
+            hosts: self.hosts.clone(),
+            methods: self.methods.clone(),
+            path_prefixes: self.path_prefixes.clone(),
+            strip_headers: self.strip_headers.clone(),
+            set_headers: self.set_headers.clone(),
+        }
+    }
+}
+
+/// Ordered request rules plus the request-logging switch.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpPolicy {
+    /// Rules in priority order; the first whose hosts match applies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<HttpRule>,
+    /// Report every intercepted request as an activity event.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub log_requests: bool,
+}
+
+impl HttpPolicy {
+    /// An empty policy: nothing is intercepted on its account.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            log_requests: false,
+        }
+    }
+
+    /// Appends a rule.
+    #[must_use]
+    pub fn rule(mut self, rule: HttpRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Turns request logging on or off.
+    #[must_use]
+    pub const fn with_logging(mut self, log_requests: bool) -> Self {
+        self.log_requests = log_requests;
+        self
+    }
+
+    /// Whether the policy has no rules (and so intercepts nothing).
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Validates every rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] for the first malformed rule.
+    pub fn validate(&self) -> Result<()> {
+        self.rules.iter().try_for_each(HttpRule::validate)
+    }
+
+    /// Converts to the gvproxy wire format.
+    #[cfg(feature = "gvproxy")]
+    pub(crate) fn to_gvproxy(&self) -> bux_gvproxy::HttpPolicyConfig {
+        bux_gvproxy::HttpPolicyConfig {
+            rules: self.rules.iter().map(HttpRule::to_gvproxy).collect(),
+            log_requests: self.log_requests,
+        }
+    }
+}
+
+/// A [`NetError::Config`] prefixed for HTTP rules.
+fn invalid(reason: impl Into<String>) -> NetError {
+    NetError::Config(format!("HTTP rule: {}", reason.into()))
+}
+
+/// RFC 9110 token (method and header-name syntax).
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
+/// Hostname or `*.suffix`, as matched against SNI.
+fn valid_host(host: &str) -> bool {
+    let name = host.strip_prefix("*.").unwrap_or(host);
+    !name.is_empty()
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+        })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "gvproxy")]
+    #[test]
+    fn read_only_rule_and_wire_format() {
+        let policy = HttpPolicy::new()
+            .rule(
+                HttpRule::read_only(["api.github.com"])
+                    .path_prefixes(["/repos/"])
+                    .strip_header("Cookie")
+                    .set_header("X-Agent", "bux"),
+            )
+            .with_logging(true);
+        policy.validate().unwrap();
+
+        let wire = serde_json::to_value(policy.to_gvproxy()).unwrap();
+        assert_eq!(
+            wire,
+            serde_json::json!({
+                "rules": [{
+                    "hosts": ["api.github.com"],
+                    "methods": ["GET", "HEAD", "OPTIONS"],
+                    "path_prefixes": ["/repos/"],
+                    "strip_headers": ["Cookie"],
+                    "set_headers": {"X-Agent": "bux"},
+                }],
+                "log_requests": true,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        for rule in [
+            HttpRule::new(Vec::<String>::new()),
+            HttpRule::new(["bad host"]),
+            HttpRule::new(["a.com"]).methods(["GE T"]),
+            HttpRule::new(["a.com"]).path_prefixes(["repos/"]),
+            HttpRule::new(["a.com"]).strip_header("X:Y"),
+            HttpRule::new(["a.com"]).set_header("X-Y", "a\r\nInjected: 1"),
+        ] {
+            assert!(rule.validate().is_err(), "{rule:?}");
+        }
+        HttpRule::new(["*.github.com"])
+            .methods(["get"])
+            .validate()
+            .unwrap();
+    }
+}
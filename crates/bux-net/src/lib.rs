@@ -1,11 +1,24 @@
 //! Network backend abstraction for bux micro-VMs.
 //!
 //! This crate layers the backend-neutral [`NetworkBackend`] trait on
-//! top of platform primitives such as [`bux_gvproxy`]. Concrete
+//! top of platform primitives such as `bux-gvproxy`. Concrete
 //! backends live under their own module:
 //!
-//! - [`GvproxyBackend`] — userspace `gvisor-tap-vsock` via the
-//!   `bux-gvproxy` crate.
+//! - `GvproxyBackend` (`gvproxy` feature, on by default) — userspace
+//!   `gvisor-tap-vsock` via the `bux-gvproxy` crate.
+//! - `UserspaceBackend` (Unix only) — pure-Rust stack on [`smoltcp`]
+//!   with the same subnet, DHCP, DNS, port-forward and `allow_net`
+//!   behaviour, minus TLS interception.
+//!
+//! [`NetworkBackendKind`] names the backends for per-VM selection.
+//! [`EgressPolicy`] is the backend-independent egress rule set both
+//! backends enforce, and [`NetEvent`] the activity record both report
+//! through [`NetworkBackend::drain_events`]. [`HttpPolicy`] restricts and
+//! logs HTTPS requests on gvproxy's MITM path, [`TrafficLimits`] caps
+//! each VM's bandwidth and transfer volume, and [`DnsConfig`] adds local
+//! DNS records, an upstream resolver and the host gateway name. A [`Segment`] (Unix only)
+//! switches frames between VMs on a private network, each at its own
+//! [`GuestAddress`].
 //!
 //! Shared utilities:
 //!
@@ -13,33 +26,65 @@
 //!   `sun_path` length workaround via `/tmp` symlinks.
 //!
 //! Network-topology defaults (subnet, gateway/guest IP & MAC, MTU,
-//! DNS search domains) live in [`bux_gvproxy::constants`] to keep a
-//! single source of truth.
+//! DNS search domains) live in [`constants`] (from `bux-net-types`,
+//! shared with `bux-gvproxy`) to keep a single source of truth.
 //!
 //! # Quick start
 //!
 //! ```no_run
-//! use bux_net::{GvproxyBackend, NetworkBackend, NetworkConfig};
+//! use bux_net::{EgressPolicy, GvproxyBackend, NetworkBackend, NetworkConfig, PortForward};
 //! use std::path::PathBuf;
 //!
 //! let config = NetworkConfig::new(
-//!     vec![(8080, 80), (8443, 443)],
+//!     vec![PortForward::tcp(8080, 80), PortForward::tcp(8443, 443)],
 //!     PathBuf::from("/tmp/my-vm/net.sock"),
 //! )
-//! .with_allow_net(vec!["example.com".into()]);
+//! .with_egress(EgressPolicy::parse(["*.github.com:443/tcp", "!169.254.169.254"])?);
 //!
 //! let backend = GvproxyBackend::new(config)?;
 //! let endpoint = backend.endpoint()?;
 //! # Ok::<(), bux_net::NetError>(())
 //! ```
 
+pub mod activity;
 pub mod backend;
+pub mod dns;
+pub mod egress;
 pub mod error;
+#[cfg(feature = "gvproxy")]
 mod gvproxy_backend;
+#[cfg(unix)]
+pub mod helper;
+pub mod http;
+#[cfg(unix)]
+pub mod segment;
+pub mod shaping;
 pub mod socket;
+#[cfg(unix)]
+mod userspace;
 
-pub use backend::{ConnectionType, NetworkBackend, NetworkConfig, NetworkEndpoint, NetworkMetrics};
+pub use activity::{NetEvent, NetEventKind};
+pub use backend::{
+    ConnectionType, GuestAddress, NetworkBackend, NetworkBackendKind, NetworkConfig,
+    NetworkEndpoint, NetworkMetrics, NetworkUpdate, PortForward,
+};
+pub use bux_net_types::{PortProtocol, SecretConfig, constants};
+pub use dns::{DnsConfig, DnsRecord};
+pub use egress::{
+    EgressAction, EgressDecision, EgressFlow, EgressPolicy, EgressProtocol, EgressRule,
+    EgressTarget, PortRange,
+};
 pub use error::{NetError, Result};
+#[cfg(feature = "gvproxy")]
 pub use gvproxy_backend::GvproxyBackend;
-// Re-export secret/CA types so callers need not depend on bux-gvproxy directly.
-pub use bux_gvproxy::{MitmCa, SecretConfig, generate_mitm_ca};
+#[cfg(unix)]
+pub use helper::HelperBackend;
+pub use http::{HttpPolicy, HttpRule};
+#[cfg(unix)]
+pub use segment::{Segment, SegmentMember, SegmentPort};
+pub use shaping::{QuotaAction, TrafficDirection, TrafficLimits, parse_byte_size};
+#[cfg(unix)]
+pub use userspace::UserspaceBackend;
+// Re-export the MITM CA so callers need not depend on bux-gvproxy directly.
+#[cfg(feature = "gvproxy")]
+pub use bux_gvproxy::{MitmCa, generate_mitm_ca};
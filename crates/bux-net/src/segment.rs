@@ -0,0 +1,695 @@
+//! Private network segments: an Ethernet switch joining several VMs.
+//!
+//! Every VM keeps its own backend (egress policy, secrets, activity log,
+//! published ports) configured with a distinct [`GuestAddress`]. A
+//! [`Segment`] sits between each guest and its backend: the engine
+//! connects to the port's socket instead of the backend's, and the switch
+//! connects to the backend acting as the guest.
+//!
+//! Frames are routed by destination MAC:
+//!
+//! - another member's MAC → that member's guest, never touching a backend
+//!   or the host's interfaces;
+//! - broadcast / multicast → the sender's backend and every other member
+//!   (an ARP request for a member address goes to that member alone, as
+//!   the userspace backend's any-IP stack would otherwise claim it too);
+//! - anything else (the gateway) → the sender's backend.
+//!
+//! Frames whose source MAC is not the sender's are dropped. DNS queries to
+//! the gateway for `<hostname>` or `<hostname>.<segment>` of a member are
+//! answered by the switch; other names reach the backend's resolver.
+//!
+//! Only the stream framing (4-byte big-endian length prefix, Linux) is
+//! switched; datagram endpoints are refused.
+
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use bux_net_types::constants::{GATEWAY_IPV4, HOST_IPV4};
+use serde::{Deserialize, Serialize};
+use smoltcp::wire::{ArpPacket, EthernetFrame, EthernetProtocol};
+
+use crate::backend::{ConnectionType, GuestAddress, NetworkEndpoint};
+use crate::error::{NetError, Result};
+use crate::userspace::{dns, frame};
+
+/// Largest frame accepted from either side.
+const MAX_FRAME: usize = 65_536;
+/// A member that does not drain its socket within this time loses frames
+/// instead of stalling the sender.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A VM's identity on a segment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentMember {
+    /// DNS label other members resolve.
+    pub hostname: String,
+    /// Guest address and MAC; must match the member's backend.
+    pub address: GuestAddress,
+}
+
+impl SegmentMember {
+    /// A member named `hostname` at `address`.
+    #[must_use]
+    pub fn new(hostname: impl Into<String>, address: GuestAddress) -> Self {
+        Self {
+            hostname: hostname.into(),
+            address,
+        }
+    }
+}
+
+/// One attached member inside the switch.
+#[derive(Debug)]
+struct Port {
+    /// Member identity.
+    member: SegmentMember,
+    /// Writer to the connected guest, if any.
+    guest: Mutex<Option<UnixStream>>,
+    /// Writer to the member's own backend.
+    backend: Mutex<UnixStream>,
+}
+
+impl Port {
+    /// Sends `frame` to the guest; dropped if none is connected.
+    fn send_guest(&self, frame: &[u8]) {
+        let mut guest = self.guest.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(stream) = guest.as_mut()
+            && let Err(e) = write_frame(stream, frame)
+        {
+            tracing::trace!(hostname = %self.member.hostname, error = %e, "segment: frame to guest dropped");
+        }
+    }
+
+    /// Sends `frame` to the member's backend.
+    fn send_backend(&self, frame: &[u8]) {
+        let mut backend = self.backend.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = write_frame(&mut backend, frame) {
+            tracing::trace!(hostname = %self.member.hostname, error = %e, "segment: frame to backend dropped");
+        }
+    }
+}
+
+/// State shared by a segment and its ports.
+#[derive(Debug)]
+struct Shared {
+    /// Segment name (also the DNS domain of its members).
+    name: String,
+    /// Attached members.
+    ports: Mutex<Vec<Arc<Port>>>,
+}
+
+impl Shared {
+    /// Snapshot of the attached ports.
+    fn ports(&self) -> Vec<Arc<Port>> {
+        self.ports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Switches one frame sent by `from`'s guest.
+    fn forward(&self, from: &Port, frame: &[u8]) {
+        let ports = self.ports();
+        let members: Vec<&SegmentMember> = ports.iter().map(|port| &port.member).collect();
+        match route(&self.name, &members, &from.member, frame) {
+            Route::Drop => {}
+            Route::Backend => from.send_backend(frame),
+            Route::Reply(reply) => from.send_guest(&reply),
+            Route::Member(mac) => {
+                if let Some(port) = ports.iter().find(|port| port.member.address.mac == mac) {
+                    port.send_guest(frame);
+                }
+            }
+            Route::Flood => {
+                from.send_backend(frame);
+                ports
+                    .iter()
+                    .filter(|port| port.member.address.mac != from.member.address.mac)
+                    .for_each(|port| port.send_guest(frame));
+            }
+        }
+    }
+}
+
+/// Where a guest frame goes.
+#[derive(Debug, PartialEq, Eq)]
+enum Route {
+    /// Discard.
+    Drop,
+    /// The sender's own backend.
+    Backend,
+    /// The member with this MAC.
+    Member([u8; 6]),
+    /// The sender's backend and every other member.
+    Flood,
+    /// A reply the switch built for the sender.
+    Reply(Vec<u8>),
+}
+
+/// Routes a frame sent by `from` on segment `segment`.
+fn route(segment: &str, members: &[&SegmentMember], from: &SegmentMember, frame: &[u8]) -> Route {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return Route::Drop;
+    };
+    if eth.src_addr().0 != from.address.mac {
+        return Route::Drop;
+    }
+    if let Some(reply) = local_dns(segment, members, from, frame) {
+        return Route::Reply(reply);
+    }
+    let dst = eth.dst_addr();
+    if dst.is_broadcast() || dst.is_multicast() {
+        if eth.ethertype() == EthernetProtocol::Arp
+            && let Ok(arp) = ArpPacket::new_checked(eth.payload())
+            && let Some(owner) = members
+                .iter()
+                .find(|m| m.address.ip.octets() == arp.target_protocol_addr())
+        {
+            return if owner.address.mac == from.address.mac {
+                Route::Drop
+            } else {
+                Route::Member(owner.address.mac)
+            };
+        }
+        return Route::Flood;
+    }
+    if dst.0 != from.address.mac && members.iter().any(|m| m.address.mac == dst.0) {
+        Route::Member(dst.0)
+    } else {
+        Route::Backend
+    }
+}
+
+/// Answers a gateway DNS query for a member's name.
+fn local_dns(
+    segment: &str,
+    members: &[&SegmentMember],
+    from: &SegmentMember,
+    frame: &[u8],
+) -> Option<Vec<u8>> {
+    let dgram = frame::parse_udp(frame)?;
+    if dgram.dst != SocketAddr::from((GATEWAY_IPV4, 53)) {
+        return None;
+    }
+    let name = dns::question_name(dgram.payload)?.to_ascii_lowercase();
+    let name = name.trim_end_matches('.');
+    let host = name
+        .strip_suffix(segment)
+        .and_then(|rest| rest.strip_suffix('.'))
+        .unwrap_or(name);
+    let owner = members
+        .iter()
+        .find(|m| m.hostname.eq_ignore_ascii_case(host))?;
+    let answer = dns::address_reply(dgram.payload, owner.address.ip)?;
+    frame::udp_reply(from.address.mac, dgram.dst, dgram.src, &answer)
+}
+
+/// Reads one length-prefixed frame.
+fn read_frame(stream: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len)?;
+    let len = usize::try_from(u32::from_be_bytes(len)).map_err(io::Error::other)?;
+    if len > MAX_FRAME {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes"),
+        ));
+    }
+    let mut frame = vec![0; len];
+    stream.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+/// Writes one length-prefixed frame.
+fn write_frame(stream: &mut UnixStream, frame: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(frame.len()).map_err(io::Error::other)?;
+    let mut buf = Vec::with_capacity(frame.len() + 4);
+    buf.extend_from_slice(&len.to_be_bytes());
+    buf.extend_from_slice(frame);
+    stream.write_all(&buf)
+}
+
+/// An in-process Ethernet segment shared by the members of one private
+/// network. Cheap to clone; the switch lives while ports are attached.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// Switch state.
+    shared: Arc<Shared>,
+}
+
+impl Segment {
+    /// An empty segment. `name` is also the DNS domain of its members.
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                name: name.into().to_ascii_lowercase(),
+                ports: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Segment name.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.shared.name
+    }
+
+    /// Attached members, in attach order.
+    #[must_use]
+    pub fn members(&self) -> Vec<SegmentMember> {
+        self.shared
+            .ports()
+            .iter()
+            .map(|port| port.member.clone())
+            .collect()
+    }
+
+    /// Whether no member is attached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.shared
+            .ports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+    }
+
+    /// Attaches `member`, whose backend listens at `backend`, and exposes
+    /// the guest side at `guest_socket`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] if the endpoint is not a stream socket,
+    /// its MAC differs from the member's, or the hostname, address or MAC
+    /// is already on the segment; [`NetError::Io`] if the sockets cannot
+    /// be connected or bound.
+    pub fn attach(
+        &self,
+        member: SegmentMember,
+        backend: &NetworkEndpoint,
+        guest_socket: PathBuf,
+    ) -> Result<SegmentPort> {
+        let NetworkEndpoint::UnixSocket {
+            path,
+            connection_type,
+            mac_address,
+        } = backend;
+        if *connection_type != ConnectionType::UnixStream {
+            return Err(NetError::Config(
+                "private networks need a stream-socket backend".to_owned(),
+            ));
+        }
+        if *mac_address != member.address.mac {
+            return Err(NetError::Config(format!(
+                "backend MAC does not match member {}",
+                member.hostname
+            )));
+        }
+
+        let mut ports = self
+            .shared
+            .ports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(taken) = ports.iter().map(|port| &port.member).find(|m| {
+            m.hostname.eq_ignore_ascii_case(&member.hostname)
+                || m.address.ip == member.address.ip
+                || m.address.mac == member.address.mac
+        }) {
+            return Err(NetError::Config(format!(
+                "{} conflicts with member {} ({}) on network {}",
+                member.hostname, taken.hostname, taken.address.ip, self.shared.name
+            )));
+        }
+
+        let upstream = UnixStream::connect(path)?;
+        if guest_socket.exists() {
+            std::fs::remove_file(&guest_socket)?;
+        }
+        let listener = UnixListener::bind(&guest_socket)?;
+        let port = Arc::new(Port {
+            member,
+            guest: Mutex::new(None),
+            backend: Mutex::new(upstream.try_clone()?),
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut threads = Vec::with_capacity(2);
+        let reader = upstream.try_clone()?;
+        let backend_port = Arc::clone(&port);
+        threads.push(
+            std::thread::Builder::new()
+                .name(format!("bux-segment-{}", port.member.hostname))
+                .spawn(move || serve_backend(reader, &backend_port))?,
+        );
+        let (shared, guest_port, guest_stop) = (
+            Arc::clone(&self.shared),
+            Arc::clone(&port),
+            Arc::clone(&stop),
+        );
+        threads.push(
+            std::thread::Builder::new()
+                .name(format!("bux-segment-{}-guest", port.member.hostname))
+                .spawn(move || serve_guest(&listener, &shared, &guest_port, &guest_stop))?,
+        );
+        ports.push(Arc::clone(&port));
+        drop(ports);
+
+        tracing::info!(
+            network = %self.shared.name,
+            hostname = %port.member.hostname,
+            ip = %port.member.address.ip,
+            "attached to private network"
+        );
+        Ok(SegmentPort {
+            shared: Arc::clone(&self.shared),
+            port,
+            upstream,
+            guest_socket,
+            stop,
+            threads,
+        })
+    }
+}
+
+/// Relays frames from a member's backend to its guest until the backend
+/// connection closes.
+fn serve_backend(mut reader: UnixStream, port: &Port) {
+    while let Ok(frame) = read_frame(&mut reader) {
+        port.send_guest(&frame);
+    }
+}
+
+/// Accepts the engine's connection (again after each hang-up) and switches
+/// its frames until `stop` is set.
+fn serve_guest(listener: &UnixListener, shared: &Shared, port: &Port, stop: &AtomicBool) {
+    for conn in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        let Ok(mut stream) = conn else {
+            continue;
+        };
+        let writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(e) => {
+                tracing::warn!(error = %e, "segment: cannot clone guest stream");
+                continue;
+            }
+        };
+        writer.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
+        *port.guest.lock().unwrap_or_else(PoisonError::into_inner) = Some(writer);
+        // Re-check after publishing the writer: a concurrent detach either
+        // saw it (and shut it down) or set `stop` before we got here.
+        if stop.load(Ordering::SeqCst) {
+            return;
+        }
+        while let Ok(frame) = read_frame(&mut stream) {
+            shared.forward(port, &frame);
+        }
+        port.guest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+    }
+}
+
+/// A member's attachment to a [`Segment`]. Dropping it detaches the
+/// member, closes both sides and removes the guest socket.
+#[derive(Debug)]
+pub struct SegmentPort {
+    /// Switch state.
+    shared: Arc<Shared>,
+    /// This member's port.
+    port: Arc<Port>,
+    /// Connection to the member's backend.
+    upstream: UnixStream,
+    /// Socket the engine connects to.
+    guest_socket: PathBuf,
+    /// Tells the accept loop to exit.
+    stop: Arc<AtomicBool>,
+    /// Relay threads.
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl SegmentPort {
+    /// Endpoint the VM engine connects to in place of the backend's.
+    #[must_use]
+    pub fn endpoint(&self) -> NetworkEndpoint {
+        NetworkEndpoint::UnixSocket {
+            path: self.guest_socket.clone(),
+            connection_type: ConnectionType::UnixStream,
+            mac_address: self.port.member.address.mac,
+        }
+    }
+
+    /// This member's identity.
+    #[must_use]
+    pub fn member(&self) -> &SegmentMember {
+        &self.port.member
+    }
+}
+
+impl Drop for SegmentPort {
+    fn drop(&mut self) {
+        let mac = self.port.member.address.mac;
+        self.shared
+            .ports
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|port| port.member.address.mac != mac);
+        self.stop.store(true, Ordering::SeqCst);
+        self.upstream.shutdown(Shutdown::Both).ok();
+        let guest = self
+            .port
+            .guest
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(guest) = guest {
+            guest.shutdown(Shutdown::Both).ok();
+        }
+        // Wake the accept loop.
+        drop(UnixStream::connect(&self.guest_socket));
+        for thread in self.threads.drain(..) {
+            if thread.join().is_err() {
+                tracing::warn!("segment relay thread panicked");
+            }
+        }
+        std::fs::remove_file(&self.guest_socket).ok();
+        tracing::debug!(
+            network = %self.shared.name,
+            hostname = %self.port.member.hostname,
+            "detached from private network"
+        );
+    }
+}
+
+/// Picks the lowest free host octet (`2..=254`) not in `taken`, skipping
+/// the gateway and host gateway addresses.
+#[must_use]
+pub fn free_host<S: BuildHasher>(taken: &HashSet<u8, S>) -> Option<u8> {
+    let [.., gateway] = GATEWAY_IPV4.octets();
+    let [.., host] = HOST_IPV4.octets();
+    (2..=254).find(|octet| !taken.contains(octet) && *octet != gateway && *octet != host)
+}
+
+/// Whether `name` is a valid member hostname or segment name (one DNS
+/// label: ASCII letters, digits and inner hyphens, at most 63 bytes).
+#[must_use]
+pub fn valid_label(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 63
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, SocketAddrV4};
+
+    use bux_net_types::constants::GATEWAY_MAC;
+
+    use super::*;
+
+    fn member(name: &str, octet: u8) -> SegmentMember {
+        SegmentMember::new(name, GuestAddress::host(octet))
+    }
+
+    /// Ethernet header + `payload` with the given ethertype.
+    fn eth(dst: [u8; 6], src: [u8; 6], ethertype: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = dst.to_vec();
+        frame.extend_from_slice(&src);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    /// ARP request from `src` for `target`.
+    fn arp_request(src: &SegmentMember, target: Ipv4Addr) -> Vec<u8> {
+        let mut arp = vec![0, 1, 8, 0, 6, 4, 0, 1];
+        arp.extend_from_slice(&src.address.mac);
+        arp.extend_from_slice(&src.address.ip.octets());
+        arp.extend_from_slice(&[0; 6]);
+        arp.extend_from_slice(&target.octets());
+        eth([0xff; 6], src.address.mac, 0x0806, &arp)
+    }
+
+    /// DNS `A` query for `name` from `src` to the gateway.
+    fn dns_query(src: &SegmentMember, name: &str) -> Vec<u8> {
+        let mut query = vec![0xab, 0xcd, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            query.push(u8::try_from(label.len()).unwrap());
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.extend_from_slice(&[0, 0, 1, 0, 1]);
+        let mut frame = frame::udp_reply(
+            GATEWAY_MAC,
+            SocketAddrV4::new(src.address.ip, 40_000),
+            SocketAddrV4::new(GATEWAY_IPV4, 53),
+            &query,
+        )
+        .unwrap();
+        // Sent by the member, to the gateway.
+        frame[..6].copy_from_slice(&GATEWAY_MAC);
+        frame[6..12].copy_from_slice(&src.address.mac);
+        frame
+    }
+
+    #[test]
+    fn routes_by_destination_mac() {
+        let (db, agent) = (member("db", 2), member("agent", 3));
+        let members = [&db, &agent];
+        let to_db = eth(db.address.mac, agent.address.mac, 0x0800, &[0; 20]);
+        assert_eq!(
+            route("lab", &members, &agent, &to_db),
+            Route::Member(db.address.mac)
+        );
+        let to_gateway = eth(GATEWAY_MAC, agent.address.mac, 0x0800, &[0; 20]);
+        assert_eq!(route("lab", &members, &agent, &to_gateway), Route::Backend);
+        let spoofed = eth(db.address.mac, [2, 0, 0, 0, 0, 1], 0x0800, &[0; 20]);
+        assert_eq!(route("lab", &members, &agent, &spoofed), Route::Drop);
+
+        let arp_gateway = arp_request(&agent, GATEWAY_IPV4);
+        assert_eq!(route("lab", &members, &agent, &arp_gateway), Route::Flood);
+        let arp_db = arp_request(&agent, db.address.ip);
+        assert_eq!(
+            route("lab", &members, &agent, &arp_db),
+            Route::Member(db.address.mac)
+        );
+    }
+
+    #[test]
+    fn answers_member_names() {
+        let (db, agent) = (member("db", 2), member("agent", 3));
+        let members = [&db, &agent];
+        for name in ["db", "DB.lab"] {
+            let route = route("lab", &members, &agent, &dns_query(&agent, name));
+            let Route::Reply(reply) = route else {
+                unreachable!("{name} not answered locally: {route:?}");
+            };
+            assert_eq!(&reply[..6], &agent.address.mac);
+            let dgram = frame::parse_udp(&reply).unwrap();
+            assert_eq!(dgram.dst, SocketAddr::from((agent.address.ip, 40_000)));
+            assert_eq!(
+                dns::address_records(dgram.payload),
+                vec![IpAddr::V4(db.address.ip)]
+            );
+        }
+        let external = dns_query(&agent, "example.com");
+        assert_eq!(route("lab", &members, &agent, &external), Route::Backend);
+    }
+
+    #[test]
+    fn switches_between_attached_members() {
+        let dir = tempfile::tempdir().unwrap();
+        let segment = Segment::new("lab");
+        let mut guests = Vec::new();
+        let mut backends = Vec::new();
+        let mut ports = Vec::new();
+        for (name, octet) in [("db", 2), ("agent", 3)] {
+            let listener = UnixListener::bind(dir.path().join(format!("{name}.up"))).unwrap();
+            let backend = NetworkEndpoint::UnixSocket {
+                path: dir.path().join(format!("{name}.up")),
+                connection_type: ConnectionType::UnixStream,
+                mac_address: GuestAddress::host(octet).mac,
+            };
+            let port = segment
+                .attach(member(name, octet), &backend, dir.path().join(name))
+                .unwrap();
+            backends.push(listener.accept().unwrap().0);
+            let NetworkEndpoint::UnixSocket { path, .. } = port.endpoint();
+            let guest = UnixStream::connect(path).unwrap();
+            guest
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            guests.push(guest);
+            ports.push(port);
+        }
+        assert!(
+            segment
+                .attach(
+                    member("DB", 9),
+                    &ports[0].endpoint(),
+                    dir.path().join("dup")
+                )
+                .is_err()
+        );
+
+        let (db, agent) = (member("db", 2), member("agent", 3));
+        let frame = eth(db.address.mac, agent.address.mac, 0x0800, &[7; 20]);
+        // The guest connection is published by the accept thread; retry
+        // until the first frame gets through.
+        guests[0]
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let mut received = None;
+        for _ in 0..100 {
+            write_frame(&mut guests[1], &frame).unwrap();
+            if let Ok(got) = read_frame(&mut guests[0]) {
+                received = Some(got);
+                break;
+            }
+        }
+        assert_eq!(received.unwrap(), frame);
+        while read_frame(&mut guests[0]).is_ok() {}
+        guests[0]
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        // Backend → guest.
+        write_frame(&mut backends[0], &frame).unwrap();
+        assert_eq!(read_frame(&mut guests[0]).unwrap(), frame);
+
+        drop(ports.pop());
+        assert_eq!(segment.members(), vec![db]);
+        assert!(!dir.path().join("agent").exists());
+    }
+
+    #[test]
+    fn allocates_and_validates() {
+        assert_eq!(free_host(&HashSet::new()), Some(2));
+        assert_eq!(free_host(&HashSet::from([2, 3])), Some(4));
+        assert_eq!(free_host(&(2..=254).collect::<HashSet<_>>()), None);
+        assert_eq!(free_host(&(2..=253).collect::<HashSet<_>>()), None);
+        assert!(valid_label("db-1"));
+        for bad in ["", "-db", "db.lab", "db_1", &"x".repeat(64)] {
+            assert!(!valid_label(bad), "{bad}");
+        }
+    }
+}
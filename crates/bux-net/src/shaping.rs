@@ -0,0 +1,290 @@
+//! Per-VM bandwidth limits and data-transfer quotas.
+//!
+//! [`TrafficLimits`] caps throughput in each direction with a token
+//! bucket and sets cumulative byte quotas. Once a quota is used up, its
+//! [`QuotaAction`] applies: new connections are refused, the direction is
+//! throttled to a lower rate, or the VM is stopped by its owner (the
+//! backend itself only refuses new connections and reports the event).
+//!
+//! Both backends meter whole frames on the guest link, so rates and
+//! quotas include protocol headers. Each direction is reported once with
+//! [`NetEventKind::QuotaExceeded`](crate::NetEventKind::QuotaExceeded) and
+//! flagged in [`NetworkMetrics::quota_exceeded`](crate::NetworkMetrics::quota_exceeded).
+//!
+//! Sizes on the command line use [`parse_byte_size`]: a number with an
+//! optional binary `K`, `M`, `G` or `T` suffix (`"10M"` = 10 MiB).
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{NetError, Result};
+
+/// Traffic from the guest (egress) or to it (ingress).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrafficDirection {
+    /// Guest → network.
+    Egress,
+    /// Network → guest.
+    Ingress,
+}
+
+impl TrafficDirection {
+    /// Stable lowercase name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Egress => "egress",
+            Self::Ingress => "ingress",
+        }
+    }
+}
+
+impl fmt::Display for TrafficDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What happens once a direction has used up its quota.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum QuotaAction {
+    /// Refuse new connections; established ones keep running.
+    #[default]
+    Block,
+    /// Cap the direction at `rate` bytes per second.
+    Throttle {
+        /// Rate after the quota, in bytes per second.
+        rate: u64,
+    },
+    /// Refuse new connections and have the VM's owner stop it.
+    Stop,
+}
+
+impl fmt::Display for QuotaAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Block => f.write_str("block"),
+            Self::Throttle { rate } => write!(f, "throttle:{rate}"),
+            Self::Stop => f.write_str("stop"),
+        }
+    }
+}
+
+impl FromStr for QuotaAction {
+    type Err = NetError;
+
+    /// Parses `block`, `stop` or `throttle:RATE` (`RATE` as in
+    /// [`parse_byte_size`], per second).
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "block" => Ok(Self::Block),
+            "stop" => Ok(Self::Stop),
+            other => match other.strip_prefix("throttle:") {
+                Some(rate) => Ok(Self::Throttle {
+                    rate: parse_byte_size(rate)?,
+                }),
+                None => Err(NetError::Config(format!(
+                    "quota action {other:?}: expected block, stop or throttle:RATE"
+                ))),
+            },
+        }
+    }
+}
+
+/// Rate limits and quotas for one VM. `None` leaves a value unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficLimits {
+    /// Guest → network rate, in bytes per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_rate: Option<u64>,
+    /// Network → guest rate, in bytes per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_rate: Option<u64>,
+    /// Total guest → network bytes before [`Self::on_quota`] applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub egress_quota: Option<u64>,
+    /// Total network → guest bytes before [`Self::on_quota`] applies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ingress_quota: Option<u64>,
+    /// Action once either quota is used up.
+    #[serde(default)]
+    pub on_quota: QuotaAction,
+}
+
+impl TrafficLimits {
+    /// No limits.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            egress_rate: None,
+            ingress_rate: None,
+            egress_quota: None,
+            ingress_quota: None,
+            on_quota: QuotaAction::Block,
+        }
+    }
+
+    /// Caps guest → network throughput.
+    #[must_use]
+    pub const fn with_egress_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.egress_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Caps network → guest throughput.
+    #[must_use]
+    pub const fn with_ingress_rate(mut self, bytes_per_sec: u64) -> Self {
+        self.ingress_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Sets the guest → network quota.
+    #[must_use]
+    pub const fn with_egress_quota(mut self, bytes: u64) -> Self {
+        self.egress_quota = Some(bytes);
+        self
+    }
+
+    /// Sets the network → guest quota.
+    #[must_use]
+    pub const fn with_ingress_quota(mut self, bytes: u64) -> Self {
+        self.ingress_quota = Some(bytes);
+        self
+    }
+
+    /// Sets the action once a quota is used up.
+    #[must_use]
+    pub const fn with_quota_action(mut self, action: QuotaAction) -> Self {
+        self.on_quota = action;
+        self
+    }
+
+    /// Whether no rate or quota is set.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.egress_rate.is_none()
+            && self.ingress_rate.is_none()
+            && self.egress_quota.is_none()
+            && self.ingress_quota.is_none()
+    }
+
+    /// Rate limit for `direction`.
+    #[must_use]
+    pub const fn rate(&self, direction: TrafficDirection) -> Option<u64> {
+        match direction {
+            TrafficDirection::Egress => self.egress_rate,
+            TrafficDirection::Ingress => self.ingress_rate,
+        }
+    }
+
+    /// Quota for `direction`.
+    #[must_use]
+    pub const fn quota(&self, direction: TrafficDirection) -> Option<u64> {
+        match direction {
+            TrafficDirection::Egress => self.egress_quota,
+            TrafficDirection::Ingress => self.ingress_quota,
+        }
+    }
+
+    /// Checks that every configured value is non-zero.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] naming the first zero value.
+    pub fn validate(&self) -> Result<()> {
+        let throttle = match self.on_quota {
+            QuotaAction::Throttle { rate } => Some(rate),
+            _ => None,
+        };
+        let values = [
+            ("egress rate", self.egress_rate),
+            ("ingress rate", self.ingress_rate),
+            ("egress quota", self.egress_quota),
+            ("ingress quota", self.ingress_quota),
+            ("throttle rate", throttle),
+        ];
+        match values.iter().find(|(_, value)| *value == Some(0)) {
+            Some((name, _)) => Err(NetError::Config(format!("{name} must be non-zero"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Parses a byte count with an optional binary suffix (`K`, `M`, `G`,
+/// `T`, case-insensitive, optionally followed by `B` or `iB`).
+///
+/// # Errors
+///
+/// Returns [`NetError::Config`] if the number does not parse or overflows.
+pub fn parse_byte_size(s: &str) -> Result<u64> {
+    let trimmed = s.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    let unit = lower
+        .strip_suffix("ib")
+        .or_else(|| lower.strip_suffix('b'))
+        .unwrap_or(&lower);
+    let (digits, shift) = match unit.char_indices().last() {
+        Some((at, 'k')) => (unit.get(..at), 10),
+        Some((at, 'm')) => (unit.get(..at), 20),
+        Some((at, 'g')) => (unit.get(..at), 30),
+        Some((at, 't')) => (unit.get(..at), 40),
+        _ => (Some(unit), 0),
+    };
+    digits
+        .and_then(|digits| digits.trim().parse::<u64>().ok())
+        .and_then(|n| n.checked_mul(1_u64 << shift))
+        .ok_or_else(|| NetError::Config(format!("invalid byte size {trimmed:?}")))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes_and_actions() {
+        assert_eq!(parse_byte_size("512").unwrap(), 512);
+        assert_eq!(parse_byte_size("10M").unwrap(), 10 << 20);
+        assert_eq!(parse_byte_size("2GiB").unwrap(), 2 << 30);
+        assert_eq!(parse_byte_size("4kb").unwrap(), 4096);
+        assert!(parse_byte_size("M").is_err());
+        assert!(parse_byte_size("1.5G").is_err());
+        assert!(parse_byte_size("99999999T").is_err());
+
+        assert_eq!("stop".parse::<QuotaAction>().unwrap(), QuotaAction::Stop);
+        assert_eq!(
+            "throttle:1M".parse::<QuotaAction>().unwrap(),
+            QuotaAction::Throttle { rate: 1 << 20 }
+        );
+        assert!("drop".parse::<QuotaAction>().is_err());
+    }
+
+    #[test]
+    fn limits_validate_and_serialize_sparsely() {
+        let limits = TrafficLimits::new()
+            .with_egress_rate(1000)
+            .with_ingress_quota(1 << 30)
+            .with_quota_action(QuotaAction::Throttle { rate: 100 });
+        assert!(!limits.is_unlimited());
+        assert!(TrafficLimits::default().is_unlimited());
+        limits.validate().unwrap();
+        assert!(limits.with_egress_quota(0).validate().is_err());
+
+        let json = serde_json::to_string(&limits).unwrap();
+        assert_eq!(
+            json,
+            r#"{"egress_rate":1000,"ingress_quota":1073741824,"on_quota":{"throttle":{"rate":100}}}"#
+        );
+        let back: TrafficLimits = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, limits);
+        assert_eq!(
+            serde_json::from_str::<TrafficLimits>("{}").unwrap(),
+            TrafficLimits::new()
+        );
+    }
+}
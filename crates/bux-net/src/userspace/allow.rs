@@ -0,0 +1,259 @@
+//! Egress policy enforcement for the userspace backend.
+//!
+//! Flows are decided by [`EgressPolicy::evaluate`]. Hostname rules are
+//! enforced at the resolver: queries the policy refuses get `NXDOMAIN`,
+//! and addresses answered for a name are learned so later connections to
+//! them are evaluated with that hostname as well as the address.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+
+use crate::egress::{EgressAction, EgressFlow, EgressPolicy, EgressProtocol};
+
+/// Hostnames remembered per learned address.
+const NAMES_PER_ADDRESS: usize = 8;
+
+/// Addresses remembered from DNS answers. Past this, the address answered
+/// least recently is forgotten (and judged by address alone again).
+const MAX_LEARNED: usize = 4096;
+
+/// Hostnames learned for one address.
+#[derive(Debug, Default)]
+struct Learned {
+    /// Names the resolver answered with the address.
+    names: Vec<String>,
+    /// Answer count when the address was last answered.
+    answered: u64,
+}
+
+/// Egress policy plus hostnames learned from DNS answers.
+#[derive(Debug, Default)]
+pub(crate) struct AllowList {
+    /// Rules and default action.
+    policy: EgressPolicy,
+    /// Gateway / guest addresses, always reachable.
+    internal: HashSet<IpAddr>,
+    /// Names the resolver answered with each address.
+    learned: HashMap<IpAddr, Learned>,
+    /// Addresses answered so far; orders [`Learned::answered`].
+    answers: u64,
+}
+
+impl AllowList {
+    /// Wraps `policy`; the empty policy yields an unrestricted matcher.
+    pub(crate) fn new(policy: EgressPolicy, internal: &[IpAddr]) -> Self {
+        Self {
+            policy,
+            internal: internal.iter().copied().collect(),
+            learned: HashMap::new(),
+            answers: 0,
+        }
+    }
+
+    /// Whether the policy can refuse anything.
+    pub(crate) fn is_restricted(&self) -> bool {
+        !self.policy.is_unrestricted()
+    }
+
+    /// Whether traffic to `ip:port` over `protocol` may leave the host;
+    /// `Err` carries the deny rule that blocked it, or `None` when nothing
+    /// admitted it.
+    ///
+    /// The flow is evaluated by address and under each learned hostname:
+    /// an explicit deny in any view blocks it, otherwise any allow admits.
+    pub(crate) fn check(
+        &self,
+        ip: IpAddr,
+        port: u16,
+        protocol: EgressProtocol,
+    ) -> Result<(), Option<String>> {
+        if !self.is_restricted() || self.internal.contains(&ip) {
+            return Ok(());
+        }
+        let flow = EgressFlow {
+            host: None,
+            ip: Some(ip),
+            port,
+            protocol,
+        };
+        let names = self
+            .learned
+            .get(&ip)
+            .map(|learned| learned.names.as_slice())
+            .unwrap_or_default();
+        let decisions: Vec<_> = std::iter::once(flow)
+            .chain(names.iter().map(|name| flow.with_host(name)))
+            .map(|view| self.policy.evaluate(&view))
+            .collect();
+        let denied = decisions
+            .iter()
+            .filter(|d| d.action == EgressAction::Deny)
+            .find_map(|d| d.rule);
+        if let Some(rule) = denied {
+            return Err(self.policy.rules().get(rule).map(ToString::to_string));
+        }
+        if decisions.iter().any(|d| d.is_allowed()) {
+            Ok(())
+        } else {
+            Err(None)
+        }
+    }
+
+    /// Whether the resolver may answer queries for `name`.
+    pub(crate) fn permits_host(&self, name: &str) -> bool {
+        !self.is_restricted() || self.policy.permits_lookup(name)
+    }
+
+    /// The deny rule refusing lookups of `name`, if one does.
+    pub(crate) fn lookup_denial(&self, name: &str) -> Option<String> {
+        self.policy.lookup_denial(name).map(ToString::to_string)
+    }
+
+    /// First hostname learned for `ip`.
+    pub(crate) fn host_of(&self, ip: IpAddr) -> Option<String> {
+        self.learned.get(&ip)?.names.first().cloned()
+    }
+
+    /// Records addresses the resolver returned for `name`, keeping at
+    /// most [`MAX_LEARNED`] addresses.
+    pub(crate) fn learn(&mut self, name: Option<String>, ips: impl IntoIterator<Item = IpAddr>) {
+        let Some(name) = name.filter(|_| self.is_restricted()) else {
+            return;
+        };
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        for ip in ips {
+            if self.learned.len() >= MAX_LEARNED && !self.learned.contains_key(&ip) {
+                self.forget_oldest();
+            }
+            self.answers += 1;
+            let learned = self.learned.entry(ip).or_default();
+            learned.answered = self.answers;
+            if !learned.names.contains(&name) && learned.names.len() < NAMES_PER_ADDRESS {
+                learned.names.push(name.clone());
+            }
+        }
+    }
+
+    /// Drops the address answered least recently.
+    fn forget_oldest(&mut self) {
+        let oldest = self
+            .learned
+            .iter()
+            .min_by_key(|(_, learned)| learned.answered)
+            .map(|(ip, _)| *ip);
+        if let Some(ip) = oldest {
+            self.learned.remove(&ip);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    fn list(rules: &[&str]) -> AllowList {
+        let policy = EgressPolicy::parse(rules).unwrap();
+        AllowList::new(policy, &[Ipv4Addr::new(192, 168, 127, 1).into()])
+    }
+
+    fn permits(
+        allow: &AllowList,
+        ip: impl Into<IpAddr>,
+        port: u16,
+        protocol: EgressProtocol,
+    ) -> bool {
+        allow.check(ip.into(), port, protocol).is_ok()
+    }
+
+    fn tcp(allow: &AllowList, ip: impl Into<IpAddr>) -> bool {
+        permits(allow, ip, 443, EgressProtocol::Tcp)
+    }
+
+    #[test]
+    fn empty_rules_allow_everything() {
+        let allow = list(&[]);
+        assert!(!allow.is_restricted());
+        assert!(tcp(&allow, Ipv4Addr::new(8, 8, 8, 8)));
+        assert!(allow.permits_host("anything.example"));
+    }
+
+    #[test]
+    fn matches_ips_cidrs_and_internal_addresses() {
+        let allow = list(&["1.2.3.4", "10.0.0.0/8"]);
+        assert!(tcp(&allow, Ipv4Addr::new(1, 2, 3, 4)));
+        assert!(tcp(&allow, Ipv4Addr::new(10, 200, 0, 1)));
+        assert!(tcp(&allow, Ipv4Addr::new(192, 168, 127, 1)));
+        assert!(!tcp(&allow, Ipv4Addr::new(11, 0, 0, 1)));
+        assert_eq!(
+            allow.check(Ipv4Addr::new(11, 0, 0, 1).into(), 443, EgressProtocol::Tcp),
+            Err(None)
+        );
+    }
+
+    #[test]
+    fn hostnames_wildcards_and_learned_addresses() {
+        let mut allow = list(&["API.example.com:443", "*.pypi.org"]);
+        assert!(allow.permits_host("api.example.com."));
+        assert!(allow.permits_host("files.pypi.org"));
+        assert!(!allow.permits_host("pypi.org"));
+        assert!(!allow.permits_host("evil.com"));
+
+        let resolved = Ipv4Addr::new(93, 184, 216, 34);
+        assert!(!tcp(&allow, resolved));
+        allow.learn(Some("api.example.com.".into()), [resolved.into()]);
+        assert!(tcp(&allow, resolved));
+        assert!(!permits(&allow, resolved, 80, EgressProtocol::Tcp));
+
+        let resolved_v6: Ipv6Addr = "2606:2800:220:1::1".parse().unwrap();
+        assert!(!tcp(&allow, resolved_v6));
+        allow.learn(Some("api.example.com".into()), [resolved_v6.into()]);
+        assert!(tcp(&allow, resolved_v6));
+    }
+
+    #[test]
+    fn learned_addresses_are_capped_least_recent_first() {
+        let mut allow = list(&["api.example.com"]);
+        let addr =
+            |i: usize| -> IpAddr { Ipv4Addr::from(0x0a00_0000 + u32::try_from(i).unwrap()).into() };
+        for i in 0..MAX_LEARNED {
+            allow.learn(Some("api.example.com".into()), [addr(i)]);
+        }
+        // Answered again, so the next eviction skips it.
+        allow.learn(Some("api.example.com".into()), [addr(0)]);
+        allow.learn(Some("api.example.com".into()), [addr(MAX_LEARNED)]);
+
+        assert_eq!(allow.learned.len(), MAX_LEARNED);
+        assert!(tcp(&allow, addr(0)));
+        assert!(!tcp(&allow, addr(1)));
+        assert!(tcp(&allow, addr(MAX_LEARNED)));
+    }
+
+    #[test]
+    fn denies_override_allows_per_port_and_protocol() {
+        let mut allow = list(&["*", "!169.254.169.254", "!*.tracker.example:443/tcp"]);
+        assert!(!tcp(&allow, Ipv4Addr::new(169, 254, 169, 254)));
+        assert!(permits(
+            &allow,
+            Ipv4Addr::new(9, 9, 9, 9),
+            53,
+            EgressProtocol::Udp
+        ));
+        assert!(allow.permits_host("ads.tracker.example"));
+
+        let shared = Ipv4Addr::new(203, 0, 113, 7);
+        allow.learn(Some("ads.tracker.example".into()), [shared.into()]);
+        assert!(!tcp(&allow, shared));
+        assert!(permits(&allow, shared, 443, EgressProtocol::Udp));
+        assert_eq!(
+            allow.host_of(shared.into()).as_deref(),
+            Some("ads.tracker.example")
+        );
+        assert_eq!(
+            allow.check(shared.into(), 443, EgressProtocol::Tcp),
+            Err(Some("!*.tracker.example:443/tcp".to_owned()))
+        );
+    }
+}
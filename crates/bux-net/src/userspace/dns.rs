@@ -0,0 +1,242 @@
+//! Minimal DNS message handling for the gateway resolver.
+//!
+//! The backend does not resolve names itself: queries are relayed to the
+//! host's upstream server. It only needs to read the question (for the
+//! egress policy), synthesize error replies, and collect `A`/`AAAA`
+//! answers.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// DNS header length.
+const HEADER_LEN: usize = 12;
+/// `A` record type.
+const TYPE_A: u16 = 1;
+/// `AAAA` record type.
+const TYPE_AAAA: u16 = 28;
+/// `IN` class.
+const CLASS_IN: u16 = 1;
+/// TTL of locally answered records, in seconds.
+const LOCAL_TTL: u32 = 60;
+
+/// `SERVFAIL` response code.
+pub(crate) const RCODE_SERVFAIL: u8 = 2;
+/// `NXDOMAIN` response code.
+pub(crate) const RCODE_NXDOMAIN: u8 = 3;
+
+/// Reads a big-endian `u16` at `at`.
+fn u16_at(msg: &[u8], at: usize) -> Option<u16> {
+    let bytes = msg.get(at..at.checked_add(2)?)?;
+    Some(u16::from_be_bytes([*bytes.first()?, *bytes.get(1)?]))
+}
+
+/// Returns the offset just past the (possibly compressed) name at `at`,
+/// appending its labels to `labels` when given.
+fn skip_name(msg: &[u8], mut at: usize, mut labels: Option<&mut Vec<String>>) -> Option<usize> {
+    loop {
+        let len = *msg.get(at)?;
+        match len {
+            0 => return Some(at + 1),
+            l if l & 0xc0 == 0xc0 => return Some(at + 2),
+            l => {
+                let start = at + 1;
+                let end = start + usize::from(l);
+                if let Some(out) = labels.as_deref_mut() {
+                    out.push(String::from_utf8_lossy(msg.get(start..end)?).into_owned());
+                }
+                at = end;
+            }
+        }
+    }
+}
+
+/// Offset just past the question section, and the first question's name.
+fn question(msg: &[u8]) -> Option<(usize, String)> {
+    if u16_at(msg, 4)? == 0 {
+        return None;
+    }
+    let mut labels = Vec::new();
+    let end = skip_name(msg, HEADER_LEN, Some(&mut labels))?;
+    // QTYPE + QCLASS.
+    let end = end + 4;
+    (end <= msg.len()).then(|| (end, labels.join(".")))
+}
+
+/// Name asked by the first question of `query`.
+pub(crate) fn question_name(query: &[u8]) -> Option<String> {
+    question(query).map(|(_, name)| name)
+}
+
+/// Builds an answer-less reply to `query` with response code `rcode`.
+pub(crate) fn error_reply(query: &[u8], rcode: u8) -> Option<Vec<u8>> {
+    let (end, _) = question(query)?;
+    let mut reply = query.get(..end)?.to_vec();
+    // QR=1, keep opcode + RD; RA=1; rcode.
+    let flags = reply.get_mut(2..4)?;
+    let [hi, lo] = flags else { return None };
+    *hi = (*hi & 0x79) | 0x80;
+    *lo = 0x80 | (rcode & 0x0f);
+    // QDCOUNT = 1, AN/NS/AR = 0.
+    reply
+        .get_mut(4..12)?
+        .copy_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    Some(reply)
+}
+
+/// Builds an authoritative reply to `query` for a name owned by `addr`:
+/// an `A` question gets that record, any other type an empty answer.
+pub(crate) fn address_reply(query: &[u8], addr: Ipv4Addr) -> Option<Vec<u8>> {
+    let (end, _) = question(query)?;
+    let mut reply = error_reply(query, 0)?;
+    // AA=1.
+    *reply.get_mut(2)? |= 0x04;
+    if u16_at(query, end - 4)? == TYPE_A {
+        reply.get_mut(6..8)?.copy_from_slice(&1u16.to_be_bytes());
+        // Name pointer to the question, type A, class IN, TTL, RDLENGTH 4.
+        reply.extend_from_slice(&[0xc0, 12]);
+        reply.extend_from_slice(&TYPE_A.to_be_bytes());
+        reply.extend_from_slice(&CLASS_IN.to_be_bytes());
+        reply.extend_from_slice(&LOCAL_TTL.to_be_bytes());
+        reply.extend_from_slice(&4u16.to_be_bytes());
+        reply.extend_from_slice(&addr.octets());
+    }
+    Some(reply)
+}
+
+/// Address carried by the data of an `A` or `AAAA` record.
+fn record_address(kind: u16, rdata: &[u8]) -> Option<IpAddr> {
+    match kind {
+        TYPE_A => <[u8; 4]>::try_from(rdata)
+            .ok()
+            .map(|o| Ipv4Addr::from(o).into()),
+        TYPE_AAAA => <[u8; 16]>::try_from(rdata)
+            .ok()
+            .map(|o| Ipv6Addr::from(o).into()),
+        _ => None,
+    }
+}
+
+/// IPv4 and IPv6 addresses in the answer section of `reply`.
+pub(crate) fn address_records(reply: &[u8]) -> Vec<IpAddr> {
+    let mut out = Vec::new();
+    let Some(answers) = u16_at(reply, 6) else {
+        return out;
+    };
+    let Some((mut at, _)) = question(reply) else {
+        return out;
+    };
+    for _ in 0..answers {
+        let Some(rr) = skip_name(reply, at, None) else {
+            break;
+        };
+        let (Some(kind), Some(class), Some(len)) = (
+            u16_at(reply, rr),
+            u16_at(reply, rr + 2),
+            u16_at(reply, rr + 8),
+        ) else {
+            break;
+        };
+        let data = rr + 10;
+        if class == CLASS_IN
+            && let Some(addr) = reply
+                .get(data..data + usize::from(len))
+                .and_then(|rdata| record_address(kind, rdata))
+        {
+            out.push(addr);
+        }
+        at = data + usize::from(len);
+    }
+    out
+}
+
+/// First usable `nameserver` in resolv.conf-formatted `text`.
+///
+/// Scoped (`%iface`) link-local entries are skipped: the relay socket is
+/// not bound to an interface.
+pub(crate) fn parse_resolv_conf(text: &str) -> Option<SocketAddr> {
+    text.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|rest| rest.trim().parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 53))
+        .next()
+}
+
+/// Upstream resolver from `/etc/resolv.conf`, if any.
+pub(crate) fn system_resolver() -> Option<SocketAddr> {
+    parse_resolv_conf(&std::fs::read_to_string("/etc/resolv.conf").ok()?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use super::*;
+
+    /// Query for `example.com A` with id 0xbeef and RD set.
+    fn query() -> Vec<u8> {
+        let mut q = vec![0xbe, 0xef, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        q.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        q
+    }
+
+    #[test]
+    fn reads_question_name() {
+        assert_eq!(question_name(&query()).as_deref(), Some("example.com"));
+        assert_eq!(question_name(&[0; 12]), None);
+    }
+
+    #[test]
+    fn error_reply_echoes_question() {
+        let reply = error_reply(&query(), RCODE_NXDOMAIN).unwrap();
+        assert_eq!(reply.len(), query().len());
+        assert_eq!(&reply[..2], &[0xbe, 0xef]);
+        assert_eq!(reply[2], 0x81);
+        assert_eq!(reply[3] & 0x0f, RCODE_NXDOMAIN);
+        assert_eq!(question_name(&reply).as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn collects_compressed_address_answers() {
+        let mut reply = query();
+        reply[2] = 0x81;
+        reply[7] = 3;
+        // CNAME-less answers using a pointer to the question name.
+        for ip in [[93, 184, 216, 34], [93, 184, 216, 35]] {
+            reply.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+            reply.extend_from_slice(&ip);
+        }
+        let v6: Ipv6Addr = "2606:2800:220:1::1".parse().unwrap();
+        reply.extend_from_slice(&[0xc0, 12, 0, 28, 0, 1, 0, 0, 0, 60, 0, 16]);
+        reply.extend_from_slice(&v6.octets());
+        assert_eq!(
+            address_records(&reply),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34)),
+                IpAddr::V4(Ipv4Addr::new(93, 184, 216, 35)),
+                IpAddr::V6(v6),
+            ]
+        );
+    }
+
+    #[test]
+    fn answers_local_names() {
+        let addr = Ipv4Addr::new(192, 168, 127, 5);
+        let reply = address_reply(&query(), addr).unwrap();
+        assert_eq!(reply[2], 0x85);
+        assert_eq!(reply[3] & 0x0f, 0);
+        assert_eq!(address_records(&reply), vec![IpAddr::V4(addr)]);
+
+        // AAAA: NOERROR without answers, so resolvers fall back to A.
+        let mut aaaa = query();
+        let len = aaaa.len();
+        aaaa[len - 3] = 28;
+        let empty = address_reply(&aaaa, addr).unwrap();
+        assert_eq!(empty[3] & 0x0f, 0);
+        assert!(address_records(&empty).is_empty());
+    }
+
+    #[test]
+    fn parses_first_nameserver() {
+        let conf = "# comment\nsearch local\nnameserver fe80::1%eth0\nnameserver 1.1.1.1\n";
+        assert_eq!(parse_resolv_conf(conf), Some("1.1.1.1:53".parse().unwrap()));
+        assert_eq!(parse_resolv_conf("search x\n"), None);
+    }
+}
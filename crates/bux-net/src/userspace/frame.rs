@@ -0,0 +1,312 @@
+//! Ethernet frame plumbing: the smoltcp [`Device`] over in-memory queues,
+//! plus the few frames the backend builds itself (UDP replies, DHCP).
+
+use std::collections::VecDeque;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use bux_net_types::constants::{DEFAULT_MTU, GATEWAY_MAC};
+use smoltcp::phy::{self, ChecksumCapabilities, Device, DeviceCapabilities, Medium};
+use smoltcp::time::Instant;
+use smoltcp::wire::{
+    DhcpMessageType, DhcpPacket, DhcpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
+    EthernetRepr, IpProtocol, Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr, UdpPacket, UdpRepr,
+};
+
+/// Ethernet header length.
+const ETHERNET_HEADER: usize = 14;
+/// IPv4 header length (no options).
+const IPV4_HEADER: usize = 20;
+/// IPv6 header length (no extension headers).
+const IPV6_HEADER: usize = 40;
+/// UDP header length.
+const UDP_HEADER: usize = 8;
+/// Lease time handed out by the DHCP server, in seconds.
+const LEASE_SECS: u32 = 3600;
+
+/// Largest UDP payload that fits one unfragmented IPv4 frame.
+pub(crate) const MAX_UDP_PAYLOAD: usize = DEFAULT_MTU as usize - IPV4_HEADER - UDP_HEADER;
+/// Largest UDP payload that fits one IPv6 frame.
+pub(crate) const MAX_UDP6_PAYLOAD: usize = DEFAULT_MTU as usize - IPV6_HEADER - UDP_HEADER;
+
+/// Frames waiting for smoltcp (`rx`) and frames it produced (`tx`).
+#[derive(Debug, Default)]
+pub(crate) struct FrameQueue {
+    /// Guest → stack.
+    pub(crate) rx: VecDeque<Vec<u8>>,
+    /// Stack → guest.
+    pub(crate) tx: Vec<Vec<u8>>,
+}
+
+/// Receive token owning one guest frame.
+pub(crate) struct RxFrame(Vec<u8>);
+
+/// Transmit token appending to [`FrameQueue::tx`].
+pub(crate) struct TxFrame<'a>(&'a mut Vec<Vec<u8>>);
+
+impl phy::RxToken for RxFrame {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+impl phy::TxToken for TxFrame<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buf = vec![0; len];
+        let out = f(&mut buf);
+        self.0.push(buf);
+        out
+    }
+}
+
+impl Device for FrameQueue {
+    type RxToken<'a> = RxFrame;
+    type TxToken<'a> = TxFrame<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let frame = self.rx.pop_front()?;
+        Some((RxFrame(frame), TxFrame(&mut self.tx)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxFrame(&mut self.tx))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.medium = Medium::Ethernet;
+        caps.max_transmission_unit = usize::from(DEFAULT_MTU) + ETHERNET_HEADER;
+        caps
+    }
+}
+
+/// A parsed guest IPv4/UDP or IPv6/UDP datagram.
+#[derive(Debug)]
+pub(crate) struct Datagram<'a> {
+    /// Source endpoint.
+    pub(crate) src: SocketAddr,
+    /// Destination endpoint.
+    pub(crate) dst: SocketAddr,
+    /// UDP payload.
+    pub(crate) payload: &'a [u8],
+}
+
+/// Parses `frame` as Ethernet/IPv4/UDP or Ethernet/IPv6/UDP.
+pub(crate) fn parse_udp(frame: &[u8]) -> Option<Datagram<'_>> {
+    let eth = EthernetFrame::new_checked(frame).ok()?;
+    // Borrow the payload from `frame`, not the temporary packet views.
+    let (src_ip, dst_ip, ip_start, ip_end) = match eth.ethertype() {
+        EthernetProtocol::Ipv4 => {
+            let ip = Ipv4Packet::new_checked(eth.payload()).ok()?;
+            if ip.next_header() != IpProtocol::Udp || ip.more_frags() || ip.frag_offset() != 0 {
+                return None;
+            }
+            (
+                IpAddr::V4(ip.src_addr()),
+                IpAddr::V4(ip.dst_addr()),
+                ETHERNET_HEADER + usize::from(ip.header_len()),
+                ETHERNET_HEADER + usize::from(ip.total_len()),
+            )
+        }
+        EthernetProtocol::Ipv6 => {
+            let ip = Ipv6Packet::new_checked(eth.payload()).ok()?;
+            if ip.next_header() != IpProtocol::Udp {
+                return None;
+            }
+            let start = ETHERNET_HEADER + IPV6_HEADER;
+            (
+                IpAddr::V6(ip.src_addr()),
+                IpAddr::V6(ip.dst_addr()),
+                start,
+                start + usize::from(ip.payload_len()),
+            )
+        }
+        _ => return None,
+    };
+    let udp = UdpPacket::new_checked(frame.get(ip_start..ip_end)?).ok()?;
+    let (src_port, dst_port) = (udp.src_port(), udp.dst_port());
+    let payload_end = ip_start + usize::from(udp.len());
+    Some(Datagram {
+        src: SocketAddr::new(src_ip, src_port),
+        dst: SocketAddr::new(dst_ip, dst_port),
+        payload: frame.get(ip_start + UDP_HEADER..payload_end)?,
+    })
+}
+
+/// Builds a gateway → guest UDP frame carrying `payload_len` bytes;
+/// `None` if `src` and `dst` are of different families.
+fn udp_frame(
+    dst_mac: [u8; 6],
+    src: SocketAddr,
+    dst: SocketAddr,
+    payload_len: usize,
+    emit: impl FnOnce(&mut [u8]),
+) -> Option<Vec<u8>> {
+    let caps = ChecksumCapabilities::default();
+    let (ethertype, ip_header) = match (src.ip(), dst.ip()) {
+        (IpAddr::V4(_), IpAddr::V4(_)) => (EthernetProtocol::Ipv4, IPV4_HEADER),
+        (IpAddr::V6(_), IpAddr::V6(_)) => (EthernetProtocol::Ipv6, IPV6_HEADER),
+        _ => return None,
+    };
+    let mut buf = vec![0; ETHERNET_HEADER + ip_header + UDP_HEADER + payload_len];
+    let mut eth = EthernetFrame::new_unchecked(buf.as_mut_slice());
+    EthernetRepr {
+        src_addr: EthernetAddress(GATEWAY_MAC),
+        dst_addr: EthernetAddress(dst_mac),
+        ethertype,
+    }
+    .emit(&mut eth);
+    let udp_repr = UdpRepr {
+        src_port: src.port(),
+        dst_port: dst.port(),
+    };
+    let (src_ip, dst_ip) = (src.ip().into(), dst.ip().into());
+    match (src.ip(), dst.ip()) {
+        (IpAddr::V4(src_addr), IpAddr::V4(dst_addr)) => {
+            let mut ip = Ipv4Packet::new_unchecked(eth.payload_mut());
+            Ipv4Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: UDP_HEADER + payload_len,
+                hop_limit: 64,
+            }
+            .emit(&mut ip, &caps);
+            let mut udp = UdpPacket::new_unchecked(ip.payload_mut());
+            udp_repr.emit(&mut udp, &src_ip, &dst_ip, payload_len, emit, &caps);
+        }
+        (IpAddr::V6(src_addr), IpAddr::V6(dst_addr)) => {
+            let mut ip = Ipv6Packet::new_unchecked(eth.payload_mut());
+            Ipv6Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: UDP_HEADER + payload_len,
+                hop_limit: 64,
+            }
+            .emit(&mut ip);
+            let mut udp = UdpPacket::new_unchecked(ip.payload_mut());
+            udp_repr.emit(&mut udp, &src_ip, &dst_ip, payload_len, emit, &caps);
+        }
+        _ => return None,
+    }
+    Some(buf)
+}
+
+/// Frame delivering `payload` from `src` to the guest endpoint `dst` at
+/// `dst_mac`.
+///
+/// Returns `None` if the payload would need IP fragmentation or the
+/// endpoints are of different families.
+pub(crate) fn udp_reply(
+    dst_mac: [u8; 6],
+    src: impl Into<SocketAddr>,
+    dst: impl Into<SocketAddr>,
+    payload: &[u8],
+) -> Option<Vec<u8>> {
+    let (src, dst) = (src.into(), dst.into());
+    let max = if dst.is_ipv4() {
+        MAX_UDP_PAYLOAD
+    } else {
+        MAX_UDP6_PAYLOAD
+    };
+    if payload.len() > max {
+        return None;
+    }
+    udp_frame(dst_mac, src, dst, payload.len(), |buf| {
+        buf.copy_from_slice(payload);
+    })
+}
+
+/// Answers a DHCP DISCOVER/REQUEST with the static guest lease, addressed
+/// to the requesting NIC.
+pub(crate) fn dhcp_reply(request: &[u8], gateway: Ipv4Addr, guest: Ipv4Addr) -> Option<Vec<u8>> {
+    let packet = DhcpPacket::new_checked(request).ok()?;
+    let req = DhcpRepr::parse(&packet).ok()?;
+    let message_type = match req.message_type {
+        DhcpMessageType::Discover => DhcpMessageType::Offer,
+        DhcpMessageType::Request if req.requested_ip.is_none_or(|ip| ip == guest) => {
+            DhcpMessageType::Ack
+        }
+        DhcpMessageType::Request => DhcpMessageType::Nak,
+        _ => return None,
+    };
+    let reply = DhcpRepr {
+        message_type,
+        transaction_id: req.transaction_id,
+        secs: 0,
+        client_hardware_address: req.client_hardware_address,
+        client_ip: Ipv4Addr::UNSPECIFIED,
+        your_ip: if message_type == DhcpMessageType::Nak {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            guest
+        },
+        server_ip: gateway,
+        router: Some(gateway),
+        subnet_mask: Some(Ipv4Addr::new(255, 255, 255, 0)),
+        relay_agent_ip: Ipv4Addr::UNSPECIFIED,
+        broadcast: false,
+        requested_ip: None,
+        client_identifier: None,
+        server_identifier: Some(gateway),
+        parameter_request_list: None,
+        dns_servers: Some(std::iter::once(gateway).collect()),
+        max_size: None,
+        lease_duration: Some(LEASE_SECS),
+        renew_duration: None,
+        rebind_duration: None,
+        additional_options: &[],
+    };
+    let len = reply.buffer_len();
+    let mut failed = false;
+    let frame = udp_frame(
+        req.client_hardware_address.0,
+        SocketAddrV4::new(gateway, 67).into(),
+        SocketAddrV4::new(Ipv4Addr::BROADCAST, 68).into(),
+        len,
+        |buf| {
+            let mut out = DhcpPacket::new_unchecked(buf);
+            failed = reply.emit(&mut out).is_err();
+        },
+    )?;
+    (!failed).then_some(frame)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn udp_reply_round_trips() {
+        let src = SocketAddrV4::new(Ipv4Addr::new(192, 168, 127, 1), 53);
+        let dst = SocketAddrV4::new(Ipv4Addr::new(192, 168, 127, 2), 40000);
+        let frame = udp_reply([2, 0, 0, 0, 0, 9], src, dst, b"answer").unwrap();
+        assert_eq!(&frame[..6], &[2, 0, 0, 0, 0, 9]);
+        let parsed = parse_udp(&frame).unwrap();
+        assert_eq!(parsed.src, SocketAddr::V4(src));
+        assert_eq!(parsed.dst, SocketAddr::V4(dst));
+        assert_eq!(parsed.payload, b"answer");
+        assert!(udp_reply(GATEWAY_MAC, src, dst, &[0; MAX_UDP_PAYLOAD + 1]).is_none());
+    }
+
+    #[test]
+    fn udp6_reply_round_trips() {
+        let src: SocketAddr = "[fd42:6275:7800::1]:53".parse().unwrap();
+        let dst: SocketAddr = "[fd42:6275:7800::2]:40000".parse().unwrap();
+        let frame = udp_reply([2, 0, 0, 0, 0, 9], src, dst, b"answer").unwrap();
+        let parsed = parse_udp(&frame).unwrap();
+        assert_eq!((parsed.src, parsed.dst), (src, dst));
+        assert_eq!(parsed.payload, b"answer");
+        assert!(udp_reply(GATEWAY_MAC, src, dst, &[0; MAX_UDP6_PAYLOAD + 1]).is_none());
+
+        let v4 = SocketAddrV4::new(Ipv4Addr::new(192, 168, 127, 1), 53);
+        assert!(udp_reply(GATEWAY_MAC, v4, dst, b"x").is_none());
+    }
+}
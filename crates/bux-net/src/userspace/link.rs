@@ -0,0 +1,200 @@
+//! Guest link transport: the Unix socket the VM engine connects to.
+//!
+//! Framing matches gvproxy so the engine side is unchanged:
+//!
+//! - `UnixStream` (QEMU protocol): each frame is preceded by its length
+//!   as a 4-byte big-endian integer.
+//! - `UnixDgram` (`VFKit` protocol): one frame per datagram; the engine
+//!   announces itself with a `VFKT` datagram and replies go to its address.
+//!
+//! Every frame passes through the [`Shaper`] in its direction and is held
+//! for the delay it returns. The stream reader and writer wait
+//! independently; the datagram loop serves both directions, so a held
+//! frame stalls the other direction too.
+
+use std::io;
+use std::os::unix::net::{UnixDatagram as StdDatagram, UnixListener as StdListener};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{UnixDatagram, UnixListener};
+use tokio::sync::mpsc;
+
+use super::shaper::Shaper;
+use crate::backend::ConnectionType;
+use crate::shaping::TrafficDirection;
+
+/// Handshake datagram sent by the engine in `VFKit` mode.
+const VFKIT_MAGIC: &[u8] = b"VFKT";
+/// Largest frame accepted from the guest.
+const MAX_FRAME: usize = 65_536;
+
+/// Bound guest socket, created before the engine starts.
+#[derive(Debug)]
+pub(crate) enum GuestSocket {
+    /// Length-prefixed stream listener.
+    Stream(StdListener),
+    /// Datagram socket.
+    Dgram(StdDatagram),
+}
+
+impl GuestSocket {
+    /// Binds `path` for the given framing.
+    pub(crate) fn bind(path: &Path, kind: ConnectionType) -> io::Result<Self> {
+        let socket = match kind {
+            ConnectionType::UnixDgram => {
+                let sock = StdDatagram::bind(path)?;
+                sock.set_nonblocking(true)?;
+                Self::Dgram(sock)
+            }
+            ConnectionType::UnixStream => {
+                let listener = StdListener::bind(path)?;
+                listener.set_nonblocking(true)?;
+                Self::Stream(listener)
+            }
+        };
+        Ok(socket)
+    }
+
+    /// Moves guest frames into `frames` and writes `out` to the guest
+    /// until the stack hangs up.
+    pub(crate) async fn serve(
+        self,
+        frames: mpsc::Sender<Vec<u8>>,
+        out: mpsc::Receiver<Vec<u8>>,
+        shaper: Arc<Shaper>,
+    ) -> io::Result<()> {
+        match self {
+            Self::Stream(listener) => {
+                serve_stream(UnixListener::from_std(listener)?, frames, out, &shaper).await
+            }
+            Self::Dgram(sock) => {
+                serve_dgram(UnixDatagram::from_std(sock)?, frames, out, &shaper).await
+            }
+        }
+    }
+}
+
+/// Waits until `shaper` lets a `len`-byte frame through.
+async fn pace(shaper: &Shaper, direction: TrafficDirection, len: usize) {
+    let delay = shaper.admit(direction, len, Instant::now());
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// QEMU-protocol loop; accepts a new engine connection after each hang-up.
+#[allow(
+    clippy::cognitive_complexity,
+    reason = "tokio::select! and tracing macros expand into branches this loop does not own"
+)]
+async fn serve_stream(
+    listener: UnixListener,
+    frames: mpsc::Sender<Vec<u8>>,
+    mut out: mpsc::Receiver<Vec<u8>>,
+    shaper: &Shaper,
+) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tracing::debug!("guest link connected");
+        let (reader, writer) = stream.into_split();
+        let res = tokio::select! {
+            res = read_frames(reader, &frames, shaper) => res,
+            res = write_frames(writer, &mut out, shaper) => match res {
+                Ok(()) => return Ok(()),
+                Err(e) => Err(e),
+            },
+        };
+        if let Err(e) = res {
+            tracing::debug!(error = %e, "guest link closed");
+        }
+        if frames.is_closed() {
+            return Ok(());
+        }
+    }
+}
+
+/// Reads length-prefixed frames until EOF or the stack hangs up.
+async fn read_frames(
+    mut reader: OwnedReadHalf,
+    frames: &mpsc::Sender<Vec<u8>>,
+    shaper: &Shaper,
+) -> io::Result<()> {
+    loop {
+        let len = usize::try_from(reader.read_u32().await?).unwrap_or(usize::MAX);
+        if len > MAX_FRAME {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("guest frame of {len} bytes"),
+            ));
+        }
+        let mut frame = vec![0; len];
+        reader.read_exact(&mut frame).await?;
+        pace(shaper, TrafficDirection::Egress, frame.len()).await;
+        if frames.send(frame).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Writes length-prefixed frames; `Ok` means the stack hung up.
+async fn write_frames(
+    mut writer: OwnedWriteHalf,
+    out: &mut mpsc::Receiver<Vec<u8>>,
+    shaper: &Shaper,
+) -> io::Result<()> {
+    while let Some(frame) = out.recv().await {
+        pace(shaper, TrafficDirection::Ingress, frame.len()).await;
+        let len = u32::try_from(frame.len()).unwrap_or(u32::MAX);
+        writer.write_u32(len).await?;
+        writer.write_all(&frame).await?;
+    }
+    Ok(())
+}
+
+/// `VFKit`-protocol loop.
+#[allow(
+    clippy::cognitive_complexity,
+    reason = "tokio::select! and tracing macros expand into branches this loop does not own"
+)]
+async fn serve_dgram(
+    sock: UnixDatagram,
+    frames: mpsc::Sender<Vec<u8>>,
+    mut out: mpsc::Receiver<Vec<u8>>,
+    shaper: &Shaper,
+) -> io::Result<()> {
+    let mut buf = vec![0; MAX_FRAME];
+    let mut connected = false;
+    loop {
+        tokio::select! {
+            res = sock.recv_from(&mut buf) => {
+                let (n, peer) = res?;
+                let Some(frame) = buf.get(..n) else { continue };
+                if frame == VFKIT_MAGIC {
+                    if let Some(path) = peer.as_pathname() {
+                        sock.connect(path)?;
+                        connected = true;
+                        tracing::debug!(peer = %path.display(), "guest link connected");
+                    }
+                } else {
+                    pace(shaper, TrafficDirection::Egress, frame.len()).await;
+                    if frames.send(frame.to_vec()).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            frame = out.recv() => {
+                let Some(frame) = frame else { return Ok(()) };
+                pace(shaper, TrafficDirection::Ingress, frame.len()).await;
+                if connected
+                    && let Err(e) = sock.send(&frame).await
+                {
+                    tracing::debug!(error = %e, "dropping frame to guest");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,586 @@
+//! `NetworkBackend` implementation in pure Rust.
+//!
+//! Serves the same guest-facing contract as gvproxy — socket framing,
+//! subnet, gateway/guest addresses, DHCP lease, gateway DNS, published
+//! ports and egress policy — without the Go library, on a dedicated thread
+//! running a single-threaded tokio runtime around a [`smoltcp`] interface.
+//!
+//! TLS interception is not implemented: configs carrying MITM secrets or
+//! an HTTP policy are rejected so they cannot silently run without
+//! substitution or enforcement.
+//!
+//! [`NetworkBackend::update`] swaps the allow-list and opens or closes
+//! published ports through a control channel into the stack task.
+//! Published UDP ports relay each host peer through its own gateway port,
+//! so guest replies find their way back.
+//!
+//! With [`NetworkConfig::record_activity`] the stack records DNS queries
+//! and answers, outbound connects and closes, published-port accepts and
+//! policy denials for [`NetworkBackend::drain_events`].
+//!
+//! [`NetworkConfig::traffic`] limits are enforced on the guest link by a
+//! [`shaper::Shaper`]; a used-up blocking quota refuses new TCP
+//! connections and UDP flows.
+//!
+//! [`NetworkConfig::dns`] records are answered by the gateway itself,
+//! ahead of the egress policy; its upstream replaces the host's
+//! resolver. With the host gateway enabled, flows to
+//! [`HOST_IPV4`](crate::dns::HOST_IPV4) are relayed to the host's
+//! loopback.
+//!
+//! With [`NetworkConfig::ipv6`] the gateway also owns
+//! [`GATEWAY_IPV6`](bux_net_types::constants::GATEWAY_IPV6) and the guest is
+//! reached at [`GuestAddress::ipv6`](crate::GuestAddress::ipv6): IPv6 TCP,
+//! UDP and DNS are handled like IPv4, under the same egress policy, and
+//! relayed over the host's IPv6. Published ports still reach the guest
+//! over IPv4.
+
+mod allow;
+pub(crate) mod dns;
+pub(crate) mod frame;
+mod link;
+mod shaper;
+mod stack;
+
+use std::net::{IpAddr, Ipv4Addr, TcpListener, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::JoinHandle;
+
+use bux_net_types::PortProtocol;
+use bux_net_types::constants::{GATEWAY_IP, GATEWAY_IPV6, SUBNET};
+use tokio::sync::{mpsc, oneshot};
+
+use self::allow::AllowList;
+use self::link::GuestSocket;
+use self::shaper::Shaper;
+use self::stack::{Bound, Counters, Ipv6Link, Reconfigure, StackConfig};
+use crate::activity::{EventBuffer, NetEvent};
+use crate::backend::{
+    ConnectionType, NetworkBackend, NetworkConfig, NetworkEndpoint, NetworkMetrics, NetworkUpdate,
+    PortForward,
+};
+use crate::dns::HOST_IPV4;
+use crate::error::{NetError, Result};
+use crate::http::HttpPolicy;
+
+/// Pure-Rust userspace network backend.
+///
+/// The guest socket and published ports are bound synchronously in
+/// [`Self::new`], so address conflicts surface before the VM boots.
+/// Dropping the backend stops the stack thread.
+#[derive(Debug)]
+pub struct UserspaceBackend {
+    /// Unix socket path exposed to the VM engine.
+    socket_path: PathBuf,
+    /// Live counters shared with the stack thread.
+    counters: Arc<Counters>,
+    /// Activity recorded by the stack thread, when enabled.
+    activity: Option<Arc<EventBuffer>>,
+    /// Rate limits and quotas shared with the link and stack.
+    shaper: Arc<Shaper>,
+    /// Gateway, guest and (when enabled) host gateway addresses, always
+    /// reachable through the allow-list.
+    internal: Vec<IpAddr>,
+    /// Guest NIC MAC (the DHCP lease).
+    guest_mac: [u8; 6],
+    /// Current published ports.
+    ports: Mutex<Vec<PortForward>>,
+    /// Live reconfiguration channel into the stack thread.
+    control: mpsc::UnboundedSender<Reconfigure>,
+    /// Signals the stack thread to exit.
+    shutdown: Option<oneshot::Sender<()>>,
+    /// Stack thread.
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Connection type the VM engine expects on this platform.
+const fn connection_type() -> ConnectionType {
+    if cfg!(target_os = "macos") {
+        ConnectionType::UnixDgram
+    } else {
+        ConnectionType::UnixStream
+    }
+}
+
+/// Binds a published port for the stack's accept or receive loop.
+fn bind_forward(forward: &PortForward) -> Result<Bound> {
+    Ok(match forward.protocol {
+        PortProtocol::Tcp => {
+            let listener = TcpListener::bind(forward.host_addr())?;
+            listener.set_nonblocking(true)?;
+            Bound::Tcp(listener)
+        }
+        PortProtocol::Udp => {
+            let socket = UdpSocket::bind(forward.host_addr())?;
+            socket.set_nonblocking(true)?;
+            Bound::Udp(socket)
+        }
+    })
+}
+
+/// Rejects MITM secrets, which this backend cannot substitute.
+fn reject_secrets(secrets: &[bux_net_types::SecretConfig]) -> Result<()> {
+    if secrets.is_empty() {
+        Ok(())
+    } else {
+        Err(NetError::Config(
+            "MITM secrets require the gvproxy backend".to_owned(),
+        ))
+    }
+}
+
+/// Rejects HTTP rules, which need the MITM proxy.
+fn reject_http(http: &HttpPolicy) -> Result<()> {
+    if http.is_empty() {
+        Ok(())
+    } else {
+        Err(NetError::Config(
+            "HTTP rules require the gvproxy backend".to_owned(),
+        ))
+    }
+}
+
+/// Parses an address constant from [`bux_net_types::constants`].
+fn constant_ip(value: &str) -> Result<Ipv4Addr> {
+    value
+        .parse()
+        .map_err(|_| NetError::Config(format!("bad address constant: {value}")))
+}
+
+impl UserspaceBackend {
+    /// Create a new userspace backend from a [`NetworkConfig`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`NetError::Config`] if MITM secrets or HTTP rules are
+    /// configured, a traffic limit is zero or a DNS record is invalid, and
+    /// [`NetError::Io`] if the guest socket or a published port cannot be
+    /// bound or the stack thread cannot be spawned.
+    pub fn new(config: NetworkConfig) -> Result<Self> {
+        reject_secrets(&config.secrets)?;
+        reject_http(&config.http)?;
+        config.traffic.validate()?;
+        config.dns.validate()?;
+        tracing::debug!(
+            socket_path = ?config.socket_path,
+            port_mappings = ?config.port_mappings,
+            egress = ?config.egress.rule_strings(),
+            "creating userspace backend",
+        );
+
+        let gateway = constant_ip(GATEWAY_IP)?;
+        let guest = config.guest.ip;
+        let prefix_len = SUBNET
+            .split_once('/')
+            .and_then(|(_, len)| len.parse().ok())
+            .ok_or_else(|| NetError::Config(format!("bad subnet constant: {SUBNET}")))?;
+
+        let mut forwards = Vec::with_capacity(config.port_mappings.len());
+        for forward in &config.port_mappings {
+            forwards.push((*forward, bind_forward(forward)?));
+        }
+
+        if config.socket_path.exists() {
+            std::fs::remove_file(&config.socket_path)?;
+        }
+        let guest_socket = GuestSocket::bind(&config.socket_path, connection_type())?;
+        let activity = config
+            .record_activity
+            .then(|| Arc::new(EventBuffer::default()));
+        let shaper = Arc::new(Shaper::new(config.traffic, activity.clone()));
+        let ipv6 = config.ipv6.then(|| Ipv6Link {
+            gateway: GATEWAY_IPV6,
+            guest: config.guest.ipv6(),
+        });
+        let mut internal = vec![IpAddr::V4(gateway), IpAddr::V4(guest)];
+        if config.dns.host_gateway {
+            internal.push(IpAddr::V4(HOST_IPV4));
+        }
+        if let Some(link) = ipv6 {
+            internal.extend([IpAddr::V6(link.gateway), IpAddr::V6(link.guest)]);
+        }
+
+        let stack_config = StackConfig {
+            gateway,
+            guest,
+            ipv6,
+            guest_mac: config.guest.mac,
+            prefix_len,
+            allow: AllowList::new(config.egress, &internal),
+            upstream_dns: config.dns.upstream.or_else(dns::system_resolver),
+            local_dns: config.dns,
+            forwards,
+            stats_logging: config.stats_logging,
+            activity: activity.clone(),
+            shaper: Arc::clone(&shaper),
+        };
+        let counters = Arc::new(Counters::default());
+        let (control, control_rx) = mpsc::unbounded_channel();
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let thread_counters = Arc::clone(&counters);
+        let thread = std::thread::Builder::new()
+            .name("bux-net-userspace".to_owned())
+            .spawn(move || {
+                runtime.block_on(stack::run(
+                    stack_config,
+                    guest_socket,
+                    thread_counters,
+                    control_rx,
+                    shutdown_rx,
+                ));
+            })?;
+
+        tracing::info!(socket_path = ?config.socket_path, "created userspace backend");
+        Ok(Self {
+            socket_path: config.socket_path,
+            counters,
+            activity,
+            shaper,
+            internal,
+            guest_mac: config.guest.mac,
+            ports: Mutex::new(config.port_mappings),
+            control,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl NetworkBackend for UserspaceBackend {
+    fn endpoint(&self) -> Result<NetworkEndpoint> {
+        Ok(NetworkEndpoint::UnixSocket {
+            path: self.socket_path.clone(),
+            connection_type: connection_type(),
+            mac_address: self.guest_mac,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        "userspace"
+    }
+
+    fn metrics(&self) -> Result<Option<NetworkMetrics>> {
+        Ok(Some(NetworkMetrics {
+            bytes_sent: self.counters.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.counters.bytes_received.load(Ordering::Relaxed),
+            tcp_connections: Some(self.counters.tcp_established.load(Ordering::Relaxed)),
+            tcp_connection_errors: Some(self.counters.tcp_errors.load(Ordering::Relaxed)),
+            quota_exceeded: self.shaper.quota_exceeded(),
+        }))
+    }
+
+    fn update(&self, update: &NetworkUpdate) -> Result<()> {
+        reject_secrets(update.secrets.as_deref().unwrap_or_default())?;
+        let mut ports = self.ports.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let mut add = Vec::new();
+        for forward in &update.port_mappings {
+            match ports.iter().find(|current| current.same_listener(forward)) {
+                Some(current) if current.guest_port == forward.guest_port => {}
+                Some(_) => {
+                    return Err(NetError::Config(format!(
+                        "{} {} is already published; unpublish it first",
+                        forward.protocol,
+                        forward.host_addr()
+                    )));
+                }
+                None => add.push((*forward, bind_forward(forward)?)),
+            }
+        }
+        let remove = ports
+            .iter()
+            .filter(|current| {
+                !update
+                    .port_mappings
+                    .iter()
+                    .any(|new| new.same_listener(current))
+            })
+            .copied()
+            .collect();
+
+        self.control
+            .send(Reconfigure {
+                allow: AllowList::new(update.egress.clone(), &self.internal),
+                add,
+                remove,
+            })
+            .map_err(|_| NetError::Config("userspace network thread has exited".to_owned()))?;
+        ports.clone_from(&update.port_mappings);
+        drop(ports);
+        tracing::info!(
+            socket_path = ?self.socket_path,
+            ports = ?update.port_mappings,
+            egress = ?update.egress.rule_strings(),
+            "updated userspace backend"
+        );
+        Ok(())
+    }
+
+    fn drain_events(&self) -> Result<Vec<NetEvent>> {
+        Ok(self
+            .activity
+            .as_ref()
+            .map(|activity| activity.drain())
+            .unwrap_or_default())
+    }
+}
+
+impl Drop for UserspaceBackend {
+    fn drop(&mut self) {
+        tracing::debug!(socket_path = ?self.socket_path, "dropping userspace backend");
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            tracing::warn!("userspace network thread panicked");
+        }
+        std::fs::remove_file(&self.socket_path).ok();
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::SocketAddr;
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    use bux_net_types::constants::GATEWAY_MAC;
+
+    use super::*;
+    use crate::activity::NetEventKind;
+    use crate::backend::GuestAddress;
+    use crate::dns::DnsConfig;
+    use crate::egress::{EgressPolicy, EgressProtocol};
+    use crate::http::HttpRule;
+
+    #[test]
+    fn rejects_http_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = NetworkConfig::new(Vec::new(), dir.path().join("net.sock"))
+            .with_http_policy(HttpPolicy::new().rule(HttpRule::read_only(["api.github.com"])));
+        let err = UserspaceBackend::new(config).unwrap_err();
+        assert!(err.to_string().contains("HTTP rules"), "{err}");
+    }
+
+    #[test]
+    fn refuses_dns_outside_allow_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("net.sock");
+        let config = NetworkConfig::new(Vec::new(), path.clone())
+            .with_egress(EgressPolicy::parse(["example.com", "!*.test"]).unwrap())
+            .with_activity(true);
+        let backend = UserspaceBackend::new(config).unwrap();
+        assert_eq!(backend.name(), "userspace");
+
+        let mut link = UnixStream::connect(&path).unwrap();
+        link.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        query.extend_from_slice(b"\x07blocked\x04test\x00\x00\x01\x00\x01");
+        let guest = SocketAddr::from((GuestAddress::DEFAULT.ip, 40_000));
+        let gateway = SocketAddr::from((constant_ip(GATEWAY_IP).unwrap(), 53));
+        let frame = frame::udp_reply(GATEWAY_MAC, guest, gateway, &query).unwrap();
+        link.write_all(&u32::try_from(frame.len()).unwrap().to_be_bytes())
+            .unwrap();
+        link.write_all(&frame).unwrap();
+
+        let mut len = [0; 4];
+        link.read_exact(&mut len).unwrap();
+        let mut reply = vec![0; u32::from_be_bytes(len) as usize];
+        link.read_exact(&mut reply).unwrap();
+        let dgram = frame::parse_udp(&reply).unwrap();
+        assert_eq!(dgram.src, gateway);
+        assert_eq!(dgram.dst, guest);
+        assert_eq!(&dgram.payload[..2], &[0x12, 0x34]);
+        assert_eq!(dgram.payload[3] & 0x0f, dns::RCODE_NXDOMAIN);
+        assert!(backend.metrics().unwrap().unwrap().bytes_sent > 0);
+
+        let kinds: Vec<_> = backend
+            .drain_events()
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                NetEventKind::DnsQuery {
+                    name: "blocked.test".into()
+                },
+                NetEventKind::Denied {
+                    protocol: EgressProtocol::Udp,
+                    remote: None,
+                    host: Some("blocked.test".into()),
+                    rule: Some("!*.test".into()),
+                },
+            ]
+        );
+        assert!(backend.drain_events().unwrap().is_empty());
+    }
+
+    #[test]
+    fn answers_dns_over_ipv6() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("net.sock");
+        let config = NetworkConfig::new(Vec::new(), path.clone())
+            .with_egress(EgressPolicy::parse(["example.com"]).unwrap())
+            .with_ipv6(true);
+        let _backend = UserspaceBackend::new(config).unwrap();
+
+        let mut link = UnixStream::connect(&path).unwrap();
+        link.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut query = vec![0x56, 0x78, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        query.extend_from_slice(b"\x04evil\x03com\x00\x00\x1c\x00\x01");
+        let guest = SocketAddr::from((GuestAddress::DEFAULT.ipv6(), 40_000));
+        let gateway = SocketAddr::from((GATEWAY_IPV6, 53));
+        let frame = frame::udp_reply(GATEWAY_MAC, guest, gateway, &query).unwrap();
+        link.write_all(&u32::try_from(frame.len()).unwrap().to_be_bytes())
+            .unwrap();
+        link.write_all(&frame).unwrap();
+
+        let mut len = [0; 4];
+        link.read_exact(&mut len).unwrap();
+        let mut reply = vec![0; u32::from_be_bytes(len) as usize];
+        link.read_exact(&mut reply).unwrap();
+        let dgram = frame::parse_udp(&reply).unwrap();
+        assert_eq!((dgram.src, dgram.dst), (gateway, guest));
+        assert_eq!(dgram.payload[3] & 0x0f, dns::RCODE_NXDOMAIN);
+    }
+
+    #[test]
+    fn answers_local_names_ahead_of_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("net.sock");
+        let config = NetworkConfig::new(Vec::new(), path.clone())
+            .with_egress(EgressPolicy::parse(["example.com"]).unwrap())
+            .with_dns(
+                DnsConfig::new()
+                    .with_record("db", Ipv4Addr::new(10, 0, 0, 5))
+                    .with_host_gateway(true),
+            );
+        let _backend = UserspaceBackend::new(config).unwrap();
+
+        let mut link = UnixStream::connect(&path).unwrap();
+        link.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let guest = SocketAddr::from((GuestAddress::DEFAULT.ip, 40_000));
+        let gateway = SocketAddr::from((constant_ip(GATEWAY_IP).unwrap(), 53));
+        for (name, want) in [
+            (&b"\x02db\x05local\x00"[..], Ipv4Addr::new(10, 0, 0, 5)),
+            (&b"\x04host\x03bux\x08internal\x00"[..], HOST_IPV4),
+        ] {
+            let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+            query.extend_from_slice(name);
+            query.extend_from_slice(&[0, 1, 0, 1]);
+            let frame = frame::udp_reply(GATEWAY_MAC, guest, gateway, &query).unwrap();
+            link.write_all(&u32::try_from(frame.len()).unwrap().to_be_bytes())
+                .unwrap();
+            link.write_all(&frame).unwrap();
+
+            let mut len = [0; 4];
+            link.read_exact(&mut len).unwrap();
+            let mut reply = vec![0; u32::from_be_bytes(len) as usize];
+            link.read_exact(&mut reply).unwrap();
+            let dgram = frame::parse_udp(&reply).unwrap();
+            assert_eq!(dgram.payload[3] & 0x0f, 0);
+            assert_eq!(dns::address_records(dgram.payload), vec![IpAddr::V4(want)]);
+        }
+    }
+
+    #[test]
+    fn publishes_ports_live() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = NetworkConfig::new(Vec::new(), dir.path().join("net.sock"));
+        let backend = UserspaceBackend::new(config).unwrap();
+        let host_port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        backend
+            .update(&NetworkUpdate::new(
+                vec![PortForward::tcp(host_port, 80)],
+                EgressPolicy::default(),
+            ))
+            .unwrap();
+        std::net::TcpStream::connect(("127.0.0.1", host_port)).unwrap();
+
+        let remap = NetworkUpdate::new(
+            vec![PortForward::tcp(host_port, 8080)],
+            EgressPolicy::default(),
+        );
+        assert!(matches!(backend.update(&remap), Err(NetError::Config(_))));
+    }
+
+    #[test]
+    fn relays_published_udp_ports() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("net.sock");
+        let host_port = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let forward = PortForward::tcp(host_port, 5000)
+            .with_bind(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .with_protocol(PortProtocol::Udp);
+        let _backend =
+            UserspaceBackend::new(NetworkConfig::new(vec![forward], path.clone())).unwrap();
+
+        let mut link = UnixStream::connect(&path).unwrap();
+        link.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        peer.send_to(b"ping", ("127.0.0.1", host_port)).unwrap();
+
+        let mut len = [0; 4];
+        link.read_exact(&mut len).unwrap();
+        let mut inbound = vec![0; u32::from_be_bytes(len) as usize];
+        link.read_exact(&mut inbound).unwrap();
+        let dgram = frame::parse_udp(&inbound).unwrap();
+        assert_eq!(
+            dgram.dst,
+            SocketAddr::from((GuestAddress::DEFAULT.ip, 5000))
+        );
+        assert_eq!(dgram.src.ip(), IpAddr::V4(constant_ip(GATEWAY_IP).unwrap()));
+        assert_eq!(dgram.payload, b"ping");
+
+        let reply = frame::udp_reply(GATEWAY_MAC, dgram.dst, dgram.src, b"pong").unwrap();
+        link.write_all(&u32::try_from(reply.len()).unwrap().to_be_bytes())
+            .unwrap();
+        link.write_all(&reply).unwrap();
+        let mut buf = [0; 16];
+        let (n, from) = peer.recv_from(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"pong");
+        assert_eq!(from.port(), host_port);
+    }
+
+    #[test]
+    fn rejects_mitm_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = bux_net_types::SecretConfig {
+            name: "k".into(),
+            hosts: vec!["api.example.com".into()],
+            placeholder: "P".into(),
+            value: "V".into(),
+        };
+        let config = NetworkConfig::new(Vec::new(), dir.path().join("net.sock")).with_secrets(
+            vec![secret],
+            String::new(),
+            String::new(),
+        );
+        assert!(matches!(
+            UserspaceBackend::new(config),
+            Err(NetError::Config(_))
+        ));
+    }
+}
@@ -0,0 +1,275 @@
+//! Rate limiting and quota accounting on the guest link.
+//!
+//! The link tasks ask [`Shaper::admit`] before moving each frame and wait
+//! for the returned delay, which holds back the guest (egress) or the
+//! stack (ingress) until the token bucket refills. The stack checks
+//! [`Shaper::blocks_new_flows`] before opening TCP connections and UDP
+//! NAT entries.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::activity::{EventBuffer, NetEventKind};
+use crate::shaping::{QuotaAction, TrafficDirection, TrafficLimits};
+
+/// Nanoseconds per second.
+const NANOS: u128 = 1_000_000_000;
+/// Smallest bucket depth, so one maximum-size frame always fits.
+const MIN_BURST: u64 = 64 * 1024;
+
+/// Integer token bucket that may run into deficit by one frame.
+#[derive(Debug)]
+struct TokenBucket {
+    /// Bytes per second.
+    rate: u64,
+    /// Bucket depth in bytes.
+    burst: u64,
+    /// Available bytes; negative while a reserved frame is paid off.
+    tokens: i128,
+    /// Time up to which refills are accounted.
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Full bucket refilling at `rate` bytes per second.
+    fn new(rate: u64, now: Instant) -> Self {
+        let burst = rate.max(MIN_BURST);
+        Self {
+            rate,
+            burst,
+            tokens: i128::from(burst),
+            last: now,
+        }
+    }
+
+    /// Takes `len` bytes and returns how long the caller must wait for
+    /// the bucket to be out of deficit.
+    fn take(&mut self, len: u64, now: Instant) -> Duration {
+        let rate = u128::from(self.rate);
+        let elapsed = now.saturating_duration_since(self.last).as_nanos();
+        let earned = elapsed.saturating_mul(rate) / NANOS;
+        let burst = i128::from(self.burst);
+        let refilled = self
+            .tokens
+            .saturating_add(i128::try_from(earned).unwrap_or(i128::MAX));
+        if refilled >= burst {
+            self.tokens = burst;
+            self.last = now;
+        } else {
+            // Only advance by whole bytes so fractions carry over.
+            self.tokens = refilled;
+            let spent = earned.saturating_mul(NANOS) / rate;
+            self.last += Duration::from_nanos(u64::try_from(spent).unwrap_or(u64::MAX));
+        }
+        self.tokens -= i128::from(len);
+        if self.tokens >= 0 {
+            return Duration::ZERO;
+        }
+        let deficit = self.tokens.unsigned_abs();
+        let wait = deficit.saturating_mul(NANOS).div_ceil(rate);
+        Duration::from_nanos(u64::try_from(wait).unwrap_or(u64::MAX))
+    }
+}
+
+/// Metering for one direction.
+#[derive(Debug)]
+struct Meter {
+    /// Rate limit, when set (or after a throttling quota).
+    bucket: Option<TokenBucket>,
+    /// Bytes moved so far.
+    used: u64,
+    /// Whether the quota has been reported.
+    exceeded: bool,
+}
+
+/// Shared rate limiter and quota tracker for one backend.
+#[derive(Debug)]
+pub(crate) struct Shaper {
+    /// Configured limits.
+    limits: TrafficLimits,
+    /// Egress and ingress meters, in that order.
+    meters: Mutex<[Meter; 2]>,
+    /// Set once a blocking or stopping quota is used up.
+    blocked: AtomicBool,
+    /// Set once any quota is used up.
+    exceeded: AtomicBool,
+    /// Activity sink, when recording is enabled.
+    activity: Option<Arc<EventBuffer>>,
+}
+
+impl Shaper {
+    /// Meters for `limits`, reporting quota events to `activity`.
+    pub(crate) fn new(limits: TrafficLimits, activity: Option<Arc<EventBuffer>>) -> Self {
+        let now = Instant::now();
+        let meter = |direction| Meter {
+            bucket: limits
+                .rate(direction)
+                .map(|rate| TokenBucket::new(rate, now)),
+            used: 0,
+            exceeded: false,
+        };
+        Self {
+            meters: Mutex::new([
+                meter(TrafficDirection::Egress),
+                meter(TrafficDirection::Ingress),
+            ]),
+            limits,
+            blocked: AtomicBool::new(false),
+            exceeded: AtomicBool::new(false),
+            activity,
+        }
+    }
+
+    /// Accounts a `len`-byte frame and returns how long to hold it.
+    pub(crate) fn admit(&self, direction: TrafficDirection, len: usize, now: Instant) -> Duration {
+        if self.limits.is_unlimited() {
+            return Duration::ZERO;
+        }
+        let len = u64::try_from(len).unwrap_or(u64::MAX);
+        let mut meters = self.meters.lock().unwrap_or_else(PoisonError::into_inner);
+        let [egress, ingress] = &mut *meters;
+        let meter = match direction {
+            TrafficDirection::Egress => egress,
+            TrafficDirection::Ingress => ingress,
+        };
+        meter.used = meter.used.saturating_add(len);
+        if let Some(quota) = self.limits.quota(direction)
+            && !meter.exceeded
+            && meter.used > quota
+        {
+            meter.exceeded = true;
+            self.on_exceeded(direction, quota, meter, now);
+        }
+        let wait = meter
+            .bucket
+            .as_mut()
+            .map_or(Duration::ZERO, |bucket| bucket.take(len, now));
+        drop(meters);
+        wait
+    }
+
+    /// Applies [`TrafficLimits::on_quota`] after `direction` used `quota`.
+    fn on_exceeded(
+        &self,
+        direction: TrafficDirection,
+        quota: u64,
+        meter: &mut Meter,
+        now: Instant,
+    ) {
+        let action = self.limits.on_quota;
+        tracing::warn!(%direction, quota, %action, "network quota exceeded");
+        self.exceeded.store(true, Ordering::Relaxed);
+        match action {
+            QuotaAction::Throttle { rate } => {
+                let rate = meter.bucket.as_ref().map_or(rate, |b| b.rate.min(rate));
+                meter.bucket = Some(TokenBucket::new(rate, now));
+            }
+            _ => self.blocked.store(true, Ordering::Relaxed),
+        }
+        if let Some(activity) = &self.activity {
+            activity.push(NetEventKind::QuotaExceeded {
+                direction,
+                limit: quota,
+                action,
+            });
+        }
+    }
+
+    /// Whether new connections must be refused.
+    pub(crate) fn blocks_new_flows(&self) -> bool {
+        self.blocked.load(Ordering::Relaxed)
+    }
+
+    /// Whether any quota has been used up.
+    pub(crate) fn quota_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_delays_past_burst_and_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, start);
+        assert_eq!(bucket.take(MIN_BURST, start), Duration::ZERO);
+        assert_eq!(bucket.take(500, start), Duration::from_millis(500));
+        // Half a second later the deficit is paid; a further 1000 bytes
+        // need one more second.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(1000, later), Duration::from_secs(1));
+        // Fractional refills carry over instead of being lost.
+        let mut slow = TokenBucket::new(3, start);
+        slow.take(MIN_BURST, start);
+        for step in 1..=3 {
+            slow.take(0, start + Duration::from_millis(100 * step));
+        }
+        assert_eq!(
+            slow.take(1, start + Duration::from_millis(334)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn quota_blocks_and_records_once() {
+        let activity = Arc::new(EventBuffer::default());
+        let limits = TrafficLimits::new().with_egress_quota(100);
+        let shaper = Shaper::new(limits, Some(Arc::clone(&activity)));
+        let now = Instant::now();
+
+        assert_eq!(
+            shaper.admit(TrafficDirection::Egress, 100, now),
+            Duration::ZERO
+        );
+        assert!(!shaper.blocks_new_flows());
+        shaper.admit(TrafficDirection::Egress, 1, now);
+        shaper.admit(TrafficDirection::Egress, 1, now);
+        shaper.admit(TrafficDirection::Ingress, 1000, now);
+        assert!(shaper.blocks_new_flows());
+        assert!(shaper.quota_exceeded());
+
+        let events = activity.drain();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events.first().unwrap().kind,
+            NetEventKind::QuotaExceeded {
+                direction: TrafficDirection::Egress,
+                limit: 100,
+                action: QuotaAction::Block,
+            }
+        );
+    }
+
+    #[test]
+    fn throttling_quota_slows_instead_of_blocking() {
+        let limits = TrafficLimits::new()
+            .with_ingress_quota(10)
+            .with_quota_action(QuotaAction::Throttle { rate: 1000 });
+        let shaper = Shaper::new(limits, None);
+        let now = Instant::now();
+
+        assert_eq!(
+            shaper.admit(TrafficDirection::Ingress, 11, now),
+            Duration::ZERO
+        );
+        assert!(!shaper.blocks_new_flows());
+        assert!(shaper.quota_exceeded());
+        let burst = usize::try_from(MIN_BURST).unwrap();
+        assert_eq!(
+            shaper.admit(TrafficDirection::Ingress, burst - 11, now),
+            Duration::ZERO
+        );
+        assert_eq!(
+            shaper.admit(TrafficDirection::Ingress, 2000, now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            shaper.admit(TrafficDirection::Egress, burst * 2, now),
+            Duration::ZERO
+        );
+    }
+}
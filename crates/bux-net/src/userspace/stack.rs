@@ -0,0 +1,1279 @@
+//! The userspace stack task: smoltcp terminates guest TCP, everything else
+//! (DHCP, DNS, UDP NAT) is answered or relayed from parsed frames.
+//!
+//! Outbound TCP is connect-then-accept: a guest SYN is held while the host
+//! connection is dialed, and only handed to smoltcp (with a listener bound
+//! to the original destination) once the dial succeeds. A refused or
+//! blocked destination therefore gets a RST, as with gvproxy.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bux_net_types::constants::GATEWAY_MAC;
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+use smoltcp::socket::tcp;
+use smoltcp::time::Instant;
+use smoltcp::wire::{
+    ArpPacket, EthernetAddress, EthernetFrame, EthernetProtocol, IpAddress, IpCidr,
+    IpListenEndpoint, IpProtocol, Ipv4Packet, Ipv6Packet, TcpPacket,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc::error::{TryRecvError, TrySendError};
+use tokio::sync::{Notify, mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use super::allow::AllowList;
+use super::dns;
+use super::frame::{self, FrameQueue};
+use super::link::GuestSocket;
+use super::shaper::Shaper;
+use crate::activity::{EventBuffer, NetEventKind};
+use crate::backend::PortForward;
+use crate::dns::{DnsConfig, HOST_IPV4};
+use crate::egress::EgressProtocol;
+
+/// Upper bound on how long the loop sleeps without a wake-up.
+const MAX_IDLE: Duration = Duration::from_secs(1);
+/// Host-side dial timeout for guest connections.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upstream DNS reply timeout.
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+/// UDP NAT entries idle longer than this are dropped.
+const UDP_IDLE: Duration = Duration::from_secs(60);
+/// TCP listeners whose SYN was never processed are dropped after this.
+const LISTEN_TIMEOUT: Duration = Duration::from_secs(30);
+/// smoltcp socket buffer size per direction.
+const TCP_BUFFER: usize = 256 * 1024;
+/// Chunks queued per direction between a host socket and the stack.
+const FLOW_QUEUE: usize = 16;
+/// Host read size.
+const READ_CHUNK: usize = 16 * 1024;
+/// Interval between stats log lines when enabled.
+const STATS_INTERVAL: Duration = Duration::from_secs(60);
+/// First local port used for host → guest connections.
+const EPHEMERAL_START: u16 = 49_152;
+/// IPv6 subnet prefix length.
+const IPV6_PREFIX_LEN: u8 = 64;
+
+/// Live counters shared with [`super::UserspaceBackend::metrics`].
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    /// Bytes delivered to the guest.
+    pub(crate) bytes_sent: AtomicU64,
+    /// Bytes received from the guest.
+    pub(crate) bytes_received: AtomicU64,
+    /// TCP connections currently established.
+    pub(crate) tcp_established: AtomicU64,
+    /// Failed outbound dials.
+    pub(crate) tcp_errors: AtomicU64,
+}
+
+/// IPv6 addressing on a dual-stack network.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Ipv6Link {
+    /// Gateway address (DNS, default route).
+    pub(crate) gateway: Ipv6Addr,
+    /// Guest address.
+    pub(crate) guest: Ipv6Addr,
+}
+
+/// Everything the stack task needs, prepared by the backend constructor.
+#[derive(Debug)]
+pub(crate) struct StackConfig {
+    /// Gateway address (DNS, DHCP server, default route).
+    pub(crate) gateway: Ipv4Addr,
+    /// Guest address (DHCP static lease).
+    pub(crate) guest: Ipv4Addr,
+    /// IPv6 addresses, when the network is dual-stack.
+    pub(crate) ipv6: Option<Ipv6Link>,
+    /// Guest NIC MAC, the destination of replies the stack builds itself.
+    pub(crate) guest_mac: [u8; 6],
+    /// Subnet prefix length.
+    pub(crate) prefix_len: u8,
+    /// Egress policy.
+    pub(crate) allow: AllowList,
+    /// Host resolver that gateway DNS queries are relayed to.
+    pub(crate) upstream_dns: Option<SocketAddr>,
+    /// Local records and the host gateway.
+    pub(crate) local_dns: DnsConfig,
+    /// Published ports and their bound host sockets.
+    pub(crate) forwards: Vec<(PortForward, Bound)>,
+    /// Periodically log counters.
+    pub(crate) stats_logging: bool,
+    /// Activity sink, when recording is enabled.
+    pub(crate) activity: Option<Arc<EventBuffer>>,
+    /// Rate limits and quotas, shared with the guest link.
+    pub(crate) shaper: Arc<Shaper>,
+}
+
+/// Live change sent by [`super::UserspaceBackend::update`].
+#[derive(Debug)]
+pub(crate) struct Reconfigure {
+    /// Replacement egress policy (learned addresses start empty).
+    pub(crate) allow: AllowList,
+    /// Newly published ports and their bound host sockets.
+    pub(crate) add: Vec<(PortForward, Bound)>,
+    /// Published ports to close.
+    pub(crate) remove: Vec<PortForward>,
+}
+
+/// Host socket bound for a published port.
+#[derive(Debug)]
+pub(crate) enum Bound {
+    /// TCP listener.
+    Tcp(std::net::TcpListener),
+    /// UDP socket.
+    Udp(std::net::UdpSocket),
+}
+
+/// Guest source port + remote endpoint.
+type FlowKey = (u16, SocketAddr);
+
+/// Work produced by host-side tasks.
+#[derive(Debug)]
+enum Event {
+    /// Host dial for a held SYN succeeded.
+    Connected {
+        /// Flow the SYN belongs to.
+        key: FlowKey,
+        /// Connected host socket.
+        stream: TcpStream,
+    },
+    /// Host dial for a held SYN failed.
+    ConnectFailed {
+        /// Flow the SYN belongs to.
+        key: FlowKey,
+    },
+    /// A published port accepted a host connection.
+    Inbound {
+        /// Accepted host socket.
+        stream: TcpStream,
+        /// Host-side client.
+        peer: SocketAddr,
+        /// Published host port.
+        host_port: u16,
+        /// Guest port it is published to.
+        guest_port: u16,
+    },
+    /// A published UDP port received a datagram.
+    InboundDatagram {
+        /// Published socket, which also carries the replies.
+        socket: Arc<UdpSocket>,
+        /// Host-side sender.
+        peer: SocketAddr,
+        /// Published host port.
+        host_port: u16,
+        /// Guest port it is published to.
+        guest_port: u16,
+        /// Datagram body.
+        payload: Vec<u8>,
+    },
+    /// UDP payload for the guest.
+    Datagram {
+        /// Source as seen by the guest.
+        from: SocketAddr,
+        /// Guest destination.
+        to: SocketAddr,
+        /// Datagram body.
+        payload: Vec<u8>,
+        /// Reply to a gateway DNS query (recorded and learned from).
+        dns_reply: bool,
+    },
+}
+
+/// A spliced TCP connection.
+#[derive(Debug)]
+struct TcpFlow {
+    /// Outbound key (`None` for published-port connections).
+    key: Option<FlowKey>,
+    /// Guest → host chunks; dropped once the guest half-closes.
+    to_host: Option<mpsc::Sender<Vec<u8>>>,
+    /// Host → guest chunks.
+    from_host: mpsc::Receiver<Vec<u8>>,
+    /// Host bytes not yet accepted by the socket.
+    pending: Vec<u8>,
+    /// Bytes the guest sent.
+    bytes_out: u64,
+    /// Bytes delivered to the guest.
+    bytes_in: u64,
+    /// Host side reached EOF.
+    host_eof: bool,
+    /// When the flow was created.
+    created: std::time::Instant,
+    /// Host reader / writer tasks.
+    tasks: [JoinHandle<()>; 2],
+}
+
+impl Drop for TcpFlow {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Whether the guest has sent FIN (or the socket is gone).
+const fn guest_finished(state: tcp::State) -> bool {
+    matches!(
+        state,
+        tcp::State::CloseWait
+            | tcp::State::LastAck
+            | tcp::State::Closing
+            | tcp::State::TimeWait
+            | tcp::State::Closed
+    )
+}
+
+impl TcpFlow {
+    /// Moves bytes between `socket` and the host tasks; returns
+    /// `(to_guest, to_host)` byte counts.
+    fn pump(&mut self, socket: &mut tcp::Socket<'_>) -> (usize, usize) {
+        let to_host = self.pump_to_host(socket);
+        if guest_finished(socket.state()) && !socket.can_recv() {
+            self.to_host = None;
+        }
+
+        let mut to_guest = 0;
+        while !self.pending.is_empty() || self.refill() {
+            match socket.send_slice(&self.pending) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    self.pending.drain(..n);
+                    to_guest += n;
+                }
+            }
+        }
+        if self.host_eof && self.pending.is_empty() && socket.may_send() {
+            socket.close();
+        }
+        (to_guest, to_host)
+    }
+
+    /// Forwards received guest bytes while the host writer has room.
+    fn pump_to_host(&self, socket: &mut tcp::Socket<'_>) -> usize {
+        let mut sent = 0;
+        while socket.can_recv() {
+            let Some(tx) = &self.to_host else { break };
+            let permit = match tx.try_reserve() {
+                Ok(permit) => permit,
+                Err(TrySendError::Full(())) => break,
+                Err(TrySendError::Closed(())) => {
+                    socket.abort();
+                    break;
+                }
+            };
+            let Ok(chunk) = socket.recv(|buf| (buf.len(), buf.to_vec())) else {
+                break;
+            };
+            sent += chunk.len();
+            permit.send(chunk);
+        }
+        sent
+    }
+
+    /// Loads the next host chunk into `pending`; false if none is ready.
+    fn refill(&mut self) -> bool {
+        match self.from_host.try_recv() {
+            Ok(chunk) => {
+                self.pending = chunk;
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.host_eof = true;
+                false
+            }
+        }
+    }
+}
+
+/// A UDP NAT entry.
+#[derive(Debug)]
+struct UdpFlow {
+    /// Connected host socket.
+    socket: Arc<UdpSocket>,
+    /// Last guest datagram.
+    last_used: std::time::Instant,
+    /// Reply reader task.
+    reader: JoinHandle<()>,
+}
+
+impl Drop for UdpFlow {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// A host peer of a published UDP port, relayed through one gateway port.
+#[derive(Debug)]
+struct PeerFlow {
+    /// Published socket replies are sent from.
+    socket: Arc<UdpSocket>,
+    /// Host-side sender.
+    peer: SocketAddr,
+    /// Published host port.
+    host_port: u16,
+    /// Last datagram in either direction.
+    last_used: std::time::Instant,
+}
+
+/// How an incoming guest frame is handled.
+#[derive(Debug)]
+enum Class {
+    /// Discard.
+    Drop,
+    /// Feed to smoltcp.
+    Stack,
+    /// Handle as UDP.
+    Udp,
+    /// A TCP SYN that opens `FlowKey`.
+    Syn(FlowKey),
+}
+
+/// Classifies a guest frame. Spoofed sources, ARP probes for the guest's
+/// own address, ICMP beyond the gateway, and IPv6 on an IPv4-only network
+/// never reach smoltcp (whose any-IP mode would otherwise answer for them).
+fn classify(frame: &[u8], gateway: Ipv4Addr, guest: Ipv4Addr, ipv6: Option<Ipv6Link>) -> Class {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return Class::Drop;
+    };
+    match eth.ethertype() {
+        EthernetProtocol::Arp => match ArpPacket::new_checked(eth.payload()) {
+            Ok(arp) if arp.target_protocol_addr() != guest.octets() => Class::Stack,
+            _ => Class::Drop,
+        },
+        EthernetProtocol::Ipv4 => {
+            let Ok(ip) = Ipv4Packet::new_checked(eth.payload()) else {
+                return Class::Drop;
+            };
+            match ip.next_header() {
+                IpProtocol::Udp => Class::Udp,
+                _ if ip.src_addr() != guest => Class::Drop,
+                IpProtocol::Tcp => classify_tcp(ip.payload(), ip.dst_addr().into()),
+                IpProtocol::Icmp if ip.dst_addr() == gateway => Class::Stack,
+                _ => Class::Drop,
+            }
+        }
+        EthernetProtocol::Ipv6 => {
+            let (Some(link), Ok(ip)) = (ipv6, Ipv6Packet::new_checked(eth.payload())) else {
+                return Class::Drop;
+            };
+            match ip.next_header() {
+                IpProtocol::Udp => Class::Udp,
+                // Neighbor discovery is sent from link-local sources.
+                IpProtocol::Icmpv6
+                    if ip.dst_addr() == link.gateway || ip.dst_addr().is_multicast() =>
+                {
+                    Class::Stack
+                }
+                _ if ip.src_addr() != link.guest => Class::Drop,
+                IpProtocol::Tcp => classify_tcp(ip.payload(), ip.dst_addr().into()),
+                _ => Class::Drop,
+            }
+        }
+        EthernetProtocol::Unknown(_) => Class::Drop,
+    }
+}
+
+/// Classifies a guest TCP segment sent to `dst`.
+fn classify_tcp(segment: &[u8], dst: IpAddr) -> Class {
+    match TcpPacket::new_checked(segment) {
+        Ok(tcp) if tcp.syn() && !tcp.ack() => {
+            Class::Syn((tcp.src_port(), SocketAddr::new(dst, tcp.dst_port())))
+        }
+        Ok(_) => Class::Stack,
+        Err(_) => Class::Drop,
+    }
+}
+
+/// Host loopback endpoint for a flow to the host gateway address.
+const fn host_side(dst: SocketAddr) -> SocketAddr {
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, dst.port()))
+}
+
+/// Fresh smoltcp TCP socket.
+fn new_tcp_socket() -> tcp::Socket<'static> {
+    let mut socket = tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; TCP_BUFFER]),
+        tcp::SocketBuffer::new(vec![0; TCP_BUFFER]),
+    );
+    socket.set_nagle_enabled(false);
+    socket
+}
+
+/// Stack state owned by the task.
+struct Stack {
+    /// Gateway address.
+    gateway: Ipv4Addr,
+    /// Guest address.
+    guest: Ipv4Addr,
+    /// IPv6 addresses, when dual-stack.
+    ipv6: Option<Ipv6Link>,
+    /// Guest NIC MAC.
+    guest_mac: [u8; 6],
+    /// Egress policy.
+    allow: AllowList,
+    /// Upstream resolver.
+    upstream_dns: Option<SocketAddr>,
+    /// Names answered by the gateway itself.
+    local_dns: DnsConfig,
+    /// smoltcp interface (gateway MAC/IP, any-IP).
+    iface: Interface,
+    /// Frame queues backing the interface.
+    device: FrameQueue,
+    /// smoltcp sockets.
+    sockets: SocketSet<'static>,
+    /// Spliced TCP connections.
+    flows: HashMap<SocketHandle, TcpFlow>,
+    /// Outbound flow lookup.
+    by_key: HashMap<FlowKey, SocketHandle>,
+    /// SYNs held while the host dial is in flight.
+    connecting: HashMap<FlowKey, Vec<u8>>,
+    /// UDP NAT entries.
+    udp: HashMap<FlowKey, UdpFlow>,
+    /// Published UDP peers by gateway port.
+    peers: HashMap<u16, PeerFlow>,
+    /// Next local port for published-port connections.
+    next_port: u16,
+    /// Host task → stack events.
+    events: mpsc::Sender<Event>,
+    /// Wakes the loop when host tasks move data.
+    notify: Arc<Notify>,
+    /// Shared counters.
+    counters: Arc<Counters>,
+    /// Activity sink, when recording is enabled.
+    activity: Option<Arc<EventBuffer>>,
+    /// Refuses new flows once a blocking quota is used up.
+    shaper: Arc<Shaper>,
+}
+
+impl Stack {
+    /// Builds the interface with the gateway identity and any-IP routing.
+    fn new(config: StackConfig, events: mpsc::Sender<Event>, counters: Arc<Counters>) -> Self {
+        let mut device = FrameQueue::default();
+        let mut iface_config = Config::new(EthernetAddress(GATEWAY_MAC).into());
+        iface_config.random_seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() ^ u64::from(d.subsec_nanos()));
+        let mut iface = Interface::new(iface_config, &mut device, Instant::now());
+        iface.update_ip_addrs(|addrs| {
+            addrs
+                .push(IpCidr::new(
+                    IpAddress::Ipv4(config.gateway),
+                    config.prefix_len,
+                ))
+                .ok();
+            // Owning the host gateway address makes the stack answer ARP for it.
+            if config.local_dns.host_gateway {
+                addrs
+                    .push(IpCidr::new(IpAddress::Ipv4(HOST_IPV4), config.prefix_len))
+                    .ok();
+            }
+            if let Some(link) = config.ipv6 {
+                addrs
+                    .push(IpCidr::new(IpAddress::Ipv6(link.gateway), IPV6_PREFIX_LEN))
+                    .ok();
+            }
+        });
+        // Accept packets for every destination routed via the gateway.
+        iface.set_any_ip(true);
+        iface
+            .routes_mut()
+            .add_default_ipv4_route(config.gateway)
+            .ok();
+        if let Some(link) = config.ipv6 {
+            iface.routes_mut().add_default_ipv6_route(link.gateway).ok();
+        }
+
+        Self {
+            gateway: config.gateway,
+            guest: config.guest,
+            ipv6: config.ipv6,
+            guest_mac: config.guest_mac,
+            allow: config.allow,
+            upstream_dns: config.upstream_dns,
+            local_dns: config.local_dns,
+            iface,
+            device,
+            sockets: SocketSet::new(Vec::new()),
+            flows: HashMap::new(),
+            by_key: HashMap::new(),
+            connecting: HashMap::new(),
+            udp: HashMap::new(),
+            peers: HashMap::new(),
+            next_port: EPHEMERAL_START,
+            events,
+            notify: Arc::new(Notify::new()),
+            counters,
+            activity: config.activity,
+            shaper: config.shaper,
+        }
+    }
+
+    /// Records the event built by `kind` if activity is enabled.
+    fn record(&self, kind: impl FnOnce() -> NetEventKind) {
+        if let Some(activity) = &self.activity {
+            activity.push(kind());
+        }
+    }
+
+    /// Runs smoltcp, moves data between sockets and host tasks, and
+    /// expires idle state.
+    fn poll(&mut self) {
+        let now = Instant::now();
+        self.iface.poll(now, &mut self.device, &mut self.sockets);
+        self.service_flows();
+        self.iface.poll(now, &mut self.device, &mut self.sockets);
+        self.udp
+            .retain(|_, flow| flow.last_used.elapsed() < UDP_IDLE);
+        self.peers
+            .retain(|_, flow| flow.last_used.elapsed() < UDP_IDLE);
+    }
+
+    /// Time until smoltcp next needs a poll.
+    fn delay(&mut self) -> Duration {
+        self.iface
+            .poll_delay(Instant::now(), &self.sockets)
+            .map_or(MAX_IDLE, |d| {
+                Duration::from_micros(d.total_micros()).min(MAX_IDLE)
+            })
+    }
+
+    /// Pumps every TCP flow and reaps finished ones.
+    fn service_flows(&mut self) {
+        let mut finished = Vec::new();
+        let mut established = 0;
+        for (handle, flow) in &mut self.flows {
+            let socket = self.sockets.get_mut::<tcp::Socket<'_>>(*handle);
+            let (to_guest, to_host) = flow.pump(socket);
+            flow.bytes_in += to_guest as u64;
+            flow.bytes_out += to_host as u64;
+            self.counters
+                .bytes_sent
+                .fetch_add(to_guest as u64, Ordering::Relaxed);
+            self.counters
+                .bytes_received
+                .fetch_add(to_host as u64, Ordering::Relaxed);
+            match socket.state() {
+                tcp::State::Established => established += 1,
+                tcp::State::Closed | tcp::State::TimeWait => finished.push(*handle),
+                tcp::State::Listen if flow.created.elapsed() > LISTEN_TIMEOUT => {
+                    finished.push(*handle);
+                }
+                _ => {}
+            }
+        }
+        for handle in finished {
+            if let Some(flow) = self.flows.remove(&handle)
+                && let Some(key) = flow.key
+            {
+                self.by_key.remove(&key);
+                self.record(|| NetEventKind::TcpClose {
+                    remote: key.1,
+                    bytes_out: flow.bytes_out,
+                    bytes_in: flow.bytes_in,
+                });
+            }
+            self.sockets.remove(handle);
+        }
+        self.counters
+            .tcp_established
+            .store(established, Ordering::Relaxed);
+    }
+
+    /// Dispatches one guest frame.
+    fn ingest(&mut self, frame: Vec<u8>) {
+        match classify(&frame, self.gateway, self.guest, self.ipv6) {
+            Class::Drop => {}
+            Class::Stack => self.device.rx.push_back(frame),
+            Class::Udp => self.guest_udp(&frame),
+            Class::Syn(key) => self.guest_syn(key, frame),
+        }
+    }
+
+    /// Host endpoint for a guest connection to `dst`, or `None` if blocked.
+    fn tcp_target(&self, dst: SocketAddr) -> Option<SocketAddr> {
+        if self.is_gateway(dst.ip()) {
+            // DNS over TCP goes to the same upstream as UDP queries.
+            return self.upstream_dns.filter(|_| dst.port() == 53);
+        }
+        if self.shaper.blocks_new_flows() {
+            tracing::debug!(%dst, "transfer quota used up: refused TCP connection");
+            return None;
+        }
+        if self.is_host_gateway(dst.ip()) {
+            return Some(host_side(dst));
+        }
+        if let Err(rule) = self.allow.check(dst.ip(), dst.port(), EgressProtocol::Tcp) {
+            tracing::info!(%dst, ?rule, "egress policy: blocked TCP connection");
+            self.record(|| NetEventKind::Denied {
+                protocol: EgressProtocol::Tcp,
+                remote: Some(dst),
+                host: self.allow.host_of(dst.ip()),
+                rule,
+            });
+            return None;
+        }
+        Some(dst)
+    }
+
+    /// Whether `ip` is the enabled host gateway address.
+    fn is_host_gateway(&self, ip: IpAddr) -> bool {
+        self.local_dns.host_gateway && ip == IpAddr::V4(HOST_IPV4)
+    }
+
+    /// Whether `ip` is one of the gateway's addresses.
+    fn is_gateway(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => ip == self.gateway,
+            IpAddr::V6(ip) => self.ipv6.is_some_and(|link| link.gateway == ip),
+        }
+    }
+
+    /// Whether `ip` is one of the guest's addresses.
+    fn is_guest(&self, ip: IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(ip) => ip == self.guest,
+            IpAddr::V6(ip) => self.ipv6.is_some_and(|link| link.guest == ip),
+        }
+    }
+
+    /// Gateway and guest addresses in the family of `peer`.
+    fn endpoints_for(&self, peer: IpAddr) -> (IpAddr, IpAddr) {
+        match (peer, self.ipv6) {
+            (IpAddr::V6(_), Some(link)) => (link.gateway.into(), link.guest.into()),
+            _ => (self.gateway.into(), self.guest.into()),
+        }
+    }
+
+    /// Holds a new SYN and dials the host side.
+    fn guest_syn(&mut self, key: FlowKey, syn: Vec<u8>) {
+        if self.by_key.contains_key(&key) {
+            // Retransmission for a flow smoltcp already owns.
+            self.device.rx.push_back(syn);
+            return;
+        }
+        if self.connecting.contains_key(&key) {
+            return;
+        }
+        let Some(target) = self.tcp_target(key.1) else {
+            // No listener: smoltcp answers with RST.
+            self.device.rx.push_back(syn);
+            return;
+        };
+        self.connecting.insert(key, syn);
+        let events = self.events.clone();
+        tokio::spawn(async move {
+            let event =
+                match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(target)).await {
+                    Ok(Ok(stream)) => Event::Connected { key, stream },
+                    Ok(Err(e)) => {
+                        tracing::debug!(%target, error = %e, "outbound dial failed");
+                        Event::ConnectFailed { key }
+                    }
+                    Err(_) => Event::ConnectFailed { key },
+                };
+            events.send(event).await.ok();
+        });
+    }
+
+    /// Handles a host-side event.
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Connected { key, stream } => {
+                let Some(syn) = self.connecting.remove(&key) else {
+                    return;
+                };
+                let mut socket = new_tcp_socket();
+                let local = IpListenEndpoint {
+                    addr: Some(key.1.ip().into()),
+                    port: key.1.port(),
+                };
+                if socket.listen(local).is_err() {
+                    return;
+                }
+                let handle = self.sockets.add(socket);
+                self.attach(handle, Some(key), stream);
+                self.by_key.insert(key, handle);
+                self.device.rx.push_back(syn);
+                self.record(|| NetEventKind::TcpConnect {
+                    remote: key.1,
+                    host: self.allow.host_of(key.1.ip()),
+                });
+            }
+            Event::ConnectFailed { key } => {
+                self.counters.tcp_errors.fetch_add(1, Ordering::Relaxed);
+                if let Some(syn) = self.connecting.remove(&key) {
+                    self.device.rx.push_back(syn);
+                }
+            }
+            Event::Inbound {
+                stream,
+                peer,
+                host_port,
+                guest_port,
+            } => {
+                self.record(|| NetEventKind::TcpAccept {
+                    remote: peer,
+                    host_port,
+                    guest_port,
+                });
+                self.inbound(stream, guest_port);
+            }
+            Event::InboundDatagram {
+                socket,
+                peer,
+                host_port,
+                guest_port,
+                payload,
+            } => self.inbound_udp(socket, peer, host_port, guest_port, &payload),
+            Event::Datagram {
+                from,
+                to,
+                payload,
+                dns_reply,
+            } => {
+                if dns_reply {
+                    self.dns_answer(&payload);
+                }
+                self.deliver_udp(from, to, &payload);
+            }
+        }
+    }
+
+    /// Next gateway port for published-port traffic, skipping ports held
+    /// by UDP peers.
+    fn next_local_port(&mut self) -> u16 {
+        loop {
+            let port = self.next_port;
+            self.next_port = self.next_port.checked_add(1).unwrap_or(EPHEMERAL_START);
+            if !self.peers.contains_key(&port) {
+                return port;
+            }
+        }
+    }
+
+    /// Opens a gateway → guest connection for a published port.
+    fn inbound(&mut self, stream: TcpStream, guest_port: u16) {
+        let mut socket = new_tcp_socket();
+        let local_port = self.next_local_port();
+        let remote = (IpAddress::Ipv4(self.guest), guest_port);
+        let local = (IpAddress::Ipv4(self.gateway), local_port);
+        if let Err(e) = socket.connect(self.iface.context(), remote, local) {
+            tracing::debug!(guest_port, error = %e, "published port connect failed");
+            return;
+        }
+        let handle = self.sockets.add(socket);
+        self.attach(handle, None, stream);
+    }
+
+    /// Splices `stream` to the smoltcp socket `handle`.
+    fn attach(&mut self, handle: SocketHandle, key: Option<FlowKey>, stream: TcpStream) {
+        stream.set_nodelay(true).ok();
+        let (reader, writer) = stream.into_split();
+        let (to_host, host_rx) = mpsc::channel::<Vec<u8>>(FLOW_QUEUE);
+        let (host_tx, from_host) = mpsc::channel::<Vec<u8>>(FLOW_QUEUE);
+
+        let read_task = tokio::spawn(host_reader(reader, host_tx, Arc::clone(&self.notify)));
+        let write_task = tokio::spawn(host_writer(writer, host_rx, Arc::clone(&self.notify)));
+
+        self.flows.insert(
+            handle,
+            TcpFlow {
+                key,
+                to_host: Some(to_host),
+                from_host,
+                pending: Vec::new(),
+                bytes_out: 0,
+                bytes_in: 0,
+                host_eof: false,
+                created: std::time::Instant::now(),
+                tasks: [read_task, write_task],
+            },
+        );
+    }
+
+    /// Delivers a datagram from a published UDP port, from the gateway
+    /// port assigned to its sender.
+    fn inbound_udp(
+        &mut self,
+        socket: Arc<UdpSocket>,
+        peer: SocketAddr,
+        host_port: u16,
+        guest_port: u16,
+        payload: &[u8],
+    ) {
+        let existing = self
+            .peers
+            .iter()
+            .find(|(_, flow)| flow.peer == peer && flow.host_port == host_port)
+            .map(|(&port, _)| port);
+        let local_port = existing.unwrap_or_else(|| self.next_local_port());
+        self.peers.insert(
+            local_port,
+            PeerFlow {
+                socket,
+                peer,
+                host_port,
+                last_used: std::time::Instant::now(),
+            },
+        );
+        self.deliver_udp(
+            SocketAddrV4::new(self.gateway, local_port).into(),
+            SocketAddrV4::new(self.guest, guest_port).into(),
+            payload,
+        );
+    }
+
+    /// Sends a guest reply to the published-port peer behind `local_port`.
+    fn reply_to_peer(&mut self, local_port: u16, payload: &[u8]) {
+        let Some(flow) = self.peers.get_mut(&local_port) else {
+            return;
+        };
+        flow.last_used = std::time::Instant::now();
+        if let Ok(n) = flow.socket.try_send_to(payload, flow.peer) {
+            self.counters
+                .bytes_received
+                .fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Handles a guest UDP datagram: DHCP, gateway DNS, published-port
+    /// replies, or NAT egress.
+    fn guest_udp(&mut self, frame: &[u8]) {
+        let Some(dgram) = frame::parse_udp(frame) else {
+            return;
+        };
+        if dgram.dst.port() == 67 && dgram.dst.is_ipv4() {
+            if let Some(reply) = frame::dhcp_reply(dgram.payload, self.gateway, self.guest) {
+                self.device.tx.push(reply);
+            }
+            return;
+        }
+        if !self.is_guest(dgram.src.ip()) {
+            return;
+        }
+        if self.is_gateway(dgram.dst.ip()) {
+            if dgram.dst.port() == 53 {
+                self.dns_query(dgram.src, dgram.payload);
+            } else {
+                self.reply_to_peer(dgram.dst.port(), dgram.payload);
+            }
+            return;
+        }
+        if let Err(rule) = self
+            .allow
+            .check(dgram.dst.ip(), dgram.dst.port(), EgressProtocol::Udp)
+        {
+            tracing::debug!(dst = %dgram.dst, ?rule, "egress policy: dropped UDP datagram");
+            self.record(|| NetEventKind::Denied {
+                protocol: EgressProtocol::Udp,
+                remote: Some(dgram.dst),
+                host: self.allow.host_of(dgram.dst.ip()),
+                rule,
+            });
+            return;
+        }
+        let key = (dgram.src.port(), dgram.dst);
+        if self.shaper.blocks_new_flows() && !self.udp.contains_key(&key) {
+            tracing::debug!(dst = %dgram.dst, "transfer quota used up: dropped UDP datagram");
+            return;
+        }
+        self.udp_send(key, dgram.payload);
+    }
+
+    /// Answers a gateway DNS query, relaying allowed names upstream.
+    fn dns_query(&mut self, src: SocketAddr, query: &[u8]) {
+        let gateway = SocketAddr::new(self.endpoints_for(src.ip()).0, 53);
+        let name = dns::question_name(query);
+        if let Some(name) = &name {
+            self.record(|| NetEventKind::DnsQuery { name: name.clone() });
+        }
+        if let Some(addr) = name.as_deref().and_then(|n| self.local_dns.lookup(n)) {
+            if let Some(reply) = dns::address_reply(query, addr) {
+                self.deliver_udp(gateway, src, &reply);
+            }
+            self.record(|| NetEventKind::DnsAnswer {
+                name: name.clone().unwrap_or_default(),
+                addresses: vec![IpAddr::V4(addr)],
+            });
+            self.allow.learn(name, [IpAddr::V4(addr)]);
+            return;
+        }
+        if self.allow.is_restricted()
+            && !name.as_deref().is_some_and(|n| self.allow.permits_host(n))
+        {
+            tracing::info!(
+                name = name.as_deref().unwrap_or("?"),
+                "egress policy: refused DNS query"
+            );
+            self.record(|| NetEventKind::Denied {
+                protocol: EgressProtocol::Udp,
+                remote: None,
+                rule: name.as_deref().and_then(|n| self.allow.lookup_denial(n)),
+                host: name,
+            });
+            self.dns_error(src, query, dns::RCODE_NXDOMAIN);
+            return;
+        }
+        let Some(upstream) = self.upstream_dns else {
+            self.dns_error(src, query, dns::RCODE_SERVFAIL);
+            return;
+        };
+        let events = self.events.clone();
+        let query = query.to_vec();
+        tokio::spawn(async move {
+            if let Some(payload) = relay_dns(upstream, &query).await {
+                let event = Event::Datagram {
+                    from: gateway,
+                    to: src,
+                    payload,
+                    dns_reply: true,
+                };
+                events.send(event).await.ok();
+            }
+        });
+    }
+
+    /// Learns and records the `A`/`AAAA` answers in a relayed DNS reply.
+    fn dns_answer(&mut self, reply: &[u8]) {
+        let name = dns::question_name(reply);
+        let addresses = dns::address_records(reply);
+        if let Some(name) = &name {
+            self.record(|| NetEventKind::DnsAnswer {
+                name: name.clone(),
+                addresses: addresses.clone(),
+            });
+        }
+        self.allow.learn(name, addresses);
+    }
+
+    /// Answers `query` from the gateway with an error `rcode`.
+    fn dns_error(&mut self, src: SocketAddr, query: &[u8], rcode: u8) {
+        if let Some(reply) = dns::error_reply(query, rcode) {
+            let gateway = SocketAddr::new(self.endpoints_for(src.ip()).0, 53);
+            self.deliver_udp(gateway, src, &reply);
+        }
+    }
+
+    /// Sends a guest datagram through its NAT entry, creating it if needed.
+    fn udp_send(&mut self, key: FlowKey, payload: &[u8]) {
+        if !self.udp.contains_key(&key) {
+            match self.open_udp(key) {
+                Ok(flow) => {
+                    self.udp.insert(key, flow);
+                }
+                Err(e) => {
+                    tracing::debug!(dst = %key.1, error = %e, "UDP NAT socket failed");
+                    return;
+                }
+            }
+        }
+        let Some(flow) = self.udp.get_mut(&key) else {
+            return;
+        };
+        flow.last_used = std::time::Instant::now();
+        if let Ok(n) = flow.socket.try_send(payload) {
+            self.counters
+                .bytes_received
+                .fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Binds a host socket for `key` and starts its reply reader.
+    fn open_udp(&self, key: FlowKey) -> std::io::Result<UdpFlow> {
+        let std_socket = std::net::UdpSocket::bind(unspecified(key.1))?;
+        let target = if self.is_host_gateway(key.1.ip()) {
+            host_side(key.1)
+        } else {
+            key.1
+        };
+        std_socket.connect(target)?;
+        std_socket.set_nonblocking(true)?;
+        let socket = Arc::new(UdpSocket::from_std(std_socket)?);
+
+        let reader = tokio::spawn(udp_reader(
+            Arc::clone(&socket),
+            key.1,
+            SocketAddr::new(self.endpoints_for(key.1.ip()).1, key.0),
+            self.events.clone(),
+        ));
+        Ok(UdpFlow {
+            socket,
+            last_used: std::time::Instant::now(),
+            reader,
+        })
+    }
+
+    /// Queues a UDP frame to the guest.
+    fn deliver_udp(&mut self, from: SocketAddr, to: SocketAddr, payload: &[u8]) {
+        let Some(reply) = frame::udp_reply(self.guest_mac, from, to, payload) else {
+            tracing::debug!(%from, len = payload.len(), "dropping oversized UDP reply");
+            return;
+        };
+        self.counters
+            .bytes_sent
+            .fetch_add(payload.len() as u64, Ordering::Relaxed);
+        self.device.tx.push(reply);
+    }
+
+    /// Logs a stats line.
+    fn log_stats(&self) {
+        tracing::info!(
+            bytes_sent = self.counters.bytes_sent.load(Ordering::Relaxed),
+            bytes_received = self.counters.bytes_received.load(Ordering::Relaxed),
+            tcp_flows = self.flows.len(),
+            udp_flows = self.udp.len(),
+            "userspace network stats"
+        );
+    }
+}
+
+/// Copies host bytes toward the stack until EOF.
+async fn host_reader(mut reader: OwnedReadHalf, tx: mpsc::Sender<Vec<u8>>, notify: Arc<Notify>) {
+    let mut buf = vec![0; READ_CHUNK];
+    while let Ok(n) = reader.read(&mut buf).await {
+        let Some(chunk) = buf.get(..n).filter(|c| !c.is_empty()) else {
+            break;
+        };
+        if tx.send(chunk.to_vec()).await.is_err() {
+            break;
+        }
+        notify.notify_one();
+    }
+    drop(tx);
+    notify.notify_one();
+}
+
+/// Writes guest bytes to the host until the guest half-closes.
+async fn host_writer(
+    mut writer: OwnedWriteHalf,
+    mut rx: mpsc::Receiver<Vec<u8>>,
+    notify: Arc<Notify>,
+) {
+    while let Some(chunk) = rx.recv().await {
+        if writer.write_all(&chunk).await.is_err() {
+            break;
+        }
+        notify.notify_one();
+    }
+    writer.shutdown().await.ok();
+}
+
+/// Turns replies on a NAT socket into guest datagrams.
+async fn udp_reader(
+    socket: Arc<UdpSocket>,
+    from: SocketAddr,
+    to: SocketAddr,
+    events: mpsc::Sender<Event>,
+) {
+    let mut buf = vec![0; usize::from(u16::MAX)];
+    while let Ok(n) = socket.recv(&mut buf).await {
+        let Some(payload) = buf.get(..n) else { break };
+        let event = Event::Datagram {
+            from,
+            to,
+            payload: payload.to_vec(),
+            dns_reply: false,
+        };
+        if events.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Wildcard local address in the family of `remote`.
+fn unspecified(remote: SocketAddr) -> SocketAddr {
+    if remote.is_ipv4() {
+        (Ipv4Addr::UNSPECIFIED, 0).into()
+    } else {
+        (Ipv6Addr::UNSPECIFIED, 0).into()
+    }
+}
+
+/// Relays one DNS query to `upstream` and returns the reply.
+async fn relay_dns(upstream: SocketAddr, query: &[u8]) -> Option<Vec<u8>> {
+    let socket = UdpSocket::bind(unspecified(upstream)).await.ok()?;
+    socket.connect(upstream).await.ok()?;
+    socket.send(query).await.ok()?;
+    let mut buf = vec![0; 4096];
+    let n = tokio::time::timeout(DNS_TIMEOUT, socket.recv(&mut buf))
+        .await
+        .ok()?
+        .ok()?;
+    buf.truncate(n);
+    Some(buf)
+}
+
+/// Receive loop for one published UDP port.
+async fn forward_udp(
+    socket: Arc<UdpSocket>,
+    host_port: u16,
+    guest_port: u16,
+    events: mpsc::Sender<Event>,
+) {
+    let mut buf = vec![0; usize::from(u16::MAX)];
+    loop {
+        let (n, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(e) => {
+                tracing::warn!(guest_port, error = %e, "published UDP port receive failed");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let Some(payload) = buf.get(..n) else {
+            continue;
+        };
+        let event = Event::InboundDatagram {
+            socket: Arc::clone(&socket),
+            peer,
+            host_port,
+            guest_port,
+            payload: payload.to_vec(),
+        };
+        if events.send(event).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Accept loop for one published port.
+async fn forward(
+    listener: TcpListener,
+    host_port: u16,
+    guest_port: u16,
+    events: mpsc::Sender<Event>,
+) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!(guest_port, error = %e, "published port accept failed");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        tracing::debug!(%peer, guest_port, "published port connection");
+        let event = Event::Inbound {
+            stream,
+            peer,
+            host_port,
+            guest_port,
+        };
+        if events.send(event).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Starts the accept or receive loop for a bound published port.
+fn spawn_forward(
+    published: PortForward,
+    bound: Bound,
+    events: &mpsc::Sender<Event>,
+) -> Option<(PortForward, JoinHandle<()>)> {
+    let PortForward {
+        host_port,
+        guest_port,
+        ..
+    } = published;
+    let task = match bound {
+        Bound::Tcp(listener) => TcpListener::from_std(listener)
+            .map(|listener| tokio::spawn(forward(listener, host_port, guest_port, events.clone()))),
+        Bound::Udp(socket) => UdpSocket::from_std(socket).map(|socket| {
+            tokio::spawn(forward_udp(
+                Arc::new(socket),
+                host_port,
+                guest_port,
+                events.clone(),
+            ))
+        }),
+    };
+    match task {
+        Ok(task) => Some((published, task)),
+        Err(e) => {
+            tracing::warn!(%published, error = %e, "published port unavailable");
+            None
+        }
+    }
+}
+
+/// Runs the stack until `shutdown` fires or the guest link task ends.
+#[allow(
+    clippy::cognitive_complexity,
+    reason = "tokio::select! and tracing macros expand into branches this loop does not own"
+)]
+pub(crate) async fn run(
+    config: StackConfig,
+    guest: GuestSocket,
+    counters: Arc<Counters>,
+    mut control: mpsc::UnboundedReceiver<Reconfigure>,
+    mut shutdown: oneshot::Receiver<()>,
+) {
+    let (frames_tx, mut frames_rx) = mpsc::channel(256);
+    let (out_tx, out_rx) = mpsc::channel(256);
+    let (events_tx, mut events_rx) = mpsc::channel(256);
+
+    let shaper = Arc::clone(&config.shaper);
+    let link = tokio::spawn(async move {
+        if let Err(e) = guest.serve(frames_tx, out_rx, shaper).await {
+            tracing::warn!(error = %e, "guest link failed");
+        }
+    });
+    let mut config = config;
+    let stats_logging = config.stats_logging;
+    let mut forwards: HashMap<PortForward, JoinHandle<()>> = std::mem::take(&mut config.forwards)
+        .into_iter()
+        .filter_map(|(published, bound)| spawn_forward(published, bound, &events_tx))
+        .collect();
+
+    let forward_events = events_tx.clone();
+    let mut stack = Stack::new(config, events_tx, counters);
+    let notify = Arc::clone(&stack.notify);
+    let mut last_stats = std::time::Instant::now();
+    'run: loop {
+        stack.poll();
+        for out in std::mem::take(&mut stack.device.tx) {
+            if out_tx.send(out).await.is_err() {
+                break 'run;
+            }
+        }
+        if stats_logging && last_stats.elapsed() >= STATS_INTERVAL {
+            stack.log_stats();
+            last_stats = std::time::Instant::now();
+        }
+        let delay = stack.delay();
+        tokio::select! {
+            _ = &mut shutdown => break,
+            frame = frames_rx.recv() => match frame {
+                Some(frame) => stack.ingest(frame),
+                None => break,
+            },
+            Some(event) = events_rx.recv() => stack.handle(event),
+            Some(update) = control.recv() => {
+                stack.allow = update.allow;
+                for published in update.remove {
+                    if let Some(task) = forwards.remove(&published) {
+                        task.abort();
+                    }
+                }
+                forwards.extend(update.add.into_iter().filter_map(|(published, bound)| {
+                    spawn_forward(published, bound, &forward_events)
+                }));
+                tracing::info!(ports = forwards.len(), "userspace network reconfigured");
+            }
+            () = notify.notified() => {}
+            () = tokio::time::sleep(delay) => {}
+        }
+    }
+    link.abort();
+    for task in forwards.into_values() {
+        task.abort();
+    }
+}
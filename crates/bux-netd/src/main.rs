@@ -0,0 +1,159 @@
+//! bux-netd — sandboxed helper serving one VM's network backend.
+//!
+//! Runtime listens on a control socket and execs:
+//! `bux-netd <control.sock>` (inside `bux-jail`). See
+//! [`bux_net::helper`] for the protocol.
+
+#![allow(clippy::print_stderr, reason = "helper reports errors via stderr")]
+#![allow(
+    clippy::disallowed_methods,
+    clippy::exit,
+    reason = "helper binary uses process::exit"
+)]
+
+#[cfg(target_os = "linux")]
+use bux_seccomp as _;
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("[bux-netd] only supported on Unix");
+    std::process::exit(1);
+}
+
+#[cfg(unix)]
+fn main() {
+    let Some(control_path) = std::env::args().nth(1) else {
+        eprintln!("[bux-netd] usage: bux-netd <control.sock>");
+        std::process::exit(1);
+    };
+
+    // Filter first: the config (and its secrets) arrives only afterwards.
+    if let Err(e) = install_seccomp() {
+        eprintln!("[bux-netd] seccomp filter failed: {e}");
+        std::process::exit(1);
+    }
+
+    let control = match std::os::unix::net::UnixStream::connect(&control_path) {
+        Ok(stream) => stream,
+        Err(e) => {
+            eprintln!("[bux-netd] cannot connect to {control_path}: {e}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(e) = bux_net::helper::serve(control) {
+        eprintln!("[bux-netd] {e}");
+        std::process::exit(1);
+    }
+}
+
+/// Syscalls an async network stack needs beyond the VMM allowlist
+/// (event fds, socket pairs, batched datagrams, thread setup) on `x86_64`.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const NETD_SYSCALLS: &[u32] = &[
+    27,  // mincore
+    53,  // socketpair
+    63,  // uname
+    234, // tgkill
+    267, // readlinkat
+    273, // set_robust_list
+    281, // epoll_pwait
+    290, // eventfd2
+    299, // recvmmsg
+    307, // sendmmsg
+];
+
+/// Syscalls an async network stack needs beyond the VMM allowlist on
+/// `aarch64`.
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const NETD_SYSCALLS: &[u32] = &[
+    19,  // eventfd2
+    22,  // epoll_pwait
+    59,  // pipe2
+    101, // nanosleep
+    123, // sched_getaffinity
+    199, // socketpair
+    232, // mincore
+    243, // recvmmsg
+    269, // sendmmsg
+];
+
+/// The helper's filter: the default `bux-seccomp` allowlist plus
+/// [`NETD_SYSCALLS`]; anything else kills the process.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn filter() -> bux_seccomp::Filter {
+    use bux_seccomp::arch::{AUDIT_ARCH, DEFAULT_ALLOWLIST};
+
+    bux_seccomp::Filter::new(AUDIT_ARCH, bux_seccomp::Action::KillProcess)
+        .allow(DEFAULT_ALLOWLIST)
+        .allow(NETD_SYSCALLS)
+}
+
+/// Installs [`filter`] on every thread.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn install_seccomp() -> Result<(), bux_seccomp::Error> {
+    bux_seccomp::install(&filter().compile()?)
+}
+
+/// No seccomp on this platform; the jail's other layers still apply.
+#[cfg(all(
+    unix,
+    not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))
+))]
+#[allow(
+    clippy::unnecessary_wraps,
+    reason = "same signature as the Linux variant"
+)]
+const fn install_seccomp() -> Result<(), std::convert::Infallible> {
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use bux_seccomp::Action;
+    use bux_seccomp::arch::{AUDIT_ARCH, DEFAULT_ALLOWLIST};
+
+    use super::*;
+
+    #[test]
+    fn extends_the_default_allowlist() {
+        assert!(
+            NETD_SYSCALLS
+                .iter()
+                .all(|nr| !DEFAULT_ALLOWLIST.contains(nr))
+        );
+        let filter = filter();
+        for &nr in DEFAULT_ALLOWLIST.iter().chain(NETD_SYSCALLS) {
+            assert_eq!(filter.evaluate(AUDIT_ARCH, nr, &[0; 6]), Action::Allow);
+        }
+        // ptrace stays denied.
+        let ptrace = if cfg!(target_arch = "x86_64") {
+            101
+        } else {
+            117
+        };
+        assert_eq!(
+            filter.evaluate(AUDIT_ARCH, ptrace, &[0; 6]),
+            Action::KillProcess
+        );
+        filter.compile().unwrap();
+    }
+}
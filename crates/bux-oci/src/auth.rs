@@ -0,0 +1,586 @@
+//! Per-registry credential resolution.
+//!
+//! Credentials are looked up by registry host through a [`CredentialChain`]
+//! of [`CredentialProvider`]s. The default chain reads the Docker CLI
+//! configuration (`$DOCKER_CONFIG/config.json` or `~/.docker/config.json`),
+//! so an existing `docker login` works unchanged:
+//!
+//! 1. `credHelpers[<host>]` — per-registry `docker-credential-<name>` helper.
+//!    Authoritative when configured: a failing helper fails the lookup, and
+//!    a "credentials not found" reply means anonymous access.
+//! 2. `credsStore` — default helper for every registry. A failing or missing
+//!    helper is logged and skipped, as the Docker CLI does.
+//! 3. `auths[<host>]` — inline base64 `user:password` or registry token.
+//!
+//! Identity tokens (OAuth refresh tokens from `docker login` against some
+//! registries) are rejected with an error rather than sent as passwords.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use base64::Engine;
+use oci_client::secrets::RegistryAuth;
+
+use crate::error::{OciError, Result};
+
+/// Canonical host used for Docker Hub images (`Reference::registry`).
+const DOCKER_HUB: &str = "docker.io";
+
+/// Key the Docker CLI uses for Docker Hub in `auths` and helper queries.
+const DOCKER_HUB_SERVER: &str = "https://index.docker.io/v1/";
+
+/// Helper `Username` marking the secret as an identity token.
+const IDENTITY_TOKEN_USER: &str = "<token>";
+
+/// Source of registry credentials.
+///
+/// Implement this to supply credentials from an embedder's own secret store.
+/// `registry` is the bare registry host (e.g. `ghcr.io`, `docker.io`,
+/// `localhost:5000`). Return `Ok(None)` to defer to the next provider.
+pub trait CredentialProvider: Send + Sync {
+    /// Looks up credentials for `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backing store exists but cannot be read.
+    fn credentials(&self, registry: &str) -> Result<Option<RegistryAuth>>;
+}
+
+impl<F> CredentialProvider for F
+where
+    F: Fn(&str) -> Result<Option<RegistryAuth>> + Send + Sync,
+{
+    fn credentials(&self, registry: &str) -> Result<Option<RegistryAuth>> {
+        self(registry)
+    }
+}
+
+/// Ordered list of credential providers; the first `Some` wins.
+#[derive(Clone)]
+pub struct CredentialChain {
+    providers: Vec<Arc<dyn CredentialProvider>>,
+}
+
+impl CredentialChain {
+    /// Creates an empty chain (every registry resolves to anonymous).
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Appends a provider, consulted after all existing ones.
+    #[must_use]
+    pub fn with(mut self, provider: impl CredentialProvider + 'static) -> Self {
+        self.push(provider);
+        self
+    }
+
+    /// Appends a provider in place.
+    pub fn push(&mut self, provider: impl CredentialProvider + 'static) {
+        self.providers.push(Arc::new(provider));
+    }
+
+    /// Inserts a provider ahead of all existing ones.
+    pub fn prepend(&mut self, provider: impl CredentialProvider + 'static) {
+        self.providers.insert(0, Arc::new(provider));
+    }
+
+    /// Number of configured providers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    /// Returns `true` if no providers are configured.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+
+    /// Resolves credentials for a registry host.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first provider error encountered.
+    pub fn resolve(&self, registry: &str) -> Result<Option<RegistryAuth>> {
+        let host = normalize_registry(registry);
+        for provider in &self.providers {
+            if let Some(auth) = provider.credentials(&host)? {
+                return Ok(Some(auth));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Default for CredentialChain {
+    /// Chain containing only [`DockerConfig::from_env`].
+    fn default() -> Self {
+        Self::new().with(DockerConfig::from_env())
+    }
+}
+
+impl std::fmt::Debug for CredentialChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialChain")
+            .field("providers", &self.providers.len())
+            .finish()
+    }
+}
+
+/// Credential provider backed by a Docker CLI `config.json`.
+///
+/// The file is re-read on every lookup so a `docker login` performed while
+/// the process is running takes effect on the next pull. A missing file
+/// yields no credentials.
+#[derive(Debug, Clone)]
+pub struct DockerConfig {
+    /// Path to `config.json`.
+    path: PathBuf,
+    /// Directory searched for `docker-credential-*` helpers instead of `PATH`.
+    helper_dir: Option<PathBuf>,
+}
+
+impl DockerConfig {
+    /// Uses `$DOCKER_CONFIG/config.json`, falling back to `~/.docker/config.json`.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let dir = std::env::var_os("DOCKER_CONFIG").map_or_else(
+            || {
+                std::env::var_os("HOME").map_or_else(
+                    || PathBuf::from(".docker"),
+                    |h| PathBuf::from(h).join(".docker"),
+                )
+            },
+            PathBuf::from,
+        );
+        Self::at(dir.join("config.json"))
+    }
+
+    /// Uses an explicit `config.json` path.
+    #[must_use]
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            helper_dir: None,
+        }
+    }
+
+    /// Resolves `docker-credential-*` helpers from `dir` instead of `PATH`.
+    #[must_use]
+    pub fn with_helper_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.helper_dir = Some(dir.into());
+        self
+    }
+
+    /// Path of the backing `config.json`.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads and parses the config file; `None` if it does not exist.
+    fn load(&self) -> Result<Option<ConfigFile>> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| OciError::Credentials(format!("parse {}: {e}", self.path.display())))
+    }
+
+    /// Runs `docker-credential-<helper> get` for `server`.
+    fn run_helper(&self, helper: &str, server: &str) -> Result<Option<RegistryAuth>> {
+        let bin = format!("docker-credential-{helper}");
+        let program = self
+            .helper_dir
+            .as_ref()
+            .map_or_else(|| PathBuf::from(&bin), |dir| dir.join(&bin));
+        let mut child = Command::new(&program)
+            .arg("get")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| OciError::Credentials(format!("spawn {bin}: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // Helpers that fail early may exit before reading the request.
+            match stdin.write_all(server.as_bytes()) {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+                Ok(()) | Err(_) => {}
+            }
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if stdout.contains("credentials not found") || stderr.contains("credentials not found")
+            {
+                return Ok(None);
+            }
+            return Err(OciError::Credentials(format!(
+                "{bin} get {server}: {}",
+                stderr.trim()
+            )));
+        }
+        let reply: HelperReply = serde_json::from_slice(&output.stdout)
+            .map_err(|e| OciError::Credentials(format!("{bin}: invalid reply: {e}")))?;
+        if reply.secret.is_empty() {
+            return Ok(None);
+        }
+        if reply.username == IDENTITY_TOKEN_USER {
+            return Err(identity_token_error(&format!("{bin} get {server}")));
+        }
+        Ok(Some(RegistryAuth::Basic(reply.username, reply.secret)))
+    }
+}
+
+impl CredentialProvider for DockerConfig {
+    fn credentials(&self, registry: &str) -> Result<Option<RegistryAuth>> {
+        let Some(file) = self.load()? else {
+            return Ok(None);
+        };
+        let host = normalize_registry(registry);
+        let server = if host == DOCKER_HUB {
+            DOCKER_HUB_SERVER
+        } else {
+            host.as_str()
+        };
+
+        let configured = file
+            .cred_helpers
+            .iter()
+            .find(|(key, _)| normalize_registry(key) == host)
+            .map(|(_, name)| name.as_str())
+            .filter(|name| !name.is_empty());
+        if let Some(helper) = configured {
+            // A per-registry helper is authoritative, as in the Docker CLI:
+            // "not found" means anonymous, not a stale `auths` entry.
+            return self.run_helper(helper, server);
+        } else if let Some(store) = file.creds_store.as_deref().filter(|name| !name.is_empty()) {
+            match self.run_helper(store, server) {
+                Ok(Some(auth)) => return Ok(Some(auth)),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    registry = %host,
+                    error = %e,
+                    "credsStore helper failed; falling back to inline auths"
+                ),
+            }
+        }
+
+        file.auths
+            .iter()
+            .find(|(key, _)| normalize_registry(key) == host)
+            .map(|(_, entry)| entry.to_auth())
+            .transpose()
+            .map(Option::flatten)
+    }
+}
+
+/// Subset of the Docker CLI `config.json` used for credentials.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConfigFile {
+    #[serde(default)]
+    auths: HashMap<String, AuthEntry>,
+    #[serde(default)]
+    cred_helpers: HashMap<String, String>,
+    #[serde(default)]
+    creds_store: Option<String>,
+}
+
+/// One `auths` entry.
+#[derive(Debug, Default, serde::Deserialize)]
+struct AuthEntry {
+    #[serde(default)]
+    auth: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+    #[serde(default)]
+    registrytoken: Option<String>,
+    #[serde(default)]
+    identitytoken: Option<String>,
+}
+
+impl AuthEntry {
+    /// Converts the entry into [`RegistryAuth`]; `None` for empty entries
+    /// (as written by `docker login` when a `credsStore` is configured).
+    fn to_auth(&self) -> Result<Option<RegistryAuth>> {
+        if let Some(token) = self.registrytoken.as_deref().filter(|t| !t.is_empty()) {
+            return Ok(Some(RegistryAuth::Bearer(token.to_owned())));
+        }
+        if self.identitytoken.as_deref().is_some_and(|t| !t.is_empty()) {
+            return Err(identity_token_error("auths entry"));
+        }
+        if let Some(encoded) = self.auth.as_deref().filter(|a| !a.is_empty()) {
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| OciError::Credentials(format!("invalid auths entry: {e}")))?;
+            let text = String::from_utf8(decoded)
+                .map_err(|e| OciError::Credentials(format!("invalid auths entry: {e}")))?;
+            let (user, pass) = text.split_once(':').ok_or_else(|| {
+                OciError::Credentials("invalid auths entry: expected user:password".into())
+            })?;
+            return Ok(Some(RegistryAuth::Basic(user.to_owned(), pass.to_owned())));
+        }
+        match (&self.username, &self.password) {
+            (Some(user), Some(pass)) if !user.is_empty() => {
+                Ok(Some(RegistryAuth::Basic(user.clone(), pass.clone())))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Error for an identity token found in `source`.
+fn identity_token_error(source: &str) -> OciError {
+    OciError::Credentials(format!(
+        "{source}: identity tokens (OAuth refresh tokens) are not supported; \
+         log in with a password or access token instead"
+    ))
+}
+
+/// Reply of `docker-credential-<helper> get`.
+#[derive(Debug, serde::Deserialize)]
+struct HelperReply {
+    #[serde(rename = "Username", default)]
+    username: String,
+    #[serde(rename = "Secret", default)]
+    secret: String,
+}
+
+/// Reduces a registry key to a bare host: strips scheme and path, and maps
+/// every Docker Hub alias to `docker.io`.
+pub(crate) fn normalize_registry(key: &str) -> String {
+    let rest = key
+        .strip_prefix("https://")
+        .or_else(|| key.strip_prefix("http://"))
+        .unwrap_or(key);
+    let host = rest.split('/').next().unwrap_or(rest).to_ascii_lowercase();
+    match host.as_str() {
+        "index.docker.io" | "registry-1.docker.io" | "registry.hub.docker.com" => {
+            DOCKER_HUB.to_owned()
+        }
+        _ => host,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &Path, json: &str) -> DockerConfig {
+        let path = dir.join("config.json");
+        std::fs::write(&path, json).unwrap();
+        DockerConfig::at(path).with_helper_dir(dir)
+    }
+
+    #[test]
+    fn normalize_strips_scheme_and_path() {
+        assert_eq!(normalize_registry(DOCKER_HUB_SERVER), "docker.io");
+        assert_eq!(normalize_registry("registry-1.docker.io"), "docker.io");
+        assert_eq!(normalize_registry("https://GHCR.io/v2/"), "ghcr.io");
+        assert_eq!(normalize_registry("localhost:5000"), "localhost:5000");
+    }
+
+    #[test]
+    fn missing_config_is_anonymous() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = DockerConfig::at(dir.path().join("absent.json"));
+        assert_eq!(cfg.credentials("ghcr.io").unwrap(), None);
+    }
+
+    #[test]
+    fn auths_base64_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        // "alice:s3cret"
+        let cfg = write_config(
+            dir.path(),
+            r#"{"auths":{"https://index.docker.io/v1/":{"auth":"YWxpY2U6czNjcmV0"},
+                "localhost:5000":{"username":"bob","password":"pw"}}}"#,
+        );
+        assert_eq!(
+            cfg.credentials("docker.io").unwrap(),
+            Some(RegistryAuth::Basic("alice".into(), "s3cret".into()))
+        );
+        assert_eq!(
+            cfg.credentials("localhost:5000").unwrap(),
+            Some(RegistryAuth::Basic("bob".into(), "pw".into()))
+        );
+        assert_eq!(cfg.credentials("quay.io").unwrap(), None);
+    }
+
+    #[test]
+    fn malformed_auth_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = write_config(
+            dir.path(),
+            r#"{"auths":{"ghcr.io":{"auth":"bm9jb2xvbg=="}}}"#,
+        );
+        assert!(matches!(
+            cfg.credentials("ghcr.io"),
+            Err(OciError::Credentials(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    fn write_helper(dir: &Path, name: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join(format!("docker-credential-{name}"));
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cred_helper_precedes_store_and_auths() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(
+            dir.path(),
+            "ecr",
+            "#!/bin/sh\nread s\necho \"{\\\"Username\\\":\\\"AWS\\\",\\\"Secret\\\":\\\"$s\\\"}\"\n",
+        );
+        write_helper(
+            dir.path(),
+            "store",
+            "#!/bin/sh\necho 'credentials not found in native keychain'\nexit 1\n",
+        );
+        let cfg = write_config(
+            dir.path(),
+            r#"{"credHelpers":{"123.dkr.ecr.aws":"ecr"},"credsStore":"store",
+                "auths":{"ghcr.io":{"auth":"dTpw"}}}"#,
+        );
+        assert_eq!(
+            cfg.credentials("123.dkr.ecr.aws").unwrap(),
+            Some(RegistryAuth::Basic("AWS".into(), "123.dkr.ecr.aws".into()))
+        );
+        // Store reports not-found → fall back to inline auths.
+        assert_eq!(
+            cfg.credentials("ghcr.io").unwrap(),
+            Some(RegistryAuth::Basic("u".into(), "p".into()))
+        );
+        assert_eq!(cfg.credentials("quay.io").unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hub_helper_receives_legacy_server_url() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(
+            dir.path(),
+            "desktop",
+            "#!/bin/sh\nread s\necho \"{\\\"Username\\\":\\\"hub\\\",\\\"Secret\\\":\\\"$s\\\"}\"\n",
+        );
+        let cfg = write_config(dir.path(), r#"{"credsStore":"desktop"}"#);
+        assert_eq!(
+            cfg.credentials("docker.io").unwrap(),
+            Some(RegistryAuth::Basic("hub".into(), DOCKER_HUB_SERVER.into()))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_cred_helper_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(dir.path(), "broken", "#!/bin/sh\necho boom >&2\nexit 2\n");
+        let cfg = write_config(dir.path(), r#"{"credHelpers":{"ghcr.io":"broken"}}"#);
+        let err = cfg.credentials("ghcr.io").unwrap_err();
+        assert!(err.to_string().contains("boom"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cred_helper_not_found_skips_auths() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(
+            dir.path(),
+            "ecr",
+            "#!/bin/sh\necho 'credentials not found in native keychain'\nexit 1\n",
+        );
+        let cfg = write_config(
+            dir.path(),
+            r#"{"credHelpers":{"ghcr.io":"ecr"},"auths":{"ghcr.io":{"auth":"dTpw"}}}"#,
+        );
+        assert_eq!(cfg.credentials("ghcr.io").unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failing_or_missing_store_falls_back() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(dir.path(), "broken", "#!/bin/sh\necho boom >&2\nexit 2\n");
+        let cfg = write_config(
+            dir.path(),
+            r#"{"credsStore":"broken","auths":{"ghcr.io":{"auth":"dTpw"}}}"#,
+        );
+        assert_eq!(
+            cfg.credentials("ghcr.io").unwrap(),
+            Some(RegistryAuth::Basic("u".into(), "p".into()))
+        );
+        assert_eq!(cfg.credentials("quay.io").unwrap(), None);
+        let missing = write_config(dir.path(), r#"{"credsStore":"desktop.exe"}"#);
+        assert_eq!(missing.credentials("ghcr.io").unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn identity_tokens_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        write_helper(
+            dir.path(),
+            "acr",
+            "#!/bin/sh\necho '{\"Username\":\"<token>\",\"Secret\":\"refresh\"}'\n",
+        );
+        let cfg = write_config(
+            dir.path(),
+            r#"{"credHelpers":{"acme.azurecr.io":"acr"},
+                "auths":{"ghcr.io":{"auth":"dTo=","identitytoken":"refresh"}}}"#,
+        );
+        for host in ["acme.azurecr.io", "ghcr.io"] {
+            let err = cfg.credentials(host).unwrap_err();
+            assert!(err.to_string().contains("identity token"), "{err}");
+        }
+    }
+
+    #[test]
+    fn chain_first_match_wins() {
+        let chain = CredentialChain::new()
+            .with(|reg: &str| {
+                Ok((reg == "ghcr.io").then(|| RegistryAuth::Bearer("embedder".into())))
+            })
+            .with(|_: &str| Ok(Some(RegistryAuth::Basic("fallback".into(), "x".into()))));
+        assert_eq!(
+            chain.resolve("https://ghcr.io/").unwrap(),
+            Some(RegistryAuth::Bearer("embedder".into()))
+        );
+        assert_eq!(
+            chain.resolve("quay.io").unwrap(),
+            Some(RegistryAuth::Basic("fallback".into(), "x".into()))
+        );
+        assert_eq!(CredentialChain::new().resolve("quay.io").unwrap(), None);
+    }
+
+    #[test]
+    fn prepend_overrides_docker_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = write_config(dir.path(), r#"{"auths":{"ghcr.io":{"auth":"dTpw"}}}"#);
+        let mut chain = CredentialChain::new().with(cfg);
+        chain.prepend(|_: &str| Ok(Some(RegistryAuth::Bearer("t".into()))));
+        assert_eq!(
+            chain.resolve("ghcr.io").unwrap(),
+            Some(RegistryAuth::Bearer("t".into()))
+        );
+    }
+}
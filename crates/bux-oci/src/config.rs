@@ -1,17 +1,45 @@
 //! Configuration and result types for OCI image operations.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use oci_client::secrets::RegistryAuth;
 
+use crate::auth::CredentialChain;
+
 /// Configuration for initializing [`crate::Oci`].
 #[non_exhaustive]
 #[derive(Debug, Clone)]
 pub struct OciConfig {
     /// Root directory for the image store. Defaults to `<platform_data_dir>/bux`.
     pub store_dir: PathBuf,
-    /// Registry authentication. Defaults to anonymous.
+    /// Per-registry credential providers. Defaults to the Docker CLI
+    /// `config.json` (`auths`, `credHelpers`, `credsStore`).
+    pub credentials: CredentialChain,
+    /// Fallback authentication when no provider has credentials for the
+    /// registry. Defaults to anonymous.
     pub auth: RegistryAuth,
+    /// Pull endpoints keyed by registry host (e.g. `docker.io`).
+    ///
+    /// Endpoints are tried in order; the upstream registry is tried last
+    /// unless it already appears in the list. Defaults to empty.
+    pub mirrors: HashMap<String, Vec<RegistryEndpoint>>,
+    /// Maximum number of layers downloaded concurrently. Defaults to 3.
+    pub max_concurrent_downloads: usize,
+}
+
+impl OciConfig {
+    /// Sets the ordered pull endpoints for `registry`.
+    #[must_use]
+    pub fn with_mirror(
+        mut self,
+        registry: impl Into<String>,
+        endpoints: impl IntoIterator<Item = RegistryEndpoint>,
+    ) -> Self {
+        self.mirrors
+            .insert(registry.into(), endpoints.into_iter().collect());
+        self
+    }
 }
 
 impl Default for OciConfig {
@@ -19,11 +47,67 @@ fn default() -> Self {
         let store_dir = crate::dirs_default_store();
         Self {
             store_dir,
+            credentials: CredentialChain::default(),
             auth: RegistryAuth::Anonymous,
+            mirrors: HashMap::new(),
+            max_concurrent_downloads: 3,
         }
     }
 }
 
+/// A registry host that can serve pulls, with its transport settings.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegistryEndpoint {
+    /// Host and optional port (e.g. `mirror.internal:5000`).
+    pub host: String,
+    /// Speak plain HTTP instead of HTTPS.
+    pub plain_http: bool,
+    /// Skip TLS certificate and hostname verification.
+    pub insecure: bool,
+    /// PEM bundle of additional root CAs trusted for this endpoint.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl RegistryEndpoint {
+    /// HTTPS endpoint with system trust roots.
+    #[must_use]
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            plain_http: false,
+            insecure: false,
+            ca_bundle: None,
+        }
+    }
+
+    /// Uses plain HTTP (e.g. a `localhost` test registry).
+    #[must_use]
+    pub const fn plain_http(mut self) -> Self {
+        self.plain_http = true;
+        self
+    }
+
+    /// Accepts invalid TLS certificates and hostnames.
+    #[must_use]
+    pub const fn insecure(mut self) -> Self {
+        self.insecure = true;
+        self
+    }
+
+    /// Trusts the CA certificates in a PEM bundle in addition to system roots.
+    #[must_use]
+    pub fn ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// Returns `true` if the endpoint needs a dedicated HTTP client.
+    pub(crate) const fn has_custom_transport(&self) -> bool {
+        self.plain_http || self.insecure || self.ca_bundle.is_some()
+    }
+}
+
 /// Subset of the OCI image configuration relevant to VM execution.
 #[non_exhaustive]
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -74,6 +158,9 @@ pub struct PullResult {
     pub reference: String,
     /// Manifest content digest.
     pub digest: String,
+    /// Digest of the multi-arch image index [`Self::digest`] was picked
+    /// from, if the reference named one.
+    pub index_digest: Option<String>,
     /// Path to the extracted rootfs directory.
     pub rootfs: PathBuf,
     /// Image configuration (Cmd, Env, `WorkingDir`, etc.).
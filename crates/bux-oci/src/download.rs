@@ -0,0 +1,156 @@
+//! Resumable, sha256-verified layer downloads.
+//!
+//! A layer streams into its staging file (`<digest>.tar.gz.tmp`). If a
+//! previous pull was interrupted, the bytes already on disk are re-hashed and
+//! the remainder is requested with an HTTP `Range` header. Registries that
+//! ignore `Range` answer with the full blob, in which case the staging file is
+//! rewritten from scratch. A descriptor without a size gives nothing to
+//! check a partial file against, so it is refetched unless it already
+//! hashes to the digest. The complete file must hash to the descriptor's
+//! digest before the caller commits it.
+
+use std::path::Path;
+
+use futures_util::StreamExt;
+use oci_client::Reference;
+use oci_client::client::BlobResponse;
+use oci_client::errors::OciDistributionError;
+use oci_client::manifest::OciDescriptor;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::error::{OciError, Result};
+use crate::progress::PullProgress;
+
+/// Downloads `layer` into `staging`, resuming from any partial file, and
+/// verifies its sha256 digest.
+///
+/// On digest mismatch the staging file is deleted so the next attempt
+/// starts clean. On transport errors it is kept for resumption.
+pub(crate) async fn download_layer(
+    client: &oci_client::Client,
+    reference: &Reference,
+    layer: &OciDescriptor,
+    staging: &Path,
+    on_progress: &(impl Fn(&PullProgress) + Sync),
+) -> Result<()> {
+    let digest = layer.digest.as_str();
+    let expected = digest
+        .strip_prefix("sha256:")
+        .ok_or_else(|| OciError::UnsupportedDigest(digest.to_owned()))?;
+    let total = u64::try_from(layer.size).unwrap_or(0);
+
+    let (mut hasher, mut offset) = resume_point(staging, expected, total).await?;
+
+    if offset == 0 || offset < total {
+        on_progress(&PullProgress::LayerStarted {
+            digest: digest.to_owned(),
+            offset,
+            total,
+        });
+        let (mut stream, append) = match client
+            .pull_blob_stream_partial(reference, layer, offset, None)
+            .await?
+        {
+            BlobResponse::Partial(stream) => (stream, true),
+            BlobResponse::Full(stream) => (stream, false),
+        };
+        if !append && offset > 0 {
+            hasher = Sha256::new();
+            offset = 0;
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(staging)
+            .await?;
+        let mut downloaded = offset;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| OciError::Registry(OciDistributionError::IoError(e)))?;
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+            downloaded += chunk.len() as u64;
+            on_progress(&PullProgress::LayerProgress {
+                digest: digest.to_owned(),
+                downloaded,
+                total,
+            });
+        }
+        file.flush().await?;
+    }
+
+    let actual = hex(&hasher.finalize());
+    if actual != expected {
+        discard(staging).await;
+        return Err(OciError::DigestMismatch {
+            expected: digest.to_owned(),
+            actual: format!("sha256:{actual}"),
+        });
+    }
+    Ok(())
+}
+
+/// Hashes the staging file and returns the hasher with the offset to
+/// resume from, deleting a file that cannot be a prefix of the blob.
+async fn resume_point(staging: &Path, expected: &str, total: u64) -> Result<(Sha256, u64)> {
+    let mut hasher = Sha256::new();
+    let offset = hash_prefix(staging, &mut hasher).await?;
+    // Longer than the descriptor: cannot be a prefix of this blob. With no
+    // size, only a file that already verifies is kept.
+    let stale = if total > 0 {
+        offset > total
+    } else {
+        offset > 0 && hex(&hasher.clone().finalize()) != expected
+    };
+    if !stale {
+        return Ok((hasher, offset));
+    }
+    tokio::fs::remove_file(staging).await?;
+    Ok((Sha256::new(), 0))
+}
+
+/// Deletes a staging file that failed verification. The mismatch is what
+/// the caller needs to see (it moves on to the next endpoint), so a failed
+/// cleanup is only logged.
+async fn discard(staging: &Path) {
+    match tokio::fs::remove_file(staging).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            tracing::warn!(path = %staging.display(), error = %e, "cannot delete corrupt layer");
+        }
+        _ => {}
+    }
+}
+
+/// Feeds an existing staging file into `hasher`; returns its length (0 if absent).
+async fn hash_prefix(path: &Path, hasher: &mut Sha256) -> Result<u64> {
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let mut buf = vec![0_u8; 64 * 1024];
+    let mut len = 0_u64;
+    loop {
+        let n = file.read(&mut buf).await?;
+        let Some(chunk) = buf.get(..n).filter(|c| !c.is_empty()) else {
+            return Ok(len);
+        };
+        hasher.update(chunk);
+        len += n as u64;
+    }
+}
+
+/// Lowercase hex encoding.
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+
+    bytes
+        .iter()
+        .fold(String::with_capacity(bytes.len() * 2), |mut acc, b| {
+            write!(acc, "{b:02x}").ok();
+            acc
+        })
+}
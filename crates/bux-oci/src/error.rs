@@ -15,6 +15,36 @@ pub enum OciError {
     #[error("image not found: {0}")]
     NotFound(String),
 
+    /// Registry credentials could not be resolved.
+    #[error("credentials: {0}")]
+    Credentials(String),
+
+    /// A downloaded blob did not hash to its descriptor digest.
+    #[error("digest mismatch: expected {expected}, got {actual}")]
+    DigestMismatch {
+        /// Digest from the manifest descriptor.
+        expected: String,
+        /// Digest of the bytes received.
+        actual: String,
+    },
+
+    /// A descriptor uses a digest algorithm other than sha256.
+    #[error("unsupported digest algorithm: {0}")]
+    UnsupportedDigest(String),
+
+    /// The image verification policy refused an image.
+    #[error("image {reference} rejected by verification policy: {reason}")]
+    Rejected {
+        /// Canonical image reference.
+        reference: String,
+        /// Why no rule admitted the image.
+        reason: String,
+    },
+
+    /// A trusted public key could not be parsed.
+    #[error("invalid public key: {0}")]
+    InvalidKey(String),
+
     /// Local store / database error.
     #[error("db: {0}")]
     Db(#[from] rusqlite::Error),
@@ -0,0 +1,605 @@
+//! Store maintenance: integrity checks, pruning, and disk accounting.
+//!
+//! All three walk the on-disk layout and compare it with the `SQLite` index.
+//! None of them take a store-wide lock, so they must not run concurrently
+//! with a pull into the same store.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::download::hex;
+use crate::store::{Store, refresh_ref_count};
+
+/// Findings of [`crate::Oci::fsck`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct FsckReport {
+    /// Layers whose bytes no longer hash to their digest.
+    pub corrupt_layers: Vec<String>,
+    /// Layers referenced by an image but absent from disk.
+    pub missing_layers: Vec<String>,
+    /// Layer blobs or index rows that no image references.
+    pub orphaned_layers: Vec<String>,
+    /// Config blobs that no image references.
+    pub orphaned_configs: Vec<PathBuf>,
+    /// Extracted rootfs directories that no image references.
+    pub orphaned_rootfs: Vec<PathBuf>,
+    /// `.tmp` staging files and directories left by interrupted pulls.
+    pub stale_staging: Vec<PathBuf>,
+    /// Layers whose stored ref count disagrees with the image index.
+    pub ref_count_mismatches: Vec<String>,
+    /// Whether the findings were repaired.
+    pub repaired: bool,
+}
+
+impl FsckReport {
+    /// Returns `true` if nothing was found.
+    #[must_use]
+    pub const fn is_clean(&self) -> bool {
+        self.corrupt_layers.is_empty()
+            && self.missing_layers.is_empty()
+            && self.orphaned_layers.is_empty()
+            && self.orphaned_configs.is_empty()
+            && self.orphaned_rootfs.is_empty()
+            && self.stale_staging.is_empty()
+            && self.ref_count_mismatches.is_empty()
+    }
+}
+
+/// Which images [`crate::Oci::prune`] keeps.
+///
+/// An image survives if any rule matches it. With no rules set, every
+/// unprotected image is removed.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default)]
+pub struct KeepPolicy {
+    /// Keep images pulled or used via `ensure` within this period.
+    pub unused_for: Option<Duration>,
+    /// Keep this many most recently used images.
+    pub keep_recent: Option<usize>,
+    /// References that are never removed (e.g. images backing existing VMs).
+    pub protect: Vec<String>,
+}
+
+impl KeepPolicy {
+    /// Creates a policy with no rules.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps images used within `period`.
+    #[must_use]
+    pub const fn unused_for(mut self, period: Duration) -> Self {
+        self.unused_for = Some(period);
+        self
+    }
+
+    /// Keeps the `count` most recently used images.
+    #[must_use]
+    pub const fn keep_recent(mut self, count: usize) -> Self {
+        self.keep_recent = Some(count);
+        self
+    }
+
+    /// Never removes `reference`.
+    #[must_use]
+    pub fn protect(mut self, reference: impl Into<String>) -> Self {
+        self.protect.push(reference.into());
+        self
+    }
+}
+
+/// Outcome of [`crate::Oci::prune`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct PruneReport {
+    /// References removed, least recently used first.
+    pub removed: Vec<String>,
+    /// Bytes freed on disk.
+    pub reclaimed_bytes: u64,
+}
+
+/// Disk usage of one image, as reported by [`crate::Oci::disk_usage`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct ImageUsage {
+    /// Full image reference.
+    pub reference: String,
+    /// Manifest digest.
+    pub digest: String,
+    /// Bytes also used by other references (shared layers, and the rootfs
+    /// when another tag points at the same manifest).
+    pub shared_bytes: u64,
+    /// Bytes freed by removing only this reference.
+    pub unique_bytes: u64,
+}
+
+/// What the image index references.
+#[derive(Default)]
+struct Index {
+    /// Manifest digests of all images.
+    manifests: HashSet<String>,
+    /// Layers referenced by at least one image.
+    layers: HashSet<String>,
+    /// Every row of the `layers` table with its stored ref count.
+    ref_counts: HashMap<String, i64>,
+    /// Config blob digests of all images.
+    configs: HashSet<String>,
+}
+
+impl Store {
+    /// Compares the on-disk layout with the index; repairs if asked.
+    ///
+    /// Repair deletes orphans and staging leftovers (including resumable
+    /// partial layer downloads), deletes corrupt layer blobs so the next pull
+    /// re-fetches them, and recomputes ref counts. Missing layers are only
+    /// reported: the image must be pulled again.
+    pub(crate) fn fsck(&self, repair: bool) -> crate::Result<FsckReport> {
+        let index = self.index()?;
+        let mut report = FsckReport::default();
+
+        for path in entries(&self.root().join("layers"))? {
+            let name = file_name(&path);
+            if is_staging(&path) {
+                report.stale_staging.push(path);
+                continue;
+            }
+            let Some(digest) = name.strip_suffix(".tar.gz").map(digest_from_name) else {
+                continue;
+            };
+            if !index.layers.contains(&digest) {
+                report.orphaned_layers.push(digest);
+            } else if !self.verify_layer(&digest)? {
+                report.corrupt_layers.push(digest);
+            }
+        }
+        for digest in index.ref_counts.keys() {
+            if !index.layers.contains(digest) && !report.orphaned_layers.contains(digest) {
+                report.orphaned_layers.push(digest.clone());
+            }
+        }
+        report.missing_layers = index
+            .layers
+            .iter()
+            .filter(|d| !self.has_layer(d))
+            .cloned()
+            .collect();
+
+        for path in entries(&self.root().join("configs"))? {
+            let name = file_name(&path);
+            if is_staging(&path) {
+                report.stale_staging.push(path);
+            } else if let Some(digest) = name.strip_suffix(".json").map(digest_from_name)
+                && !index.configs.contains(&digest)
+            {
+                report.orphaned_configs.push(path);
+            }
+        }
+
+        for path in entries(&self.root().join("rootfs"))? {
+            let name = file_name(&path);
+            if is_staging(&path) {
+                report.stale_staging.push(path);
+            } else if !index.manifests.contains(&digest_from_name(&name)) {
+                report.orphaned_rootfs.push(path);
+            }
+        }
+
+        let conn = self.lock();
+        for (digest, stored) in &index.ref_counts {
+            let actual: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM image_layers WHERE layer_digest = ?1",
+                [digest],
+                |row| row.get(0),
+            )?;
+            if actual != *stored && index.layers.contains(digest) {
+                report.ref_count_mismatches.push(digest.clone());
+            }
+        }
+        drop(conn);
+
+        for list in [
+            &mut report.corrupt_layers,
+            &mut report.missing_layers,
+            &mut report.orphaned_layers,
+            &mut report.ref_count_mismatches,
+        ] {
+            list.sort();
+        }
+        for list in [
+            &mut report.orphaned_configs,
+            &mut report.orphaned_rootfs,
+            &mut report.stale_staging,
+        ] {
+            list.sort();
+        }
+
+        if repair {
+            self.repair(&report)?;
+            report.repaired = true;
+        }
+        Ok(report)
+    }
+
+    /// Applies the fixes described on [`Store::fsck`].
+    fn repair(&self, report: &FsckReport) -> crate::Result<()> {
+        for path in report
+            .stale_staging
+            .iter()
+            .chain(&report.orphaned_rootfs)
+            .chain(&report.orphaned_configs)
+        {
+            remove_path(path)?;
+        }
+        for digest in report.orphaned_layers.iter().chain(&report.corrupt_layers) {
+            remove_path(&self.layer_path(digest))?;
+        }
+
+        let conn = self.lock();
+        let tx = conn.unchecked_transaction()?;
+        for digest in &report.orphaned_layers {
+            tx.execute("DELETE FROM layers WHERE digest = ?1", [digest])?;
+        }
+        for digest in &report.ref_count_mismatches {
+            refresh_ref_count(&tx, digest)?;
+        }
+        tx.commit()?;
+        drop(conn);
+        Ok(())
+    }
+
+    /// References that `policy` does not keep, least recently used first.
+    pub(crate) fn prune_candidates(&self, policy: &KeepPolicy) -> crate::Result<Vec<String>> {
+        let conn = self.lock();
+        let by_recency: Vec<(String, i64)> = conn
+            .prepare(
+                "SELECT reference,
+                        CAST(strftime('%s', 'now')
+                             - strftime('%s', COALESCE(last_used, created)) AS INTEGER)
+                 FROM images
+                 ORDER BY 2 ASC, reference",
+            )?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        drop(conn);
+
+        let keep_recent = policy.keep_recent.unwrap_or(0);
+        let mut candidates: Vec<String> = by_recency
+            .into_iter()
+            .enumerate()
+            .filter(|(rank, (reference, idle_secs))| {
+                let recent = *rank < keep_recent;
+                let fresh = policy.unused_for.is_some_and(|period| {
+                    u64::try_from(*idle_secs).unwrap_or(0) < period.as_secs()
+                });
+                !(recent || fresh || policy.protect.contains(reference))
+            })
+            .map(|(_, (reference, _))| reference)
+            .collect();
+        candidates.reverse();
+        Ok(candidates)
+    }
+
+    /// Bytes used by layers, configs, and rootfs directories.
+    pub(crate) fn disk_bytes(&self) -> crate::Result<u64> {
+        ["layers", "configs", "rootfs"]
+            .iter()
+            .map(|dir| dir_size(&self.root().join(dir)))
+            .sum()
+    }
+
+    /// Shared vs unique bytes per image reference.
+    pub(crate) fn disk_usage(&self) -> crate::Result<Vec<ImageUsage>> {
+        let conn = self.lock();
+        let images: Vec<(String, String)> = conn
+            .prepare("SELECT reference, digest FROM images ORDER BY reference")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        let layer_refs: Vec<(String, String)> = conn
+            .prepare("SELECT image_ref, layer_digest FROM image_layers")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        drop(conn);
+
+        let mut layer_users: HashMap<&str, usize> = HashMap::new();
+        let mut layers_by_image: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (image, layer) in &layer_refs {
+            *layer_users.entry(layer).or_default() += 1;
+            layers_by_image.entry(image).or_default().push(layer);
+        }
+        let mut digest_users: HashMap<&str, usize> = HashMap::new();
+        for (_, digest) in &images {
+            *digest_users.entry(digest).or_default() += 1;
+        }
+
+        let mut usage = Vec::with_capacity(images.len());
+        for (reference, digest) in &images {
+            let (shared, unique): (Vec<&str>, Vec<&str>) = layers_by_image
+                .get(reference.as_str())
+                .into_iter()
+                .flatten()
+                .partition(|layer| layer_users.get(*layer).copied().unwrap_or(0) > 1);
+            let layer_bytes = |layers: Vec<&str>| -> u64 {
+                layers
+                    .iter()
+                    .map(|l| fs::metadata(self.layer_path(l)).map_or(0, |m| m.len()))
+                    .sum()
+            };
+            let mut shared_bytes = layer_bytes(shared);
+            let mut unique_bytes = layer_bytes(unique);
+            let rootfs = dir_size(&self.rootfs_path(digest))?;
+            if digest_users.get(digest.as_str()).copied().unwrap_or(0) > 1 {
+                shared_bytes += rootfs;
+            } else {
+                unique_bytes += rootfs;
+            }
+            usage.push(ImageUsage {
+                reference: reference.clone(),
+                digest: digest.clone(),
+                shared_bytes,
+                unique_bytes,
+            });
+        }
+        Ok(usage)
+    }
+
+    /// Loads what the image index references.
+    fn index(&self) -> crate::Result<Index> {
+        let conn = self.lock();
+        let mut index = Index::default();
+        let images: Vec<(String, Option<String>, Option<String>)> = conn
+            .prepare("SELECT digest, config_digest, config FROM images")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        for (digest, config_digest, config) in images {
+            index.manifests.insert(digest);
+            // Rows written before schema v3 lack `config_digest`; the config
+            // blob is stored verbatim, so its hash is the digest.
+            let config_digest = config_digest
+                .or_else(|| config.map(|json| format!("sha256:{}", hex(&Sha256::digest(json)))));
+            index.configs.extend(config_digest);
+        }
+        index.layers = conn
+            .prepare("SELECT DISTINCT layer_digest FROM image_layers")?
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        index.ref_counts = conn
+            .prepare("SELECT digest, ref_count FROM layers")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        drop(conn);
+        Ok(index)
+    }
+}
+
+/// Directory entries of `dir` (empty if it does not exist).
+fn entries(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    match fs::read_dir(dir) {
+        Ok(rd) => rd.map(|e| e.map(|e| e.path())).collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// `true` for `.tmp` staging files and directories.
+fn is_staging(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "tmp")
+}
+
+/// Final path component as a string.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Reverses the store's `algo:hex` → `algo-hex` file naming.
+fn digest_from_name(name: &str) -> String {
+    name.replacen('-', ":", 1)
+}
+
+/// Removes a file or directory tree; already-absent paths are fine.
+fn remove_path(path: &Path) -> io::Result<()> {
+    let result = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Apparent size of a file or directory tree, without following symlinks.
+fn dir_size(root: &Path) -> crate::Result<u64> {
+    let mut total = 0;
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(path) = stack.pop() {
+        let meta = match fs::symlink_metadata(&path) {
+            Ok(m) => m,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if meta.is_dir() {
+            stack.extend(entries(&path)?);
+        } else {
+            total += meta.len();
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::test_registry::{TestRegistry, sha256_digest};
+    use crate::{CredentialChain, Oci, OciConfig, RegistryAuth, RegistryEndpoint};
+
+    use super::*;
+
+    const BASE: &[(&str, &[u8])] = &[("etc/os-release", b"ID=test")];
+
+    fn open(dir: &Path, registry: &TestRegistry) -> Oci {
+        Oci::open_with(OciConfig {
+            store_dir: dir.to_path_buf(),
+            credentials: CredentialChain::new(),
+            auth: RegistryAuth::Anonymous,
+            mirrors: HashMap::from([(
+                registry.host().to_owned(),
+                vec![RegistryEndpoint::new(registry.host()).plain_http()],
+            )]),
+            max_concurrent_downloads: 2,
+        })
+        .unwrap()
+    }
+
+    async fn pull(oci: &Oci, registry: &TestRegistry, tag: &str) -> String {
+        oci.pull(&format!("{}/team/app:{tag}", registry.host()), |_| {})
+            .await
+            .unwrap()
+            .reference
+    }
+
+    fn set_idle(oci: &Oci, reference: &str, secs: u64) {
+        oci.store
+            .lock()
+            .execute(
+                "UPDATE images SET last_used = datetime('now', ?2) WHERE reference = ?1",
+                rusqlite::params![reference, format!("-{secs} seconds")],
+            )
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fsck_reports_then_repairs() {
+        let registry = TestRegistry::start().await;
+        let (_, blobs) = registry.add_layered_image("1", &[BASE]);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open(dir.path(), &registry);
+        pull(&oci, &registry, "1").await;
+        assert!(oci.fsck(false).unwrap().is_clean());
+
+        let layer = sha256_digest(&blobs[0]);
+        fs::write(oci.store.layer_path(&layer), b"bitrot").unwrap();
+        let stale = oci.store.layer_staging_path("sha256:0000");
+        fs::write(&stale, b"partial").unwrap();
+        let orphan_rootfs = oci.store.rootfs_path("sha256:dead");
+        fs::create_dir_all(orphan_rootfs.join("etc")).unwrap();
+        let orphan_config = oci.store.config_path("sha256:beef");
+        fs::write(&orphan_config, b"{}").unwrap();
+        fs::write(oci.store.layer_path("sha256:0123"), b"orphan").unwrap();
+        oci.store
+            .lock()
+            .execute("UPDATE layers SET ref_count = 7", [])
+            .unwrap();
+
+        let report = oci.fsck(false).unwrap();
+        assert_eq!(report.corrupt_layers, vec![layer.clone()]);
+        assert_eq!(report.orphaned_layers, vec!["sha256:0123".to_owned()]);
+        assert_eq!(report.orphaned_configs, vec![orphan_config.clone()]);
+        assert_eq!(report.orphaned_rootfs, vec![orphan_rootfs.clone()]);
+        assert_eq!(report.stale_staging, vec![stale.clone()]);
+        assert_eq!(report.ref_count_mismatches, vec![layer.clone()]);
+        assert!(!report.repaired);
+        assert!(stale.exists(), "report-only run must not modify the store");
+
+        assert!(oci.fsck(true).unwrap().repaired);
+        assert!(!stale.exists() && !orphan_rootfs.exists() && !orphan_config.exists());
+        let after = oci.fsck(false).unwrap();
+        assert_eq!(after.missing_layers, vec![layer]);
+        assert!(after.corrupt_layers.is_empty() && after.ref_count_mismatches.is_empty());
+
+        oci.remove(&format!("{}/team/app:1", registry.host()))
+            .unwrap();
+        pull(&oci, &registry, "1").await;
+        assert!(oci.fsck(false).unwrap().is_clean());
+    }
+
+    #[tokio::test]
+    async fn remove_keeps_layers_and_rootfs_still_in_use() {
+        let registry = TestRegistry::start().await;
+        registry.add_layered_image("a", &[BASE, &[("a", b"a")]]);
+        registry.add_layered_image("b", &[BASE, &[("b", b"b")]]);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open(dir.path(), &registry);
+        let a = pull(&oci, &registry, "a").await;
+        let b = pull(&oci, &registry, "b").await;
+
+        oci.remove(&a).unwrap();
+        assert!(oci.fsck(false).unwrap().is_clean());
+        let again = oci.ensure(&b, |_| {}).await.unwrap();
+        assert!(again.rootfs.join("etc/os-release").exists());
+    }
+
+    #[tokio::test]
+    async fn disk_usage_splits_shared_and_unique() {
+        let registry = TestRegistry::start().await;
+        let (_, a_blobs) = registry.add_layered_image("a", &[BASE, &[("a", b"aaaa")]]);
+        let (_, b_blobs) = registry.add_layered_image("b", &[BASE, &[("b", b"bb")]]);
+        registry.add_layered_image("b-alias", &[BASE, &[("b", b"bb")]]);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open(dir.path(), &registry);
+        let a = pull(&oci, &registry, "a").await;
+        let b = pull(&oci, &registry, "b").await;
+        pull(&oci, &registry, "b-alias").await;
+
+        let usage: HashMap<String, ImageUsage> = oci
+            .disk_usage()
+            .unwrap()
+            .into_iter()
+            .map(|u| (u.reference.clone(), u))
+            .collect();
+        let base = a_blobs[0].len() as u64;
+        let rootfs_a = dir_size(&oci.store.rootfs_path(&usage[&a].digest)).unwrap();
+        assert_eq!(usage[&a].shared_bytes, base);
+        assert_eq!(usage[&a].unique_bytes, a_blobs[1].len() as u64 + rootfs_a);
+        // `b` and `b-alias` share every layer and the rootfs.
+        assert_eq!(usage[&b].unique_bytes, 0);
+        assert!(usage[&b].shared_bytes > base + b_blobs[1].len() as u64);
+    }
+
+    #[tokio::test]
+    async fn prune_honours_recency_and_protection() {
+        let registry = TestRegistry::start().await;
+        for tag in ["old", "mid", "new"] {
+            registry.add_layered_image(tag, &[&[(tag, tag.as_bytes())]]);
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open(dir.path(), &registry);
+        let old = pull(&oci, &registry, "old").await;
+        let mid = pull(&oci, &registry, "mid").await;
+        let new = pull(&oci, &registry, "new").await;
+        set_idle(&oci, &old, 3 * 86_400);
+        set_idle(&oci, &mid, 2 * 86_400);
+
+        let keep_all = KeepPolicy::new().unused_for(Duration::from_hours(7 * 24));
+        assert!(oci.prune(&keep_all).unwrap().removed.is_empty());
+
+        let policy = KeepPolicy::new()
+            .unused_for(Duration::from_hours(24))
+            .protect(format!("{}/team/app:old", registry.host()));
+        let report = oci.prune(&policy).unwrap();
+        assert_eq!(report.removed, vec![mid]);
+        assert!(report.reclaimed_bytes > 0);
+
+        let lru = oci.prune(&KeepPolicy::new().keep_recent(1)).unwrap();
+        assert_eq!(lru.removed, vec![old]);
+        let left: Vec<String> = oci
+            .images()
+            .unwrap()
+            .into_iter()
+            .map(|i| i.reference)
+            .collect();
+        assert_eq!(left, vec![new]);
+        assert!(oci.fsck(false).unwrap().is_clean());
+    }
+}
@@ -11,7 +11,8 @@
 //!  │    ├── layers/   — sha256-addressed layer tarballs
 //!  │    ├── configs/  — sha256-addressed config blobs
 //!  │    └── rootfs/   — extracted rootfs directories
-//!  └── oci_client::Client (registry communication)
+//!  ├── CredentialChain (per-registry auth: docker config, helpers, embedders)
+//!  └── Registries (mirror table → per-endpoint oci_client::Client)
 //! ```
 
 #![allow(
@@ -19,21 +20,39 @@
     reason = "internal modules have self-explanatory fields"
 )]
 
+mod auth;
 mod config;
+mod download;
 mod error;
 mod extract;
+mod gc;
+mod progress;
+mod registry;
 mod store;
+#[cfg(any(test, feature = "test-registry"))]
+#[doc(hidden)]
+pub mod test_registry;
+mod verify;
 
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
-use oci_client::Reference;
-use oci_client::client::ClientConfig;
-use oci_client::secrets::RegistryAuth;
-
-pub use config::{ImageConfig, OciConfig, PullResult};
+pub use auth::{CredentialChain, CredentialProvider, DockerConfig};
+pub use config::{ImageConfig, OciConfig, PullResult, RegistryEndpoint};
 pub use error::{OciError, Result};
+use futures_util::{StreamExt, TryStreamExt};
+pub use gc::{FsckReport, ImageUsage, KeepPolicy, PruneReport};
+use oci_client::Reference;
+use oci_client::manifest::{OciDescriptor, OciImageManifest};
+pub use oci_client::secrets::RegistryAuth;
+pub use progress::PullProgress;
+use registry::Registries;
 pub use store::ImageMeta;
 use store::Store;
+pub use verify::{
+    CosignSignature, ImageVerifier, Requirement, TrustedKey, Verification, VerifyMethod,
+    VerifyPolicy, VerifyRequest,
+};
 
 /// OCI image manager backed by a content-addressed store.
 ///
@@ -42,10 +61,14 @@
 pub struct Oci {
     /// Content-addressed image store.
     store: Store,
-    /// OCI registry HTTP client.
-    client: oci_client::Client,
-    /// Registry authentication credentials.
+    /// Mirror table and per-endpoint registry clients.
+    registries: Registries,
+    /// Per-registry credential providers.
+    credentials: CredentialChain,
+    /// Fallback credentials when no provider matches.
     auth: RegistryAuth,
+    /// Upper bound on layers downloaded at once.
+    max_concurrent_downloads: usize,
 }
 
 impl std::fmt::Debug for Oci {
@@ -70,17 +93,17 @@ pub fn open() -> Result<Self> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the store directory cannot be created or the database fails to open.
+    /// Returns an error if the store directory cannot be created, the database fails to open,
+    /// or a mirror's CA bundle cannot be loaded.
     pub fn open_with(config: OciConfig) -> Result<Self> {
         let store = Store::open(&config.store_dir)?;
-        let client = oci_client::Client::new(ClientConfig {
-            platform_resolver: Some(Box::new(linux_platform_resolver)),
-            ..Default::default()
-        });
+        let registries = Registries::new(config.mirrors)?;
         Ok(Self {
             store,
-            client,
+            registries,
+            credentials: config.credentials,
             auth: config.auth,
+            max_concurrent_downloads: config.max_concurrent_downloads.max(1),
         })
     }
 
@@ -98,43 +121,51 @@ pub fn open_at(store_dir: &Path) -> Result<Self> {
 
     /// Pulls an image from a registry, caches layers, extracts rootfs.
     ///
-    /// Uses streaming downloads — each layer is written directly to disk
-    /// via `pull_blob`, keeping memory usage at `O(chunk_size)` instead of
-    /// `O(total_image_size)`. `on_status` receives human-readable progress.
-    pub async fn pull(&self, image: &str, on_status: impl Fn(&str)) -> Result<PullResult> {
+    /// Endpoints from [`OciConfig::mirrors`] are tried in order, then the
+    /// upstream registry; the endpoint that served the image is recorded in
+    /// [`ImageMeta::endpoint`]. Up to [`OciConfig::max_concurrent_downloads`]
+    /// layers stream to disk at once, keeping memory usage at `O(chunk_size)`
+    /// per layer. Interrupted downloads resume from their staging file, and
+    /// every layer is sha256-verified before it enters the store.
+    /// `on_progress` receives structured [`PullProgress`] events.
+    pub async fn pull(
+        &self,
+        image: &str,
+        on_progress: impl Fn(&PullProgress) + Send + Sync,
+    ) -> Result<PullResult> {
         #![allow(clippy::missing_errors_doc, reason = "documented at module level")]
         let reference = parse_reference(image)?;
         let ref_str = reference.to_string();
 
-        // 1. Pull manifest + config (small, OK in memory).
-        on_status(&format!("Pulling {ref_str}..."));
-        let (manifest, manifest_digest, config_json) = self
-            .client
-            .pull_manifest_and_config(&reference, &self.auth)
-            .await?;
-
-        // 2. Stream each layer to disk — O(chunk) memory per layer.
-        let layer_count = manifest.layers.len();
-        let mut total_size: u64 = 0;
-        for (i, layer) in manifest.layers.iter().enumerate() {
-            let digest = &layer.digest;
-            let size = u64::try_from(layer.size).unwrap_or(0);
-
-            if self.store.has_layer(digest) {
-                on_status(&format!("Layer {}/{} cached", i + 1, layer_count));
-            } else {
-                on_status(&format!(
-                    "Downloading layer {}/{} ({size} bytes)...",
-                    i + 1,
-                    layer_count
-                ));
-                let staging = self.store.layer_staging_path(digest);
-                let mut file = tokio::fs::File::create(&staging).await?;
-                self.client.pull_blob(&reference, layer, &mut file).await?;
-                self.store.commit_layer(digest, &layer.media_type, size)?;
+        let mut last_err = None;
+        let mut fetched = None;
+        for endpoint in self.registries.endpoints_for(reference.registry()) {
+            on_progress(&PullProgress::Resolving {
+                reference: ref_str.clone(),
+                endpoint: endpoint.host.clone(),
+            });
+            match self.fetch(&reference, &endpoint, &on_progress).await {
+                Ok(pulled) => {
+                    fetched = Some((endpoint, pulled));
+                    break;
+                }
+                // Registry/transport failures and corrupt blobs fall through to
+                // the next endpoint; local store errors are not endpoint-specific.
+                Err(e @ (OciError::Registry(_) | OciError::DigestMismatch { .. })) => {
+                    on_progress(&PullProgress::EndpointFailed {
+                        endpoint: endpoint.host.clone(),
+                        error: e.to_string(),
+                    });
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
             }
-            total_size += size;
         }
+        let Some((endpoint, (manifest, manifest_digest, index_digest, config_json, total_size))) =
+            fetched
+        else {
+            return Err(last_err.unwrap_or(OciError::NotFound(ref_str)));
+        };
 
         // 3. Save config blob.
         let config_digest = &manifest.config.digest;
@@ -144,7 +175,7 @@ pub async fn pull(&self, image: &str, on_status: impl Fn(&str)) -> Result<PullRe
         // 4. Extract rootfs atomically (staging dir → rename).
         let rootfs = self.store.rootfs_path(&manifest_digest);
         if !self.store.rootfs_complete(&manifest_digest) {
-            on_status("Extracting rootfs...");
+            on_progress(&PullProgress::Extracting);
             let layer_files: Vec<(PathBuf, String)> = manifest
                 .layers
                 .iter()
@@ -173,20 +204,90 @@ pub async fn pull(&self, image: &str, on_status: impl Fn(&str)) -> Result<PullRe
         self.store.upsert_image(
             &ref_str,
             &manifest_digest,
+            index_digest.as_deref(),
             total_size,
             config_digest,
             &layer_digests,
+            &endpoint.host,
         )?;
 
-        on_status("Done.");
+        on_progress(&PullProgress::Done);
         Ok(PullResult {
             reference: ref_str,
             digest: manifest_digest,
+            index_digest,
             rootfs,
             config,
         })
     }
 
+    /// Fetches manifest, config, and layers from one endpoint.
+    ///
+    /// Returns `(manifest, manifest_digest, index_digest, config_json,
+    /// total_layer_size)`.
+    async fn fetch(
+        &self,
+        reference: &Reference,
+        endpoint: &RegistryEndpoint,
+        on_progress: impl Fn(&PullProgress) + Send + Sync,
+    ) -> Result<(OciImageManifest, String, Option<String>, String, u64)> {
+        let client = self.registries.client_for(endpoint);
+        let target = registry::reference_for(reference, endpoint);
+
+        // 1. Pull manifest + config (small, OK in memory).
+        let auth = self.registry_auth(&endpoint.host).await?;
+        let (manifest, manifest_digest, config_json) =
+            client.pull_manifest_and_config(&target, &auth).await?;
+        let index_digest = registry::index_digest(client, &target, &auth, &manifest_digest).await;
+
+        // 2. Stream layers to disk in parallel; duplicate digests download once.
+        let total_size = manifest
+            .layers
+            .iter()
+            .map(|l| u64::try_from(l.size).unwrap_or(0))
+            .sum();
+        let mut seen = HashSet::new();
+        let unique = manifest.layers.iter().filter(|l| seen.insert(&l.digest));
+        futures_util::stream::iter(unique)
+            .map(|layer| self.fetch_layer(client, &target, layer, &on_progress))
+            .buffer_unordered(self.max_concurrent_downloads)
+            .try_collect::<()>()
+            .await?;
+        Ok((
+            manifest,
+            manifest_digest,
+            index_digest,
+            config_json,
+            total_size,
+        ))
+    }
+
+    /// Downloads, verifies, and commits one layer unless already stored.
+    async fn fetch_layer(
+        &self,
+        client: &oci_client::Client,
+        target: &Reference,
+        layer: &OciDescriptor,
+        on_progress: &(impl Fn(&PullProgress) + Sync),
+    ) -> Result<()> {
+        let digest = &layer.digest;
+        let size = u64::try_from(layer.size).unwrap_or(0);
+        if self.store.has_layer(digest) {
+            on_progress(&PullProgress::LayerCached {
+                digest: digest.clone(),
+                size,
+            });
+            return Ok(());
+        }
+        let staging = self.store.layer_staging_path(digest);
+        download::download_layer(client, target, layer, &staging, on_progress).await?;
+        self.store.commit_layer(digest, &layer.media_type, size)?;
+        on_progress(&PullProgress::LayerVerified {
+            digest: digest.clone(),
+        });
+        Ok(())
+    }
+
     /// Returns a cached [`PullResult`] if already present, otherwise pulls.
     ///
     /// This is the preferred entry point for `bux run <image>` — instant when
@@ -196,7 +297,11 @@ pub async fn pull(&self, image: &str, on_status: impl Fn(&str)) -> Result<PullRe
     ///
     /// Returns an error if the image reference is invalid, a pull or extraction fails,
     /// or database access encounters an error.
-    pub async fn ensure(&self, image: &str, on_status: impl Fn(&str)) -> Result<PullResult> {
+    pub async fn ensure(
+        &self,
+        image: &str,
+        on_progress: impl Fn(&PullProgress) + Send + Sync,
+    ) -> Result<PullResult> {
         let reference = parse_reference(image)?;
         let ref_str = reference.to_string();
 
@@ -204,12 +309,14 @@ pub async fn ensure(&self, image: &str, on_status: impl Fn(&str)) -> Result<Pull
         if let Some(digest) = self.store.get_digest(&ref_str)?
             && self.store.rootfs_complete(&digest)
         {
+            self.store.touch_image(&ref_str)?;
             let rootfs = self.store.rootfs_path(&digest);
             let config = self
                 .store
                 .load_image_config(&ref_str)?
                 .and_then(|json| parse_image_config(&json));
             return Ok(PullResult {
+                index_digest: self.store.get_index_digest(&ref_str)?,
                 reference: ref_str,
                 digest,
                 rootfs,
@@ -217,7 +324,93 @@ pub async fn ensure(&self, image: &str, on_status: impl Fn(&str)) -> Result<Pull
             });
         }
 
-        self.pull(image, on_status).await
+        self.pull(image, on_progress).await
+    }
+
+    /// Checks a pulled image against `policy` before it is allowed to boot.
+    ///
+    /// Signatures are only fetched when the matching rule needs them; mirror
+    /// endpoints are consulted in the same order as for pulls, and the first
+    /// endpoint that publishes any signature is used.
+    ///
+    /// Multi-arch images are usually signed by their index digest, so
+    /// [`PullResult::index_digest`] is checked first and the platform
+    /// manifest digest only if the index is not admitted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OciError::Rejected`] if no rule admits the image, or an
+    /// error if the reference is invalid or credentials cannot be resolved.
+    pub async fn verify(&self, pulled: &PullResult, policy: &VerifyPolicy) -> Result<Verification> {
+        let reference = parse_reference(&pulled.reference)?;
+        if let Some(index) = &pulled.index_digest {
+            match self.verify_digest(&reference, index, policy).await {
+                Err(OciError::Rejected { .. }) => {}
+                admitted => return admitted,
+            }
+        }
+        self.verify_digest(&reference, &pulled.digest, policy).await
+    }
+
+    /// Evaluates `policy` for `reference@digest`.
+    async fn verify_digest(
+        &self,
+        reference: &Reference,
+        digest: &str,
+        policy: &VerifyPolicy,
+    ) -> Result<Verification> {
+        let signatures = if policy.needs_signatures(&verify::repository_of(reference), digest) {
+            self.signatures(reference, digest).await?
+        } else {
+            Vec::new()
+        };
+        policy.evaluate(reference, digest, &signatures)
+    }
+
+    /// Cosign signatures for `digest`, from the first endpoint that has any.
+    async fn signatures(
+        &self,
+        reference: &Reference,
+        digest: &str,
+    ) -> Result<Vec<CosignSignature>> {
+        let sig_tag = Reference::with_tag(
+            reference.registry().to_owned(),
+            reference.repository().to_owned(),
+            format!("{}.sig", digest.replace(':', "-")),
+        );
+        let signed = reference.clone_with_digest(digest.to_owned());
+        for endpoint in self.registries.endpoints_for(reference.registry()) {
+            let auth = self.registry_auth(&endpoint.host).await?;
+            let found = verify::fetch_signatures(
+                self.registries.client_for(&endpoint),
+                &auth,
+                &registry::reference_for(&sig_tag, &endpoint),
+                &registry::reference_for(&signed, &endpoint),
+            )
+            .await;
+            if !found.is_empty() {
+                return Ok(found);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    /// Resolves credentials for a registry host through the configured
+    /// [`CredentialChain`], falling back to [`OciConfig::auth`].
+    ///
+    /// Providers may spawn credential helper processes, so resolution runs
+    /// on the blocking pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a provider fails (unreadable config, helper error).
+    pub async fn registry_auth(&self, registry: &str) -> Result<RegistryAuth> {
+        let chain = self.credentials.clone();
+        let host = registry.to_owned();
+        let resolved = tokio::task::spawn_blocking(move || chain.resolve(&host))
+            .await
+            .map_err(|e| OciError::Io(std::io::Error::other(e)))??;
+        Ok(resolved.unwrap_or_else(|| self.auth.clone()))
     }
 
     /// Lists all locally stored images.
@@ -241,6 +434,65 @@ pub fn remove(&self, image: &str) -> Result<()> {
         let reference = parse_reference(image)?;
         self.store.remove_image(&reference.to_string())
     }
+
+    /// Checks the store for corrupt or missing layers, orphaned blobs and
+    /// rootfs directories, stale `.tmp` staging files, and ref counts that
+    /// disagree with the index. With `repair`, fixes what it can.
+    ///
+    /// Must not run concurrently with a pull into the same store: repair
+    /// deletes in-flight staging files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database or filesystem operation fails.
+    pub fn fsck(&self, repair: bool) -> Result<FsckReport> {
+        self.store.fsck(repair)
+    }
+
+    /// Removes images that `policy` does not keep, least recently used first.
+    ///
+    /// Recency is the last pull or `ensure` cache hit. Protected references
+    /// are canonicalized the same way as [`Oci::remove`] arguments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database or filesystem operation fails.
+    pub fn prune(&self, policy: &KeepPolicy) -> Result<PruneReport> {
+        let mut policy = policy.clone();
+        for reference in &mut policy.protect {
+            if let Ok(parsed) = parse_reference(reference) {
+                *reference = parsed.to_string();
+            }
+        }
+        let before = self.store.disk_bytes()?;
+        let removed = self.store.prune_candidates(&policy)?;
+        for reference in &removed {
+            self.store.remove_image(reference)?;
+        }
+        Ok(PruneReport {
+            removed,
+            reclaimed_bytes: before.saturating_sub(self.store.disk_bytes()?),
+        })
+    }
+
+    /// Reports shared vs unique disk usage for every stored image.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database or filesystem operation fails.
+    pub fn disk_usage(&self) -> Result<Vec<ImageUsage>> {
+        self.store.disk_usage()
+    }
+}
+
+/// Returns the registry host an image reference pulls from, with Docker
+/// Hub aliases folded into `docker.io` (`python:slim` → `docker.io`).
+///
+/// # Errors
+///
+/// Returns [`OciError::InvalidReference`] if `image` does not parse.
+pub fn image_registry(image: &str) -> Result<String> {
+    Ok(auth::normalize_registry(parse_reference(image)?.registry()))
 }
 
 /// Parses an image string into an [`oci_client::Reference`].
@@ -325,3 +577,347 @@ fn dirs_default_store() -> PathBuf {
     }
     PathBuf::from("bux")
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn open_with_chain(dir: &Path, credentials: CredentialChain) -> Oci {
+        Oci::open_with(OciConfig {
+            store_dir: dir.to_path_buf(),
+            credentials,
+            auth: RegistryAuth::Basic("default".into(), "pw".into()),
+            mirrors: HashMap::new(),
+            max_concurrent_downloads: 3,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn image_registry_folds_docker_hub() {
+        assert_eq!(image_registry("python:slim").unwrap(), "docker.io");
+        assert_eq!(
+            image_registry("localhost:5000/team/app:1").unwrap(),
+            "localhost:5000"
+        );
+        assert!(image_registry("UPPER CASE").is_err());
+    }
+
+    #[tokio::test]
+    async fn registry_auth_prefers_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        let chain = CredentialChain::new().with(|reg: &str| {
+            Ok((reg == "localhost:5000").then(|| RegistryAuth::Bearer("local".into())))
+        });
+        let oci = open_with_chain(dir.path(), chain);
+        let reference = parse_reference("localhost:5000/team/app:1").unwrap();
+        assert_eq!(
+            oci.registry_auth(reference.registry()).await.unwrap(),
+            RegistryAuth::Bearer("local".into())
+        );
+        assert_eq!(
+            oci.registry_auth("ghcr.io").await.unwrap(),
+            RegistryAuth::Basic("default".into(), "pw".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn registry_auth_surfaces_provider_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let chain = CredentialChain::new()
+            .with(|_: &str| Err(OciError::Credentials("store locked".into())));
+        let oci = open_with_chain(dir.path(), chain);
+        assert!(matches!(
+            oci.registry_auth("ghcr.io").await,
+            Err(OciError::Credentials(_))
+        ));
+    }
+
+    fn open_with_mirrors(
+        dir: &Path,
+        credentials: CredentialChain,
+        mirrors: HashMap<String, Vec<RegistryEndpoint>>,
+    ) -> Oci {
+        Oci::open_with(OciConfig {
+            store_dir: dir.to_path_buf(),
+            credentials,
+            auth: RegistryAuth::Anonymous,
+            mirrors,
+            max_concurrent_downloads: 2,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn pull_from_plain_http_registry_with_credentials() {
+        let registry = test_registry::TestRegistry::start().await;
+        registry.require_basic("alice", "s3cret");
+        let digest = registry.add_image("1", &[("hello.txt", b"hi")]);
+        let host = registry.host().to_owned();
+
+        let dir = tempfile::tempdir().unwrap();
+        let expected = host.clone();
+        let chain = CredentialChain::new().with(move |reg: &str| {
+            Ok((reg == expected).then(|| RegistryAuth::Basic("alice".into(), "s3cret".into())))
+        });
+        let oci = open_with_mirrors(
+            dir.path(),
+            chain,
+            HashMap::from([(
+                host.clone(),
+                vec![RegistryEndpoint::new(host.clone()).plain_http()],
+            )]),
+        );
+
+        let result = oci
+            .pull(&format!("{host}/team/app:1"), |_| {})
+            .await
+            .unwrap();
+        assert_eq!(result.digest, digest);
+        assert_eq!(
+            std::fs::read(result.rootfs.join("hello.txt")).unwrap(),
+            b"hi"
+        );
+        let images = oci.images().unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].endpoint.as_deref(), Some(host.as_str()));
+    }
+
+    #[tokio::test]
+    async fn pull_falls_back_through_mirrors_in_order() {
+        let registry = test_registry::TestRegistry::start().await;
+        registry.add_image("1", &[("etc/os-release", b"ID=test")]);
+        let dead = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .to_string();
+
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open_with_mirrors(
+            dir.path(),
+            CredentialChain::new(),
+            HashMap::from([(
+                "registry.invalid".to_owned(),
+                vec![
+                    RegistryEndpoint::new(dead).plain_http(),
+                    RegistryEndpoint::new(registry.host()).plain_http(),
+                ],
+            )]),
+        );
+
+        let events = std::sync::Mutex::new(Vec::new());
+        oci.pull("registry.invalid/team/app:1", |p| {
+            events.lock().unwrap().push(p.clone());
+        })
+        .await
+        .unwrap();
+
+        let images = oci.images().unwrap();
+        assert_eq!(images[0].endpoint.as_deref(), Some(registry.host()));
+        assert!(
+            registry
+                .requests()
+                .iter()
+                .any(|r| r.contains("/manifests/1?ns=registry.invalid")),
+            "{:?}",
+            registry.requests()
+        );
+        assert!(
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|p| matches!(p, PullProgress::EndpointFailed { .. }))
+        );
+    }
+
+    fn plain_http_oci(dir: &Path, registry: &test_registry::TestRegistry) -> Oci {
+        open_with_mirrors(
+            dir,
+            CredentialChain::new(),
+            HashMap::from([(
+                registry.host().to_owned(),
+                vec![RegistryEndpoint::new(registry.host()).plain_http()],
+            )]),
+        )
+    }
+
+    #[tokio::test]
+    async fn pull_downloads_layers_in_parallel_with_progress() {
+        let registry = test_registry::TestRegistry::start().await;
+        let (_, blobs) = registry.add_layered_image(
+            "1",
+            &[&[("a.txt", b"a")], &[("b.txt", b"b")], &[("c.txt", b"c")]],
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let oci = plain_http_oci(dir.path(), &registry);
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let result = oci
+            .pull(&format!("{}/team/app:1", registry.host()), |p| {
+                events.lock().unwrap().push(p.clone());
+            })
+            .await
+            .unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            assert!(result.rootfs.join(name).exists(), "{name}");
+        }
+
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.last(), Some(&PullProgress::Done));
+        for blob in &blobs {
+            let digest = test_registry::sha256_digest(blob);
+            let total = blob.len() as u64;
+            assert!(events.contains(&PullProgress::LayerStarted {
+                digest: digest.clone(),
+                offset: 0,
+                total,
+            }));
+            assert!(events.contains(&PullProgress::LayerProgress {
+                digest: digest.clone(),
+                downloaded: total,
+                total,
+            }));
+            assert!(events.contains(&PullProgress::LayerVerified { digest }));
+        }
+    }
+
+    #[tokio::test]
+    async fn pull_resumes_partial_layer_with_range() {
+        let registry = test_registry::TestRegistry::start().await;
+        let (digest, blobs) = registry.add_layered_image("1", &[&[("big.bin", &[7_u8; 4096])]]);
+        let blob = &blobs[0];
+        let layer_digest = test_registry::sha256_digest(blob);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = plain_http_oci(dir.path(), &registry);
+
+        let half = blob.len() / 2;
+        std::fs::write(oci.store.layer_staging_path(&layer_digest), &blob[..half]).unwrap();
+
+        let events = std::sync::Mutex::new(Vec::new());
+        let result = oci
+            .pull(&format!("{}/team/app:1", registry.host()), |p| {
+                events.lock().unwrap().push(p.clone());
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.digest, digest);
+        assert_eq!(
+            std::fs::read(result.rootfs.join("big.bin")).unwrap(),
+            [7_u8; 4096]
+        );
+        assert!(
+            events
+                .lock()
+                .unwrap()
+                .contains(&PullProgress::LayerStarted {
+                    digest: layer_digest.clone(),
+                    offset: half as u64,
+                    total: blob.len() as u64,
+                })
+        );
+        let range = format!("range=bytes={half}-");
+        assert!(
+            registry
+                .requests()
+                .iter()
+                .any(|r| r.contains(&layer_digest) && r.ends_with(&range)),
+            "{:?}",
+            registry.requests()
+        );
+    }
+
+    #[tokio::test]
+    async fn corrupt_partial_layer_is_discarded() {
+        let registry = test_registry::TestRegistry::start().await;
+        let (_, blobs) = registry.add_layered_image("1", &[&[("f", b"data")]]);
+        let layer_digest = test_registry::sha256_digest(&blobs[0]);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = plain_http_oci(dir.path(), &registry);
+        let staging = oci.store.layer_staging_path(&layer_digest);
+        std::fs::write(&staging, b"garbage").unwrap();
+
+        let image = format!("{}/team/app:1", registry.host());
+        let err = oci.pull(&image, |_| {}).await.unwrap_err();
+        assert!(matches!(err, OciError::DigestMismatch { .. }), "{err}");
+        assert!(!staging.exists());
+        assert!(!oci.store.has_layer(&layer_digest));
+
+        oci.pull(&image, |_| {}).await.unwrap();
+        assert!(oci.store.has_layer(&layer_digest));
+    }
+
+    #[tokio::test]
+    async fn unsized_partial_layer_is_refetched() {
+        let registry = test_registry::TestRegistry::start().await;
+        let (_, blobs) = registry.add_layered_image("1", &[&[("f", b"data")]]);
+        let layer_digest = test_registry::sha256_digest(&blobs[0]);
+        let dir = tempfile::tempdir().unwrap();
+        let oci = plain_http_oci(dir.path(), &registry);
+        let staging = oci.store.layer_staging_path(&layer_digest);
+        std::fs::write(&staging, b"garbage").unwrap();
+
+        let client = oci
+            .registries
+            .client_for(&RegistryEndpoint::new(registry.host()).plain_http());
+        let reference = parse_reference(&format!("{}/team/app:1", registry.host())).unwrap();
+        let layer = OciDescriptor {
+            digest: layer_digest.clone(),
+            size: 0,
+            ..OciDescriptor::default()
+        };
+        download::download_layer(client, &reference, &layer, &staging, &|_| {})
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&staging).unwrap(), blobs[0]);
+
+        // A staging file that already verifies is not fetched again.
+        let fetched = registry.requests().len();
+        download::download_layer(client, &reference, &layer, &staging, &|_| {})
+            .await
+            .unwrap();
+        assert_eq!(registry.requests().len(), fetched);
+    }
+
+    #[tokio::test]
+    async fn corrupt_mirror_blob_falls_back_to_next_endpoint() {
+        let mirror = test_registry::TestRegistry::start().await;
+        let upstream = test_registry::TestRegistry::start().await;
+        let files: &[(&str, &[u8])] = &[("etc/hostname", b"bux")];
+        let (_, blobs) = mirror.add_layered_image("1", &[files]);
+        upstream.add_layered_image("1", &[files]);
+        mirror.set_blob(
+            &test_registry::sha256_digest(&blobs[0]),
+            b"tampered".to_vec(),
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let oci = open_with_mirrors(
+            dir.path(),
+            CredentialChain::new(),
+            HashMap::from([(
+                "registry.invalid".to_owned(),
+                vec![
+                    RegistryEndpoint::new(mirror.host()).plain_http(),
+                    RegistryEndpoint::new(upstream.host()).plain_http(),
+                ],
+            )]),
+        );
+        let result = oci
+            .pull("registry.invalid/team/app:1", |_| {})
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(result.rootfs.join("etc/hostname")).unwrap(),
+            b"bux"
+        );
+        assert_eq!(
+            oci.images().unwrap()[0].endpoint.as_deref(),
+            Some(upstream.host())
+        );
+    }
+}
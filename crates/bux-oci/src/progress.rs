@@ -0,0 +1,101 @@
+//! Structured pull progress events.
+
+use std::fmt;
+
+/// Progress event emitted by [`crate::Oci::pull`] and [`crate::Oci::ensure`].
+///
+/// Layers download in parallel, so per-layer events interleave; key them by
+/// `digest` to drive one progress bar per layer.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PullProgress {
+    /// Fetching the manifest from an endpoint.
+    Resolving {
+        /// Canonical image reference.
+        reference: String,
+        /// Endpoint host being tried.
+        endpoint: String,
+    },
+    /// An endpoint failed; the next one (if any) is tried.
+    EndpointFailed {
+        /// Endpoint host that failed.
+        endpoint: String,
+        /// Failure description.
+        error: String,
+    },
+    /// Layer already present in the store.
+    LayerCached {
+        /// Layer digest.
+        digest: String,
+        /// Compressed size in bytes.
+        size: u64,
+    },
+    /// Layer download started (`offset > 0` when resuming a partial blob).
+    LayerStarted {
+        /// Layer digest.
+        digest: String,
+        /// Bytes already on disk from an interrupted pull.
+        offset: u64,
+        /// Expected size from the manifest descriptor.
+        total: u64,
+    },
+    /// Bytes received for a layer.
+    LayerProgress {
+        /// Layer digest.
+        digest: String,
+        /// Bytes on disk so far, including any resumed prefix.
+        downloaded: u64,
+        /// Expected size from the manifest descriptor.
+        total: u64,
+    },
+    /// Layer passed sha256 verification and was committed to the store.
+    LayerVerified {
+        /// Layer digest.
+        digest: String,
+    },
+    /// Layers are being unpacked into the rootfs.
+    Extracting,
+    /// Pull finished.
+    Done,
+}
+
+impl fmt::Display for PullProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resolving {
+                reference,
+                endpoint,
+            } => write!(f, "Pulling {reference} from {endpoint}..."),
+            Self::EndpointFailed { endpoint, error } => write!(f, "{endpoint}: {error}"),
+            Self::LayerCached { digest, .. } => write!(f, "Layer {} cached", short(digest)),
+            Self::LayerStarted {
+                digest,
+                offset: 0,
+                total,
+            } => write!(f, "Downloading layer {} ({total} bytes)...", short(digest)),
+            Self::LayerStarted {
+                digest,
+                offset,
+                total,
+            } => write!(
+                f,
+                "Resuming layer {} at {offset}/{total} bytes...",
+                short(digest)
+            ),
+            Self::LayerProgress {
+                digest,
+                downloaded,
+                total,
+            } => write!(f, "Layer {}: {downloaded}/{total} bytes", short(digest)),
+            Self::LayerVerified { digest } => write!(f, "Layer {} verified", short(digest)),
+            Self::Extracting => f.write_str("Extracting rootfs..."),
+            Self::Done => f.write_str("Done."),
+        }
+    }
+}
+
+/// First 12 hex characters of a digest, as shown by `docker pull`.
+fn short(digest: &str) -> &str {
+    let hex = digest.split_once(':').map_or(digest, |(_, h)| h);
+    hex.get(..12).unwrap_or(hex)
+}
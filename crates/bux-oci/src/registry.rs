@@ -0,0 +1,187 @@
+//! Registry endpoint selection: mirror tables and per-endpoint HTTP clients.
+
+use std::collections::HashMap;
+
+use oci_client::Reference;
+use oci_client::client::{Certificate, CertificateEncoding, ClientConfig, ClientProtocol};
+use oci_client::manifest::OciManifest;
+use oci_client::secrets::RegistryAuth;
+
+use crate::auth::normalize_registry;
+use crate::config::RegistryEndpoint;
+use crate::error::Result;
+
+/// Resolves which endpoints serve a registry and which client talks to each.
+pub(crate) struct Registries {
+    /// Client for endpoints with default transport (HTTPS, system roots).
+    default: oci_client::Client,
+    /// Dedicated clients for endpoints with custom transport settings.
+    custom: HashMap<RegistryEndpoint, oci_client::Client>,
+    /// Ordered endpoints keyed by normalized registry host.
+    mirrors: HashMap<String, Vec<RegistryEndpoint>>,
+}
+
+impl Registries {
+    /// Builds clients for every configured endpoint.
+    ///
+    /// Fails fast on unreadable CA bundles so misconfiguration surfaces at
+    /// open time rather than on the first pull.
+    pub(crate) fn new(mirrors: HashMap<String, Vec<RegistryEndpoint>>) -> Result<Self> {
+        let mut custom = HashMap::new();
+        let mut table: HashMap<String, Vec<RegistryEndpoint>> = HashMap::new();
+        for (registry, endpoints) in mirrors {
+            let pending: Vec<_> = endpoints
+                .iter()
+                .filter(|e| e.has_custom_transport() && !custom.contains_key(*e))
+                .collect();
+            for endpoint in pending {
+                custom.insert(endpoint.clone(), build_client(endpoint)?);
+            }
+            table
+                .entry(normalize_registry(&registry))
+                .or_default()
+                .extend(endpoints);
+        }
+        Ok(Self {
+            default: build_client(&RegistryEndpoint::new(""))?,
+            custom,
+            mirrors: table,
+        })
+    }
+
+    /// Endpoints to try for `registry`, in order.
+    ///
+    /// The upstream registry is appended unless the table already lists it.
+    pub(crate) fn endpoints_for(&self, registry: &str) -> Vec<RegistryEndpoint> {
+        let host = normalize_registry(registry);
+        let mut endpoints = self.mirrors.get(&host).cloned().unwrap_or_default();
+        if !endpoints
+            .iter()
+            .any(|e| normalize_registry(&e.host) == host)
+        {
+            endpoints.push(RegistryEndpoint::new(registry));
+        }
+        endpoints
+    }
+
+    /// Client configured for `endpoint`'s transport.
+    pub(crate) fn client_for(&self, endpoint: &RegistryEndpoint) -> &oci_client::Client {
+        self.custom.get(endpoint).unwrap_or(&self.default)
+    }
+}
+
+/// Rewrites `reference` so requests go to `endpoint`.
+///
+/// Mirrors receive the original registry in the `ns` query parameter, which
+/// pull-through caches use to select the upstream.
+pub(crate) fn reference_for(reference: &Reference, endpoint: &RegistryEndpoint) -> Reference {
+    let mut target = reference.clone();
+    if normalize_registry(&endpoint.host) != normalize_registry(reference.registry()) {
+        target.set_mirror_registry(endpoint.host.clone());
+    }
+    target
+}
+
+/// Digest of the image index `target` names, if it is one and lists
+/// `platform_digest`.
+///
+/// Best effort: lookup failures yield `None`, leaving signature checks to
+/// the platform manifest digest.
+pub(crate) async fn index_digest(
+    client: &oci_client::Client,
+    target: &Reference,
+    auth: &RegistryAuth,
+    platform_digest: &str,
+) -> Option<String> {
+    if target.digest() == Some(platform_digest) {
+        return None;
+    }
+    match client.pull_manifest(target, auth).await {
+        Ok((OciManifest::ImageIndex(index), digest))
+            if index.manifests.iter().any(|m| m.digest == platform_digest) =>
+        {
+            Some(digest)
+        }
+        _ => None,
+    }
+}
+
+/// Creates an OCI client for one endpoint's transport settings.
+fn build_client(endpoint: &RegistryEndpoint) -> Result<oci_client::Client> {
+    let extra_root_certificates = match &endpoint.ca_bundle {
+        Some(path) => vec![Certificate {
+            encoding: CertificateEncoding::Pem,
+            data: std::fs::read(path)?,
+        }],
+        None => Vec::new(),
+    };
+    let protocol = if endpoint.plain_http {
+        ClientProtocol::Http
+    } else {
+        ClientProtocol::Https
+    };
+    Ok(oci_client::Client::try_from(ClientConfig {
+        protocol,
+        accept_invalid_certificates: endpoint.insecure,
+        extra_root_certificates,
+        platform_resolver: Some(Box::new(crate::linux_platform_resolver)),
+        ..Default::default()
+    })?)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upstream_appended_after_mirrors() {
+        let mirror = RegistryEndpoint::new("mirror.internal");
+        let regs = Registries::new(HashMap::from([(
+            "https://index.docker.io/v1/".to_owned(),
+            vec![mirror.clone()],
+        )]))
+        .unwrap();
+        assert_eq!(
+            regs.endpoints_for("docker.io"),
+            vec![mirror, RegistryEndpoint::new("docker.io")]
+        );
+        assert_eq!(
+            regs.endpoints_for("ghcr.io"),
+            vec![RegistryEndpoint::new("ghcr.io")]
+        );
+    }
+
+    #[test]
+    fn listed_upstream_keeps_its_position_and_transport() {
+        let local = RegistryEndpoint::new("localhost:5000").plain_http();
+        let regs = Registries::new(HashMap::from([(
+            "localhost:5000".to_owned(),
+            vec![local.clone()],
+        )]))
+        .unwrap();
+        assert_eq!(regs.endpoints_for("localhost:5000"), vec![local.clone()]);
+        assert!(!std::ptr::eq(
+            regs.client_for(&local),
+            std::ptr::from_ref(&regs.default)
+        ));
+    }
+
+    #[test]
+    fn missing_ca_bundle_fails_open() {
+        let endpoint = RegistryEndpoint::new("mirror.internal").ca_bundle("/nonexistent/ca.pem");
+        assert!(
+            Registries::new(HashMap::from([("docker.io".to_owned(), vec![endpoint])])).is_err()
+        );
+    }
+
+    #[test]
+    fn mirror_reference_keeps_original_registry() {
+        let reference: Reference = "alpine:3".parse().unwrap();
+        let mirrored = reference_for(&reference, &RegistryEndpoint::new("mirror.internal"));
+        assert_eq!(mirrored.resolve_registry(), "mirror.internal");
+        assert_eq!(mirrored.registry(), "docker.io");
+        let direct = reference_for(&reference, &RegistryEndpoint::new("docker.io"));
+        assert_eq!(direct.resolve_registry(), "index.docker.io");
+    }
+}
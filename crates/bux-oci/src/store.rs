@@ -14,7 +14,7 @@
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, TransactionBehavior, params};
 use sha2::{Digest, Sha256};
 
 /// Metadata for a locally stored image.
@@ -29,6 +29,12 @@ pub struct ImageMeta {
     pub size: u64,
     /// ISO 8601 timestamp when the image was cached.
     pub created_at: String,
+    /// Registry endpoint (upstream or mirror host) that served the image.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// ISO 8601 timestamp of the last pull or cache hit via `ensure`.
+    #[serde(default)]
+    pub last_used: Option<String>,
 }
 
 /// Content-addressed OCI image store with `SQLite` indexing.
@@ -51,10 +57,13 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     }
 }
 
-// SQL schema — single migration for now, extensible via version table.
+// SQL schema — base tables plus a single-row version table for [`MIGRATIONS`].
 const SCHEMA: &str = "\
-    CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL);
-    INSERT OR IGNORE INTO schema_version VALUES (1);
+    CREATE TABLE IF NOT EXISTS schema_version (
+        id      INTEGER PRIMARY KEY CHECK (id = 0),
+        version INTEGER NOT NULL
+    );
+    INSERT OR IGNORE INTO schema_version (id, version) VALUES (0, 1);
     CREATE TABLE IF NOT EXISTS images (
         reference TEXT PRIMARY KEY,
         digest    TEXT NOT NULL,
@@ -76,6 +85,67 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     );
 ";
 
+/// Incremental migrations applied after [`SCHEMA`]; entry `i` upgrades the
+/// store to schema version `i + 2`.
+const MIGRATIONS: &[&str] = &[
+    // v2: record which registry endpoint served each image.
+    "ALTER TABLE images ADD COLUMN endpoint TEXT;",
+    // v3: config blob digest (for orphan detection) and LRU timestamp.
+    "ALTER TABLE images ADD COLUMN config_digest TEXT;
+     ALTER TABLE images ADD COLUMN last_used TEXT;",
+    // v4: image index digest of multi-arch pulls (signature lookup).
+    "ALTER TABLE images ADD COLUMN index_digest TEXT;",
+];
+
+/// Collapses a `schema_version` table from before it had a single-row
+/// constraint (where every open appended a row) into the current form.
+const SINGLE_ROW_VERSION: &str = "\
+    CREATE TABLE schema_version_new (
+        id      INTEGER PRIMARY KEY CHECK (id = 0),
+        version INTEGER NOT NULL
+    );
+    INSERT INTO schema_version_new SELECT 0, COALESCE(MAX(version), 1) FROM schema_version;
+    DROP TABLE schema_version;
+    ALTER TABLE schema_version_new RENAME TO schema_version;
+";
+
+/// Creates the base schema, upgrading an old `schema_version` table.
+///
+/// Runs as one `BEGIN IMMEDIATE` transaction so that concurrent opens of
+/// a fresh or old store serialize instead of racing.
+fn init_schema(db: &mut Connection) -> rusqlite::Result<()> {
+    let tx = db.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let columns: i64 = tx.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+    if columns == 1 {
+        tx.execute_batch(SINGLE_ROW_VERSION)?;
+    }
+    tx.execute_batch(SCHEMA)?;
+    tx.commit()
+}
+
+/// Applies pending [`MIGRATIONS`] and bumps `schema_version`.
+///
+/// Each step runs in its own `BEGIN IMMEDIATE` transaction and re-reads
+/// the version once it holds the write lock, so a step another process
+/// applied in the meantime is skipped rather than run twice.
+fn migrate(db: &mut Connection) -> rusqlite::Result<()> {
+    for (version, sql) in (2_i64..).zip(MIGRATIONS) {
+        let tx = db.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let current: i64 =
+            tx.query_row("SELECT version FROM schema_version", [], |row| row.get(0))?;
+        if version > current {
+            tx.execute_batch(sql)?;
+            tx.execute("UPDATE schema_version SET version = ?1", params![version])?;
+        }
+        tx.commit()?;
+    }
+    Ok(())
+}
+
 impl Store {
     /// Opens (or creates) the store at the given root directory.
     pub(crate) fn open(root: &Path) -> crate::Result<Self> {
@@ -84,9 +154,10 @@ pub(crate) fn open(root: &Path) -> crate::Result<Self> {
         fs::create_dir_all(root.join("rootfs"))?;
 
         let db_path = root.join("images.db");
-        let db = Connection::open(&db_path)?;
+        let mut db = Connection::open(&db_path)?;
         db.execute_batch("PRAGMA journal_mode=WAL; PRAGMA foreign_keys=ON;")?;
-        db.execute_batch(SCHEMA)?;
+        init_schema(&mut db)?;
+        migrate(&mut db)?;
 
         Ok(Self {
             root: root.to_path_buf(),
@@ -100,10 +171,15 @@ pub(crate) fn open(root: &Path) -> crate::Result<Self> {
     ///
     /// Panics if the mutex is poisoned (prior panic during DB operation).
     #[allow(clippy::expect_used, reason = "poisoned mutex is unrecoverable")]
-    fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
+    pub(crate) fn lock(&self) -> std::sync::MutexGuard<'_, Connection> {
         self.db.lock().expect("Store db mutex poisoned")
     }
 
+    /// Root directory of the store.
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
     /// Returns the path to a layer tarball on disk.
     pub(crate) fn layer_path(&self, digest: &str) -> PathBuf {
         let filename = digest.replace(':', "-");
@@ -153,16 +229,19 @@ pub(crate) fn commit_layer(
     /// Verifies layer integrity by recomputing SHA256.
     ///
     /// Returns `Ok(true)` if the hash matches, `Ok(false)` if it doesn't,
-    /// and `Err` on I/O failure. Not used internally — `oci-client` verifies
-    /// digests during download. Exposed for external callers.
-    #[allow(
-        dead_code,
-        reason = "exposed for external callers, not used internally"
-    )]
+    /// and `Err` on I/O failure. The file is streamed through the hasher.
     pub(crate) fn verify_layer(&self, digest: &str) -> crate::Result<bool> {
-        let path = self.layer_path(digest);
-        let data = fs::read(&path)?;
-        let hash = Sha256::digest(&data);
+        let mut file = fs::File::open(self.layer_path(digest))?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0_u8; 64 * 1024];
+        loop {
+            let n = io::Read::read(&mut file, &mut buf)?;
+            let Some(chunk) = buf.get(..n).filter(|c| !c.is_empty()) else {
+                break;
+            };
+            hasher.update(chunk);
+        }
+        let hash = hasher.finalize();
         let hex = hash.iter().fold(String::new(), |mut acc, b| {
             write!(acc, "{b:02x}").ok();
             acc
@@ -172,7 +251,7 @@ pub(crate) fn verify_layer(&self, digest: &str) -> crate::Result<bool> {
     }
 
     /// Path to a config blob on disk.
-    fn config_path(&self, digest: &str) -> PathBuf {
+    pub(crate) fn config_path(&self, digest: &str) -> PathBuf {
         let filename = digest.replace(':', "-");
         self.root.join("configs").join(format!("{filename}.json"))
     }
@@ -223,17 +302,23 @@ pub(crate) fn commit_rootfs(&self, manifest_digest: &str) -> crate::Result<()> {
     }
 
     /// Inserts or updates an image record and its layer associations.
+    ///
+    /// `index_digest` is the image index `digest` was resolved from, for
+    /// multi-arch images.
     #[allow(
         clippy::significant_drop_tightening,
         reason = "transaction requires conn to live"
     )]
+    #[allow(clippy::too_many_arguments, reason = "one argument per column")]
     pub(crate) fn upsert_image(
         &self,
         reference: &str,
         digest: &str,
+        index_digest: Option<&str>,
         size: u64,
         config_digest: &str,
         layer_digests: &[String],
+        endpoint: &str,
     ) -> crate::Result<()> {
         // Load config JSON from blob store for embedding in the DB (before locking).
         let config_json = fs::read_to_string(self.config_path(config_digest)).ok();
@@ -242,22 +327,32 @@ pub(crate) fn upsert_image(
         let tx = conn.unchecked_transaction()?;
 
         tx.execute(
-            "INSERT INTO images (reference, digest, size, config)
-             VALUES (?1, ?2, ?3, ?4)
+            "INSERT INTO images
+                (reference, digest, size, config, endpoint, config_digest, last_used,
+                 index_digest)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), ?7)
              ON CONFLICT(reference) DO UPDATE SET
                 digest = excluded.digest,
+                index_digest = excluded.index_digest,
                 size = excluded.size,
                 config = excluded.config,
-                created = datetime('now')",
+                endpoint = excluded.endpoint,
+                config_digest = excluded.config_digest,
+                created = datetime('now'),
+                last_used = datetime('now')",
             params![
                 reference,
                 digest,
                 i64::try_from(size).unwrap_or(i64::MAX),
-                config_json
+                config_json,
+                endpoint,
+                config_digest,
+                index_digest
             ],
         )?;
 
         // Clear old layer associations, then insert new ones.
+        let previous = layers_of(&tx, reference)?;
         tx.execute(
             "DELETE FROM image_layers WHERE image_ref = ?1",
             params![reference],
@@ -274,22 +369,39 @@ pub(crate) fn upsert_image(
                 ],
             )?;
         }
+        for layer_digest in previous.iter().chain(layer_digests) {
+            refresh_ref_count(&tx, layer_digest)?;
+        }
 
         tx.commit()?;
         Ok(())
     }
 
+    /// Records a cache hit so LRU pruning keeps recently used images.
+    pub(crate) fn touch_image(&self, reference: &str) -> crate::Result<()> {
+        self.lock().execute(
+            "UPDATE images SET last_used = datetime('now') WHERE reference = ?1",
+            params![reference],
+        )?;
+        Ok(())
+    }
+
     /// Lists all stored images.
     pub(crate) fn list_images(&self) -> crate::Result<Vec<ImageMeta>> {
         let conn = self.lock();
         Ok(conn
-            .prepare("SELECT reference, digest, size, created FROM images ORDER BY created DESC")?
+            .prepare(
+                "SELECT reference, digest, size, created, endpoint, last_used FROM images
+                 ORDER BY created DESC",
+            )?
             .query_map([], |row| {
                 Ok(ImageMeta {
                     reference: row.get(0)?,
                     digest: row.get(1)?,
                     size: u64::try_from(row.get::<_, i64>(2)?).unwrap_or(0),
                     created_at: row.get::<_, String>(3).unwrap_or_default(),
+                    endpoint: row.get(4)?,
+                    last_used: row.get(5)?,
                 })
             })?
             .collect::<rusqlite::Result<Vec<_>>>()?)
@@ -323,36 +435,47 @@ pub(crate) fn get_digest(&self, reference: &str) -> crate::Result<Option<String>
         }
     }
 
+    /// Looks up the image index digest recorded for a reference, if any.
+    pub(crate) fn get_index_digest(&self, reference: &str) -> crate::Result<Option<String>> {
+        let result: rusqlite::Result<Option<String>> = self.lock().query_row(
+            "SELECT index_digest FROM images WHERE reference = ?1",
+            params![reference],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(d) => Ok(d),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Removes an image and its rootfs. Layer blobs are ref-counted and only
-    /// deleted when no other image references them.
+    /// deleted when no other image references them; the rootfs is kept while
+    /// another reference shares its manifest digest.
     pub(crate) fn remove_image(&self, reference: &str) -> crate::Result<()> {
         // Look up digest for rootfs cleanup.
         let digest = self.get_digest(reference)?;
 
         let conn = self.lock();
-
-        // Decrement layer ref counts and collect orphans.
-        let layer_digests: Vec<String> = {
-            let mut stmt =
-                conn.prepare("SELECT layer_digest FROM image_layers WHERE image_ref = ?1")?;
-            let rows = stmt.query_map(params![reference], |row| row.get(0))?;
-            rows.filter_map(Result::ok).collect()
-        };
-
+        let layer_digests = layers_of(&conn, reference)?;
         let tx = conn.unchecked_transaction()?;
 
-        for ld in &layer_digests {
-            tx.execute(
-                "UPDATE layers SET ref_count = ref_count - 1 WHERE digest = ?1",
-                params![ld],
-            )?;
-        }
-
-        // Delete the image (CASCADE deletes image_layers).
+        // Delete the image (CASCADE deletes image_layers), then recount.
         tx.execute(
             "DELETE FROM images WHERE reference = ?1",
             params![reference],
         )?;
+        for ld in &layer_digests {
+            refresh_ref_count(&tx, ld)?;
+        }
+        let rootfs_shared = match &digest {
+            Some(d) => tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM images WHERE digest = ?1)",
+                params![d],
+                |row| row.get::<_, bool>(0),
+            )?,
+            None => false,
+        };
 
         // Remove orphaned layer blobs (ref_count <= 0).
         let orphans: Vec<String> = {
@@ -369,7 +492,9 @@ pub(crate) fn remove_image(&self, reference: &str) -> crate::Result<()> {
 
         // Remove rootfs directory.
         drop(conn);
-        if let Some(ref d) = digest {
+        if let Some(ref d) = digest
+            && !rootfs_shared
+        {
             let rootfs = self.rootfs_path(d);
             if rootfs.exists() {
                 fs::remove_dir_all(&rootfs)?;
@@ -380,6 +505,24 @@ pub(crate) fn remove_image(&self, reference: &str) -> crate::Result<()> {
     }
 }
 
+/// Layer digests associated with `reference`.
+fn layers_of(conn: &Connection, reference: &str) -> rusqlite::Result<Vec<String>> {
+    conn.prepare("SELECT layer_digest FROM image_layers WHERE image_ref = ?1")?
+        .query_map(params![reference], |row| row.get(0))?
+        .collect()
+}
+
+/// Sets a layer's `ref_count` to the number of images that use it.
+pub(crate) fn refresh_ref_count(conn: &Connection, digest: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE layers SET ref_count =
+            (SELECT COUNT(*) FROM image_layers WHERE layer_digest = ?1)
+         WHERE digest = ?1",
+        params![digest],
+    )?;
+    Ok(())
+}
+
 /// Writes data to a file atomically (write to .tmp, then rename).
 fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
     let tmp = path.with_extension("tmp");
@@ -389,3 +532,69 @@ fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
     fs::rename(&tmp, path)?;
     Ok(())
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    /// Schema version after every migration.
+    fn latest() -> i64 {
+        i64::try_from(MIGRATIONS.len()).unwrap() + 1
+    }
+
+    /// Current schema version and row count of `schema_version`.
+    fn version_rows(store: &Store) -> (i64, i64) {
+        store
+            .lock()
+            .query_row(
+                "SELECT MAX(version), COUNT(*) FROM schema_version",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn reopening_keeps_one_version_row() {
+        let dir = tempfile::tempdir().unwrap();
+        drop(Store::open(dir.path()).unwrap());
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(version_rows(&store), (latest(), 1));
+        assert!(
+            store
+                .lock()
+                .execute("INSERT INTO schema_version (id, version) VALUES (1, 1)", [])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn old_version_table_is_collapsed() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            // A v2 store from before the single-row constraint, opened
+            // twice since its last migration.
+            let db = Connection::open(dir.path().join("images.db")).unwrap();
+            db.execute_batch(
+                "CREATE TABLE schema_version (version INTEGER NOT NULL);
+                 INSERT INTO schema_version VALUES (2), (1), (1);
+                 CREATE TABLE images (
+                     reference TEXT PRIMARY KEY,
+                     digest    TEXT NOT NULL,
+                     size      INTEGER NOT NULL DEFAULT 0,
+                     config    TEXT,
+                     created   TEXT NOT NULL DEFAULT (datetime('now')),
+                     endpoint  TEXT
+                 );",
+            )
+            .unwrap();
+        }
+        let store = Store::open(dir.path()).unwrap();
+        assert_eq!(version_rows(&store), (latest(), 1));
+        store
+            .lock()
+            .prepare("SELECT config_digest, last_used, index_digest FROM images")
+            .unwrap();
+    }
+}
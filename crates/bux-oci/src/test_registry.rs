@@ -0,0 +1,405 @@
+//! Minimal in-process OCI distribution server for tests.
+//!
+//! Serves `/v2/`, manifests by tag or digest, and blobs (with `Range`
+//! support) over plain HTTP on `127.0.0.1`. Optionally demands HTTP Basic
+//! credentials, and records every request line (plus any `Range` header) for
+//! assertions.
+
+#![allow(
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::significant_drop_tightening,
+    clippy::must_use_candidate,
+    clippy::missing_panics_doc,
+    missing_debug_implementations,
+    reason = "test support"
+)]
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::sync::{Arc, Mutex};
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Layer media type served for every layer.
+const LAYER_MEDIA_TYPE: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+#[derive(Default)]
+struct State {
+    blobs: HashMap<String, Vec<u8>>,
+    manifests: HashMap<String, (String, Vec<u8>)>,
+    /// Referrer descriptors keyed by subject digest.
+    referrers: HashMap<String, Vec<serde_json::Value>>,
+    requests: Vec<String>,
+    basic_auth: Option<String>,
+}
+
+/// Handle to a running stand-in registry.
+pub struct TestRegistry {
+    host: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl TestRegistry {
+    /// Binds an ephemeral port and starts serving on the current runtime.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let state = Arc::new(Mutex::new(State::default()));
+        let accept_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&accept_state)));
+            }
+        });
+        Self { host, state }
+    }
+
+    /// `127.0.0.1:<port>`.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Requires `Authorization: Basic` with these credentials on every request.
+    pub fn require_basic(&self, user: &str, password: &str) {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{user}:{password}"));
+        self.state.lock().unwrap().basic_auth = Some(format!("Basic {token}"));
+    }
+
+    /// Request lines (`METHOD /path?query`, suffixed with ` range=<value>` when
+    /// a `Range` header was sent) received so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Publishes a single-layer image under `tag`; returns the manifest digest.
+    pub fn add_image(&self, tag: &str, files: &[(&str, &[u8])]) -> String {
+        self.add_layered_image(tag, &[files]).0
+    }
+
+    /// Publishes an image with one layer per entry of `layers`; returns the
+    /// manifest digest and the compressed layer blobs in manifest order.
+    pub fn add_layered_image(
+        &self,
+        tag: &str,
+        layers: &[&[(&str, &[u8])]],
+    ) -> (String, Vec<Vec<u8>>) {
+        let blobs: Vec<Vec<u8>> = layers.iter().map(|files| gzip_tar(files)).collect();
+        let descriptors: Vec<_> = blobs
+            .iter()
+            .map(|blob| {
+                serde_json::json!({
+                    "mediaType": LAYER_MEDIA_TYPE,
+                    "digest": sha256_digest(blob),
+                    "size": blob.len(),
+                })
+            })
+            .collect();
+        let config = serde_json::to_vec(&serde_json::json!({
+            "architecture": "amd64",
+            "os": "linux",
+            "config": { "Cmd": ["/bin/sh"] },
+            "rootfs": { "type": "layers", "diff_ids": [] },
+        }))
+        .unwrap();
+        let config_digest = sha256_digest(&config);
+        let manifest = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "digest": config_digest,
+                "size": config.len(),
+            },
+            "layers": descriptors,
+        }))
+        .unwrap();
+        let manifest_digest = sha256_digest(&manifest);
+
+        let mut state = self.state.lock().unwrap();
+        for blob in &blobs {
+            state.blobs.insert(sha256_digest(blob), blob.clone());
+        }
+        state.blobs.insert(config_digest, config);
+        state
+            .manifests
+            .insert(tag.to_owned(), (manifest_digest.clone(), manifest.clone()));
+        state
+            .manifests
+            .insert(manifest_digest.clone(), (manifest_digest.clone(), manifest));
+        (manifest_digest, blobs)
+    }
+
+    /// Publishes a multi-arch index under `tag` whose only entry is the
+    /// `linux/amd64` manifest `platform_digest`; returns the index digest.
+    pub fn add_index(&self, tag: &str, platform_digest: &str) -> String {
+        let mut state = self.state.lock().unwrap();
+        let size = state.manifests[platform_digest].1.len();
+        let index = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": [{
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": platform_digest,
+                "size": size,
+                "platform": { "os": "linux", "architecture": "amd64" },
+            }],
+        }))
+        .unwrap();
+        let digest = sha256_digest(&index);
+        state
+            .manifests
+            .insert(tag.to_owned(), (digest.clone(), index.clone()));
+        state
+            .manifests
+            .insert(digest.clone(), (digest.clone(), index));
+        digest
+    }
+
+    /// Publishes a cosign signature of `payload` for `subject` under the
+    /// `sha256-<hex>.sig` tag (or as an OCI referrer); returns the signature
+    /// manifest digest.
+    pub fn add_signature(
+        &self,
+        subject: &str,
+        payload: &[u8],
+        signature: &[u8],
+        as_referrer: bool,
+    ) -> String {
+        const ARTIFACT_TYPE: &str = "application/vnd.dev.cosign.artifact.sig.v1+json";
+        let config = b"{}".to_vec();
+        let mut manifest = serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.manifest.v1+json",
+            "config": {
+                "mediaType": "application/vnd.oci.image.config.v1+json",
+                "digest": sha256_digest(&config),
+                "size": config.len(),
+            },
+            "layers": [{
+                "mediaType": "application/vnd.dev.cosign.simplesigning.v1+json",
+                "digest": sha256_digest(payload),
+                "size": payload.len(),
+                "annotations": {
+                    "dev.cosignproject.cosign/signature":
+                        base64::engine::general_purpose::STANDARD.encode(signature),
+                },
+            }],
+        });
+        if as_referrer {
+            manifest["artifactType"] = ARTIFACT_TYPE.into();
+            manifest["subject"] = serde_json::json!({
+                "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                "digest": subject,
+                "size": 0,
+            });
+        }
+        let manifest = serde_json::to_vec(&manifest).unwrap();
+        let digest = sha256_digest(&manifest);
+
+        let mut state = self.state.lock().unwrap();
+        state.blobs.insert(sha256_digest(&config), config);
+        state.blobs.insert(sha256_digest(payload), payload.to_vec());
+        if as_referrer {
+            state
+                .referrers
+                .entry(subject.to_owned())
+                .or_default()
+                .push(serde_json::json!({
+                    "mediaType": "application/vnd.oci.image.manifest.v1+json",
+                    "digest": digest,
+                    "size": manifest.len(),
+                    "artifactType": ARTIFACT_TYPE,
+                }));
+        } else {
+            let tag = format!("{}.sig", subject.replace(':', "-"));
+            state
+                .manifests
+                .insert(tag, (digest.clone(), manifest.clone()));
+        }
+        state
+            .manifests
+            .insert(digest.clone(), (digest.clone(), manifest));
+        digest
+    }
+
+    /// Replaces the bytes served for `digest`, e.g. to simulate corruption.
+    pub fn set_blob(&self, digest: &str, data: Vec<u8>) {
+        self.state
+            .lock()
+            .unwrap()
+            .blobs
+            .insert(digest.to_owned(), data);
+    }
+}
+
+/// `sha256:<hex>` of `data`.
+pub fn sha256_digest(data: &[u8]) -> String {
+    let mut hex = String::from("sha256:");
+    for byte in Sha256::digest(data) {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    hex
+}
+
+/// Builds a gzip-compressed tarball of regular files.
+fn gzip_tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::fast(),
+    ));
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, *data).unwrap();
+    }
+    let mut gz = builder.into_inner().unwrap();
+    gz.flush().unwrap();
+    gz.finish().unwrap()
+}
+
+/// Serves HTTP/1.1 requests on one keep-alive connection.
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let mut headers = HashMap::new();
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let header = header.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((k, v)) = header.split_once(':') {
+                headers.insert(k.trim().to_ascii_lowercase(), v.trim().to_owned());
+            }
+        }
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let target = parts.next().unwrap_or_default().to_owned();
+        let response = respond(&state, &method, &target, &headers);
+        if write.write_all(&response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Routes one request.
+fn respond(
+    state: &Mutex<State>,
+    method: &str,
+    target: &str,
+    headers: &HashMap<String, String>,
+) -> Vec<u8> {
+    let mut state = state.lock().unwrap();
+    let mut request = format!("{method} {target}");
+    if let Some(range) = headers.get("range") {
+        write!(request, " range={range}").unwrap();
+    }
+    state.requests.push(request);
+    let path = target.split('?').next().unwrap_or(target);
+
+    if let Some(expected) = &state.basic_auth
+        && headers.get("authorization") != Some(expected)
+    {
+        return response(
+            "401 Unauthorized",
+            &[("WWW-Authenticate", "Basic realm=\"test\"".to_owned())],
+            b"{}",
+        );
+    }
+    if path == "/v2/" {
+        return response("200 OK", &[], b"{}");
+    }
+    if let Some((_, subject)) = path.rsplit_once("/referrers/") {
+        let Some(manifests) = state.referrers.get(subject) else {
+            return response("404 Not Found", &[], b"{}");
+        };
+        let index = serde_json::to_vec(&serde_json::json!({
+            "schemaVersion": 2,
+            "mediaType": "application/vnd.oci.image.index.v1+json",
+            "manifests": manifests,
+        }))
+        .unwrap();
+        return response(
+            "200 OK",
+            &[(
+                "Content-Type",
+                "application/vnd.oci.image.index.v1+json".to_owned(),
+            )],
+            &index,
+        );
+    }
+    if let Some((_, key)) = path.rsplit_once("/manifests/") {
+        return match state.manifests.get(key) {
+            Some((digest, body)) => response(
+                "200 OK",
+                &[
+                    ("Content-Type", media_type(body)),
+                    ("Docker-Content-Digest", digest.clone()),
+                ],
+                body,
+            ),
+            None => response("404 Not Found", &[], b"{}"),
+        };
+    }
+    if let Some((_, digest)) = path.rsplit_once("/blobs/") {
+        let Some(blob) = state.blobs.get(digest) else {
+            return response("404 Not Found", &[], b"{}");
+        };
+        let offset = headers
+            .get("range")
+            .and_then(|r| r.strip_prefix("bytes="))
+            .and_then(|r| r.split('-').next())
+            .and_then(|o| o.parse::<usize>().ok())
+            .filter(|o| *o < blob.len());
+        return offset.map_or_else(
+            || response("200 OK", &[], blob),
+            |offset| {
+                response(
+                    "206 Partial Content",
+                    &[(
+                        "Content-Range",
+                        format!("bytes {offset}-{}/{}", blob.len() - 1, blob.len()),
+                    )],
+                    &blob[offset..],
+                )
+            },
+        );
+    }
+    response("404 Not Found", &[], b"{}")
+}
+
+/// `mediaType` of a manifest body.
+fn media_type(body: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|m| m["mediaType"].as_str().map(str::to_owned))
+        .unwrap_or_else(|| "application/vnd.oci.image.manifest.v1+json".to_owned())
+}
+
+/// Serializes an HTTP/1.1 response with `Content-Length`.
+fn response(status: &str, headers: &[(&str, String)], body: &[u8]) -> Vec<u8> {
+    let mut out = format!("HTTP/1.1 {status}\r\nContent-Length: {}\r\n", body.len());
+    for (k, v) in headers {
+        write!(out, "{k}: {v}\r\n").unwrap();
+    }
+    out.push_str("\r\n");
+    let mut bytes = out.into_bytes();
+    bytes.extend_from_slice(body);
+    bytes
+}
@@ -0,0 +1,780 @@
+//! Image admission policy: allow-lists and cosign-style signatures.
+//!
+//! A [`VerifyPolicy`] maps repository scopes to a [`Requirement`]; the most
+//! specific scope wins, and unmatched repositories fall back to the policy
+//! default. Signatures are looked up the way cosign stores them: under the
+//! `sha256-<hex>.sig` tag next to the image, and as OCI 1.1 referrers of the
+//! manifest digest. Each signature layer is a "simple signing" JSON payload
+//! whose `critical.image.docker-manifest-digest` must name the image, signed
+//! with ECDSA P-256/SHA-256 or Ed25519.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use base64::Engine;
+use oci_client::Reference;
+use oci_client::manifest::{OciDescriptor, OciManifest};
+use oci_client::secrets::RegistryAuth;
+use ring::signature::{self, UnparsedPublicKey};
+
+use crate::auth::normalize_registry;
+use crate::error::{OciError, Result};
+
+/// Layer annotation carrying the base64 signature over the layer blob.
+const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+/// Layer annotation carrying a Fulcio certificate (keyless signing).
+const CERTIFICATE_ANNOTATION: &str = "dev.sigstore.cosign/certificate";
+/// Artifact type of cosign signatures stored as OCI referrers.
+pub(crate) const COSIGN_ARTIFACT_TYPE: &str = "application/vnd.dev.cosign.artifact.sig.v1+json";
+/// Upper bound on a signature payload blob.
+const MAX_PAYLOAD_BYTES: i64 = 1 << 20;
+
+/// DER prefix of a P-256 `SubjectPublicKeyInfo`, followed by the 65-byte point.
+const P256_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+];
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, followed by the 32-byte key.
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Public key algorithms accepted for signatures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyAlgorithm {
+    /// ECDSA over P-256 with SHA-256, ASN.1 DER signatures (cosign default).
+    EcdsaP256,
+    /// Ed25519.
+    Ed25519,
+}
+
+/// A public key trusted to sign images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedKey {
+    /// Label recorded in [`VerifyMethod::Signature`].
+    name: String,
+    /// Signature scheme.
+    algorithm: KeyAlgorithm,
+    /// Raw public key bytes (SEC1 point or Ed25519 key).
+    public_key: Vec<u8>,
+}
+
+impl TrustedKey {
+    /// Parses a PEM `PUBLIC KEY` (as written by `cosign generate-key-pair`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OciError::InvalidKey`] for malformed PEM or key types other
+    /// than ECDSA P-256 and Ed25519.
+    pub fn from_pem(name: impl Into<String>, pem: &str) -> Result<Self> {
+        let name = name.into();
+        let invalid = |why: &str| OciError::InvalidKey(format!("{name}: {why}"));
+        let body: String = pem
+            .lines()
+            .map(str::trim)
+            .skip_while(|l| *l != "-----BEGIN PUBLIC KEY-----")
+            .skip(1)
+            .take_while(|l| *l != "-----END PUBLIC KEY-----")
+            .collect();
+        if body.is_empty() {
+            return Err(invalid("no PUBLIC KEY block"));
+        }
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(body)
+            .map_err(|e| invalid(&e.to_string()))?;
+        let (algorithm, public_key) = if let Some(point) = der.strip_prefix(P256_SPKI_PREFIX)
+            && point.len() == 65
+        {
+            (KeyAlgorithm::EcdsaP256, point.to_vec())
+        } else if let Some(key) = der.strip_prefix(ED25519_SPKI_PREFIX)
+            && key.len() == 32
+        {
+            (KeyAlgorithm::Ed25519, key.to_vec())
+        } else {
+            return Err(invalid("only ECDSA P-256 and Ed25519 keys are supported"));
+        };
+        Ok(Self {
+            name,
+            algorithm,
+            public_key,
+        })
+    }
+
+    /// Reads [`TrustedKey::from_pem`] input from a file named after the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_pem_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        Self::from_pem(path.display().to_string(), &std::fs::read_to_string(path)?)
+    }
+
+    /// Label identifying this key in verification records.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks `sig` over `message`.
+    fn verifies(&self, message: &[u8], sig: &[u8]) -> bool {
+        let algorithm: &dyn signature::VerificationAlgorithm = match self.algorithm {
+            KeyAlgorithm::EcdsaP256 => &signature::ECDSA_P256_SHA256_ASN1,
+            KeyAlgorithm::Ed25519 => &signature::ED25519,
+        };
+        UnparsedPublicKey::new(algorithm, &self.public_key)
+            .verify(message, sig)
+            .is_ok()
+    }
+}
+
+/// A signature found for an image, before any key is checked.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CosignSignature {
+    /// Signed payload blob (simple signing JSON).
+    pub payload: Vec<u8>,
+    /// Decoded signature over `payload`.
+    pub signature: Vec<u8>,
+    /// PEM certificate for keyless signatures, if attached.
+    pub certificate: Option<String>,
+    /// Digest of the signature manifest this came from.
+    pub source: String,
+}
+
+impl CosignSignature {
+    /// Manifest digest named in the simple signing payload.
+    #[must_use]
+    pub fn signed_digest(&self) -> Option<String> {
+        let payload: serde_json::Value = serde_json::from_slice(&self.payload).ok()?;
+        payload
+            .pointer("/critical/image/docker-manifest-digest")?
+            .as_str()
+            .map(str::to_owned)
+    }
+}
+
+/// Input to an [`ImageVerifier`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub struct VerifyRequest<'a> {
+    /// Canonical image reference.
+    pub reference: &'a str,
+    /// Manifest digest that will boot.
+    pub digest: &'a str,
+    /// Signatures published for `digest` (possibly empty).
+    pub signatures: &'a [CosignSignature],
+}
+
+/// Custom admission check, e.g. keyless signatures or an attestation service.
+///
+/// Return a short description of why the image is trusted, or an error to
+/// reject it. Called on the async runtime; avoid long blocking work.
+pub trait ImageVerifier: Send + Sync {
+    /// Decides whether `request` may boot.
+    ///
+    /// # Errors
+    ///
+    /// Any error rejects the image; its message becomes the rejection reason.
+    fn verify(&self, request: &VerifyRequest<'_>) -> Result<String>;
+}
+
+impl<F> ImageVerifier for F
+where
+    F: Fn(&VerifyRequest<'_>) -> Result<String> + Send + Sync,
+{
+    fn verify(&self, request: &VerifyRequest<'_>) -> Result<String> {
+        self(request)
+    }
+}
+
+/// What an image in a scope must satisfy.
+#[non_exhaustive]
+#[derive(Clone, Default)]
+pub enum Requirement {
+    /// Admit without checks.
+    #[default]
+    Accept,
+    /// Refuse every image.
+    Reject,
+    /// Admit only these manifest digests.
+    Digests(Vec<String>),
+    /// Admit images with a valid signature from any of these keys.
+    Signed(Vec<TrustedKey>),
+    /// Delegate to a custom verifier.
+    Custom(Arc<dyn ImageVerifier>),
+}
+
+impl fmt::Debug for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => f.write_str("Accept"),
+            Self::Reject => f.write_str("Reject"),
+            Self::Digests(d) => f.debug_tuple("Digests").field(d).finish(),
+            Self::Signed(k) => f.debug_tuple("Signed").field(k).finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Admission policy checked before an image boots.
+///
+/// The default policy accepts everything, matching behavior without a policy.
+#[derive(Debug, Clone, Default)]
+pub struct VerifyPolicy {
+    /// Requirement for repositories no rule matches.
+    default: Requirement,
+    /// `(scope, requirement)` rules; the longest matching scope wins.
+    rules: Vec<(String, Requirement)>,
+    /// Manifest digests admitted regardless of scope.
+    digests: HashSet<String>,
+}
+
+impl VerifyPolicy {
+    /// Creates a policy that accepts every image.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the requirement for repositories no rule matches.
+    #[must_use]
+    pub fn default_requirement(mut self, requirement: Requirement) -> Self {
+        self.default = requirement;
+        self
+    }
+
+    /// Applies `requirement` to a scope: a registry (`ghcr.io`), a
+    /// namespace (`ghcr.io/acme`), or a repository (`docker.io/library/alpine`).
+    #[must_use]
+    pub fn require(mut self, scope: impl Into<String>, requirement: Requirement) -> Self {
+        self.rules
+            .push((normalize_scope(&scope.into()), requirement));
+        self
+    }
+
+    /// Admits every image under `scope` without further checks.
+    #[must_use]
+    pub fn allow_repository(self, scope: impl Into<String>) -> Self {
+        self.require(scope, Requirement::Accept)
+    }
+
+    /// Admits a manifest digest from any repository.
+    #[must_use]
+    pub fn allow_digest(mut self, digest: impl Into<String>) -> Self {
+        self.digests.insert(digest.into());
+        self
+    }
+
+    /// Returns `true` if evaluating `digest` in `repository` consults signatures.
+    pub(crate) fn needs_signatures(&self, repository: &str, digest: &str) -> bool {
+        !self.digests.contains(digest)
+            && matches!(
+                self.requirement_for(repository).1,
+                Requirement::Signed(_) | Requirement::Custom(_)
+            )
+    }
+
+    /// The most specific rule matching `repository` (`registry/path`).
+    pub(crate) fn requirement_for(&self, repository: &str) -> (Option<&str>, &Requirement) {
+        self.rules
+            .iter()
+            .filter(|(scope, _)| {
+                repository == scope
+                    || repository
+                        .strip_prefix(scope.as_str())
+                        .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|(scope, _)| scope.len())
+            .map_or((None, &self.default), |(scope, req)| {
+                (Some(scope.as_str()), req)
+            })
+    }
+
+    /// Evaluates the policy for `reference@digest` given its signatures.
+    pub(crate) fn evaluate(
+        &self,
+        reference: &Reference,
+        digest: &str,
+        signatures: &[CosignSignature],
+    ) -> Result<Verification> {
+        let ref_str = reference.to_string();
+        let repository = repository_of(reference);
+        let (scope, requirement) = self.requirement_for(&repository);
+        let admit = |matched: Option<&str>, method| {
+            Ok(Verification {
+                reference: ref_str.clone(),
+                digest: digest.to_owned(),
+                scope: matched.map(str::to_owned),
+                method,
+            })
+        };
+        let reject = |reason: String| {
+            Err(OciError::Rejected {
+                reference: ref_str.clone(),
+                reason,
+            })
+        };
+
+        if self.digests.contains(digest) {
+            return admit(None, VerifyMethod::DigestAllowed);
+        }
+        match requirement {
+            Requirement::Accept => admit(scope, VerifyMethod::Accepted),
+            Requirement::Reject => reject(scope.map_or_else(
+                || format!("{repository} is not in an allowed scope"),
+                |s| format!("scope {s} is rejected"),
+            )),
+            Requirement::Digests(allowed) if allowed.iter().any(|d| d == digest) => {
+                admit(scope, VerifyMethod::DigestAllowed)
+            }
+            Requirement::Digests(_) => reject(format!("digest {digest} is not allow-listed")),
+            Requirement::Signed(keys) => {
+                let valid = signatures
+                    .iter()
+                    .filter(|s| s.signed_digest().as_deref() == Some(digest))
+                    .find_map(|s| {
+                        keys.iter()
+                            .find(|k| k.verifies(&s.payload, &s.signature))
+                            .map(|k| (k, s))
+                    });
+                match valid {
+                    Some((key, sig)) => admit(
+                        scope,
+                        VerifyMethod::Signature {
+                            key: key.name.clone(),
+                            signature: sig.source.clone(),
+                        },
+                    ),
+                    None => reject(format!(
+                        "no valid signature from a trusted key ({} found)",
+                        signatures.len()
+                    )),
+                }
+            }
+            Requirement::Custom(verifier) => {
+                let request = VerifyRequest {
+                    reference: &ref_str,
+                    digest,
+                    signatures,
+                };
+                match verifier.verify(&request) {
+                    Ok(detail) => admit(scope, VerifyMethod::Custom { detail }),
+                    Err(e) => reject(e.to_string()),
+                }
+            }
+        }
+    }
+}
+
+/// How an admitted image satisfied the policy.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VerifyMethod {
+    /// The matching requirement was [`Requirement::Accept`].
+    Accepted,
+    /// The manifest digest is allow-listed.
+    DigestAllowed,
+    /// A signature verified against a trusted key.
+    Signature {
+        /// [`TrustedKey::name`] of the verifying key.
+        key: String,
+        /// Digest of the signature manifest.
+        signature: String,
+    },
+    /// A [`Requirement::Custom`] verifier admitted the image.
+    Custom {
+        /// Verifier-provided description.
+        detail: String,
+    },
+}
+
+/// Outcome of [`crate::Oci::verify`] for an admitted image.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Verification {
+    /// Canonical image reference.
+    pub reference: String,
+    /// Manifest digest that was checked.
+    pub digest: String,
+    /// Policy scope that matched (`None` = policy default or digest allow-list).
+    pub scope: Option<String>,
+    /// How the image satisfied the policy.
+    pub method: VerifyMethod,
+}
+
+/// `registry/repository` used for scope matching.
+pub(crate) fn repository_of(reference: &Reference) -> String {
+    format!(
+        "{}/{}",
+        normalize_registry(reference.registry()),
+        reference.repository()
+    )
+}
+
+/// Canonicalizes the registry part of a scope (`index.docker.io` → `docker.io`).
+fn normalize_scope(scope: &str) -> String {
+    let scope = scope.trim_end_matches('/');
+    match scope.split_once('/') {
+        Some((registry, path)) => format!("{}/{path}", normalize_registry(registry)),
+        None => normalize_registry(scope),
+    }
+}
+
+/// Fetches cosign signatures for `digest` from one endpoint.
+///
+/// `tag_ref` names the `sha256-<hex>.sig` tag and `digest_ref` the image
+/// manifest, both already rewritten for the endpoint. Lookup failures (no
+/// signature tag, no referrers API) yield no signatures rather than errors.
+pub(crate) async fn fetch_signatures(
+    client: &oci_client::Client,
+    auth: &RegistryAuth,
+    tag_ref: &Reference,
+    digest_ref: &Reference,
+) -> Vec<CosignSignature> {
+    let mut manifests = Vec::new();
+    if let Ok(found) = client.pull_manifest(tag_ref, auth).await {
+        manifests.push((tag_ref.clone(), found));
+    }
+    if let Ok(index) = client
+        .pull_referrers(digest_ref, Some(COSIGN_ARTIFACT_TYPE))
+        .await
+    {
+        for entry in index.manifests {
+            let target = digest_ref.clone_with_digest(entry.digest);
+            if let Ok(found) = client.pull_manifest(&target, auth).await {
+                manifests.push((target, found));
+            }
+        }
+    }
+
+    let mut signatures = Vec::new();
+    for (target, (manifest, source)) in manifests {
+        let OciManifest::Image(image) = manifest else {
+            continue;
+        };
+        for layer in &image.layers {
+            if let Some(sig) = read_signature(client, &target, layer, &source).await {
+                signatures.push(sig);
+            }
+        }
+    }
+    signatures
+}
+
+/// Downloads and decodes one signature layer; `None` if it is not one.
+async fn read_signature(
+    client: &oci_client::Client,
+    target: &Reference,
+    layer: &OciDescriptor,
+    source: &str,
+) -> Option<CosignSignature> {
+    let annotations = layer.annotations.as_ref()?;
+    let signature = base64::engine::general_purpose::STANDARD
+        .decode(annotations.get(SIGNATURE_ANNOTATION)?)
+        .ok()?;
+    if layer.size > MAX_PAYLOAD_BYTES {
+        return None;
+    }
+    let mut payload = Vec::new();
+    client.pull_blob(target, layer, &mut payload).await.ok()?;
+    Some(CosignSignature {
+        payload,
+        signature,
+        certificate: annotations.get(CERTIFICATE_ANNOTATION).cloned(),
+        source: source.to_owned(),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use std::collections::HashMap;
+
+    use ring::rand::SystemRandom;
+    use ring::signature::{ECDSA_P256_SHA256_ASN1_SIGNING, EcdsaKeyPair, Ed25519KeyPair, KeyPair};
+
+    use super::*;
+    use crate::{CredentialChain, Oci, OciConfig, RegistryEndpoint, test_registry};
+
+    /// Wraps a raw public key in an SPKI PEM block.
+    fn pem(prefix: &[u8], key: &[u8]) -> String {
+        let der = [prefix, key].concat();
+        format!(
+            "-----BEGIN PUBLIC KEY-----\n{}\n-----END PUBLIC KEY-----\n",
+            base64::engine::general_purpose::STANDARD.encode(der)
+        )
+    }
+
+    /// Generates a P-256 signing key and its trusted public key.
+    fn p256(name: &str) -> (EcdsaKeyPair, TrustedKey) {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+            .unwrap();
+        let key =
+            TrustedKey::from_pem(name, &pem(P256_SPKI_PREFIX, pair.public_key().as_ref())).unwrap();
+        (pair, key)
+    }
+
+    fn payload(digest: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "critical": {
+                "identity": {"docker-reference": "example"},
+                "image": {"docker-manifest-digest": digest},
+                "type": "cosign container image signature",
+            },
+            "optional": null,
+        }))
+        .unwrap()
+    }
+
+    fn sign(pair: &EcdsaKeyPair, message: &[u8]) -> Vec<u8> {
+        pair.sign(&SystemRandom::new(), message)
+            .unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    fn reference(s: &str) -> Reference {
+        crate::parse_reference(s).unwrap()
+    }
+
+    #[test]
+    fn parses_p256_and_ed25519_keys() {
+        let (_, key) = p256("cosign.pub");
+        assert_eq!(key.algorithm, KeyAlgorithm::EcdsaP256);
+        assert_eq!(key.name(), "cosign.pub");
+
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let ed = TrustedKey::from_pem("ed", &pem(ED25519_SPKI_PREFIX, pair.public_key().as_ref()))
+            .unwrap();
+        let sig = pair.sign(b"msg");
+        assert!(ed.verifies(b"msg", sig.as_ref()));
+        assert!(!ed.verifies(b"other", sig.as_ref()));
+
+        assert!(matches!(
+            TrustedKey::from_pem("rsa", &pem(&[0x30, 0x82], &[0; 64])),
+            Err(OciError::InvalidKey(_))
+        ));
+        assert!(TrustedKey::from_pem("empty", "not a key").is_err());
+    }
+
+    #[test]
+    fn most_specific_scope_wins() {
+        let policy = VerifyPolicy::new()
+            .default_requirement(Requirement::Reject)
+            .allow_repository("index.docker.io/library")
+            .require("docker.io/library/alpine", Requirement::Digests(vec![]));
+
+        assert!(matches!(
+            policy.requirement_for("docker.io/library/alpine"),
+            (Some("docker.io/library/alpine"), Requirement::Digests(_))
+        ));
+        assert!(matches!(
+            policy.requirement_for("docker.io/library/alpine-extra"),
+            (Some("docker.io/library"), Requirement::Accept)
+        ));
+        assert!(matches!(
+            policy.requirement_for("ghcr.io/acme/app"),
+            (None, Requirement::Reject)
+        ));
+    }
+
+    #[test]
+    fn evaluates_allow_lists_and_rejections() {
+        let policy = VerifyPolicy::new()
+            .default_requirement(Requirement::Reject)
+            .require(
+                "ghcr.io/acme",
+                Requirement::Digests(vec!["sha256:aa".into()]),
+            )
+            .allow_digest("sha256:ff");
+
+        let ok = policy
+            .evaluate(&reference("ghcr.io/acme/app:1"), "sha256:aa", &[])
+            .unwrap();
+        assert_eq!(ok.method, VerifyMethod::DigestAllowed);
+        assert_eq!(ok.scope.as_deref(), Some("ghcr.io/acme"));
+
+        assert!(matches!(
+            policy.evaluate(&reference("ghcr.io/acme/app:1"), "sha256:bb", &[]),
+            Err(OciError::Rejected { .. })
+        ));
+        assert!(matches!(
+            policy.evaluate(&reference("alpine"), "sha256:aa", &[]),
+            Err(OciError::Rejected { .. })
+        ));
+        let pinned = policy
+            .evaluate(&reference("alpine"), "sha256:ff", &[])
+            .unwrap();
+        assert_eq!(pinned.scope, None);
+    }
+
+    #[test]
+    fn signature_must_name_the_booted_digest() {
+        let (pair, key) = p256("k");
+        let policy = VerifyPolicy::new().default_requirement(Requirement::Signed(vec![key]));
+        let signed = payload("sha256:aa");
+        let sig = CosignSignature {
+            signature: sign(&pair, &signed),
+            payload: signed,
+            certificate: None,
+            source: "sha256:51".into(),
+        };
+
+        let ok = policy
+            .evaluate(&reference("app"), "sha256:aa", std::slice::from_ref(&sig))
+            .unwrap();
+        assert_eq!(
+            ok.method,
+            VerifyMethod::Signature {
+                key: "k".into(),
+                signature: "sha256:51".into()
+            }
+        );
+        assert!(matches!(
+            policy.evaluate(&reference("app"), "sha256:bb", &[sig]),
+            Err(OciError::Rejected { .. })
+        ));
+    }
+
+    #[test]
+    fn custom_verifier_sees_request() {
+        let policy = VerifyPolicy::new().default_requirement(Requirement::Custom(Arc::new(
+            |req: &VerifyRequest<'_>| {
+                if req.digest == "sha256:aa" {
+                    Ok(format!("attested {}", req.reference))
+                } else {
+                    Err(OciError::Rejected {
+                        reference: req.reference.to_owned(),
+                        reason: "no attestation".into(),
+                    })
+                }
+            },
+        )));
+        let ok = policy
+            .evaluate(&reference("app"), "sha256:aa", &[])
+            .unwrap();
+        assert!(matches!(ok.method, VerifyMethod::Custom { .. }));
+        assert!(
+            policy
+                .evaluate(&reference("app"), "sha256:bb", &[])
+                .is_err()
+        );
+    }
+
+    /// Serves one image from a fresh registry and pulls it.
+    async fn pulled_image(
+        dir: &Path,
+    ) -> (test_registry::TestRegistry, Oci, crate::PullResult, String) {
+        let registry = test_registry::TestRegistry::start().await;
+        let digest = registry.add_image("1", &[("hello.txt", b"hi")]);
+        let oci = plain_http_oci(dir, &registry);
+        let pulled = oci
+            .pull(&format!("{}/team/app:1", registry.host()), |_| {})
+            .await
+            .unwrap();
+        (registry, oci, pulled, digest)
+    }
+
+    /// Store in `dir` pulling from `registry` over plain HTTP.
+    fn plain_http_oci(dir: &Path, registry: &test_registry::TestRegistry) -> Oci {
+        let host = registry.host().to_owned();
+        Oci::open_with(OciConfig {
+            store_dir: dir.to_path_buf(),
+            credentials: CredentialChain::new(),
+            auth: RegistryAuth::Anonymous,
+            mirrors: HashMap::from([(
+                host.clone(),
+                vec![RegistryEndpoint::new(host).plain_http()],
+            )]),
+            max_concurrent_downloads: 2,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verifies_signature_tag_from_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let (registry, oci, pulled, digest) = pulled_image(dir.path()).await;
+        let (pair, release) = p256("release");
+        let (_, other) = p256("other");
+        let policy = VerifyPolicy::new()
+            .default_requirement(Requirement::Reject)
+            .require(
+                format!("{}/team", registry.host()),
+                Requirement::Signed(vec![release]),
+            );
+        let unrelated = VerifyPolicy::new().default_requirement(Requirement::Signed(vec![other]));
+
+        assert!(matches!(
+            oci.verify(&pulled, &policy).await,
+            Err(OciError::Rejected { .. })
+        ));
+
+        let signed = payload(&digest);
+        let sig_manifest = registry.add_signature(&digest, &signed, &sign(&pair, &signed), false);
+        let verified = oci.verify(&pulled, &policy).await.unwrap();
+        assert_eq!(verified.digest, digest);
+        assert_eq!(
+            verified.method,
+            VerifyMethod::Signature {
+                key: "release".into(),
+                signature: sig_manifest,
+            }
+        );
+        assert!(oci.verify(&pulled, &unrelated).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn verifies_signature_referrer_from_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let (registry, oci, pulled, digest) = pulled_image(dir.path()).await;
+        let (pair, release) = p256("release");
+        let signed = payload(&digest);
+        let sig_manifest = registry.add_signature(&digest, &signed, &sign(&pair, &signed), true);
+        let policy = VerifyPolicy::new().default_requirement(Requirement::Signed(vec![release]));
+        let verified = oci.verify(&pulled, &policy).await.unwrap();
+        assert_eq!(
+            verified.method,
+            VerifyMethod::Signature {
+                key: "release".into(),
+                signature: sig_manifest,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn multi_arch_image_is_checked_by_index_then_platform_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = test_registry::TestRegistry::start().await;
+        let platform = registry.add_image("amd64", &[("hello.txt", b"hi")]);
+        let index = registry.add_index("1", &platform);
+        let oci = plain_http_oci(dir.path(), &registry);
+        let image = format!("{}/team/app:1", registry.host());
+        let pulled = oci.pull(&image, |_| {}).await.unwrap();
+        assert_eq!(pulled.digest, platform);
+        assert_eq!(pulled.index_digest.as_deref(), Some(index.as_str()));
+        let cached = oci.ensure(&image, |_| {}).await.unwrap();
+        assert_eq!(cached.index_digest, pulled.index_digest);
+
+        let (pair, release) = p256("release");
+        let policy = VerifyPolicy::new().default_requirement(Requirement::Signed(vec![release]));
+        let signed = payload(&index);
+        registry.add_signature(&index, &signed, &sign(&pair, &signed), false);
+        assert_eq!(oci.verify(&pulled, &policy).await.unwrap().digest, index);
+
+        let only_platform =
+            VerifyPolicy::new().default_requirement(Requirement::Digests(vec![platform.clone()]));
+        assert_eq!(
+            oci.verify(&pulled, &only_platform).await.unwrap().digest,
+            platform
+        );
+    }
+}
@@ -4,6 +4,8 @@
 //! `BUX_GUEST_CONFIG=<json>` and passes it through libkrun `set_exec` env.
 //! The guest agent parses it before configuring network / MITM trust.
 
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 use serde::{Deserialize, Serialize};
 
 /// Environment variable name carrying compact JSON of [`GuestBootConfig`].
@@ -36,6 +38,18 @@ pub struct GuestBootConfig {
     /// Default `true`. Failures fall back to Phase A without aborting the agent.
     #[serde(default = "default_true")]
     pub primary_container: bool,
+    /// eth0 address on a private network (default `192.168.127.2`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<Ipv4Addr>,
+    /// Guest hostname (default `bux`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// DNS search domain (the private network's name).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_domain: Option<String>,
+    /// eth0 IPv6 address on a dual-stack network; `None` = IPv4 only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipv6: Option<Ipv6Addr>,
 }
 
 /// Serde default for `primary_container`.
@@ -52,6 +66,10 @@ pub fn new(vm_id: impl Into<String>, network: GuestNetworkMode) -> Self {
             mitm_ca_pem: None,
             vm_id: vm_id.into(),
             primary_container: true,
+            ip: None,
+            hostname: None,
+            search_domain: None,
+            ipv6: None,
         }
     }
 
@@ -92,4 +110,21 @@ fn env_roundtrip() {
         let de: GuestBootConfig = serde_json::from_str(json).unwrap();
         assert_eq!(de, cfg);
     }
+
+    #[test]
+    fn network_identity_is_optional() {
+        let minimal = r#"{"network":"enabled","vm_id":"vm1"}"#;
+        let de: GuestBootConfig = serde_json::from_str(minimal).unwrap();
+        assert_eq!(de.ip, None);
+        assert_eq!(de.hostname, None);
+        assert_eq!(de.ipv6, None);
+
+        let mut cfg = GuestBootConfig::new("vm1", GuestNetworkMode::Enabled);
+        cfg.ip = Some(Ipv4Addr::new(192, 168, 127, 3));
+        cfg.hostname = Some("db".into());
+        cfg.search_domain = Some("lab".into());
+        cfg.ipv6 = Some("fd42:6275:7800::3".parse().unwrap());
+        let json = serde_json::to_string(&cfg).unwrap();
+        assert_eq!(serde_json::from_str::<GuestBootConfig>(&json).unwrap(), cfg);
+    }
 }
@@ -21,6 +21,22 @@
 /// (`BPF_MAXINSNS`). Seccomp filters larger than this are rejected.
 pub const MAX_LEN: usize = 4096;
 
+/// Decode an [`Instruction`] into `(code, jt, jf, k)`; inverse of
+/// [`instruction`].
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    reason = "each field is masked to its width before the cast"
+)]
+pub const fn decode(insn: Instruction) -> (u16, u8, u8, u32) {
+    (
+        (insn & 0xFFFF) as u16,
+        ((insn >> 16) & 0xFF) as u8,
+        ((insn >> 24) & 0xFF) as u8,
+        (insn >> 32) as u32,
+    )
+}
+
 /// Encode a `struct sock_filter` as a single `u64`.
 ///
 /// Layout:
@@ -47,15 +63,33 @@ pub const fn instruction(code: u16, jt: u8, jf: u8, k: u32) -> Instruction {
 /// Return instruction class.
 pub const BPF_RET: u16 = 0x06;
 
+/// Arithmetic/logic instruction class.
+pub const BPF_ALU: u16 = 0x04;
+
 /// 32-bit word size.
 pub const BPF_W: u16 = 0x00;
 
 /// Absolute addressing mode.
 pub const BPF_ABS: u16 = 0x20;
 
+/// Unconditional jump by `k`.
+pub const BPF_JA: u16 = 0x00;
+
 /// Jump if equal.
 pub const BPF_JEQ: u16 = 0x10;
 
+/// Jump if greater than (unsigned).
+pub const BPF_JGT: u16 = 0x20;
+
+/// Jump if greater than or equal (unsigned).
+pub const BPF_JGE: u16 = 0x30;
+
+/// Jump if any bit of `k` is set.
+pub const BPF_JSET: u16 = 0x40;
+
+/// Bitwise AND (ALU operation).
+pub const BPF_AND: u16 = 0x50;
+
 /// Immediate (constant) operand.
 pub const BPF_K: u16 = 0x00;
 
@@ -67,6 +101,55 @@ pub const fn instruction(code: u16, jt: u8, jf: u8, k: u32) -> Instruction {
 /// Kill the offending process with `SIGSYS`.
 pub const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
 
+/// Allow the syscall after logging it (Linux 4.14+).
+pub const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+
+/// Fail the syscall; the low 16 bits carry the errno.
+pub const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+
+/// Deliver `SIGSYS` to the thread; the low 16 bits land in `si_errno`.
+pub const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+
+/// Forward the syscall to a user-space supervisor (Linux 5.0+).
+pub const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+
+/// What a filter does with a syscall: the verdict of a rule, or the
+/// default for syscalls no rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Action {
+    /// Kill the process with `SIGSYS`.
+    #[default]
+    KillProcess,
+    /// Allow the syscall.
+    Allow,
+    /// Allow the syscall and record it in the kernel audit log.
+    Log,
+    /// Fail the syscall with this errno.
+    Errno(u16),
+    /// Send `SIGSYS` to the calling thread, with this value in `si_errno`.
+    Trap(u16),
+    /// Hand the syscall to a supervisor listening on the filter's
+    /// notification fd. [`crate::install`] creates no listener, so
+    /// without one the kernel fails the syscall with `ENOSYS`.
+    UserNotif,
+}
+
+impl Action {
+    /// The `SECCOMP_RET_*` value returned by the filter for this action.
+    #[must_use]
+    pub const fn ret(self) -> u32 {
+        match self {
+            Self::KillProcess => SECCOMP_RET_KILL_PROCESS,
+            Self::Allow => SECCOMP_RET_ALLOW,
+            Self::Log => SECCOMP_RET_LOG,
+            Self::Errno(errno) => SECCOMP_RET_ERRNO | errno as u32,
+            Self::Trap(data) => SECCOMP_RET_TRAP | data as u32,
+            Self::UserNotif => SECCOMP_RET_USER_NOTIF,
+        }
+    }
+}
+
 // `struct seccomp_data` field offsets.
 
 /// Offset of `nr` (syscall number) in `struct seccomp_data`.
@@ -75,6 +158,31 @@ pub const fn instruction(code: u16, jt: u8, jf: u8, k: u32) -> Instruction {
 /// Offset of `arch` (audit architecture) in `struct seccomp_data`.
 pub const SECCOMP_ARCH_OFFSET: u32 = 4;
 
+/// Offset of `instruction_pointer` in `struct seccomp_data`.
+pub const SECCOMP_IP_OFFSET: u32 = 8;
+
+/// Offset of `args[0]` in `struct seccomp_data`; each argument is 8 bytes.
+pub const SECCOMP_ARGS_OFFSET: u32 = 16;
+
+/// Size of `struct seccomp_data` in bytes.
+pub const SECCOMP_DATA_LEN: usize = 64;
+
+/// Number of syscall arguments in `struct seccomp_data`.
+pub const SECCOMP_ARG_COUNT: u8 = 6;
+
+/// Offsets of the `(low, high)` 32-bit halves of `args[index]` for the
+/// target's byte order. Classic BPF loads 32 bits at a time, so 64-bit
+/// argument comparisons check each half separately.
+#[must_use]
+pub const fn arg_offsets(index: u8) -> (u32, u32) {
+    let base = SECCOMP_ARGS_OFFSET + 8 * index as u32;
+    if cfg!(target_endian = "little") {
+        (base, base + 4)
+    } else {
+        (base + 4, base)
+    }
+}
+
 #[cfg(test)]
 #[allow(
     clippy::unwrap_used,
@@ -91,6 +199,7 @@ fn instruction_packs_fields_into_correct_bits() {
         assert_eq!((i >> 16) & 0xFF, 0x56);
         assert_eq!((i >> 24) & 0xFF, 0x78);
         assert_eq!((i >> 32) & 0xFFFF_FFFF, 0x9abc_def0);
+        assert_eq!(decode(i), (0x1234, 0x56, 0x78, 0x9abc_def0));
     }
 
     #[test]
@@ -101,12 +210,29 @@ fn opcodes_have_expected_kernel_values() {
         assert_eq!(BPF_RET, 0x06);
         assert_eq!(BPF_ABS, 0x20);
         assert_eq!(BPF_JEQ, 0x10);
+        assert_eq!(BPF_ALU | BPF_AND | BPF_K, 0x54);
+        assert_eq!(BPF_JMP | BPF_JGT | BPF_K, 0x25);
+        assert_eq!(BPF_JMP | BPF_JGE | BPF_K, 0x35);
     }
 
     #[test]
     fn seccomp_return_values_match_kernel_header() {
         assert_eq!(SECCOMP_RET_ALLOW, 0x7fff_0000);
         assert_eq!(SECCOMP_RET_KILL_PROCESS, 0x8000_0000);
+        assert_eq!(SECCOMP_RET_LOG, 0x7ffc_0000);
+        assert_eq!(SECCOMP_RET_ERRNO, 0x0005_0000);
+        assert_eq!(SECCOMP_RET_TRAP, 0x0003_0000);
+        assert_eq!(SECCOMP_RET_USER_NOTIF, 0x7fc0_0000);
+    }
+
+    #[test]
+    fn actions_map_to_return_values() {
+        assert_eq!(Action::default().ret(), SECCOMP_RET_KILL_PROCESS);
+        assert_eq!(Action::Log.ret(), SECCOMP_RET_LOG);
+        assert_eq!(Action::Errno(1).ret(), 0x0005_0001);
+        assert_eq!(Action::Allow.ret(), SECCOMP_RET_ALLOW);
+        assert_eq!(Action::Trap(7).ret(), 0x0003_0007);
+        assert_eq!(Action::UserNotif.ret(), SECCOMP_RET_USER_NOTIF);
     }
 
     #[test]
@@ -114,5 +240,11 @@ fn seccomp_data_offsets_are_byte_correct() {
         // struct seccomp_data { int nr; __u32 arch; ... }
         assert_eq!(SECCOMP_NR_OFFSET, 0);
         assert_eq!(SECCOMP_ARCH_OFFSET, 4);
+        // ... __u64 instruction_pointer; __u64 args[6]; }
+        assert_eq!(SECCOMP_IP_OFFSET, 8);
+        assert_eq!(SECCOMP_ARGS_OFFSET, 16);
+        let (lo, hi) = arg_offsets(5);
+        assert_eq!(lo.min(hi), 56);
+        assert_eq!(lo.max(hi) as usize + 4, SECCOMP_DATA_LEN);
     }
 }
@@ -0,0 +1,480 @@
+//! Compile a [`Filter`] into a seccomp BPF program.
+//!
+//! Program layout:
+//!
+//! ```text
+//! LD  arch; JEQ audit_arch ? next : default   ; wrong arch -> default
+//! LD  nr                                      ; binary search over the
+//! JGE nr_mid ? right : left                   ;   sorted syscall numbers,
+//! ...                                         ;   down to short JEQ runs
+//! JEQ nr_i ? target_i : ...                   ; target: a shared RET, or
+//! <per-syscall argument blocks>               ;   the syscall's rule block
+//! RET default; RET allow; ...                 ; one RET per distinct action
+//! ```
+//!
+//! A lookup takes `O(log n)` comparisons instead of the `O(n)` of a
+//! linear JEQ chain. Classic BPF only jumps forward and conditional
+//! offsets are 8 bits, so the assembler relaxes any conditional jump
+//! whose target is more than 255 instructions away into a `JA`
+//! trampoline.
+
+use std::collections::BTreeMap;
+
+use crate::bpf::{
+    Action, BPF_ABS, BPF_ALU, BPF_AND, BPF_JA, BPF_JEQ, BPF_JGE, BPF_JGT, BPF_JMP, BPF_K, BPF_LD,
+    BPF_RET, BPF_W, Instruction, SECCOMP_ARCH_OFFSET, SECCOMP_NR_OFFSET, arg_offsets, instruction,
+};
+use crate::rule::{ArgCmp, ArgOp, Filter, Rule};
+
+/// Longest run of syscalls matched with a linear JEQ chain before the
+/// search splits again.
+const LINEAR_RUN: usize = 4;
+
+/// Largest forward offset a conditional jump can encode.
+const MAX_COND_JUMP: usize = u8::MAX as usize;
+
+/// Compile `filter` (argument indices already validated).
+pub(crate) fn compile(filter: &Filter) -> Vec<Instruction> {
+    let mut by_nr: BTreeMap<u32, Vec<&Rule>> = BTreeMap::new();
+    for rule in &filter.rules {
+        by_nr.entry(rule.syscall).or_default().push(rule);
+    }
+
+    let mut asm = Asm::default();
+    let default_ret = filter.default_action.ret();
+    let mut rets = BTreeMap::new();
+    let default = asm.ret_label(&mut rets, filter.default_action);
+
+    let start = asm.label();
+    asm.push(Op::Load(SECCOMP_ARCH_OFFSET));
+    asm.jump(BPF_JEQ, filter.audit_arch, start, default);
+    asm.bind(start);
+    asm.push(Op::Load(SECCOMP_NR_OFFSET));
+
+    let mut blocks = Vec::new();
+    let mut targets = Vec::with_capacity(by_nr.len());
+    for (&nr, rules) in &by_nr {
+        let target = match rules.first() {
+            Some(rule) if rule.args.is_empty() => asm.ret_label(&mut rets, rule.action),
+            _ => {
+                let block = asm.label();
+                blocks.push((block, rules));
+                block
+            }
+        };
+        targets.push((nr, target));
+    }
+    search(&mut asm, &targets, default);
+
+    for (block, rules) in blocks {
+        asm.bind(block);
+        rule_block(&mut asm, rules, default_ret);
+    }
+    for (ret, label) in rets {
+        asm.bind(label);
+        asm.push(Op::Ret(ret));
+    }
+    asm.finish()
+}
+
+/// Binary search over `targets` (sorted by syscall number, A = nr).
+fn search(asm: &mut Asm, targets: &[(u32, Label)], default: Label) {
+    if targets.is_empty() {
+        asm.push(Op::Ja(default));
+        return;
+    }
+    if targets.len() <= LINEAR_RUN {
+        let mut rest = targets.iter().peekable();
+        while let Some(&(nr, target)) = rest.next() {
+            let miss = if rest.peek().is_some() {
+                asm.label()
+            } else {
+                default
+            };
+            asm.jump(BPF_JEQ, nr, target, miss);
+            if miss != default {
+                asm.bind(miss);
+            }
+        }
+        return;
+    }
+    let (low, high) = targets.split_at(targets.len() / 2);
+    let (left, right) = (asm.label(), asm.label());
+    let pivot = high.first().map_or(0, |&(nr, _)| nr);
+    asm.jump(BPF_JGE, pivot, right, left);
+    asm.bind(left);
+    search(asm, low, default);
+    asm.bind(right);
+    search(asm, high, default);
+}
+
+/// Argument checks for one syscall's rules, in order, falling back to
+/// the default action.
+fn rule_block(asm: &mut Asm, rules: &[&Rule], default_ret: u32) {
+    for rule in rules {
+        let next = asm.label();
+        for &cmp in &rule.args {
+            arg_check(asm, cmp, next);
+        }
+        asm.push(Op::Ret(rule.action.ret()));
+        asm.bind(next);
+        if rule.args.is_empty() {
+            // Later rules are unreachable.
+            return;
+        }
+    }
+    asm.push(Op::Ret(default_ret));
+}
+
+/// Fall through when `cmp` holds, jump to `fail` otherwise.
+///
+/// Compares the high 32-bit half first; the low half decides only when
+/// the high halves are equal.
+fn arg_check(asm: &mut Asm, cmp: ArgCmp, fail: Label) {
+    let (lo_off, hi_off) = arg_offsets(cmp.arg);
+    let split = |v: u64| {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "splitting a u64 into its 32-bit halves"
+        )]
+        let halves = ((v >> 32) as u32, v as u32);
+        halves
+    };
+    let (pass, low) = (asm.label(), asm.label());
+    match cmp.op {
+        ArgOp::Eq(value) => {
+            let (hi, lo) = split(value);
+            asm.push(Op::Load(hi_off));
+            asm.jump(BPF_JEQ, hi, low, fail);
+            asm.bind(low);
+            asm.push(Op::Load(lo_off));
+            asm.jump(BPF_JEQ, lo, pass, fail);
+        }
+        ArgOp::MaskedEq { mask, value } => {
+            let ((mask_hi, mask_lo), (hi, lo)) = (split(mask), split(value & mask));
+            asm.push(Op::Load(hi_off));
+            asm.push(Op::And(mask_hi));
+            asm.jump(BPF_JEQ, hi, low, fail);
+            asm.bind(low);
+            asm.push(Op::Load(lo_off));
+            asm.push(Op::And(mask_lo));
+            asm.jump(BPF_JEQ, lo, pass, fail);
+        }
+        ArgOp::Gt(value) => {
+            let (hi, lo) = split(value);
+            let equal = asm.label();
+            asm.push(Op::Load(hi_off));
+            asm.jump(BPF_JGT, hi, pass, equal);
+            asm.bind(equal);
+            asm.jump(BPF_JEQ, hi, low, fail);
+            asm.bind(low);
+            asm.push(Op::Load(lo_off));
+            asm.jump(BPF_JGT, lo, pass, fail);
+        }
+        ArgOp::Lt(value) => {
+            let (hi, lo) = split(value);
+            let equal = asm.label();
+            asm.push(Op::Load(hi_off));
+            asm.jump(BPF_JGT, hi, fail, equal);
+            asm.bind(equal);
+            asm.jump(BPF_JEQ, hi, low, pass);
+            asm.bind(low);
+            asm.push(Op::Load(lo_off));
+            asm.jump(BPF_JGE, lo, fail, pass);
+        }
+    }
+    asm.bind(pass);
+}
+
+/// Jump target placeholder, resolved by [`Asm::finish`].
+type Label = usize;
+
+/// Assembler operation; [`Op::Label`] occupies no instruction.
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    /// Position of a label.
+    Label(Label),
+    /// `LD W ABS offset`.
+    Load(u32),
+    /// `ALU AND K`.
+    And(u32),
+    /// Conditional `JMP code K`.
+    Jump {
+        /// `BPF_JEQ`, `BPF_JGT` or `BPF_JGE`.
+        code: u16,
+        /// Compared constant.
+        k: u32,
+        /// Target when the comparison holds.
+        jt: Label,
+        /// Target otherwise.
+        jf: Label,
+    },
+    /// Unconditional `JA`.
+    Ja(Label),
+    /// `RET K`.
+    Ret(u32),
+}
+
+/// Label-based program builder.
+#[derive(Debug, Default)]
+struct Asm {
+    /// Operations in program order.
+    ops: Vec<Op>,
+    /// Number of labels allocated.
+    labels: usize,
+}
+
+impl Asm {
+    /// Allocate an unbound label.
+    const fn label(&mut self) -> Label {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    /// Label of the shared `RET` for `action`, allocated on first use.
+    fn ret_label(&mut self, rets: &mut BTreeMap<u32, Label>, action: Action) -> Label {
+        *rets.entry(action.ret()).or_insert_with(|| self.label())
+    }
+
+    /// Append an operation.
+    fn push(&mut self, op: Op) {
+        self.ops.push(op);
+    }
+
+    /// Place `label` at the current position.
+    fn bind(&mut self, label: Label) {
+        self.push(Op::Label(label));
+    }
+
+    /// Append a conditional jump.
+    fn jump(&mut self, code: u16, k: u32, jt: Label, jf: Label) {
+        self.push(Op::Jump { code, k, jt, jf });
+    }
+
+    /// Resolve labels and encode the program.
+    ///
+    /// Starts with every conditional jump short and widens any whose
+    /// target is out of range until nothing changes. Widening only
+    /// grows the program, so this terminates.
+    fn finish(self) -> Vec<Instruction> {
+        // Per op: does (jt, jf) need a JA trampoline?
+        let mut far = vec![(false, false); self.ops.len()];
+        let (addrs, labels) = loop {
+            let (addrs, labels) = self.layout(&far);
+            if !self.widen(&addrs, &labels, &mut far) {
+                break (addrs, labels);
+            }
+        };
+
+        let target = |l: Label| labels.get(l).copied().unwrap_or_default();
+        let mut prog = Vec::with_capacity(addrs.last().map_or(0, |&a| a + 1));
+        for ((op, &addr), &(far_t, far_f)) in self.ops.iter().zip(&addrs).zip(&far) {
+            match *op {
+                Op::Label(_) => {}
+                Op::Load(offset) => prog.push(instruction(BPF_LD | BPF_W | BPF_ABS, 0, 0, offset)),
+                Op::And(k) => prog.push(instruction(BPF_ALU | BPF_AND | BPF_K, 0, 0, k)),
+                Op::Ret(k) => prog.push(instruction(BPF_RET | BPF_K, 0, 0, k)),
+                Op::Ja(l) => prog.push(ja(target(l) - (addr + 1))),
+                Op::Jump { code, k, jt, jf } => {
+                    let jt = (target(jt), far_t);
+                    let jf = (target(jf), far_f);
+                    prog.extend(encode_jump(addr, code, k, jt, jf));
+                }
+            }
+        }
+        prog
+    }
+
+    /// Mark jumps whose targets are out of reach as far; returns whether
+    /// anything changed.
+    fn widen(&self, addrs: &[usize], labels: &[usize], far: &mut [(bool, bool)]) -> bool {
+        let mut changed = false;
+        for ((op, &addr), wide) in self.ops.iter().zip(addrs).zip(far) {
+            let Op::Jump { jt, jf, .. } = *op else {
+                continue;
+            };
+            let out_of_reach = |l: Label| {
+                labels
+                    .get(l)
+                    .is_some_and(|&t| t - (addr + 1) > MAX_COND_JUMP)
+            };
+            let next = (wide.0 || out_of_reach(jt), wide.1 || out_of_reach(jf));
+            changed |= next != *wide;
+            *wide = next;
+        }
+        changed
+    }
+
+    /// Instruction address of every op and every label for the given
+    /// trampoline choices.
+    fn layout(&self, far: &[(bool, bool)]) -> (Vec<usize>, Vec<usize>) {
+        let mut addrs = Vec::with_capacity(self.ops.len());
+        let mut labels = vec![0; self.labels];
+        let mut pc = 0;
+        for (op, &(far_t, far_f)) in self.ops.iter().zip(far) {
+            addrs.push(pc);
+            let label = if let Op::Label(l) = *op {
+                Some(l)
+            } else {
+                None
+            };
+            if let Some(slot) = label.and_then(|l| labels.get_mut(l)) {
+                *slot = pc;
+            }
+            pc += match *op {
+                Op::Label(_) => 0,
+                Op::Jump { .. } => 1 + usize::from(far_t) + usize::from(far_f),
+                Op::Load(_) | Op::And(_) | Op::Ja(_) | Op::Ret(_) => 1,
+            };
+        }
+        (addrs, labels)
+    }
+}
+
+/// Conditional jump at `addr` to `(target, far)` pairs, followed by a
+/// `JA` trampoline for each far target.
+fn encode_jump(
+    addr: usize,
+    code: u16,
+    k: u32,
+    (jt, far_t): (usize, bool),
+    (jf, far_f): (usize, bool),
+) -> impl Iterator<Item = Instruction> {
+    let after = addr + 1;
+    let jt_off = if far_t { 0 } else { jt - after };
+    let jf_off = if far_f {
+        usize::from(far_t)
+    } else {
+        jf - after
+    };
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "offsets over 255 were relaxed into trampolines"
+    )]
+    let cond = instruction(BPF_JMP | code | BPF_K, jt_off as u8, jf_off as u8, k);
+    let tramp_t = far_t.then(|| ja(jt - (after + 1)));
+    let tramp_f = far_f.then(|| ja(jf - (after + 1 + usize::from(far_t))));
+    std::iter::once(cond).chain(tramp_t).chain(tramp_f)
+}
+
+/// `JA offset`.
+const fn ja(offset: usize) -> Instruction {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "program length is bounded by BPF MAX_LEN"
+    )]
+    instruction(BPF_JMP | BPF_JA, 0, 0, offset as u32)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    clippy::indexing_slicing,
+    reason = "tests are allowed to use unwrap/indexing and omit docs"
+)]
+mod tests {
+    use super::*;
+    use crate::bpf::decode;
+    use crate::interp::{SeccompData, run, trace};
+
+    const ARCH: u32 = 0xc000_003e;
+
+    /// Run `filter`'s program on `(nr, args)` and compare against the
+    /// rule-level reference semantics.
+    fn check(filter: &Filter, prog: &[Instruction], nr: u32, args: [u64; 6]) {
+        let data = SeccompData::new(ARCH, nr, args);
+        let expect = filter.evaluate(ARCH, nr, &args).ret();
+        assert_eq!(run(prog, &data).unwrap(), expect, "nr {nr} args {args:?}");
+    }
+
+    #[test]
+    fn large_allowlist_matches_reference() {
+        let list: Vec<u32> = (0..400).step_by(3).collect();
+        let filter = Filter::new(ARCH, Action::Errno(1)).allow(&list);
+        let prog = filter.compile().unwrap();
+        for nr in 0..410 {
+            check(&filter, &prog, nr, [0; 6]);
+        }
+        let wrong_arch = SeccompData::new(0x4000_0003, 0, [0; 6]);
+        assert_eq!(run(&prog, &wrong_arch).unwrap(), Action::Errno(1).ret());
+    }
+
+    #[test]
+    fn search_depth_is_logarithmic() {
+        let list: Vec<u32> = (0..1024).collect();
+        let prog = Filter::new(ARCH, Action::KillProcess)
+            .allow(&list)
+            .compile()
+            .unwrap();
+        for nr in [0, 511, 1023] {
+            let data = SeccompData::new(ARCH, nr, [0; 6]);
+            let (ret, steps) = trace(&prog, &data).unwrap();
+            assert_eq!(ret, Action::Allow.ret());
+            assert!(steps < 24, "{steps} instructions executed for {nr}");
+        }
+    }
+
+    #[test]
+    fn argument_comparators_match_reference() {
+        let values = [
+            0,
+            1,
+            0xae80,
+            0xffff_ffff,
+            1 << 32,
+            (1 << 32) | 0xae80,
+            u64::MAX,
+        ];
+        let filter = Filter::new(ARCH, Action::KillProcess)
+            .rule(Rule::allow(16).arg(ArgCmp::eq(1, (1 << 32) | 0xae80)))
+            .rule(Rule::new(16, Action::Errno(25)).arg(ArgCmp::gt(1, 0xffff_ffff)))
+            .rule(Rule::new(16, Action::Log).arg(ArgCmp::lt(1, 0xae80)))
+            .rule(Rule::new(16, Action::Trap(3)))
+            .rule(Rule::allow(56).arg(ArgCmp::masked_eq(0, 0x7e02_0000, 0)))
+            .rule(
+                Rule::new(56, Action::UserNotif)
+                    .arg(ArgCmp::eq(5, 1))
+                    .arg(ArgCmp::gt(4, 0)),
+            );
+        let prog = filter.compile().unwrap();
+        for &a in &values {
+            for &b in &values {
+                check(&filter, &prog, 16, [0, a, 0, 0, 0, 0]);
+                check(&filter, &prog, 56, [a, 0, 0, 0, b, 1]);
+                check(&filter, &prog, 56, [0x1_0000_0000 | a, 0, 0, 0, b, a]);
+            }
+        }
+        check(&filter, &prog, 57, [0; 6]);
+    }
+
+    #[test]
+    fn far_targets_use_trampolines() {
+        // Enough argument blocks that the search and the early blocks
+        // must jump more than 255 instructions.
+        let mut filter = Filter::new(ARCH, Action::KillProcess);
+        for nr in 0..120 {
+            filter = filter
+                .rule(Rule::allow(nr).arg(ArgCmp::eq(0, u64::from(nr))))
+                .rule(Rule::new(nr, Action::Errno(1)).arg(ArgCmp::gt(1, 10)));
+        }
+        let prog = filter.compile().unwrap();
+        assert!(prog.len() > MAX_COND_JUMP);
+        assert!(
+            prog.iter()
+                .any(|&i| decode(i).0 == BPF_JMP | BPF_JA && decode(i).3 > 0)
+        );
+        for nr in [0, 1, 59, 60, 118, 119, 200] {
+            for args in [[u64::from(nr), 0, 0, 0, 0, 0], [7, 11, 0, 0, 0, 0], [7; 6]] {
+                check(&filter, &prog, nr, args);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_filter_applies_default() {
+        let filter = Filter::new(ARCH, Action::Log);
+        let prog = filter.compile().unwrap();
+        check(&filter, &prog, 0, [0; 6]);
+    }
+}
@@ -31,4 +31,17 @@ pub enum Error {
     /// The value is the TID of the thread that could not be synced.
     #[error("seccomp TSYNC failed for thread {0}")]
     TsyncFailed(i64),
+
+    /// A rule compares an argument index outside `0..=5`.
+    #[error("seccomp rule references args[{0}] (max 5)")]
+    InvalidArgIndex(u8),
+
+    /// The interpreter hit an instruction it cannot execute.
+    #[error("invalid BPF program at instruction {pc}: {reason}")]
+    InvalidProgram {
+        /// Index of the offending instruction.
+        pc: usize,
+        /// What was wrong with it.
+        reason: &'static str,
+    },
 }
@@ -1,25 +1,16 @@
 //! Assemble the BPF program that enforces the default bux allowlist.
 //!
-//! Program structure (identical to what libseccomp would emit for an
-//! allow-list with `SCMP_ACT_KILL_PROCESS` as the default action):
-//!
-//! ```text
-//! LD  W  ABS   seccomp_data.arch         ; load the syscall's arch
-//! JEQ K       AUDIT_ARCH, +0, +kill_off  ; wrong arch -> jump to kill
-//! LD  W  ABS   seccomp_data.nr           ; load the syscall number
-//! JEQ K       nr_0, +allow_off_0, +0     ; for each allowed syscall:
-//! JEQ K       nr_1, +allow_off_1, +0     ;   jump to ALLOW on match
-//! ...
-//! RET K       SECCOMP_RET_KILL_PROCESS   ; default: kill
-//! RET K       SECCOMP_RET_ALLOW          ; allow target
-//! ```
+//! An allowlist is a [`Filter`] of unconditional allow rules, so the
+//! program is the binary search described in the compiler: wrong arch
+//! or an unlisted syscall reaches the default `RET`
+//! (`SECCOMP_RET_KILL_PROCESS` unless [`build_with`] picks another
+//! [`Action`]), a listed one reaches `RET ALLOW`.
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 use crate::arch::{AUDIT_ARCH, DEFAULT_ALLOWLIST};
-use crate::bpf::{
-    BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, Instruction, SECCOMP_ARCH_OFFSET,
-    SECCOMP_NR_OFFSET, SECCOMP_RET_ALLOW, SECCOMP_RET_KILL_PROCESS, instruction,
-};
+use crate::bpf::{Action, Instruction};
+use crate::compile;
+use crate::rule::Filter;
 
 /// Build the bux default seccomp BPF program.
 ///
@@ -32,60 +23,28 @@ pub fn build_default() -> Vec<Instruction> {
     build(DEFAULT_ALLOWLIST, AUDIT_ARCH)
 }
 
+/// Build the default allowlist program with `action` for everything
+/// outside it.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[must_use]
+pub fn build_default_with(action: Action) -> Vec<Instruction> {
+    build_with(DEFAULT_ALLOWLIST, AUDIT_ARCH, action)
+}
+
 /// Build an allowlist filter with an explicit audit-arch and syscall list.
 ///
 /// Exposed for testing and for callers that want a reduced allowlist
 /// (e.g. a stricter filter for the guest agent).
 #[must_use]
 pub fn build(allowlist: &[u32], audit_arch: u32) -> Vec<Instruction> {
-    let n = allowlist.len();
-    // 2 (arch check) + 1 (load nr) + n (one JEQ per syscall) + 1 (kill) + 1 (allow)
-    let prog_len = 2 + 1 + n + 1 + 1;
-    let mut prog = Vec::with_capacity(prog_len);
-
-    prog.push(instruction(
-        BPF_LD | BPF_W | BPF_ABS,
-        0,
-        0,
-        SECCOMP_ARCH_OFFSET,
-    ));
-    #[allow(
-        clippy::cast_possible_truncation,
-        reason = "BPF jump offset fits in u8 when len < BPF::MAX_LEN"
-    )]
-    let kill_offset = (n + 2) as u8;
-    prog.push(instruction(
-        BPF_JMP | BPF_JEQ | BPF_K,
-        0,
-        kill_offset,
-        audit_arch,
-    ));
-
-    prog.push(instruction(
-        BPF_LD | BPF_W | BPF_ABS,
-        0,
-        0,
-        SECCOMP_NR_OFFSET,
-    ));
-
-    for (i, &syscall) in allowlist.iter().enumerate() {
-        #[allow(
-            clippy::cast_possible_truncation,
-            reason = "BPF jump offset fits in u8 when len < BPF::MAX_LEN"
-        )]
-        let allow_offset = (n - i) as u8;
-        prog.push(instruction(
-            BPF_JMP | BPF_JEQ | BPF_K,
-            allow_offset,
-            0,
-            syscall,
-        ));
-    }
-
-    prog.push(instruction(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_KILL_PROCESS));
-    prog.push(instruction(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW));
+    build_with(allowlist, audit_arch, Action::KillProcess)
+}
 
-    prog
+/// Build an allowlist filter that applies `action` to syscalls outside
+/// `allowlist` and to syscalls from another audit arch.
+#[must_use]
+pub fn build_with(allowlist: &[u32], audit_arch: u32, action: Action) -> Vec<Instruction> {
+    compile::compile(&Filter::new(audit_arch, action).allow(allowlist))
 }
 
 #[cfg(test)]
@@ -97,20 +56,45 @@ pub fn build(allowlist: &[u32], audit_arch: u32) -> Vec<Instruction> {
 )]
 mod tests {
     use super::*;
-    use crate::bpf::MAX_LEN;
+    use crate::bpf::{MAX_LEN, SECCOMP_RET_ALLOW, SECCOMP_RET_KILL_PROCESS};
+    use crate::interp::{SeccompData, run};
+
+    fn verdict(prog: &[Instruction], arch: u32, nr: u32) -> u32 {
+        run(prog, &SeccompData::new(arch, nr, [0; 6])).unwrap()
+    }
 
     #[test]
     fn empty_allowlist_kills_everything() {
         let prog = build(&[], 0);
-        // 2 (arch check) + 1 (load nr) + 0 (no JEQs) + 1 (kill) + 1 (allow) = 5
+        // 2 (arch check) + 1 (load nr) + 1 (jump to default) + 1 (kill) = 5
         assert_eq!(prog.len(), 5);
+        assert_eq!(verdict(&prog, 0, 0), SECCOMP_RET_KILL_PROCESS);
     }
 
     #[test]
-    fn program_size_matches_prediction() {
-        let list: Vec<u32> = (0..100).collect();
-        let prog = build(&list, 0);
-        assert_eq!(prog.len(), 2 + 1 + 100 + 1 + 1);
+    fn allows_exactly_the_listed_syscalls() {
+        let list: Vec<u32> = (0..100).map(|n| n * 2).collect();
+        let prog = build(&list, 7);
+        for nr in 0..210 {
+            let expect = if nr < 200 && nr % 2 == 0 {
+                SECCOMP_RET_ALLOW
+            } else {
+                SECCOMP_RET_KILL_PROCESS
+            };
+            assert_eq!(verdict(&prog, 7, nr), expect, "nr {nr}");
+        }
+        assert_eq!(verdict(&prog, 8, 0), SECCOMP_RET_KILL_PROCESS);
+    }
+
+    #[test]
+    fn default_action_is_configurable() {
+        let kill = build(&[1, 2], 0);
+        assert_eq!(verdict(&kill, 0, 3), SECCOMP_RET_KILL_PROCESS);
+        let errno = build_with(&[1, 2], 0, Action::Errno(1));
+        assert_eq!(verdict(&errno, 0, 3), Action::Errno(1).ret());
+        assert_eq!(verdict(&errno, 1, 1), Action::Errno(1).ret());
+        assert_eq!(verdict(&errno, 0, 2), SECCOMP_RET_ALLOW);
+        assert_eq!(kill.len(), errno.len());
     }
 
     #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
@@ -119,6 +103,9 @@ fn default_filter_within_kernel_limit() {
         let prog = build_default();
         assert!(prog.len() <= MAX_LEN);
         assert!(prog.len() >= 5);
+        for &nr in DEFAULT_ALLOWLIST {
+            assert_eq!(verdict(&prog, AUDIT_ARCH, nr), SECCOMP_RET_ALLOW);
+        }
     }
 
     #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
@@ -15,7 +15,7 @@
 
 use std::io;
 
-use crate::bpf::{Instruction, MAX_LEN};
+use crate::bpf::{Action, Instruction, MAX_LEN};
 use crate::error::{Error, Result};
 use crate::filter;
 
@@ -56,6 +56,18 @@ pub fn install_default() -> Result<()> {
     install(&filter::build_default())
 }
 
+/// Install the default allowlist with `action` for syscalls outside it:
+/// [`Action::Log`] to audit which syscalls a workload needs,
+/// [`Action::Errno`] to fail them without killing the process.
+///
+/// # Errors
+///
+/// See [`install_default`].
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub fn install_default_with(action: Action) -> Result<()> {
+    install(&filter::build_default_with(action))
+}
+
 /// Install an arbitrary pre-built seccomp BPF program.
 ///
 /// Most callers want [`install_default`]. This variant exists for
@@ -0,0 +1,175 @@
+//! Pure-Rust interpreter for seccomp BPF programs.
+//!
+//! Runs a program against a synthetic `struct seccomp_data` the way the
+//! kernel would, so tests can check a compiled filter's verdicts without
+//! installing it. Supports the subset seccomp accepts for the opcodes
+//! this crate emits: absolute 32-bit loads, `AND K`, `JA`,
+//! `JEQ`/`JGT`/`JGE`/`JSET K` and `RET K`.
+
+use crate::bpf::{
+    BPF_ABS, BPF_ALU, BPF_AND, BPF_JA, BPF_JEQ, BPF_JGE, BPF_JGT, BPF_JMP, BPF_JSET, BPF_K, BPF_LD,
+    BPF_RET, BPF_W, Instruction, SECCOMP_DATA_LEN, decode,
+};
+use crate::error::{Error, Result};
+
+/// Synthetic `struct seccomp_data` for [`run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SeccompData {
+    /// Syscall number.
+    pub nr: u32,
+    /// `AUDIT_ARCH_*` of the calling convention.
+    pub arch: u32,
+    /// Instruction pointer at the syscall.
+    pub instruction_pointer: u64,
+    /// Syscall arguments.
+    pub args: [u64; 6],
+}
+
+impl SeccompData {
+    /// Syscall `nr` from `arch` with `args`.
+    #[must_use]
+    pub const fn new(arch: u32, nr: u32, args: [u64; 6]) -> Self {
+        Self {
+            nr,
+            arch,
+            instruction_pointer: 0,
+            args,
+        }
+    }
+
+    /// Native-endian byte image, as the kernel lays it out.
+    #[must_use]
+    pub fn to_bytes(self) -> [u8; SECCOMP_DATA_LEN] {
+        let mut out = [0; SECCOMP_DATA_LEN];
+        let (nr, rest) = out.split_at_mut(4);
+        nr.copy_from_slice(&self.nr.to_ne_bytes());
+        let (arch, rest) = rest.split_at_mut(4);
+        arch.copy_from_slice(&self.arch.to_ne_bytes());
+        let words = std::iter::once(self.instruction_pointer).chain(self.args);
+        for (chunk, word) in rest.chunks_exact_mut(8).zip(words) {
+            chunk.copy_from_slice(&word.to_ne_bytes());
+        }
+        out
+    }
+}
+
+/// Run `program` on `data` and return the filter's `SECCOMP_RET_*` value.
+///
+/// # Errors
+///
+/// [`Error::InvalidProgram`] for an unsupported opcode, a misaligned or
+/// out-of-range load, a jump past the end, or running off the end.
+pub fn run(program: &[Instruction], data: &SeccompData) -> Result<u32> {
+    trace(program, data).map(|(ret, _)| ret)
+}
+
+/// Like [`run`], also returning how many instructions were executed.
+///
+/// # Errors
+///
+/// See [`run`].
+pub fn trace(program: &[Instruction], data: &SeccompData) -> Result<(u32, usize)> {
+    let bytes = data.to_bytes();
+    let mut acc: u32 = 0;
+    let mut pc = 0;
+    let mut steps = 0;
+    loop {
+        let invalid = |reason| Error::InvalidProgram { pc, reason };
+        let &insn = program.get(pc).ok_or_else(|| invalid("ran off the end"))?;
+        let (code, jt, jf, k) = decode(insn);
+        steps += 1;
+        let skip = match code {
+            c if c == BPF_LD | BPF_W | BPF_ABS => {
+                acc = load(&bytes, k).ok_or_else(|| invalid("load outside seccomp_data"))?;
+                0
+            }
+            c if c == BPF_ALU | BPF_AND | BPF_K => {
+                acc &= k;
+                0
+            }
+            c if c == BPF_RET | BPF_K => return Ok((k, steps)),
+            c if c == BPF_JMP | BPF_JA => {
+                usize::try_from(k).map_err(|_| invalid("jump too far"))?
+            }
+            c if c == BPF_JMP | BPF_JEQ | BPF_K => branch(acc == k, jt, jf),
+            c if c == BPF_JMP | BPF_JGT | BPF_K => branch(acc > k, jt, jf),
+            c if c == BPF_JMP | BPF_JGE | BPF_K => branch(acc >= k, jt, jf),
+            c if c == BPF_JMP | BPF_JSET | BPF_K => branch(acc & k != 0, jt, jf),
+            _ => return Err(invalid("unsupported opcode")),
+        };
+        pc = pc
+            .checked_add(1 + skip)
+            .filter(|&next| next < program.len())
+            .ok_or_else(|| invalid("jump past the end"))?;
+    }
+}
+
+/// Offset taken by a conditional jump.
+fn branch(taken: bool, jt: u8, jf: u8) -> usize {
+    usize::from(if taken { jt } else { jf })
+}
+
+/// 32-bit native-endian word at `offset`; must be aligned and in range.
+fn load(bytes: &[u8; SECCOMP_DATA_LEN], offset: u32) -> Option<u32> {
+    let start = usize::try_from(offset).ok().filter(|o| o % 4 == 0)?;
+    let word = bytes.get(start..start.checked_add(4)?)?;
+    Some(u32::from_ne_bytes(word.try_into().ok()?))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use super::*;
+    use crate::bpf::{
+        SECCOMP_ARCH_OFFSET, SECCOMP_NR_OFFSET, SECCOMP_RET_ALLOW, SECCOMP_RET_KILL_PROCESS,
+        arg_offsets, instruction,
+    };
+
+    #[test]
+    fn loads_fields_at_kernel_offsets() {
+        let data = SeccompData::new(0xc000_003e, 16, [1, 2, 3, 4, 5, (7 << 32) | 6]);
+        let bytes = data.to_bytes();
+        assert_eq!(load(&bytes, SECCOMP_NR_OFFSET), Some(16));
+        assert_eq!(load(&bytes, SECCOMP_ARCH_OFFSET), Some(0xc000_003e));
+        let (lo, hi) = arg_offsets(5);
+        assert_eq!(load(&bytes, lo), Some(6));
+        assert_eq!(load(&bytes, hi), Some(7));
+        assert_eq!(load(&bytes, 2), None);
+        assert_eq!(load(&bytes, 64), None);
+    }
+
+    #[test]
+    fn executes_jumps_and_returns() {
+        let prog = [
+            instruction(BPF_LD | BPF_W | BPF_ABS, 0, 0, SECCOMP_NR_OFFSET),
+            instruction(BPF_JMP | BPF_JSET | BPF_K, 0, 1, 0x4000_0000),
+            instruction(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_KILL_PROCESS),
+            instruction(BPF_JMP | BPF_JA, 0, 0, 1),
+            instruction(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_KILL_PROCESS),
+            instruction(BPF_RET | BPF_K, 0, 0, SECCOMP_RET_ALLOW),
+        ];
+        let x32 = SeccompData::new(0, 0x4000_0001, [0; 6]);
+        assert_eq!(trace(&prog, &x32).unwrap(), (SECCOMP_RET_KILL_PROCESS, 3));
+        let native = SeccompData::new(0, 1, [0; 6]);
+        assert_eq!(trace(&prog, &native).unwrap(), (SECCOMP_RET_ALLOW, 4));
+    }
+
+    #[test]
+    fn rejects_malformed_programs() {
+        let data = SeccompData::default();
+        let fall_off = [instruction(BPF_LD | BPF_W | BPF_ABS, 0, 0, 0)];
+        assert!(matches!(
+            run(&fall_off, &data),
+            Err(Error::InvalidProgram { pc: 0, .. })
+        ));
+        let bad_load = [instruction(BPF_LD | BPF_W | BPF_ABS, 0, 0, 100)];
+        assert!(run(&bad_load, &data).is_err());
+        let bad_op = [instruction(0xff, 0, 0, 0)];
+        assert!(run(&bad_op, &data).is_err());
+        assert!(run(&[], &data).is_err());
+    }
+}
@@ -5,6 +5,11 @@
 //! through the `seccomp(2)` syscall with `SECCOMP_FILTER_FLAG_TSYNC` so
 //! every thread of the calling process inherits the filter atomically.
 //!
+//! Beyond the flat allowlist, [`rule::Filter`] expresses per-syscall
+//! argument conditions and per-rule actions, compiled into a
+//! binary-search program; [`interp`] evaluates programs in pure Rust
+//! for tests.
+//!
 //! The allowlist is intentionally broad — it exists to block things
 //! that would be catastrophic if the shim were compromised (`mount`,
 //! `ptrace`, `reboot`, `kexec_load`, `init_module`, `pivot_root`, …)
@@ -41,7 +46,10 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 pub mod bpf;
+mod compile;
 mod error;
+pub mod interp;
+pub mod rule;
 
 #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
 pub mod arch;
@@ -54,10 +62,12 @@
 ))]
 mod install;
 
+pub use bpf::Action;
 pub use error::{Error, Result};
+pub use rule::{ArgCmp, ArgOp, Filter, Rule};
 
 #[cfg(all(
     target_os = "linux",
     any(target_arch = "x86_64", target_arch = "aarch64")
 ))]
-pub use install::{default_allowlist_size, install, install_default};
+pub use install::{default_allowlist_size, install, install_default, install_default_with};
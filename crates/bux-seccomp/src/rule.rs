@@ -0,0 +1,289 @@
+//! Rule model for seccomp filters with argument-level conditions.
+//!
+//! A [`Filter`] maps syscalls to ordered [`Rule`]s. Each rule carries
+//! zero or more [`ArgCmp`] conditions (all must hold) and the
+//! [`Action`] returned when they do. Rules for the same syscall are
+//! tried in insertion order; the first match wins, and a syscall with
+//! no matching rule gets the filter's default action.
+//!
+//! ```
+//! use bux_seccomp::Action;
+//! use bux_seccomp::rule::{ArgCmp, Filter, Rule};
+//!
+//! const IOCTL: u32 = 16;
+//! const KVM_RUN: u64 = 0xae80;
+//!
+//! let filter = Filter::new(0xc000_003e, Action::KillProcess)
+//!     .allow(&[0, 1, 3])
+//!     .rule(Rule::allow(IOCTL).arg(ArgCmp::eq(1, KVM_RUN)))
+//!     .rule(Rule::new(IOCTL, Action::Errno(25)));
+//! let program = filter.compile()?;
+//! # let _ = program;
+//! # Ok::<(), bux_seccomp::Error>(())
+//! ```
+
+use crate::bpf::{Action, Instruction, MAX_LEN, SECCOMP_ARG_COUNT};
+use crate::compile;
+use crate::error::{Error, Result};
+
+/// Comparison applied to one 64-bit syscall argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArgOp {
+    /// `arg == value`.
+    Eq(u64),
+    /// `arg & mask == value & mask`.
+    MaskedEq {
+        /// Bits of the argument that are compared.
+        mask: u64,
+        /// Expected value of those bits.
+        value: u64,
+    },
+    /// `arg < value` (unsigned).
+    Lt(u64),
+    /// `arg > value` (unsigned).
+    Gt(u64),
+}
+
+impl ArgOp {
+    /// Whether `arg` satisfies the comparison.
+    #[must_use]
+    pub const fn matches(self, arg: u64) -> bool {
+        match self {
+            Self::Eq(value) => arg == value,
+            Self::MaskedEq { mask, value } => arg & mask == value & mask,
+            Self::Lt(value) => arg < value,
+            Self::Gt(value) => arg > value,
+        }
+    }
+}
+
+/// A condition on `args[arg]` of the syscall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ArgCmp {
+    /// Argument index, `0..=5`.
+    pub arg: u8,
+    /// Comparison applied to the argument.
+    pub op: ArgOp,
+}
+
+impl ArgCmp {
+    /// Condition `op` on `args[arg]`.
+    #[must_use]
+    pub const fn new(arg: u8, op: ArgOp) -> Self {
+        Self { arg, op }
+    }
+
+    /// `args[arg] == value`.
+    #[must_use]
+    pub const fn eq(arg: u8, value: u64) -> Self {
+        Self::new(arg, ArgOp::Eq(value))
+    }
+
+    /// `args[arg] & mask == value & mask`, e.g. "no namespace flags"
+    /// for `clone`.
+    #[must_use]
+    pub const fn masked_eq(arg: u8, mask: u64, value: u64) -> Self {
+        Self::new(arg, ArgOp::MaskedEq { mask, value })
+    }
+
+    /// `args[arg] < value` (unsigned).
+    #[must_use]
+    pub const fn lt(arg: u8, value: u64) -> Self {
+        Self::new(arg, ArgOp::Lt(value))
+    }
+
+    /// `args[arg] > value` (unsigned).
+    #[must_use]
+    pub const fn gt(arg: u8, value: u64) -> Self {
+        Self::new(arg, ArgOp::Gt(value))
+    }
+}
+
+/// What happens to one syscall when all of the rule's conditions hold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Rule {
+    /// Syscall number for the filter's audit arch.
+    pub syscall: u32,
+    /// Conditions that must all hold; empty matches unconditionally.
+    pub args: Vec<ArgCmp>,
+    /// Verdict when the rule matches.
+    pub action: Action,
+}
+
+impl Rule {
+    /// Unconditional rule returning `action` for `syscall`.
+    #[must_use]
+    pub const fn new(syscall: u32, action: Action) -> Self {
+        Self {
+            syscall,
+            args: Vec::new(),
+            action,
+        }
+    }
+
+    /// Unconditional allow for `syscall`.
+    #[must_use]
+    pub const fn allow(syscall: u32) -> Self {
+        Self::new(syscall, Action::Allow)
+    }
+
+    /// Add a condition (all conditions must hold).
+    #[must_use]
+    pub fn arg(mut self, cmp: ArgCmp) -> Self {
+        self.args.push(cmp);
+        self
+    }
+
+    /// Whether the rule applies to `nr` called with `args`.
+    #[must_use]
+    pub fn matches(&self, nr: u32, args: &[u64; 6]) -> bool {
+        self.syscall == nr
+            && self.args.iter().all(|c| {
+                args.get(usize::from(c.arg))
+                    .is_some_and(|&value| c.op.matches(value))
+            })
+    }
+}
+
+/// A complete seccomp policy: audit arch, ordered rules and the default
+/// action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    /// Syscalls from any other `AUDIT_ARCH_*` get the default action.
+    pub(crate) audit_arch: u32,
+    /// Verdict for syscalls no rule matches.
+    pub(crate) default_action: Action,
+    /// Rules in insertion order.
+    pub(crate) rules: Vec<Rule>,
+}
+
+impl Filter {
+    /// Empty filter for `audit_arch`; everything gets `default_action`.
+    #[must_use]
+    pub const fn new(audit_arch: u32, default_action: Action) -> Self {
+        Self {
+            audit_arch,
+            default_action,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Append a rule.
+    #[must_use]
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Append an unconditional allow for every syscall in `syscalls`.
+    #[must_use]
+    pub fn allow(mut self, syscalls: &[u32]) -> Self {
+        self.rules.extend(syscalls.iter().copied().map(Rule::allow));
+        self
+    }
+
+    /// The verdict this filter gives, evaluated directly on the rules.
+    ///
+    /// Reference semantics for the compiled program; see
+    /// [`crate::interp`] to run the program itself.
+    #[must_use]
+    pub fn evaluate(&self, arch: u32, nr: u32, args: &[u64; 6]) -> Action {
+        if arch != self.audit_arch {
+            return self.default_action;
+        }
+        self.rules
+            .iter()
+            .find(|r| r.matches(nr, args))
+            .map_or(self.default_action, |r| r.action)
+    }
+
+    /// Compile into a BPF program: a binary search over the syscall
+    /// numbers, then one block of argument checks per conditional
+    /// syscall.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::InvalidArgIndex`] if a condition names `args[6]` or later.
+    /// - [`Error::FilterTooLarge`] if the program exceeds the kernel limit.
+    pub fn compile(&self) -> Result<Vec<Instruction>> {
+        let bad_arg = self
+            .rules
+            .iter()
+            .flat_map(|r| &r.args)
+            .find(|c| c.arg >= SECCOMP_ARG_COUNT);
+        if let Some(cmp) = bad_arg {
+            return Err(Error::InvalidArgIndex(cmp.arg));
+        }
+        let program = compile::compile(self);
+        if program.len() > MAX_LEN {
+            return Err(Error::FilterTooLarge(program.len()));
+        }
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests are allowed to use unwrap and omit docs"
+)]
+mod tests {
+    use super::*;
+
+    const ARCH: u32 = 0xc000_003e;
+
+    #[test]
+    fn comparators() {
+        assert!(ArgOp::Eq(5).matches(5));
+        assert!(!ArgOp::Eq(5).matches(6));
+        assert!(
+            ArgOp::MaskedEq {
+                mask: 0xf0,
+                value: 0x30
+            }
+            .matches(0x3f)
+        );
+        assert!(
+            !ArgOp::MaskedEq {
+                mask: 0xf0,
+                value: 0x30
+            }
+            .matches(0x4f)
+        );
+        assert!(ArgOp::Lt(1 << 40).matches(u64::from(u32::MAX)));
+        assert!(!ArgOp::Lt(3).matches(3));
+        assert!(ArgOp::Gt(3).matches(u64::MAX));
+        assert!(!ArgOp::Gt(3).matches(3));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let filter = Filter::new(ARCH, Action::KillProcess)
+            .rule(Rule::allow(16).arg(ArgCmp::eq(1, 0xae80)))
+            .rule(Rule::new(16, Action::Errno(25)));
+        let mut args = [0; 6];
+        assert_eq!(filter.evaluate(ARCH, 16, &args), Action::Errno(25));
+        args[1] = 0xae80;
+        assert_eq!(filter.evaluate(ARCH, 16, &args), Action::Allow);
+        assert_eq!(filter.evaluate(ARCH, 17, &args), Action::KillProcess);
+        assert_eq!(filter.evaluate(0, 16, &args), Action::KillProcess);
+    }
+
+    #[test]
+    fn rejects_out_of_range_argument() {
+        let filter =
+            Filter::new(ARCH, Action::KillProcess).rule(Rule::allow(1).arg(ArgCmp::eq(6, 0)));
+        assert!(matches!(filter.compile(), Err(Error::InvalidArgIndex(6))));
+    }
+
+    #[test]
+    fn rejects_oversized_program() {
+        let list: Vec<u32> = (0..5000).collect();
+        let filter = Filter::new(ARCH, Action::KillProcess).allow(&list);
+        assert!(matches!(filter.compile(), Err(Error::FilterTooLarge(_))));
+    }
+}
@@ -5,7 +5,7 @@
 
 use bux_krun::ctx as sys;
 
-use crate::config::{ShimConfig, ShimDiskFormat, ShimNetConn};
+use crate::config::{ShimConfig, ShimDiskFormat, ShimNetConn, ShimSeccomp};
 use crate::error::{Error, Result};
 
 /// Prepared libkrun context ready for [`PreparedVm::start`].
@@ -91,15 +91,57 @@ pub fn start(ctx: u32) -> Result<()> {
     Ok(sys::start_enter(ctx)?)
 }
 
-/// `prepare` then `start`. Never returns on success.
+/// `prepare`, install the [`ShimConfig::seccomp`] filter, then `start`.
+/// Never returns on success.
+///
+/// The filter goes in last so libkrun's setup is not subject to it.
 ///
 /// # Errors
 ///
-/// Propagates prepare/start errors.
+/// Propagates prepare/start errors, and seccomp installation errors
+/// (including a filter requested on a platform without seccomp).
 pub fn boot(cfg: &ShimConfig) -> Result<()> {
-    prepare(cfg)?.start()
+    let vm = prepare(cfg)?;
+    install_seccomp(cfg.seccomp)?;
+    vm.start()
+}
+
+/// Installs the default VMM allowlist in `mode` on every thread.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn install_seccomp(mode: ShimSeccomp) -> Result<()> {
+    let action = match mode {
+        ShimSeccomp::Disabled => return Ok(()),
+        ShimSeccomp::Enforce => bux_seccomp::Action::KillProcess,
+        ShimSeccomp::Log => bux_seccomp::Action::Log,
+        ShimSeccomp::Errno => bux_seccomp::Action::Errno(EPERM),
+    };
+    Ok(bux_seccomp::install_default_with(action)?)
 }
 
+/// Rejects any filter request where seccomp is unavailable.
+#[cfg(not(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+fn install_seccomp(mode: ShimSeccomp) -> Result<()> {
+    match mode {
+        ShimSeccomp::Disabled => Ok(()),
+        _ => Err(Error::InvalidConfig(
+            "seccomp filtering is not supported on this platform".into(),
+        )),
+    }
+}
+
+/// errno returned by the filter in [`ShimSeccomp::Errno`] mode.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+const EPERM: u16 = 1;
+
 /// Apply every field of `cfg` to an existing libkrun context.
 fn apply_all(ctx: u32, cfg: &ShimConfig) -> Result<()> {
     if let Some(level) = cfg.log_level {
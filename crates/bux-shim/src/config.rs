@@ -71,6 +71,22 @@ pub enum ShimNetConn {
     UnixDgram,
 }
 
+/// Syscall filter the shim installs right before `krun_start_enter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ShimSeccomp {
+    /// No filter.
+    #[default]
+    Disabled,
+    /// Kill the VMM on any syscall outside the allowlist.
+    Enforce,
+    /// Allow syscalls outside the allowlist but record them in the audit log.
+    Log,
+    /// Fail syscalls outside the allowlist with `EPERM`.
+    Errno,
+}
+
 /// Complete configuration applied by the shim to libkrun.
 ///
 /// Written as JSON by Runtime; consumed only by `bux-shim`.
@@ -146,6 +162,9 @@ pub struct ShimConfig {
     /// Console log path on host.
     #[serde(default)]
     pub console_output: Option<String>,
+    /// Syscall filter for the VMM process (Linux only).
+    #[serde(default)]
+    pub seccomp: ShimSeccomp,
 }
 
 impl ShimConfig {
@@ -214,6 +233,7 @@ fn roundtrip_minimal() {
             nested_virt: None,
             snd_device: None,
             console_output: None,
+            seccomp: ShimSeccomp::Enforce,
         };
         let json = cfg.to_json().unwrap();
         let de = ShimConfig::from_json(&json).unwrap();
@@ -222,6 +242,21 @@ fn roundtrip_minimal() {
         assert_eq!(de.ports, vec!["8080:80".to_owned()]);
         assert!(de.network.is_none());
         assert_eq!(de.vsock_ports.first().map(|v| v.port), Some(1024));
+        assert_eq!(de.seccomp, ShimSeccomp::Enforce);
+    }
+
+    #[test]
+    fn seccomp_defaults_to_disabled() {
+        let log = br#"{"vcpus":1,"ram_mib":256,"seccomp":"log"}"#;
+        assert_eq!(
+            ShimConfig::from_json(log).unwrap().seccomp,
+            ShimSeccomp::Log
+        );
+        let unset = br#"{"vcpus":1,"ram_mib":256}"#;
+        assert_eq!(
+            ShimConfig::from_json(unset).unwrap().seccomp,
+            ShimSeccomp::Disabled
+        );
     }
 
     #[test]
@@ -252,6 +287,7 @@ fn network_variant_roundtrip() {
             nested_virt: None,
             snd_device: None,
             console_output: None,
+            seccomp: ShimSeccomp::Disabled,
         };
         let de = ShimConfig::from_json(&cfg.to_json().unwrap()).unwrap();
         let net = de.network.unwrap();
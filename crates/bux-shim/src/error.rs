@@ -26,4 +26,9 @@ pub enum Error {
     /// JSON (de)serialisation.
     #[error(transparent)]
     Json(#[from] serde_json::Error),
+
+    /// Syscall filter installation.
+    #[cfg(target_os = "linux")]
+    #[error(transparent)]
+    Seccomp(#[from] bux_seccomp::Error),
 }
@@ -13,7 +13,7 @@
 
 pub use apply::{PreparedVm, boot, prepare, start};
 pub use config::{
-    ShimConfig, ShimDiskFormat, ShimNetConn, ShimNetwork, ShimVirtioFs, ShimVsockPort,
+    ShimConfig, ShimDiskFormat, ShimNetConn, ShimNetwork, ShimSeccomp, ShimVirtioFs, ShimVsockPort,
 };
 pub use crash::{install_crash_capture, write_exit_error};
 pub use error::{Error, Result};
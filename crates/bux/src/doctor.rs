@@ -0,0 +1,677 @@
+//! Preflight host diagnostics behind `bux system doctor`.
+//!
+//! Where [`crate::HostInfo`] answers yes/no per layer, each [`Probe`] says
+//! what was found, why it matters and how to fix it: `/dev/kvm` access,
+//! the libkrun build, the shim and guest binaries, the Landlock ABI,
+//! seccomp, bubblewrap, user namespaces and cgroup delegation.
+//! [`probe_boot`] finishes with a test boot of a small VM.
+//!
+//! A [`ProbeStatus::Fail`] means VMs will not start with the default
+//! [`crate::SecurityOptions`]; [`ProbeStatus::Warn`] means a layer or
+//! feature some options need is missing.
+
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use bux_krun::Feature;
+use serde::{Deserialize, Serialize};
+
+use crate::guest::{self, ManagedGuestBinary};
+use crate::{ExecStart, Runtime, Vm, VmOptions};
+
+/// libkrun build-time features with their display names.
+pub const KRUN_FEATURES: &[(Feature, &str)] = &[
+    (Feature::Net, "net"),
+    (Feature::Blk, "blk"),
+    (Feature::Gpu, "gpu"),
+    (Feature::Snd, "snd"),
+    (Feature::Input, "input"),
+    (Feature::Efi, "efi"),
+    (Feature::Tee, "tee"),
+    (Feature::AmdSev, "amd-sev"),
+    (Feature::IntelTdx, "intel-tdx"),
+    (Feature::AwsNitro, "aws-nitro"),
+    (Feature::VirglResourceMap2, "virgl-resource-map2"),
+];
+
+/// libkrun features bux uses: name, what needs it, the libkrun build flag.
+const KRUN_USED: [(&str, &str, &str); 2] = [
+    ("blk", "disk-image root filesystems", "BLK=1"),
+    ("net", "virtio-net networking", "NET=1"),
+];
+
+/// cgroup v2 controllers [`crate::HostLimits`] may need.
+#[cfg(target_os = "linux")]
+const CGROUP_CONTROLLERS: [&str; 5] = ["cpu", "cpuset", "io", "memory", "pids"];
+
+/// Outcome of a [`Probe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ProbeStatus {
+    /// Works as bux needs it.
+    Pass,
+    /// Missing or limited; some options degrade or are unavailable.
+    Warn,
+    /// VMs will not start with the default options.
+    Fail,
+    /// Not run.
+    Skip,
+}
+
+impl ProbeStatus {
+    /// Lowercase name (`pass`, `warn`, `fail`, `skip`).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Warn => "warn",
+            Self::Fail => "fail",
+            Self::Skip => "skip",
+        }
+    }
+}
+
+impl fmt::Display for ProbeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Result of one doctor check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Probe {
+    /// Short probe name (`kvm`, `landlock`, `boot`, ...).
+    pub name: String,
+    /// Outcome.
+    pub status: ProbeStatus,
+    /// What was found and what it means for VMs.
+    pub explanation: String,
+    /// How to fix a `warn` or `fail`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl Probe {
+    /// A probe result without a remediation hint.
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        status: ProbeStatus,
+        explanation: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            explanation: explanation.into(),
+            remediation: None,
+        }
+    }
+
+    /// Set the remediation hint.
+    #[must_use]
+    pub fn remediation(mut self, hint: impl Into<String>) -> Self {
+        self.remediation = Some(hint.into());
+        self
+    }
+
+    /// Whether this probe failed.
+    #[must_use]
+    pub fn is_failure(&self) -> bool {
+        self.status == ProbeStatus::Fail
+    }
+}
+
+/// A passing probe.
+fn pass(name: &str, explanation: impl Into<String>) -> Probe {
+    Probe::new(name, ProbeStatus::Pass, explanation)
+}
+
+/// A warning with its fix.
+fn warn(name: &str, explanation: impl Into<String>, fix: impl Into<String>) -> Probe {
+    Probe::new(name, ProbeStatus::Warn, explanation).remediation(fix)
+}
+
+/// A failure with its fix.
+fn fail(name: &str, explanation: impl Into<String>, fix: impl Into<String>) -> Probe {
+    Probe::new(name, ProbeStatus::Fail, explanation).remediation(fix)
+}
+
+/// Run every host probe (all but the test boot) against `data_dir`.
+#[must_use]
+pub fn probe_host(data_dir: &Path) -> Vec<Probe> {
+    let mut probes = vec![
+        #[cfg(target_os = "linux")]
+        kvm(Path::new("/dev/kvm")),
+        #[cfg(not(target_os = "linux"))]
+        hypervisor(),
+        libkrun(),
+        shim(),
+        guest_binary(),
+        data_dir_probe(data_dir),
+    ];
+    #[cfg(target_os = "linux")]
+    {
+        let user_ns = user_namespaces(&|path| {
+            std::fs::read_to_string(path)
+                .ok()
+                .and_then(|s| s.trim().parse().ok())
+        });
+        let userns_ok = user_ns.status == ProbeStatus::Pass;
+        probes.extend([
+            landlock(bux_jail::checks::landlock_abi()),
+            seccomp(),
+            bwrap(userns_ok),
+            user_ns,
+            cgroups(),
+        ]);
+    }
+    probes.extend([mac(), netd()]);
+    probes
+}
+
+/// Test-boot `image` with one vCPU, run `true` in it, then remove it.
+///
+/// Creation uses the default [`crate::SecurityOptions`], so a host that
+/// only works under `allow_degraded` fails here.
+pub async fn probe_boot(rt: &Runtime, image: &str, timeout: Duration) -> Probe {
+    const NAME: &str = "boot";
+    let started = Instant::now();
+    let opts = VmOptions::from_image(image)
+        .vcpus(1)
+        .ram_mib(256)
+        .ready_timeout(timeout);
+    let vm = match rt.create(opts).await {
+        Ok(vm) => vm,
+        Err(e) => return fail(NAME, format!("{image} did not boot: {e}"), boot_fix(&e)),
+    };
+    let out = vm.exec_output(ExecStart::new("true")).await;
+    let elapsed = started.elapsed();
+    discard(rt, vm).await;
+    match out {
+        Ok(o) if o.code == 0 => pass(
+            NAME,
+            format!(
+                "booted {image} and ran `true` in {} ms",
+                elapsed.as_millis()
+            ),
+        ),
+        Ok(o) => fail(
+            NAME,
+            format!(
+                "{image} booted but `true` exited {}: {}",
+                o.code,
+                String::from_utf8_lossy(&o.stderr).trim()
+            ),
+            "check the image has a working /bin/true and the guest agent log (`bux logs`)",
+        ),
+        Err(e) => fail(
+            NAME,
+            format!("{image} booted but exec failed: {e}"),
+            "check the guest agent log (`bux logs`) and the bux-guest build",
+        ),
+    }
+}
+
+/// Remediation for a test VM that could not be created.
+const fn boot_fix(e: &crate::Error) -> &'static str {
+    if e.is_policy_denied() {
+        "allow the test VM in the host policy, or pick another --boot-image"
+    } else if matches!(e, crate::Error::SecurityUnavailable(_)) {
+        "fix the failed or warned security probes above (or set SecurityOptions.allow_degraded)"
+    } else {
+        "re-run with RUST_LOG=bux=debug; the shim's stderr is kept next to its config in the data dir"
+    }
+}
+
+/// Stop and remove the test VM (best-effort).
+async fn discard(rt: &Runtime, mut vm: crate::VmHandle) {
+    let id = vm.state().id.clone();
+    if let Err(e) = vm.stop().await {
+        tracing::warn!(vm_id = %id, error = %e, "doctor: stopping test VM failed");
+    }
+    drop(vm);
+    if let Err(e) = rt.remove(&id) {
+        tracing::warn!(vm_id = %id, error = %e, "doctor: removing test VM failed");
+    }
+}
+
+/// `/dev/kvm` exists, is a character device and is read-write for us.
+#[cfg(target_os = "linux")]
+fn kvm(path: &Path) -> Probe {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    const NAME: &str = "kvm";
+    let Ok(meta) = std::fs::metadata(path) else {
+        return fail(
+            NAME,
+            format!(
+                "{} is missing: hardware virtualization is off or KVM is not loaded",
+                path.display()
+            ),
+            "enable VT-x/AMD-V in firmware and `modprobe kvm_intel` (or kvm_amd); inside a VM, enable nested virtualization",
+        );
+    };
+    if !meta.file_type().is_char_device() {
+        return fail(
+            NAME,
+            format!("{} is not a character device", path.display()),
+            format!("recreate it: `mknod {} c 10 232`", path.display()),
+        );
+    }
+    let mode = meta.mode() & 0o7777;
+    match std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+    {
+        Ok(_) => pass(
+            NAME,
+            format!(
+                "{} is read-write (mode {mode:o}, gid {})",
+                path.display(),
+                meta.gid()
+            ),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => fail(
+            NAME,
+            format!(
+                "{} is not read-write for uid {} (mode {mode:o}, owner {}:{})",
+                path.display(),
+                nix::unistd::geteuid(),
+                meta.uid(),
+                meta.gid()
+            ),
+            format!(
+                "add the user to the group owning it (`usermod -aG kvm $USER`, gid {}) and log in again",
+                meta.gid()
+            ),
+        ),
+        Err(e) => fail(
+            NAME,
+            format!("cannot open {}: {e}", path.display()),
+            "check `dmesg` for KVM errors",
+        ),
+    }
+}
+
+/// Hypervisor.framework (macOS) or the platform equivalent.
+#[cfg(not(target_os = "linux"))]
+fn hypervisor() -> Probe {
+    const NAME: &str = "hypervisor";
+    if bux_jail::checks::check_host().virtualization {
+        pass(NAME, "hardware virtualization available")
+    } else {
+        fail(
+            NAME,
+            "no hardware virtualization (kern.hv_support is 0)",
+            "run on Apple Silicon or a Mac with VT-x, outside a VM without nested virtualization",
+        )
+    }
+}
+
+/// The linked libkrun answers feature queries and has the features bux uses.
+fn libkrun() -> Probe {
+    const NAME: &str = "libkrun";
+    let mut supported = Vec::new();
+    for &(feature, name) in KRUN_FEATURES {
+        match Vm::has_feature(feature) {
+            Ok(true) => supported.push(name),
+            Ok(false) => {}
+            Err(e) => {
+                return fail(
+                    NAME,
+                    format!("libkrun feature query failed: {e}"),
+                    "install the libkrun release this bux was built against",
+                );
+            }
+        }
+    }
+    let vcpus = Vm::max_vcpus().map_or_else(|_| "unknown".to_owned(), |n| n.to_string());
+    let list = if supported.is_empty() {
+        "none".to_owned()
+    } else {
+        supported.join(", ")
+    };
+    let explanation = format!("features: {list}; max vCPUs: {vcpus}");
+    let missing: Vec<_> = KRUN_USED
+        .iter()
+        .filter(|(name, ..)| !supported.contains(name))
+        .collect();
+    if missing.is_empty() {
+        return pass(NAME, explanation);
+    }
+    let needs: Vec<_> = missing
+        .iter()
+        .map(|(name, use_, _)| format!("{name} ({use_})"))
+        .collect();
+    let flags: Vec<_> = missing.iter().map(|(.., flag)| *flag).collect();
+    warn(
+        NAME,
+        format!("{explanation}; missing {}", needs.join(", ")),
+        format!("rebuild libkrun with {}", flags.join(" ")),
+    )
+}
+
+/// The `bux-shim` binary is found.
+fn shim() -> Probe {
+    match crate::util::find_binary("bux-shim", "BUX_SHIM_PATH") {
+        Ok(path) => pass("shim", path.display().to_string()),
+        Err(e) => fail(
+            "shim",
+            e.to_string(),
+            "install bux-shim next to bux or in PATH, or set BUX_SHIM_PATH",
+        ),
+    }
+}
+
+/// A static guest agent for the host arch is found.
+fn guest_binary() -> Probe {
+    const NAME: &str = "guest";
+    let fix = format!(
+        "install a static bux-guest-{} next to bux or in PATH, or set BUX_GUEST_PATH",
+        guest::linux_guest_target()
+    );
+    let Some(path) = guest::candidate_paths().into_iter().find(|p| p.exists()) else {
+        return fail(
+            NAME,
+            "no bux-guest binary in BUX_GUEST_PATH, next to bux, or in PATH",
+            fix,
+        );
+    };
+    if let Err(e) = crate::check_guest_binary(&path) {
+        return fail(NAME, format!("{}: {e}", path.display()), fix);
+    }
+    match ManagedGuestBinary::resolve() {
+        Ok(_) => pass(NAME, path.display().to_string()),
+        Err(e) => fail(NAME, e.to_string(), fix),
+    }
+}
+
+/// The data directory can be created and written.
+fn data_dir_probe(dir: &Path) -> Probe {
+    const NAME: &str = "data_dir";
+    let marker = dir.join(".doctor");
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&marker, b""))
+        .and_then(|()| std::fs::remove_file(&marker));
+    match result {
+        Ok(()) => pass(NAME, format!("{} is writable", dir.display())),
+        Err(e) => fail(
+            NAME,
+            format!("{} is not writable: {e}", dir.display()),
+            "fix its ownership, or set BUX_HOME to a writable directory",
+        ),
+    }
+}
+
+/// Landlock ABI level, by the rule kinds it can enforce.
+#[cfg(target_os = "linux")]
+fn landlock(abi: bux_jail::LandlockAbi) -> Probe {
+    const NAME: &str = "landlock";
+    if !abi.is_supported() {
+        return fail(
+            NAME,
+            "Landlock is unavailable, so VMs with default options are refused (fail-closed)",
+            "use Linux 5.13+ with `landlock` in the `lsm=` boot parameter, or set SecurityOptions.allow_degraded",
+        );
+    }
+    let mut missing = Vec::new();
+    if !abi.supports_network() {
+        missing.push("TCP port rules (ABI v4, Linux 6.7)");
+    }
+    if !abi.supports_scoping() {
+        missing.push("IPC scoping (ABI v6, Linux 6.12)");
+    }
+    if missing.is_empty() {
+        pass(
+            NAME,
+            format!("ABI {abi}: filesystem, network and IPC rules"),
+        )
+    } else {
+        warn(
+            NAME,
+            format!("ABI {abi} cannot enforce {}", missing.join(" or ")),
+            "upgrade to Linux 6.12 or newer",
+        )
+    }
+}
+
+/// The kernel reports a seccomp mode for this process.
+#[cfg(target_os = "linux")]
+fn seccomp() -> Probe {
+    const NAME: &str = "seccomp";
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    if status.lines().any(|l| l.starts_with("Seccomp:")) {
+        pass(NAME, "seccomp BPF filtering available")
+    } else {
+        fail(
+            NAME,
+            "the kernel has no seccomp support, so VMs with default options are refused",
+            "use a kernel built with CONFIG_SECCOMP_FILTER, or set SecurityOptions.seccomp to off",
+        )
+    }
+}
+
+/// bubblewrap is installed and can create namespaces.
+#[cfg(target_os = "linux")]
+fn bwrap(user_namespaces: bool) -> Probe {
+    use std::os::unix::fs::MetadataExt;
+
+    const NAME: &str = "bwrap";
+    let Some(path) = bux_jail::checks::bwrap_path() else {
+        return warn(
+            NAME,
+            "bubblewrap not found: the shim runs without namespace isolation",
+            "install bubblewrap (`apt install bubblewrap` or `dnf install bubblewrap`)",
+        );
+    };
+    let setuid = std::fs::metadata(path).is_ok_and(|m| m.mode() & 0o4000 != 0);
+    if setuid {
+        pass(NAME, format!("{} (setuid root)", path.display()))
+    } else if user_namespaces || nix::unistd::geteuid().is_root() {
+        pass(NAME, path.display().to_string())
+    } else {
+        fail(
+            NAME,
+            format!(
+                "{} is not setuid and unprivileged user namespaces are unavailable, so it cannot sandbox the shim",
+                path.display()
+            ),
+            format!(
+                "enable user namespaces (see the user_namespaces probe) or `chmod u+s {}`",
+                path.display()
+            ),
+        )
+    }
+}
+
+/// Unprivileged user namespaces, from the sysctls read by `sysctl`.
+#[cfg(target_os = "linux")]
+fn user_namespaces(sysctl: &dyn Fn(&str) -> Option<u64>) -> Probe {
+    const NAME: &str = "user_namespaces";
+    let blocked = if sysctl("/proc/sys/user/max_user_namespaces") == Some(0) {
+        Some((
+            "user.max_user_namespaces is 0",
+            "`sysctl -w user.max_user_namespaces=15000`",
+        ))
+    } else if sysctl("/proc/sys/kernel/unprivileged_userns_clone") == Some(0) {
+        Some((
+            "kernel.unprivileged_userns_clone is 0",
+            "`sysctl -w kernel.unprivileged_userns_clone=1`",
+        ))
+    } else if sysctl("/proc/sys/kernel/apparmor_restrict_unprivileged_userns") == Some(1) {
+        Some((
+            "AppArmor restricts unprivileged user namespaces",
+            "grant `userns` to bwrap in an AppArmor profile, or `sysctl -w kernel.apparmor_restrict_unprivileged_userns=0`",
+        ))
+    } else {
+        None
+    };
+    match blocked {
+        Some((why, fix)) => warn(
+            NAME,
+            format!("{why}: SecurityOptions.user_namespace cannot be enforced"),
+            fix,
+        ),
+        None => pass(NAME, "unprivileged user namespaces enabled"),
+    }
+}
+
+/// A writable cgroup v2 subtree with the controllers host limits use.
+#[cfg(target_os = "linux")]
+fn cgroups() -> Probe {
+    const NAME: &str = "cgroups";
+    let Some(placement) = bux_cgroup::Placement::detect() else {
+        return warn(
+            NAME,
+            "no writable cgroup v2 subtree: VMs with host limits are refused",
+            "run as root, or inside a systemd user session (`systemd-run --user --scope bux ...`)",
+        );
+    };
+    let kind = if matches!(placement, bux_cgroup::Placement::Delegated(_)) {
+        "delegated"
+    } else {
+        "root"
+    };
+    let parent = placement.dir().parent().unwrap_or_else(|| placement.dir());
+    let available = std::fs::read_to_string(parent.join("cgroup.controllers")).unwrap_or_default();
+    let missing = missing_controllers(&available);
+    if missing.is_empty() {
+        pass(NAME, format!("{} ({kind})", placement.dir().display()))
+    } else {
+        warn(
+            NAME,
+            format!(
+                "{} ({kind}) lacks controllers: {}",
+                placement.dir().display(),
+                missing.join(", ")
+            ),
+            "delegate them: `systemctl edit user@.service` with `[Service] Delegate=cpu cpuset io memory pids`",
+        )
+    }
+}
+
+/// Controllers in [`CGROUP_CONTROLLERS`] absent from a `cgroup.controllers` line.
+#[cfg(target_os = "linux")]
+fn missing_controllers(available: &str) -> Vec<&'static str> {
+    let available: Vec<_> = available.split_whitespace().collect();
+    CGROUP_CONTROLLERS
+        .into_iter()
+        .filter(|c| !available.contains(c))
+        .collect()
+}
+
+/// Mandatory access control (`AppArmor`, `SELinux` or Seatbelt).
+fn mac() -> Probe {
+    const NAME: &str = "mac";
+    if bux_jail::checks::check_host().mandatory_access_control {
+        pass(NAME, "mandatory access control available")
+    } else {
+        warn(
+            NAME,
+            "no AppArmor or SELinux: no mandatory access control around the shim",
+            "enable AppArmor or SELinux in the kernel and distribution",
+        )
+    }
+}
+
+/// The `bux-netd` helper is found.
+fn netd() -> Probe {
+    match crate::util::find_binary("bux-netd", "BUX_NETD_PATH") {
+        Ok(path) => pass("netd", path.display().to_string()),
+        Err(e) => warn(
+            "netd",
+            format!("{e}: SecurityOptions.network_helper is unavailable"),
+            "install bux-netd next to bux or in PATH, or set BUX_NETD_PATH",
+        ),
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_serializes_without_empty_remediation() {
+        let ok = serde_json::to_value(pass("shim", "/usr/bin/bux-shim")).unwrap();
+        assert_eq!(ok.get("status").unwrap(), "pass");
+        assert!(ok.get("remediation").is_none());
+        let bad = fail("kvm", "missing", "modprobe kvm");
+        assert!(bad.is_failure());
+        assert_eq!(
+            serde_json::to_value(&bad)
+                .unwrap()
+                .get("remediation")
+                .unwrap(),
+            "modprobe kvm"
+        );
+        assert_eq!(format!("[{:<5}]", ProbeStatus::Warn), "[warn ]");
+    }
+
+    #[test]
+    fn data_dir_must_be_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = data_dir_probe(&dir.path().join("nested"));
+        assert_eq!(probe.status, ProbeStatus::Pass);
+        assert!(!dir.path().join("nested/.doctor").exists());
+        let file = dir.path().join("file");
+        std::fs::write(&file, b"").unwrap();
+        assert!(data_dir_probe(&file.join("sub")).is_failure());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn kvm_explains_missing_or_wrong_device() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = kvm(&dir.path().join("kvm"));
+        assert!(missing.is_failure());
+        assert!(missing.remediation.unwrap().contains("modprobe"));
+        let file = dir.path().join("file");
+        std::fs::write(&file, b"").unwrap();
+        assert!(kvm(&file).explanation.contains("character device"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn landlock_grades_by_abi() {
+        use bux_jail::LandlockAbi;
+
+        assert!(landlock(LandlockAbi::UNSUPPORTED).is_failure());
+        let v3 = landlock(LandlockAbi::new(3));
+        assert_eq!(v3.status, ProbeStatus::Warn);
+        assert!(v3.explanation.contains("TCP") && v3.explanation.contains("IPC"));
+        let v5 = landlock(LandlockAbi::new(5));
+        assert!(!v5.explanation.contains("TCP"));
+        assert_eq!(landlock(LandlockAbi::LATEST).status, ProbeStatus::Pass);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn user_namespaces_name_the_blocking_sysctl() {
+        let open = user_namespaces(&|_| None);
+        assert_eq!(open.status, ProbeStatus::Pass);
+        let apparmor = user_namespaces(&|p| {
+            p.ends_with("apparmor_restrict_unprivileged_userns")
+                .then_some(1)
+        });
+        assert_eq!(apparmor.status, ProbeStatus::Warn);
+        assert!(apparmor.explanation.contains("AppArmor"));
+        let zero = user_namespaces(&|p| p.ends_with("max_user_namespaces").then_some(0));
+        assert!(zero.remediation.unwrap().contains("max_user_namespaces"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reports_missing_cgroup_controllers() {
+        assert!(missing_controllers("cpuset cpu io memory hugetlb pids rdma").is_empty());
+        assert_eq!(missing_controllers("memory pids"), ["cpu", "cpuset", "io"]);
+    }
+}
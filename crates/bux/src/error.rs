@@ -49,14 +49,21 @@ pub enum Error {
     #[error("secrets required: re-supply with start_with(StartOptions {{ secrets, .. }})")]
     SecretsRequired,
 
-    /// Secrets were requested but virtio-net / gvproxy is disabled.
-    #[error("secrets require virtio_net (gvproxy MITM); enable virtio_net or omit secrets")]
+    /// Secrets or HTTP rules were requested but virtio-net / gvproxy is disabled.
+    #[error(
+        "secrets and HTTP rules require virtio_net (gvproxy MITM); enable virtio_net or omit them"
+    )]
     SecretsNeedVirtioNet,
 
     /// A requested security layer is unavailable and degraded mode is not allowed (K22).
     #[error("{0}")]
     SecurityUnavailable(String),
 
+    /// VM options break the runtime's host policy; lists every violation.
+    #[cfg(unix)]
+    #[error("host policy denied: {}", join_violations(.0))]
+    PolicyDenied(Vec<crate::policy::PolicyViolation>),
+
     /// A `libkrun` FFI call failed or a string argument contained an
     /// interior NUL byte. Details are carried by [`bux_krun::Error`].
     #[error(transparent)]
@@ -138,7 +145,17 @@ pub const fn is_user_error(&self) -> bool {
                 | Self::InvalidState(_)
                 | Self::SecretsRequired
                 | Self::SecretsNeedVirtioNet
-        )
+        ) || self.is_policy_denied()
+    }
+
+    /// Returns `true` if the runtime's host policy rejected the request.
+    #[must_use]
+    pub const fn is_policy_denied(&self) -> bool {
+        #[cfg(unix)]
+        if let Self::PolicyDenied(_) = self {
+            return true;
+        }
+        false
     }
 
     /// Returns `true` if this is a transient error that may succeed on retry.
@@ -154,6 +171,16 @@ pub const fn is_fatal(&self) -> bool {
     }
 }
 
+/// Renders policy violations as one `; `-separated line.
+#[cfg(unix)]
+fn join_violations(violations: &[crate::policy::PolicyViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
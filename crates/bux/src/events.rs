@@ -1,8 +1,10 @@
 //! Audit event system for observable VM lifecycle operations.
 //!
 //! Events are emitted at key lifecycle points (create, start, stop, exec,
-//! snapshot, file copy) and delivered to registered [`EventListener`]
-//! implementations.
+//! snapshot, file copy, network update) and delivered to registered [`EventListener`]
+//! implementations. Network activity (DNS, TCP connections, egress
+//! denials) is drained from each VM's network backend a few times a
+//! second and delivered as [`AuditEventKind::NetworkActivity`].
 //!
 //! The built-in [`RingBufferListener`] stores the most recent N events
 //! in a bounded, mutex-protected ring buffer for querying.
@@ -10,6 +12,8 @@
 use std::sync::{Arc, Mutex};
 use std::time::SystemTime;
 
+use crate::ports::PublishedPort;
+
 /// Kinds of auditable events emitted by the bux runtime.
 #[derive(Debug, Clone)]
 #[non_exhaustive]
@@ -76,6 +80,29 @@ pub enum AuditEventKind {
         /// Path involved in the copy.
         path: String,
     },
+    /// A running VM's egress allow-list, published ports or secrets changed.
+    NetworkUpdated {
+        /// VM identifier.
+        box_id: String,
+        /// Allow-list rules added.
+        allowed: Vec<String>,
+        /// Allow-list rules removed.
+        revoked: Vec<String>,
+        /// Ports newly published.
+        published: Vec<PublishedPort>,
+        /// Host ports no longer published.
+        unpublished: Vec<u16>,
+        /// Whether the secret set was replaced.
+        secrets_rotated: bool,
+    },
+    /// A VM's network backend observed DNS, connection or denial activity.
+    #[cfg(unix)]
+    NetworkActivity {
+        /// VM identifier.
+        box_id: String,
+        /// What the backend observed.
+        event: bux_net::NetEventKind,
+    },
 }
 
 /// Direction of a file copy operation.
@@ -102,10 +129,13 @@ impl AuditEvent {
     /// Creates a new event with the current timestamp.
     #[must_use]
     pub fn now(kind: AuditEventKind) -> Self {
-        Self {
-            timestamp: SystemTime::now(),
-            kind,
-        }
+        Self::at(SystemTime::now(), kind)
+    }
+
+    /// Creates an event that occurred at `timestamp`.
+    #[must_use]
+    pub const fn at(timestamp: SystemTime, kind: AuditEventKind) -> Self {
+        Self { timestamp, kind }
     }
 }
 
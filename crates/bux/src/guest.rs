@@ -107,7 +107,7 @@ pub(crate) fn inject_into_disk(&self, image: &Path) -> Result<()> {
     }
 }
 
-fn candidate_paths() -> Vec<PathBuf> {
+pub(crate) fn candidate_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
     if let Some(explicit) = std::env::var_os("BUX_GUEST_PATH") {
@@ -143,7 +143,7 @@ fn candidate_paths() -> Vec<PathBuf> {
     ]
 }
 
-fn linux_guest_target() -> &'static str {
+pub(crate) fn linux_guest_target() -> &'static str {
     match std::env::consts::ARCH {
         "x86_64" => "x86_64-unknown-linux-musl",
         "aarch64" => "aarch64-unknown-linux-musl",
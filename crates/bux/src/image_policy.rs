@@ -0,0 +1,208 @@
+//! Image admission policy loaded from the data directory.
+//!
+//! [`crate::Runtime::open`] reads `image-policy.toml` (or
+//! `image-policy.json`) into the Runtime's initial
+//! [`bux_oci::VerifyPolicy`], so CLI users get the same signature and
+//! allow-list checks as embedders calling
+//! [`crate::Runtime::set_image_policy`], which replaces it. A missing file
+//! admits every image.
+//!
+//! ```toml
+//! default = { require = "reject" }
+//! digests = ["sha256:0123..."]
+//!
+//! [scopes."docker.io/library"]
+//! require = "accept"
+//!
+//! [scopes."ghcr.io/acme"]
+//! require = "signed"
+//! keys = ["keys/acme.pub"]
+//! ```
+//!
+//! Relative key paths are resolved against the policy file's directory.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use bux_oci::{Requirement, TrustedKey, VerifyPolicy};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// Image policy file names looked up in the data directory, in order.
+pub const IMAGE_POLICY_FILES: [&str; 2] = ["image-policy.toml", "image-policy.json"];
+
+/// File form of a [`VerifyPolicy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct ImagePolicy {
+    /// Rule for repositories no scope matches.
+    pub default: ImageRule,
+    /// Rules keyed by registry, namespace or repository; the longest
+    /// matching scope wins.
+    pub scopes: BTreeMap<String, ImageRule>,
+    /// Manifest digests admitted from any repository.
+    pub digests: Vec<String>,
+}
+
+/// What images in a scope must satisfy (file form of [`Requirement`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "require", rename_all = "snake_case", deny_unknown_fields)]
+#[non_exhaustive]
+pub enum ImageRule {
+    /// Admit without checks.
+    #[default]
+    Accept,
+    /// Refuse every image.
+    Reject,
+    /// Admit only these manifest digests.
+    Digests {
+        /// Allowed manifest digests.
+        digests: Vec<String>,
+    },
+    /// Admit images signed by any of these PEM public keys.
+    Signed {
+        /// Key files (ECDSA P-256 or Ed25519 `PUBLIC KEY` PEM).
+        keys: Vec<PathBuf>,
+    },
+}
+
+impl ImagePolicy {
+    /// Path of the image policy file in `data_dir`, if one exists.
+    #[must_use]
+    pub fn find(data_dir: &Path) -> Option<PathBuf> {
+        IMAGE_POLICY_FILES
+            .iter()
+            .map(|name| data_dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the image policy file in `data_dir` as a [`VerifyPolicy`], or
+    /// one admitting every image without a file.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::from_file`] and [`Self::to_verify_policy`].
+    pub fn load(data_dir: &Path) -> Result<VerifyPolicy> {
+        let Some(path) = Self::find(data_dir) else {
+            return Ok(VerifyPolicy::new());
+        };
+        Self::from_file(&path)?.to_verify_policy(path.parent().unwrap_or(data_dir))
+    }
+
+    /// Parses an image policy file: JSON for a `.json` extension, TOML
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Io`] if the file cannot be read, or
+    /// [`crate::Error::InvalidConfig`] if it does not parse.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| {
+            crate::Error::InvalidConfig(format!("image policy {}: {e}", path.display()))
+        })
+    }
+
+    /// Builds the policy, reading key files relative to `base`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidConfig`] if a key file cannot be read
+    /// or parsed.
+    pub fn to_verify_policy(&self, base: &Path) -> Result<VerifyPolicy> {
+        let mut policy = VerifyPolicy::new().default_requirement(self.default.requirement(base)?);
+        for (scope, rule) in &self.scopes {
+            policy = policy.require(scope.clone(), rule.requirement(base)?);
+        }
+        Ok(self
+            .digests
+            .iter()
+            .fold(policy, |policy, digest| policy.allow_digest(digest.clone())))
+    }
+}
+
+impl ImageRule {
+    /// The [`Requirement`] this rule stands for.
+    fn requirement(&self, base: &Path) -> Result<Requirement> {
+        Ok(match self {
+            Self::Accept => Requirement::Accept,
+            Self::Reject => Requirement::Reject,
+            Self::Digests { digests } => Requirement::Digests(digests.clone()),
+            Self::Signed { keys } => Requirement::Signed(
+                keys.iter()
+                    .map(|key| {
+                        TrustedKey::from_pem_file(base.join(key)).map_err(|e| {
+                            crate::Error::InvalidConfig(format!("image policy key: {e}"))
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests"
+)]
+mod tests {
+    use super::*;
+
+    const ED25519_PEM: &str = "-----BEGIN PUBLIC KEY-----\n\
+        MCowBQYDK2VwAyEAAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=\n\
+        -----END PUBLIC KEY-----\n";
+
+    #[test]
+    fn missing_file_admits_everything() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = ImagePolicy::load(dir.path()).unwrap();
+        assert_eq!(format!("{policy:?}"), format!("{:?}", VerifyPolicy::new()));
+    }
+
+    #[test]
+    fn loads_scopes_and_relative_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("keys")).unwrap();
+        std::fs::write(dir.path().join("keys/acme.pub"), ED25519_PEM).unwrap();
+        std::fs::write(
+            dir.path().join("image-policy.toml"),
+            r#"
+            default = { require = "reject" }
+            digests = ["sha256:aa"]
+
+            [scopes."ghcr.io/acme"]
+            require = "signed"
+            keys = ["keys/acme.pub"]
+            "#,
+        )
+        .unwrap();
+        let policy = format!("{:?}", ImagePolicy::load(dir.path()).unwrap());
+        assert!(policy.contains("default: Reject"), "{policy}");
+        assert!(policy.contains("\"ghcr.io/acme\", Signed"), "{policy}");
+        assert!(policy.contains("acme.pub"), "{policy}");
+        assert!(policy.contains("sha256:aa"), "{policy}");
+    }
+
+    #[test]
+    fn unreadable_key_is_a_config_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("image-policy.toml"),
+            "default = { require = \"signed\", keys = [\"missing.pub\"] }",
+        )
+        .unwrap();
+        assert!(matches!(
+            ImagePolicy::load(dir.path()),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+    }
+}
@@ -47,6 +47,8 @@
 #[cfg(unix)]
 mod client;
 mod disk;
+#[cfg(unix)]
+pub mod doctor;
 mod error;
 pub mod events;
 #[cfg(unix)]
@@ -54,7 +56,10 @@
 #[cfg(unix)]
 pub mod health;
 #[cfg(unix)]
+pub mod image_policy;
+#[cfg(unix)]
 pub mod lifecycle;
+pub mod limits;
 mod log_level;
 pub mod metrics;
 #[cfg(unix)]
@@ -63,6 +68,8 @@
 pub mod options;
 #[cfg(unix)]
 mod pipeline;
+#[cfg(unix)]
+pub mod policy;
 pub mod ports;
 #[cfg(unix)]
 pub mod process;
@@ -84,16 +91,27 @@
 #[cfg(unix)]
 pub mod watchdog;
 
+#[cfg(target_os = "linux")]
+pub use bux_cgroup::{CgroupStats, CpuStats, IoStat, MemoryEvents};
 #[cfg(unix)]
 pub use bux_jail::checks::{HostCapabilities, audit_isolation, check_guest_binary, check_host};
 #[cfg(target_os = "linux")]
 pub use bux_jail::credentials::CredentialConfig;
 #[cfg(unix)]
 pub use bux_jail::{
-    JailConfig, NoopSandbox, ResourceLimits, Sandbox, SandboxCapabilities, SandboxKind,
+    JailConfig, NamespaceOptions, NoopSandbox, ResourceLimits, Sandbox, SandboxCapabilities,
+    SandboxKind,
 };
 #[cfg(unix)]
 pub use bux_krun::{Feature, KernelFormat, LogStyle, SyncMode};
+#[cfg(unix)]
+pub use bux_net::dns::parse_upstream as parse_dns_upstream;
+#[cfg(unix)]
+pub use bux_net::{
+    DnsConfig, DnsRecord, EgressAction, EgressPolicy, EgressRule, EgressTarget, HttpPolicy,
+    HttpRule, NetEvent, NetEventKind, NetworkBackendKind, PortProtocol, QuotaAction,
+    TrafficDirection, TrafficLimits, parse_byte_size,
+};
 pub use bux_proto::{ExecStart, GUEST_BOOT_CONFIG_ENV, GuestBootConfig, GuestNetworkMode};
 #[cfg(target_os = "linux")]
 pub use bux_seccomp::Error as SeccompError;
@@ -106,6 +124,8 @@
 pub use disk::DiskFormat;
 #[cfg(unix)]
 pub use disk::{Disk, DiskManager, QcowHeader};
+#[cfg(unix)]
+pub use doctor::{Probe, ProbeStatus};
 pub use error::{Error, Result};
 pub use events::{
     AuditEvent, AuditEventKind, CopyDirection, EventDispatcher, EventListener, RingBufferListener,
@@ -113,12 +133,23 @@
 #[cfg(unix)]
 pub use health::{HealthCheckConfig, HealthCheckHandle};
 #[cfg(unix)]
-pub use lifecycle::{RecoverAction, SECRETS_RESUPPLY_ERROR, SweepReport, recover_action};
+pub use image_policy::{ImagePolicy, ImageRule};
+#[cfg(unix)]
+pub use lifecycle::{
+    QUOTA_STOP_ERROR, RecoverAction, SECRETS_RESUPPLY_ERROR, SweepReport, recover_action,
+};
+pub use limits::HostLimits;
 pub use log_level::{LogLevel, ParseLogLevelError};
 pub use metrics::{BoxMetrics, RuntimeMetrics};
 #[cfg(unix)]
+pub use net_manager::{NetworkAttachment, NetworkInfo, NetworkPatch};
+#[cfg(unix)]
 pub use options::{ImageRef, VmOptions};
-pub use ports::{BIND_ADDR, PortSpec, PublishedPort, parse_publish_spec, resolve_ports};
+#[cfg(unix)]
+pub use policy::{HostPolicy, PolicyRule, PolicyViolation};
+pub use ports::{
+    BIND_ADDR, PortSpec, PublishedPort, format_published_ports, parse_publish_spec, resolve_ports,
+};
 #[cfg(unix)]
 pub use process::{
     PHASE_A_LIMITS, PHASE_B_LIMITS, apply_workload_defaults, merge_env, parse_numeric_user,
@@ -127,7 +158,9 @@
 pub use runtime::{HealthStatus, RunOptions, Runtime, VmHandle, default_data_dir};
 #[cfg(unix)]
 pub use secrets::{SECRET_PLACEHOLDER_PREFIX, Secret, StartOptions, default_placeholder};
-pub use security::{HostInfo, LayerStatus, SecurityOptions, SecurityStatus};
+pub use security::{
+    HostInfo, LandlockRights, LayerStatus, SeccompMode, SecurityOptions, SecurityStatus, UidRange,
+};
 #[cfg(unix)]
 pub use snapshot::{SnapshotInfo, SnapshotManager};
 #[cfg(unix)]
@@ -44,6 +44,10 @@ pub const fn recover_action(
 pub const SECRETS_RESUPPLY_ERROR: &str =
     "secrets re-supply required: call start_with(StartOptions { secrets, .. })";
 
+/// Message stored in `last_error` when a VM is stopped for using up a
+/// network transfer quota ([`bux_net::QuotaAction::Stop`]).
+pub const QUOTA_STOP_ERROR: &str = "stopped: network transfer quota used up";
+
 /// Whether idle duration has exceeded the policy threshold.
 #[must_use]
 pub fn idle_expired(
@@ -0,0 +1,336 @@
+//! Host-side resource limits for a VM's VMM process ([`HostLimits`]).
+//!
+//! Unlike `vcpus` / `ram_mib`, which size the guest, these cap what the
+//! shim may consume on the host — including virtio-fs page cache and
+//! device threads the guest never sees. Applied through a per-VM cgroup
+//! v2 on Linux (see `bux-cgroup`). Disk throttles apply to the block
+//! devices holding the VM's disk images.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result};
+
+/// Highest accepted `io.weight`.
+const MAX_IO_WEIGHT: u16 = 10_000;
+
+/// Host-side limits for one VM. `None` leaves a value unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostLimits {
+    /// CPU bandwidth in cores (e.g. `1.5`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+    /// Memory limit in bytes (`memory.max`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_bytes: Option<u64>,
+    /// Memory soft limit in bytes (`memory.high`): throttle and reclaim
+    /// above it instead of OOM-killing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_high_bytes: Option<u64>,
+    /// Swap limit in bytes (`memory.swap.max`); `0` disables swap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_swap_bytes: Option<u64>,
+    /// Maximum tasks (processes + threads) in the VMM (`pids.max`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pids: Option<u64>,
+    /// Proportional block IO weight, `1..=10000` (`io.weight`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_weight: Option<u16>,
+    /// Disk image read throughput in bytes per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_read_bps: Option<u64>,
+    /// Disk image write throughput in bytes per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_write_bps: Option<u64>,
+    /// Disk image read operations per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_read_iops: Option<u64>,
+    /// Disk image write operations per second.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disk_write_iops: Option<u64>,
+    /// Host CPUs the VMM may run on, in kernel list syntax (`"0-3,6"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpuset: Option<String>,
+}
+
+impl HostLimits {
+    /// No limits.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            cpus: None,
+            memory_bytes: None,
+            memory_high_bytes: None,
+            memory_swap_bytes: None,
+            pids: None,
+            io_weight: None,
+            disk_read_bps: None,
+            disk_write_bps: None,
+            disk_read_iops: None,
+            disk_write_iops: None,
+            cpuset: None,
+        }
+    }
+
+    /// Caps CPU bandwidth at `cores`.
+    #[must_use]
+    pub const fn with_cpus(mut self, cores: f64) -> Self {
+        self.cpus = Some(cores);
+        self
+    }
+
+    /// Caps host memory.
+    #[must_use]
+    pub const fn with_memory(mut self, bytes: u64) -> Self {
+        self.memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Sets the memory soft limit.
+    #[must_use]
+    pub const fn with_memory_high(mut self, bytes: u64) -> Self {
+        self.memory_high_bytes = Some(bytes);
+        self
+    }
+
+    /// Caps swap usage.
+    #[must_use]
+    pub const fn with_memory_swap(mut self, bytes: u64) -> Self {
+        self.memory_swap_bytes = Some(bytes);
+        self
+    }
+
+    /// Caps the number of tasks.
+    #[must_use]
+    pub const fn with_pids(mut self, max: u64) -> Self {
+        self.pids = Some(max);
+        self
+    }
+
+    /// Sets the block IO weight.
+    #[must_use]
+    pub const fn with_io_weight(mut self, weight: u16) -> Self {
+        self.io_weight = Some(weight);
+        self
+    }
+
+    /// Caps disk image read throughput.
+    #[must_use]
+    pub const fn with_disk_read_bps(mut self, bytes_per_sec: u64) -> Self {
+        self.disk_read_bps = Some(bytes_per_sec);
+        self
+    }
+
+    /// Caps disk image write throughput.
+    #[must_use]
+    pub const fn with_disk_write_bps(mut self, bytes_per_sec: u64) -> Self {
+        self.disk_write_bps = Some(bytes_per_sec);
+        self
+    }
+
+    /// Caps disk image read operations per second.
+    #[must_use]
+    pub const fn with_disk_read_iops(mut self, ops: u64) -> Self {
+        self.disk_read_iops = Some(ops);
+        self
+    }
+
+    /// Caps disk image write operations per second.
+    #[must_use]
+    pub const fn with_disk_write_iops(mut self, ops: u64) -> Self {
+        self.disk_write_iops = Some(ops);
+        self
+    }
+
+    /// Pins the VMM to host `cpus` (e.g. `"0-3,6"`).
+    #[must_use]
+    pub fn with_cpuset(mut self, cpus: impl Into<String>) -> Self {
+        self.cpuset = Some(cpus.into());
+        self
+    }
+
+    /// Whether any disk throttle is set.
+    #[must_use]
+    pub const fn has_disk_limits(&self) -> bool {
+        self.disk_read_bps.is_some()
+            || self.disk_write_bps.is_some()
+            || self.disk_read_iops.is_some()
+            || self.disk_write_iops.is_some()
+    }
+
+    /// Whether no limit is set.
+    #[must_use]
+    pub const fn is_unlimited(&self) -> bool {
+        self.cpus.is_none()
+            && self.memory_bytes.is_none()
+            && self.memory_high_bytes.is_none()
+            && self.memory_swap_bytes.is_none()
+            && self.pids.is_none()
+            && self.io_weight.is_none()
+            && !self.has_disk_limits()
+            && self.cpuset.is_none()
+    }
+
+    /// Reject values the kernel would refuse.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidConfig`] for a non-positive CPU count, a
+    /// zero memory, pids or disk limit, an IO weight outside `1..=10000`,
+    /// or a malformed CPU list.
+    pub fn validate(&self) -> Result<()> {
+        if self.cpus.is_some_and(|c| !c.is_finite() || c <= 0.0) {
+            return Err(Error::InvalidConfig("cpu limit must be > 0".into()));
+        }
+        if self.memory_bytes == Some(0) {
+            return Err(Error::InvalidConfig("memory limit must be > 0".into()));
+        }
+        if self.pids == Some(0) {
+            return Err(Error::InvalidConfig("pids limit must be > 0".into()));
+        }
+        if self.io_weight.is_some_and(|w| w == 0 || w > MAX_IO_WEIGHT) {
+            return Err(Error::InvalidConfig(format!(
+                "io weight must be in 1..={MAX_IO_WEIGHT}"
+            )));
+        }
+        let disk = [
+            self.disk_read_bps,
+            self.disk_write_bps,
+            self.disk_read_iops,
+            self.disk_write_iops,
+        ];
+        if disk.contains(&Some(0)) {
+            return Err(Error::InvalidConfig("disk limits must be > 0".into()));
+        }
+        #[cfg(unix)]
+        if let Some(ref cpus) = self.cpuset {
+            bux_cgroup::validate_cpu_list(cpus).map_err(|e| Error::InvalidConfig(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Equivalent cgroup limits, with the disk throttles applied to each
+    /// of `disks` (whole-disk devices holding the VM's images).
+    #[cfg(unix)]
+    #[must_use]
+    pub fn to_cgroup(&self, disks: &[bux_cgroup::DeviceNumber]) -> bux_cgroup::ResourceLimits {
+        let mut b = bux_cgroup::ResourceLimits::builder();
+        if let Some(cores) = self.cpus {
+            b = b.cpu_cores(cores);
+        }
+        if let Some(bytes) = self.memory_bytes {
+            b = b.memory_bytes(bytes);
+        }
+        if let Some(bytes) = self.memory_high_bytes {
+            b = b.memory_high_bytes(bytes);
+        }
+        if let Some(bytes) = self.memory_swap_bytes {
+            b = b.memory_swap_bytes(bytes);
+        }
+        if let Some(max) = self.pids {
+            b = b.pids_max(max);
+        }
+        if let Some(weight) = self.io_weight {
+            b = b.io_weight(weight);
+        }
+        if self.has_disk_limits() {
+            for &device in disks {
+                b = b.io_max(self.io_max(device));
+            }
+        }
+        if let Some(ref cpus) = self.cpuset {
+            b = b.cpuset_cpus(cpus.clone());
+        }
+        b.build()
+    }
+
+    /// Disk throttles for `device`.
+    #[cfg(unix)]
+    const fn io_max(&self, device: bux_cgroup::DeviceNumber) -> bux_cgroup::IoMax {
+        let mut io = bux_cgroup::IoMax::new(device);
+        io.read_bps = self.disk_read_bps;
+        io.write_bps = self.disk_write_bps;
+        io.read_iops = self.disk_read_iops;
+        io.write_iops = self.disk_write_iops;
+        io
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builders_and_unlimited() {
+        assert!(HostLimits::new().is_unlimited());
+        let l = HostLimits::new()
+            .with_cpus(1.5)
+            .with_memory(1 << 30)
+            .with_pids(256)
+            .with_io_weight(50);
+        assert!(!l.is_unlimited());
+        assert!(l.validate().is_ok());
+        #[cfg(unix)]
+        {
+            let c = l.to_cgroup(&[bux_cgroup::DeviceNumber::new(8, 0)]);
+            assert_eq!(c.cpu_cores, Some(1.5));
+            assert_eq!(c.memory_bytes, Some(1 << 30));
+            assert_eq!(c.memory_swap_bytes, None);
+            assert_eq!(c.pids_max, Some(256));
+            assert_eq!(c.io_weight, Some(50));
+            assert!(c.io_max.is_empty());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn disk_throttles_apply_per_device() {
+        let l = HostLimits::new()
+            .with_disk_write_bps(1 << 20)
+            .with_disk_read_iops(500)
+            .with_memory_high(1 << 29)
+            .with_cpuset("0-1");
+        assert!(l.has_disk_limits());
+        assert!(l.validate().is_ok());
+        let disks = [
+            bux_cgroup::DeviceNumber::new(8, 0),
+            bux_cgroup::DeviceNumber::new(259, 0),
+        ];
+        let c = l.to_cgroup(&disks);
+        assert_eq!(c.memory_high_bytes, Some(1 << 29));
+        assert_eq!(c.cpuset_cpus.as_deref(), Some("0-1"));
+        let lines: Vec<_> = c.io_max.iter().map(|io| io.to_line()).collect();
+        assert_eq!(
+            lines,
+            [
+                "8:0 rbps=max wbps=1048576 riops=500 wiops=max",
+                "259:0 rbps=max wbps=1048576 riops=500 wiops=max",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_kernel_invalid_values() {
+        for bad in [
+            HostLimits::new().with_cpus(0.0),
+            HostLimits::new().with_cpus(f64::NAN),
+            HostLimits::new().with_memory(0),
+            HostLimits::new().with_pids(0),
+            HostLimits::new().with_io_weight(0),
+            HostLimits::new().with_io_weight(10_001),
+            HostLimits::new().with_disk_write_iops(0),
+            HostLimits::new().with_cpuset("3-1"),
+        ] {
+            assert!(bad.validate().is_err(), "{bad:?}");
+        }
+        assert!(HostLimits::new().with_memory_swap(0).validate().is_ok());
+    }
+
+    #[test]
+    fn serde_omits_unset() {
+        let json = serde_json::to_string(&HostLimits::new().with_pids(8)).unwrap();
+        assert_eq!(json, r#"{"pids":8}"#);
+        let back: HostLimits = serde_json::from_str("{}").unwrap();
+        assert!(back.is_unlimited());
+    }
+}
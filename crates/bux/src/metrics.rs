@@ -7,7 +7,7 @@
 //! Callers are responsible for computing deltas when needed —
 //! this matches the Prometheus / Tokio `RuntimeMetrics` convention.
 
-use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 
 /// Runtime-level metrics covering all VMs managed by this [`Runtime`](crate::Runtime).
 ///
@@ -103,7 +103,8 @@ pub fn set_disk_bytes_used(&self, bytes: u64) {
 /// Per-box metrics for a single VM instance.
 ///
 /// Typically embedded in a [`VmHandle`](crate::VmHandle) and updated
-/// as operations are performed on the VM.
+/// as operations are performed on the VM. Network counters are refreshed
+/// from the VM's network backend each time the handle returns them.
 #[derive(Debug)]
 pub struct BoxMetrics {
     /// Time from spawn to guest-agent-ready in milliseconds.
@@ -112,6 +113,12 @@ pub struct BoxMetrics {
     exec_count: AtomicU64,
     /// Duration of the most recent exec in milliseconds.
     last_exec_duration_ms: AtomicU64,
+    /// Bytes delivered to the guest by its network backend.
+    net_bytes_sent: AtomicU64,
+    /// Bytes received from the guest by its network backend.
+    net_bytes_received: AtomicU64,
+    /// Whether a network transfer quota has been used up.
+    net_quota_exceeded: AtomicBool,
 }
 
 impl Default for BoxMetrics {
@@ -128,6 +135,9 @@ pub const fn new() -> Self {
             boot_duration_ms: AtomicU64::new(0),
             exec_count: AtomicU64::new(0),
             last_exec_duration_ms: AtomicU64::new(0),
+            net_bytes_sent: AtomicU64::new(0),
+            net_bytes_received: AtomicU64::new(0),
+            net_quota_exceeded: AtomicBool::new(false),
         }
     }
 
@@ -146,6 +156,21 @@ pub fn last_exec_duration_ms(&self) -> u64 {
         self.last_exec_duration_ms.load(Ordering::Relaxed)
     }
 
+    /// Bytes the network backend delivered to the guest.
+    pub fn net_bytes_sent(&self) -> u64 {
+        self.net_bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Bytes the network backend received from the guest.
+    pub fn net_bytes_received(&self) -> u64 {
+        self.net_bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Whether a network transfer quota has been used up.
+    pub fn net_quota_exceeded(&self) -> bool {
+        self.net_quota_exceeded.load(Ordering::Relaxed)
+    }
+
     /// Records the boot duration.
     pub(crate) fn set_boot_duration_ms(&self, ms: u64) {
         self.boot_duration_ms.store(ms, Ordering::Relaxed);
@@ -157,6 +182,15 @@ pub(crate) fn on_exec_completed(&self, duration_ms: u64) {
         self.last_exec_duration_ms
             .store(duration_ms, Ordering::Relaxed);
     }
+
+    /// Records the latest network backend counters.
+    pub(crate) fn set_network(&self, net: &bux_net::NetworkMetrics) {
+        self.net_bytes_sent.store(net.bytes_sent, Ordering::Relaxed);
+        self.net_bytes_received
+            .store(net.bytes_received, Ordering::Relaxed);
+        self.net_quota_exceeded
+            .store(net.quota_exceeded, Ordering::Relaxed);
+    }
 }
 
 #[cfg(test)]
@@ -196,4 +230,19 @@ fn box_metrics_exec_tracking() {
         assert_eq!(m.exec_count(), 2);
         assert_eq!(m.last_exec_duration_ms(), 350);
     }
+
+    #[test]
+    fn box_metrics_network_snapshot() {
+        let m = BoxMetrics::new();
+        assert!(!m.net_quota_exceeded());
+        m.set_network(&bux_net::NetworkMetrics {
+            bytes_sent: 10,
+            bytes_received: 20,
+            quota_exceeded: true,
+            ..bux_net::NetworkMetrics::default()
+        });
+        assert_eq!(m.net_bytes_sent(), 10);
+        assert_eq!(m.net_bytes_received(), 20);
+        assert!(m.net_quota_exceeded());
+    }
 }
@@ -1,88 +1,706 @@
-//! Per-VM gvproxy lifecycle for managed Runtime.
+//! Per-VM network backend lifecycle for managed Runtime.
 //!
-//! Owns [`GvproxyBackend`] instances keyed by VM id. When a VM uses
-//! virtio-net (`VmConfig::virtio_net`), Runtime starts a backend before
-//! spawning the shim and drops it when the VM stops.
+//! Owns [`NetworkBackend`] instances keyed by VM id — [`GvproxyBackend`]
+//! by default, [`UserspaceBackend`] when `VmConfig::net_backend` selects
+//! it. When a VM uses virtio-net (`VmConfig::virtio_net`), Runtime starts
+//! a backend before spawning the shim and drops it when the VM stops.
 //!
 //! Managed default uses virtio-net (`virtio_net = true`): guest configures
 //! static eth0 via `BUX_GUEST_CONFIG`. Set `virtio_net = false` for TSI-only.
 //!
-//! `allow_net` empty means **unrestricted egress** (K20).
+//! `allow_net` empty means **unrestricted egress** (K20). Its entries are
+//! [`EgressRule`](bux_net::EgressRule) strings, turned into an
+//! [`EgressPolicy`] together with `VmConfig::egress_default_deny`.
+//!
+//! A running backend can be reconfigured with a [`NetworkPatch`] through
+//! `VmHandle::update_network`; the guest keeps running.
+//!
+//! Every backend records activity. A pump thread per VM drains it every
+//! [`PUMP_INTERVAL`], emits each [`NetEvent`] as
+//! [`AuditEventKind::NetworkActivity`] and appends it to
+//! `{socks_dir}/{id}.net.jsonl`, which rotates to `.net.jsonl.1` past
+//! [`ACTIVITY_LOG_MAX`]. The log outlives the backend so
+//! [`NetworkManager::activity`] works from another process and after
+//! the VM stops; it is removed with the VM.
+//!
+//! `VmConfig::traffic_limits` are enforced by the backend. When a quota
+//! with [`QuotaAction::Stop`] runs out, the pump records
+//! [`QUOTA_STOP_ERROR`](crate::QUOTA_STOP_ERROR) as the VM's last error
+//! and sends its shim `SIGTERM`; the Runtime reconciles the status as for
+//! any exited shim.
+//!
+//! Named private networks ([`NetworkManager::create_network`]) join VMs
+//! on one [`Segment`]. Each member gets a unique address and hostname
+//! ([`NetworkManager::assign`], persisted in `VmConfig::network`); its
+//! backend listens at `{id}.uplink.sock` and the engine attaches to the
+//! segment port at `{id}.net.sock`, so traffic between members never
+//! reaches a backend. Segments live in this process, so members must be
+//! started by the same Runtime.
+//!
+//! With `SecurityOptions::network_helper` the backend runs in a `bux-netd`
+//! helper spawned through `bux-jail` instead of this process, so the
+//! packet path, the MITM CA key and secret values stay out of the
+//! Runtime's memory. The helper sees only its own directory
+//! (`{socks_dir}/{id}.netd/`, holding its control and backend sockets)
+//! plus read-only system paths, runs under seccomp, and is driven over
+//! the [`bux_net::helper`] control protocol.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::Ipv4Addr;
+use std::os::unix::net::UnixListener;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
 
-use bux_net::{ConnectionType, GvproxyBackend, NetworkBackend, NetworkConfig};
+use bux_net::segment::{free_host, valid_label};
+use bux_net::{
+    ConnectionType, EgressPolicy, GuestAddress, GvproxyBackend, HelperBackend, NetEvent,
+    NetEventKind, NetworkBackend, NetworkBackendKind, NetworkConfig, NetworkEndpoint,
+    NetworkMetrics, NetworkUpdate, PortForward, QuotaAction, Segment, SegmentMember, SegmentPort,
+    UserspaceBackend,
+};
 use bux_shim::{ShimNetConn, ShimNetwork};
+use nix::sys::signal::{self, Signal};
+use nix::unistd::Pid;
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
 use crate::Result;
-use crate::secrets::LiveSecrets;
+use crate::events::{AuditEvent, AuditEventKind, EventDispatcher};
+use crate::ports::{PortSpec, PublishedPort, resolve_ports};
+use crate::secrets::{LiveSecrets, Secret};
+use crate::security::{LayerStatus, SecurityOptions};
+use crate::state::{StateDb, VmConfig};
+
+/// Converts resolved published ports into backend forwards.
+///
+/// # Errors
+///
+/// Returns [`crate::Error::InvalidConfig`] if a bind address does not parse.
+pub(crate) fn port_forwards(published: &[PublishedPort]) -> Result<Vec<PortForward>> {
+    published
+        .iter()
+        .map(|port| {
+            Ok(PortForward::tcp(port.host, port.guest)
+                .with_bind(port.bind_ip()?)
+                .with_protocol(port.protocol))
+        })
+        .collect()
+}
+
+/// Builds the egress policy for persisted `allow_net` rules and default.
+///
+/// # Errors
+///
+/// Returns [`crate::Error::Net`] if a rule does not parse.
+pub(crate) fn egress_policy(rules: &[String], default_deny: Option<bool>) -> Result<EgressPolicy> {
+    let policy = EgressPolicy::parse(rules)?;
+    Ok(match default_deny {
+        Some(deny) => policy.with_default_deny(deny),
+        None => policy,
+    })
+}
+
+/// Changes to apply to a running VM's network backend.
+///
+/// Built with chained calls and applied by `VmHandle::update_network`.
+/// Revocations and unpublishes run before additions, so a rule or host
+/// port can be replaced in one patch.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct NetworkPatch {
+    /// Egress rules ([`EgressRule`](bux_net::EgressRule) syntax) to add.
+    pub allow: Vec<String>,
+    /// Allow-list rules to remove.
+    pub revoke: Vec<String>,
+    /// Ports to publish (ephemeral host ports are resolved on apply).
+    pub publish: Vec<PortSpec>,
+    /// Host ports to stop publishing.
+    pub unpublish: Vec<u16>,
+    /// Replacement secret set (requires the VM to hold live secrets).
+    pub secrets: Option<Vec<Secret>>,
+}
+
+impl NetworkPatch {
+    /// Empty patch.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an allow-list rule.
+    #[must_use]
+    pub fn allow(mut self, rule: impl Into<String>) -> Self {
+        self.allow.push(rule.into());
+        self
+    }
+
+    /// Remove an allow-list rule.
+    #[must_use]
+    pub fn revoke(mut self, rule: impl Into<String>) -> Self {
+        self.revoke.push(rule.into());
+        self
+    }
+
+    /// Publish a port.
+    #[must_use]
+    pub fn publish(mut self, spec: PortSpec) -> Self {
+        self.publish.push(spec);
+        self
+    }
+
+    /// Stop publishing the given host port.
+    #[must_use]
+    pub fn unpublish(mut self, host_port: u16) -> Self {
+        self.unpublish.push(host_port);
+        self
+    }
+
+    /// Replace the MITM secret set (the CA is kept).
+    #[must_use]
+    pub fn rotate_secrets(mut self, secrets: Vec<Secret>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Whether the patch changes nothing.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.allow.is_empty()
+            && self.revoke.is_empty()
+            && self.publish.is_empty()
+            && self.unpublish.is_empty()
+            && self.secrets.is_none()
+    }
+
+    /// Allow-list after applying revocations, then additions (deduplicated).
+    pub(crate) fn apply_allow(&self, current: &[String]) -> Vec<String> {
+        let mut rules: Vec<String> = current
+            .iter()
+            .filter(|rule| !self.revoke.contains(rule))
+            .cloned()
+            .collect();
+        for rule in &self.allow {
+            if !rules.contains(rule) {
+                rules.push(rule.clone());
+            }
+        }
+        rules
+    }
+
+    /// Published ports after applying unpublishes, then new publishes.
+    ///
+    /// Returns the full list and the newly published entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidConfig`] if an unpublished port is not
+    /// published or a new host port is already taken for the same protocol,
+    /// and I/O errors from
+    /// ephemeral port probing.
+    pub(crate) fn apply_ports(
+        &self,
+        current: &[PublishedPort],
+    ) -> Result<(Vec<PublishedPort>, Vec<PublishedPort>)> {
+        for host in &self.unpublish {
+            if !current.iter().any(|port| port.host == *host) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "host port {host} is not published"
+                )));
+            }
+        }
+        let mut ports: Vec<PublishedPort> = current
+            .iter()
+            .filter(|port| !self.unpublish.contains(&port.host))
+            .cloned()
+            .collect();
+        let added = resolve_ports(&self.publish)?;
+        for port in &added {
+            if ports.iter().any(|existing| existing.conflicts_with(port)) {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "host port {}/{} is already published",
+                    port.host, port.protocol
+                )));
+            }
+            ports.push(port.clone());
+        }
+        Ok((ports, added))
+    }
+}
+
+/// Metadata for a named private network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct NetworkInfo {
+    /// Unique name (a DNS label; also the members' search domain).
+    pub name: String,
+    /// Creation time.
+    pub created_at: SystemTime,
+}
+
+/// A VM's membership in a named private network.
+///
+/// `hostname` and `ip` are filled in at spawn when left unset and then
+/// persisted, so a member keeps its identity across restarts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct NetworkAttachment {
+    /// Network name (see [`NetworkManager::create_network`]).
+    pub network: String,
+    /// Hostname other members resolve (default: VM name, else id prefix).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Address inside `192.168.127.0/24` (default: lowest free).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip: Option<Ipv4Addr>,
+}
+
+impl NetworkAttachment {
+    /// Join `network` with an assigned hostname and address.
+    #[must_use]
+    pub fn new(network: impl Into<String>) -> Self {
+        Self {
+            network: network.into(),
+            hostname: None,
+            ip: None,
+        }
+    }
+
+    /// Request a specific hostname.
+    #[must_use]
+    pub fn hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Segment identity; requires a prior [`NetworkManager::assign`].
+    fn member(&self) -> Result<SegmentMember> {
+        let (Some(hostname), Some(ip)) = (&self.hostname, self.ip) else {
+            return Err(crate::Error::InvalidConfig(format!(
+                "no address assigned on network '{}'",
+                self.network
+            )));
+        };
+        Ok(SegmentMember::new(
+            hostname.clone(),
+            GuestAddress::host(host_octet(ip)),
+        ))
+    }
+}
+
+/// Last octet of a member address.
+const fn host_octet(ip: Ipv4Addr) -> u8 {
+    let [.., host] = ip.octets();
+    host
+}
+
+/// Converts a backend endpoint into the engine's virtio-net attachment.
+fn shim_network(endpoint: NetworkEndpoint) -> Result<ShimNetwork> {
+    let NetworkEndpoint::UnixSocket {
+        path,
+        connection_type,
+        mac_address,
+    } = endpoint
+    else {
+        return Err(crate::Error::InvalidConfig(
+            "unsupported network endpoint kind".into(),
+        ));
+    };
+    let connection = match connection_type {
+        ConnectionType::UnixStream => ShimNetConn::UnixStream,
+        ConnectionType::UnixDgram => ShimNetConn::UnixDgram,
+        _ => {
+            return Err(crate::Error::InvalidConfig(
+                "unsupported network connection type".into(),
+            ));
+        }
+    };
+    Ok(ShimNetwork {
+        socket_path: path,
+        connection,
+        mac: mac_address,
+    })
+}
+
+/// How often backend activity is drained.
+const PUMP_INTERVAL: Duration = Duration::from_millis(250);
+/// Size past which an activity log rotates.
+const ACTIVITY_LOG_MAX: u64 = 16 * 1024 * 1024;
+/// How long a network helper may take to connect and start its backend.
+const HELPER_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `bux-netd` binary when [`SecurityOptions::network_helper`] is set
+/// and the helper can run (Linux; `$BUX_NETD_PATH` overrides the
+/// lookup). A missing helper fails closed unless degraded security is
+/// allowed, in which case the backend runs in-process.
+fn helper_binary(sec: SecurityOptions) -> Result<Option<PathBuf>> {
+    if !sec.network_helper {
+        return Ok(None);
+    }
+    let found = if cfg!(target_os = "linux") {
+        crate::util::find_binary("bux-netd", "BUX_NETD_PATH")
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the network helper requires Linux",
+        ))
+    };
+    match found {
+        Ok(path) => Ok(Some(path)),
+        Err(e) if sec.allow_degraded => {
+            warn!(error = %e, "network helper unavailable; running the backend in-process");
+            Ok(None)
+        }
+        Err(e) => Err(crate::Error::SecurityUnavailable(format!(
+            "network helper required but unavailable: {e} (set SecurityOptions.allow_degraded to proceed)"
+        ))),
+    }
+}
+
+/// Status of a helper that spawned in `sandbox` with the given Landlock
+/// and MAC outcomes: enforced only inside a platform sandbox with no
+/// layer degraded, so a fallback to the no-op sandbox or a degraded
+/// Landlock shows up in inspect.
+fn helper_status(
+    sandbox: bux_jail::SandboxKind,
+    layers: [bux_jail::LayerStatus; 2],
+) -> LayerStatus {
+    if sandbox == bux_jail::SandboxKind::Noop || layers.contains(&bux_jail::LayerStatus::Degraded) {
+        LayerStatus::Degraded
+    } else {
+        LayerStatus::Enforced
+    }
+}
+
+/// Appends `events` to the JSONL log at `path`, rotating it when full.
+fn append_activity(path: &Path, events: &[NetEvent]) -> io::Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() >= ACTIVITY_LOG_MAX) {
+        fs::rename(path, rotated_log(path))?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut out = BufWriter::new(file);
+    for event in events {
+        serde_json::to_writer(&mut out, event)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()
+}
+
+/// Previous generation of the activity log at `path`.
+fn rotated_log(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Parses a JSONL activity log, skipping lines that do not decode.
+fn read_activity(path: &Path, out: &mut Vec<NetEvent>) -> io::Result<()> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for line in BufReader::new(file).lines() {
+        if let Ok(event) = serde_json::from_str(&line?) {
+            out.push(event);
+        }
+    }
+    Ok(())
+}
+
+/// Logs and emits one drained batch.
+fn publish_activity(vm_id: &str, batch: Vec<NetEvent>, log: &Path, events: &EventDispatcher) {
+    if let Err(e) = append_activity(log, &batch) {
+        warn!(vm_id, error = %e, path = %log.display(), "failed to write network activity");
+    }
+    for event in batch {
+        events.emit(AuditEvent::at(
+            event.timestamp(),
+            AuditEventKind::NetworkActivity {
+                box_id: vm_id.to_owned(),
+                event: event.kind,
+            },
+        ));
+    }
+}
+
+/// Called by the pump with the VM id when a stopping quota runs out.
+type QuotaStop = Box<dyn Fn(&str) + Send>;
+
+/// Whether `batch` reports a used-up quota whose action stops the VM.
+fn stops_vm(batch: &[NetEvent]) -> bool {
+    batch.iter().any(|event| {
+        matches!(
+            event.kind,
+            NetEventKind::QuotaExceeded {
+                action: QuotaAction::Stop,
+                ..
+            }
+        )
+    })
+}
+
+/// Records [`QUOTA_STOP_ERROR`](crate::QUOTA_STOP_ERROR) on `vm_id` and
+/// sends its shim `SIGTERM`.
+fn stop_over_quota(db: &StateDb, vm_id: &str) {
+    let mut vm = match db.get_by_id_prefix(vm_id) {
+        Ok(vm) => vm,
+        Err(e) => {
+            warn!(vm_id, error = %e, "quota stop: VM not found");
+            return;
+        }
+    };
+    warn!(
+        vm_id,
+        pid = vm.pid,
+        "network transfer quota used up: stopping VM"
+    );
+    vm.config.last_error = Some(crate::QUOTA_STOP_ERROR.to_owned());
+    drop(db.update_config(&vm.id, &vm.config));
+    if vm.pid > 0 {
+        signal::kill(Pid::from_raw(vm.pid), Signal::SIGTERM).ok();
+    }
+}
+
+/// Drains `backend` until `stop` disconnects, then drains once more.
+fn pump_activity(
+    vm_id: &str,
+    backend: &dyn NetworkBackend,
+    log: &Path,
+    events: &EventDispatcher,
+    stop: &mpsc::Receiver<()>,
+    on_quota_stop: &QuotaStop,
+) {
+    loop {
+        let stopping = !matches!(
+            stop.recv_timeout(PUMP_INTERVAL),
+            Err(RecvTimeoutError::Timeout)
+        );
+        match backend.drain_events() {
+            Ok(batch) if !batch.is_empty() => {
+                if stops_vm(&batch) {
+                    on_quota_stop(vm_id);
+                }
+                publish_activity(vm_id, batch, log, events);
+            }
+            Ok(_) => {}
+            Err(e) => debug!(vm_id, error = %e, "failed to drain network activity"),
+        }
+        if stopping {
+            return;
+        }
+    }
+}
+
+/// Background thread draining one backend's activity.
+#[derive(Debug)]
+struct ActivityPump {
+    /// Dropped to stop the thread.
+    stop: Option<mpsc::Sender<()>>,
+    /// Pump thread.
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ActivityPump {
+    /// Starts pumping `backend`'s activity for `vm_id`, calling
+    /// `on_quota_stop` when a stopping quota runs out.
+    fn spawn(
+        vm_id: &str,
+        backend: Arc<dyn NetworkBackend>,
+        log: PathBuf,
+        events: Arc<EventDispatcher>,
+        on_quota_stop: QuotaStop,
+    ) -> io::Result<Self> {
+        let (stop, stop_rx) = mpsc::channel();
+        let id = vm_id.to_owned();
+        let thread = std::thread::Builder::new()
+            .name(format!("bux-net-activity-{vm_id}"))
+            .spawn(move || {
+                pump_activity(
+                    &id,
+                    backend.as_ref(),
+                    &log,
+                    &events,
+                    &stop_rx,
+                    &on_quota_stop,
+                );
+            })?;
+        Ok(Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for ActivityPump {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            warn!("network activity pump panicked");
+        }
+    }
+}
+
+/// A running backend and the pump draining it.
+#[derive(Debug)]
+struct LiveBackend {
+    /// Private-network attachment; detached before the backend stops.
+    _port: Option<SegmentPort>,
+    /// Declared before the backend so the final drain runs first.
+    _pump: ActivityPump,
+    /// The backend itself (shared with the pump).
+    backend: Arc<dyn NetworkBackend>,
+}
 
 /// Result of starting a per-VM network backend.
 #[derive(Debug)]
 pub(crate) struct StartNetResult {
     /// Engine virtio-net attachment.
     pub(crate) shim_network: ShimNetwork,
+    /// Whether the backend runs in a sandboxed helper process.
+    pub(crate) network_helper: LayerStatus,
 }
 
-/// Owns live gvproxy instances for a Runtime data directory.
+/// Owns live network backends for a Runtime data directory.
 #[derive(Debug)]
 pub struct NetworkManager {
-    /// Per-VM backends (RAII: drop stops Go side).
-    backends: Mutex<HashMap<String, GvproxyBackend>>,
+    /// Per-VM backends (RAII: drop stops the pump, then the backend).
+    backends: Mutex<HashMap<String, LiveBackend>>,
     /// Directory for per-VM net sockets (`{socks_dir}/{id}.net.sock`).
     socks_dir: PathBuf,
+    /// Receives [`AuditEventKind::NetworkActivity`] events.
+    events: Arc<EventDispatcher>,
+    /// Named networks and member assignments.
+    db: Arc<StateDb>,
+    /// Live private-network switches by name (pruned when empty).
+    segments: Mutex<HashMap<String, Segment>>,
 }
 
 impl NetworkManager {
-    /// Create a manager that places sockets under `socks_dir`.
+    /// Create a manager that places sockets and activity logs under
+    /// `socks_dir`, emits activity to `events` and keeps named networks
+    /// in `db`.
     #[must_use]
-    pub fn new(socks_dir: PathBuf) -> Self {
+    pub fn new(socks_dir: PathBuf, events: Arc<EventDispatcher>, db: Arc<StateDb>) -> Self {
         Self {
             backends: Mutex::new(HashMap::new()),
             socks_dir,
+            events,
+            db,
+            segments: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Socket path for a VM's gvproxy endpoint.
+    /// Socket path for a VM's network backend endpoint.
     #[must_use]
     pub fn socket_path(&self, vm_id: &str) -> PathBuf {
         self.socks_dir.join(format!("{vm_id}.net.sock"))
     }
 
-    /// Start gvproxy for `vm_id`.
+    /// Backend socket for a VM on a private network
+    /// (`{socks_dir}/{id}.uplink.sock`); the segment owns `{id}.net.sock`.
+    #[must_use]
+    pub fn uplink_socket_path(&self, vm_id: &str) -> PathBuf {
+        self.socks_dir.join(format!("{vm_id}.uplink.sock"))
+    }
+
+    /// Private directory of a VM's network helper
+    /// (`{socks_dir}/{id}.netd/`).
+    #[must_use]
+    pub fn helper_dir(&self, vm_id: &str) -> PathBuf {
+        self.socks_dir.join(format!("{vm_id}.netd"))
+    }
+
+    /// JSONL activity log for a VM (`{socks_dir}/{id}.net.jsonl`).
+    #[must_use]
+    pub fn activity_log_path(&self, vm_id: &str) -> PathBuf {
+        self.socks_dir.join(format!("{vm_id}.net.jsonl"))
+    }
+
+    /// Network activity recorded for `vm_id`, oldest first.
+    ///
+    /// Reads the rotated and current logs; events not yet drained from a
+    /// live backend appear within [`PUMP_INTERVAL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns I/O errors other than a missing log.
+    pub fn activity(&self, vm_id: &str) -> Result<Vec<NetEvent>> {
+        let log = self.activity_log_path(vm_id);
+        let mut events = Vec::new();
+        read_activity(&rotated_log(&log), &mut events)?;
+        read_activity(&log, &mut events)?;
+        Ok(events)
+    }
+
+    /// Start the backend selected by `vm`'s `net_backend` for `vm_id`,
+    /// joining its private network when `vm.network` is set.
     ///
+    /// - `vm`: egress rules, HTTP rules, traffic limits and network
+    ///   attachment
     /// - `port_mappings`: concrete `(host, guest)` (ephemeral already resolved)
-    /// - `allow_net`: empty = full egress; non-empty = DNS/TCP allow-list
-    /// - `secrets`: optional MITM material (CA + secret list)
+    /// - `secrets`: optional MITM material (CA + secret list); HTTP rules
+    ///   need its CA
     ///
     /// # Errors
     ///
-    /// Returns [`crate::Error::Net`] if gvproxy fails, or I/O errors cleaning
-    /// a stale socket path.
+    /// Returns [`crate::Error::Net`] if the backend or segment attachment
+    /// fails (including secrets or HTTP rules on the userspace backend, or
+    /// a zero traffic limit),
+    /// [`crate::Error::InvalidConfig`] if an egress rule does not parse or
+    /// the attachment has no assigned address,
+    /// [`crate::Error::SecurityUnavailable`] if a requested network helper
+    /// cannot run, [`crate::Error::Jail`] if it cannot be spawned, or I/O
+    /// errors cleaning a stale socket path.
     pub(crate) fn start(
         &self,
         vm_id: &str,
-        port_mappings: Vec<(u16, u16)>,
-        allow_net: Vec<String>,
+        vm: &VmConfig,
+        published: &[PublishedPort],
         secrets: Option<&LiveSecrets>,
     ) -> Result<StartNetResult> {
         // Replace any previous backend for this id.
         self.stop(vm_id);
 
-        let socket_path = self.socket_path(vm_id);
-        if socket_path.exists() {
-            std::fs::remove_file(&socket_path)?;
+        let member = vm
+            .network
+            .as_ref()
+            .map(NetworkAttachment::member)
+            .transpose()?;
+        let helper = helper_binary(vm.security)?;
+        let socket_path = match (&helper, member.is_some()) {
+            (Some(_), true) => self.helper_dir(vm_id).join("uplink.sock"),
+            (Some(_), false) => self.helper_dir(vm_id).join("net.sock"),
+            (None, true) => self.uplink_socket_path(vm_id),
+            (None, false) => self.socket_path(vm_id),
+        };
+        for path in [&socket_path, &self.socket_path(vm_id)] {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
         }
         if let Some(parent) = socket_path.parent() {
-            std::fs::create_dir_all(parent)?;
+            fs::create_dir_all(parent)?;
         }
 
+        let port_mappings = port_forwards(published)?;
         let port_count = port_mappings.len();
-        let mut config =
-            NetworkConfig::new(port_mappings, socket_path.clone()).with_allow_net(allow_net);
+        let mut config = NetworkConfig::new(port_mappings, socket_path.clone())
+            .with_egress(egress_policy(&vm.allow_net, vm.egress_default_deny)?)
+            .with_http_policy(vm.http_policy.clone())
+            .with_traffic_limits(vm.traffic_limits)
+            .with_dns(vm.dns.clone())
+            .with_ipv6(vm.ipv6)
+            .with_activity(true);
+        if let Some(ref member) = member {
+            config = config.with_guest_address(member.address);
+        }
         if let Some(live) = secrets {
             config = config.with_secrets(
                 live.gvproxy_secrets(),
@@ -90,51 +708,168 @@ pub(crate) fn start(
                 live.ca_key_pem.clone(),
             );
         }
-        let backend = GvproxyBackend::new(config)?;
-        let endpoint = backend.endpoint()?;
-
-        let (path, connection, mac) = match endpoint {
-            bux_net::NetworkEndpoint::UnixSocket {
-                path,
-                connection_type,
-                mac_address,
-            } => {
-                let conn = match connection_type {
-                    ConnectionType::UnixStream => ShimNetConn::UnixStream,
-                    ConnectionType::UnixDgram => ShimNetConn::UnixDgram,
-                    _ => {
-                        return Err(crate::Error::InvalidConfig(
-                            "unsupported network connection type".into(),
-                        ));
-                    }
-                };
-                (path, conn, mac_address)
+        let in_process = if vm.security.network_helper {
+            LayerStatus::Degraded
+        } else {
+            LayerStatus::Disabled
+        };
+        let (backend, network_helper): (Arc<dyn NetworkBackend>, _) = match (helper, vm.net_backend)
+        {
+            (Some(netd), kind) => {
+                let (remote, status) =
+                    self.spawn_helper(vm_id, &netd, kind, config, vm.security)?;
+                (Arc::new(remote), status)
             }
-            _ => {
-                return Err(crate::Error::InvalidConfig(
-                    "unsupported network endpoint kind".into(),
-                ));
+            (None, NetworkBackendKind::Userspace) => {
+                (Arc::new(UserspaceBackend::new(config)?), in_process)
             }
+            (None, _) => (Arc::new(GvproxyBackend::new(config)?), in_process),
         };
+        let mut endpoint = backend.endpoint()?;
 
-        let shim_network = ShimNetwork {
-            socket_path: path,
-            connection,
-            mac,
+        let port = match (member, &vm.network) {
+            (Some(member), Some(attachment)) => {
+                let segment = self.segment(&attachment.network);
+                let port = segment.attach(member, &endpoint, self.socket_path(vm_id))?;
+                endpoint = port.endpoint();
+                Some(port)
+            }
+            _ => None,
         };
+        let shim_network = shim_network(endpoint)?;
 
+        let backend_name = backend.name();
+        let db = Arc::clone(&self.db);
+        let pump = ActivityPump::spawn(
+            vm_id,
+            Arc::clone(&backend),
+            self.activity_log_path(vm_id),
+            Arc::clone(&self.events),
+            Box::new(move |id| stop_over_quota(&db, id)),
+        )?;
         self.backends
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner)
-            .insert(vm_id.to_owned(), backend);
+            .insert(
+                vm_id.to_owned(),
+                LiveBackend {
+                    _port: port,
+                    _pump: pump,
+                    backend,
+                },
+            );
 
         info!(
             vm_id,
             ?socket_path,
             published = port_count,
-            "gvproxy backend started"
+            backend = backend_name,
+            network = vm
+                .network
+                .as_ref()
+                .map(|attachment| attachment.network.as_str()),
+            "network backend started"
+        );
+        Ok(StartNetResult {
+            shim_network,
+            network_helper,
+        })
+    }
+
+    /// Runs a `kind` backend for `vm_id` in the `netd` helper, jailed to
+    /// [`Self::helper_dir`], and returns it with the helper's status (see
+    /// [`helper_status`]). The helper's stderr goes to
+    /// `{socks_dir}/{id}.netd.stderr`.
+    fn spawn_helper(
+        &self,
+        vm_id: &str,
+        netd: &Path,
+        kind: NetworkBackendKind,
+        config: NetworkConfig,
+        sec: SecurityOptions,
+    ) -> Result<(HelperBackend, LayerStatus)> {
+        let dir = self.helper_dir(vm_id);
+        let control_path = dir.join("control.sock");
+        if control_path.exists() {
+            fs::remove_file(&control_path)?;
+        }
+        let listener = UnixListener::bind(&control_path)?;
+
+        let sandbox: Option<Box<dyn bux_jail::Sandbox>> = if sec.jailer {
+            None // auto-detect bwrap/seatbelt
+        } else {
+            Some(Box::new(bux_jail::NoopSandbox::default()))
+        };
+        let jail = bux_jail::JailConfig {
+            rootfs: None,
+            root_disk: None,
+            readonly_paths: Vec::new(),
+            socks_dir: dir,
+            virtiofs_paths: Vec::new(),
+            watchdog_fd: None,
+            sandbox,
+            resource_limits: None,
+            stderr_file: Some(File::create(
+                self.socks_dir.join(format!("{vm_id}.netd.stderr")),
+            )?),
+            landlock: sec.landlock,
+            allow_degraded_security: sec.allow_degraded,
+            // The backend dials out on the host network.
+            namespaces: bux_jail::NamespaceOptions::new(),
+            #[cfg(target_os = "linux")]
+            credentials: None,
+        };
+        let spawned = bux_jail::spawn(netd, &control_path, jail, vm_id)?;
+        let status = helper_status(
+            spawned.security.sandbox,
+            [spawned.security.landlock, spawned.security.mac],
+        );
+        let helper =
+            HelperBackend::start(spawned.child, &listener, kind, config, HELPER_START_TIMEOUT);
+        drop(fs::remove_file(&control_path));
+        Ok((helper?, status))
+    }
+
+    /// Apply `update` to the live backend for `vm_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidState`] if no backend is running and
+    /// [`crate::Error::Net`] if the backend rejects the change.
+    pub(crate) fn update(&self, vm_id: &str, update: &NetworkUpdate) -> Result<()> {
+        let backend_name = self
+            .backends
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(vm_id)
+            .map(|live| live.backend.update(update).map(|()| live.backend.name()))
+            .ok_or_else(|| {
+                crate::Error::InvalidState(format!("VM {vm_id} has no running network backend"))
+            })??;
+        info!(
+            vm_id,
+            backend = backend_name,
+            published = update.port_mappings.len(),
+            egress_rules = update.egress.rules().len(),
+            "network backend updated"
         );
-        Ok(StartNetResult { shim_network })
+        Ok(())
+    }
+
+    /// Live counters of the backend for `vm_id`, if one is running and
+    /// reports them.
+    #[must_use]
+    pub fn metrics(&self, vm_id: &str) -> Option<NetworkMetrics> {
+        let backends = self
+            .backends
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let result = backends.get(vm_id)?.backend.metrics();
+        drop(backends);
+        result.unwrap_or_else(|e| {
+            debug!(vm_id, error = %e, "failed to read network metrics");
+            None
+        })
     }
 
     /// Stop and drop the backend for `vm_id` (no-op if absent).
@@ -145,13 +880,30 @@ pub fn stop(&self, vm_id: &str) {
             .unwrap_or_else(std::sync::PoisonError::into_inner)
             .remove(vm_id);
         if removed.is_some() {
-            debug!(vm_id, "gvproxy backend stopped");
+            drop(removed);
+            self.segments
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .retain(|_, segment| !segment.is_empty());
+            debug!(vm_id, "network backend stopped");
         }
-        let path = self.socket_path(vm_id);
-        if path.exists()
-            && let Err(e) = std::fs::remove_file(&path)
+        self.remove_sockets(vm_id);
+    }
+
+    /// Removes `vm_id`'s backend sockets and helper directory.
+    fn remove_sockets(&self, vm_id: &str) {
+        for path in [self.socket_path(vm_id), self.uplink_socket_path(vm_id)] {
+            if path.exists()
+                && let Err(e) = fs::remove_file(&path)
+            {
+                warn!(vm_id, error = %e, path = %path.display(), "failed to remove net socket");
+            }
+        }
+        let dir = self.helper_dir(vm_id);
+        if dir.exists()
+            && let Err(e) = fs::remove_dir_all(&dir)
         {
-            warn!(vm_id, error = %e, path = %path.display(), "failed to remove net socket");
+            warn!(vm_id, error = %e, path = %dir.display(), "failed to remove net helper dir");
         }
     }
 
@@ -178,9 +930,531 @@ pub fn is_running(&self, vm_id: &str) -> bool {
             .contains_key(vm_id)
     }
 
+    /// Create a named private network (idempotent if it already exists).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidConfig`] if `name` is not a DNS
+    /// label, or a database error.
+    pub fn create_network(&self, name: &str) -> Result<NetworkInfo> {
+        if !valid_label(name) {
+            return Err(crate::Error::InvalidConfig(format!(
+                "invalid network name {name:?}: use letters, digits and inner '-' (max 63)"
+            )));
+        }
+        if let Some(existing) = self.db.get_network(name)? {
+            return Ok(existing);
+        }
+        let info = NetworkInfo {
+            name: name.to_owned(),
+            created_at: SystemTime::now(),
+        };
+        self.db.insert_network(&info)?;
+        Ok(info)
+    }
+
+    /// List named private networks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub fn networks(&self) -> Result<Vec<NetworkInfo>> {
+        self.db.list_networks()
+    }
+
+    /// Look up a named private network.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::NotFound`] if missing.
+    pub fn get_network(&self, name: &str) -> Result<NetworkInfo> {
+        self.db
+            .get_network(name)?
+            .ok_or_else(|| crate::Error::NotFound(format!("network '{name}' not found")))
+    }
+
+    /// Remove a named private network (fails while any VM is attached).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Busy`] if a VM still references it,
+    /// [`crate::Error::NotFound`] if missing, or a database error.
+    pub fn remove_network(&self, name: &str) -> Result<()> {
+        let info = self.get_network(name)?;
+        let n = self
+            .db
+            .list()?
+            .iter()
+            .filter(|vm| {
+                vm.config
+                    .network
+                    .as_ref()
+                    .is_some_and(|attachment| attachment.network == info.name)
+            })
+            .count();
+        if n > 0 {
+            return Err(crate::Error::Busy(format!(
+                "network '{name}' has {n} member VM(s); remove them first"
+            )));
+        }
+        self.db.delete_network(&info.name)
+    }
+
+    /// Fill in `attachment`'s hostname and address for `vm_id`, keeping
+    /// requested values and avoiding those held by other members.
+    ///
+    /// The default hostname is `vm_name` when it is a DNS label, else the
+    /// first 12 characters of `vm_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::NotFound`] if the network does not exist,
+    /// [`crate::Error::InvalidConfig`] if the hostname is invalid or taken,
+    /// the address is outside the subnet or taken, or the subnet is full.
+    pub(crate) fn assign(
+        &self,
+        attachment: &mut NetworkAttachment,
+        vm_id: &str,
+        vm_name: Option<&str>,
+    ) -> Result<()> {
+        let network = self.get_network(&attachment.network)?;
+        let mut hostnames = HashSet::new();
+        let mut hosts = HashSet::new();
+        for vm in self.db.list()? {
+            if vm.id == vm_id {
+                continue;
+            }
+            if let Some(other) = vm.config.network
+                && other.network == network.name
+            {
+                hostnames.extend(other.hostname.map(|h| h.to_ascii_lowercase()));
+                hosts.extend(other.ip.map(host_octet));
+            }
+        }
+
+        let hostname = attachment.hostname.clone().unwrap_or_else(|| {
+            vm_name
+                .filter(|name| valid_label(name))
+                .map_or_else(|| vm_id.chars().take(12).collect(), str::to_owned)
+        });
+        if !valid_label(&hostname) {
+            return Err(crate::Error::InvalidConfig(format!(
+                "invalid hostname {hostname:?}: use letters, digits and inner '-' (max 63)"
+            )));
+        }
+        if hostnames.contains(&hostname.to_ascii_lowercase()) {
+            return Err(crate::Error::InvalidConfig(format!(
+                "hostname '{hostname}' is already used on network '{}'",
+                network.name
+            )));
+        }
+
+        let host = match attachment.ip {
+            Some(ip) => {
+                let host = host_octet(ip);
+                if GuestAddress::host(host).ip != ip
+                    || !(2..=254).contains(&host)
+                    || hosts.contains(&host)
+                {
+                    return Err(crate::Error::InvalidConfig(format!(
+                        "address {ip} is unavailable on network '{}'",
+                        network.name
+                    )));
+                }
+                host
+            }
+            None => free_host(&hosts).ok_or_else(|| {
+                crate::Error::InvalidConfig(format!("network '{}' is full", network.name))
+            })?,
+        };
+        attachment.hostname = Some(hostname);
+        attachment.ip = Some(GuestAddress::host(host).ip);
+        Ok(())
+    }
+
+    /// Live switch for `name`, created on first use.
+    fn segment(&self, name: &str) -> Segment {
+        self.segments
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .entry(name.to_owned())
+            .or_insert_with(|| Segment::new(name))
+            .clone()
+    }
+
     /// Socks directory (for tests / diagnostics).
     #[must_use]
     pub fn socks_dir(&self) -> &Path {
         &self.socks_dir
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
+mod tests {
+    use std::sync::PoisonError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn patch_revokes_before_allowing() {
+        let current = vec!["pypi.org".to_owned(), "10.0.0.0/8".to_owned()];
+        let patch = NetworkPatch::new()
+            .revoke("pypi.org")
+            .allow("files.pythonhosted.org")
+            .allow("10.0.0.0/8");
+        assert_eq!(
+            patch.apply_allow(&current),
+            vec!["10.0.0.0/8", "files.pythonhosted.org"]
+        );
+        assert!(!patch.is_empty());
+        assert!(NetworkPatch::new().is_empty());
+    }
+
+    #[test]
+    fn patch_replaces_published_port() {
+        let current = vec![PublishedPort::new(8080, 80)];
+        let patch = NetworkPatch::new()
+            .unpublish(8080)
+            .publish(PortSpec::new(8080, 3000));
+        let (ports, added) = patch.apply_ports(&current).unwrap();
+        assert_eq!(ports, vec![PublishedPort::new(8080, 3000)]);
+        assert_eq!(added, ports);
+    }
+
+    #[test]
+    fn patch_rejects_unknown_and_taken_ports() {
+        let current = vec![PublishedPort::new(8080, 80)];
+        let unknown = NetworkPatch::new().unpublish(9090);
+        assert!(matches!(
+            unknown.apply_ports(&current),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        let taken = NetworkPatch::new().publish(PortSpec::new(8080, 81));
+        assert!(matches!(
+            taken.apply_ports(&current),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        let udp = NetworkPatch::new()
+            .publish(PortSpec::new(8080, 80).with_protocol(bux_net::PortProtocol::Udp));
+        let (ports, _) = udp.apply_ports(&current).unwrap();
+        assert_eq!(ports.len(), 2);
+        assert_eq!(
+            port_forwards(&ports).unwrap()[1].to_string(),
+            "0.0.0.0:8080->80/udp"
+        );
+    }
+
+    #[test]
+    fn egress_policy_applies_default_and_rejects_bad_rules() {
+        let rules = vec!["*.example.com:443".to_owned(), "!10.0.0.0/8".to_owned()];
+        assert!(egress_policy(&rules, None).unwrap().default_deny());
+        assert!(!egress_policy(&rules, Some(false)).unwrap().default_deny());
+        assert!(matches!(
+            egress_policy(&["host:0".into()], None),
+            Err(crate::Error::Net(_))
+        ));
+    }
+
+    /// Backend that hands out one batch of activity.
+    #[derive(Debug, Default)]
+    struct Recorded(Mutex<Vec<NetEvent>>);
+
+    impl NetworkBackend for Recorded {
+        fn endpoint(&self) -> bux_net::Result<NetworkEndpoint> {
+            Err(bux_net::NetError::Config("test backend".into()))
+        }
+
+        fn name(&self) -> &'static str {
+            "recorded"
+        }
+
+        fn drain_events(&self) -> bux_net::Result<Vec<NetEvent>> {
+            Ok(std::mem::take(
+                &mut *self.0.lock().unwrap_or_else(PoisonError::into_inner),
+            ))
+        }
+    }
+
+    fn test_db() -> Arc<StateDb> {
+        Arc::new(StateDb::open(":memory:").unwrap())
+    }
+
+    /// Records `vm_id` as a member of `attachment`'s network.
+    fn insert_member(db: &StateDb, vm_id: &str, attachment: NetworkAttachment) {
+        let mut config = crate::vm::Vm::builder().network(attachment).to_config();
+        config.exec_path = Some("/bin/sh".into());
+        db.insert(&crate::state::VmState {
+            id: vm_id.to_owned(),
+            name: None,
+            pid: 1,
+            image: None,
+            socket: PathBuf::from(format!("/tmp/{vm_id}.sock")),
+            status: crate::state::Status::Stopped,
+            config,
+            created_at: SystemTime::now(),
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn networks_are_validated_listed_and_guarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = test_db();
+        let manager =
+            NetworkManager::new(dir.path().to_path_buf(), Arc::default(), Arc::clone(&db));
+        assert!(matches!(
+            manager.create_network("no_underscores"),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        manager.create_network("lab").unwrap();
+        assert_eq!(manager.create_network("lab").unwrap().name, "lab");
+        let names: Vec<_> = manager
+            .networks()
+            .unwrap()
+            .into_iter()
+            .map(|network| network.name)
+            .collect();
+        assert_eq!(names, ["lab"]);
+
+        let mut attachment = NetworkAttachment::new("lab");
+        manager.assign(&mut attachment, "vm1", Some("db")).unwrap();
+        insert_member(&db, "vm1", attachment);
+        assert!(matches!(
+            manager.remove_network("lab"),
+            Err(crate::Error::Busy(_))
+        ));
+        db.delete("vm1").unwrap();
+        manager.remove_network("lab").unwrap();
+        assert!(matches!(
+            manager.get_network("lab"),
+            Err(crate::Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn assign_gives_members_distinct_identities() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = test_db();
+        let manager =
+            NetworkManager::new(dir.path().to_path_buf(), Arc::default(), Arc::clone(&db));
+        let mut missing = NetworkAttachment::new("lab");
+        assert!(matches!(
+            manager.assign(&mut missing, "vm1", None),
+            Err(crate::Error::NotFound(_))
+        ));
+        manager.create_network("lab").unwrap();
+
+        let mut first = NetworkAttachment::new("lab");
+        manager.assign(&mut first, "vm1", Some("db")).unwrap();
+        assert_eq!(first.hostname.as_deref(), Some("db"));
+        assert_eq!(first.ip, Some(Ipv4Addr::new(192, 168, 127, 2)));
+        insert_member(&db, "vm1", first.clone());
+
+        // Re-assigning a member keeps its identity.
+        manager.assign(&mut first, "vm1", Some("db")).unwrap();
+        assert_eq!(first.ip, Some(Ipv4Addr::new(192, 168, 127, 2)));
+
+        let mut second = NetworkAttachment::new("lab");
+        manager
+            .assign(&mut second, "0123456789abcdef", Some("not a label"))
+            .unwrap();
+        assert_eq!(second.hostname.as_deref(), Some("0123456789ab"));
+        assert_eq!(second.ip, Some(Ipv4Addr::new(192, 168, 127, 3)));
+
+        let mut clash = NetworkAttachment::new("lab").hostname("DB");
+        assert!(matches!(
+            manager.assign(&mut clash, "vm3", None),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        let mut taken = NetworkAttachment::new("lab");
+        taken.ip = Some(Ipv4Addr::new(192, 168, 127, 2));
+        assert!(matches!(
+            manager.assign(&mut taken, "vm3", Some("cache")),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+        taken.ip = Some(Ipv4Addr::new(10, 0, 0, 2));
+        assert!(matches!(
+            manager.assign(&mut taken, "vm3", Some("cache")),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+
+        let member = second.member().unwrap();
+        assert_eq!(member.address, GuestAddress::host(3));
+        assert!(NetworkAttachment::new("lab").member().is_err());
+    }
+
+    fn dns_query(name: &str) -> NetEvent {
+        NetEvent::now(NetEventKind::DnsQuery { name: name.into() })
+    }
+
+    #[test]
+    fn stopping_quota_triggers_callback_and_records_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(Recorded::default());
+        let quota = |action| {
+            NetEvent::now(NetEventKind::QuotaExceeded {
+                direction: bux_net::TrafficDirection::Egress,
+                limit: 10,
+                action,
+            })
+        };
+        backend.0.lock().unwrap().push(quota(QuotaAction::Block));
+        assert!(!stops_vm(&backend.0.lock().unwrap()));
+        backend.0.lock().unwrap().push(quota(QuotaAction::Stop));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = Arc::clone(&calls);
+        let pump = ActivityPump::spawn(
+            "vm1",
+            backend,
+            dir.path().join("vm1.net.jsonl"),
+            Arc::new(EventDispatcher::new()),
+            Box::new(move |id| {
+                assert_eq!(id, "vm1");
+                seen.fetch_add(1, Ordering::Relaxed);
+            }),
+        )
+        .unwrap();
+        drop(pump);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+        let db = test_db();
+        let mut config = crate::vm::Vm::builder().to_config();
+        config.exec_path = Some("/bin/sh".into());
+        db.insert(&crate::state::VmState {
+            id: "vm2".to_owned(),
+            name: None,
+            pid: 0,
+            image: None,
+            socket: PathBuf::from("/tmp/vm2.sock"),
+            status: crate::state::Status::Running,
+            config,
+            created_at: SystemTime::now(),
+        })
+        .unwrap();
+        stop_over_quota(&db, "vm2");
+        assert_eq!(
+            db.get_by_id_prefix("vm2")
+                .unwrap()
+                .config
+                .last_error
+                .as_deref(),
+            Some(crate::QUOTA_STOP_ERROR)
+        );
+    }
+
+    #[test]
+    fn pump_drains_on_stop_into_log_and_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let events = Arc::new(EventDispatcher::new());
+        let ring = Arc::new(crate::RingBufferListener::new(8));
+        #[allow(
+            clippy::clone_on_ref_ptr,
+            reason = "coercion to dyn trait requires .clone()"
+        )]
+        let listener: Arc<dyn crate::EventListener> = ring.clone();
+        events.add_listener(listener);
+        let manager = NetworkManager::new(dir.path().to_path_buf(), Arc::clone(&events), test_db());
+
+        let backend = Arc::new(Recorded::default());
+        backend.0.lock().unwrap().push(dns_query("example.com"));
+        let pump = ActivityPump::spawn(
+            "vm1",
+            backend,
+            manager.activity_log_path("vm1"),
+            events,
+            Box::new(|_| {}),
+        )
+        .unwrap();
+        drop(pump);
+
+        let logged = manager.activity("vm1").unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(
+            logged[0].kind,
+            NetEventKind::DnsQuery {
+                name: "example.com".into()
+            }
+        );
+        let emitted = ring.recent(8);
+        assert!(matches!(
+            &emitted[0].kind,
+            AuditEventKind::NetworkActivity { box_id, .. } if box_id == "vm1"
+        ));
+        assert_eq!(emitted[0].timestamp, logged[0].timestamp());
+    }
+
+    #[test]
+    fn activity_reads_rotated_log_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = NetworkManager::new(dir.path().to_path_buf(), Arc::default(), test_db());
+        let log = manager.activity_log_path("vm1");
+        assert!(manager.activity("vm1").unwrap().is_empty());
+
+        append_activity(&rotated_log(&log), &[dns_query("old.example")]).unwrap();
+        append_activity(&log, &[dns_query("new.example")]).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&log)
+            .unwrap()
+            .write_all(b"not an event\n")
+            .unwrap();
+        let names: Vec<_> = manager
+            .activity("vm1")
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind.to_string())
+            .collect();
+        assert_eq!(names, ["dns query old.example", "dns query new.example"]);
+    }
+
+    #[test]
+    fn update_requires_running_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = NetworkManager::new(dir.path().to_path_buf(), Arc::default(), test_db());
+        let egress = egress_policy(&["example.com".into()], None).unwrap();
+        let update = NetworkUpdate::new(Vec::new(), egress);
+        assert!(matches!(
+            manager.update("missing", &update),
+            Err(crate::Error::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn helper_is_opt_in_and_fails_closed_off_linux() {
+        assert!(helper_binary(SecurityOptions::new()).unwrap().is_none());
+        let requested = SecurityOptions::new().network_helper(true);
+        if !cfg!(target_os = "linux") {
+            assert!(matches!(
+                helper_binary(requested),
+                Err(crate::Error::SecurityUnavailable(_))
+            ));
+            assert!(
+                helper_binary(requested.allow_degraded(true))
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn helper_status_follows_the_jail() {
+        use bux_jail::{LayerStatus as Jail, SandboxKind};
+
+        let enforced = [Jail::Enforced, Jail::Disabled];
+        assert_eq!(
+            helper_status(SandboxKind::Bwrap, enforced),
+            LayerStatus::Enforced
+        );
+        assert_eq!(
+            helper_status(SandboxKind::Noop, enforced),
+            LayerStatus::Degraded
+        );
+        assert_eq!(
+            helper_status(SandboxKind::Bwrap, [Jail::Degraded, Jail::Disabled]),
+            LayerStatus::Degraded
+        );
+    }
+}
@@ -3,11 +3,14 @@
 //! Managed Runtime entry points take [`VmOptions`] only. Low-level
 //! [`crate::VmBuilder`] remains for process-takeover and tests.
 
+use std::net::{Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::limits::HostLimits;
+use crate::net_manager::NetworkAttachment;
 use crate::secrets::Secret;
 use crate::security::SecurityOptions;
 use crate::volumes::VolumeMount;
@@ -51,6 +54,7 @@ pub fn label(&self) -> String {
 ///
 /// Construct only via [`VmOptions::from_image`] (image is required).
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools, reason = "independent option flags")]
 pub struct VmOptions {
     /// Root image / rootfs / base disk.
     pub image: ImageRef,
@@ -62,12 +66,26 @@ pub struct VmOptions {
     pub ram_mib: u32,
     /// Publish specs (`host:guest`, ephemeral forms). Resolved at boot.
     pub ports: Vec<String>,
-    /// Egress allow-list; empty = unrestricted.
+    /// Egress rules ([`bux_net::EgressRule`] syntax); empty = unrestricted.
     pub allow_net: Vec<String>,
+    /// Explicit egress default; `None` = deny iff an allow rule exists.
+    pub egress_default_deny: Option<bool>,
     /// Host-only secrets for MITM (memory only).
     pub secrets: Vec<Secret>,
+    /// HTTPS request rules and logging on the MITM path.
+    pub http_policy: bux_net::HttpPolicy,
     /// Use gvproxy virtio-net (default true).
     pub virtio_net: bool,
+    /// Backend serving virtio-net (default gvproxy).
+    pub net_backend: bux_net::NetworkBackendKind,
+    /// Named private network to join (requires virtio-net).
+    pub network: Option<NetworkAttachment>,
+    /// Bandwidth limits and transfer quotas (requires virtio-net).
+    pub traffic_limits: bux_net::TrafficLimits,
+    /// Local DNS records, resolver override and host gateway (requires virtio-net).
+    pub dns: bux_net::DnsConfig,
+    /// Give the guest an IPv6 address alongside IPv4 (userspace backend only).
+    pub ipv6: bool,
     /// Volume mounts (bind or named) resolved at create.
     pub volumes: Vec<VolumeMount>,
     /// Workload environment (`KEY=VALUE`) — applied to **exec**, not VM boot.
@@ -86,6 +104,8 @@ pub struct VmOptions {
     pub detach: bool,
     /// Isolation policy (Landlock / jailer). Default: fail-closed Landlock on Linux.
     pub security: SecurityOptions,
+    /// Host-side CPU / memory / pids / IO limits on the VMM (cgroup v2).
+    pub host_limits: HostLimits,
     /// Stop after this many idle seconds (`None` = never). Default off.
     pub auto_stop_secs: Option<u64>,
     /// Delete a stopped VM after this many idle seconds (`None` = never). Default off.
@@ -103,8 +123,15 @@ pub fn from_image(image: impl Into<ImageRef>) -> Self {
             ram_mib: 512,
             ports: Vec::new(),
             allow_net: Vec::new(),
+            egress_default_deny: None,
             secrets: Vec::new(),
+            http_policy: bux_net::HttpPolicy::new(),
             virtio_net: true,
+            net_backend: bux_net::NetworkBackendKind::Gvproxy,
+            network: None,
+            traffic_limits: bux_net::TrafficLimits::new(),
+            dns: bux_net::DnsConfig::new(),
+            ipv6: false,
             volumes: Vec::new(),
             env: Vec::new(),
             workdir: None,
@@ -113,6 +140,7 @@ pub fn from_image(image: impl Into<ImageRef>) -> Self {
             ready_timeout: Duration::from_secs(30),
             detach: false,
             security: SecurityOptions::default(),
+            host_limits: HostLimits::new(),
             auto_stop_secs: None,
             auto_delete_secs: None,
         }
@@ -146,13 +174,28 @@ pub fn port(mut self, spec: impl Into<String>) -> Self {
         self
     }
 
-    /// Set egress allow-list.
+    /// Set egress rules (`host`, `10.0.0.0/8:5432`, `!169.254.169.254`, ...).
     #[must_use]
     pub fn allow_net(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
         self.allow_net = hosts.into_iter().map(Into::into).collect();
         self
     }
 
+    /// Set egress rules and default from a typed policy.
+    #[must_use]
+    pub fn egress(mut self, policy: &bux_net::EgressPolicy) -> Self {
+        self.allow_net = policy.rule_strings();
+        self.egress_default_deny = policy.explicit_default_deny();
+        self
+    }
+
+    /// Set the action for traffic no egress rule matches.
+    #[must_use]
+    pub const fn egress_default_deny(mut self, deny: bool) -> Self {
+        self.egress_default_deny = Some(deny);
+        self
+    }
+
     /// Attach secrets for MITM.
     #[must_use]
     pub fn secrets(mut self, secrets: impl IntoIterator<Item = Secret>) -> Self {
@@ -160,6 +203,14 @@ pub fn secrets(mut self, secrets: impl IntoIterator<Item = Secret>) -> Self {
         self
     }
 
+    /// Set the HTTPS request policy (method / path restrictions, header
+    /// edits, request logging).
+    #[must_use]
+    pub fn http_policy(mut self, policy: bux_net::HttpPolicy) -> Self {
+        self.http_policy = policy;
+        self
+    }
+
     /// Enable/disable virtio-net.
     #[must_use]
     pub const fn virtio_net(mut self, enable: bool) -> Self {
@@ -167,6 +218,57 @@ pub const fn virtio_net(mut self, enable: bool) -> Self {
         self
     }
 
+    /// Select the virtio-net backend.
+    #[must_use]
+    pub const fn net_backend(mut self, kind: bux_net::NetworkBackendKind) -> Self {
+        self.net_backend = kind;
+        self
+    }
+
+    /// Join a named private network.
+    #[must_use]
+    pub fn network(mut self, attachment: NetworkAttachment) -> Self {
+        self.network = Some(attachment);
+        self
+    }
+
+    /// Limit bandwidth and set transfer quotas.
+    #[must_use]
+    pub const fn traffic_limits(mut self, limits: bux_net::TrafficLimits) -> Self {
+        self.traffic_limits = limits;
+        self
+    }
+
+    /// Resolve `name` to `ip` inside the guest, ahead of the egress policy.
+    #[must_use]
+    pub fn dns_record(mut self, name: &str, ip: Ipv4Addr) -> Self {
+        self.dns = self.dns.with_record(name, ip);
+        self
+    }
+
+    /// Relay guest DNS queries to `resolver` instead of the host's
+    /// (userspace backend only).
+    #[must_use]
+    pub const fn dns_upstream(mut self, resolver: SocketAddr) -> Self {
+        self.dns.upstream = Some(resolver);
+        self
+    }
+
+    /// Resolve `host.bux.internal` to an address that reaches the host's
+    /// loopback.
+    #[must_use]
+    pub const fn host_gateway(mut self, enable: bool) -> Self {
+        self.dns.host_gateway = enable;
+        self
+    }
+
+    /// Serve IPv6 to the guest alongside IPv4 (userspace backend only).
+    #[must_use]
+    pub const fn ipv6(mut self, enable: bool) -> Self {
+        self.ipv6 = enable;
+        self
+    }
+
     /// Add a volume mount (bind or named).
     #[must_use]
     pub fn volume(mut self, mount: VolumeMount) -> Self {
@@ -241,6 +343,13 @@ pub const fn security(mut self, opts: SecurityOptions) -> Self {
         self
     }
 
+    /// Cap the VMM's host CPU, memory, pids and IO (cgroup v2, Linux).
+    #[must_use]
+    pub fn host_limits(mut self, limits: HostLimits) -> Self {
+        self.host_limits = limits;
+        self
+    }
+
     /// Auto-stop after idle seconds (default off).
     #[must_use]
     pub const fn auto_stop_secs(mut self, secs: Option<u64>) -> Self {
@@ -269,12 +378,25 @@ fn from_image_defaults() {
         assert_eq!(o.vcpus, 1);
         assert_eq!(o.ram_mib, 512);
         assert!(o.virtio_net);
+        assert_eq!(o.net_backend, bux_net::NetworkBackendKind::Gvproxy);
+        assert!(o.network.is_none());
         assert!(o.ports.is_empty());
         assert!(o.env.is_empty());
         assert!(o.workdir.is_none());
         assert!(o.user.is_none());
         assert!(!o.auto_remove);
         assert!(!o.detach);
+        assert!(o.host_limits.is_unlimited());
+    }
+
+    #[test]
+    fn egress_policy_sets_rules_and_default() {
+        let policy = bux_net::EgressPolicy::parse(["*", "!169.254.169.254:80/tcp"])
+            .unwrap()
+            .with_default_deny(false);
+        let o = VmOptions::from_image("alpine").egress(&policy);
+        assert_eq!(o.allow_net, vec!["*", "!169.254.169.254:80/tcp"]);
+        assert_eq!(o.egress_default_deny, Some(false));
     }
 
     #[test]
@@ -285,6 +407,19 @@ fn fluent_chain() {
             .ram_mib(1024)
             .port("8080:80")
             .allow_net(["example.com"])
+            .net_backend(bux_net::NetworkBackendKind::Userspace)
+            .network(NetworkAttachment::new("lab").hostname("db"))
+            .traffic_limits(
+                bux_net::TrafficLimits::new()
+                    .with_egress_rate(1 << 20)
+                    .with_quota_action(bux_net::QuotaAction::Stop),
+            )
+            .http_policy(
+                bux_net::HttpPolicy::new().rule(bux_net::HttpRule::read_only(["api.github.com"])),
+            )
+            .dns_record("db.test", Ipv4Addr::new(10, 0, 0, 5))
+            .dns_upstream("1.1.1.1:53".parse().unwrap())
+            .host_gateway(true)
             .env(["A=1", "B=2"])
             .workdir("/work")
             .user("1000:1000")
@@ -295,6 +430,16 @@ fn fluent_chain() {
         assert_eq!(o.ram_mib, 1024);
         assert_eq!(o.ports, vec!["8080:80"]);
         assert_eq!(o.allow_net, vec!["example.com"]);
+        assert_eq!(o.net_backend, bux_net::NetworkBackendKind::Userspace);
+        let network = o.network.as_ref().unwrap();
+        assert_eq!(network.network, "lab");
+        assert_eq!(network.hostname.as_deref(), Some("db"));
+        assert_eq!(o.traffic_limits.egress_rate, Some(1 << 20));
+        assert_eq!(o.traffic_limits.on_quota, bux_net::QuotaAction::Stop);
+        assert_eq!(o.http_policy.rules.len(), 1);
+        assert_eq!(o.dns.lookup("db.test"), Some(Ipv4Addr::new(10, 0, 0, 5)));
+        assert_eq!(o.dns.upstream, Some("1.1.1.1:53".parse().unwrap()));
+        assert!(o.dns.host_gateway);
         assert_eq!(o.env, vec!["A=1", "B=2"]);
         assert_eq!(o.workdir.as_deref(), Some("/work"));
         assert_eq!(o.user.as_deref(), Some("1000:1000"));
@@ -2,7 +2,7 @@
 //!
 //! Stages (product path):
 //! ```text
-//! Validate → ResolveImage → EnsureBaseDisk → (overlay in spawn)
+//! Validate → ResolveImage → VerifyImage → EnsureBaseDisk → (overlay in spawn)
 //!   → Network → ShimConfig → JailSpawn → WaitReady
 //! ```
 //!
@@ -15,6 +15,7 @@
 
 use crate::Result;
 use crate::options::{ImageRef, VmOptions};
+use crate::policy::HostPolicy;
 use crate::process::merge_image_config;
 use crate::runtime::{Runtime, VmHandle};
 use crate::vm::Vm;
@@ -30,7 +31,7 @@ pub(crate) async fn create(
     on_progress: impl Fn(&str) + Send + Sync,
 ) -> Result<VmHandle> {
     on_progress("validating options");
-    validate(&opts)?;
+    validate(&opts, rt.host_policy())?;
 
     on_progress("resolving image");
     let (builder_base, image_label, oci_cfg) = resolve_image(rt, &opts.image, &on_progress).await?;
@@ -44,10 +45,16 @@ pub(crate) async fn create(
         .vcpus(opts.vcpus)
         .ram_mib(opts.ram_mib)
         .virtio_net(opts.virtio_net)
+        .net_backend(opts.net_backend)
         .allow_net(opts.allow_net.clone())
         .secrets(opts.secrets.clone())
+        .http_policy(opts.http_policy.clone())
+        .traffic_limits(opts.traffic_limits)
+        .dns(opts.dns.clone())
+        .ipv6(opts.ipv6)
         .workload_env(opts.env.clone())
         .security(opts.security)
+        .host_limits(opts.host_limits.clone())
         .auto_stop_secs(opts.auto_stop_secs)
         .auto_delete_secs(opts.auto_delete_secs);
 
@@ -57,6 +64,12 @@ pub(crate) async fn create(
     if let Some(ref user) = opts.user {
         builder = builder.user(user.clone());
     }
+    if let Some(deny) = opts.egress_default_deny {
+        builder = builder.egress_default_deny(deny);
+    }
+    if let Some(ref network) = opts.network {
+        builder = builder.network(network.clone());
+    }
 
     for p in &opts.ports {
         builder = builder.port(p.clone());
@@ -89,8 +102,9 @@ pub(crate) async fn create(
     Ok(handle)
 }
 
-/// Validate product options before expensive work.
-fn validate(opts: &VmOptions) -> Result<()> {
+/// Validate product options before expensive work, then check them
+/// against the runtime's host policy.
+fn validate(opts: &VmOptions, policy: &HostPolicy) -> Result<()> {
     if opts.vcpus == 0 {
         return Err(crate::Error::InvalidConfig("vcpus must be >= 1".into()));
     }
@@ -100,10 +114,56 @@ fn validate(opts: &VmOptions) -> Result<()> {
     if !opts.secrets.is_empty() && !opts.virtio_net {
         return Err(crate::Error::SecretsNeedVirtioNet);
     }
+    if !opts.secrets.is_empty() && opts.net_backend == bux_net::NetworkBackendKind::Userspace {
+        return Err(crate::Error::InvalidConfig(
+            "secrets require the gvproxy network backend".into(),
+        ));
+    }
+    if opts.network.is_some() && !opts.virtio_net {
+        return Err(crate::Error::InvalidConfig(
+            "private networks require virtio-net".into(),
+        ));
+    }
+    if !opts.traffic_limits.is_unlimited() && !opts.virtio_net {
+        return Err(crate::Error::InvalidConfig(
+            "traffic limits require virtio-net".into(),
+        ));
+    }
+    opts.traffic_limits.validate()?;
+    if !opts.dns.is_empty() && !opts.virtio_net {
+        return Err(crate::Error::InvalidConfig(
+            "DNS records and the host gateway require virtio-net".into(),
+        ));
+    }
+    opts.dns.validate()?;
+    opts.host_limits.validate()?;
+    if opts.security.network_namespace && !opts.virtio_net {
+        return Err(crate::Error::InvalidConfig(
+            "a network namespace requires virtio-net (TSI uses the host network)".into(),
+        ));
+    }
+    if opts.security.network_helper && !opts.virtio_net {
+        return Err(crate::Error::InvalidConfig(
+            "a network helper requires virtio-net (TSI has no network backend)".into(),
+        ));
+    }
+    if opts.ipv6 && (!opts.virtio_net || opts.net_backend != bux_net::NetworkBackendKind::Userspace)
+    {
+        return Err(crate::Error::InvalidConfig(
+            "IPv6 requires virtio-net with the userspace network backend (gvproxy is IPv4-only)"
+                .into(),
+        ));
+    }
     for p in &opts.ports {
-        crate::ports::parse_publish_spec(p)?;
+        let spec = crate::ports::parse_publish_spec(p)?;
+        if !spec.is_plain() && !opts.virtio_net {
+            return Err(crate::Error::InvalidConfig(format!(
+                "port {p:?}: bind addresses, UDP and port ranges require virtio-net"
+            )));
+        }
     }
-    Ok(())
+    bux_net::EgressPolicy::parse(&opts.allow_net)?;
+    policy.enforce(opts)
 }
 
 /// Resolve [`ImageRef`] into builder + label + optional OCI process config.
@@ -119,9 +179,20 @@ async fn resolve_image(
     match image {
         ImageRef::Oci(reference) => {
             on_progress("pulling/ensuring OCI image");
-            let pull = rt.oci().ensure(reference, on_progress).await?;
+            let pull = rt
+                .oci()
+                .ensure(reference, |p| {
+                    if !matches!(p, bux_oci::PullProgress::LayerProgress { .. }) {
+                        on_progress(&p.to_string());
+                    }
+                })
+                .await?;
             let oci_cfg = pull.config.clone();
 
+            on_progress("verifying image");
+            let verification = rt.oci().verify(&pull, &rt.image_policy()).await?;
+            info!(image = %verification.reference, method = ?verification.method, "image admitted");
+
             on_progress("building ext4 base disk");
             let image_label = reference.clone();
             let base_path = {
@@ -138,7 +209,9 @@ async fn resolve_image(
             };
 
             Ok((
-                Vm::builder().base_disk(base_path.to_string_lossy()),
+                Vm::builder()
+                    .base_disk(base_path.to_string_lossy())
+                    .image_verification(verification),
                 Some(image_label),
                 oci_cfg,
             ))
@@ -190,7 +263,7 @@ pub(crate) async fn create_from_oci(
     }
 
     on_progress("validating options");
-    validate(&opts)?;
+    validate(&opts, rt.host_policy())?;
 
     on_progress("resolving image");
     let (builder, image_label, _oci_cfg) = resolve_image(rt, &opts.image, &on_progress).await?;
@@ -198,7 +271,8 @@ pub(crate) async fn create_from_oci(
         builder
             .vcpus(opts.vcpus)
             .ram_mib(opts.ram_mib)
-            .virtio_net(opts.virtio_net),
+            .virtio_net(opts.virtio_net)
+            .net_backend(opts.net_backend),
     );
 
     on_progress("spawning shim");
@@ -0,0 +1,635 @@
+//! Runtime-wide host policy constraining what [`VmOptions`] may request.
+//!
+//! Loaded by [`crate::Runtime::open`] from `policy.toml` (or `policy.json`)
+//! in the data directory, so an embedder cannot relax it through
+//! [`VmOptions`]. The Runtime enforces it on every VM's final config at
+//! spawn, however it was built; checking [`VmOptions`] up front is only an
+//! early dry run. Every field defaults to "no constraint"; a missing file
+//! admits everything. Violations are reported together as
+//! [`crate::Error::PolicyDenied`].
+//!
+//! ```toml
+//! max_vcpus = 4
+//! max_ram_mib = 4096
+//! forbidden_paths = ["/home", "/var/lib/secrets"]
+//! deny_degraded_security = true
+//! require_egress_allowlist = true
+//! allowed_registries = ["ghcr.io", "docker.io"]
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::options::{ImageRef, VmOptions};
+use crate::security::SecurityOptions;
+use crate::state::VmConfig;
+use crate::volumes::VolumeSource;
+
+/// Policy file names looked up in the data directory, in order.
+pub const POLICY_FILES: [&str; 2] = ["policy.toml", "policy.json"];
+
+/// Host-side limits on managed VM options.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+#[allow(clippy::struct_excessive_bools, reason = "independent policy switches")]
+pub struct HostPolicy {
+    /// Most vCPUs a VM may request.
+    pub max_vcpus: Option<u8>,
+    /// Most guest RAM (MiB) a VM may request.
+    pub max_ram_mib: Option<u32>,
+    /// Host paths no bind mount, rootfs or base disk may be at or under.
+    pub forbidden_paths: Vec<PathBuf>,
+    /// Reject mounts that set [`crate::VolumeMount::allow_sensitive`].
+    pub deny_sensitive_mounts: bool,
+    /// Reject [`crate::SecurityOptions::allow_degraded`].
+    pub deny_degraded_security: bool,
+    /// Reject VMs that turn the platform jailer off.
+    pub require_jailer: bool,
+    /// Reject VMs without an egress allow-list (virtio-net, at least one
+    /// rule, no explicit allow-all default).
+    pub require_egress_allowlist: bool,
+    /// Registries OCI images may come from (`docker.io`, `ghcr.io`, ...).
+    /// Empty admits any; otherwise local rootfs and base disks are
+    /// rejected too.
+    pub allowed_registries: Vec<String>,
+}
+
+/// Which policy rule a violation broke (named after the policy key).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum PolicyRule {
+    /// [`HostPolicy::max_vcpus`].
+    MaxVcpus,
+    /// [`HostPolicy::max_ram_mib`].
+    MaxRamMib,
+    /// [`HostPolicy::forbidden_paths`].
+    ForbiddenPaths,
+    /// [`HostPolicy::deny_sensitive_mounts`].
+    DenySensitiveMounts,
+    /// [`HostPolicy::deny_degraded_security`].
+    DenyDegradedSecurity,
+    /// [`HostPolicy::require_jailer`].
+    RequireJailer,
+    /// [`HostPolicy::require_egress_allowlist`].
+    RequireEgressAllowlist,
+    /// [`HostPolicy::allowed_registries`].
+    AllowedRegistries,
+}
+
+impl PolicyRule {
+    /// Policy key for this rule.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::MaxVcpus => "max_vcpus",
+            Self::MaxRamMib => "max_ram_mib",
+            Self::ForbiddenPaths => "forbidden_paths",
+            Self::DenySensitiveMounts => "deny_sensitive_mounts",
+            Self::DenyDegradedSecurity => "deny_degraded_security",
+            Self::RequireJailer => "require_jailer",
+            Self::RequireEgressAllowlist => "require_egress_allowlist",
+            Self::AllowedRegistries => "allowed_registries",
+        }
+    }
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// One way [`VmOptions`] break the host policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PolicyViolation {
+    /// Broken rule.
+    pub rule: PolicyRule,
+    /// What the options asked for.
+    pub message: String,
+}
+
+impl PolicyViolation {
+    /// Violation of `rule`.
+    fn new(rule: PolicyRule, message: impl Into<String>) -> Self {
+        Self {
+            rule,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.rule, self.message)
+    }
+}
+
+impl HostPolicy {
+    /// No constraints.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path of the policy file in `data_dir`, if one exists.
+    #[must_use]
+    pub fn find(data_dir: &Path) -> Option<PathBuf> {
+        POLICY_FILES
+            .iter()
+            .map(|name| data_dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads the policy file in `data_dir`, or no constraints without one.
+    ///
+    /// # Errors
+    ///
+    /// See [`Self::from_file`].
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        Self::find(data_dir).map_or_else(|| Ok(Self::default()), |path| Self::from_file(&path))
+    }
+
+    /// Parses a policy file: JSON for a `.json` extension, TOML otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Io`] if the file cannot be read, or
+    /// [`crate::Error::InvalidConfig`] if it does not parse.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let parsed = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| {
+            crate::Error::InvalidConfig(format!("host policy {}: {e}", path.display()))
+        })
+    }
+
+    /// Every way `opts` break this policy (empty when admitted).
+    ///
+    /// A dry run for the options a caller asks for; the Runtime enforces
+    /// the policy again on the final config at spawn
+    /// ([`Self::check_config`]).
+    #[must_use]
+    pub fn check(&self, opts: &VmOptions) -> Vec<PolicyViolation> {
+        let image = match opts.image {
+            ImageRef::Oci(ref reference) => Image::Oci(reference),
+            ImageRef::Rootfs(ref p) | ImageRef::BaseDisk(ref p) => Image::Local(p),
+        };
+        let binds = opts.volumes.iter().filter_map(|m| match m.source {
+            VolumeSource::Bind { ref host_path } => Some(host_path.as_path()),
+            VolumeSource::Named { .. } => None,
+        });
+        let local = match image {
+            Image::Local(p) => Some(p),
+            Image::Oci(_) | Image::None => None,
+        };
+        self.check_subject(&Subject {
+            vcpus: opts.vcpus,
+            ram_mib: opts.ram_mib,
+            paths: local.into_iter().chain(binds).collect(),
+            sensitive: opts
+                .volumes
+                .iter()
+                .filter(|m| m.allow_sensitive)
+                .map(|m| format!("mount at {} allows sensitive host paths", m.guest_path))
+                .collect(),
+            security: opts.security,
+            virtio_net: opts.virtio_net,
+            allow_rules: !opts.allow_net.is_empty(),
+            egress_default_deny: opts.egress_default_deny,
+            image,
+        })
+    }
+
+    /// Every way the final `config` of a VM about to spawn breaks this
+    /// policy, whether it came from [`VmOptions`] or a hand-built
+    /// [`crate::VmBuilder`].
+    ///
+    /// Shares are judged by where they resolve on the host, so
+    /// `deny_sensitive_mounts` rejects any share under a sensitive prefix.
+    /// Only images the Runtime pulled and verified count as registry
+    /// images.
+    #[must_use]
+    pub fn check_config(&self, config: &VmConfig) -> Vec<PolicyViolation> {
+        let local = config
+            .rootfs
+            .iter()
+            .chain(&config.base_disk)
+            .chain(&config.root_disk)
+            .map(Path::new);
+        let image = match (&config.image_verification, local.clone().next()) {
+            (Some(v), _) => Image::Oci(&v.reference),
+            (None, Some(p)) => Image::Local(p),
+            (None, None) => Image::None,
+        };
+        let shares = config.virtiofs.iter().map(|v| Path::new(&v.path));
+        self.check_subject(&Subject {
+            vcpus: config.vcpus,
+            ram_mib: config.ram_mib,
+            paths: local.chain(shares).collect(),
+            sensitive: config
+                .virtiofs
+                .iter()
+                .filter(|v| crate::volumes::is_sensitive(&resolve(Path::new(&v.path))))
+                .map(|v| format!("share {} exposes sensitive host path {}", v.tag, v.path))
+                .collect(),
+            security: config.security,
+            virtio_net: config.virtio_net,
+            allow_rules: !config.allow_net.is_empty(),
+            egress_default_deny: config.egress_default_deny,
+            image,
+        })
+    }
+
+    /// Rejects `opts` with [`crate::Error::PolicyDenied`] if they break
+    /// this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::PolicyDenied`] listing every violation.
+    pub fn enforce(&self, opts: &VmOptions) -> Result<()> {
+        denied(self.check(opts))
+    }
+
+    /// Rejects a final VM `config` that breaks this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::PolicyDenied`] listing every violation.
+    pub fn enforce_config(&self, config: &VmConfig) -> Result<()> {
+        denied(self.check_config(config))
+    }
+
+    /// Every rule, against either view of a VM.
+    fn check_subject(&self, subject: &Subject<'_>) -> Vec<PolicyViolation> {
+        let mut out = Vec::new();
+        if let Some(max) = self.max_vcpus
+            && subject.vcpus > max
+        {
+            out.push(PolicyViolation::new(
+                PolicyRule::MaxVcpus,
+                format!("{} vCPUs requested, at most {max} allowed", subject.vcpus),
+            ));
+        }
+        if let Some(max) = self.max_ram_mib
+            && subject.ram_mib > max
+        {
+            out.push(PolicyViolation::new(
+                PolicyRule::MaxRamMib,
+                format!(
+                    "{} MiB RAM requested, at most {max} allowed",
+                    subject.ram_mib
+                ),
+            ));
+        }
+        self.check_paths(subject, &mut out);
+        self.check_security(subject, &mut out);
+        self.check_image(subject.image, &mut out);
+        out
+    }
+
+    /// Forbidden host paths and sensitive mounts.
+    fn check_paths(&self, subject: &Subject<'_>, out: &mut Vec<PolicyViolation>) {
+        for path in &subject.paths {
+            let resolved = resolve(path);
+            if let Some(forbidden) = self
+                .forbidden_paths
+                .iter()
+                .find(|f| resolved.starts_with(resolve(f)))
+            {
+                out.push(PolicyViolation::new(
+                    PolicyRule::ForbiddenPaths,
+                    format!("{} is under {}", path.display(), forbidden.display()),
+                ));
+            }
+        }
+        if self.deny_sensitive_mounts {
+            for message in &subject.sensitive {
+                out.push(PolicyViolation::new(
+                    PolicyRule::DenySensitiveMounts,
+                    message.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Security layers and egress.
+    fn check_security(&self, subject: &Subject<'_>, out: &mut Vec<PolicyViolation>) {
+        if self.deny_degraded_security && subject.security.allow_degraded {
+            out.push(PolicyViolation::new(
+                PolicyRule::DenyDegradedSecurity,
+                "allow_degraded is set",
+            ));
+        }
+        if self.require_jailer && !subject.security.jailer {
+            out.push(PolicyViolation::new(
+                PolicyRule::RequireJailer,
+                "the jailer is disabled",
+            ));
+        }
+        if self.require_egress_allowlist {
+            let reason = if !subject.virtio_net {
+                Some("egress rules need virtio-net")
+            } else if !subject.allow_rules {
+                Some("no egress allow rules")
+            } else if subject.egress_default_deny == Some(false) {
+                Some("egress default is allow")
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                out.push(PolicyViolation::new(
+                    PolicyRule::RequireEgressAllowlist,
+                    reason,
+                ));
+            }
+        }
+    }
+
+    /// Image source registry.
+    fn check_image(&self, image: Image<'_>, out: &mut Vec<PolicyViolation>) {
+        if self.allowed_registries.is_empty() {
+            return;
+        }
+        let message = match image {
+            Image::Oci(reference) => match bux_oci::image_registry(reference) {
+                Ok(registry)
+                    if self
+                        .allowed_registries
+                        .iter()
+                        .any(|r| r.eq_ignore_ascii_case(&registry)) =>
+                {
+                    return;
+                }
+                Ok(registry) => format!("{reference} is from {registry}"),
+                Err(e) => format!("{reference}: {e}"),
+            },
+            Image::Local(p) => format!("{} is a local image", p.display()),
+            Image::None => return,
+        };
+        out.push(PolicyViolation::new(PolicyRule::AllowedRegistries, message));
+    }
+}
+
+/// What the rules look at, taken from [`VmOptions`] or a [`VmConfig`].
+struct Subject<'a> {
+    /// Requested vCPUs.
+    vcpus: u8,
+    /// Requested guest RAM (MiB).
+    ram_mib: u32,
+    /// Host paths the VM reaches: local image and shares.
+    paths: Vec<&'a Path>,
+    /// One message per mount reaching sensitive host paths.
+    sensitive: Vec<String>,
+    /// Requested security layers.
+    security: SecurityOptions,
+    /// Whether virtio-net (and so egress filtering) is on.
+    virtio_net: bool,
+    /// Whether any egress allow rule is set.
+    allow_rules: bool,
+    /// Explicit egress default.
+    egress_default_deny: Option<bool>,
+    /// Where the root filesystem comes from.
+    image: Image<'a>,
+}
+
+/// Root filesystem source as far as `allowed_registries` is concerned.
+#[derive(Clone, Copy)]
+enum Image<'a> {
+    /// An OCI reference.
+    Oci(&'a str),
+    /// A local rootfs directory or disk image.
+    Local(&'a Path),
+    /// No root filesystem configured.
+    None,
+}
+
+/// `Ok` when nothing was violated.
+fn denied(violations: Vec<PolicyViolation>) -> Result<()> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::Error::PolicyDenied(violations))
+    }
+}
+
+/// Canonical form of `path` when it exists, so symlinks and `..` cannot
+/// step around a forbidden prefix.
+fn resolve(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests"
+)]
+mod tests {
+    use super::*;
+    use crate::volumes::VolumeMount;
+
+    fn rules(policy: &HostPolicy, opts: &VmOptions) -> Vec<PolicyRule> {
+        policy.check(opts).into_iter().map(|v| v.rule).collect()
+    }
+
+    #[test]
+    fn empty_policy_admits_everything() {
+        let opts = VmOptions::from_image("python:slim")
+            .vcpus(32)
+            .security(SecurityOptions::new().jailer(false).allow_degraded(true));
+        assert!(HostPolicy::new().check(&opts).is_empty());
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let policy: HostPolicy = toml::from_str(
+            r#"
+            max_vcpus = 2
+            max_ram_mib = 1024
+            forbidden_paths = ["/etc"]
+            deny_sensitive_mounts = true
+            deny_degraded_security = true
+            require_jailer = true
+            require_egress_allowlist = true
+            allowed_registries = ["ghcr.io"]
+            "#,
+        )
+        .unwrap();
+        let opts = VmOptions::from_image("python:slim")
+            .vcpus(4)
+            .ram_mib(2048)
+            .volume(VolumeMount::bind("/etc/ssl", "/ssl").allow_sensitive(true))
+            .security(SecurityOptions::new().jailer(false).allow_degraded(true));
+        assert_eq!(
+            rules(&policy, &opts),
+            vec![
+                PolicyRule::MaxVcpus,
+                PolicyRule::MaxRamMib,
+                PolicyRule::ForbiddenPaths,
+                PolicyRule::DenySensitiveMounts,
+                PolicyRule::DenyDegradedSecurity,
+                PolicyRule::RequireJailer,
+                PolicyRule::RequireEgressAllowlist,
+                PolicyRule::AllowedRegistries,
+            ]
+        );
+        let err = policy.enforce(&opts).unwrap_err();
+        assert!(err.is_user_error());
+        assert!(err.to_string().contains("max_vcpus: 4 vCPUs requested"));
+
+        let ok = VmOptions::from_image("ghcr.io/acme/agent:1")
+            .allow_net(["pypi.org"])
+            .volume(VolumeMount::bind("/srv/data", "/data"));
+        assert!(policy.enforce(&ok).is_ok());
+    }
+
+    #[test]
+    fn egress_allowlist_needs_rules_and_deny_default() {
+        let policy = HostPolicy {
+            require_egress_allowlist: true,
+            ..HostPolicy::default()
+        };
+        let opts = VmOptions::from_image("alpine").allow_net(["pypi.org"]);
+        assert!(policy.check(&opts).is_empty());
+        assert_eq!(
+            rules(&policy, &opts.egress_default_deny(false)),
+            vec![PolicyRule::RequireEgressAllowlist]
+        );
+    }
+
+    #[test]
+    fn registry_list_rejects_local_images() {
+        let mut policy = HostPolicy::new();
+        let opts = VmOptions::from_image(ImageRef::Rootfs("/srv/rootfs".into()));
+        assert!(policy.check(&opts).is_empty());
+        policy.allowed_registries = vec!["docker.io".into()];
+        assert_eq!(rules(&policy, &opts), vec![PolicyRule::AllowedRegistries]);
+        assert!(
+            policy
+                .check(&VmOptions::from_image("python:slim"))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn final_config_is_checked_like_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = HostPolicy {
+            max_vcpus: Some(2),
+            forbidden_paths: vec![dir.path().join("secret")],
+            deny_sensitive_mounts: true,
+            allowed_registries: vec!["ghcr.io".into()],
+            ..HostPolicy::default()
+        };
+        let secret = dir.path().join("secret/keys");
+        let config = crate::Vm::builder()
+            .vcpus(4)
+            .root(dir.path().to_string_lossy())
+            .virtiofs("keys", secret.to_string_lossy())
+            .virtiofs("ssh", "/etc/ssh")
+            .to_config();
+        let found: Vec<_> = policy
+            .check_config(&config)
+            .into_iter()
+            .map(|v| v.rule)
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                PolicyRule::MaxVcpus,
+                PolicyRule::ForbiddenPaths,
+                PolicyRule::DenySensitiveMounts,
+                PolicyRule::AllowedRegistries,
+            ]
+        );
+        assert!(
+            policy
+                .enforce_config(&config)
+                .unwrap_err()
+                .is_policy_denied()
+        );
+    }
+
+    #[test]
+    fn pulled_image_config_is_judged_by_its_reference() {
+        let dir = tempfile::tempdir().unwrap();
+        let policy = HostPolicy {
+            allowed_registries: vec!["ghcr.io".into()],
+            ..HostPolicy::default()
+        };
+        let base = dir.path().join("base.raw");
+        let disk = || crate::Vm::builder().base_disk(base.to_string_lossy());
+        let pulled = |reference: &str| {
+            let verification = serde_json::from_value(serde_json::json!({
+                "reference": reference,
+                "digest": "sha256:aa",
+                "scope": null,
+                "method": { "kind": "accepted" },
+            }))
+            .unwrap();
+            disk().image_verification(verification).to_config()
+        };
+
+        assert!(
+            policy
+                .check_config(&pulled("ghcr.io/acme/agent:1"))
+                .is_empty()
+        );
+        let denied = |config: &VmConfig, why: &str| {
+            matches!(
+                policy.check_config(config).as_slice(),
+                [v] if v.rule == PolicyRule::AllowedRegistries && v.message.contains(why)
+            )
+        };
+        assert!(denied(
+            &pulled("docker.io/library/python:slim"),
+            "is from docker.io"
+        ));
+        // Without the admission record the same disk is a local image.
+        assert!(denied(&disk().to_config(), "is a local image"));
+    }
+
+    #[test]
+    fn runtime_spawn_rejects_forbidden_share() {
+        let dir = tempfile::tempdir().unwrap();
+        let (secret, data) = (dir.path().join("secret"), dir.path().join("data"));
+        std::fs::create_dir(&secret).unwrap();
+        std::fs::create_dir(&data).unwrap();
+        std::fs::write(
+            data.join("policy.toml"),
+            format!("forbidden_paths = [{:?}]\n", secret.display().to_string()),
+        )
+        .unwrap();
+        let rt = crate::Runtime::open(&data).unwrap();
+        let builder = crate::Vm::builder()
+            .root(dir.path().to_string_lossy())
+            .virtiofs("keys", secret.join("keys").to_string_lossy());
+        let err = rt.spawn(&builder, None, None, false).unwrap_err();
+        assert!(err.is_policy_denied(), "{err}");
+    }
+
+    #[test]
+    fn loads_toml_or_json_from_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(HostPolicy::load(dir.path()).unwrap(), HostPolicy::new());
+        std::fs::write(dir.path().join("policy.json"), r#"{"max_vcpus":2}"#).unwrap();
+        assert_eq!(HostPolicy::load(dir.path()).unwrap().max_vcpus, Some(2));
+        std::fs::write(dir.path().join("policy.toml"), "max_vcpus = 3\n").unwrap();
+        assert_eq!(HostPolicy::load(dir.path()).unwrap().max_vcpus, Some(3));
+        std::fs::write(dir.path().join("policy.toml"), "max_cpus = 3\n").unwrap();
+        assert!(matches!(
+            HostPolicy::load(dir.path()),
+            Err(crate::Error::InvalidConfig(_))
+        ));
+    }
+}
@@ -1,23 +1,44 @@
 //! Port publish specs and ephemeral host-port resolution.
 //!
-//! v1: TCP only; host bind address is always `0.0.0.0` (matches gvproxy).
+//! A spec names an optional host bind address, a host port or range, a
+//! guest port or range and the transport:
+//! `[bind:][host[-end]:]guest[-end][/tcp|/udp]`. The bind address defaults
+//! to [`BIND_ADDR`] (all interfaces); pass `127.0.0.1` to keep a port off
+//! the LAN. Ranges are expanded into one [`PublishedPort`] per port.
 
-use std::net::TcpListener;
+use std::fmt;
+use std::net::{IpAddr, TcpListener, UdpSocket};
 
+use bux_net::PortProtocol;
 use serde::{Deserialize, Serialize};
 
 use crate::Result;
 
-/// Host bind address used for all published ports (gvproxy forward key).
+/// Default host bind address for published ports (all interfaces).
 pub const BIND_ADDR: &str = "0.0.0.0";
 
+/// Serde default for [`PortSpec::count`].
+const fn one() -> u16 {
+    1
+}
+
 /// Requested port publish mapping (before ephemeral resolution).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PortSpec {
-    /// Host port. `None` or `Some(0)` → probe an ephemeral free port.
+    /// Host address to bind. `None` → [`BIND_ADDR`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bind: Option<IpAddr>,
+    /// First host port. `None` or `Some(0)` → probe an ephemeral free port
+    /// for each port of the range.
     pub host: Option<u16>,
-    /// Guest TCP port.
+    /// First guest port.
     pub guest: u16,
+    /// Number of consecutive ports (1 for a single port).
+    #[serde(default = "one")]
+    pub count: u16,
+    /// Transport forwarded.
+    #[serde(default)]
+    pub protocol: PortProtocol,
 }
 
 impl PortSpec {
@@ -25,132 +46,282 @@ impl PortSpec {
     #[must_use]
     pub const fn new(host: u16, guest: u16) -> Self {
         Self {
+            bind: None,
             host: Some(host),
             guest,
+            count: 1,
+            protocol: PortProtocol::Tcp,
         }
     }
 
     /// Ephemeral host port → fixed guest port.
     #[must_use]
     pub const fn ephemeral(guest: u16) -> Self {
-        Self { host: None, guest }
+        Self {
+            bind: None,
+            host: None,
+            guest,
+            count: 1,
+            protocol: PortProtocol::Tcp,
+        }
+    }
+
+    /// Bind only `ip` on the host.
+    #[must_use]
+    pub const fn with_bind(mut self, ip: IpAddr) -> Self {
+        self.bind = Some(ip);
+        self
+    }
+
+    /// Forward `protocol` instead of TCP.
+    #[must_use]
+    pub const fn with_protocol(mut self, protocol: PortProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Publish `count` consecutive ports starting at the host and guest
+    /// ports.
+    #[must_use]
+    pub const fn with_count(mut self, count: u16) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Whether the spec only uses what TSI port maps support (TCP on all
+    /// interfaces, single port).
+    #[must_use]
+    pub const fn is_plain(&self) -> bool {
+        self.bind.is_none() && self.count == 1 && matches!(self.protocol, PortProtocol::Tcp)
     }
 }
 
-/// Concrete published port after resolution (what gvproxy actually binds).
+/// Concrete published port after resolution (what the backend binds).
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PublishedPort {
-    /// Host TCP port (always concrete, never 0).
+    /// Host port (always concrete, never 0).
     pub host: u16,
-    /// Guest TCP port.
+    /// Guest port.
     pub guest: u16,
-    /// Always [`BIND_ADDR`] in v1.
+    /// Host bind address ([`BIND_ADDR`] unless restricted).
     pub bind_addr: String,
+    /// Transport forwarded.
+    #[serde(default)]
+    pub protocol: PortProtocol,
 }
 
 impl PublishedPort {
-    /// Construct with the v1 bind address.
+    /// TCP on [`BIND_ADDR`].
     #[must_use]
     pub fn new(host: u16, guest: u16) -> Self {
         Self {
             host,
             guest,
             bind_addr: BIND_ADDR.to_owned(),
+            protocol: PortProtocol::Tcp,
+        }
+    }
+
+    /// Bind only `ip` on the host.
+    #[must_use]
+    pub fn with_bind(mut self, ip: IpAddr) -> Self {
+        self.bind_addr = ip.to_string();
+        self
+    }
+
+    /// Forward `protocol` instead of TCP.
+    #[must_use]
+    pub const fn with_protocol(mut self, protocol: PortProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Whether `other` needs the same host port (same number and
+    /// transport; binds on one port overlap).
+    #[must_use]
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        self.host == other.host && self.protocol == other.protocol
+    }
+
+    /// Parsed host bind address.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidConfig`] if `bind_addr` is not an IP.
+    pub fn bind_ip(&self) -> Result<IpAddr> {
+        self.bind_addr.parse().map_err(|_| {
+            crate::Error::InvalidConfig(format!("invalid bind address {:?}", self.bind_addr))
+        })
+    }
+}
+
+impl fmt::Display for PublishedPort {
+    /// Formats as a publish spec that [`parse_publish_spec`] reads back
+    /// (`host:guest` for TCP on [`BIND_ADDR`]).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.bind_addr.contains(':') {
+            write!(f, "[{}]:", self.bind_addr)?;
+        } else if self.bind_addr != BIND_ADDR {
+            write!(f, "{}:", self.bind_addr)?;
+        }
+        write!(f, "{}:{}", self.host, self.guest)?;
+        match self.protocol {
+            PortProtocol::Tcp => Ok(()),
+            PortProtocol::Udp => f.write_str("/udp"),
         }
     }
 }
 
+/// Parses `port` or `start-end` into the first port and the port count.
+fn parse_port_range(s: &str, what: &str, spec: &str) -> Result<(u16, u16)> {
+    let invalid = || crate::Error::InvalidConfig(format!("invalid {what} port in {spec:?}"));
+    let (start, end) = s.split_once('-').unwrap_or((s, s));
+    let start = start.parse::<u16>().map_err(|_| invalid())?;
+    let end = end.parse::<u16>().map_err(|_| invalid())?;
+    if end < start {
+        return Err(crate::Error::InvalidConfig(format!(
+            "{what} port range ends before it starts in {spec:?}"
+        )));
+    }
+    Ok((start, end - start + 1))
+}
+
+/// Splits an optional bind address off `map`: `[v6]:rest`, `v4:host:guest`.
+fn split_bind<'a>(map: &'a str, spec: &str) -> Result<(Option<IpAddr>, &'a str)> {
+    let invalid = || crate::Error::InvalidConfig(format!("invalid bind address in {spec:?}"));
+    if let Some(rest) = map.strip_prefix('[') {
+        let (ip, rest) = rest.split_once("]:").ok_or_else(invalid)?;
+        return Ok((Some(ip.parse().map_err(|_| invalid())?), rest));
+    }
+    if map.matches(':').count() == 2 {
+        let (ip, rest) = map.split_once(':').ok_or_else(invalid)?;
+        return Ok((Some(ip.parse().map_err(|_| invalid())?), rest));
+    }
+    Ok((None, map))
+}
+
 /// Parse CLI / Docker-style publish strings.
 ///
 /// Accepted:
 /// - `8080:80` — host 8080 → guest 80
 /// - `80` — ephemeral host → guest 80
 /// - `0:80` / `:80` — ephemeral host → guest 80
-/// - optional `/tcp` suffix (default); `/udp` is rejected in v1
+/// - `127.0.0.1:8080:80`, `[::1]:8080:80` — bind one host address
+///   (`127.0.0.1::80` with an ephemeral host port)
+/// - `9000-9010:9000-9010` — equal-length ranges; `9000-9010` alone takes
+///   ephemeral host ports
+/// - optional `/tcp` (default) or `/udp` suffix
 ///
 /// # Errors
 ///
-/// Returns [`crate::Error::InvalidConfig`] on malformed input or UDP.
+/// Returns [`crate::Error::InvalidConfig`] on malformed input, an unknown
+/// protocol, or host and guest ranges of different lengths.
 pub fn parse_publish_spec(spec: &str) -> Result<PortSpec> {
     let (map, proto) = match spec.rsplit_once('/') {
         Some((m, p)) => (m, Some(p)),
         None => (spec, None),
     };
-    if let Some(p) = proto {
-        let p = p.to_ascii_lowercase();
-        if p != "tcp" {
+    let protocol = match proto.map(str::to_ascii_lowercase).as_deref() {
+        None | Some("tcp") => PortProtocol::Tcp,
+        Some("udp") => PortProtocol::Udp,
+        Some(p) => {
             return Err(crate::Error::InvalidConfig(format!(
-                "unsupported port protocol /{p} in {spec:?}; v1 is TCP only"
+                "unsupported port protocol /{p} in {spec:?}; expected tcp or udp"
             )));
         }
-    }
+    };
 
-    // ":80" or "0:80" or "8080:80" or "80"
-    if let Some((host_s, guest_s)) = map.split_once(':') {
-        let guest: u16 = guest_s
-            .parse()
-            .map_err(|_| crate::Error::InvalidConfig(format!("invalid guest port in {spec:?}")))?;
-        if host_s.is_empty() || host_s == "0" {
-            return Ok(PortSpec::ephemeral(guest));
+    let (bind, map) = split_bind(map, spec)?;
+    let (host_s, guest_s) = match map.split_once(':') {
+        Some((host_s, guest_s)) => (Some(host_s), guest_s),
+        None if bind.is_some() => {
+            return Err(crate::Error::InvalidConfig(format!(
+                "invalid port spec {spec:?}"
+            )));
         }
-        let host: u16 = host_s
-            .parse()
-            .map_err(|_| crate::Error::InvalidConfig(format!("invalid host port in {spec:?}")))?;
-        if host == 0 {
-            return Ok(PortSpec::ephemeral(guest));
+        None => (None, map),
+    };
+    let (guest, count) = parse_port_range(guest_s, "guest", spec)?;
+    let host = match host_s {
+        None | Some("" | "0") => None,
+        Some(host_s) => {
+            let (host, host_count) = parse_port_range(host_s, "host", spec)?;
+            if host_count != count {
+                return Err(crate::Error::InvalidConfig(format!(
+                    "host and guest port ranges differ in length in {spec:?}"
+                )));
+            }
+            Some(host).filter(|&h| h != 0)
         }
-        return Ok(PortSpec::new(host, guest));
-    }
-
-    // bare guest port → ephemeral host
-    let guest: u16 = map
-        .parse()
-        .map_err(|_| crate::Error::InvalidConfig(format!("invalid port spec {spec:?}")))?;
-    Ok(PortSpec::ephemeral(guest))
+    };
+    Ok(PortSpec {
+        bind,
+        host,
+        guest,
+        count,
+        protocol,
+    })
 }
 
-/// Concrete host→guest port pairs after ephemeral resolution.
-pub type PortPairs = Vec<(u16, u16)>;
-
-/// Resolve specs to concrete `(host, guest)` pairs and [`PublishedPort`] list.
+/// Resolve specs to concrete [`PublishedPort`]s, one per port of each
+/// range.
 ///
-/// Ephemeral ports are chosen by binding `0.0.0.0:0` then releasing the
-/// socket so gvproxy can re-bind (small race window; acceptable for v1).
+/// Ephemeral ports are chosen by binding port 0 on the spec's address
+/// then releasing the socket so the backend can re-bind (small race
+/// window; acceptable for v1).
 ///
 /// # Errors
 ///
 /// Returns config or I/O errors from probe-bind.
-pub fn resolve_ports(specs: &[PortSpec]) -> Result<(PortPairs, Vec<PublishedPort>)> {
-    let mut pairs = Vec::with_capacity(specs.len());
+pub fn resolve_ports(specs: &[PortSpec]) -> Result<Vec<PublishedPort>> {
     let mut published = Vec::with_capacity(specs.len());
     for spec in specs {
-        let host = match spec.host {
-            None | Some(0) => probe_ephemeral_port()?,
-            Some(h) => h,
+        let bind = match spec.bind {
+            Some(ip) => ip,
+            None => BIND_ADDR.parse().map_err(|_| {
+                crate::Error::InvalidConfig(format!("bad bind address constant {BIND_ADDR}"))
+            })?,
         };
-        pairs.push((host, spec.guest));
-        published.push(PublishedPort::new(host, spec.guest));
+        for offset in 0..spec.count {
+            let guest = spec.guest.checked_add(offset).ok_or_else(|| {
+                crate::Error::InvalidConfig(format!("guest port range past 65535 in {spec:?}"))
+            })?;
+            let host = match spec.host {
+                None | Some(0) => probe_ephemeral_port(bind, spec.protocol)?,
+                Some(h) => h.checked_add(offset).ok_or_else(|| {
+                    crate::Error::InvalidConfig(format!("host port range past 65535 in {spec:?}"))
+                })?,
+            };
+            published.push(
+                PublishedPort::new(host, guest)
+                    .with_bind(bind)
+                    .with_protocol(spec.protocol),
+            );
+        }
     }
-    Ok((pairs, published))
+    Ok(published)
 }
 
-/// Bind `0.0.0.0:0` and return the kernel-assigned port.
-fn probe_ephemeral_port() -> Result<u16> {
-    let listener = TcpListener::bind((BIND_ADDR, 0)).map_err(|e| {
+/// Bind port 0 on `bind` for `protocol` and return the kernel-assigned port.
+fn probe_ephemeral_port(bind: IpAddr, protocol: PortProtocol) -> Result<u16> {
+    let probed = match protocol {
+        PortProtocol::Tcp => TcpListener::bind((bind, 0)).and_then(|l| l.local_addr()),
+        PortProtocol::Udp => UdpSocket::bind((bind, 0)).and_then(|s| s.local_addr()),
+    };
+    let addr = probed.map_err(|e| {
         crate::Error::Io(std::io::Error::new(
             e.kind(),
             format!("ephemeral port probe failed: {e}"),
         ))
     })?;
-    let port = listener.local_addr()?.port();
-    drop(listener);
-    Ok(port)
+    Ok(addr.port())
 }
 
-/// Format concrete pairs as legacy `"host:guest"` strings (TSI / storage).
+/// Format published ports as publish specs (storage; see
+/// [`PublishedPort`]'s `Display`).
 #[must_use]
-pub fn format_port_pairs(pairs: &[(u16, u16)]) -> Vec<String> {
-    pairs.iter().map(|(h, g)| format!("{h}:{g}")).collect()
+pub fn format_published_ports(ports: &[PublishedPort]) -> Vec<String> {
+    ports.iter().map(ToString::to_string).collect()
 }
 
 /// Parse stored `"host:guest"` list into concrete pairs (no ephemeral).
@@ -183,7 +354,7 @@ pub(crate) fn parse_concrete_port_strings(ports: &[String]) -> Result<Vec<(u16,
 }
 
 #[cfg(test)]
-#[allow(clippy::unwrap_used, reason = "tests")]
+#[allow(clippy::unwrap_used, clippy::indexing_slicing, reason = "tests")]
 mod tests {
     use super::*;
 
@@ -200,24 +371,90 @@ fn parse_publish_variants() {
             parse_publish_spec("8080:80/tcp").unwrap(),
             PortSpec::new(8080, 80)
         );
-        assert!(parse_publish_spec("8080:80/udp").is_err());
+        assert_eq!(
+            parse_publish_spec("53:53/UDP").unwrap(),
+            PortSpec::new(53, 53).with_protocol(PortProtocol::Udp)
+        );
+        assert!(parse_publish_spec("8080:80/sctp").is_err());
+    }
+
+    #[test]
+    fn parse_bind_addresses_and_ranges() {
+        let local = IpAddr::from([127, 0, 0, 1]);
+        assert_eq!(
+            parse_publish_spec("127.0.0.1:8080:80").unwrap(),
+            PortSpec::new(8080, 80).with_bind(local)
+        );
+        assert_eq!(
+            parse_publish_spec("127.0.0.1::80").unwrap(),
+            PortSpec::ephemeral(80).with_bind(local)
+        );
+        assert_eq!(
+            parse_publish_spec("[::1]:8080:80/udp").unwrap(),
+            PortSpec::new(8080, 80)
+                .with_bind(IpAddr::from(std::net::Ipv6Addr::LOCALHOST))
+                .with_protocol(PortProtocol::Udp)
+        );
+        assert_eq!(
+            parse_publish_spec("9000-9010:8000-8010").unwrap(),
+            PortSpec::new(9000, 8000).with_count(11)
+        );
+        assert_eq!(
+            parse_publish_spec("9000-9001").unwrap(),
+            PortSpec::ephemeral(9000).with_count(2)
+        );
+        assert!(!parse_publish_spec("9000-9001").unwrap().is_plain());
+        assert!(parse_publish_spec("9000-9010:9000-9005").is_err());
+        assert!(parse_publish_spec("9010-9000:9010-9000").is_err());
+        assert!(parse_publish_spec("localhost:8080:80").is_err());
+        assert!(parse_publish_spec("127.0.0.1:80").is_err());
     }
 
     #[test]
     fn resolve_fixed() {
-        let (pairs, pubd) = resolve_ports(&[PortSpec::new(18080, 80)]).unwrap();
-        assert_eq!(pairs, vec![(18080, 80)]);
+        let pubd = resolve_ports(&[PortSpec::new(18080, 80)]).unwrap();
         let p = pubd.first().expect("one published port");
         assert_eq!(p.bind_addr, BIND_ADDR);
         assert_eq!(p.host, 18080);
+        assert_eq!(format_published_ports(&pubd), ["18080:80"]);
     }
 
     #[test]
     fn resolve_ephemeral_nonzero() {
-        let (pairs, _) = resolve_ports(&[PortSpec::ephemeral(443)]).unwrap();
-        assert_eq!(pairs.len(), 1);
-        let (host, guest) = pairs.first().copied().expect("one pair");
-        assert_ne!(host, 0);
-        assert_eq!(guest, 443);
+        let pubd = resolve_ports(&[PortSpec::ephemeral(443)]).unwrap();
+        assert_eq!(pubd.len(), 1);
+        let p = pubd.first().expect("one port");
+        assert_ne!(p.host, 0);
+        assert_eq!(p.guest, 443);
+    }
+
+    #[test]
+    fn resolve_expands_ranges_and_round_trips() {
+        let local = IpAddr::from([127, 0, 0, 1]);
+        let specs = [
+            PortSpec::new(19000, 9000)
+                .with_count(3)
+                .with_bind(local)
+                .with_protocol(PortProtocol::Udp),
+            PortSpec::ephemeral(53).with_protocol(PortProtocol::Udp),
+            PortSpec::new(18443, 443).with_bind(IpAddr::from(std::net::Ipv6Addr::LOCALHOST)),
+        ];
+        let pubd = resolve_ports(&specs).unwrap();
+        assert_eq!(pubd.len(), 5);
+        let stored = format_published_ports(&pubd);
+        assert_eq!(
+            stored[..3],
+            [
+                "127.0.0.1:19000:9000/udp",
+                "127.0.0.1:19001:9001/udp",
+                "127.0.0.1:19002:9002/udp",
+            ]
+        );
+        assert_eq!(stored[4], "[::1]:18443:443");
+        for (s, port) in stored.iter().zip(&pubd) {
+            let again = resolve_ports(&[parse_publish_spec(s).unwrap()]).unwrap();
+            assert_eq!(again, std::slice::from_ref(port));
+        }
+        assert!(resolve_ports(&[PortSpec::new(65535, 80).with_count(2)]).is_err());
     }
 }
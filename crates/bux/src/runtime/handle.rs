@@ -10,7 +10,7 @@
 
 use super::HealthStatus;
 use super::spawn::{
-    clean_vm_files, inject_guest_boot_env, is_pid_alive, prepare_managed_config,
+    clean_vm_files, inject_guest_boot_env, is_pid_alive, prepare_managed_config, remove_cgroup,
     shim_death_message, spawn_shim, wait_for_exit,
 };
 use crate::Result;
@@ -18,9 +18,9 @@
 use crate::disk::DiskManager;
 use crate::events::{AuditEvent, AuditEventKind, EventDispatcher};
 use crate::metrics::{BoxMetrics, RuntimeMetrics};
-use crate::net_manager::NetworkManager;
+use crate::net_manager::{NetworkManager, NetworkPatch, egress_policy, port_forwards};
 use crate::ports::{
-    PublishedPort, format_port_pairs, parse_concrete_port_strings, parse_publish_spec,
+    PublishedPort, format_published_ports, parse_concrete_port_strings, parse_publish_spec,
     resolve_ports,
 };
 use crate::secrets::{LiveSecrets, StartOptions};
@@ -153,6 +153,30 @@ pub const fn security_options(&self) -> &crate::security::SecurityOptions {
         &self.state.config.security
     }
 
+    /// Host-side CPU, memory and IO usage of the VMM, read from its cgroup
+    /// without involving the guest agent.
+    ///
+    /// `None` when the VM runs without an enforced host-limits cgroup.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::Cgroup`] if the statistics cannot be read.
+    #[cfg(target_os = "linux")]
+    pub fn host_stats(&self) -> Result<Option<bux_cgroup::CgroupStats>> {
+        if self.state.config.security_status.cgroup != crate::security::LayerStatus::Enforced {
+            return Ok(None);
+        }
+        Ok(Some(bux_cgroup::stats(&self.state.id)?))
+    }
+
+    /// Image admission outcome from the verification policy at create time.
+    ///
+    /// `None` for VMs booted from a rootfs directory or base disk.
+    #[must_use]
+    pub const fn image_verification(&self) -> Option<&bux_oci::Verification> {
+        self.state.config.image_verification.as_ref()
+    }
+
     /// Last error recorded on this VM (e.g. secrets re-supply after recovery).
     #[must_use]
     pub fn last_error(&self) -> Option<&str> {
@@ -183,8 +207,12 @@ pub const fn client(&self) -> &Client {
         &self.client
     }
 
-    /// Returns per-box metrics for this VM.
-    pub const fn box_metrics(&self) -> &BoxMetrics {
+    /// Returns per-box metrics for this VM, with network counters
+    /// refreshed from its running backend.
+    pub fn box_metrics(&self) -> &BoxMetrics {
+        if let Some(net) = self.net.metrics(&self.state.id) {
+            self.box_metrics.set_network(&net);
+        }
         &self.box_metrics
     }
 
@@ -352,6 +380,90 @@ pub async fn exec_output(&self, req: ExecStart) -> Result<ExecOutput> {
         Ok(output)
     }
 
+    /// Applies a [`NetworkPatch`] to the running VM without restarting it.
+    ///
+    /// New connections see the change; established ones are left alone.
+    /// The resulting allow-list and ports are persisted to the VM config
+    /// and a [`NetworkUpdated`](AuditEventKind::NetworkUpdated) event is
+    /// emitted. The gvproxy backend cannot lift a non-empty allow-list
+    /// back to unrestricted egress.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::InvalidState`] if the VM is not running on
+    /// virtio-net, [`crate::Error::InvalidConfig`] for an inapplicable patch
+    /// (unknown or taken host port, secrets without live secrets), or the
+    /// backend error if it rejects the change.
+    pub fn update_network(&mut self, patch: NetworkPatch) -> Result<()> {
+        if self.state.status != Status::Running || !self.state.config.virtio_net {
+            return Err(crate::Error::InvalidState(format!(
+                "VM {} network cannot be updated (status: {:?}, virtio_net: {})",
+                self.state.id, self.state.status, self.state.config.virtio_net
+            )));
+        }
+
+        let allow_net = patch.apply_allow(&self.state.config.allow_net);
+        let (published, added) = patch.apply_ports(&self.state.config.published_ports)?;
+        let egress = egress_policy(&allow_net, self.state.config.egress_default_deny)?;
+        let mut update = bux_net::NetworkUpdate::new(port_forwards(&published)?, egress);
+        let rotated = match patch.secrets {
+            Some(secrets) => {
+                let held = self
+                    .secrets
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .get(&self.state.id)
+                    .cloned()
+                    .ok_or_else(|| {
+                        crate::Error::InvalidConfig(
+                            "secret rotation requires secrets supplied at create or start".into(),
+                        )
+                    })?;
+                let live = held.rotate(secrets);
+                update = update.with_secrets(live.gvproxy_secrets());
+                Some(live)
+            }
+            None => None,
+        };
+        self.net.update(&self.state.id, &update)?;
+
+        let secrets_rotated = rotated.is_some();
+        if let Some(live) = rotated {
+            self.secrets
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .insert(self.state.id.clone(), live);
+        }
+        let previous = std::mem::replace(&mut self.state.config.allow_net, allow_net);
+        let allowed = self
+            .state
+            .config
+            .allow_net
+            .iter()
+            .filter(|rule| !previous.contains(rule))
+            .cloned()
+            .collect();
+        let revoked = previous
+            .into_iter()
+            .filter(|rule| !self.state.config.allow_net.contains(rule))
+            .collect();
+        self.state.config.ports = format_published_ports(&published);
+        self.state.config.published_ports = published;
+        self.db.update_config(&self.state.id, &self.state.config)?;
+
+        info!(vm_id = %self.state.id, secrets_rotated, "VM network updated");
+        self.events
+            .emit(AuditEvent::now(AuditEventKind::NetworkUpdated {
+                box_id: self.state.id.clone(),
+                allowed,
+                revoked,
+                published: added,
+                unpublished: patch.unpublish,
+                secrets_rotated,
+            }));
+        Ok(())
+    }
+
     /// Restarts a stopped VM (uses memory-held secrets if still present).
     ///
     /// If `secrets_required` and secrets were lost (Runtime restart), returns
@@ -398,6 +510,15 @@ pub async fn start_with(&mut self, opts: StartOptions) -> Result<()> {
                 None if self.state.config.secrets_required => {
                     return Err(crate::Error::SecretsRequired);
                 }
+                // The guest reboots, so a fresh CA for HTTP rules is fine.
+                None if !self.state.config.http_policy.is_empty() => {
+                    let live = LiveSecrets::mint(Vec::new())?;
+                    self.secrets
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .insert(self.state.id.clone(), live.clone());
+                    Some(live)
+                }
                 None => None,
             }
         } else {
@@ -421,16 +542,15 @@ pub async fn start_with(&mut self, opts: StartOptions) -> Result<()> {
             for s in &self.state.config.ports {
                 specs.push(parse_publish_spec(s)?);
             }
-            let (pairs, published) = resolve_ports(&specs)?;
-            self.state.config.ports = format_port_pairs(&pairs);
+            let published = resolve_ports(&specs)?;
+            self.state.config.ports = format_published_ports(&published);
             self.state.config.published_ports = published;
-            let net = self.net.start(
+            Some(self.net.start(
                 &self.state.id,
-                pairs,
-                self.state.config.allow_net.clone(),
+                &self.state.config,
+                &self.state.config.published_ports,
                 live.as_ref(),
-            )?;
-            Some(net.shim_network)
+            )?)
         } else {
             let _ = parse_concrete_port_strings(&self.state.config.ports)?;
             self.state.config.published_ports.clear();
@@ -737,6 +857,7 @@ pub async fn handshake(&self) -> Result<()> {
     fn mark_stopped(&mut self) -> Result<()> {
         self.state.status = Status::Stopped;
         self.net.stop(&self.state.id);
+        remove_cgroup(&self.state.id);
 
         let uptime_ms = u64::try_from(self.spawned_at.elapsed().as_millis()).unwrap_or(u64::MAX);
         self.runtime_metrics.on_box_stopped(uptime_ms);
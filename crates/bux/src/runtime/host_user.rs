@@ -0,0 +1,266 @@
+//! Dedicated host uid/gid per VM (jailer credential isolation).
+//!
+//! With [`SecurityOptions::dedicated_user`] set, each VM takes its own id
+//! from the configured range as both uid and gid. The files bux creates for
+//! the VM (overlay, shim config, network socket) are handed to that id
+//! before the shim drops to it, so two VMs cannot read each other's files
+//! on the host. User-supplied paths (a rootfs directory, bind volumes) keep
+//! their owners: the shim reaches them through their owning group, kept as
+//! a supplementary group. The id is free again once the VM's row is
+//! deleted.
+
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::Result;
+use crate::security::{LayerStatus, SecurityOptions, UidRange};
+use crate::state::VmConfig;
+
+/// Mode of the sockets directory while VMs run as dedicated users: anyone
+/// may create entries (the agent socket, the exit record) but only owners
+/// may remove or list them.
+const SHARED_SOCKS_MODE: u32 = 0o1733;
+
+/// Ids reserved by spawns that have not persisted their VM yet.
+#[derive(Debug, Default)]
+pub(super) struct HostUsers {
+    /// Ids handed out but not yet recorded in the state database.
+    pending: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl HostUsers {
+    /// Reserves the lowest id in `range` neither in `taken` (ids recorded
+    /// for existing VMs) nor reserved by another spawn.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::Error::SecurityUnavailable`] when the range is
+    /// exhausted.
+    pub(super) fn reserve(&self, range: UidRange, taken: &HashSet<u32>) -> Result<HostUserLease> {
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        let uid = range
+            .ids()
+            .find(|id| !taken.contains(id) && !pending.contains(id))
+            .ok_or_else(|| {
+                crate::Error::SecurityUnavailable(format!(
+                    "no free dedicated user in {}..{}",
+                    range.first,
+                    u64::from(range.first) + u64::from(range.count)
+                ))
+            })?;
+        pending.insert(uid);
+        drop(pending);
+        Ok(HostUserLease {
+            uid,
+            pending: Arc::clone(&self.pending),
+        })
+    }
+}
+
+/// An id reserved for a VM being spawned. Released on drop, by which time
+/// the VM's persisted config records it (or the spawn failed).
+#[derive(Debug)]
+pub(super) struct HostUserLease {
+    /// Reserved uid (and gid).
+    uid: u32,
+    /// Reservation set to release the id from.
+    pending: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl HostUserLease {
+    /// Reserved uid (and gid).
+    pub(super) const fn uid(&self) -> u32 {
+        self.uid
+    }
+}
+
+impl Drop for HostUserLease {
+    fn drop(&mut self) {
+        self.pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.uid);
+    }
+}
+
+/// Resolve the dedicated-user layer for a spawn with `assigned` as the
+/// VM's recorded id (fail-closed like the other layers).
+///
+/// Switching users takes root; an unprivileged Runtime degrades to its own
+/// user under `allow_degraded`.
+pub(super) fn status(sec: SecurityOptions, assigned: Option<u32>) -> Result<LayerStatus> {
+    if !cfg!(target_os = "linux") {
+        return Ok(LayerStatus::NotApplicable);
+    }
+    if sec.dedicated_user.is_none() {
+        Ok(LayerStatus::Disabled)
+    } else if nix::unistd::geteuid().is_root() && assigned.is_some() {
+        Ok(LayerStatus::Enforced)
+    } else if sec.allow_degraded {
+        Ok(LayerStatus::Degraded)
+    } else {
+        Err(crate::Error::SecurityUnavailable(
+            "dedicated user required but unavailable (needs root; set SecurityOptions.allow_degraded to proceed)".into(),
+        ))
+    }
+}
+
+/// Whether a spawn with `sec` should be given a dedicated user.
+pub(super) fn wanted(sec: SecurityOptions) -> bool {
+    cfg!(target_os = "linux") && sec.dedicated_user.is_some() && nix::unistd::geteuid().is_root()
+}
+
+/// Hand the per-VM files bux created to `uid`: the shim config, the root
+/// disk overlay and the network socket. Also opens the sockets directory
+/// for the shim's own entries. Shared directories are left alone.
+///
+/// # Errors
+///
+/// Returns the first `chown`/`chmod` failure.
+#[cfg(target_os = "linux")]
+pub(super) fn hand_over(
+    config: &VmConfig,
+    config_path: &Path,
+    socks_dir: &Path,
+    net_socket: Option<&Path>,
+    uid: u32,
+) -> io::Result<()> {
+    use std::os::unix::fs::{PermissionsExt, lchown};
+
+    for private in std::iter::once(config_path).chain(config.root_disk.as_deref().map(Path::new)) {
+        lchown(private, Some(uid), Some(uid))?;
+        std::fs::set_permissions(private, std::fs::Permissions::from_mode(0o600))?;
+    }
+    if let Some(socket) = net_socket {
+        lchown(socket, Some(uid), Some(uid))?;
+    }
+    std::fs::set_permissions(
+        socks_dir,
+        std::fs::Permissions::from_mode(SHARED_SOCKS_MODE),
+    )
+}
+
+/// Hand the paths the shim opens to `uid` (Linux only).
+#[cfg(not(target_os = "linux"))]
+pub(super) fn hand_over(
+    _config: &VmConfig,
+    _config_path: &Path,
+    _socks_dir: &Path,
+    _net_socket: Option<&Path>,
+    _uid: u32,
+) -> io::Result<()> {
+    Ok(())
+}
+
+/// Credentials the shim drops to, keeping the group that owns `/dev/kvm`
+/// and the owning group of each shared directory.
+///
+/// The root group is never granted: a share it owns is reachable only
+/// through its "other" permission bits.
+#[cfg(target_os = "linux")]
+pub(super) fn credentials(uid: u32, config: &VmConfig) -> bux_jail::credentials::CredentialConfig {
+    use std::os::unix::fs::MetadataExt;
+
+    let kvm_group = std::fs::metadata("/dev/kvm").ok().map(|m| m.gid());
+    let share_groups = shared_dirs(config).filter_map(|dir| {
+        let gid = std::fs::metadata(dir).ok()?.gid();
+        if gid == 0 {
+            tracing::warn!(path = %dir.display(), "dedicated user: share owned by the root group; not granting it");
+            return None;
+        }
+        Some(gid)
+    });
+    bux_jail::credentials::CredentialConfig::new(uid, uid)
+        .supplementary_gids(kvm_group.into_iter().chain(share_groups))
+}
+
+/// Directory trees the VM reads and writes: its rootfs and virtio-fs shares.
+fn shared_dirs(config: &VmConfig) -> impl Iterator<Item = &Path> {
+    config
+        .rootfs
+        .iter()
+        .map(String::as_str)
+        .chain(config.virtiofs.iter().map(|v| v.path.as_str()))
+        .map(Path::new)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::missing_docs_in_private_items,
+    reason = "tests"
+)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_skips_taken_and_pending_ids() {
+        let users = HostUsers::default();
+        let range = UidRange::new(1000, 3);
+        let taken = HashSet::from([1000]);
+        let first = users.reserve(range, &taken).unwrap();
+        assert_eq!(first.uid(), 1001);
+        let second = users.reserve(range, &taken).unwrap();
+        assert_eq!(second.uid(), 1002);
+        assert!(matches!(
+            users.reserve(range, &taken),
+            Err(crate::Error::SecurityUnavailable(_))
+        ));
+        drop(first);
+        assert_eq!(users.reserve(range, &taken).unwrap().uid(), 1001);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn shares_keep_their_owner() {
+        use std::os::unix::fs::{MetadataExt, chown};
+
+        if !nix::unistd::geteuid().is_root() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let share = dir.path().join("share");
+        std::fs::create_dir_all(share.join("sub")).unwrap();
+        std::fs::write(share.join("sub/file"), b"x").unwrap();
+        for path in [share.as_path(), &share.join("sub"), &share.join("sub/file")] {
+            chown(path, Some(1234), Some(1234)).unwrap();
+        }
+        let config_path = dir.path().join("vm.json");
+        std::fs::write(&config_path, b"{}").unwrap();
+        let socks = dir.path().join("socks");
+        std::fs::create_dir(&socks).unwrap();
+        let config = crate::Vm::builder()
+            .virtiofs("data", share.to_string_lossy())
+            .to_config();
+
+        hand_over(&config, &config_path, &socks, None, 4242).unwrap();
+        assert_eq!(std::fs::metadata(&config_path).unwrap().uid(), 4242);
+        let creds = credentials(4242, &config);
+        assert!(creds.supplementary_gids.contains(&1234));
+        for path in [share.as_path(), &share.join("sub/file")] {
+            let meta = std::fs::metadata(path).unwrap();
+            assert_eq!((meta.uid(), meta.gid()), (1234, 1234));
+        }
+    }
+
+    #[test]
+    fn status_is_opt_in_and_fails_closed() {
+        let off = SecurityOptions::new();
+        let on = off.dedicated_user(Some(UidRange::new(1000, 10)));
+        if cfg!(target_os = "linux") {
+            assert_eq!(status(off, None).unwrap(), LayerStatus::Disabled);
+            assert!(matches!(
+                status(on, None),
+                Err(crate::Error::SecurityUnavailable(_))
+            ));
+            assert_eq!(
+                status(on.allow_degraded(true), None).unwrap(),
+                LayerStatus::Degraded
+            );
+        } else {
+            assert_eq!(status(on, None).unwrap(), LayerStatus::NotApplicable);
+        }
+    }
+}
@@ -9,6 +9,8 @@
 
 /// Per-VM runtime handles and async event processing.
 mod handle;
+/// Dedicated host uid/gid per VM.
+mod host_user;
 /// Crash recovery and graceful shutdown.
 mod recover;
 /// Shim process spawning and lifecycle utilities.
@@ -24,7 +26,8 @@
 pub use handle::VmHandle;
 use nix::fcntl::{Flock, FlockArg};
 use spawn::{
-    clean_vm_files, inject_guest_boot_env, is_pid_alive, prepare_managed_config, spawn_shim,
+    clean_vm_files, inject_guest_boot_env, is_pid_alive, prepare_managed_config, remove_cgroup,
+    spawn_shim,
 };
 use tracing::info;
 
@@ -35,8 +38,9 @@
 use crate::net_manager::NetworkManager;
 use crate::options::VmOptions;
 use crate::pipeline;
+use crate::policy::HostPolicy;
 use crate::ports::{
-    format_port_pairs, parse_concrete_port_strings, parse_publish_spec, resolve_ports,
+    format_published_ports, parse_concrete_port_strings, parse_publish_spec, resolve_ports,
 };
 use crate::secrets::LiveSecrets;
 use crate::snapshot::SnapshotManager;
@@ -125,6 +129,12 @@ pub struct Runtime {
     metrics: Arc<RuntimeMetrics>,
     /// Audit event dispatcher.
     events: Arc<EventDispatcher>,
+    /// Admission policy checked before OCI images boot.
+    image_policy: Mutex<Arc<bux_oci::VerifyPolicy>>,
+    /// Dedicated host ids reserved by in-flight spawns.
+    host_users: host_user::HostUsers,
+    /// Host policy from the data dir, checked before managed VMs boot.
+    host_policy: HostPolicy,
 }
 
 // Runtime is Send + Sync because:
@@ -135,13 +145,15 @@ pub struct Runtime {
 impl Runtime {
     /// Opens (or creates) the runtime data directory and database.
     ///
+    /// Loads the host policy file (`policy.toml` / `policy.json`), if any.
     /// Runs crash recovery to reconcile stale state from previous runs.
     /// Acquires an exclusive file lock to prevent concurrent access.
     ///
     /// # Errors
     ///
     /// Returns an error if the data directory cannot be created, the lock
-    /// is already held, or the database fails to open.
+    /// is already held, the database fails to open, or the host policy
+    /// file does not parse.
     pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
         let base = data_dir.as_ref();
         fs::create_dir_all(base)?;
@@ -162,9 +174,16 @@ pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
         let disk = DiskManager::open(base)?;
         let oci = bux_oci::Oci::open_at(base)?;
         let snapshots = SnapshotManager::new(Arc::clone(&db), base)?;
-        let net = Arc::new(NetworkManager::new(socks_dir.clone()));
+        let events = Arc::new(EventDispatcher::new());
+        let net = Arc::new(NetworkManager::new(
+            socks_dir.clone(),
+            Arc::clone(&events),
+            Arc::clone(&db),
+        ));
         let secrets = Arc::new(Mutex::new(HashMap::new()));
         let volumes = VolumeManager::open(base, Arc::clone(&db))?;
+        let host_policy = HostPolicy::load(base)?;
+        let image_policy = crate::image_policy::ImagePolicy::load(base)?;
 
         let rt = Self {
             db,
@@ -177,7 +196,10 @@ pub fn open(data_dir: impl AsRef<Path>) -> Result<Self> {
             secrets,
             volumes,
             metrics: Arc::new(RuntimeMetrics::new()),
-            events: Arc::new(EventDispatcher::new()),
+            events,
+            image_policy: Mutex::new(Arc::new(image_policy)),
+            host_users: host_user::HostUsers::default(),
+            host_policy,
         };
 
         rt.recover();
@@ -195,6 +217,33 @@ pub const fn oci(&self) -> &bux_oci::Oci {
         &self.oci
     }
 
+    /// Host policy loaded from the data directory at open.
+    pub const fn host_policy(&self) -> &HostPolicy {
+        &self.host_policy
+    }
+
+    /// Replaces the image admission policy for subsequently created VMs.
+    ///
+    /// The initial policy comes from the data directory's image policy
+    /// file ([`crate::ImagePolicy`]) and admits every image without one.
+    pub fn set_image_policy(&self, policy: bux_oci::VerifyPolicy) {
+        *self
+            .image_policy
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Arc::new(policy);
+    }
+
+    /// Returns the current image admission policy.
+    #[must_use]
+    pub fn image_policy(&self) -> Arc<bux_oci::VerifyPolicy> {
+        Arc::clone(
+            &self
+                .image_policy
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner),
+        )
+    }
+
     /// Returns a reference to the snapshot manager.
     pub const fn snapshots(&self) -> &SnapshotManager {
         &self.snapshots
@@ -296,6 +345,11 @@ pub fn clone_box(
         builder = builder
             .vcpus(source_state.config.vcpus)
             .ram_mib(source_state.config.ram_mib);
+        // The clone boots the source's pulled image, so the host policy
+        // judges it by the same reference.
+        if let Some(verification) = &source_state.config.image_verification {
+            builder = builder.image_verification(verification.clone());
+        }
         builder = configure(builder);
 
         let handle = self.spawn(&builder, source_state.image.clone(), name, opts.auto_remove)?;
@@ -422,16 +476,15 @@ fn spawn_impl(
 
         let id = state::gen_id();
         let socket = self.socks_dir.join(format!("{id}.sock"));
-        let socket_str = socket.to_string_lossy().into_owned();
 
         // Secrets live on the builder only (not in VmConfig JSON values).
         let staged_secrets = builder.secrets.clone();
-        let mut config = builder.to_config();
-        prepare_managed_config(&mut config)?;
+        let mut config = self.admit(builder)?;
         config.auto_remove = auto_remove;
+        let _host_user = self.reserve_host_user(&mut config)?;
         config.vsock_ports.push(VsockPort {
             port: AGENT_PORT,
-            path: socket_str,
+            path: socket.to_string_lossy().into_owned(),
             listen: true,
         });
 
@@ -444,14 +497,23 @@ fn spawn_impl(
             config.base_disk = None;
         }
 
-        let live_secrets = if staged_secrets.is_empty() {
-            config.secrets_required = false;
+        if let Some(ref mut attachment) = config.network {
+            if !config.virtio_net {
+                return Err(crate::Error::InvalidConfig(
+                    "private networks require virtio-net".into(),
+                ));
+            }
+            self.net.assign(attachment, &id, name.as_deref())?;
+        }
+
+        config.secrets_required = !staged_secrets.is_empty();
+        // HTTP rules ride the MITM path, so they need the CA too.
+        let live_secrets = if staged_secrets.is_empty() && config.http_policy.is_empty() {
             None
         } else {
             if !config.virtio_net {
                 return Err(crate::Error::SecretsNeedVirtioNet);
             }
-            config.secrets_required = true;
             Some(LiveSecrets::mint(staged_secrets)?)
         };
 
@@ -463,13 +525,13 @@ fn spawn_impl(
             for s in &config.ports {
                 specs.push(parse_publish_spec(s)?);
             }
-            let (pairs, published) = resolve_ports(&specs)?;
-            config.ports = format_port_pairs(&pairs);
+            let published = resolve_ports(&specs)?;
+            config.ports = format_published_ports(&published);
             config.published_ports = published;
-            let net =
+            Some(
                 self.net
-                    .start(&id, pairs, config.allow_net.clone(), live_secrets.as_ref())?;
-            Some(net.shim_network)
+                    .start(&id, &config, &config.published_ports, live_secrets.as_ref())?,
+            )
         } else {
             let _ = parse_concrete_port_strings(&config.ports)?;
             config.published_ports.clear();
@@ -541,6 +603,41 @@ fn spawn_impl(
         ))
     }
 
+    /// The config a new VM boots with, once the host policy admits it.
+    ///
+    /// Checked before the managed preparation writes into the rootfs.
+    fn admit(&self, builder: &VmBuilder) -> Result<state::VmConfig> {
+        let mut config = builder.to_config();
+        self.host_policy.enforce_config(&config)?;
+        prepare_managed_config(&mut config)?;
+        Ok(config)
+    }
+
+    /// Reserves a dedicated host user for a new VM when its security
+    /// options ask for one and this process can switch users.
+    ///
+    /// The lease must be held until the VM row records the id.
+    fn reserve_host_user(
+        &self,
+        config: &mut state::VmConfig,
+    ) -> Result<Option<host_user::HostUserLease>> {
+        let Some(range) = config.security.dedicated_user else {
+            return Ok(None);
+        };
+        if !host_user::wanted(config.security) {
+            return Ok(None);
+        }
+        let taken = self
+            .db
+            .list()?
+            .into_iter()
+            .filter_map(|vm| vm.config.host_user)
+            .collect();
+        let lease = self.host_users.reserve(range, &taken)?;
+        config.host_user = Some(lease.uid());
+        Ok(Some(lease))
+    }
+
     /// Lists all known VMs, reconciling liveness and auto-removing stopped VMs.
     ///
     /// # Errors
@@ -634,8 +731,9 @@ pub fn remove(&self, id_or_name: &str) -> Result<()> {
             )));
         }
 
-        clean_vm_files(&state.socket);
         self.net.stop(&state.id);
+        clean_vm_files(&state.socket);
+        remove_cgroup(&state.id);
         self.secrets
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner)
@@ -10,9 +10,9 @@
 use tracing::{info, warn};
 
 use super::Runtime;
-use super::spawn::{clean_vm_files, is_pid_alive};
+use super::spawn::{clean_vm_files, is_pid_alive, remove_cgroup};
 use crate::lifecycle::{self, RecoverAction, SECRETS_RESUPPLY_ERROR};
-use crate::ports::{parse_concrete_port_strings, parse_publish_spec, resolve_ports};
+use crate::ports::{parse_publish_spec, resolve_ports};
 use crate::state::{Status, VmConfig, VmState};
 
 impl Runtime {
@@ -152,6 +152,7 @@ fn recover_reattach_net(&self, vm: &VmState) {
     /// Delete sock/disk/db rows for a VM.
     fn purge_vm_files(&self, vm: &VmState) {
         clean_vm_files(&vm.socket);
+        remove_cgroup(&vm.id);
         drop(self.volumes.unlink_vm(&vm.id));
         drop(self.disk.remove_vm_disk(&vm.id));
         drop(self.db.delete(&vm.id));
@@ -214,21 +215,21 @@ fn sweep_one(&self, now: SystemTime, vm: &VmState, report: &mut lifecycle::Sweep
 }
 
 /// Rebuild gvproxy for an orphaned virtio-net VM (no secrets).
+///
+/// HTTP rules are refused: the guest trusts a CA that died with the old
+/// Runtime, so the VM is left without a backend rather than unfiltered.
 fn reattach_network_ports(rt: &Runtime, vm_id: &str, config: &VmConfig) -> crate::Result<()> {
+    if !config.http_policy.is_empty() {
+        return Err(crate::Error::InvalidConfig(
+            "HTTP rules need the MITM CA lost with the previous Runtime; restart the VM".into(),
+        ));
+    }
     let mut specs = Vec::with_capacity(config.ports.len());
     for s in &config.ports {
-        if let Ok(pair) = parse_concrete_one(s) {
-            specs.push(pair);
-            continue;
-        }
-        let spec = parse_publish_spec(s)?;
-        let (pairs, _) = resolve_ports(std::slice::from_ref(&spec))?;
-        specs.extend(pairs);
-    }
-    if specs.is_empty() && !config.ports.is_empty() {
-        specs = parse_concrete_port_strings(&config.ports)?;
+        specs.push(parse_publish_spec(s)?);
     }
-    let _ = rt.net.start(vm_id, specs, config.allow_net.clone(), None)?;
+    let published = resolve_ports(&specs)?;
+    let _ = rt.net.start(vm_id, config, &published, None)?;
     Ok(())
 }
 
@@ -250,7 +251,7 @@ fn terminate_pid(pid: i32, vm_id: &str) {
     }
 }
 
-/// Remove sock/json/stderr files for VMs no longer in the database.
+/// Remove sock/json/stderr/activity files for VMs no longer in the database.
 fn clean_orphan_socks(socks_dir: &Path, known_ids: &HashSet<String>) -> u32 {
     let Ok(entries) = fs::read_dir(socks_dir) else {
         return 0;
@@ -270,8 +271,10 @@ fn sock_entry_is_orphan(name: &std::ffi::OsStr, known_ids: &HashSet<String>) ->
     let Some(name_str) = name.to_str() else {
         return false;
     };
-    if let Some(id) = name_str.strip_suffix(".net.sock") {
-        return !known_ids.contains(id);
+    for ext in [".net.sock", ".uplink.sock", ".net.jsonl", ".net.jsonl.1"] {
+        if let Some(id) = name_str.strip_suffix(ext) {
+            return !known_ids.contains(id);
+        }
     }
     for ext in [".sock", ".exit", ".json", ".stderr"] {
         if let Some(id) = name_str.strip_suffix(ext)
@@ -282,22 +285,3 @@ fn sock_entry_is_orphan(name: &std::ffi::OsStr, known_ids: &HashSet<String>) ->
     }
     false
 }
-
-/// Parse a single concrete `host:guest` pair.
-fn parse_concrete_one(s: &str) -> crate::Result<(u16, u16)> {
-    let (h, g) = s
-        .split_once(':')
-        .ok_or_else(|| crate::Error::InvalidConfig(format!("invalid port pair {s}")))?;
-    let host: u16 = h
-        .parse()
-        .map_err(|_| crate::Error::InvalidConfig(format!("invalid host port in {s}")))?;
-    let guest: u16 = g
-        .parse()
-        .map_err(|_| crate::Error::InvalidConfig(format!("invalid guest port in {s}")))?;
-    if host == 0 {
-        return Err(crate::Error::InvalidConfig(
-            "ephemeral host port not valid for reattach".into(),
-        ));
-    }
-    Ok((host, guest))
-}
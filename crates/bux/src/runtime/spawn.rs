@@ -13,10 +13,13 @@
 
 use bux_jail::JailConfig;
 use bux_proto::{GUEST_BOOT_CONFIG_ENV, GuestBootConfig, GuestNetworkMode};
-use bux_shim::ShimNetwork;
+use bux_shim::ShimSeccomp;
 
+use super::host_user;
 use crate::Result;
 use crate::guest::ManagedGuestBinary;
+use crate::net_manager::StartNetResult;
+use crate::security::{LayerStatus, SeccompMode, SecurityOptions};
 use crate::state;
 use crate::watchdog::{self, Keepalive};
 
@@ -55,15 +58,34 @@ pub(super) fn shim_death_message(pid: i32, exit_file: &Path) -> String {
 
 /// Removes all transient files associated with a VM socket path.
 ///
-/// Cleans `.sock`, `.exit`, `.json`, and `.stderr` files that share the
-/// same stem as the socket.
+/// Cleans `.sock`, `.exit`, `.json`, `.stderr`, network helper stderr and
+/// network activity log files that share the same stem as the socket.
 pub(super) fn clean_vm_files(socket: &Path) {
     drop(fs::remove_file(socket));
-    for ext in ["exit", "json", "stderr"] {
+    for ext in [
+        "exit",
+        "json",
+        "stderr",
+        "netd.stderr",
+        "net.jsonl",
+        "net.jsonl.1",
+    ] {
         drop(fs::remove_file(socket.with_extension(ext)));
     }
 }
 
+/// Removes the VM's host-limits cgroup once its shim has exited.
+///
+/// Best-effort: a missing cgroup, or one still holding processes, is left
+/// alone.
+#[allow(clippy::missing_const_for_fn, reason = "body is non-const on Linux")]
+pub(super) fn remove_cgroup(vm_id: &str) {
+    #[cfg(target_os = "linux")]
+    drop(bux_cgroup::remove(vm_id));
+    #[cfg(not(target_os = "linux"))]
+    let _ = vm_id;
+}
+
 /// Checks if a process is alive via `kill(pid, 0)`.
 pub(super) fn is_pid_alive(pid: i32) -> bool {
     signal::kill(Pid::from_raw(pid), None).is_ok()
@@ -170,6 +192,18 @@ pub(super) fn inject_guest_boot_env(
     };
     let mut boot = GuestBootConfig::new(vm_id, mode);
     boot.mitm_ca_pem = mitm_ca_pem;
+    if let Some(ref attachment) = config.network {
+        boot.ip = attachment.ip;
+        boot.hostname.clone_from(&attachment.hostname);
+        boot.search_domain = Some(attachment.network.clone());
+    }
+    if config.ipv6 {
+        let [.., host] = boot
+            .ip
+            .unwrap_or(bux_net::GuestAddress::DEFAULT.ip)
+            .octets();
+        boot.ipv6 = Some(bux_net::GuestAddress::host(host).ipv6());
+    }
     let entry = boot
         .to_env_assignment()
         .map_err(crate::Error::InvalidConfig)?;
@@ -181,26 +215,53 @@ pub(super) fn inject_guest_boot_env(
 ///
 /// Shared by [`super::Runtime::spawn()`] and [`super::VmHandle::start()`].
 ///
-/// `network`: when `Some`, shim attaches virtio-net (gvproxy); when `None`, TSI ports.
+/// `network`: when `Some`, shim attaches virtio-net to the started backend
+/// (whose helper status is recorded); when `None`, TSI ports.
 ///
 /// # Errors
 ///
-/// Returns [`crate::Error::SecurityUnavailable`] when Landlock is required but missing (K22),
-/// or I/O / jail errors on spawn failure.
+/// Returns [`crate::Error::SecurityUnavailable`] when Landlock, seccomp or a
+/// requested sandbox namespace is missing (K22), or I/O / jail errors on
+/// spawn failure.
 pub(super) fn spawn_shim(
     config: &state::VmConfig,
     config_path: &Path,
     socks_dir: &Path,
     vm_id: &str,
     watch_parent: bool,
-    network: Option<ShimNetwork>,
+    network: Option<StartNetResult>,
 ) -> Result<ShimSpawnResult> {
+    let network_helper = network
+        .as_ref()
+        .map_or(LayerStatus::Disabled, |net| net.network_helper);
     // Engine wire format is ShimConfig (not product VmConfig).
-    let shim_cfg = crate::shim_convert::to_shim_config(vm_id, config, network);
+    let mut shim_cfg =
+        crate::shim_convert::to_shim_config(vm_id, config, network.map(|net| net.shim_network));
+    let seccomp = seccomp_status(config.security)?;
+    if !matches!(seccomp, LayerStatus::Enforced | LayerStatus::Permissive) {
+        shim_cfg.seccomp = ShimSeccomp::Disabled;
+    }
+    let (resource_limits, cgroup) = cgroup_layer(config)?;
+    let sec = config.security;
+    let user_ns = namespace_status(
+        "user",
+        sec.user_namespace,
+        sec.jailer && bux_jail::checks::check_host().user_namespaces,
+        sec,
+    )?;
+    let net_ns = namespace_status("network", sec.network_namespace, sec.jailer, sec)?;
+    let dedicated_user = host_user::status(sec, config.host_user)?;
+    let run_as = config
+        .host_user
+        .filter(|_| dedicated_user == LayerStatus::Enforced);
     let json = shim_cfg
         .to_json()
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
     fs::write(config_path, &json)?;
+    if let Some(uid) = run_as {
+        let net_socket = shim_cfg.network.as_ref().map(|n| n.socket_path.as_path());
+        host_user::hand_over(config, config_path, socks_dir, net_socket, uid)?;
+    }
 
     // Capture shim stderr to a file for post-mortem diagnostics.
     let stderr_path = config_path.with_extension("stderr");
@@ -222,7 +283,6 @@ pub(super) fn spawn_shim(
         .map(|d| crate::disk::readonly_disk_paths(Path::new(d)))
         .unwrap_or_default();
 
-    let sec = &config.security;
     let sandbox: Option<Box<dyn bux_jail::Sandbox>> = if sec.jailer {
         None // auto-detect bwrap/seatbelt
     } else {
@@ -244,17 +304,40 @@ pub(super) fn spawn_shim(
             .as_ref()
             .map(std::os::unix::io::AsRawFd::as_raw_fd),
         sandbox,
-        resource_limits: None,
+        resource_limits,
         stderr_file: Some(stderr_file),
         landlock: sec.landlock,
         allow_degraded_security: sec.allow_degraded,
+        namespaces: bux_jail::NamespaceOptions::new()
+            .user(user_ns == LayerStatus::Enforced)
+            .network(net_ns == LayerStatus::Enforced),
+        #[cfg(target_os = "linux")]
+        credentials: run_as.map(|uid| host_user::credentials(uid, config)),
     };
 
-    let result = bux_jail::spawn(&shim, config_path, jail_config, vm_id).map_err(|e| {
+    let mut result = bux_jail::spawn(&shim, config_path, jail_config, vm_id).map_err(|e| {
         drop(fs::remove_file(config_path));
         map_jail_error(e, &shim)
     })?;
 
+    // The jail falls back to no sandbox when bwrap is missing at spawn.
+    let applied = &result.security.capabilities;
+    let namespaces = confirm_namespace(user_ns, applied.user_namespace, sec).and_then(|u| {
+        Ok((
+            u,
+            confirm_namespace(net_ns, applied.network_namespace, sec)?,
+        ))
+    });
+    let (user_namespace, network_namespace) = match namespaces {
+        Ok(statuses) => statuses,
+        Err(e) => {
+            drop(result.child.kill());
+            drop(result.child.wait());
+            drop(fs::remove_file(config_path));
+            return Err(e);
+        }
+    };
+
     #[allow(
         clippy::cast_possible_wrap,
         reason = "PID fits in i32 on all supported platforms"
@@ -262,13 +345,134 @@ pub(super) fn spawn_shim(
     let pid = result.child.id() as i32;
     drop(shim_wd_fd);
 
+    let mut security = crate::security::SecurityStatus::from_report(&result.security);
+    security.seccomp = seccomp;
+    security.cgroup = cgroup;
+    security.user_namespace = user_namespace;
+    security.network_namespace = network_namespace;
+    security.network_helper = network_helper;
+    security.dedicated_user = dedicated_user;
     Ok(ShimSpawnResult {
         pid,
         keepalive,
-        security: crate::security::SecurityStatus::from_report(&result.security),
+        security,
     })
 }
 
+/// Resolve the shim seccomp layer status (fail-closed like Landlock).
+/// Only [`SeccompMode::Enforce`] counts as enforced; `log` and `errno`
+/// filters are reported as permissive.
+fn seccomp_status(sec: SecurityOptions) -> Result<LayerStatus> {
+    if sec.seccomp == SeccompMode::Disabled {
+        return Ok(LayerStatus::Disabled);
+    }
+    if !cfg!(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )) {
+        return Ok(LayerStatus::NotApplicable);
+    }
+    if bux_jail::checks::check_host().seccomp {
+        Ok(if sec.seccomp == SeccompMode::Enforce {
+            LayerStatus::Enforced
+        } else {
+            LayerStatus::Permissive
+        })
+    } else if sec.allow_degraded {
+        Ok(LayerStatus::Degraded)
+    } else {
+        Err(crate::Error::SecurityUnavailable(
+            "seccomp required but unavailable on this kernel (set SecurityOptions.allow_degraded to proceed)"
+                .into(),
+        ))
+    }
+}
+
+/// Resolve an optional bwrap namespace before spawn (fail-closed like
+/// Landlock). `available` is whether the jailer and host can provide it.
+fn namespace_status(
+    name: &str,
+    requested: bool,
+    available: bool,
+    sec: SecurityOptions,
+) -> Result<LayerStatus> {
+    if !cfg!(target_os = "linux") {
+        return Ok(LayerStatus::NotApplicable);
+    }
+    if !requested {
+        Ok(LayerStatus::Disabled)
+    } else if available {
+        Ok(LayerStatus::Enforced)
+    } else if sec.allow_degraded {
+        Ok(LayerStatus::Degraded)
+    } else {
+        Err(crate::Error::SecurityUnavailable(format!(
+            "{name} namespace required but unavailable (needs the jailer and kernel support; set SecurityOptions.allow_degraded to proceed)"
+        )))
+    }
+}
+
+/// Check a namespace expected to be enforced against what the sandbox
+/// actually applied.
+fn confirm_namespace(
+    status: LayerStatus,
+    applied: bool,
+    sec: SecurityOptions,
+) -> Result<LayerStatus> {
+    if status != LayerStatus::Enforced || applied {
+        Ok(status)
+    } else {
+        namespace_status("sandbox", true, false, sec)
+    }
+}
+
+/// Resolve the host-limits cgroup: the limits to hand the jail, and the
+/// layer status (fail-closed like Landlock).
+fn cgroup_layer(
+    config: &state::VmConfig,
+) -> Result<(Option<bux_jail::ResourceLimits>, LayerStatus)> {
+    if config.host_limits.is_unlimited() {
+        return Ok((None, LayerStatus::Disabled));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        if bux_cgroup::Placement::detect().is_some() {
+            let disks = if config.host_limits.has_disk_limits() {
+                disk_devices(config)?
+            } else {
+                Vec::new()
+            };
+            let limits = config.host_limits.to_cgroup(&disks);
+            Ok((Some(limits), LayerStatus::Enforced))
+        } else if config.security.allow_degraded {
+            Ok((None, LayerStatus::Degraded))
+        } else {
+            Err(crate::Error::SecurityUnavailable(
+                "host limits require a writable cgroup v2 hierarchy (run as root or in a systemd user session with delegation, or set SecurityOptions.allow_degraded to proceed)"
+                    .into(),
+            ))
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    Ok((None, LayerStatus::NotApplicable))
+}
+
+/// Whole-disk devices holding the VM's root disk and its base image.
+#[cfg(target_os = "linux")]
+fn disk_devices(config: &state::VmConfig) -> Result<Vec<bux_cgroup::DeviceNumber>> {
+    let mut devices = [&config.root_disk, &config.base_disk]
+        .into_iter()
+        .flatten()
+        .map(|path| {
+            bux_cgroup::block_device(Path::new(path))
+                .map_err(|e| crate::Error::InvalidConfig(format!("disk IO limits: {e}")))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    devices.sort_unstable();
+    devices.dedup();
+    Ok(devices)
+}
+
 /// Map jail errors to product errors (preserve K22 fail-closed).
 fn map_jail_error(e: bux_jail::Error, shim: &Path) -> crate::Error {
     match e {
@@ -289,42 +493,10 @@ fn map_jail_error(e: bux_jail::Error, shim: &Path) -> crate::Error {
     }
 }
 
-/// Locates the `bux-shim` binary.
-///
-/// Search order:
-/// 1. `$BUX_SHIM_PATH` environment variable (development override).
-/// 2. Next to the current executable.
-/// 3. In `$PATH`.
+/// Locates the `bux-shim` binary (`$BUX_SHIM_PATH` overrides; see
+/// [`crate::util::find_binary`]).
 fn find_shim() -> io::Result<PathBuf> {
-    const NAME: &str = "bux-shim";
-
-    if let Ok(p) = std::env::var("BUX_SHIM_PATH") {
-        let path = PathBuf::from(p);
-        if path.is_file() {
-            return Ok(path);
-        }
-    }
-
-    if let Ok(exe) = std::env::current_exe() {
-        let sibling = exe.with_file_name(NAME);
-        if sibling.is_file() {
-            return Ok(sibling);
-        }
-    }
-
-    if let Ok(path_var) = std::env::var("PATH") {
-        for dir in std::env::split_paths(&path_var) {
-            let candidate = dir.join(NAME);
-            if candidate.is_file() {
-                return Ok(candidate);
-            }
-        }
-    }
-
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        format!("'{NAME}' not found; install it next to the bux binary or in $PATH"),
-    ))
+    crate::util::find_binary("bux-shim", "BUX_SHIM_PATH")
 }
 
 #[cfg(target_os = "macos")]
@@ -381,6 +553,8 @@ fn empty_config() -> VmConfig {
             workdir: None,
             ports: vec![],
             allow_net: vec![],
+            egress_default_deny: None,
+            http_policy: bux_net::HttpPolicy::default(),
             published_ports: vec![],
             virtiofs: vec![],
             vsock_ports: vec![],
@@ -392,20 +566,50 @@ fn empty_config() -> VmConfig {
             snd_device: None,
             console_output: None,
             virtio_net: true,
+            net_backend: bux_net::NetworkBackendKind::default(),
+            network: None,
+            traffic_limits: bux_net::TrafficLimits::default(),
+            dns: bux_net::DnsConfig::default(),
+            ipv6: false,
             secrets_required: false,
             workload_env: vec![],
             workload_workdir: None,
             workload_user: None,
-            security: crate::security::SecurityOptions::default(),
+            security: SecurityOptions::default(),
+            host_limits: crate::limits::HostLimits::default(),
+            host_user: None,
             security_status: crate::security::SecurityStatus::default(),
             auto_remove: false,
             auto_stop_secs: None,
             auto_delete_secs: None,
             last_activity_at: None,
             last_error: None,
+            image_verification: None,
         }
     }
 
+    #[test]
+    fn seccomp_status_sets_non_killing_modes_apart() {
+        let sec = SecurityOptions::new().allow_degraded(true);
+        let status = |mode| seccomp_status(sec.seccomp(mode)).unwrap();
+        assert_eq!(status(SeccompMode::Disabled), LayerStatus::Disabled);
+        let expect = |active| {
+            if !cfg!(all(
+                target_os = "linux",
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            )) {
+                LayerStatus::NotApplicable
+            } else if bux_jail::checks::check_host().seccomp {
+                active
+            } else {
+                LayerStatus::Degraded
+            }
+        };
+        assert_eq!(status(SeccompMode::Enforce), expect(LayerStatus::Enforced));
+        assert_eq!(status(SeccompMode::Log), expect(LayerStatus::Permissive));
+        assert_eq!(status(SeccompMode::Errno), expect(LayerStatus::Permissive));
+    }
+
     #[test]
     fn route_migrates_env_workdir_user() {
         let mut c = empty_config();
@@ -124,6 +124,16 @@ pub(crate) fn mint(secrets: Vec<Secret>) -> crate::Result<Self> {
         })
     }
 
+    /// Same CA, new secret set (live rotation).
+    #[must_use]
+    pub(crate) fn rotate(&self, secrets: Vec<Secret>) -> Self {
+        Self {
+            secrets,
+            ca_cert_pem: self.ca_cert_pem.clone(),
+            ca_key_pem: self.ca_key_pem.clone(),
+        }
+    }
+
     /// Wire configs for `bux-net` / gvproxy.
     #[must_use]
     pub(crate) fn gvproxy_secrets(&self) -> Vec<bux_net::SecretConfig> {
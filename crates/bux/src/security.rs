@@ -5,16 +5,45 @@
 /// Requested isolation policy for a managed VM.
 ///
 /// Defaults: jailer on; Landlock **on** on Linux (fail-closed unless
-/// [`Self::allow_degraded`]); Landlock off on other platforms.
+/// [`Self::allow_degraded`]) and seccomp in [`SeccompMode::Log`] there;
+/// both off on other platforms.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
+#[allow(clippy::struct_excessive_bools, reason = "independent layer toggles")]
 pub struct SecurityOptions {
     /// Use the platform jailer (bwrap / seatbelt). Default `true`.
     pub jailer: bool,
     /// Request Landlock LSM on Linux. Default `true` on Linux, `false` elsewhere.
     pub landlock: bool,
-    /// If true, missing Landlock (when requested) degrades instead of failing create/start.
+    /// If true, a missing requested layer degrades instead of failing create/start.
     pub allow_degraded: bool,
+    /// VMM syscall filter installed by the shim before the guest starts.
+    ///
+    /// Records persisted before this field existed ran without a filter
+    /// and deserialize as [`SeccompMode::Disabled`] rather than picking up
+    /// the Linux default.
+    #[serde(default = "SeccompMode::unset")]
+    pub seccomp: SeccompMode,
+    /// Run the shim in its own user namespace, mapped to the invoking
+    /// uid/gid (Linux bwrap). Default `false`.
+    #[serde(default)]
+    pub user_namespace: bool,
+    /// Run the shim without host network access (Linux bwrap). Requires
+    /// virtio-net, whose backend the shim reaches over a Unix socket.
+    /// Default `false`.
+    #[serde(default)]
+    pub network_namespace: bool,
+    /// Run the VM's network backend in a sandboxed `bux-netd` helper
+    /// process instead of the embedding process (Linux). Requires
+    /// virtio-net. Default `false`.
+    #[serde(default)]
+    pub network_helper: bool,
+    /// Run the VMM as a dedicated host uid/gid allocated from this range,
+    /// owning only its own overlay, config and sockets (Linux; needs root).
+    /// Shared directories keep their owners and are reached through their
+    /// owning group. Default `None` (the invoking user).
+    #[serde(default)]
+    pub dedicated_user: Option<UidRange>,
 }
 
 impl Default for SecurityOptions {
@@ -23,6 +52,69 @@ fn default() -> Self {
             jailer: true,
             landlock: cfg!(target_os = "linux"),
             allow_degraded: false,
+            seccomp: SeccompMode::default(),
+            user_namespace: false,
+            network_namespace: false,
+            network_helper: false,
+            dedicated_user: None,
+        }
+    }
+}
+
+/// Contiguous host uid range for dedicated VM users. Each VM takes one id
+/// as both its uid and gid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct UidRange {
+    /// First id in the range.
+    pub first: u32,
+    /// Number of ids.
+    pub count: u32,
+}
+
+impl UidRange {
+    /// `count` ids starting at `first`.
+    #[must_use]
+    pub const fn new(first: u32, count: u32) -> Self {
+        Self { first, count }
+    }
+
+    /// Ids in the range, lowest first.
+    pub fn ids(self) -> impl Iterator<Item = u32> {
+        (0..self.count).map_while(move |i| self.first.checked_add(i))
+    }
+}
+
+/// How the shim's seccomp allowlist treats syscalls outside the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SeccompMode {
+    /// No filter.
+    Disabled,
+    /// Kill the VMM.
+    Enforce,
+    /// Allow, but record the syscall in the kernel audit log. Default on
+    /// Linux until the allowlist is validated on real boots
+    /// (`tests/seccomp_boot.rs`); opt into [`Self::Enforce`] explicitly.
+    Log,
+    /// Fail the syscall with `EPERM`.
+    Errno,
+}
+
+impl SeccompMode {
+    /// Mode for a persisted record without a `seccomp` field.
+    const fn unset() -> Self {
+        Self::Disabled
+    }
+}
+
+impl Default for SeccompMode {
+    fn default() -> Self {
+        if cfg!(target_os = "linux") {
+            Self::Log
+        } else {
+            Self::Disabled
         }
     }
 }
@@ -54,6 +146,41 @@ pub const fn jailer(mut self, enable: bool) -> Self {
         self.jailer = enable;
         self
     }
+
+    /// Set the VMM seccomp filter mode.
+    #[must_use]
+    pub const fn seccomp(mut self, mode: SeccompMode) -> Self {
+        self.seccomp = mode;
+        self
+    }
+
+    /// Enable/disable the shim's user namespace.
+    #[must_use]
+    pub const fn user_namespace(mut self, enable: bool) -> Self {
+        self.user_namespace = enable;
+        self
+    }
+
+    /// Enable/disable the shim's network namespace.
+    #[must_use]
+    pub const fn network_namespace(mut self, enable: bool) -> Self {
+        self.network_namespace = enable;
+        self
+    }
+
+    /// Enable/disable the out-of-process network backend.
+    #[must_use]
+    pub const fn network_helper(mut self, enable: bool) -> Self {
+        self.network_helper = enable;
+        self
+    }
+
+    /// Run the VMM as a dedicated host user from `range` (`None` disables).
+    #[must_use]
+    pub const fn dedicated_user(mut self, range: Option<UidRange>) -> Self {
+        self.dedicated_user = range;
+        self
+    }
 }
 
 /// Status of one isolation layer after spawn (persisted for inspect).
@@ -65,6 +192,9 @@ pub enum LayerStatus {
     Enforced,
     /// Requested, unavailable, continued under `allow_degraded`.
     Degraded,
+    /// Active but not fail-closed: a seccomp filter in `log` (audit only)
+    /// or `errno` mode.
+    Permissive,
     /// Not requested.
     #[default]
     Disabled,
@@ -80,8 +210,35 @@ pub struct SecurityStatus {
     pub sandbox: String,
     /// Landlock layer status.
     pub landlock: LayerStatus,
+    /// Landlock rights in force, when [`Self::landlock`] is enforced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub landlock_rights: Option<LandlockRights>,
     /// MAC / seatbelt layer status.
     pub mac: LayerStatus,
+    /// VMM seccomp filter status.
+    #[serde(default)]
+    pub seccomp: LayerStatus,
+    /// Host-limits cgroup status.
+    #[serde(default)]
+    pub cgroup: LayerStatus,
+    /// Shim user namespace status.
+    #[serde(default)]
+    pub user_namespace: LayerStatus,
+    /// Shim network namespace status.
+    #[serde(default)]
+    pub network_namespace: LayerStatus,
+    /// Out-of-process network backend status.
+    #[serde(default)]
+    pub network_helper: LayerStatus,
+    /// Dedicated host user status.
+    #[serde(default)]
+    pub dedicated_user: LayerStatus,
+    /// Whether the sandbox dropped every capability.
+    #[serde(default)]
+    pub capabilities_dropped: bool,
+    /// Whether the sandbox masked sensitive `/proc` and `/sys` paths.
+    #[serde(default)]
+    pub masked_paths: bool,
 }
 
 impl SecurityStatus {
@@ -92,11 +249,77 @@ pub fn from_report(r: &bux_jail::SecurityReport) -> Self {
         Self {
             sandbox: r.sandbox.as_str().to_owned(),
             landlock: map_layer(r.landlock),
+            landlock_rights: r.landlock_enforcement.as_ref().map(LandlockRights::from),
             mac: map_layer(r.mac),
+            seccomp: LayerStatus::Disabled,
+            cgroup: LayerStatus::Disabled,
+            user_namespace: LayerStatus::Disabled,
+            network_namespace: LayerStatus::Disabled,
+            network_helper: LayerStatus::Disabled,
+            dedicated_user: LayerStatus::Disabled,
+            capabilities_dropped: r.capabilities.capabilities_dropped,
+            masked_paths: r.capabilities.masked_paths,
+        }
+    }
+}
+
+/// Landlock restrictions in force for a VM, after negotiating with the
+/// host kernel's ABI.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct LandlockRights {
+    /// Negotiated Landlock ABI version.
+    pub abi: u8,
+    /// Filesystem rights restricted to the jail's paths.
+    pub filesystem: Vec<String>,
+    /// Whether TCP bind/connect is restricted.
+    pub network: bool,
+    /// IPC scopes in force (`abstract_unix_socket`, `signal`).
+    pub scopes: Vec<String>,
+    /// Requested restrictions the kernel cannot enforce.
+    pub not_enforced: Vec<String>,
+}
+
+#[cfg(unix)]
+impl From<&bux_jail::LandlockEnforcement> for LandlockRights {
+    fn from(e: &bux_jail::LandlockEnforcement) -> Self {
+        let owned = |names: Vec<&str>| names.into_iter().map(str::to_owned).collect();
+        let mut not_enforced = e.filesystem_unsupported.names();
+        not_enforced.extend(&e.unsupported);
+        Self {
+            abi: e.abi.get(),
+            filesystem: owned(e.filesystem.names()),
+            network: e.network,
+            scopes: owned(e.scopes()),
+            not_enforced: owned(not_enforced),
         }
     }
 }
 
+impl std::fmt::Display for LandlockRights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |v: &[String]| {
+            if v.is_empty() {
+                "none".to_owned()
+            } else {
+                v.join(",")
+            }
+        };
+        write!(
+            f,
+            "ABI v{}; {} filesystem rights; network: {}; scoped: {}",
+            self.abi,
+            self.filesystem.len(),
+            if self.network { "tcp" } else { "none" },
+            list(&self.scopes),
+        )?;
+        if !self.not_enforced.is_empty() {
+            write!(f, "; not enforced: {}", list(&self.not_enforced))?;
+        }
+        Ok(())
+    }
+}
+
 /// Map jail layer status into product enum.
 #[cfg(unix)]
 const fn map_layer(s: bux_jail::LayerStatus) -> LayerStatus {
@@ -120,6 +343,9 @@ pub struct HostInfo {
     pub virtualization: bool,
     /// bubblewrap available (Linux namespaces).
     pub namespaces: bool,
+    /// Unprivileged user namespaces enabled (Linux).
+    #[serde(default)]
+    pub user_namespaces: bool,
     /// seccomp BPF present.
     pub seccomp: bool,
     /// AppArmor/SELinux/Seatbelt.
@@ -140,6 +366,7 @@ pub fn probe() -> Self {
             Self {
                 virtualization: caps.virtualization,
                 namespaces: caps.namespaces,
+                user_namespaces: caps.user_namespaces,
                 seccomp: caps.seccomp,
                 mandatory_access_control: caps.mandatory_access_control,
                 cgroups: caps.cgroups,
@@ -151,6 +378,7 @@ pub fn probe() -> Self {
             Self {
                 virtualization: false,
                 namespaces: false,
+                user_namespaces: false,
                 seccomp: false,
                 mandatory_access_control: false,
                 cgroups: false,
@@ -161,6 +389,7 @@ pub fn probe() -> Self {
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, reason = "tests")]
 mod tests {
     use super::*;
 
@@ -170,6 +399,8 @@ fn defaults_match_platform() {
         assert!(s.jailer);
         assert!(!s.allow_degraded);
         assert_eq!(s.landlock, cfg!(target_os = "linux"));
+        let audit_only = s.seccomp == SeccompMode::Log;
+        assert_eq!(audit_only, cfg!(target_os = "linux"));
     }
 
     #[test]
@@ -177,9 +408,61 @@ fn fluent() {
         let s = SecurityOptions::new()
             .landlock(false)
             .allow_degraded(true)
-            .jailer(false);
+            .jailer(false)
+            .seccomp(SeccompMode::Log)
+            .user_namespace(true)
+            .network_namespace(true)
+            .network_helper(true)
+            .dedicated_user(Some(UidRange::new(100_000, 1000)));
+        assert!(s.user_namespace && s.network_namespace && s.network_helper);
+        assert_eq!(s.dedicated_user, Some(UidRange::new(100_000, 1000)));
         assert!(!s.landlock);
         assert!(s.allow_degraded);
         assert!(!s.jailer);
+        assert_eq!(s.seccomp, SeccompMode::Log);
+    }
+
+    #[test]
+    fn seccomp_fields_default_when_missing() {
+        let opts: SecurityOptions =
+            serde_json::from_str(r#"{"jailer":true,"landlock":false,"allow_degraded":false}"#)
+                .unwrap();
+        assert_eq!(opts.seccomp, SeccompMode::Disabled);
+        assert!(!opts.user_namespace && !opts.network_namespace && !opts.network_helper);
+        assert_eq!(opts.dedicated_user, None);
+        let status: SecurityStatus =
+            serde_json::from_str(r#"{"sandbox":"bwrap","landlock":"enforced","mac":"disabled"}"#)
+                .unwrap();
+        assert_eq!(status.seccomp, LayerStatus::Disabled);
+        assert_eq!(status.cgroup, LayerStatus::Disabled);
+        assert_eq!(status.network_namespace, LayerStatus::Disabled);
+        assert_eq!(status.network_helper, LayerStatus::Disabled);
+        assert_eq!(status.dedicated_user, LayerStatus::Disabled);
+        assert!(!status.masked_paths);
+        assert_eq!(status.landlock_rights, None);
+    }
+
+    #[test]
+    fn uid_range_ids_stop_at_overflow() {
+        assert_eq!(UidRange::new(5, 3).ids().collect::<Vec<_>>(), vec![5, 6, 7]);
+        assert_eq!(UidRange::new(u32::MAX - 1, 5).ids().count(), 2);
+    }
+
+    #[test]
+    fn landlock_rights_display() {
+        let rights = LandlockRights {
+            abi: 5,
+            filesystem: vec!["execute".into(), "ioctl_dev".into()],
+            network: true,
+            scopes: Vec::new(),
+            not_enforced: vec!["signal".into()],
+        };
+        assert_eq!(
+            rights.to_string(),
+            "ABI v5; 2 filesystem rights; network: tcp; scoped: none; not enforced: signal"
+        );
+        let json = serde_json::to_string(&rights).unwrap();
+        let back: LandlockRights = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, rights);
     }
 }
@@ -2,9 +2,10 @@
 //!
 //! Single mapping so Runtime spawn and low-level builders share wire shape.
 
-use bux_shim::{ShimConfig, ShimDiskFormat, ShimNetwork, ShimVirtioFs, ShimVsockPort};
+use bux_shim::{ShimConfig, ShimDiskFormat, ShimNetwork, ShimSeccomp, ShimVirtioFs, ShimVsockPort};
 
 use crate::disk::DiskFormat;
+use crate::security::SeccompMode;
 use crate::state::VmConfig;
 
 /// Map a persisted / product [`VmConfig`] into engine [`ShimConfig`].
@@ -63,5 +64,11 @@ pub(crate) fn to_shim_config(
         nested_virt: config.nested_virt,
         snd_device: config.snd_device,
         console_output: config.console_output.clone(),
+        seccomp: match config.security.seccomp {
+            SeccompMode::Disabled => ShimSeccomp::Disabled,
+            SeccompMode::Enforce => ShimSeccomp::Enforce,
+            SeccompMode::Log => ShimSeccomp::Log,
+            SeccompMode::Errno => ShimSeccomp::Errno,
+        },
     }
 }
@@ -17,7 +17,8 @@
 /// path — callers must wipe the data directory.
 ///
 /// v2: named `volumes` + `vm_volumes` attachment tables (PR9).
-pub const PRODUCT_SCHEMA_VERSION: u32 = 2;
+/// v3: named private `networks` table.
+pub const PRODUCT_SCHEMA_VERSION: u32 = 3;
 
 /// DDL for a fresh product database.
 const PRODUCT_SCHEMA_SQL: &str = "
@@ -67,6 +68,11 @@
     PRIMARY KEY (vm_id, volume_id)
 );
 CREATE INDEX idx_vm_volumes_volume ON vm_volumes(volume_id);
+
+CREATE TABLE networks (
+    name        TEXT PRIMARY KEY NOT NULL,
+    created_at  REAL NOT NULL
+);
 ";
 
 /// Persisted snapshot metadata (disk-only; no memory snapshots).
@@ -564,6 +570,74 @@ pub fn delete_vm_volumes(&self, vm_id: &str) -> Result<()> {
             .execute("DELETE FROM vm_volumes WHERE vm_id = ?1", params![vm_id])?;
         Ok(())
     }
+
+    // ── networks ─────────────────────────────────────────────────────────
+
+    /// Insert a named network row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the insert fails (e.g. duplicate name).
+    pub fn insert_network(&self, n: &crate::net_manager::NetworkInfo) -> Result<()> {
+        self.lock().execute(
+            "INSERT INTO networks (name, created_at) VALUES (?1, ?2)",
+            params![n.name, system_time_to_f64(n.created_at)],
+        )?;
+        Ok(())
+    }
+
+    /// Look up a network by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn get_network(&self, name: &str) -> Result<Option<crate::net_manager::NetworkInfo>> {
+        let conn = self.lock();
+        conn.query_row(
+            "SELECT name, created_at FROM networks WHERE name = ?1",
+            params![name],
+            row_to_network,
+        )
+        .optional()
+        .map_err(Into::into)
+    }
+
+    /// List all named networks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    #[allow(
+        clippy::significant_drop_tightening,
+        reason = "stmt borrows conn; collect before drop"
+    )]
+    pub fn list_networks(&self) -> Result<Vec<crate::net_manager::NetworkInfo>> {
+        let conn = self.lock();
+        let mut stmt = conn.prepare("SELECT name, created_at FROM networks ORDER BY name")?;
+        let out = stmt
+            .query_map([], row_to_network)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(out)
+    }
+
+    /// Delete a network by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the delete fails.
+    pub fn delete_network(&self, name: &str) -> Result<()> {
+        self.lock()
+            .execute("DELETE FROM networks WHERE name = ?1", params![name])?;
+        Ok(())
+    }
+}
+
+/// Map a `networks` table row into [`crate::net_manager::NetworkInfo`].
+fn row_to_network(row: &rusqlite::Row<'_>) -> rusqlite::Result<crate::net_manager::NetworkInfo> {
+    Ok(crate::net_manager::NetworkInfo {
+        name: row.get(0)?,
+        created_at: f64_to_system_time(row.get(1)?),
+    })
 }
 
 /// Map a `volumes` table row into [`crate::volumes::VolumeInfo`].
@@ -144,6 +144,7 @@ const fn default_virtio_net() -> bool {
 /// a derived [`bux_shim::ShimConfig`], not this type directly.
 #[non_exhaustive]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::struct_excessive_bools, reason = "independent config flags")]
 pub struct VmConfig {
     /// Number of virtual CPUs.
     pub vcpus: u8,
@@ -184,10 +185,18 @@ pub struct VmConfig {
     #[serde(default)]
     pub ports: Vec<String>,
 
-    /// Egress allow-list (hostnames / CIDRs). Empty = unrestricted egress.
+    /// Egress rules in [`bux_net::EgressRule`] syntax. Empty = unrestricted egress.
     #[serde(default)]
     pub allow_net: Vec<String>,
 
+    /// Explicit egress default (`None` = deny iff an allow rule exists).
+    #[serde(default)]
+    pub egress_default_deny: Option<bool>,
+
+    /// HTTPS request rules and logging on the MITM path (gvproxy only).
+    #[serde(default)]
+    pub http_policy: bux_net::HttpPolicy,
+
     /// Resolved published ports (set by Runtime after ephemeral probe).
     #[serde(default)]
     pub published_ports: Vec<crate::ports::PublishedPort>,
@@ -228,6 +237,27 @@ pub struct VmConfig {
     #[serde(default = "default_virtio_net")]
     pub virtio_net: bool,
 
+    /// Which backend serves virtio-net (`gvproxy` unless chosen per VM).
+    #[serde(default)]
+    pub net_backend: bux_net::NetworkBackendKind,
+
+    /// Named private network membership (hostname and address assigned
+    /// at spawn).
+    #[serde(default)]
+    pub network: Option<crate::net_manager::NetworkAttachment>,
+
+    /// Bandwidth limits and transfer quotas enforced by the backend.
+    #[serde(default)]
+    pub traffic_limits: bux_net::TrafficLimits,
+
+    /// Local DNS records, resolver override and host gateway.
+    #[serde(default)]
+    pub dns: bux_net::DnsConfig,
+
+    /// Guest gets an IPv6 address alongside IPv4.
+    #[serde(default)]
+    pub ipv6: bool,
+
     /// When true, restart requires secret re-supply (`StartOptions.secrets`)
     /// if the Runtime process does not still hold memory-only secrets.
     ///
@@ -253,6 +283,15 @@ pub struct VmConfig {
     #[serde(default)]
     pub security: crate::security::SecurityOptions,
 
+    /// Dedicated host uid (and gid) the VMM runs as, allocated from
+    /// [`crate::security::SecurityOptions::dedicated_user`] at spawn.
+    #[serde(default)]
+    pub host_user: Option<u32>,
+
+    /// Host-side cgroup limits on the VMM process (applied at each spawn/start).
+    #[serde(default)]
+    pub host_limits: crate::limits::HostLimits,
+
     /// Actual security posture from the last successful spawn.
     #[serde(default)]
     pub security_status: crate::security::SecurityStatus,
@@ -276,6 +315,10 @@ pub struct VmConfig {
     /// Last fatal/recoverable error message (e.g. secrets re-supply required).
     #[serde(default)]
     pub last_error: Option<String>,
+
+    /// Image admission outcome recorded when the OCI image was resolved.
+    #[serde(default)]
+    pub image_verification: Option<bux_oci::Verification>,
 }
 
 /// Serde helpers for `Option<SystemTime>` as optional f64 unix seconds.
@@ -394,6 +437,8 @@ fn test_vm(id: &str, name: Option<&str>) -> VmState {
                 workdir: None,
                 ports: vec![],
                 allow_net: vec![],
+                egress_default_deny: None,
+                http_policy: bux_net::HttpPolicy::default(),
                 published_ports: vec![],
                 virtiofs: vec![],
                 vsock_ports: vec![],
@@ -405,17 +450,25 @@ fn test_vm(id: &str, name: Option<&str>) -> VmState {
                 snd_device: None,
                 console_output: None,
                 virtio_net: true,
+                net_backend: bux_net::NetworkBackendKind::default(),
+                network: None,
+                traffic_limits: bux_net::TrafficLimits::default(),
+                dns: bux_net::DnsConfig::default(),
+                ipv6: false,
                 secrets_required: false,
                 workload_env: vec![],
                 workload_workdir: None,
                 workload_user: None,
                 security: crate::security::SecurityOptions::default(),
+                host_limits: crate::limits::HostLimits::default(),
+                host_user: None,
                 security_status: crate::security::SecurityStatus::default(),
                 auto_remove: false,
                 auto_stop_secs: None,
                 auto_delete_secs: None,
                 last_activity_at: None,
                 last_error: None,
+                image_verification: None,
             },
             created_at: SystemTime::now(),
         }
@@ -618,7 +671,7 @@ fn health_update() {
     fn product_schema_version() {
         let db = open_test_db();
         // Fresh in-memory DB uses product schema.
-        assert_eq!(PRODUCT_SCHEMA_VERSION, 2);
+        assert_eq!(PRODUCT_SCHEMA_VERSION, 3);
         db.insert(&test_vm("vm1", None)).unwrap();
         assert_eq!(db.list().unwrap().len(), 1);
     }
@@ -8,3 +8,40 @@ pub(crate) fn push_unique_path(paths: &mut Vec<PathBuf>, path: PathBuf) {
         paths.push(path);
     }
 }
+
+/// Locates a helper binary shipped with bux.
+///
+/// Search order:
+/// 1. `$env_var` (development override).
+/// 2. Next to the current executable.
+/// 3. In `$PATH`.
+#[cfg(unix)]
+pub(crate) fn find_binary(name: &str, env_var: &str) -> std::io::Result<PathBuf> {
+    if let Ok(p) = std::env::var(env_var) {
+        let path = PathBuf::from(p);
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+
+    if let Ok(exe) = std::env::current_exe() {
+        let sibling = exe.with_file_name(name);
+        if sibling.is_file() {
+            return Ok(sibling);
+        }
+    }
+
+    if let Ok(path_var) = std::env::var("PATH") {
+        for dir in std::env::split_paths(&path_var) {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    Err(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("'{name}' not found; install it next to the bux binary or in $PATH"),
+    ))
+}
@@ -51,8 +51,12 @@ pub struct VmBuilder {
     pub(super) workdir: Option<String>,
     /// TCP port mappings (`"host_port:guest_port"` or ephemeral forms).
     pub(super) ports: Vec<String>,
-    /// Egress allow-list (empty = unrestricted).
+    /// Egress rules (empty = unrestricted).
     pub(super) allow_net: Vec<String>,
+    /// Explicit egress default (`None` = inferred from the rules).
+    pub(super) egress_default_deny: Option<bool>,
+    /// HTTPS request rules and logging.
+    pub(super) http_policy: bux_net::HttpPolicy,
     /// virtio-fs shared directories.
     pub(super) virtiofs: Vec<crate::state::VirtioFs>,
     /// Global log level for libkrun.
@@ -73,6 +77,16 @@ pub struct VmBuilder {
     pub(super) vsock_ports: Vec<(u32, String, bool)>,
     /// Use gvproxy virtio-net (default `true` after network redesign).
     pub(super) virtio_net: bool,
+    /// Backend serving virtio-net.
+    pub(super) net_backend: bux_net::NetworkBackendKind,
+    /// Named private network membership.
+    pub(super) network: Option<crate::net_manager::NetworkAttachment>,
+    /// Bandwidth limits and transfer quotas.
+    pub(super) traffic_limits: bux_net::TrafficLimits,
+    /// Local DNS records, resolver override and host gateway.
+    pub(super) dns: bux_net::DnsConfig,
+    /// Dual-stack guest networking.
+    pub(super) ipv6: bool,
     /// Host-only secrets for MITM (not serialised into `SQLite` values).
     pub(crate) secrets: Vec<crate::secrets::Secret>,
     /// Workload user string for Phase A (`uid[:gid]` or `name[:group]`).
@@ -85,10 +99,14 @@ pub struct VmBuilder {
     pub(crate) workload_workdir: Option<String>,
     /// Isolation policy (Landlock / jailer).
     pub(crate) security: crate::security::SecurityOptions,
+    /// Host-side cgroup limits on the VMM process.
+    pub(crate) host_limits: crate::limits::HostLimits,
     /// Auto-stop idle seconds (`None` = off).
     pub(crate) auto_stop_secs: Option<u64>,
     /// Auto-delete stopped idle seconds (`None` = off).
     pub(crate) auto_delete_secs: Option<u64>,
+    /// Image admission outcome from the managed pipeline.
+    pub(crate) image_verification: Option<bux_oci::Verification>,
 }
 
 impl VmBuilder {
@@ -107,6 +125,8 @@ pub(super) fn new() -> Self {
             workdir: None,
             ports: Vec::new(),
             allow_net: Vec::new(),
+            egress_default_deny: None,
+            http_policy: bux_net::HttpPolicy::new(),
             virtiofs: Vec::new(),
             log_level: None,
             uid: None,
@@ -117,13 +137,20 @@ pub(super) fn new() -> Self {
             console_output: None,
             vsock_ports: Vec::new(),
             virtio_net: true,
+            net_backend: bux_net::NetworkBackendKind::Gvproxy,
+            network: None,
+            traffic_limits: bux_net::TrafficLimits::new(),
+            dns: bux_net::DnsConfig::new(),
+            ipv6: false,
             secrets: Vec::new(),
             workload_user: None,
             workload_env: Vec::new(),
             workload_workdir: None,
             security: crate::security::SecurityOptions::default(),
+            host_limits: crate::limits::HostLimits::new(),
             auto_stop_secs: None,
             auto_delete_secs: None,
+            image_verification: None,
         }
     }
 
@@ -202,15 +229,43 @@ pub fn port(mut self, mapping: impl Into<String>) -> Self {
         self
     }
 
-    /// Restrict egress to the given hosts/CIDRs (repeatable via multiple calls).
+    /// Adds egress rules (repeatable via multiple calls).
     ///
-    /// Empty list (default) = **unrestricted** egress. Non-empty = allow-list
-    /// enforced by gvproxy DNS/TCP filters.
+    /// Rules use [`bux_net::EgressRule`] syntax (`!` deny, `:port`, `/tcp`).
+    /// Empty list (default) = **unrestricted** egress; any allow rule makes
+    /// unmatched traffic denied unless [`Self::egress_default_deny`] says
+    /// otherwise. Rules are validated when the network backend starts.
     pub fn allow_net(mut self, hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
         self.allow_net.extend(hosts.into_iter().map(Into::into));
         self
     }
 
+    /// Adds the rules of a typed [`bux_net::EgressPolicy`], including its
+    /// explicit default if it has one.
+    pub fn egress(mut self, policy: &bux_net::EgressPolicy) -> Self {
+        self.allow_net.extend(policy.rule_strings());
+        if let Some(deny) = policy.explicit_default_deny() {
+            self.egress_default_deny = Some(deny);
+        }
+        self
+    }
+
+    /// Sets the action for traffic no egress rule matches.
+    pub const fn egress_default_deny(mut self, deny: bool) -> Self {
+        self.egress_default_deny = Some(deny);
+        self
+    }
+
+    /// Sets the HTTPS request policy ([`bux_net::HttpPolicy`]).
+    ///
+    /// Requires `virtio_net` with the gvproxy backend. Hosts named by a
+    /// rule are intercepted with a per-VM CA the guest is told to trust,
+    /// as for secrets. Rules are validated when the network backend starts.
+    pub fn http_policy(mut self, policy: bux_net::HttpPolicy) -> Self {
+        self.http_policy = policy;
+        self
+    }
+
     /// Adds a virtio-fs shared directory.
     ///
     /// - `tag` — identifier used to mount the filesystem in the guest.
@@ -291,6 +346,45 @@ pub const fn virtio_net(mut self, enable: bool) -> Self {
         self
     }
 
+    /// Select the virtio-net backend (default [`bux_net::NetworkBackendKind::Gvproxy`]).
+    ///
+    /// The userspace backend has no TLS interception, so it cannot be
+    /// combined with [`Self::secrets`].
+    pub const fn net_backend(mut self, kind: bux_net::NetworkBackendKind) -> Self {
+        self.net_backend = kind;
+        self
+    }
+
+    /// Join a named private network (requires `virtio_net`).
+    ///
+    /// Unset hostname and address are assigned by the Runtime at spawn.
+    pub fn network(mut self, attachment: crate::net_manager::NetworkAttachment) -> Self {
+        self.network = Some(attachment);
+        self
+    }
+
+    /// Sets bandwidth limits and transfer quotas (requires `virtio_net`).
+    ///
+    /// Limits are validated when the network backend starts.
+    pub const fn traffic_limits(mut self, limits: bux_net::TrafficLimits) -> Self {
+        self.traffic_limits = limits;
+        self
+    }
+
+    /// Sets local DNS records, the upstream resolver and the host gateway
+    /// (requires `virtio_net`).
+    pub fn dns(mut self, dns: bux_net::DnsConfig) -> Self {
+        self.dns = dns;
+        self
+    }
+
+    /// Gives the guest an IPv6 address alongside IPv4 (requires
+    /// `virtio_net` and the userspace backend).
+    pub const fn ipv6(mut self, enable: bool) -> Self {
+        self.ipv6 = enable;
+        self
+    }
+
     /// Attach secrets for gvproxy MITM substitution (host-only values).
     ///
     /// Requires `virtio_net`. Guest traffic uses placeholders like
@@ -332,6 +426,13 @@ pub const fn security(mut self, opts: crate::security::SecurityOptions) -> Self
         self
     }
 
+    /// Host-side CPU / memory / pids / IO limits on the VMM process
+    /// (cgroup v2, Linux only).
+    pub fn host_limits(mut self, limits: crate::limits::HostLimits) -> Self {
+        self.host_limits = limits;
+        self
+    }
+
     /// Idle auto-stop policy in seconds (`None` = off).
     pub const fn auto_stop_secs(mut self, secs: Option<u64>) -> Self {
         self.auto_stop_secs = secs;
@@ -344,6 +445,15 @@ pub const fn auto_delete_secs(mut self, secs: Option<u64>) -> Self {
         self
     }
 
+    /// Records the image admission outcome from [`bux_oci::Oci::verify`].
+    ///
+    /// The host policy's `allowed_registries` then judges the VM by the
+    /// verified reference rather than as a local disk.
+    pub fn image_verification(mut self, verification: bux_oci::Verification) -> Self {
+        self.image_verification = Some(verification);
+        self
+    }
+
     /// Extracts a serializable configuration snapshot.
     #[cfg(unix)]
     pub(crate) fn to_config(&self) -> VmConfig {
@@ -361,6 +471,8 @@ pub(crate) fn to_config(&self) -> VmConfig {
             workdir: self.workdir.clone(),
             ports: self.ports.clone(),
             allow_net: self.allow_net.clone(),
+            egress_default_deny: self.egress_default_deny,
+            http_policy: self.http_policy.clone(),
             published_ports: vec![],
             virtiofs: self.virtiofs.clone(),
             vsock_ports: self
@@ -380,17 +492,25 @@ pub(crate) fn to_config(&self) -> VmConfig {
             snd_device: self.snd_device,
             console_output: self.console_output.clone(),
             virtio_net: self.virtio_net,
+            net_backend: self.net_backend,
+            network: self.network.clone(),
+            traffic_limits: self.traffic_limits,
+            dns: self.dns.clone(),
+            ipv6: self.ipv6,
             secrets_required: !self.secrets.is_empty(),
             workload_env: self.workload_env.clone(),
             workload_workdir: self.workload_workdir.clone(),
             workload_user: self.workload_user.clone(),
             security: self.security,
+            host_limits: self.host_limits.clone(),
+            host_user: None,
             security_status: crate::security::SecurityStatus::default(),
             auto_remove: false,
             auto_stop_secs: self.auto_stop_secs,
             auto_delete_secs: self.auto_delete_secs,
             last_activity_at: Some(std::time::SystemTime::now()),
             last_error: None,
+            image_verification: self.image_verification.clone(),
         }
     }
 
@@ -412,6 +532,8 @@ pub fn from_config(c: &VmConfig) -> Self {
             workdir: c.workdir.clone(),
             ports: c.ports.clone(),
             allow_net: c.allow_net.clone(),
+            egress_default_deny: c.egress_default_deny,
+            http_policy: c.http_policy.clone(),
             virtiofs: c.virtiofs.clone(),
             vsock_ports: c
                 .vsock_ports
@@ -426,13 +548,20 @@ pub fn from_config(c: &VmConfig) -> Self {
             snd_device: c.snd_device,
             console_output: c.console_output.clone(),
             virtio_net: c.virtio_net,
+            net_backend: c.net_backend,
+            network: c.network.clone(),
+            traffic_limits: c.traffic_limits,
+            dns: c.dns.clone(),
+            ipv6: c.ipv6,
             secrets: Vec::new(),
             workload_user: c.workload_user.clone(),
             workload_env: c.workload_env.clone(),
             workload_workdir: c.workload_workdir.clone(),
             security: c.security,
+            host_limits: c.host_limits.clone(),
             auto_stop_secs: c.auto_stop_secs,
             auto_delete_secs: c.auto_delete_secs,
+            image_verification: c.image_verification.clone(),
         }
     }
 
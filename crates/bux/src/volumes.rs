@@ -388,6 +388,14 @@ fn validate_resolved_path(path: &Path, allow_sensitive: bool) -> Result<PathBuf>
     Ok(path.to_path_buf())
 }
 
+/// Whether the resolved host `path` is `/` or under a sensitive prefix.
+pub(crate) fn is_sensitive(path: &Path) -> bool {
+    path == Path::new("/")
+        || sensitive_prefixes()
+            .iter()
+            .any(|denied| path_is_or_under(path, denied))
+}
+
 /// Whether `path` equals or is nested under `prefix`.
 fn path_is_or_under(path: &Path, prefix: &Path) -> bool {
     if path == prefix {
@@ -0,0 +1,69 @@
+//! Boots a managed VM with the shim's seccomp filter enforced.
+//!
+//! Any syscall libkrun makes outside the allowlist kills the VMM, so this
+//! catches allowlist gaps after a libkrun upgrade; until it runs in CI the
+//! Linux default stays `SeccompMode::Log`. Needs KVM, a `bux-shim`
+//! on `$BUX_SHIM_PATH` / `$PATH` and registry access, hence `#[ignore]`:
+//!
+//! ```sh
+//! cargo test -p bux --test seccomp_boot -- --ignored
+//! ```
+
+#![cfg(target_os = "linux")]
+#![allow(
+    clippy::unwrap_used,
+    clippy::tests_outside_test_module,
+    reason = "integration tests intentionally use unwrap for brevity; \
+              Cargo's tests/ layout implies every fn is a test, no explicit #[cfg(test)] module"
+)]
+
+// Dependencies of the library that this binary never names — silence the
+// workspace lint.
+use bux_cgroup as _;
+use bux_e2fs as _;
+use bux_jail as _;
+use bux_krun as _;
+use bux_net as _;
+use bux_oci as _;
+use bux_proto as _;
+use bux_qcow2 as _;
+use bux_seccomp as _;
+use bux_shim as _;
+use dirs as _;
+use nix as _;
+use rusqlite as _;
+use serde as _;
+use serde_json as _;
+use sha2 as _;
+use thiserror as _;
+use toml as _;
+use tracing as _;
+
+use bux::{ExecStart, LayerStatus, Runtime, SeccompMode, SecurityOptions, VmOptions};
+use tempfile::TempDir;
+
+#[tokio::test]
+#[ignore = "boots a VM: needs KVM, bux-shim and registry access"]
+async fn boots_and_execs_under_enforced_seccomp() {
+    let dir = TempDir::new().unwrap();
+    let rt = Runtime::open(dir.path()).unwrap();
+    let opts = VmOptions::from_image("alpine:latest")
+        .security(SecurityOptions::new().seccomp(SeccompMode::Enforce));
+
+    let mut vm = rt.create(opts).await.unwrap();
+    assert_eq!(vm.security_status().seccomp, LayerStatus::Enforced);
+
+    let out = vm
+        .exec_output(ExecStart::new("sh").args(vec!["-c".to_owned(), "echo ok".to_owned()]))
+        .await
+        .unwrap();
+    assert_eq!(
+        out.code,
+        0,
+        "stderr: {}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    assert_eq!(out.stdout, b"ok\n");
+
+    vm.stop().await.unwrap();
+}